@@ -0,0 +1,57 @@
+mod datasets_paths;
+mod utils;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use milli::{obkv_to_json, DocumentReader};
+use utils::Conf;
+
+#[cfg(target_os = "linux")]
+#[global_allocator]
+static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
+
+fn reading_songs(c: &mut Criterion) {
+    let conf = Conf {
+        database_name: "benches_documents.mmdb",
+        dataset: datasets_paths::SMOL_SONGS,
+        primary_key: Some("id"),
+        ..Conf::BASE
+    };
+    let index = utils::base_setup(&conf);
+
+    let mut group = c.benchmark_group("documents");
+    group.sample_size(10);
+
+    group.bench_function("Reading the raw values of all the songs", |b| {
+        b.iter(|| {
+            let rtxn = index.read_txn().unwrap();
+            let mut bytes = 0;
+            for result in index.all_documents(&rtxn).unwrap() {
+                let (_id, obkv) = result.unwrap();
+                let document = DocumentReader::new(obkv);
+                bytes += document.iter().map(|(_, value)| value.len()).sum::<usize>();
+            }
+            bytes
+        })
+    });
+
+    group.bench_function("Deserializing all the songs", |b| {
+        b.iter(|| {
+            let rtxn = index.read_txn().unwrap();
+            let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+            let fields: Vec<_> = fields_ids_map.ids().collect();
+            let mut count = 0;
+            for result in index.all_documents(&rtxn).unwrap() {
+                let (_id, obkv) = result.unwrap();
+                count += obkv_to_json(&fields, &fields_ids_map, obkv).unwrap().len();
+            }
+            count
+        })
+    });
+
+    group.finish();
+
+    index.prepare_for_closing().wait();
+}
+
+criterion_group!(benches, reading_songs);
+criterion_main!(benches);
@@ -155,6 +155,11 @@ fn bench_songs(c: &mut criterion::Criterion) {
             filter: Some("released-timestamp != 1262347200 AND (NOT (released-timestamp = 946728000)) AND (duration-float = 1 OR (duration-float 1.1 TO 1.5 AND released-timestamp > 315576000))"),
             ..BASE_CONF
         },
+        utils::Conf {
+            group_name: "multi-clause filter",
+            filter: Some("released-timestamp > 315576000 AND duration-float 1.1 TO 1.5 AND released-timestamp != 1262347200 AND genre = \"rock\""),
+            ..BASE_CONF
+        },
 
         /* the we bench some global / normal search with all the default criterion in the default
          * order */
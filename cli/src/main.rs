@@ -276,13 +276,11 @@ impl Search {
 
         let result = search.execute()?;
 
-        let fields_ids_map = index.fields_ids_map(&txn)?;
-        let displayed_fields =
-            index.displayed_fields_ids(&txn)?.unwrap_or_else(|| fields_ids_map.ids().collect());
+        let projection = index.document_projection(&txn, false)?;
         let documents = index.documents(&txn, result.documents_ids)?;
         let mut jsons = Vec::new();
         for (_, obkv) in documents {
-            let json = milli::obkv_to_json(&displayed_fields, &fields_ids_map, obkv)?;
+            let json = projection.to_json(obkv)?;
             jsons.push(json);
         }
 
@@ -23,9 +23,7 @@ use meilisearch_tokenizer::{Analyzer, AnalyzerConfig};
 use milli::documents::DocumentBatchReader;
 use milli::update::UpdateIndexingStep::*;
 use milli::update::{IndexDocumentsMethod, Setting, UpdateBuilder};
-use milli::{
-    obkv_to_json, CompressionType, FilterCondition, Index, MatchingWords, SearchResult, SortError,
-};
+use milli::{CompressionType, FilterCondition, Index, MatchingWords, SearchResult, SortError};
 use once_cell::sync::OnceCell;
 use rayon::ThreadPool;
 use serde::{Deserialize, Serialize};
@@ -764,7 +762,7 @@ async fn main() -> anyhow::Result<()> {
                 search.sort_criteria(vec![sort.parse().map_err(SortError::from).unwrap()]);
             }
 
-            let SearchResult { matching_words, candidates, documents_ids } =
+            let SearchResult { matching_words, candidates, documents_ids, .. } =
                 search.execute().unwrap();
 
             let number_of_candidates = candidates.len();
@@ -776,10 +774,7 @@ async fn main() -> anyhow::Result<()> {
 
             let mut documents = Vec::new();
             let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
-            let displayed_fields = match index.displayed_fields_ids(&rtxn).unwrap() {
-                Some(fields) => fields,
-                None => fields_ids_map.iter().map(|(id, _)| id).collect(),
-            };
+            let projection = index.document_projection(&rtxn, false).unwrap();
             let attributes_to_highlight = match index.searchable_fields(&rtxn).unwrap() {
                 Some(fields) => fields.into_iter().map(String::from).collect(),
                 None => fields_ids_map.iter().map(|(_, name)| name).map(String::from).collect(),
@@ -789,7 +784,7 @@ async fn main() -> anyhow::Result<()> {
             let highlighter = Highlighter::new(&stop_words);
 
             for (_id, obkv) in index.documents(&rtxn, documents_ids).unwrap() {
-                let mut object = obkv_to_json(&displayed_fields, &fields_ids_map, obkv).unwrap();
+                let mut object = projection.to_json(obkv).unwrap();
                 if !disable_highlighting {
                     highlighter.highlight_record(
                         &mut object,
@@ -817,18 +812,14 @@ async fn main() -> anyhow::Result<()> {
             let rtxn = index.read_txn().unwrap();
 
             let external_documents_ids = index.external_documents_ids(&rtxn).unwrap();
-            let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
-            let displayed_fields = match index.displayed_fields_ids(&rtxn).unwrap() {
-                Some(fields) => fields,
-                None => fields_ids_map.iter().map(|(id, _)| id).collect(),
-            };
+            let projection = index.document_projection(&rtxn, false).unwrap();
 
             match external_documents_ids.get(&id) {
                 Some(document_id) => {
                     let document_id = document_id as u32;
                     let (_, obkv) =
                         index.documents(&rtxn, Some(document_id)).unwrap().pop().unwrap();
-                    let document = obkv_to_json(&displayed_fields, &fields_ids_map, obkv).unwrap();
+                    let document = projection.to_json(obkv).unwrap();
 
                     Response::builder()
                         .header("Content-Type", "application/json")
@@ -18,6 +18,7 @@ static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
 const ALL_DATABASE_NAMES: &[&str] = &[
     MAIN,
     WORD_DOCIDS,
+    EXACT_WORD_DOCIDS,
     WORD_PREFIX_DOCIDS,
     DOCID_WORD_POSITIONS,
     WORD_PAIR_PROXIMITY_DOCIDS,
@@ -25,15 +26,23 @@ const ALL_DATABASE_NAMES: &[&str] = &[
     WORD_POSITION_DOCIDS,
     WORD_PREFIX_POSITION_DOCIDS,
     FIELD_ID_WORD_COUNT_DOCIDS,
+    FIELD_ID_EXACT_VALUE_DOCIDS,
     FACET_ID_F64_DOCIDS,
     FACET_ID_STRING_DOCIDS,
     FIELD_ID_DOCID_FACET_F64S,
     FIELD_ID_DOCID_FACET_STRINGS,
+    FACET_ID_EXISTS_DOCIDS,
+    FACET_ID_IS_NULL_DOCIDS,
+    FACET_ID_IS_EMPTY_DOCIDS,
     DOCUMENTS,
+    DOCID_FINGERPRINTS,
+    FINGERPRINT_BAND_DOCIDS,
+    DOCID_WORD_COUNTS,
 ];
 
 const POSTINGS_DATABASE_NAMES: &[&str] = &[
     WORD_DOCIDS,
+    EXACT_WORD_DOCIDS,
     WORD_PREFIX_DOCIDS,
     DOCID_WORD_POSITIONS,
     WORD_PAIR_PROXIMITY_DOCIDS,
@@ -168,6 +177,9 @@ enum Command {
         field_name: String,
     },
 
+    /// Outputs the number of distinct values of every faceted field.
+    FacetValuesCounts,
+
     /// Outputs the average number of *different* words by document.
     AverageNumberOfWordsByDoc,
 
@@ -293,6 +305,7 @@ fn main() -> anyhow::Result<()> {
             docids_words_positions(&index, &rtxn, !full_display, internal_documents_ids)
         }
         FacetNumberStats { field_name } => facet_number_stats(&index, &rtxn, field_name),
+        FacetValuesCounts => facet_values_counts(&index, &rtxn),
         AverageNumberOfWordsByDoc => average_number_of_words_by_doc(&index, &rtxn),
         AverageNumberOfPositionsByWord => average_number_of_positions_by_word(&index, &rtxn),
         SizeOfDatabase { databases } => size_of_databases(&index, &rtxn, databases),
@@ -375,6 +388,7 @@ fn biggest_value_sizes(index: &Index, rtxn: &heed::RoTxn, limit: usize) -> anyho
         env: _env,
         main,
         word_docids,
+        exact_word_docids,
         word_prefix_docids,
         docid_word_positions,
         word_pair_proximity_docids,
@@ -386,11 +400,19 @@ fn biggest_value_sizes(index: &Index, rtxn: &heed::RoTxn, limit: usize) -> anyho
         facet_id_string_docids,
         field_id_docid_facet_f64s: _,
         field_id_docid_facet_strings: _,
+        facet_id_exists_docids: _,
+        facet_id_is_null_docids: _,
+        facet_id_is_empty_docids: _,
         documents,
+        docid_fingerprints: _,
+        fingerprint_band_docids: _,
+        docid_word_counts: _,
+        ..
     } = index;
 
     let main_name = "main";
     let word_docids_name = "word_docids";
+    let exact_word_docids_name = "exact_word_docids";
     let word_prefix_docids_name = "word_prefix_docids";
     let docid_word_positions_name = "docid_word_positions";
     let word_prefix_pair_proximity_docids_name = "word_prefix_pair_proximity_docids";
@@ -436,6 +458,14 @@ fn biggest_value_sizes(index: &Index, rtxn: &heed::RoTxn, limit: usize) -> anyho
             }
         }
 
+        for result in exact_word_docids.remap_data_type::<ByteSlice>().iter(rtxn)? {
+            let (word, value) = result?;
+            heap.push(Reverse((value.len(), word.to_string(), exact_word_docids_name)));
+            if heap.len() > limit {
+                heap.pop();
+            }
+        }
+
         for result in word_prefix_docids.remap_data_type::<ByteSlice>().iter(rtxn)? {
             let (word, value) = result?;
             heap.push(Reverse((value.len(), word.to_string(), word_prefix_docids_name)));
@@ -887,6 +917,21 @@ fn export_documents(
     Ok(())
 }
 
+fn facet_values_counts(index: &Index, rtxn: &heed::RoTxn) -> anyhow::Result<()> {
+    let fields_ids_map = index.fields_ids_map(rtxn)?;
+
+    let stdout = io::stdout();
+    let mut wtr = csv::Writer::from_writer(stdout.lock());
+    wtr.write_record(&["field_name", "distinct_values"])?;
+
+    for (field_id, count) in index.facet_values_counts(rtxn)? {
+        let field_name = fields_ids_map.name(field_id).unwrap_or("<unknown>");
+        wtr.write_record(&[field_name, &count.to_string()])?;
+    }
+
+    Ok(wtr.flush()?)
+}
+
 fn average_number_of_words_by_doc(index: &Index, rtxn: &heed::RoTxn) -> anyhow::Result<()> {
     use heed::types::DecodeIgnore;
     use milli::{BEU32StrCodec, DocumentId};
@@ -956,6 +1001,7 @@ fn size_of_databases(index: &Index, rtxn: &heed::RoTxn, names: Vec<String>) -> a
         env: _env,
         main,
         word_docids,
+        exact_word_docids,
         word_prefix_docids,
         docid_word_positions,
         word_pair_proximity_docids,
@@ -963,11 +1009,19 @@ fn size_of_databases(index: &Index, rtxn: &heed::RoTxn, names: Vec<String>) -> a
         word_position_docids,
         word_prefix_position_docids,
         field_id_word_count_docids,
+        field_id_exact_value_docids,
         facet_id_f64_docids,
         facet_id_string_docids,
         field_id_docid_facet_f64s,
         field_id_docid_facet_strings,
+        facet_id_exists_docids,
+        facet_id_is_null_docids,
+        facet_id_is_empty_docids,
         documents,
+        docid_fingerprints,
+        fingerprint_band_docids,
+        docid_word_counts,
+        ..
     } = index;
 
     let names = if names.is_empty() {
@@ -981,18 +1035,26 @@ fn size_of_databases(index: &Index, rtxn: &heed::RoTxn, names: Vec<String>) -> a
             MAIN => &main,
             WORD_PREFIX_DOCIDS => word_prefix_docids.as_polymorph(),
             WORD_DOCIDS => word_docids.as_polymorph(),
+            EXACT_WORD_DOCIDS => exact_word_docids.as_polymorph(),
             DOCID_WORD_POSITIONS => docid_word_positions.as_polymorph(),
             WORD_PAIR_PROXIMITY_DOCIDS => word_pair_proximity_docids.as_polymorph(),
             WORD_PREFIX_PAIR_PROXIMITY_DOCIDS => word_prefix_pair_proximity_docids.as_polymorph(),
             WORD_POSITION_DOCIDS => word_position_docids.as_polymorph(),
             WORD_PREFIX_POSITION_DOCIDS => word_prefix_position_docids.as_polymorph(),
             FIELD_ID_WORD_COUNT_DOCIDS => field_id_word_count_docids.as_polymorph(),
+            FIELD_ID_EXACT_VALUE_DOCIDS => field_id_exact_value_docids.as_polymorph(),
             FACET_ID_F64_DOCIDS => facet_id_f64_docids.as_polymorph(),
             FACET_ID_STRING_DOCIDS => facet_id_string_docids.as_polymorph(),
             FIELD_ID_DOCID_FACET_F64S => field_id_docid_facet_f64s.as_polymorph(),
             FIELD_ID_DOCID_FACET_STRINGS => field_id_docid_facet_strings.as_polymorph(),
+            FACET_ID_EXISTS_DOCIDS => facet_id_exists_docids.as_polymorph(),
+            FACET_ID_IS_NULL_DOCIDS => facet_id_is_null_docids.as_polymorph(),
+            FACET_ID_IS_EMPTY_DOCIDS => facet_id_is_empty_docids.as_polymorph(),
 
             DOCUMENTS => documents.as_polymorph(),
+            DOCID_FINGERPRINTS => docid_fingerprints.as_polymorph(),
+            FINGERPRINT_BAND_DOCIDS => fingerprint_band_docids.as_polymorph(),
+            DOCID_WORD_COUNTS => docid_word_counts.as_polymorph(),
             unknown => anyhow::bail!("unknown database {:?}", unknown),
         };
 
@@ -1076,6 +1138,10 @@ fn database_stats(index: &Index, rtxn: &heed::RoTxn, name: &str) -> anyhow::Resu
             let db = index.word_docids.as_polymorph();
             compute_stats::<RoaringBitmapCodec>(*db, rtxn, name)
         }
+        EXACT_WORD_DOCIDS => {
+            let db = index.exact_word_docids.as_polymorph();
+            compute_stats::<RoaringBitmapCodec>(*db, rtxn, name)
+        }
         WORD_PREFIX_DOCIDS => {
             let db = index.word_prefix_docids.as_polymorph();
             compute_stats::<RoaringBitmapCodec>(*db, rtxn, name)
@@ -1096,6 +1162,10 @@ fn database_stats(index: &Index, rtxn: &heed::RoTxn, name: &str) -> anyhow::Resu
             let db = index.field_id_word_count_docids.as_polymorph();
             compute_stats::<CboRoaringBitmapCodec>(*db, rtxn, name)
         }
+        FIELD_ID_EXACT_VALUE_DOCIDS => {
+            let db = index.field_id_exact_value_docids.as_polymorph();
+            compute_stats::<RoaringBitmapCodec>(*db, rtxn, name)
+        }
         unknown => anyhow::bail!("unknown database {:?}", unknown),
     }
 }
@@ -0,0 +1,343 @@
+//! Standard datasets and scenarios to measure the performances of the indexing and the search.
+//!
+//! The movies and geo points datasets are the ones used by the benchmarks of the repository,
+//! they are read from a directory, `MILLI_BENCH_DATASETS_PATH` for the benchmarks. The logs
+//! dataset is generated from a seed and is the same on every machine.
+
+use std::fs::File;
+use std::io::{BufReader, Cursor};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use heed::EnvOpenOptions;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::documents::{DocumentBatchBuilder, DocumentBatchReader};
+use crate::update::{IndexDocuments, Settings};
+use crate::{AscDesc, FilterCondition, Index, Result, SortError, TempDirIndex};
+
+/// The number of documents of the generated logs dataset.
+pub const LOGS_COUNT: usize = 100_000;
+
+const LOGS_SEED: u64 = 42;
+const LOGS_LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error"];
+const LOGS_SERVICES: &[&str] = &["auth", "billing", "gateway", "search", "storage"];
+const LOGS_MESSAGES: &[&str] = &[
+    "request completed",
+    "connection timeout",
+    "cache miss on key",
+    "user session expired",
+    "retrying failed request",
+    "disk usage above threshold",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Dataset {
+    Movies,
+    GeoPoints,
+    Logs,
+}
+
+impl Dataset {
+    /// The name of the file of the dataset in the datasets directory,
+    /// `None` if the dataset is generated.
+    pub fn file_name(self) -> Option<&'static str> {
+        match self {
+            Dataset::Movies => Some("movies.json"),
+            Dataset::GeoPoints => Some("smol-all-countries.jsonl"),
+            Dataset::Logs => None,
+        }
+    }
+
+    /// Reads the documents of the dataset from the datasets directory or generates them.
+    pub fn load(
+        self,
+        datasets_dir: impl AsRef<Path>,
+    ) -> Result<DocumentBatchReader<Cursor<Vec<u8>>>> {
+        let mut writer = Cursor::new(Vec::new());
+        let mut builder = DocumentBatchBuilder::new(&mut writer)?;
+        match self.file_name() {
+            Some(file_name) => {
+                let file = File::open(datasets_dir.as_ref().join(file_name))?;
+                match self {
+                    Dataset::Movies => builder.add_json_payload(BufReader::new(file))?,
+                    _otherwise => builder.add_ndjson_payload(BufReader::new(file))?,
+                }
+            }
+            None => {
+                for document in generate_logs(LOGS_COUNT, LOGS_SEED) {
+                    builder.add_documents(document)?;
+                }
+            }
+        }
+        builder.finish()?;
+
+        writer.set_position(0);
+        Ok(DocumentBatchReader::from_reader(writer)?)
+    }
+
+    /// Applies the settings the scenarios of the dataset are measured with.
+    pub fn configure(self, builder: &mut Settings) {
+        fn strings(fields: &[&str]) -> Vec<String> {
+            fields.iter().map(|s| s.to_string()).collect()
+        }
+
+        match self {
+            Dataset::Movies => {
+                builder.set_primary_key("id".to_owned());
+                builder.set_searchable_fields(strings(&["title", "overview"]));
+                builder.set_filterable_fields(
+                    strings(&["release_date", "genres"]).into_iter().collect(),
+                );
+                builder.set_sortable_fields(strings(&["release_date"]).into_iter().collect());
+            }
+            Dataset::GeoPoints => {
+                builder.set_primary_key("geonameid".to_owned());
+                builder.set_searchable_fields(strings(&["name", "alternatenames", "elevation"]));
+                let fields = strings(&["_geo", "population", "elevation"]);
+                builder.set_filterable_fields(fields.iter().cloned().collect());
+                builder.set_sortable_fields(fields.into_iter().collect());
+            }
+            Dataset::Logs => {
+                builder.set_primary_key("id".to_owned());
+                builder.set_searchable_fields(strings(&["message", "service"]));
+                builder.set_filterable_fields(strings(&["level", "service"]).into_iter().collect());
+                builder.set_sortable_fields(strings(&["timestamp"]).into_iter().collect());
+            }
+        }
+    }
+
+    /// The standard searches measured on the dataset.
+    pub fn search_scenarios(self) -> Vec<SearchScenario> {
+        match self {
+            Dataset::Movies => vec![
+                SearchScenario::new("placeholder", ""),
+                SearchScenario::new("one word", "the"),
+                SearchScenario::new("two words", "star wars"),
+                SearchScenario::new("typo", "harry poter"),
+                SearchScenario::new("filter", "love").filter("genres = Drama"),
+                SearchScenario::new("sort", "love").sort("release_date:desc"),
+            ],
+            Dataset::GeoPoints => vec![
+                SearchScenario::new("placeholder", ""),
+                SearchScenario::new("word", "saint"),
+                SearchScenario::new("sort from Lille", "")
+                    .sort("_geoPoint(50.62999333378238, 3.086269263384099):asc"),
+                SearchScenario::new("radius around Lille", "")
+                    .filter("_geoRadius(50.62999333378238, 3.086269263384099, 10000)"),
+                SearchScenario::new("population filter", "").filter("population > 1000000"),
+            ],
+            Dataset::Logs => vec![
+                SearchScenario::new("placeholder", ""),
+                SearchScenario::new("message", "connection timeout"),
+                SearchScenario::new("level filter", "request").filter("level = error"),
+                SearchScenario::new("latest errors", "")
+                    .filter("level = error AND service = gateway")
+                    .sort("timestamp:desc"),
+                SearchScenario::new("multi-clause filter", "").filter(
+                    "level != trace AND level != debug AND service != auth AND service = gateway AND level = error",
+                ),
+            ],
+        }
+    }
+}
+
+/// Generates deterministic log documents, the same seed always generates the same documents.
+pub fn generate_logs(count: usize, seed: u64) -> impl Iterator<Item = serde_json::Value> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut timestamp = 1_600_000_000u64;
+    (0..count).map(move |id| {
+        timestamp += rng.gen_range(0..10);
+        json!({
+            "id": id,
+            "timestamp": timestamp,
+            "level": LOGS_LEVELS[rng.gen_range(0..LOGS_LEVELS.len())],
+            "service": LOGS_SERVICES[rng.gen_range(0..LOGS_SERVICES.len())],
+            "message": LOGS_MESSAGES[rng.gen_range(0..LOGS_MESSAGES.len())],
+        })
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchScenario {
+    pub name: String,
+    pub query: String,
+    pub filter: Option<String>,
+    pub sort: Vec<String>,
+}
+
+impl SearchScenario {
+    pub fn new(name: impl Into<String>, query: impl Into<String>) -> SearchScenario {
+        SearchScenario { name: name.into(), query: query.into(), filter: None, sort: Vec::new() }
+    }
+
+    pub fn filter(mut self, filter: impl Into<String>) -> SearchScenario {
+        self.filter = Some(filter.into());
+        self
+    }
+
+    pub fn sort(mut self, sort: impl Into<String>) -> SearchScenario {
+        self.sort.push(sort.into());
+        self
+    }
+}
+
+/// The durations of the runs of a scenario.
+#[derive(Debug, Clone, Serialize)]
+pub struct Timings {
+    pub name: String,
+    pub samples: Vec<Duration>,
+}
+
+impl Timings {
+    pub fn min(&self) -> Duration {
+        self.samples.iter().min().copied().unwrap_or_default()
+    }
+
+    pub fn max(&self) -> Duration {
+        self.samples.iter().max().copied().unwrap_or_default()
+    }
+
+    pub fn mean(&self) -> Duration {
+        match self.samples.len() {
+            0 => Duration::default(),
+            len => self.samples.iter().sum::<Duration>() / len as u32,
+        }
+    }
+
+    pub fn median(&self) -> Duration {
+        let mut samples = self.samples.clone();
+        samples.sort_unstable();
+        samples.get(samples.len() / 2).copied().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexingTimings {
+    pub nb_documents: usize,
+    pub settings: Duration,
+    pub indexing: Duration,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    pub dataset: Dataset,
+    pub indexing: IndexingTimings,
+    pub searches: Vec<Timings>,
+}
+
+/// Configures the empty index for the dataset and indexes the documents in it.
+pub fn run_indexing(
+    index: &Index,
+    dataset: Dataset,
+    documents: DocumentBatchReader<Cursor<Vec<u8>>>,
+) -> Result<IndexingTimings> {
+    let before = Instant::now();
+    let mut wtxn = index.write_txn()?;
+    let mut builder = Settings::new(&mut wtxn, index, 0);
+    dataset.configure(&mut builder);
+    builder.execute(|_, _| ())?;
+    wtxn.commit()?;
+    let settings = before.elapsed();
+
+    let before = Instant::now();
+    let mut wtxn = index.write_txn()?;
+    let result = IndexDocuments::new(&mut wtxn, index, 1).execute(documents, |_, _| ())?;
+    wtxn.commit()?;
+    let indexing = before.elapsed();
+
+    Ok(IndexingTimings { nb_documents: result.nb_documents, settings, indexing })
+}
+
+/// Runs every one of the searches the given number of times.
+pub fn run_searches(
+    index: &Index,
+    scenarios: &[SearchScenario],
+    iterations: usize,
+) -> Result<Vec<Timings>> {
+    let rtxn = index.read_txn()?;
+    let mut timings = Vec::with_capacity(scenarios.len());
+
+    for scenario in scenarios {
+        let mut search = index.search(&rtxn);
+        search.query(&scenario.query);
+        if let Some(filter) = &scenario.filter {
+            search.filter(FilterCondition::from_str(&rtxn, index, filter)?);
+        }
+        if !scenario.sort.is_empty() {
+            let mut sort_criteria = Vec::new();
+            for text in &scenario.sort {
+                let asc_desc: AscDesc = text.parse().map_err(SortError::from)?;
+                sort_criteria.push(asc_desc);
+            }
+            search.sort_criteria(sort_criteria);
+        }
+
+        let mut samples = Vec::with_capacity(iterations);
+        for _ in 0..iterations {
+            let before = Instant::now();
+            search.execute()?;
+            samples.push(before.elapsed());
+        }
+        timings.push(Timings { name: scenario.name.clone(), samples });
+    }
+
+    Ok(timings)
+}
+
+/// Indexes the dataset in a temporary index and runs its standard searches on it.
+pub fn run_dataset(
+    dataset: Dataset,
+    datasets_dir: impl AsRef<Path>,
+    iterations: usize,
+) -> Result<BenchReport> {
+    let mut options = EnvOpenOptions::new();
+    options.map_size(10 * 1024 * 1024 * 1024); // 10 GB
+    let index = TempDirIndex::new(options)?;
+
+    let documents = dataset.load(datasets_dir)?;
+    let indexing = run_indexing(&index, dataset, documents)?;
+    let searches = run_searches(&index, &dataset.search_scenarios(), iterations)?;
+
+    Ok(BenchReport { dataset, indexing, searches })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_logs_are_deterministic() {
+        let first: Vec<_> = generate_logs(100, 1).collect();
+        let second: Vec<_> = generate_logs(100, 1).collect();
+        assert_eq!(first, second);
+        assert_ne!(first, generate_logs(100, 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn run_logs_searches() {
+        let mut options = EnvOpenOptions::new();
+        options.map_size(100 * 1024 * 1024); // 100 MB
+        let index = TempDirIndex::new(options).unwrap();
+
+        let mut writer = Cursor::new(Vec::new());
+        let mut builder = DocumentBatchBuilder::new(&mut writer).unwrap();
+        for document in generate_logs(1000, LOGS_SEED) {
+            builder.add_documents(document).unwrap();
+        }
+        builder.finish().unwrap();
+        writer.set_position(0);
+        let documents = DocumentBatchReader::from_reader(writer).unwrap();
+
+        let indexing = run_indexing(&index, Dataset::Logs, documents).unwrap();
+        assert_eq!(indexing.nb_documents, 1000);
+
+        let scenarios = Dataset::Logs.search_scenarios();
+        let timings = run_searches(&index, &scenarios, 3).unwrap();
+        assert_eq!(timings.len(), scenarios.len());
+        assert!(timings.iter().all(|timings| timings.samples.len() == 3));
+    }
+}
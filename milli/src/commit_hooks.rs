@@ -0,0 +1,62 @@
+use std::fmt;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use roaring::RoaringBitmap;
+
+/// What a write transaction changed, given to the commit hooks once it is committed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommitSummary {
+    /// The number of write transactions that updated the index, including this one if it did.
+    pub commits_count: u64,
+    /// The documents ids that were not used before the commit.
+    pub added_documents_ids: RoaringBitmap,
+    /// The documents ids that were used before the commit and whose documents were written.
+    pub updated_documents_ids: RoaringBitmap,
+    /// The documents ids that are no longer used after the commit.
+    pub deleted_documents_ids: RoaringBitmap,
+    /// The number of documents in the index after the commit.
+    pub documents_count: u64,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Identifies a registered commit hook, it is used to unregister the hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CommitHookId(u64);
+
+type CommitHook = Arc<dyn Fn(&CommitSummary) + Send + Sync>;
+
+/// The callbacks invoked after the write transactions are committed.
+#[derive(Default)]
+pub(crate) struct CommitHooks {
+    hooks: Vec<(CommitHookId, CommitHook)>,
+    next_id: u64,
+}
+
+impl CommitHooks {
+    pub fn register(&mut self, hook: CommitHook) -> CommitHookId {
+        let id = CommitHookId(self.next_id);
+        self.next_id += 1;
+        self.hooks.push((id, hook));
+        id
+    }
+
+    /// Returns `false` if there is no hook registered with this id.
+    pub fn unregister(&mut self, id: CommitHookId) -> bool {
+        let len = self.hooks.len();
+        self.hooks.retain(|(hook_id, _)| *hook_id != id);
+        self.hooks.len() != len
+    }
+
+    /// Returns the hooks in the order they were registered, they are cloned so that
+    /// they can be invoked without holding the lock of the hooks.
+    pub fn hooks(&self) -> Vec<CommitHook> {
+        self.hooks.iter().map(|(_, hook)| hook.clone()).collect()
+    }
+}
+
+impl fmt::Debug for CommitHooks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CommitHooks").field("count", &self.hooks.len()).finish()
+    }
+}
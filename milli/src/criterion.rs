@@ -47,6 +47,33 @@ impl From<CriterionError> for Error {
     }
 }
 
+/// A ranking rule that depends on a setting that isn't enabled, the ranking rule is kept
+/// but it has no effect on the results until its dependency is satisfied.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub enum CriterionWarning {
+    /// The `sort` ranking rule requires some sortable attributes.
+    SortWithoutSortableAttributes,
+    /// An `asc` or `desc` ranking rule requires its field to be present in the documents.
+    UnknownAscDescField { criterion: Criterion, field: String },
+}
+
+impl fmt::Display for CriterionWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::SortWithoutSortableAttributes => f.write_str(
+                "the sort ranking rule has no effect as there is no sortable attributes, \
+it depends on the sortableAttributes setting",
+            ),
+            Self::UnknownAscDescField { criterion, field } => write!(
+                f,
+                "the {} ranking rule has no effect as the field {} is not present \
+in any document",
+                criterion, field
+            ),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub enum Criterion {
     /// Sorted by decreasing number of matched query terms.
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::Sha256;
+
+use crate::error::{FieldIdMapMissingEntry, InternalError};
+use crate::{FieldId, FieldsIdsMap, Result};
+
+/// The value returned in place of the value of a redacted field.
+pub const REDACTED_VALUE: &str = "[REDACTED]";
+
+/// How the value of a masked field is returned by the document retrieval APIs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MaskingMethod {
+    /// The value is replaced by the `[REDACTED]` string.
+    Redact,
+    /// The value is replaced by its HMAC-SHA256 keyed with a secret of the index, the documents
+    /// that have the same value for this field can still be grouped together. The hashes can't
+    /// be reversed by hashing the candidate values without the secret, which never leaves the
+    /// index, and they differ from one index to another.
+    Hash,
+}
+
+impl MaskingMethod {
+    fn mask(&self, raw_value: &[u8], secret: Option<&[u8]>) -> Value {
+        match (self, secret) {
+            (MaskingMethod::Hash, Some(secret)) => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(secret)
+                    .expect("HMAC accepts keys of any length");
+                mac.update(raw_value);
+                let hash = mac.finalize().into_bytes();
+                Value::String(hash.iter().map(|byte| format!("{:02x}", byte)).collect())
+            }
+            // The fields masked before the indexes had a secret are redacted until
+            // the masked fields are updated, which generates the secret.
+            (MaskingMethod::Hash, None) | (MaskingMethod::Redact, _) => {
+                Value::String(REDACTED_VALUE.to_string())
+            }
+        }
+    }
+}
+
+/// Transforms the raw obkv stores of the documents into the JSON objects returned to the users,
+/// only the displayed fields are returned and the values of the masked fields are masked.
+///
+/// It is built by [`Index::document_projection`](crate::Index::document_projection).
+#[derive(Debug, Clone)]
+pub struct DocumentProjection {
+    displayed_fields: Vec<FieldId>,
+    masked_fields: HashMap<FieldId, MaskingMethod>,
+    masking_secret: Option<Vec<u8>>,
+    fields_ids_map: FieldsIdsMap,
+}
+
+impl DocumentProjection {
+    pub(crate) fn new(
+        displayed_fields: Vec<FieldId>,
+        masked_fields: HashMap<FieldId, MaskingMethod>,
+        masking_secret: Option<Vec<u8>>,
+        fields_ids_map: FieldsIdsMap,
+    ) -> DocumentProjection {
+        DocumentProjection { displayed_fields, masked_fields, masking_secret, fields_ids_map }
+    }
+
+    pub fn to_json(&self, obkv: obkv::KvReaderU16) -> Result<Map<String, Value>> {
+        self.displayed_fields
+            .iter()
+            .copied()
+            .flat_map(|id| obkv.get(id).map(|value| (id, value)))
+            .map(|(id, value)| {
+                let name = self.fields_ids_map.name(id).ok_or(FieldIdMapMissingEntry::FieldId {
+                    field_id: id,
+                    process: "DocumentProjection::to_json",
+                })?;
+                let value = match self.masked_fields.get(&id) {
+                    Some(method) => method.mask(value, self.masking_secret.as_deref()),
+                    None => serde_json::from_slice(value).map_err(InternalError::SerdeJson)?,
+                };
+                Ok((name.to_owned(), value))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::btreemap;
+    use serde_json::json;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::update::{IndexDocuments, Settings};
+
+    #[test]
+    fn masked_fields() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_displayed_fields(vec![S("id"), S("name"), S("email"), S("phone")]);
+        builder.set_masked_fields(btreemap! {
+            S("email") => MaskingMethod::Hash,
+            S("phone") => MaskingMethod::Redact,
+        });
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": 0, "name": "kevin", "email": "kevin@example.com", "phone": "0123", "age": 20 },
+            { "id": 1, "name": "kevina", "email": "kevin@example.com" },
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let projection = index.document_projection(&rtxn, false).unwrap();
+        let documents = index.documents(&rtxn, vec![0, 1]).unwrap();
+        let first = projection.to_json(documents[0].1).unwrap();
+        let second = projection.to_json(documents[1].1).unwrap();
+        assert_eq!(first["name"], json!("kevin"));
+        assert_eq!(first["phone"], json!(REDACTED_VALUE));
+        assert_ne!(first["email"], json!("kevin@example.com"));
+        assert_eq!(first["email"], second["email"]);
+        assert_eq!(first["email"].as_str().unwrap().len(), 64);
+        assert!(first.get("age").is_none());
+
+        // The hashes are keyed with a secret of the index, another index
+        // returns another hash for the same value.
+        let other = TempIndex::new();
+        let mut wtxn = other.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &other, 0);
+        builder.set_masked_fields(btreemap! { S("email") => MaskingMethod::Hash });
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([{ "id": 0, "email": "kevin@example.com" }]);
+        IndexDocuments::new(&mut wtxn, &other, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let other_rtxn = other.read_txn().unwrap();
+        let other_projection = other.document_projection(&other_rtxn, false).unwrap();
+        let other_documents = other.documents(&other_rtxn, vec![0]).unwrap();
+        let other_first = other_projection.to_json(other_documents[0].1).unwrap();
+        assert_ne!(first["email"], other_first["email"]);
+
+        let projection = index.document_projection(&rtxn, true).unwrap();
+        let first = projection.to_json(documents[0].1).unwrap();
+        assert_eq!(first["email"], json!("kevin@example.com"));
+        assert_eq!(first["phone"], json!("0123"));
+    }
+}
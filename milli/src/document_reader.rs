@@ -0,0 +1,112 @@
+use obkv::KvReaderU16;
+use serde_json::{Map, Value};
+
+use crate::error::{FieldIdMapMissingEntry, InternalError};
+use crate::{FieldId, FieldsIdsMap, Result};
+
+/// A zero-copy reader over a document stored in the index.
+///
+/// The values of the fields are the raw JSON slices that are stored in the database,
+/// they are only deserialized when explicitly asked for.
+#[derive(Clone, Copy)]
+pub struct DocumentReader<'t> {
+    obkv: KvReaderU16<'t>,
+}
+
+impl<'t> DocumentReader<'t> {
+    pub fn new(obkv: KvReaderU16<'t>) -> DocumentReader<'t> {
+        DocumentReader { obkv }
+    }
+
+    /// Returns the raw JSON value of the given field, if the document contains it.
+    pub fn raw_value(&self, field_id: FieldId) -> Option<&'t [u8]> {
+        self.obkv.get(field_id)
+    }
+
+    /// Returns an iterator over the fields ids of the document and their raw JSON values,
+    /// ordered by field id.
+    pub fn iter(&self) -> impl Iterator<Item = (FieldId, &'t [u8])> + 't {
+        self.obkv.iter()
+    }
+
+    /// Deserializes the value of the given field, if the document contains it.
+    pub fn value(&self, field_id: FieldId) -> Result<Option<Value>> {
+        match self.raw_value(field_id) {
+            Some(bytes) => {
+                let value = serde_json::from_slice(bytes).map_err(InternalError::SerdeJson)?;
+                Ok(Some(value))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Deserializes all the fields of the document into a JSON object.
+    pub fn to_json(&self, fields_ids_map: &FieldsIdsMap) -> Result<Map<String, Value>> {
+        self.iter()
+            .map(|(id, bytes)| {
+                let name = fields_ids_map.name(id).ok_or(FieldIdMapMissingEntry::FieldId {
+                    field_id: id,
+                    process: "DocumentReader::to_json",
+                })?;
+                let value = serde_json::from_slice(bytes).map_err(InternalError::SerdeJson)?;
+                Ok((name.to_owned(), value))
+            })
+            .collect()
+    }
+
+    /// Returns the underlying obkv store of the document.
+    pub fn into_inner(self) -> KvReaderU16<'t> {
+        self.obkv
+    }
+}
+
+impl<'t> From<KvReaderU16<'t>> for DocumentReader<'t> {
+    fn from(obkv: KvReaderU16<'t>) -> DocumentReader<'t> {
+        DocumentReader::new(obkv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heed::EnvOpenOptions;
+    use serde_json::json;
+
+    use super::*;
+    use crate::update::IndexDocuments;
+    use crate::Index;
+
+    #[test]
+    fn read_stored_documents() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "name": "kevin", "tags": ["a", "b"] },
+            { "id": 2, "name": "bob" }
+        ]);
+        let builder = IndexDocuments::new(&mut wtxn, &index, 0);
+        builder.execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let name = fields_ids_map.id("name").unwrap();
+        let tags = fields_ids_map.id("tags").unwrap();
+
+        let document = index.document_reader(&rtxn, 0).unwrap().unwrap();
+        assert_eq!(document.raw_value(name), Some(&br#""kevin""#[..]));
+        assert_eq!(document.value(tags).unwrap(), Some(json!(["a", "b"])));
+        assert_eq!(document.iter().count(), 3);
+
+        let object = Value::Object(document.to_json(&fields_ids_map).unwrap());
+        assert_eq!(object, json!({ "id": 1, "name": "kevin", "tags": ["a", "b"] }));
+
+        let document = index.document_reader(&rtxn, 1).unwrap().unwrap();
+        assert_eq!(document.value(tags).unwrap(), None);
+
+        assert!(index.document_reader(&rtxn, 2).unwrap().is_none());
+    }
+}
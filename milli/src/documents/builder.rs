@@ -1,8 +1,15 @@
-use std::io;
+use std::convert::TryInto;
+use std::io::Write;
+use std::{fmt, io};
 
 use byteorder::{BigEndian, WriteBytesExt};
+use serde::de::value::MapAccessDeserializer;
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
 use serde::ser::Serialize;
+use serde::Deserialize;
+use serde_json::{Map, Value};
 
+use super::compression::decompress;
 use super::serde::DocumentSerializer;
 use super::{ByteCounter, DocumentsBatchIndex, DocumentsMetadata, Error};
 
@@ -29,7 +36,12 @@ pub struct DocumentBatchBuilder<W> {
 
 impl<W: io::Write + io::Seek> DocumentBatchBuilder<W> {
     pub fn new(writer: W) -> Result<Self, Error> {
-        let index = DocumentsBatchIndex::new();
+        DocumentBatchBuilder::with_index(writer, DocumentsBatchIndex::new())
+    }
+
+    /// Creates a builder whose fields ids start from the given index, the documents of
+    /// a batch read with this index can then be appended without being parsed again.
+    pub(super) fn with_index(writer: W, index: DocumentsBatchIndex) -> Result<Self, Error> {
         let mut writer = ByteCounter::new(writer);
         // add space to write the offset of the metadata at the end of the writer
         writer.write_u64::<BigEndian>(0)?;
@@ -68,6 +80,15 @@ impl<W: io::Write + io::Seek> DocumentBatchBuilder<W> {
         Ok(())
     }
 
+    /// Appends a document whose fields ids are the ones of the index of the builder.
+    pub(super) fn append_raw_document(&mut self, document: &[u8]) -> Result<(), Error> {
+        let document_len: u32 = document.len().try_into().map_err(|_| Error::DocumentTooLarge)?;
+        self.serializer.writer.write_u32::<BigEndian>(document_len)?;
+        self.serializer.writer.write_all(document)?;
+        self.serializer.count += 1;
+        Ok(())
+    }
+
     /// Adds documents to the builder.
     ///
     /// The internal index is updated with the fields found
@@ -77,4 +98,77 @@ impl<W: io::Write + io::Seek> DocumentBatchBuilder<W> {
         document.serialize(&mut self.serializer)?;
         Ok(())
     }
+
+    /// Adds the documents of a JSON payload, an object or an array of objects.
+    ///
+    /// The payload can be gzip or zstd compressed, it is decompressed and the documents
+    /// of an array are added one by one while it is read.
+    pub fn add_json_payload<R: io::BufRead>(&mut self, reader: R) -> Result<(), Error> {
+        let mut deserializer = serde_json::Deserializer::from_reader(decompress(reader)?);
+        let mut error = None;
+        let visitor = PayloadVisitor { builder: self, error: &mut error };
+        let result = de::Deserializer::deserialize_any(&mut deserializer, visitor);
+        // The errors of the builder are reported as they are, not as JSON errors.
+        match error {
+            Some(error) => return Err(error),
+            None => result?,
+        }
+        deserializer.end()?;
+        Ok(())
+    }
+
+    /// Adds the documents of a newline delimited JSON payload, one object per line.
+    ///
+    /// The payload can be gzip or zstd compressed, it is decompressed and
+    /// the documents are added one by one while it is read.
+    pub fn add_ndjson_payload<R: io::BufRead>(&mut self, reader: R) -> Result<(), Error> {
+        let reader = decompress(reader)?;
+        for document in
+            serde_json::Deserializer::from_reader(reader).into_iter::<Map<String, Value>>()
+        {
+            self.add_documents(document?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds the documents of a JSON payload to the builder while they are deserialized,
+/// only one document of an array is in memory at a time.
+struct PayloadVisitor<'a, W> {
+    builder: &'a mut DocumentBatchBuilder<W>,
+    error: &'a mut Option<Error>,
+}
+
+impl<W: io::Write + io::Seek> PayloadVisitor<'_, W> {
+    fn add_document<E: de::Error>(&mut self, document: Value) -> Result<(), E> {
+        let result = match document {
+            Value::Object(_) => self.builder.add_documents(document),
+            _ => Err(Error::InvalidDocumentFormat),
+        };
+        result.map_err(|error| {
+            let message = error.to_string();
+            *self.error = Some(error);
+            E::custom(message)
+        })
+    }
+}
+
+impl<'de, W: io::Write + io::Seek> Visitor<'de> for PayloadVisitor<'_, W> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an object or an array of objects")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(mut self, map: A) -> Result<(), A::Error> {
+        let document = Map::deserialize(MapAccessDeserializer::new(map))?;
+        self.add_document(Value::Object(document))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(document) = seq.next_element()? {
+            self.add_document(document)?;
+        }
+        Ok(())
+    }
 }
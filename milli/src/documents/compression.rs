@@ -0,0 +1,44 @@
+use std::io::{self, BufRead, Read};
+
+use flate2::bufread::MultiGzDecoder;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// The compression of a documents payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadCompression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl PayloadCompression {
+    /// Detects the compression of a payload from its first bytes.
+    pub fn detect(header: &[u8]) -> PayloadCompression {
+        if header.starts_with(GZIP_MAGIC) {
+            PayloadCompression::Gzip
+        } else if header.starts_with(ZSTD_MAGIC) {
+            PayloadCompression::Zstd
+        } else {
+            PayloadCompression::None
+        }
+    }
+
+    /// Wraps the reader of a payload compressed with this compression into
+    /// a reader that decompresses it while it is read.
+    pub fn decoder<'a, R: BufRead + 'a>(self, reader: R) -> io::Result<Box<dyn Read + 'a>> {
+        match self {
+            PayloadCompression::None => Ok(Box::new(reader)),
+            PayloadCompression::Gzip => Ok(Box::new(MultiGzDecoder::new(reader))),
+            PayloadCompression::Zstd => Ok(Box::new(zstd::Decoder::with_buffer(reader)?)),
+        }
+    }
+}
+
+/// Returns a reader that streams the decompressed content of the payload,
+/// the compression is detected from the first bytes of the payload.
+pub fn decompress<'a, R: BufRead + 'a>(mut reader: R) -> io::Result<Box<dyn Read + 'a>> {
+    let compression = PayloadCompression::detect(reader.fill_buf()?);
+    compression.decoder(reader)
+}
@@ -1,4 +1,6 @@
 mod builder;
+mod compression;
+mod partition;
 /// The documents module defines an intermediary document format that milli uses for indexation, and
 /// provides an API to easily build and read such documents.
 ///
@@ -12,6 +14,8 @@ use std::{fmt, io};
 use ::serde::{Deserialize, Serialize};
 use bimap::BiHashMap;
 pub use builder::DocumentBatchBuilder;
+pub use compression::{decompress, PayloadCompression};
+pub use partition::{partition_documents, partition_documents_ids, partition_of};
 pub use reader::DocumentBatchReader;
 
 use crate::FieldId;
@@ -64,6 +68,12 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(other: serde_json::Error) -> Self {
+        Self::JsonError(other)
+    }
+}
+
 impl From<bincode::Error> for Error {
     fn from(other: bincode::Error) -> Self {
         Self::Serialize(other)
@@ -230,4 +240,56 @@ mod test {
         let nested: Value = serde_json::from_slice(doc.get(0).unwrap()).unwrap();
         assert_eq!(nested, json!({ "toto": ["hello"] }));
     }
+
+    #[test]
+    fn add_compressed_payloads() {
+        use std::io::Write;
+
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+
+        let ndjson = "{ \"id\": 1, \"name\": \"kevin\" }\n{ \"id\": 2, \"name\": \"kevina\" }\n";
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(ndjson.as_bytes()).unwrap();
+        let gzip = encoder.finish().unwrap();
+        assert_eq!(PayloadCompression::detect(&gzip), PayloadCompression::Gzip);
+
+        let json = r#"[{ "id": 3, "name": "benoit" }]"#;
+        let zstd = zstd::encode_all(json.as_bytes(), 0).unwrap();
+        assert_eq!(PayloadCompression::detect(&zstd), PayloadCompression::Zstd);
+
+        let mut cursor = io::Cursor::new(Vec::new());
+        let mut builder = DocumentBatchBuilder::new(&mut cursor).unwrap();
+        builder.add_ndjson_payload(&gzip[..]).unwrap();
+        builder.add_json_payload(&zstd[..]).unwrap();
+        builder.add_ndjson_payload(ndjson.as_bytes()).unwrap();
+        builder.finish().unwrap();
+
+        cursor.set_position(0);
+        let documents = DocumentBatchReader::from_reader(cursor).unwrap();
+        assert_eq!(documents.len(), 5);
+    }
+
+    #[test]
+    fn add_json_payload_documents_one_by_one() {
+        let mut cursor = io::Cursor::new(Vec::new());
+        let mut builder = DocumentBatchBuilder::new(&mut cursor).unwrap();
+        builder.add_json_payload(r#"{ "id": 1 }"#.as_bytes()).unwrap();
+        builder.add_json_payload(r#"[{ "id": 2 }, { "id": 3 }]"#.as_bytes()).unwrap();
+        assert_eq!(builder.len(), 3);
+
+        // The documents before an invalid one are already added.
+        let result = builder.add_json_payload(r#"[{ "id": 4 }, 5, { "id": 6 }]"#.as_bytes());
+        assert!(matches!(result, Err(Error::InvalidDocumentFormat)));
+        assert_eq!(builder.len(), 4);
+        let result = builder.add_json_payload(r#"[[{ "id": 7 }]]"#.as_bytes());
+        assert!(matches!(result, Err(Error::InvalidDocumentFormat)));
+        let result = builder.add_json_payload(r#"{ "id": 8 } 9"#.as_bytes());
+        assert!(matches!(result, Err(Error::JsonError(_))));
+        builder.finish().unwrap();
+
+        cursor.set_position(0);
+        let documents = DocumentBatchReader::from_reader(cursor).unwrap();
+        assert_eq!(documents.len(), 5);
+    }
 }
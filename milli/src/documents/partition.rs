@@ -0,0 +1,156 @@
+use std::hash::Hasher;
+use std::io;
+use std::ops::RangeInclusive;
+
+use obkv::KvReader;
+use serde_json::{Map, Value};
+use siphasher::sip::SipHasher13;
+
+use super::{DocumentBatchBuilder, DocumentBatchReader};
+use crate::error::{InternalError, UserError};
+use crate::{FieldId, Result};
+
+/// Returns the partition, between `0` and `partitions`, the document with the external id
+/// belongs to. The id is hashed with SipHash-1-3 and zero keys, which is a specified hash
+/// that doesn't change across versions and machines, the documents can be routed to the
+/// same partition by every process.
+pub fn partition_of(external_id: &str, partitions: usize) -> usize {
+    assert!(partitions > 0, "the number of partitions must be greater than zero");
+    (external_id_hash(external_id) % partitions as u64) as usize
+}
+
+fn external_id_hash(external_id: &str) -> u64 {
+    let mut hasher = SipHasher13::new_with_keys(0, 0);
+    hasher.write(external_id.trim().as_bytes());
+    hasher.finish()
+}
+
+/// Returns the internal documents ids the documents of the partition are given, the
+/// documents ids space is split in contiguous ranges of the same size for every partition.
+///
+/// The ids of the documents of the different partitions never collide, the results
+/// of the indexes of the partitions can be merged by their internal documents ids.
+pub fn partition_documents_ids(partition: usize, partitions: usize) -> RangeInclusive<u32> {
+    assert!(partition < partitions, "the partition must be lower than the number of partitions");
+    let size = (u32::max_value() as u64 + 1) / partitions as u64;
+    let start = partition as u64 * size;
+    let end = if partition + 1 == partitions { u32::max_value() as u64 } else { start + size - 1 };
+    start as u32..=end as u32
+}
+
+/// Splits the documents of the batch into one sub-batch per writer, by a hash of their
+/// primary key, and returns the number of documents written in every sub-batch.
+///
+/// The documents are copied as they are in the sub-batches, only the value of their
+/// primary key is parsed. Panics if there is no writer and the batch isn't empty.
+pub fn partition_documents<R, W>(
+    mut reader: DocumentBatchReader<R>,
+    primary_key: &str,
+    writers: Vec<W>,
+) -> Result<Vec<usize>>
+where
+    R: io::Read + io::Seek,
+    W: io::Write + io::Seek,
+{
+    let index = reader.index().clone();
+    let primary_key_id = index.get_by_right(primary_key).copied();
+
+    let mut builders = Vec::with_capacity(writers.len());
+    for writer in writers {
+        builders.push(DocumentBatchBuilder::with_index(writer, index.clone())?);
+    }
+
+    while let Some(bytes) = reader.next_document_bytes()? {
+        let document = KvReader::<FieldId>::new(bytes);
+        let value = primary_key_id.and_then(|id| document.get(id));
+        let value =
+            value.map(serde_json::from_slice).transpose().map_err(InternalError::SerdeJson)?;
+        let external_id = match value {
+            Some(Value::String(string)) => string,
+            Some(Value::Number(number)) => number.to_string(),
+            Some(content) => return Err(UserError::InvalidDocumentId { document_id: content })?,
+            None => {
+                let mut json = Map::new();
+                for (key, value) in document.iter() {
+                    let key = index.get_by_left(&key).cloned();
+                    let value = serde_json::from_slice::<Value>(&value).ok();
+                    if let Some((k, v)) = key.zip(value) {
+                        json.insert(k, v);
+                    }
+                }
+                return Err(UserError::MissingDocumentId { document: json })?;
+            }
+        };
+
+        let partition = partition_of(&external_id, builders.len());
+        builders[partition].append_raw_document(bytes)?;
+    }
+
+    let mut counts = Vec::with_capacity(builders.len());
+    for builder in builders {
+        counts.push(builder.len());
+        builder.finish()?;
+    }
+
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn partition_by_primary_key() {
+        let documents = documents!([
+            { "id": 1, "name": "kevin" },
+            { "id": "2", "name": "kevina" },
+            { "id": 3, "name": "benoit" },
+            { "id": 4, "name": "bernard" }
+        ]);
+
+        let mut writers = vec![Cursor::new(Vec::new()), Cursor::new(Vec::new())];
+        let counts = partition_documents(documents, "id", writers.iter_mut().collect()).unwrap();
+        assert_eq!(counts.iter().sum::<usize>(), 4);
+
+        for (partition, writer) in writers.into_iter().enumerate() {
+            let mut reader =
+                DocumentBatchReader::from_reader(Cursor::new(writer.into_inner())).unwrap();
+            assert_eq!(reader.len(), counts[partition]);
+            let id_field = reader.index().get_by_right("id").copied().unwrap();
+            while let Some((_, document)) = reader.next_document_with_index().unwrap() {
+                let id: Value = serde_json::from_slice(document.get(id_field).unwrap()).unwrap();
+                let id = match id {
+                    Value::String(string) => string,
+                    value => value.to_string(),
+                };
+                assert_eq!(partition_of(&id, 2), partition);
+            }
+        }
+
+        let documents = documents!([{ "name": "kevin" }]);
+        let writers = vec![Cursor::new(Vec::new())];
+        assert!(partition_documents(documents, "id", writers).is_err());
+    }
+
+    #[test]
+    fn partition_hash_is_stable() {
+        // These values must never change, the documents would be routed to other partitions.
+        assert_eq!(external_id_hash("1"), 11582886058036617813);
+        assert_eq!(external_id_hash(" 2 "), 1450935647650615885);
+        assert_eq!(external_id_hash("kevin"), 11974067066368579610);
+        assert_eq!(partition_of("1", 3), 0);
+        assert_eq!(partition_of("2", 3), 1);
+        assert_eq!(partition_of("kevin", 3), 2);
+        assert_eq!(partition_of("kevin", 7), 4);
+    }
+
+    #[test]
+    fn partitions_documents_ids_ranges() {
+        assert_eq!(partition_documents_ids(0, 1), 0..=u32::max_value());
+        assert_eq!(partition_documents_ids(0, 2), 0..=2_147_483_647);
+        assert_eq!(partition_documents_ids(1, 2), 2_147_483_648..=u32::max_value());
+        assert_eq!(partition_documents_ids(2, 3).end(), &u32::max_value());
+    }
+}
@@ -1,9 +1,11 @@
+use std::hash::Hasher;
 use std::io;
-use std::io::{BufReader, Read};
+use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::mem::size_of;
 
 use byteorder::{BigEndian, ReadBytesExt};
 use obkv::KvReader;
+use siphasher::sip::SipHasher13;
 
 use super::{DocumentsBatchIndex, DocumentsMetadata, Error};
 use crate::FieldId;
@@ -46,19 +48,44 @@ impl<R: io::Read + io::Seek> DocumentBatchReader<R> {
     pub fn next_document_with_index<'a>(
         &'a mut self,
     ) -> io::Result<Option<(&'a DocumentsBatchIndex, KvReader<'a, FieldId>)>> {
+        match self.next_document_bytes()? {
+            Some(bytes) => Ok(Some((&self.metadata.index, KvReader::new(bytes)))),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the obkv bytes of the next document in the reader.
+    pub(super) fn next_document_bytes(&mut self) -> io::Result<Option<&[u8]>> {
         if self.seen_documents < self.metadata.count {
             let doc_len = self.reader.read_u32::<BigEndian>()?;
             self.buffer.resize(doc_len as usize, 0);
             self.reader.read_exact(&mut self.buffer)?;
             self.seen_documents += 1;
-
-            let reader = KvReader::new(&self.buffer);
-            Ok(Some((&self.metadata.index, reader)))
+            Ok(Some(&self.buffer))
         } else {
             Ok(None)
         }
     }
 
+    /// Returns a digest of the whole documents batch, it is stable across versions and machines.
+    ///
+    /// It must be called before the documents are read, the reader is moved back to the
+    /// first document once the batch is hashed.
+    pub(crate) fn digest(&mut self) -> io::Result<u64> {
+        debug_assert_eq!(self.seen_documents, 0);
+        let mut hasher = SipHasher13::new_with_keys(0, 0);
+        let mut buffer = [0; 8192];
+        self.reader.seek(SeekFrom::Start(0))?;
+        loop {
+            match self.reader.read(&mut buffer)? {
+                0 => break,
+                n => hasher.write(&buffer[..n]),
+            }
+        }
+        self.reader.seek(SeekFrom::Start(size_of::<u64>() as u64))?;
+        Ok(hasher.finish())
+    }
+
     /// Return the fields index for the documents batch.
     pub fn index(&self) -> &DocumentsBatchIndex {
         &self.metadata.index
@@ -0,0 +1,52 @@
+use chacha20poly1305::aead::{Aead, NewAead};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+
+use crate::error::UserError;
+use crate::Result;
+
+/// The length of the random nonce stored in front of every encrypted value.
+const NONCE_LENGTH: usize = 24;
+
+/// The 256 bits key used to encrypt the documents of an index.
+#[derive(Clone)]
+pub struct EncryptionKey([u8; 32]);
+
+impl EncryptionKey {
+    pub fn from_bytes(bytes: [u8; 32]) -> EncryptionKey {
+        EncryptionKey(bytes)
+    }
+}
+
+/// Encrypts the values of the documents database with XChaCha20-Poly1305,
+/// every value is stored with the random nonce it was encrypted with.
+pub(crate) struct DocumentsCipher {
+    cipher: XChaCha20Poly1305,
+}
+
+impl DocumentsCipher {
+    pub fn new(key: &EncryptionKey) -> DocumentsCipher {
+        DocumentsCipher { cipher: XChaCha20Poly1305::new(Key::from_slice(&key.0)) }
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+        let nonce: [u8; NONCE_LENGTH] = rand::random();
+        // The encryption can only fail when the plaintext is larger than 256 GiB.
+        let ciphertext = self.cipher.encrypt(XNonce::from_slice(&nonce), plaintext).unwrap();
+
+        let mut bytes = Vec::with_capacity(NONCE_LENGTH + ciphertext.len());
+        bytes.extend_from_slice(&nonce);
+        bytes.extend_from_slice(&ciphertext);
+        bytes
+    }
+
+    /// Decrypts a value, returns an error if it wasn't encrypted with the same key.
+    pub fn decrypt(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+        if bytes.len() < NONCE_LENGTH {
+            return Err(UserError::InvalidEncryptionKey.into());
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LENGTH);
+        self.cipher
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| UserError::InvalidEncryptionKey.into())
+    }
+}
@@ -7,8 +7,11 @@ use heed::{Error as HeedError, MdbError};
 use rayon::ThreadPoolBuildError;
 use serde_json::{Map, Value};
 
+use crate::proximity::MAX_ONE_ATTRIBUTE;
 use crate::search::ParserRule;
-use crate::{CriterionError, DocumentId, FieldId, SortError};
+use crate::{
+    documents, CriterionError, DocumentId, FieldId, NonFilterableAttributeError, SortError,
+};
 
 pub type Object = Map<String, Value>;
 
@@ -21,12 +24,15 @@ pub enum Error {
     InternalError(InternalError),
     IoError(io::Error),
     UserError(UserError),
+    /// The search has been aborted by the caller before it completed.
+    SearchAborted,
 }
 
 #[derive(Debug)]
 pub enum InternalError {
     DatabaseClosing,
     DatabaseMissingEntry { db_name: &'static str, key: Option<&'static str> },
+    DocumentsBatch(documents::Error),
     FieldIdMapMissingEntry(FieldIdMapMissingEntry),
     Fst(fst::Error),
     GrenadInvalidCompressionType,
@@ -57,22 +63,38 @@ pub enum UserError {
     AttributeLimitReached,
     CriterionError(CriterionError),
     DocumentLimitReached,
+    EncryptedDocuments,
+    IndexAlreadyHasWriter { pid: Option<u32> },
     InvalidDocumentId { document_id: Value },
+    InvalidEncryptionKey,
     InvalidFacetsDistribution { invalid_facets_name: HashSet<String> },
     InvalidFilter(pest::error::Error<ParserRule>),
     InvalidFilterAttribute(pest::error::Error<ParserRule>),
     InvalidGeoField { document_id: Value, object: Value },
+    InvalidGroupByAttribute { field: String, valid_fields: HashSet<String> },
+    InvalidCandidatesFacetAttribute { field: String, valid_fields: HashSet<String> },
+    InvalidFacetRenameAttribute { field: String, valid_fields: HashSet<String> },
+    InvalidLocale { locale: String },
+    InvalidPositionStride { stride: u32 },
+    InvalidMaxAttributesToRank,
+    InvalidMinWordSizeForTypos { one_typo: u8, two_typos: u8 },
+    InvalidLookupAttribute { field: String, valid_fields: HashSet<String> },
     InvalidSortableAttribute { field: String, valid_fields: HashSet<String> },
     SortRankingRuleMissing,
+    ParentFieldMissing,
     InvalidStoreFile,
     MaxDatabaseSizeReached,
     MissingDocumentId { document: Object },
+    MissingEncryptionKey,
     MissingPrimaryKey,
+    NonFilterableAttribute(NonFilterableAttributeError),
     NoSpaceLeftOnDevice,
     PrimaryKeyCannotBeChanged,
+    PrimaryKeyCannotBeEdited { document_id: Value },
     PrimaryKeyCannotBeReset,
     SerdeJson(serde_json::Error),
     SortError(SortError),
+    UnencryptedDocuments,
     UnknownInternalDocumentId { document_id: DocumentId },
 }
 
@@ -83,6 +105,15 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<documents::Error> for Error {
+    fn from(error: documents::Error) -> Error {
+        match error {
+            documents::Error::Io(error) => Error::IoError(error),
+            error => Error::InternalError(InternalError::DocumentsBatch(error)),
+        }
+    }
+}
+
 impl From<fst::Error> for Error {
     fn from(error: fst::Error) -> Error {
         Error::InternalError(InternalError::Fst(error))
@@ -172,6 +203,7 @@ impl fmt::Display for Error {
             Self::InternalError(error) => write!(f, "internal: {}", error),
             Self::IoError(error) => error.fmt(f),
             Self::UserError(error) => error.fmt(f),
+            Self::SearchAborted => f.write_str("the search has been aborted"),
         }
     }
 }
@@ -184,6 +216,7 @@ impl fmt::Display for InternalError {
             Self::DatabaseMissingEntry { db_name, key } => {
                 write!(f, "missing {} in the {} database", key.unwrap_or("key"), db_name)
             }
+            Self::DocumentsBatch(error) => error.fmt(f),
             Self::FieldIdMapMissingEntry(error) => error.fmt(f),
             Self::Fst(error) => error.fmt(f),
             Self::GrenadInvalidCompressionType => {
@@ -211,6 +244,24 @@ impl fmt::Display for UserError {
             Self::AttributeLimitReached => f.write_str("maximum number of attributes reached"),
             Self::CriterionError(error) => write!(f, "{}", error),
             Self::DocumentLimitReached => f.write_str("maximum number of documents reached"),
+            Self::EncryptedDocuments => {
+                f.write_str("the documents are encrypted and can only be read as owned bytes")
+            }
+            Self::InvalidEncryptionKey => {
+                f.write_str("the encryption key cannot decrypt the documents of the index")
+            }
+            Self::MissingEncryptionKey => {
+                f.write_str("the documents of the index are encrypted but no key was provided")
+            }
+            Self::UnencryptedDocuments => f.write_str(
+                "the index already contains unencrypted documents and cannot be encrypted",
+            ),
+            Self::IndexAlreadyHasWriter { pid: Some(pid) } => {
+                write!(f, "the index is already opened for writing by the process {}", pid)
+            }
+            Self::IndexAlreadyHasWriter { pid: None } => {
+                f.write_str("the index is already opened for writing by another process")
+            }
             Self::InvalidFacetsDistribution { invalid_facets_name } => {
                 let name_list =
                     invalid_facets_name.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(", ");
@@ -226,6 +277,63 @@ impl fmt::Display for UserError {
                 "the document with the id: {} contains an invalid _geo field: {}",
                 document_id, object
             ),
+            Self::InvalidGroupByAttribute { field, valid_fields } => {
+                let valid_names =
+                    valid_fields.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(", ");
+                write!(
+                    f,
+                    "Attribute {} is not faceted and can't be used to group the documents, \
+available faceted attributes are: {}",
+                    field, valid_names
+                )
+            }
+            Self::InvalidCandidatesFacetAttribute { field, valid_fields } => {
+                let valid_names =
+                    valid_fields.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(", ");
+                write!(
+                    f,
+                    "Attribute {} is not faceted and its values can't be used to select \
+candidates, available faceted attributes are: {}",
+                    field, valid_names
+                )
+            }
+            Self::InvalidFacetRenameAttribute { field, valid_fields } => {
+                let valid_names =
+                    valid_fields.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(", ");
+                write!(
+                    f,
+                    "Attribute {} is not faceted and its values can't be renamed, \
+available faceted attributes are: {}",
+                    field, valid_names
+                )
+            }
+            Self::InvalidLocale { locale } => {
+                write!(f, "invalid locale `{}`, available locales are: zh, ja, ko", locale)
+            }
+            Self::InvalidMinWordSizeForTypos { one_typo, two_typos } => write!(
+                f,
+                "the minimum word size for one typo ({}) must be less than or equal to \
+the minimum word size for two typos ({})",
+                one_typo, two_typos
+            ),
+            Self::InvalidPositionStride { stride } => write!(
+                f,
+                "invalid position stride {}, it must be between 1 and {}",
+                stride, MAX_ONE_ATTRIBUTE
+            ),
+            Self::InvalidMaxAttributesToRank => {
+                f.write_str("the maximum number of attributes to rank must be greater than 0")
+            }
+            Self::InvalidLookupAttribute { field, valid_fields } => {
+                let valid_names =
+                    valid_fields.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(", ");
+                write!(
+                    f,
+                    "Attribute {} is not faceted and can't be used in a lookup, \
+available faceted attributes are: {}",
+                    field, valid_names
+                )
+            }
             Self::InvalidDocumentId { document_id } => {
                 let json = serde_json::to_string(document_id).unwrap();
                 write!(
@@ -237,6 +345,7 @@ only composed of alphanumeric characters (a-z A-Z 0-9), hyphens (-) and undersco
                 )
             }
             Self::InvalidFilterAttribute(error) => error.fmt(f),
+            Self::NonFilterableAttribute(error) => write!(f, "{}", error),
             Self::InvalidSortableAttribute { field, valid_fields } => {
                 let valid_names =
                     valid_fields.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(", ");
@@ -249,6 +358,10 @@ only composed of alphanumeric characters (a-z A-Z 0-9), hyphens (-) and undersco
             Self::SortRankingRuleMissing => f.write_str(
                 "You must specify where \"sort\" is listed in the \
 rankingRules setting to use the sort parameter at search time",
+            ),
+            Self::ParentFieldMissing => f.write_str(
+                "You must specify the parent field setting to use \
+a relationship at search time",
             ),
             Self::MissingDocumentId { document } => {
                 let json = serde_json::to_string(document).unwrap();
@@ -262,6 +375,10 @@ rankingRules setting to use the sort parameter at search time",
             Self::PrimaryKeyCannotBeChanged => {
                 f.write_str("primary key cannot be changed if the database contains documents")
             }
+            Self::PrimaryKeyCannotBeEdited { document_id } => {
+                let json = serde_json::to_string(document_id).unwrap();
+                write!(f, "the primary key of the document {} cannot be edited", json)
+            }
             Self::PrimaryKeyCannotBeReset => {
                 f.write_str("primary key cannot be reset if the database contains documents")
             }
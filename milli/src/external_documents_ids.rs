@@ -3,9 +3,12 @@ use std::collections::HashMap;
 use std::convert::TryInto;
 use std::{fmt, str};
 
-use fst::map::IndexedValue;
+use fst::automaton::{Automaton, Str};
+use fst::map::{IndexedValue, OpBuilder, Union};
 use fst::{IntoStreamer, Streamer};
 
+use crate::DocumentId;
+
 const DELETED_ID: u64 = u64::MAX;
 
 pub struct ExternalDocumentsIds<'a> {
@@ -82,6 +85,22 @@ impl<'a> ExternalDocumentsIds<'a> {
         self.merge_soft_into_hard()
     }
 
+    /// Returns an iterator over all the external ids and their internal ids,
+    /// ordered by external id.
+    pub fn iter(&self) -> ExternalDocumentsIdsIter {
+        self.prefix_iter("")
+    }
+
+    /// Returns an iterator over the external ids that start with the given prefix
+    /// and their internal ids, ordered by external id.
+    pub fn prefix_iter<'p>(&'p self, prefix: &'p str) -> ExternalDocumentsIdsIter<'p> {
+        let union = OpBuilder::new()
+            .add(self.hard.search(Str::new(prefix).starts_with()))
+            .add(self.soft.search(Str::new(prefix).starts_with()))
+            .union();
+        ExternalDocumentsIdsIter { union }
+    }
+
     /// An helper function to debug this type, returns an `HashMap` of both,
     /// soft and hard fst maps, combined.
     pub fn to_hash_map(&self) -> HashMap<String, u32> {
@@ -123,6 +142,27 @@ impl<'a> ExternalDocumentsIds<'a> {
     }
 }
 
+/// An iterator over the external ids of the documents and their internal ids,
+/// the external ids of the deleted documents are skipped.
+pub struct ExternalDocumentsIdsIter<'a> {
+    union: Union<'a>,
+}
+
+impl Iterator for ExternalDocumentsIdsIter<'_> {
+    type Item = (String, DocumentId);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((external_id, marked_docids)) = self.union.next() {
+            let id = indexed_last_value(marked_docids).unwrap();
+            if id != DELETED_ID {
+                let external_id = str::from_utf8(external_id).unwrap();
+                return Some((external_id.to_owned(), id.try_into().unwrap()));
+            }
+        }
+        None
+    }
+}
+
 impl fmt::Debug for ExternalDocumentsIds<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_tuple("ExternalDocumentsIds").field(&self.to_hash_map()).finish()
@@ -194,6 +234,34 @@ mod tests {
         assert_eq!(external_documents_ids.get("h"), Some(8));
     }
 
+    #[test]
+    fn iter_external_ids() {
+        let mut external_documents_ids = ExternalDocumentsIds::default();
+
+        let new_ids = fst::Map::from_iter(vec![("a", 1), ("ab", 2), ("abc", 3), ("b", 4)]).unwrap();
+        external_documents_ids.insert_ids(&new_ids).unwrap();
+
+        let del_ids = fst::Set::from_iter(vec!["ab"]).unwrap();
+        external_documents_ids.delete_ids(del_ids).unwrap();
+
+        let new_ids = fst::Map::from_iter(vec![("a", 5), ("ac", 6)]).unwrap();
+        external_documents_ids.insert_ids(&new_ids).unwrap();
+
+        let ids: Vec<_> = external_documents_ids.iter().collect();
+        let expected = vec![
+            (String::from("a"), 5),
+            (String::from("abc"), 3),
+            (String::from("ac"), 6),
+            (String::from("b"), 4),
+        ];
+        assert_eq!(ids, expected);
+
+        let ids: Vec<_> = external_documents_ids.prefix_iter("ab").collect();
+        assert_eq!(ids, vec![(String::from("abc"), 3)]);
+
+        assert_eq!(external_documents_ids.prefix_iter("z").count(), 0);
+    }
+
     #[test]
     fn strange_delete_insert_ids() {
         let mut external_documents_ids = ExternalDocumentsIds::default();
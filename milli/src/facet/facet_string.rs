@@ -0,0 +1,85 @@
+use std::borrow::Cow;
+use std::cmp;
+use std::hash::Hasher;
+use std::mem::size_of;
+
+use fxhash::FxHasher64;
+
+use crate::{DocumentId, FieldId};
+
+/// The maximum length of the LMDB keys.
+const MAX_LMDB_KEY_LENGTH: usize = 511;
+/// The length of the hexadecimal hash appended to the facet strings that are too long.
+const HASH_SUFFIX_LENGTH: usize = 16;
+
+/// The maximum length, in bytes, of a normalized facet string stored in the keys of the
+/// databases, these keys also contain a field id and, sometimes, a document id.
+pub const MAX_FACET_STRING_LENGTH: usize =
+    MAX_LMDB_KEY_LENGTH - size_of::<FieldId>() - size_of::<DocumentId>();
+
+/// Returns the string that is stored in the keys of the facet databases for a normalized
+/// facet string, the strings that are too long to fit in a key are truncated and suffixed
+/// with a hash of the whole string. The original strings are stored in the values.
+pub fn facet_string_key(normalized: &str) -> Cow<str> {
+    if normalized.len() <= MAX_FACET_STRING_LENGTH {
+        return Cow::Borrowed(normalized);
+    }
+
+    let mut end = MAX_FACET_STRING_LENGTH - HASH_SUFFIX_LENGTH;
+    while !normalized.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    let mut hasher = FxHasher64::default();
+    hasher.write(normalized.as_bytes());
+    Cow::Owned(format!("{}{:016x}", &normalized[..end], hasher.finish()))
+}
+
+/// Returns the part of a normalized prefix that starts the keys of all the strings that start
+/// with this prefix, the strings that are too long are truncated in the keys.
+pub fn facet_string_key_prefix(prefix: &str) -> &str {
+    // The truncated strings keep at least this number of bytes of the string.
+    let mut end = cmp::min(prefix.len(), MAX_FACET_STRING_LENGTH - HASH_SUFFIX_LENGTH - 3);
+    while !prefix.is_char_boundary(end) {
+        end -= 1;
+    }
+    &prefix[..end]
+}
+
+/// Returns the whole normalized string of a key of the facet databases, the strings that
+/// were truncated in the key are normalized again from their original string.
+pub fn facet_string_from_key<'a>(key: &'a str, original: &str) -> Cow<'a, str> {
+    // A truncated key ends with the hash and is at most 3 bytes shorter than the
+    // maximum length, as the string is truncated on a char boundary.
+    if key.len() + 3 < MAX_FACET_STRING_LENGTH {
+        return Cow::Borrowed(key);
+    }
+
+    let normalized = original.trim().to_lowercase();
+    if normalized.len() > MAX_FACET_STRING_LENGTH {
+        Cow::Owned(normalized)
+    } else {
+        Cow::Borrowed(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_facet_strings_keys() {
+        assert_eq!(facet_string_key("hello"), "hello");
+
+        let long = "é".repeat(MAX_FACET_STRING_LENGTH);
+        let other = format!("{}a", long);
+        let key = facet_string_key(&long);
+        assert!(key.len() <= MAX_FACET_STRING_LENGTH);
+        assert!(long.starts_with(&key[..key.len() - HASH_SUFFIX_LENGTH]));
+        assert_ne!(key, facet_string_key(&other));
+        assert_eq!(facet_string_from_key(&key, &long), long);
+        assert_eq!(facet_string_from_key("hello", "Hello "), "hello");
+        assert_eq!(facet_string_key_prefix("hel"), "hel");
+        assert!(key.starts_with(facet_string_key_prefix(&long)));
+    }
+}
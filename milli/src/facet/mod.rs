@@ -1,6 +1,10 @@
+mod facet_string;
 mod facet_type;
 mod facet_value;
 pub mod value_encoding;
 
+pub use self::facet_string::{
+    facet_string_from_key, facet_string_key, facet_string_key_prefix, MAX_FACET_STRING_LENGTH,
+};
 pub use self::facet_type::FacetType;
 pub use self::facet_value::FacetValue;
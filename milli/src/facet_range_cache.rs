@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::ops::Bound::{self, Excluded, Included, Unbounded};
+use std::sync::Arc;
+
+use roaring::RoaringBitmap;
+
+use crate::heed_codec::facet::FacetLevelValueF64Codec;
+use crate::search::FacetNumberRange;
+use crate::{CboRoaringBitmapCodec, FieldId};
+
+/// The cumulative documents ids of the groups of the highest facet level of a number field.
+///
+/// An open-ended range is answered with one of the cumulative bitmaps, only the group
+/// that contains the bound of the range must be explored in the deeper levels.
+#[derive(Debug)]
+pub(crate) struct FacetLevelCumulatives {
+    /// The left and right bounds of the groups of the level, in ascending order.
+    bounds: Vec<(f64, f64)>,
+    /// The documents ids of the groups up to the group at the same index, included.
+    prefixes: Vec<RoaringBitmap>,
+    /// The documents ids of the groups from the group at the same index to the last one.
+    suffixes: Vec<RoaringBitmap>,
+}
+
+impl FacetLevelCumulatives {
+    pub fn compute(
+        rtxn: &heed::RoTxn,
+        db: heed::Database<FacetLevelValueF64Codec, CboRoaringBitmapCodec>,
+        field_id: FieldId,
+        level: u8,
+    ) -> heed::Result<FacetLevelCumulatives> {
+        let mut bounds = Vec::new();
+        let mut groups = Vec::new();
+        for result in FacetNumberRange::new(rtxn, db, field_id, level, Unbounded, Unbounded)? {
+            let ((_fid, _level, left, right), docids) = result?;
+            bounds.push((left, right));
+            groups.push(docids);
+        }
+
+        let mut prefixes = Vec::with_capacity(groups.len());
+        let mut cumulative = RoaringBitmap::new();
+        for docids in &groups {
+            cumulative |= docids;
+            prefixes.push(cumulative.clone());
+        }
+
+        let mut suffixes = Vec::with_capacity(groups.len());
+        let mut cumulative = RoaringBitmap::new();
+        for docids in groups.iter().rev() {
+            cumulative |= docids;
+            suffixes.push(cumulative.clone());
+        }
+        suffixes.reverse();
+
+        Ok(FacetLevelCumulatives { bounds, prefixes, suffixes })
+    }
+
+    /// Returns the documents ids of the groups that are entirely greater than the bound,
+    /// with the left bound of the first of these groups.
+    pub fn greater_than(&self, left: Bound<f64>) -> Option<(&RoaringBitmap, f64)> {
+        let index = self.bounds.partition_point(|(l, _)| match left {
+            Included(left) => *l < left,
+            Excluded(left) => *l <= left,
+            Unbounded => false,
+        });
+        self.suffixes.get(index).map(|docids| (docids, self.bounds[index].0))
+    }
+
+    /// Returns the documents ids of the groups that are entirely lower than the bound,
+    /// with the right bound of the last of these groups.
+    pub fn lower_than(&self, right: Bound<f64>) -> Option<(&RoaringBitmap, f64)> {
+        let count = self.bounds.partition_point(|(_, r)| match right {
+            Included(right) => *r <= right,
+            Excluded(right) => *r < right,
+            Unbounded => true,
+        });
+        let index = count.checked_sub(1)?;
+        Some((&self.prefixes[index], self.bounds[index].1))
+    }
+}
+
+/// The cumulative bitmaps of the number fields, computed for a single commit of the index.
+///
+/// The entries of a commit are never modified, the cache is emptied as soon as
+/// the entries of a more recent commit are inserted.
+#[derive(Debug, Default)]
+pub(crate) struct FacetRangeCache {
+    commits_count: u64,
+    fields: HashMap<FieldId, Arc<FacetLevelCumulatives>>,
+}
+
+impl FacetRangeCache {
+    pub fn get(&self, commits_count: u64, field_id: FieldId) -> Option<Arc<FacetLevelCumulatives>> {
+        if self.commits_count == commits_count {
+            self.fields.get(&field_id).cloned()
+        } else {
+            None
+        }
+    }
+
+    pub fn insert(
+        &mut self,
+        commits_count: u64,
+        field_id: FieldId,
+        cumulatives: Arc<FacetLevelCumulatives>,
+    ) {
+        if commits_count > self.commits_count {
+            self.commits_count = commits_count;
+            self.fields.clear();
+        }
+        if commits_count == self.commits_count {
+            self.fields.insert(field_id, cumulatives);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Associates every field name with the JSON types of the values it took in the documents.
+///
+/// A field that is associated with more than one type had its type changed by an update.
+pub type FieldTypes = BTreeMap<String, BTreeSet<FieldType>>;
+
+/// The JSON type of the value of a field, `null` values don't have a type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FieldType {
+    Boolean,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl FieldType {
+    /// Returns the type of a raw JSON value as stored in the documents obkvs.
+    pub fn from_raw_value(value: &[u8]) -> Option<FieldType> {
+        match value.iter().find(|b| !b.is_ascii_whitespace())? {
+            b't' | b'f' => Some(FieldType::Boolean),
+            b'-' | b'0'..=b'9' => Some(FieldType::Number),
+            b'"' => Some(FieldType::String),
+            b'[' => Some(FieldType::Array),
+            b'{' => Some(FieldType::Object),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldType::Boolean => f.write_str("boolean"),
+            FieldType::Number => f.write_str("number"),
+            FieldType::String => f.write_str("string"),
+            FieldType::Array => f.write_str("array"),
+            FieldType::Object => f.write_str("object"),
+        }
+    }
+}
+
+/// Reported by a documents addition when the values of a field
+/// are of a type that this field never had before.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldTypeWarning {
+    pub field: String,
+    pub previous_types: BTreeSet<FieldType>,
+    pub new_type: FieldType,
+}
+
+impl fmt::Display for FieldTypeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let previous_types: Vec<_> = self.previous_types.iter().map(ToString::to_string).collect();
+        write!(
+            f,
+            "the field `{}` was of type {} and received a value of type {}",
+            self.field,
+            previous_types.join(", "),
+            self.new_type,
+        )
+    }
+}
+
+/// Records the type of a new value of a field, returns a warning when the field
+/// previously took values of other types and this type was not seen before.
+#[cfg(feature = "indexing")]
+pub(crate) fn record_field_type(
+    field_types: &mut FieldTypes,
+    field: &str,
+    value: &[u8],
+) -> Option<FieldTypeWarning> {
+    let new_type = FieldType::from_raw_value(value)?;
+    let types = field_types.entry(field.to_string()).or_default();
+    if types.is_empty() || types.contains(&new_type) {
+        types.insert(new_type);
+        return None;
+    }
+
+    let warning =
+        FieldTypeWarning { field: field.to_string(), previous_types: types.clone(), new_type };
+    types.insert(new_type);
+    Some(warning)
+}
@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+
+/// The maximum number of distinct fields tracked by the filter attempts,
+/// the fields that were the least often filtered on are forgotten first.
+pub const MAX_TRACKED_FILTER_ATTEMPTS: usize = 100;
+
+/// Counts the filters that were attempted on fields that are not filterable.
+#[derive(Debug, Default)]
+pub(crate) struct FilterAttempts {
+    fields: HashMap<String, u64>,
+}
+
+impl FilterAttempts {
+    pub fn record(&mut self, field: &str) {
+        if !self.fields.contains_key(field) && self.fields.len() >= MAX_TRACKED_FILTER_ATTEMPTS {
+            let least_attempted = self
+                .fields
+                .iter()
+                .min_by(|(fa, a), (fb, b)| a.cmp(b).then_with(|| fb.cmp(fa)))
+                .map(|(field, _)| field.clone());
+            if let Some(field) = least_attempted {
+                self.fields.remove(&field);
+            }
+        }
+
+        *self.fields.entry(field.to_string()).or_default() += 1;
+    }
+
+    /// Returns the fields with the number of filters attempted on them, most attempted first.
+    pub fn fields(&self) -> Vec<(String, u64)> {
+        let mut fields: Vec<_> =
+            self.fields.iter().map(|(field, count)| (field.clone(), *count)).collect();
+        fields.sort_by(|(fa, a), (fb, b)| b.cmp(a).then_with(|| fa.cmp(fb)));
+        fields
+    }
+
+    pub fn clear(&mut self) {
+        self.fields.clear();
+    }
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+/// The kinds of filters that can be used on a filterable field.
+///
+/// The facet levels that speed up the comparisons are not computed for the fields
+/// that can't be compared, it makes the indexing of the id-like fields faster.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterCapabilities {
+    /// The `=`, `!=` and `IN` filters.
+    pub equality: bool,
+    /// The `>`, `>=`, `<`, `<=` and `TO` filters.
+    pub comparison: bool,
+}
+
+impl Default for FilterCapabilities {
+    fn default() -> FilterCapabilities {
+        FilterCapabilities { equality: true, comparison: true }
+    }
+}
@@ -0,0 +1,60 @@
+use std::hash::Hasher;
+
+use fxhash::FxHasher64;
+
+/// Computes the simhash of a document from its words, the fingerprints of two documents
+/// that contain almost the same words only differ by a small number of bits.
+#[derive(Debug, Clone)]
+pub(crate) struct SimHasher {
+    weights: [i64; 64],
+}
+
+impl SimHasher {
+    pub fn new() -> SimHasher {
+        SimHasher { weights: [0; 64] }
+    }
+
+    /// Adds a word to the document, the weight is the number of times it appears in it.
+    pub fn add(&mut self, word: &[u8], weight: u32) {
+        let mut hasher = FxHasher64::default();
+        hasher.write(word);
+        let hash = mix(hasher.finish());
+        for (i, total) in self.weights.iter_mut().enumerate() {
+            if hash & (1 << i) != 0 {
+                *total += weight as i64;
+            } else {
+                *total -= weight as i64;
+            }
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.weights
+            .iter()
+            .enumerate()
+            .filter(|(_, total)| **total > 0)
+            .fold(0, |fingerprint, (i, _)| fingerprint | (1 << i))
+    }
+}
+
+/// The bits of a fx hash are not evenly distributed, we mix them with the splitmix64 finalizer.
+fn mix(mut hash: u64) -> u64 {
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+    hash ^ (hash >> 31)
+}
+
+/// The number of bands of 16 bits a fingerprint is split into, two fingerprints
+/// that differ by less bits than there are bands have at least one identical band.
+pub(crate) const FINGERPRINT_BANDS: u32 = 4;
+
+/// Returns the keys of the bands of a fingerprint in the fingerprint bands database,
+/// the index of the band in the upper 16 bits and the bits of the band in the lower ones.
+pub(crate) fn fingerprint_bands(fingerprint: u64) -> impl Iterator<Item = u32> {
+    (0..FINGERPRINT_BANDS).map(move |band| band << 16 | (fingerprint >> (band * 16)) as u16 as u32)
+}
+
+/// Returns the number of bits that differ between two fingerprints.
+pub fn fingerprints_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
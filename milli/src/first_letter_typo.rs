@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+/// How a typo on the first letter of a query word is counted.
+///
+/// The users rarely misspell the first letter of a word, the derivations that differ
+/// from the query word on their first letter are therefore often unrelated words.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FirstLetterTypo {
+    /// A typo on the first letter counts as one typo, like any other typo.
+    Normal,
+    /// A typo on the first letter counts as two typos.
+    Double,
+    /// The derivations of a word must start with the same letter as the word.
+    Disallowed,
+}
+
+impl FirstLetterTypo {
+    /// Returns the number of typos of a derivation of the word, given the edit distance
+    /// between them, `None` if the derivation isn't allowed.
+    pub fn typos(&self, word: &str, derivation: &str, distance: u8) -> Option<u8> {
+        if distance == 0 || word.chars().next() == derivation.chars().next() {
+            return Some(distance);
+        }
+
+        match self {
+            FirstLetterTypo::Normal => Some(distance),
+            FirstLetterTypo::Double => Some(distance + 1),
+            FirstLetterTypo::Disallowed => None,
+        }
+    }
+}
+
+impl Default for FirstLetterTypo {
+    fn default() -> FirstLetterTypo {
+        FirstLetterTypo::Normal
+    }
+}
@@ -10,7 +10,7 @@ pub use self::facet_level_value_f64_codec::FacetLevelValueF64Codec;
 pub use self::facet_level_value_u32_codec::FacetLevelValueU32Codec;
 pub use self::facet_string_level_zero_codec::FacetStringLevelZeroCodec;
 pub use self::facet_string_level_zero_value_codec::{
-    decode_prefix_string, encode_prefix_string, FacetStringLevelZeroValueCodec,
+    decode_prefix_string, encode_prefix_string, FacetStringLevelZeroValueCodec, StringValueCodec,
 };
 pub use self::facet_string_zero_bounds_value_codec::FacetStringZeroBoundsValueCodec;
 pub use self::field_doc_id_facet_f64_codec::FieldDocIdFacetF64Codec;
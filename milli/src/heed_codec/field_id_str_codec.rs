@@ -0,0 +1,28 @@
+use std::borrow::Cow;
+use std::str;
+
+use crate::{try_split_array_at, FieldId};
+
+pub struct FieldIdStrCodec;
+
+impl<'a> heed::BytesDecode<'a> for FieldIdStrCodec {
+    type DItem = (FieldId, &'a str);
+
+    fn bytes_decode(bytes: &'a [u8]) -> Option<Self::DItem> {
+        let (field_id_bytes, bytes) = try_split_array_at(bytes)?;
+        let field_id = u16::from_be_bytes(field_id_bytes);
+        let value = str::from_utf8(bytes).ok()?;
+        Some((field_id, value))
+    }
+}
+
+impl<'a> heed::BytesEncode<'a> for FieldIdStrCodec {
+    type EItem = (FieldId, &'a str);
+
+    fn bytes_encode((field_id, value): &Self::EItem) -> Option<Cow<[u8]>> {
+        let mut bytes = Vec::with_capacity(2 + value.len());
+        bytes.extend_from_slice(&field_id.to_be_bytes());
+        bytes.extend_from_slice(value.as_bytes());
+        Some(Cow::Owned(bytes))
+    }
+}
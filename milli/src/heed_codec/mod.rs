@@ -1,5 +1,6 @@
 mod beu32_str_codec;
 pub mod facet;
+mod field_id_str_codec;
 mod field_id_word_count_codec;
 mod obkv_codec;
 mod roaring_bitmap;
@@ -8,6 +9,7 @@ mod str_beu32_codec;
 mod str_str_u8_codec;
 
 pub use self::beu32_str_codec::BEU32StrCodec;
+pub use self::field_id_str_codec::FieldIdStrCodec;
 pub use self::field_id_word_count_codec::FieldIdWordCountCodec;
 pub use self::obkv_codec::ObkvCodec;
 pub use self::roaring_bitmap::{BoRoaringBitmapCodec, CboRoaringBitmapCodec, RoaringBitmapCodec};
@@ -1,47 +1,136 @@
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryInto;
 use std::mem::size_of;
+use std::ops::Bound::Unbounded;
+use std::ops::{Deref, DerefMut};
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 
 use chrono::{DateTime, Utc};
 use heed::flags::Flags;
 use heed::types::*;
-use heed::{Database, PolyDatabase, RoTxn, RwTxn};
+use heed::{CompactionOption, Database, PolyDatabase, RoTxn, RwTxn};
+use meilisearch_tokenizer::{Analyzer, AnalyzerConfig, TokenKind};
 use roaring::RoaringBitmap;
 use rstar::RTree;
-
-use crate::error::{InternalError, UserError};
+use tempfile::TempDir;
+use uuid::Uuid;
+
+use crate::commit_hooks::{CommitHookId, CommitHooks, CommitSummary};
+use crate::document_projection::{DocumentProjection, MaskingMethod};
+use crate::encryption::{DocumentsCipher, EncryptionKey};
+use crate::error::{InternalError, SerializationError, UserError};
+use crate::facet::{facet_string_from_key, facet_string_key_prefix};
+use crate::facet_range_cache::{FacetLevelCumulatives, FacetRangeCache};
 use crate::fields_ids_map::FieldsIdsMap;
+use crate::filter_attempts::FilterAttempts;
+use crate::fingerprint::{fingerprint_bands, fingerprints_distance, FINGERPRINT_BANDS};
 use crate::heed_codec::facet::{
     FacetLevelValueF64Codec, FacetStringLevelZeroCodec, FacetStringLevelZeroValueCodec,
     FieldDocIdFacetF64Codec, FieldDocIdFacetStringCodec,
 };
+use crate::proximity::ONE_ATTRIBUTE;
+use crate::query_statistics::QueryStatistics;
+use crate::search::{FacetNumberRange, SearchLogHook};
+use crate::writer_lock::WriterLock;
 use crate::{
-    default_criteria, BEU32StrCodec, BoRoaringBitmapCodec, CboRoaringBitmapCodec, Criterion,
-    DocumentId, ExternalDocumentsIds, FacetDistribution, FieldDistribution, FieldId,
-    FieldIdWordCountCodec, GeoPoint, ObkvCodec, Result, RoaringBitmapCodec, RoaringBitmapLenCodec,
-    Search, StrBEU32Codec, StrStrU8Codec, BEU32,
+    default_criteria, exact_word, process_tokens, AscDesc, BEU32StrCodec, BoRoaringBitmapCodec,
+    CboRoaringBitmapCodec, Criterion, CriterionWarning, DebugToken, DocumentId, DocumentReader,
+    ExternalDocumentsIds, FacetDistribution, FieldDistribution, FieldId, FieldIdStrCodec,
+    FieldIdWordCountCodec, FieldTypes, FilterCapabilities, FirstLetterTypo, GeoPoint, ObkvCodec,
+    Position, Result, RoaringBitmapCodec, RoaringBitmapLenCodec, Search, StopWordsMode,
+    StrBEU32Codec, StrStrU8Codec, TypoDistance, BEU16, BEU32, BEU64,
 };
 
+/// The number of words of a document, see [`Index::document_word_counts`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocumentWordCounts {
+    pub total: u32,
+    /// The number of words of each field of the document that contains words.
+    pub fields: BTreeMap<FieldId, u32>,
+}
+
+/// The parts of the index read by [`Index::warmup`] to load them in the page cache.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WarmupPlan {
+    /// Reads the words and the words prefixes FSTs.
+    pub words_fst: bool,
+    /// The number of prefixes whose documents ids are read, the shortest prefixes first
+    /// as they are the ones hit by the first letters typed by the users.
+    pub top_prefixes: usize,
+    /// Reads the highest level of the facet values of every faceted field,
+    /// the one the facet range searches start from.
+    pub facet_roots: bool,
+}
+
+impl Default for WarmupPlan {
+    fn default() -> WarmupPlan {
+        WarmupPlan { words_fst: true, top_prefixes: 100, facet_roots: true }
+    }
+}
+
+/// What was read by [`Index::warmup`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WarmupStats {
+    pub bytes_read: u64,
+    pub prefixes: usize,
+    pub facet_entries: usize,
+}
+
+/// The minimum number of characters of a query word to allow one typo on it by default.
+pub const DEFAULT_MIN_WORD_SIZE_FOR_ONE_TYPO: u8 = 5;
+/// The minimum number of characters of a query word to allow two typos on it by default.
+pub const DEFAULT_MIN_WORD_SIZE_FOR_TWO_TYPOS: u8 = 9;
+
 pub mod main_key {
+    pub const ATTRIBUTES_MAX_TYPOS_KEY: &str = "attributes-max-typos";
+    pub const COMMITS_COUNT_KEY: &str = "commits-count";
     pub const CRITERIA_KEY: &str = "criteria";
+    pub const DEFAULT_SORT_KEY: &str = "default-sort";
     pub const DISPLAYED_FIELDS_KEY: &str = "displayed-fields";
     pub const DISTINCT_FIELD_KEY: &str = "distinct-field-key";
+    pub const DOCUMENTS_ENCRYPTION_CHECK_KEY: &str = "documents-encryption-check";
+    pub const EXACT_ATTRIBUTES_KEY: &str = "exact-attributes";
+    pub const DOCUMENTS_FINGERPRINTING_KEY: &str = "documents-fingerprinting";
     pub const DOCUMENTS_IDS_KEY: &str = "documents-ids";
     pub const FILTERABLE_FIELDS_KEY: &str = "filterable-fields";
+    pub const FILTER_CAPABILITIES_KEY: &str = "filter-capabilities";
+    pub const FIRST_LETTER_TYPO_KEY: &str = "first-letter-typo";
     pub const SORTABLE_FIELDS_KEY: &str = "sortable-fields";
+    pub const FACET_VALUES_COUNTS_KEY: &str = "facet-values-counts";
     pub const FIELD_DISTRIBUTION_KEY: &str = "fields-distribution";
+    pub const FIELD_TYPES_KEY: &str = "field-types";
     pub const FIELDS_IDS_MAP_KEY: &str = "fields-ids-map";
     pub const GEO_FACETED_DOCUMENTS_IDS_KEY: &str = "geo-faceted-documents-ids";
     pub const GEO_RTREE_KEY: &str = "geo-rtree";
     pub const HARD_EXTERNAL_DOCUMENTS_IDS_KEY: &str = "hard-external-documents-ids";
+    pub const INDEXED_SYNONYMS_KEY: &str = "indexed-synonyms";
+    pub const MASKED_FIELDS_KEY: &str = "masked-fields";
+    pub const MASKING_SECRET_KEY: &str = "masking-secret";
+    pub const MAX_ATTRIBUTES_TO_RANK_KEY: &str = "max-attributes-to-rank";
+    pub const MAX_VALUES_PER_FACET_KEY: &str = "max-values-per-facet";
+    pub const MIN_WORD_SIZE_FOR_ONE_TYPO_KEY: &str = "min-word-size-for-one-typo";
+    pub const MIN_WORD_SIZE_FOR_TWO_TYPOS_KEY: &str = "min-word-size-for-two-typos";
     pub const NUMBER_FACETED_DOCUMENTS_IDS_PREFIX: &str = "number-faceted-documents-ids";
+    pub const PARENT_FIELD_KEY: &str = "parent-field";
+    pub const POSITION_STRIDE_KEY: &str = "position-stride";
+    pub const PREFIX_PAIR_PROXIMITY_DISABLED_KEY: &str = "prefix-pair-proximity-disabled";
+    pub const PROXIMITY_APPROXIMATION_THRESHOLD_KEY: &str = "proximity-approximation-threshold";
+    pub const PROXIMITY_EXCLUDED_FIELDS_KEY: &str = "proximity-excluded-fields";
     pub const PRIMARY_KEY_KEY: &str = "primary-key";
+    pub const QUERY_STATISTICS_KEY: &str = "query-statistics";
     pub const SEARCHABLE_FIELDS_KEY: &str = "searchable-fields";
     pub const SOFT_EXTERNAL_DOCUMENTS_IDS_KEY: &str = "soft-external-documents-ids";
     pub const STOP_WORDS_KEY: &str = "stop-words";
+    pub const STOP_WORDS_MODE_KEY: &str = "stop-words-mode";
+    pub const STOP_WORDS_MIGRATED_KEY: &str = "stop-words-migrated";
     pub const STRING_FACETED_DOCUMENTS_IDS_PREFIX: &str = "string-faceted-documents-ids";
     pub const SYNONYMS_KEY: &str = "synonyms";
+    pub const TYPO_DISABLED_ON_WORDS_KEY: &str = "typo-disabled-on-words";
+    pub const TYPO_DISTANCE_KEY: &str = "typo-distance";
+    pub const UUID_KEY: &str = "uuid";
+    pub const VERSION_KEY: &str = "version";
     pub const WORDS_FST_KEY: &str = "words-fst";
     pub const WORDS_PREFIXES_FST_KEY: &str = "words-prefixes-fst";
     pub const CREATED_AT_KEY: &str = "created-at";
@@ -51,6 +140,7 @@ pub mod main_key {
 pub mod db_name {
     pub const MAIN: &str = "main";
     pub const WORD_DOCIDS: &str = "word-docids";
+    pub const EXACT_WORD_DOCIDS: &str = "exact-word-docids";
     pub const WORD_PREFIX_DOCIDS: &str = "word-prefix-docids";
     pub const DOCID_WORD_POSITIONS: &str = "docid-word-positions";
     pub const WORD_PAIR_PROXIMITY_DOCIDS: &str = "word-pair-proximity-docids";
@@ -58,11 +148,18 @@ pub mod db_name {
     pub const WORD_POSITION_DOCIDS: &str = "word-position-docids";
     pub const WORD_PREFIX_POSITION_DOCIDS: &str = "word-prefix-position-docids";
     pub const FIELD_ID_WORD_COUNT_DOCIDS: &str = "field-id-word-count-docids";
+    pub const FIELD_ID_EXACT_VALUE_DOCIDS: &str = "field-id-exact-value-docids";
     pub const FACET_ID_F64_DOCIDS: &str = "facet-id-f64-docids";
     pub const FACET_ID_STRING_DOCIDS: &str = "facet-id-string-docids";
     pub const FIELD_ID_DOCID_FACET_F64S: &str = "field-id-docid-facet-f64s";
     pub const FIELD_ID_DOCID_FACET_STRINGS: &str = "field-id-docid-facet-strings";
+    pub const FACET_ID_EXISTS_DOCIDS: &str = "facet-id-exists-docids";
+    pub const FACET_ID_IS_NULL_DOCIDS: &str = "facet-id-is-null-docids";
+    pub const FACET_ID_IS_EMPTY_DOCIDS: &str = "facet-id-is-empty-docids";
     pub const DOCUMENTS: &str = "documents";
+    pub const DOCID_FINGERPRINTS: &str = "docid-fingerprints";
+    pub const FINGERPRINT_BAND_DOCIDS: &str = "fingerprint-band-docids";
+    pub const DOCID_WORD_COUNTS: &str = "docid-word-counts";
 }
 
 #[derive(Clone)]
@@ -75,6 +172,8 @@ pub struct Index {
 
     /// A word and all the documents ids containing the word.
     pub word_docids: Database<Str, RoaringBitmapCodec>,
+    /// A word that is not normalized, only lowercased, and all the documents ids containing it.
+    pub exact_word_docids: Database<Str, RoaringBitmapCodec>,
     /// A prefix of word and all the documents ids containing this prefix.
     pub word_prefix_docids: Database<Str, RoaringBitmapCodec>,
 
@@ -90,6 +189,9 @@ pub struct Index {
     pub word_position_docids: Database<StrBEU32Codec, CboRoaringBitmapCodec>,
     /// Maps the field id and the word count with the docids that corresponds to it.
     pub field_id_word_count_docids: Database<FieldIdWordCountCodec, CboRoaringBitmapCodec>,
+    /// Maps the field id and the normalized value of an exact attribute with the docids whose
+    /// field is exactly this value.
+    pub field_id_exact_value_docids: Database<FieldIdStrCodec, RoaringBitmapCodec>,
     /// Maps the position of a word prefix with all the docids where this prefix appears.
     pub word_prefix_position_docids: Database<StrBEU32Codec, CboRoaringBitmapCodec>,
 
@@ -102,21 +204,73 @@ pub struct Index {
     pub field_id_docid_facet_f64s: Database<FieldDocIdFacetF64Codec, Unit>,
     /// Maps the document id, the facet field id and the strings.
     pub field_id_docid_facet_strings: Database<FieldDocIdFacetStringCodec, Str>,
+    /// Maps the facet field id with the docids of the documents that contain the field,
+    /// whatever its value is.
+    pub facet_id_exists_docids: Database<OwnedType<BEU16>, CboRoaringBitmapCodec>,
+    /// Maps the facet field id with the docids of the documents where the field is null.
+    pub facet_id_is_null_docids: Database<OwnedType<BEU16>, CboRoaringBitmapCodec>,
+    /// Maps the facet field id with the docids of the documents where the field
+    /// is an empty string, an empty array or an empty object.
+    pub facet_id_is_empty_docids: Database<OwnedType<BEU16>, CboRoaringBitmapCodec>,
 
     /// Maps the document id to the document as an obkv store.
     pub documents: Database<OwnedType<BEU32>, ObkvCodec>,
+    /// Maps the document id to the simhash of its words, only when the fingerprinting is enabled.
+    pub docid_fingerprints: Database<OwnedType<BEU32>, OwnedType<BEU64>>,
+    /// Maps the bands of the fingerprints with the docids of the documents
+    /// whose fingerprint contains this band.
+    pub fingerprint_band_docids: Database<OwnedType<BEU32>, CboRoaringBitmapCodec>,
+    /// Maps the document id to the number of words of its fields as an obkv store.
+    pub docid_word_counts: Database<OwnedType<BEU32>, ObkvCodec>,
+
+    /// The callback invoked on every search executed on this index.
+    search_log_hook: Option<SearchLogHook>,
+    /// The lock held when the index was opened with `Index::open_writer`.
+    writer_lock: Option<Arc<WriterLock>>,
+    /// The cipher of the documents when the index was opened with an encryption key.
+    documents_cipher: Option<Arc<DocumentsCipher>>,
+    /// The filters attempted on fields that are not filterable, shared by the clones of the index.
+    filter_attempts: Arc<Mutex<FilterAttempts>>,
+    /// The cumulative bitmaps of the facet number levels, shared by the clones of the index.
+    facet_range_cache: Arc<Mutex<FacetRangeCache>>,
+    /// The callbacks invoked after the commits, shared by the clones of the index.
+    commit_hooks: Arc<Mutex<CommitHooks>>,
+    /// The documents ids written by the transaction created with `Index::write_txn_with_hooks`.
+    written_documents_ids: Arc<Mutex<Option<RoaringBitmap>>>,
 }
 
 impl Index {
-    pub fn new<P: AsRef<Path>>(mut options: heed::EnvOpenOptions, path: P) -> Result<Index> {
+    pub fn new<P: AsRef<Path>>(options: heed::EnvOpenOptions, path: P) -> Result<Index> {
+        Index::open(options, path, None)
+    }
+
+    /// Opens an index whose documents are encrypted with the given key.
+    ///
+    /// Only the documents database is encrypted, the words and the facet values
+    /// stored in the other databases are readable by anyone that can read the files.
+    /// An index that already contains unencrypted documents can't be encrypted.
+    pub fn new_with_encryption_key<P: AsRef<Path>>(
+        options: heed::EnvOpenOptions,
+        path: P,
+        key: EncryptionKey,
+    ) -> Result<Index> {
+        Index::open(options, path, Some(key))
+    }
+
+    fn open<P: AsRef<Path>>(
+        mut options: heed::EnvOpenOptions,
+        path: P,
+        encryption_key: Option<EncryptionKey>,
+    ) -> Result<Index> {
         use db_name::*;
 
-        options.max_dbs(14);
+        options.max_dbs(22);
         unsafe { options.flag(Flags::MdbAlwaysFreePages) };
 
         let env = options.open(path)?;
         let main = env.create_poly_database(Some(MAIN))?;
         let word_docids = env.create_database(Some(WORD_DOCIDS))?;
+        let exact_word_docids = env.create_database(Some(EXACT_WORD_DOCIDS))?;
         let word_prefix_docids = env.create_database(Some(WORD_PREFIX_DOCIDS))?;
         let docid_word_positions = env.create_database(Some(DOCID_WORD_POSITIONS))?;
         let word_pair_proximity_docids = env.create_database(Some(WORD_PAIR_PROXIMITY_DOCIDS))?;
@@ -124,20 +278,30 @@ impl Index {
             env.create_database(Some(WORD_PREFIX_PAIR_PROXIMITY_DOCIDS))?;
         let word_position_docids = env.create_database(Some(WORD_POSITION_DOCIDS))?;
         let field_id_word_count_docids = env.create_database(Some(FIELD_ID_WORD_COUNT_DOCIDS))?;
+        let field_id_exact_value_docids = env.create_database(Some(FIELD_ID_EXACT_VALUE_DOCIDS))?;
         let word_prefix_position_docids = env.create_database(Some(WORD_PREFIX_POSITION_DOCIDS))?;
         let facet_id_f64_docids = env.create_database(Some(FACET_ID_F64_DOCIDS))?;
         let facet_id_string_docids = env.create_database(Some(FACET_ID_STRING_DOCIDS))?;
         let field_id_docid_facet_f64s = env.create_database(Some(FIELD_ID_DOCID_FACET_F64S))?;
         let field_id_docid_facet_strings =
             env.create_database(Some(FIELD_ID_DOCID_FACET_STRINGS))?;
+        let facet_id_exists_docids = env.create_database(Some(FACET_ID_EXISTS_DOCIDS))?;
+        let facet_id_is_null_docids = env.create_database(Some(FACET_ID_IS_NULL_DOCIDS))?;
+        let facet_id_is_empty_docids = env.create_database(Some(FACET_ID_IS_EMPTY_DOCIDS))?;
         let documents = env.create_database(Some(DOCUMENTS))?;
+        let docid_fingerprints = env.create_database(Some(DOCID_FINGERPRINTS))?;
+        let fingerprint_band_docids = env.create_database(Some(FINGERPRINT_BAND_DOCIDS))?;
+        let docid_word_counts = env.create_database(Some(DOCID_WORD_COUNTS))?;
 
-        Index::initialize_creation_dates(&env, main)?;
+        Index::initialize_metadata(&env, main)?;
+        let documents_cipher =
+            Index::initialize_documents_encryption(&env, main, documents, encryption_key)?;
 
         Ok(Index {
             env,
             main,
             word_docids,
+            exact_word_docids,
             word_prefix_docids,
             docid_word_positions,
             word_pair_proximity_docids,
@@ -145,31 +309,179 @@ impl Index {
             word_position_docids,
             word_prefix_position_docids,
             field_id_word_count_docids,
+            field_id_exact_value_docids,
             facet_id_f64_docids,
             facet_id_string_docids,
             field_id_docid_facet_f64s,
             field_id_docid_facet_strings,
+            facet_id_exists_docids,
+            facet_id_is_null_docids,
+            facet_id_is_empty_docids,
             documents,
+            docid_fingerprints,
+            fingerprint_band_docids,
+            docid_word_counts,
+            search_log_hook: None,
+            writer_lock: None,
+            documents_cipher: documents_cipher.map(Arc::new),
+            filter_attempts: Arc::default(),
+            facet_range_cache: Arc::default(),
+            commit_hooks: Arc::default(),
+            written_documents_ids: Arc::default(),
         })
     }
 
-    fn initialize_creation_dates(env: &heed::Env, main: PolyDatabase) -> heed::Result<()> {
+    /// Opens the index in the writer mode, the other processes can open the index to
+    /// read it but an error is returned when another process opened it in this mode.
+    ///
+    /// The writer lock is released when this index and all of its clones are dropped.
+    pub fn open_writer<P: AsRef<Path>>(options: heed::EnvOpenOptions, path: P) -> Result<Index> {
+        let writer_lock = WriterLock::acquire(path.as_ref())?;
+        let mut index = Index::new(options, path)?;
+        index.writer_lock = Some(Arc::new(writer_lock));
+        Ok(index)
+    }
+
+    fn initialize_metadata(env: &heed::Env, main: PolyDatabase) -> heed::Result<()> {
         let mut txn = env.write_txn()?;
+        let mut modified = false;
         // The db was just created, we update its metadata with the relevant information.
         if main.get::<_, Str, SerdeJson<DateTime<Utc>>>(&txn, main_key::CREATED_AT_KEY)?.is_none() {
             let now = Utc::now();
             main.put::<_, Str, SerdeJson<DateTime<Utc>>>(&mut txn, main_key::UPDATED_AT_KEY, &now)?;
             main.put::<_, Str, SerdeJson<DateTime<Utc>>>(&mut txn, main_key::CREATED_AT_KEY, &now)?;
+            main.put::<_, Str, Str>(&mut txn, main_key::VERSION_KEY, env!("CARGO_PKG_VERSION"))?;
+            main.put::<_, Str, Unit>(&mut txn, main_key::STOP_WORDS_MIGRATED_KEY, &())?;
+            modified = true;
+        }
+        // The indexes created before the uuids were stored are given one the first
+        // time they are opened, the uuid of an index never changes afterward.
+        if main.get::<_, Str, ByteSlice>(&txn, main_key::UUID_KEY)?.is_none() {
+            let uuid = Uuid::new_v4();
+            main.put::<_, Str, ByteSlice>(&mut txn, main_key::UUID_KEY, uuid.as_bytes())?;
+            modified = true;
+        }
+        if modified {
             txn.commit()?;
         }
         Ok(())
     }
 
+    /// Stores a value encrypted with the key the first time the index is opened with a key,
+    /// this value is used to ensure that the index is always opened with the same key.
+    fn initialize_documents_encryption(
+        env: &heed::Env,
+        main: PolyDatabase,
+        documents: Database<OwnedType<BEU32>, ObkvCodec>,
+        encryption_key: Option<EncryptionKey>,
+    ) -> Result<Option<DocumentsCipher>> {
+        let key = main_key::DOCUMENTS_ENCRYPTION_CHECK_KEY;
+        let mut txn = env.write_txn()?;
+        match (main.get::<_, Str, ByteSlice>(&txn, key)?, encryption_key) {
+            (None, None) => Ok(None),
+            (Some(_), None) => Err(UserError::MissingEncryptionKey.into()),
+            (Some(check), Some(encryption_key)) => {
+                let cipher = DocumentsCipher::new(&encryption_key);
+                cipher.decrypt(check)?;
+                Ok(Some(cipher))
+            }
+            (None, Some(encryption_key)) => {
+                if !documents.is_empty(&txn)? {
+                    return Err(UserError::UnencryptedDocuments.into());
+                }
+                let cipher = DocumentsCipher::new(&encryption_key);
+                let check = cipher.encrypt(key.as_bytes());
+                main.put::<_, Str, ByteSlice>(&mut txn, key, &check)?;
+                txn.commit()?;
+                Ok(Some(cipher))
+            }
+        }
+    }
+
+    /// Sets the callback that is invoked on every search executed on this index
+    /// and on the clones of this index that are made after this call.
+    pub fn set_search_log_hook(&mut self, hook: Option<SearchLogHook>) {
+        self.search_log_hook = hook;
+    }
+
+    pub(crate) fn search_log_hook(&self) -> Option<&SearchLogHook> {
+        self.search_log_hook.as_ref()
+    }
+
+    pub(crate) fn record_filter_attempt(&self, field: &str) {
+        self.filter_attempts.lock().unwrap().record(field);
+    }
+
+    /// Returns the fields that are not filterable but that the users tried to filter on,
+    /// with the number of attempts, most attempted first.
+    ///
+    /// The attempts are kept in memory since the index was opened,
+    /// at most `MAX_TRACKED_FILTER_ATTEMPTS` fields are tracked.
+    pub fn non_filterable_attempts(&self) -> Vec<(String, u64)> {
+        self.filter_attempts.lock().unwrap().fields()
+    }
+
+    pub fn clear_non_filterable_attempts(&self) {
+        self.filter_attempts.lock().unwrap().clear();
+    }
+
+    pub(crate) fn cached_facet_cumulatives(
+        &self,
+        commits_count: u64,
+        field_id: FieldId,
+    ) -> Option<Arc<FacetLevelCumulatives>> {
+        self.facet_range_cache.lock().unwrap().get(commits_count, field_id)
+    }
+
+    pub(crate) fn cache_facet_cumulatives(
+        &self,
+        commits_count: u64,
+        field_id: FieldId,
+        cumulatives: Arc<FacetLevelCumulatives>,
+    ) {
+        self.facet_range_cache.lock().unwrap().insert(commits_count, field_id, cumulatives);
+    }
+
     /// Create a write transaction to be able to write into the index.
     pub fn write_txn(&self) -> heed::Result<RwTxn> {
         self.env.write_txn()
     }
 
+    /// Create a write transaction that invokes the commit hooks of the index with a summary
+    /// of its changes once it is committed, the documents it writes are tracked to that end.
+    pub fn write_txn_with_hooks(&self) -> heed::Result<IndexRwTxn> {
+        let wtxn = self.env.write_txn()?;
+        let previous_documents_ids = self.documents_ids(&wtxn)?;
+        *self.written_documents_ids.lock().unwrap() = Some(RoaringBitmap::new());
+        Ok(IndexRwTxn {
+            wtxn,
+            index: self,
+            previous_documents_ids,
+            _written_documents_tracking: WrittenDocumentsTracking { index: self },
+        })
+    }
+
+    /// Commits the write transaction and returns the summary of the changes given to the
+    /// commit hooks, it is the same as calling `IndexRwTxn::commit`.
+    pub fn commit(&self, wtxn: IndexRwTxn) -> Result<CommitSummary> {
+        wtxn.commit_with_summary()
+    }
+
+    /// Registers a callback invoked after every transaction created with
+    /// `Index::write_txn_with_hooks` on this index or on one of its clones is committed,
+    /// the callbacks are invoked in their registration order.
+    pub fn register_commit_hook<F>(&self, hook: F) -> CommitHookId
+    where
+        F: Fn(&CommitSummary) + Send + Sync + 'static,
+    {
+        self.commit_hooks.lock().unwrap().register(Arc::new(hook))
+    }
+
+    /// Returns `false` if no commit hook is registered with this id.
+    pub fn unregister_commit_hook(&self, id: CommitHookId) -> bool {
+        self.commit_hooks.lock().unwrap().unregister(id)
+    }
+
     /// Create a read transaction to be able to read the index.
     pub fn read_txn(&self) -> heed::Result<RoTxn> {
         self.env.read_txn()
@@ -180,6 +492,13 @@ impl Index {
         self.env.path()
     }
 
+    /// Writes a compacted copy of the index into the given file, it can be shipped
+    /// and opened by the programs that are compiled without the indexing feature.
+    pub fn copy_to_path<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.env.copy_to_path(path, CompactionOption::Enabled)?;
+        Ok(())
+    }
+
     /// Returns an `EnvClosingEvent` that can be used to wait for the closing event,
     /// multiple threads can wait on this event.
     ///
@@ -192,6 +511,7 @@ impl Index {
     /* documents ids */
 
     /// Writes the documents ids that corresponds to the user-ids-documents-ids FST.
+    #[cfg(feature = "indexing")]
     pub(crate) fn put_documents_ids(
         &self,
         wtxn: &mut RwTxn,
@@ -208,6 +528,16 @@ impl Index {
             .unwrap_or_default())
     }
 
+    /// Records the documents ids written by the current write transaction when it was created
+    /// with `Index::write_txn_with_hooks`, the commit hooks report them as updated when they
+    /// were already used before the transaction.
+    #[cfg(feature = "indexing")]
+    pub(crate) fn record_written_documents_ids(&self, docids: &RoaringBitmap) {
+        if let Some(written) = &mut *self.written_documents_ids.lock().unwrap() {
+            *written |= docids;
+        }
+    }
+
     /// Returns the number of documents indexed in the database.
     pub fn number_of_documents(&self, rtxn: &RoTxn) -> Result<u64> {
         let count =
@@ -218,12 +548,14 @@ impl Index {
     /* primary key */
 
     /// Writes the documents primary key, this is the field name that is used to store the id.
+    #[cfg(feature = "indexing")]
     pub(crate) fn put_primary_key(&self, wtxn: &mut RwTxn, primary_key: &str) -> heed::Result<()> {
         self.set_updated_at(wtxn, &Utc::now())?;
         self.main.put::<_, Str, Str>(wtxn, main_key::PRIMARY_KEY_KEY, &primary_key)
     }
 
     /// Deletes the primary key of the documents, this can be done to reset indexes settings.
+    #[cfg(feature = "indexing")]
     pub(crate) fn delete_primary_key(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(wtxn, main_key::PRIMARY_KEY_KEY)
     }
@@ -236,6 +568,7 @@ impl Index {
     /* external documents ids */
 
     /// Writes the external documents ids and internal ids (i.e. `u32`).
+    #[cfg(feature = "indexing")]
     pub(crate) fn put_external_documents_ids<'a>(
         &self,
         wtxn: &mut RwTxn,
@@ -258,7 +591,7 @@ impl Index {
     }
 
     /// Returns the external documents ids map which associate the external ids
-    /// with the internal ids (i.e. `u32`).
+    /// with the internal ids (i.e. `u32`), it can be iterated by external ids prefix.
     pub fn external_documents_ids<'t>(&self, rtxn: &'t RoTxn) -> Result<ExternalDocumentsIds<'t>> {
         let hard =
             self.main.get::<_, Str, ByteSlice>(rtxn, main_key::HARD_EXTERNAL_DOCUMENTS_IDS_KEY)?;
@@ -279,6 +612,7 @@ impl Index {
 
     /// Writes the fields ids map which associate the documents keys with an internal field id
     /// (i.e. `u8`), this field id is used to identify fields in the obkv documents.
+    #[cfg(feature = "indexing")]
     pub(crate) fn put_fields_ids_map(
         &self,
         wtxn: &mut RwTxn,
@@ -299,6 +633,7 @@ impl Index {
     /* geo rtree */
 
     /// Writes the provided `rtree` which associates coordinates to documents ids.
+    #[cfg(feature = "indexing")]
     pub(crate) fn put_geo_rtree(
         &self,
         wtxn: &mut RwTxn,
@@ -308,6 +643,7 @@ impl Index {
     }
 
     /// Delete the `rtree` which associates coordinates to documents ids.
+    #[cfg(feature = "indexing")]
     pub(crate) fn delete_geo_rtree(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(wtxn, main_key::GEO_RTREE_KEY)
     }
@@ -326,6 +662,7 @@ impl Index {
     /* geo faceted */
 
     /// Writes the documents ids that are faceted with a _geo field.
+    #[cfg(feature = "indexing")]
     pub(crate) fn put_geo_faceted_documents_ids(
         &self,
         wtxn: &mut RwTxn,
@@ -339,6 +676,7 @@ impl Index {
     }
 
     /// Delete the documents ids that are faceted with a _geo field.
+    #[cfg(feature = "indexing")]
     pub(crate) fn delete_geo_faceted_documents_ids(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(wtxn, main_key::GEO_FACETED_DOCUMENTS_IDS_KEY)
     }
@@ -358,6 +696,7 @@ impl Index {
 
     /// Writes the field distribution which associates every field name with
     /// the number of times it occurs in the documents.
+    #[cfg(feature = "indexing")]
     pub(crate) fn put_field_distribution(
         &self,
         wtxn: &mut RwTxn,
@@ -379,10 +718,36 @@ impl Index {
             .unwrap_or_default())
     }
 
+    /* field types */
+
+    /// Writes the field types which associates every field name with
+    /// the JSON types of the values it took in the documents.
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_field_types(&self, wtxn: &mut RwTxn, types: &FieldTypes) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<FieldTypes>>(wtxn, main_key::FIELD_TYPES_KEY, types)
+    }
+
+    /// Returns the field types which associates every field name with
+    /// the JSON types of the values it took in the documents.
+    pub fn field_types(&self, rtxn: &RoTxn) -> heed::Result<FieldTypes> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<FieldTypes>>(rtxn, main_key::FIELD_TYPES_KEY)?
+            .unwrap_or_default())
+    }
+
+    /// Returns the fields that took values of different JSON types in the documents.
+    pub fn conflicting_field_types(&self, rtxn: &RoTxn) -> heed::Result<FieldTypes> {
+        let mut field_types = self.field_types(rtxn)?;
+        field_types.retain(|_, types| types.len() > 1);
+        Ok(field_types)
+    }
+
     /* displayed fields */
 
     /// Writes the fields that must be displayed in the defined order.
     /// There must be not be any duplicate field id.
+    #[cfg(feature = "indexing")]
     pub(crate) fn put_displayed_fields(
         &self,
         wtxn: &mut RwTxn,
@@ -397,6 +762,7 @@ impl Index {
 
     /// Deletes the displayed fields ids, this will make the engine to display
     /// all the documents attributes in the order of the `FieldsIdsMap`.
+    #[cfg(feature = "indexing")]
     pub(crate) fn delete_displayed_fields(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(wtxn, main_key::DISPLAYED_FIELDS_KEY)
     }
@@ -427,6 +793,7 @@ impl Index {
     /* searchable fields */
 
     /// Writes the searchable fields, when this list is specified, only these are indexed.
+    #[cfg(feature = "indexing")]
     pub(crate) fn put_searchable_fields(
         &self,
         wtxn: &mut RwTxn,
@@ -440,6 +807,7 @@ impl Index {
     }
 
     /// Deletes the searchable fields, when no fields are specified, all fields are indexed.
+    #[cfg(feature = "indexing")]
     pub(crate) fn delete_searchable_fields(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(wtxn, main_key::SEARCHABLE_FIELDS_KEY)
     }
@@ -470,6 +838,7 @@ impl Index {
     /* filterable fields */
 
     /// Writes the filterable fields names in the database.
+    #[cfg(feature = "indexing")]
     pub(crate) fn put_filterable_fields(
         &self,
         wtxn: &mut RwTxn,
@@ -479,6 +848,7 @@ impl Index {
     }
 
     /// Deletes the filterable fields ids in the database.
+    #[cfg(feature = "indexing")]
     pub(crate) fn delete_filterable_fields(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(wtxn, main_key::FILTERABLE_FIELDS_KEY)
     }
@@ -506,9 +876,69 @@ impl Index {
         Ok(fields_ids)
     }
 
+    /* filter capabilities */
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_filter_capabilities(
+        &self,
+        wtxn: &mut RwTxn,
+        capabilities: &HashMap<String, FilterCapabilities>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<_>>(wtxn, main_key::FILTER_CAPABILITIES_KEY, capabilities)
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_filter_capabilities(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::FILTER_CAPABILITIES_KEY)
+    }
+
+    /// Returns the capabilities of the filterable fields that don't support all the filters.
+    pub fn filter_capabilities(
+        &self,
+        rtxn: &RoTxn,
+    ) -> heed::Result<HashMap<String, FilterCapabilities>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<_>>(rtxn, main_key::FILTER_CAPABILITIES_KEY)?
+            .unwrap_or_default())
+    }
+
+    /// Returns the faceted fields whose facet levels are not computed, the filterable fields
+    /// that can't be compared and that aren't sorted, their values are only compared for equality.
+    #[cfg(feature = "indexing")]
+    pub(crate) fn unleveled_faceted_fields_ids(&self, rtxn: &RoTxn) -> Result<HashSet<FieldId>> {
+        let filterable_fields = self.filterable_fields(rtxn)?;
+        let sortable_fields = self.sortable_fields(rtxn)?;
+        let asc_desc_fields: HashSet<_> = self
+            .criteria(rtxn)?
+            .into_iter()
+            .filter_map(|criterion| match criterion {
+                Criterion::Asc(field) | Criterion::Desc(field) => Some(field),
+                _otherwise => None,
+            })
+            .collect();
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+
+        let mut fields_ids = HashSet::new();
+        for (name, capabilities) in self.filter_capabilities(rtxn)? {
+            if !capabilities.comparison
+                && filterable_fields.contains(&name)
+                && !sortable_fields.contains(&name)
+                && !asc_desc_fields.contains(&name)
+            {
+                if let Some(field_id) = fields_ids_map.id(&name) {
+                    fields_ids.insert(field_id);
+                }
+            }
+        }
+
+        Ok(fields_ids)
+    }
+
     /* sortable fields */
 
     /// Writes the sortable fields names in the database.
+    #[cfg(feature = "indexing")]
     pub(crate) fn put_sortable_fields(
         &self,
         wtxn: &mut RwTxn,
@@ -518,6 +948,7 @@ impl Index {
     }
 
     /// Deletes the sortable fields ids in the database.
+    #[cfg(feature = "indexing")]
     pub(crate) fn delete_sortable_fields(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(wtxn, main_key::SORTABLE_FIELDS_KEY)
     }
@@ -537,15 +968,145 @@ impl Index {
         Ok(fields.into_iter().filter_map(|name| fields_ids_map.id(&name)).collect())
     }
 
+    /* first letter typo */
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_first_letter_typo(
+        &self,
+        wtxn: &mut RwTxn,
+        first_letter_typo: FirstLetterTypo,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<FirstLetterTypo>>(
+            wtxn,
+            main_key::FIRST_LETTER_TYPO_KEY,
+            &first_letter_typo,
+        )
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_first_letter_typo(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::FIRST_LETTER_TYPO_KEY)
+    }
+
+    /// Returns how the typos on the first letter of the query words are counted,
+    /// like any other typo by default.
+    pub fn first_letter_typo(&self, rtxn: &RoTxn) -> heed::Result<FirstLetterTypo> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<FirstLetterTypo>>(rtxn, main_key::FIRST_LETTER_TYPO_KEY)?
+            .unwrap_or_default())
+    }
+
+    /* typo distance */
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_typo_distance(
+        &self,
+        wtxn: &mut RwTxn,
+        typo_distance: TypoDistance,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<TypoDistance>>(
+            wtxn,
+            main_key::TYPO_DISTANCE_KEY,
+            &typo_distance,
+        )
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_typo_distance(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::TYPO_DISTANCE_KEY)
+    }
+
+    /// Returns the edit distance used to count the typos of the derivations of the query
+    /// words, a transposition counts as a single typo by default.
+    pub fn typo_distance(&self, rtxn: &RoTxn) -> heed::Result<TypoDistance> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<TypoDistance>>(rtxn, main_key::TYPO_DISTANCE_KEY)?
+            .unwrap_or_default())
+    }
+
+    /* attributes max typos */
+
+    /// Writes the maximum number of typos allowed on the words found in every given field.
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_attributes_max_typos(
+        &self,
+        wtxn: &mut RwTxn,
+        max_typos: &BTreeMap<String, u8>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<_>>(wtxn, main_key::ATTRIBUTES_MAX_TYPOS_KEY, max_typos)
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_attributes_max_typos(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::ATTRIBUTES_MAX_TYPOS_KEY)
+    }
+
+    /// Returns the maximum number of typos allowed on the words found in the fields that
+    /// override it, the other fields allow the typos that the length of the words allow.
+    pub fn attributes_max_typos(&self, rtxn: &RoTxn) -> heed::Result<BTreeMap<String, u8>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<_>>(rtxn, main_key::ATTRIBUTES_MAX_TYPOS_KEY)?
+            .unwrap_or_default())
+    }
+
+    /// Returns the maximum number of typos allowed in the fields that restrict them, by
+    /// their ids. The fields with a maximum of zero don't allow any typo.
+    pub fn fields_max_typos(&self, rtxn: &RoTxn) -> Result<HashMap<FieldId, u8>> {
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        let mut fields_max_typos = HashMap::new();
+        for (name, max_typos) in self.attributes_max_typos(rtxn)? {
+            if let Some(field_id) = fields_ids_map.id(&name) {
+                fields_max_typos.insert(field_id, max_typos);
+            }
+        }
+        Ok(fields_max_typos)
+    }
+
+    /* typo disabled on words */
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_typo_disabled_on_words<A: AsRef<[u8]>>(
+        &self,
+        wtxn: &mut RwTxn,
+        fst: &fst::Set<A>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, ByteSlice>(
+            wtxn,
+            main_key::TYPO_DISABLED_ON_WORDS_KEY,
+            fst.as_fst().as_bytes(),
+        )
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_typo_disabled_on_words(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::TYPO_DISABLED_ON_WORDS_KEY)
+    }
+
+    /// Returns the words of the queries that must only match exactly.
+    pub fn typo_disabled_on_words<'t>(
+        &self,
+        rtxn: &'t RoTxn,
+    ) -> Result<Option<fst::Set<&'t [u8]>>> {
+        match self.main.get::<_, Str, ByteSlice>(rtxn, main_key::TYPO_DISABLED_ON_WORDS_KEY)? {
+            Some(bytes) => Ok(Some(fst::Set::new(bytes)?)),
+            None => Ok(None),
+        }
+    }
+
     /* faceted documents ids */
 
     /// Returns the faceted fields names.
     ///
-    /// Faceted fields are the union of all the filterable, sortable, distinct, and Asc/Desc fields.
+    /// Faceted fields are the union of all the filterable, sortable, distinct, parent,
+    /// and Asc/Desc fields.
     pub fn faceted_fields(&self, rtxn: &RoTxn) -> Result<HashSet<String>> {
         let filterable_fields = self.filterable_fields(rtxn)?;
         let sortable_fields = self.sortable_fields(rtxn)?;
         let distinct_field = self.distinct_field(rtxn)?;
+        let parent_field = self.parent_field(rtxn)?;
         let asc_desc_fields =
             self.criteria(rtxn)?.into_iter().filter_map(|criterion| match criterion {
                 Criterion::Asc(field) | Criterion::Desc(field) => Some(field),
@@ -558,6 +1119,9 @@ impl Index {
         if let Some(field) = distinct_field {
             faceted_fields.insert(field.to_owned());
         }
+        if let Some(field) = parent_field {
+            faceted_fields.insert(field.to_owned());
+        }
 
         Ok(faceted_fields)
     }
@@ -580,6 +1144,7 @@ impl Index {
     /* faceted documents ids */
 
     /// Writes the documents ids that are faceted with numbers under this field id.
+    #[cfg(feature = "indexing")]
     pub(crate) fn put_number_faceted_documents_ids(
         &self,
         wtxn: &mut RwTxn,
@@ -614,6 +1179,7 @@ impl Index {
     }
 
     /// Writes the documents ids that are faceted with strings under this field id.
+    #[cfg(feature = "indexing")]
     pub(crate) fn put_string_faceted_documents_ids(
         &self,
         wtxn: &mut RwTxn,
@@ -647,8 +1213,148 @@ impl Index {
         }
     }
 
+    /// Retrieve all the documents ids that contain this faceted field, whatever its value is.
+    pub fn exists_faceted_documents_ids(
+        &self,
+        rtxn: &RoTxn,
+        field_id: FieldId,
+    ) -> heed::Result<RoaringBitmap> {
+        Ok(self.facet_id_exists_docids.get(rtxn, &BEU16::new(field_id))?.unwrap_or_default())
+    }
+
+    /// Retrieve all the documents ids where this faceted field is null.
+    pub fn null_faceted_documents_ids(
+        &self,
+        rtxn: &RoTxn,
+        field_id: FieldId,
+    ) -> heed::Result<RoaringBitmap> {
+        Ok(self.facet_id_is_null_docids.get(rtxn, &BEU16::new(field_id))?.unwrap_or_default())
+    }
+
+    /// Retrieve all the documents ids where this faceted field is an empty string,
+    /// an empty array or an empty object.
+    pub fn empty_faceted_documents_ids(
+        &self,
+        rtxn: &RoTxn,
+        field_id: FieldId,
+    ) -> heed::Result<RoaringBitmap> {
+        Ok(self.facet_id_is_empty_docids.get(rtxn, &BEU16::new(field_id))?.unwrap_or_default())
+    }
+
+    /// Returns an iterator over the numbers the documents are faceted with under this field id,
+    /// in ascending order, along with the documents ids associated with each one of them.
+    pub fn facet_number_iter<'t>(
+        &self,
+        rtxn: &'t RoTxn,
+        field_id: FieldId,
+    ) -> heed::Result<impl Iterator<Item = heed::Result<(f64, RoaringBitmap)>> + 't> {
+        let db = self.facet_id_f64_docids;
+        let iter = FacetNumberRange::new(rtxn, db, field_id, 0, Unbounded, Unbounded)?;
+        Ok(iter.map(|result| result.map(|((_fid, _level, value, _), docids)| (value, docids))))
+    }
+
+    /// Returns an iterator over the strings the documents are faceted with under this field id,
+    /// in lexicographic order of the normalized string, along with the original string and the
+    /// documents ids associated with each one of them.
+    pub fn facet_string_iter<'t>(
+        &self,
+        rtxn: &'t RoTxn,
+        field_id: FieldId,
+    ) -> heed::Result<impl Iterator<Item = heed::Result<(&'t str, &'t str, RoaringBitmap)>> + 't>
+    {
+        // The strings of the level zero are prefixed by the field id and the level.
+        let mut prefix = [0u8; size_of::<FieldId>() + 1];
+        prefix[..size_of::<FieldId>()].copy_from_slice(&field_id.to_be_bytes());
+        let iter = self
+            .facet_id_string_docids
+            .remap_key_type::<ByteSlice>()
+            .prefix_iter(rtxn, &prefix)?
+            .remap_key_type::<FacetStringLevelZeroCodec>();
+
+        Ok(iter.map(|result| {
+            result.map(|((_fid, normalized), (original, docids))| (normalized, original, docids))
+        }))
+    }
+
+    /// Returns an iterator over the strings the documents are faceted with under this field id
+    /// whose normalized string starts with the given normalized prefix, in lexicographic order,
+    /// only the range of the keys that start with this prefix is scanned.
+    pub fn facet_string_prefix_iter<'t>(
+        &self,
+        rtxn: &'t RoTxn,
+        field_id: FieldId,
+        prefix: &str,
+    ) -> heed::Result<impl Iterator<Item = heed::Result<(&'t str, &'t str, RoaringBitmap)>> + 't>
+    {
+        let mut key_prefix = Vec::new();
+        FacetStringLevelZeroCodec::serialize_into(
+            field_id,
+            facet_string_key_prefix(prefix),
+            &mut key_prefix,
+        );
+        let iter = self
+            .facet_id_string_docids
+            .remap_key_type::<ByteSlice>()
+            .prefix_iter(rtxn, &key_prefix)?
+            .remap_key_type::<FacetStringLevelZeroCodec>();
+
+        // The strings that are truncated in the keys must be checked on their whole string.
+        let prefix = prefix.to_owned();
+        Ok(iter.filter_map(move |result| match result {
+            Ok(((_fid, normalized), (original, docids))) => {
+                if facet_string_from_key(normalized, original).starts_with(&prefix) {
+                    Some(Ok((normalized, original, docids)))
+                } else {
+                    None
+                }
+            }
+            Err(error) => Some(Err(error)),
+        }))
+    }
+
+    /* facet values counts */
+
+    /// Writes the number of distinct values of every faceted field.
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_facet_values_counts(
+        &self,
+        wtxn: &mut RwTxn,
+        counts: &BTreeMap<FieldId, u64>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeBincode<BTreeMap<FieldId, u64>>>(
+            wtxn,
+            main_key::FACET_VALUES_COUNTS_KEY,
+            counts,
+        )
+    }
+
+    /// Returns the number of distinct values, numbers and strings, of every faceted field.
+    ///
+    /// The counts are maintained by the documents additions and deletions,
+    /// they don't require to scan the facet databases.
+    pub fn facet_values_counts(&self, rtxn: &RoTxn) -> heed::Result<BTreeMap<FieldId, u64>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeBincode<BTreeMap<FieldId, u64>>>(
+                rtxn,
+                main_key::FACET_VALUES_COUNTS_KEY,
+            )?
+            .unwrap_or_default())
+    }
+
+    /// Returns the number of distinct values, numbers and strings, of this faceted field.
+    pub fn facet_values_count(&self, rtxn: &RoTxn, field_id: FieldId) -> heed::Result<u64> {
+        Ok(self.facet_values_counts(rtxn)?.get(&field_id).copied().unwrap_or(0))
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_facet_values_counts(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::FACET_VALUES_COUNTS_KEY)
+    }
+
     /* distinct field */
 
+    #[cfg(feature = "indexing")]
     pub(crate) fn put_distinct_field(
         &self,
         wtxn: &mut RwTxn,
@@ -661,53 +1367,459 @@ impl Index {
         self.main.get::<_, Str, Str>(rtxn, main_key::DISTINCT_FIELD_KEY)
     }
 
+    #[cfg(feature = "indexing")]
     pub(crate) fn delete_distinct_field(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(wtxn, main_key::DISTINCT_FIELD_KEY)
     }
 
-    /* criteria */
+    /* parent field */
 
-    pub(crate) fn put_criteria(
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_parent_field(
         &self,
         wtxn: &mut RwTxn,
-        criteria: &[Criterion],
+        parent_field: &str,
     ) -> heed::Result<()> {
-        self.main.put::<_, Str, SerdeJson<&[Criterion]>>(wtxn, main_key::CRITERIA_KEY, &criteria)
+        self.main.put::<_, Str, Str>(wtxn, main_key::PARENT_FIELD_KEY, parent_field)
     }
 
-    pub(crate) fn delete_criteria(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
-        self.main.delete::<_, Str>(wtxn, main_key::CRITERIA_KEY)
+    /// Returns the field of the child documents that contains the id of their parent document.
+    pub fn parent_field<'a>(&self, rtxn: &'a RoTxn) -> heed::Result<Option<&'a str>> {
+        self.main.get::<_, Str, Str>(rtxn, main_key::PARENT_FIELD_KEY)
     }
 
-    pub fn criteria(&self, rtxn: &RoTxn) -> heed::Result<Vec<Criterion>> {
-        match self.main.get::<_, Str, SerdeJson<Vec<Criterion>>>(rtxn, main_key::CRITERIA_KEY)? {
-            Some(criteria) => Ok(criteria),
-            None => Ok(default_criteria()),
-        }
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_parent_field(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::PARENT_FIELD_KEY)
     }
 
-    /* words fst */
+    /* position stride */
 
-    /// Writes the FST which is the words dictionary of the engine.
-    pub(crate) fn put_words_fst<A: AsRef<[u8]>>(
-        &self,
-        wtxn: &mut RwTxn,
-        fst: &fst::Set<A>,
-    ) -> heed::Result<()> {
-        self.main.put::<_, Str, ByteSlice>(wtxn, main_key::WORDS_FST_KEY, fst.as_fst().as_bytes())
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_position_stride(&self, wtxn: &mut RwTxn, stride: u32) -> heed::Result<()> {
+        self.main.put::<_, Str, OwnedType<BEU32>>(
+            wtxn,
+            main_key::POSITION_STRIDE_KEY,
+            &BEU32::new(stride),
+        )
     }
 
-    /// Returns the FST which is the words dictionary of the engine.
-    pub fn words_fst<'t>(&self, rtxn: &'t RoTxn) -> Result<fst::Set<Cow<'t, [u8]>>> {
-        match self.main.get::<_, Str, ByteSlice>(rtxn, main_key::WORDS_FST_KEY)? {
-            Some(bytes) => Ok(fst::Set::new(bytes)?.map_data(Cow::Borrowed)?),
-            None => Ok(fst::Set::default().map_data(Cow::Owned)?),
-        }
+    /// Returns the number of positions reserved for every attribute of the documents,
+    /// the indexes that don't store it have been written with the `ONE_ATTRIBUTE` stride.
+    pub fn position_stride(&self, rtxn: &RoTxn) -> heed::Result<u32> {
+        Ok(self
+            .main
+            .get::<_, Str, OwnedType<BEU32>>(rtxn, main_key::POSITION_STRIDE_KEY)?
+            .map_or(ONE_ATTRIBUTE, |stride| stride.get()))
     }
 
-    /* stop words */
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_position_stride(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::POSITION_STRIDE_KEY)
+    }
 
-    pub(crate) fn put_stop_words<A: AsRef<[u8]>>(
+    /* proximity approximation threshold */
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_proximity_approximation_threshold(
+        &self,
+        wtxn: &mut RwTxn,
+        threshold: u64,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, OwnedType<BEU64>>(
+            wtxn,
+            main_key::PROXIMITY_APPROXIMATION_THRESHOLD_KEY,
+            &BEU64::new(threshold),
+        )
+    }
+
+    /// Returns the number of candidates above which the proximity criterion
+    /// only buckets the documents by the minimal proximity between the query words.
+    pub fn proximity_approximation_threshold(&self, rtxn: &RoTxn) -> heed::Result<Option<u64>> {
+        Ok(self
+            .main
+            .get::<_, Str, OwnedType<BEU64>>(rtxn, main_key::PROXIMITY_APPROXIMATION_THRESHOLD_KEY)?
+            .map(|threshold| threshold.get()))
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_proximity_approximation_threshold(
+        &self,
+        wtxn: &mut RwTxn,
+    ) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::PROXIMITY_APPROXIMATION_THRESHOLD_KEY)
+    }
+
+    /* prefix pair proximity */
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_prefix_pair_proximity_disabled(&self, wtxn: &mut RwTxn) -> heed::Result<()> {
+        self.main.put::<_, Str, Unit>(wtxn, main_key::PREFIX_PAIR_PROXIMITY_DISABLED_KEY, &())
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_prefix_pair_proximity_disabled(
+        &self,
+        wtxn: &mut RwTxn,
+    ) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::PREFIX_PAIR_PROXIMITY_DISABLED_KEY)
+    }
+
+    /// Returns whether the word prefix pair proximity database is computed during indexing,
+    /// it is enabled by default. When it is disabled the proximity of a word and a prefix
+    /// is computed from the word pair proximities of the words that start with the prefix.
+    pub fn prefix_pair_proximity(&self, rtxn: &RoTxn) -> heed::Result<bool> {
+        let disabled =
+            self.main.get::<_, Str, Unit>(rtxn, main_key::PREFIX_PAIR_PROXIMITY_DISABLED_KEY)?;
+        Ok(disabled.is_none())
+    }
+
+    /* proximity excluded fields */
+
+    /// Writes the names of the fields whose word pairs proximities are not indexed.
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_proximity_excluded_fields(
+        &self,
+        wtxn: &mut RwTxn,
+        fields: &HashSet<String>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<_>>(wtxn, main_key::PROXIMITY_EXCLUDED_FIELDS_KEY, fields)
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_proximity_excluded_fields(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::PROXIMITY_EXCLUDED_FIELDS_KEY)
+    }
+
+    /// Returns the names of the fields whose word pairs proximities are not indexed,
+    /// the words of these fields are still searchable.
+    pub fn proximity_excluded_fields(&self, rtxn: &RoTxn) -> heed::Result<HashSet<String>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<_>>(rtxn, main_key::PROXIMITY_EXCLUDED_FIELDS_KEY)?
+            .unwrap_or_default())
+    }
+
+    /// Identical to `proximity_excluded_fields`, but returns ids instead.
+    pub fn proximity_excluded_fields_ids(&self, rtxn: &RoTxn) -> Result<HashSet<FieldId>> {
+        let fields = self.proximity_excluded_fields(rtxn)?;
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        Ok(fields.into_iter().filter_map(|name| fields_ids_map.id(&name)).collect())
+    }
+
+    /* exact attributes */
+
+    /// Writes the names of the fields whose whole value is indexed to rank the documents
+    /// whose field is exactly the query first.
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_exact_attributes(
+        &self,
+        wtxn: &mut RwTxn,
+        fields: &HashSet<String>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<_>>(wtxn, main_key::EXACT_ATTRIBUTES_KEY, fields)
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_exact_attributes(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::EXACT_ATTRIBUTES_KEY)
+    }
+
+    /// Returns the names of the fields whose whole value is indexed in the
+    /// `field_id_exact_value_docids` database.
+    pub fn exact_attributes(&self, rtxn: &RoTxn) -> heed::Result<HashSet<String>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<_>>(rtxn, main_key::EXACT_ATTRIBUTES_KEY)?
+            .unwrap_or_default())
+    }
+
+    /// Identical to `exact_attributes`, but returns ids instead.
+    pub fn exact_attributes_ids(&self, rtxn: &RoTxn) -> Result<HashSet<FieldId>> {
+        let fields = self.exact_attributes(rtxn)?;
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        Ok(fields.into_iter().filter_map(|name| fields_ids_map.id(&name)).collect())
+    }
+
+    /* masked fields */
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_masked_fields(
+        &self,
+        wtxn: &mut RwTxn,
+        fields: &BTreeMap<String, MaskingMethod>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeBincode<BTreeMap<String, MaskingMethod>>>(
+            wtxn,
+            main_key::MASKED_FIELDS_KEY,
+            fields,
+        )
+    }
+
+    /// Returns the fields whose values are masked by the document retrieval APIs.
+    pub fn masked_fields(&self, rtxn: &RoTxn) -> heed::Result<BTreeMap<String, MaskingMethod>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeBincode<BTreeMap<String, MaskingMethod>>>(
+                rtxn,
+                main_key::MASKED_FIELDS_KEY,
+            )?
+            .unwrap_or_default())
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_masked_fields(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::MASKED_FIELDS_KEY)
+    }
+
+    /// Generates the secret the hashed masked values are keyed with, if the index doesn't
+    /// have one yet, it is kept when the masked fields change so that the hashes are stable.
+    #[cfg(feature = "indexing")]
+    pub(crate) fn ensure_masking_secret(&self, wtxn: &mut RwTxn) -> heed::Result<()> {
+        if self.masking_secret(wtxn)?.is_none() {
+            let secret: [u8; 32] = rand::random();
+            self.main.put::<_, Str, ByteSlice>(wtxn, main_key::MASKING_SECRET_KEY, &secret)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn masking_secret(&self, rtxn: &RoTxn) -> heed::Result<Option<Vec<u8>>> {
+        let secret = self.main.get::<_, Str, ByteSlice>(rtxn, main_key::MASKING_SECRET_KEY)?;
+        Ok(secret.map(ToOwned::to_owned))
+    }
+
+    /// Returns the projection that transforms the documents into the JSON objects returned
+    /// to the users, the values of the masked fields are only returned when `unmask` is `true`.
+    pub fn document_projection(&self, rtxn: &RoTxn, unmask: bool) -> Result<DocumentProjection> {
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        let displayed_fields = match self.displayed_fields_ids(rtxn)? {
+            Some(fields) => fields,
+            None => fields_ids_map.ids().collect(),
+        };
+
+        let mut masked_fields = HashMap::new();
+        if !unmask {
+            for (name, method) in self.masked_fields(rtxn)? {
+                if let Some(field_id) = fields_ids_map.id(&name) {
+                    masked_fields.insert(field_id, method);
+                }
+            }
+        }
+        let masking_secret = self.masking_secret(rtxn)?;
+
+        Ok(DocumentProjection::new(displayed_fields, masked_fields, masking_secret, fields_ids_map))
+    }
+
+    /* max attributes to rank */
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_max_attributes_to_rank(
+        &self,
+        wtxn: &mut RwTxn,
+        max: usize,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, OwnedType<BEU64>>(
+            wtxn,
+            main_key::MAX_ATTRIBUTES_TO_RANK_KEY,
+            &BEU64::new(max as u64),
+        )
+    }
+
+    /// Returns the number of searchable attributes, in their ranking order, whose positions
+    /// are used by the attribute criterion, the positions in the other attributes are ignored.
+    pub fn max_attributes_to_rank(&self, rtxn: &RoTxn) -> heed::Result<Option<usize>> {
+        Ok(self
+            .main
+            .get::<_, Str, OwnedType<BEU64>>(rtxn, main_key::MAX_ATTRIBUTES_TO_RANK_KEY)?
+            .map(|max| max.get() as usize))
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_max_attributes_to_rank(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::MAX_ATTRIBUTES_TO_RANK_KEY)
+    }
+
+    /* max values per facet */
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_max_values_per_facet(
+        &self,
+        wtxn: &mut RwTxn,
+        max: usize,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, OwnedType<BEU64>>(
+            wtxn,
+            main_key::MAX_VALUES_PER_FACET_KEY,
+            &BEU64::new(max as u64),
+        )
+    }
+
+    /// Returns the maximum number of values returned for every facet by the facet distribution.
+    pub fn max_values_per_facet(&self, rtxn: &RoTxn) -> heed::Result<Option<usize>> {
+        Ok(self
+            .main
+            .get::<_, Str, OwnedType<BEU64>>(rtxn, main_key::MAX_VALUES_PER_FACET_KEY)?
+            .map(|max| max.get() as usize))
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_max_values_per_facet(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::MAX_VALUES_PER_FACET_KEY)
+    }
+
+    /* min word size for typos */
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_min_word_size_for_one_typo(
+        &self,
+        wtxn: &mut RwTxn,
+        size: u8,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, OwnedType<u8>>(
+            wtxn,
+            main_key::MIN_WORD_SIZE_FOR_ONE_TYPO_KEY,
+            &size,
+        )
+    }
+
+    /// Returns the minimum number of characters of a query word to allow one typo on it.
+    pub fn min_word_size_for_one_typo(&self, rtxn: &RoTxn) -> heed::Result<u8> {
+        Ok(self
+            .main
+            .get::<_, Str, OwnedType<u8>>(rtxn, main_key::MIN_WORD_SIZE_FOR_ONE_TYPO_KEY)?
+            .unwrap_or(DEFAULT_MIN_WORD_SIZE_FOR_ONE_TYPO))
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_min_word_size_for_one_typo(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::MIN_WORD_SIZE_FOR_ONE_TYPO_KEY)
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_min_word_size_for_two_typos(
+        &self,
+        wtxn: &mut RwTxn,
+        size: u8,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, OwnedType<u8>>(
+            wtxn,
+            main_key::MIN_WORD_SIZE_FOR_TWO_TYPOS_KEY,
+            &size,
+        )
+    }
+
+    /// Returns the minimum number of characters of a query word to allow two typos on it.
+    pub fn min_word_size_for_two_typos(&self, rtxn: &RoTxn) -> heed::Result<u8> {
+        Ok(self
+            .main
+            .get::<_, Str, OwnedType<u8>>(rtxn, main_key::MIN_WORD_SIZE_FOR_TWO_TYPOS_KEY)?
+            .unwrap_or(DEFAULT_MIN_WORD_SIZE_FOR_TWO_TYPOS))
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_min_word_size_for_two_typos(
+        &self,
+        wtxn: &mut RwTxn,
+    ) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::MIN_WORD_SIZE_FOR_TWO_TYPOS_KEY)
+    }
+
+    /* criteria */
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_criteria(
+        &self,
+        wtxn: &mut RwTxn,
+        criteria: &[Criterion],
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<&[Criterion]>>(wtxn, main_key::CRITERIA_KEY, &criteria)
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_criteria(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::CRITERIA_KEY)
+    }
+
+    pub fn criteria(&self, rtxn: &RoTxn) -> heed::Result<Vec<Criterion>> {
+        match self.main.get::<_, Str, SerdeJson<Vec<Criterion>>>(rtxn, main_key::CRITERIA_KEY)? {
+            Some(criteria) => Ok(criteria),
+            None => Ok(default_criteria()),
+        }
+    }
+
+    /// Returns the ranking rules that depend on a setting that isn't enabled.
+    pub fn criteria_warnings(&self, rtxn: &RoTxn) -> Result<Vec<CriterionWarning>> {
+        let sortable_fields = self.sortable_fields(rtxn)?;
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+
+        let mut warnings = Vec::new();
+        for criterion in self.criteria(rtxn)? {
+            match criterion {
+                Criterion::Sort if sortable_fields.is_empty() => {
+                    warnings.push(CriterionWarning::SortWithoutSortableAttributes);
+                }
+                Criterion::Asc(ref field) | Criterion::Desc(ref field)
+                    if fields_ids_map.id(field).is_none() =>
+                {
+                    let field = field.clone();
+                    warnings.push(CriterionWarning::UnknownAscDescField { criterion, field });
+                }
+                _ => (),
+            }
+        }
+
+        Ok(warnings)
+    }
+
+    /* default sort */
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_default_sort(
+        &self,
+        wtxn: &mut RwTxn,
+        default_sort: &[AscDesc],
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<&[AscDesc]>>(
+            wtxn,
+            main_key::DEFAULT_SORT_KEY,
+            &default_sort,
+        )
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_default_sort(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::DEFAULT_SORT_KEY)
+    }
+
+    /// Returns the sort applied to the searches that have neither a query nor a sort.
+    pub fn default_sort(&self, rtxn: &RoTxn) -> heed::Result<Option<Vec<AscDesc>>> {
+        self.main.get::<_, Str, SerdeJson<Vec<AscDesc>>>(rtxn, main_key::DEFAULT_SORT_KEY)
+    }
+
+    /* words fst */
+
+    /// Writes the FST which is the words dictionary of the engine.
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_words_fst<A: AsRef<[u8]>>(
+        &self,
+        wtxn: &mut RwTxn,
+        fst: &fst::Set<A>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, ByteSlice>(wtxn, main_key::WORDS_FST_KEY, fst.as_fst().as_bytes())
+    }
+
+    /// Returns the FST which is the words dictionary of the engine.
+    pub fn words_fst<'t>(&self, rtxn: &'t RoTxn) -> Result<fst::Set<Cow<'t, [u8]>>> {
+        match self.main.get::<_, Str, ByteSlice>(rtxn, main_key::WORDS_FST_KEY)? {
+            Some(bytes) => Ok(fst::Set::new(bytes)?.map_data(Cow::Borrowed)?),
+            None => Ok(fst::Set::default().map_data(Cow::Owned)?),
+        }
+    }
+
+    /* stop words */
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_stop_words<A: AsRef<[u8]>>(
         &self,
         wtxn: &mut RwTxn,
         fst: &fst::Set<A>,
@@ -715,10 +1827,24 @@ impl Index {
         self.main.put::<_, Str, ByteSlice>(wtxn, main_key::STOP_WORDS_KEY, fst.as_fst().as_bytes())
     }
 
+    #[cfg(feature = "indexing")]
     pub(crate) fn delete_stop_words(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(wtxn, main_key::STOP_WORDS_KEY)
     }
 
+    /// Marks the word databases as free of the stop words that the extraction used to keep.
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_stop_words_migrated(&self, wtxn: &mut RwTxn) -> heed::Result<()> {
+        self.main.put::<_, Str, Unit>(wtxn, main_key::STOP_WORDS_MIGRATED_KEY, &())
+    }
+
+    /// Returns whether the stop words that the extraction used to keep were removed from the
+    /// word databases, it is always the case for the indexes created since it removes them.
+    #[cfg(feature = "indexing")]
+    pub(crate) fn stop_words_migrated(&self, rtxn: &RoTxn) -> heed::Result<bool> {
+        Ok(self.main.get::<_, Str, Unit>(rtxn, main_key::STOP_WORDS_MIGRATED_KEY)?.is_some())
+    }
+
     pub fn stop_words<'t>(&self, rtxn: &'t RoTxn) -> Result<Option<fst::Set<&'t [u8]>>> {
         match self.main.get::<_, Str, ByteSlice>(rtxn, main_key::STOP_WORDS_KEY)? {
             Some(bytes) => Ok(Some(fst::Set::new(bytes)?)),
@@ -726,8 +1852,77 @@ impl Index {
         }
     }
 
+    /// Returns the stop words that must be removed from the documents, none
+    /// when the stop words mode keeps them in the documents.
+    pub fn documents_stop_words<'t>(&self, rtxn: &'t RoTxn) -> Result<Option<fst::Set<&'t [u8]>>> {
+        if self.stop_words_mode(rtxn)?.removes_from_documents() {
+            self.stop_words(rtxn)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns the stop words that must be removed from the queries, none
+    /// when the stop words mode keeps them in the queries.
+    pub fn query_stop_words<'t>(&self, rtxn: &'t RoTxn) -> Result<Option<fst::Set<&'t [u8]>>> {
+        if self.stop_words_mode(rtxn)?.removes_from_queries() {
+            self.stop_words(rtxn)
+        } else {
+            Ok(None)
+        }
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_stop_words_mode(
+        &self,
+        wtxn: &mut RwTxn,
+        mode: StopWordsMode,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<StopWordsMode>>(
+            wtxn,
+            main_key::STOP_WORDS_MODE_KEY,
+            &mode,
+        )
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_stop_words_mode(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::STOP_WORDS_MODE_KEY)
+    }
+
+    /// Returns where the stop words are removed, from the documents and the queries by default.
+    pub fn stop_words_mode(&self, rtxn: &RoTxn) -> heed::Result<StopWordsMode> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<StopWordsMode>>(rtxn, main_key::STOP_WORDS_MODE_KEY)?
+            .unwrap_or_default())
+    }
+
+    /// Returns the words that appear in more than the given ratio of the documents of the
+    /// index with the number of documents they appear in, the most frequent words first.
+    ///
+    /// Those words are good stop words candidates as they don't help to discriminate documents.
+    pub fn stop_words_candidates(&self, rtxn: &RoTxn, ratio: f64) -> Result<Vec<(String, u64)>> {
+        let number_of_documents = self.number_of_documents(rtxn)? as f64;
+        let mut candidates = Vec::new();
+        if number_of_documents == 0.0 {
+            return Ok(candidates);
+        }
+
+        for result in self.word_docids.remap_data_type::<RoaringBitmapLenCodec>().iter(rtxn)? {
+            let (word, count) = result?;
+            if count as f64 / number_of_documents > ratio {
+                candidates.push((word.to_string(), count));
+            }
+        }
+
+        candidates.sort_by(|(wa, a), (wb, b)| b.cmp(a).then_with(|| wa.cmp(wb)));
+        Ok(candidates)
+    }
+
     /* synonyms */
 
+    #[cfg(feature = "indexing")]
     pub(crate) fn put_synonyms(
         &self,
         wtxn: &mut RwTxn,
@@ -736,6 +1931,7 @@ impl Index {
         self.main.put::<_, Str, SerdeBincode<_>>(wtxn, main_key::SYNONYMS_KEY, synonyms)
     }
 
+    #[cfg(feature = "indexing")]
     pub(crate) fn delete_synonyms(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(wtxn, main_key::SYNONYMS_KEY)
     }
@@ -747,18 +1943,272 @@ impl Index {
             .unwrap_or_default())
     }
 
+    /// Returns the synonyms that are expanded at query time, the synonyms expanded
+    /// at indexing time are already in the word databases.
+    pub fn query_synonyms(
+        &self,
+        rtxn: &RoTxn,
+    ) -> heed::Result<HashMap<Vec<String>, Vec<Vec<String>>>> {
+        let indexed_synonyms = self.indexed_synonyms(rtxn)?;
+        let mut synonyms = self.synonyms(rtxn)?;
+        synonyms.retain(|words, _| !indexed_synonyms.contains(words));
+        Ok(synonyms)
+    }
+
+    /// Returns the synonyms of the words that are expanded at query time.
+    ///
+    /// All the synonyms are deserialized on every call, the searches load them
+    /// once with [`Index::query_synonyms`].
     pub fn words_synonyms<S: AsRef<str>>(
         &self,
         rtxn: &RoTxn,
         words: &[S],
     ) -> heed::Result<Option<Vec<Vec<String>>>> {
         let words: Vec<_> = words.iter().map(|s| s.as_ref().to_owned()).collect();
-        Ok(self.synonyms(rtxn)?.remove(&words))
+        Ok(self.query_synonyms(rtxn)?.remove(&words))
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_indexed_synonyms(
+        &self,
+        wtxn: &mut RwTxn,
+        words: &HashSet<Vec<String>>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeBincode<_>>(wtxn, main_key::INDEXED_SYNONYMS_KEY, words)
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_indexed_synonyms(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::INDEXED_SYNONYMS_KEY)
+    }
+
+    /// Returns the normalized words whose synonyms are expanded at indexing time.
+    pub fn indexed_synonyms(&self, rtxn: &RoTxn) -> heed::Result<HashSet<Vec<String>>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeBincode<_>>(rtxn, main_key::INDEXED_SYNONYMS_KEY)?
+            .unwrap_or_default())
+    }
+
+    /// Returns the words that are added to the documents at indexing time, indexed by
+    /// the synonyms, separated by spaces, that the documents must contain.
+    #[cfg(feature = "indexing")]
+    pub(crate) fn synonyms_expansions(
+        &self,
+        rtxn: &RoTxn,
+    ) -> heed::Result<HashMap<String, Vec<Vec<String>>>> {
+        let indexed_synonyms = self.indexed_synonyms(rtxn)?;
+        let mut expansions = HashMap::new();
+        for (words, synonyms) in self.synonyms(rtxn)? {
+            if indexed_synonyms.contains(&words) {
+                for synonym in synonyms {
+                    let entry = expansions.entry(synonym.join(" ")).or_insert_with(Vec::new);
+                    entry.push(words.clone());
+                }
+            }
+        }
+        Ok(expansions)
+    }
+
+    /* documents fingerprinting */
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn put_documents_fingerprinting(&self, wtxn: &mut RwTxn) -> heed::Result<()> {
+        self.main.put::<_, Str, Unit>(wtxn, main_key::DOCUMENTS_FINGERPRINTING_KEY, &())
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn delete_documents_fingerprinting(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::DOCUMENTS_FINGERPRINTING_KEY)
+    }
+
+    /// Returns whether the documents are fingerprinted during indexing, it is disabled by default.
+    pub fn documents_fingerprinting(&self, rtxn: &RoTxn) -> heed::Result<bool> {
+        let enabled =
+            self.main.get::<_, Str, Unit>(rtxn, main_key::DOCUMENTS_FINGERPRINTING_KEY)?;
+        Ok(enabled.is_some())
+    }
+
+    /// Returns the fingerprint of the given document, if the documents fingerprinting is enabled.
+    pub fn document_fingerprint(
+        &self,
+        rtxn: &RoTxn,
+        docid: DocumentId,
+    ) -> heed::Result<Option<u64>> {
+        let fingerprint = self.docid_fingerprints.get(rtxn, &BEU32::new(docid))?;
+        Ok(fingerprint.map(|fingerprint| fingerprint.get()))
+    }
+
+    /// Returns the documents whose fingerprint differs by at most `max_distance` bits from the
+    /// fingerprint of the given document, the given document is not part of the returned ones.
+    ///
+    /// A distance of 3 bits or less indicates documents with almost the same words, only the
+    /// documents that share a band of their fingerprint are then compared. A larger distance
+    /// compares the fingerprints of all the documents.
+    pub fn near_duplicates(
+        &self,
+        rtxn: &RoTxn,
+        docid: DocumentId,
+        max_distance: u32,
+    ) -> heed::Result<RoaringBitmap> {
+        let mut duplicates = RoaringBitmap::new();
+        let fingerprint = match self.document_fingerprint(rtxn, docid)? {
+            Some(fingerprint) => fingerprint,
+            None => return Ok(duplicates),
+        };
+
+        // A document whose fingerprint differs by less bits than there are bands shares at least
+        // one band with the given one, only the documents of its bands are compared.
+        if max_distance < FINGERPRINT_BANDS {
+            let mut candidates = RoaringBitmap::new();
+            for band in fingerprint_bands(fingerprint) {
+                if let Some(docids) = self.fingerprint_band_docids.get(rtxn, &BEU32::new(band))? {
+                    candidates |= docids;
+                }
+            }
+            candidates.remove(docid);
+
+            for other in candidates {
+                if let Some(other_fingerprint) = self.document_fingerprint(rtxn, other)? {
+                    if fingerprints_distance(fingerprint, other_fingerprint) <= max_distance {
+                        duplicates.insert(other);
+                    }
+                }
+            }
+
+            return Ok(duplicates);
+        }
+
+        for result in self.docid_fingerprints.iter(rtxn)? {
+            let (other, other_fingerprint) = result?;
+            let other = other.get();
+            if other != docid
+                && fingerprints_distance(fingerprint, other_fingerprint.get()) <= max_distance
+            {
+                duplicates.insert(other);
+            }
+        }
+
+        Ok(duplicates)
+    }
+
+    /* documents word counts */
+
+    /// Returns the number of words of the given document, in total and for each field,
+    /// `None` if the document doesn't exist.
+    ///
+    /// The words are counted when they are extracted from the documents, neither the stop words
+    /// nor the words after the position stride of a field are counted.
+    pub fn document_word_counts(
+        &self,
+        rtxn: &RoTxn,
+        docid: DocumentId,
+    ) -> Result<Option<DocumentWordCounts>> {
+        if !self.documents_ids(rtxn)?.contains(docid) {
+            return Ok(None);
+        }
+
+        let mut fields = BTreeMap::new();
+        if let Some(counts) = self.docid_word_counts.get(rtxn, &BEU32::new(docid))? {
+            for (field_id, count) in counts.iter() {
+                let count = count
+                    .try_into()
+                    .map(u32::from_be_bytes)
+                    .map_err(|_| SerializationError::InvalidNumberSerialization)?;
+                fields.insert(field_id, count);
+            }
+        }
+
+        let total = fields.values().sum();
+        Ok(Some(DocumentWordCounts { total, fields }))
+    }
+
+    /* warmup */
+
+    /// Reads the hottest pages of the index so that the first searches after the index is
+    /// opened or swapped don't pay the latency of a cold page cache.
+    pub fn warmup(&self, rtxn: &RoTxn, plan: &WarmupPlan) -> Result<WarmupStats> {
+        let mut stats = WarmupStats::default();
+
+        if plan.words_fst {
+            for key in &[main_key::WORDS_FST_KEY, main_key::WORDS_PREFIXES_FST_KEY] {
+                if let Some(bytes) = self.main.get::<_, Str, ByteSlice>(rtxn, *key)? {
+                    stats.bytes_read += touch_pages(bytes);
+                }
+            }
+        }
+
+        if plan.top_prefixes > 0 {
+            let prefixes_fst = self.words_prefixes_fst(rtxn)?;
+            let mut prefixes = prefixes_fst.stream().into_strs()?;
+            prefixes.sort_by_key(|prefix| prefix.len());
+            prefixes.truncate(plan.top_prefixes);
+
+            let word_prefix_docids = self.word_prefix_docids.remap_data_type::<ByteSlice>();
+            for prefix in prefixes {
+                if let Some(bytes) = word_prefix_docids.get(rtxn, prefix.as_str())? {
+                    stats.bytes_read += touch_pages(bytes);
+                    stats.prefixes += 1;
+                }
+            }
+        }
+
+        if plan.facet_roots {
+            let facet_id_f64_docids =
+                self.facet_id_f64_docids.remap_types::<ByteSlice, ByteSlice>();
+            let facet_id_string_docids =
+                self.facet_id_string_docids.remap_types::<ByteSlice, ByteSlice>();
+            for field_id in self.faceted_fields_ids(rtxn)? {
+                for db in &[facet_id_f64_docids, facet_id_string_docids] {
+                    let (entries, bytes_read) = warmup_facet_root(rtxn, *db, field_id)?;
+                    stats.facet_entries += entries;
+                    stats.bytes_read += bytes_read;
+                }
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /* query statistics */
+
+    pub(crate) fn put_query_statistics(
+        &self,
+        wtxn: &mut RwTxn,
+        statistics: &QueryStatistics,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeBincode<QueryStatistics>>(
+            wtxn,
+            main_key::QUERY_STATISTICS_KEY,
+            statistics,
+        )
+    }
+
+    /// Forgets all the queries that were recorded.
+    pub fn delete_query_statistics(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::QUERY_STATISTICS_KEY)
+    }
+
+    /// Returns the frequency of the queries recorded with `Index::record_query`.
+    pub fn query_statistics(&self, rtxn: &RoTxn) -> heed::Result<QueryStatistics> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeBincode<QueryStatistics>>(rtxn, main_key::QUERY_STATISTICS_KEY)?
+            .unwrap_or_default())
+    }
+
+    /// Records that the given query was executed and the number of documents it matched,
+    /// the recorded queries are used to find the popular and the zero results queries.
+    pub fn record_query(&self, wtxn: &mut RwTxn, query: &str, hits: u64) -> heed::Result<()> {
+        let mut statistics = self.query_statistics(wtxn)?;
+        statistics.record(query, hits, Utc::now());
+        self.put_query_statistics(wtxn, &statistics)
     }
 
     /* words prefixes fst */
 
     /// Writes the FST which is the words prefixes dictionnary of the engine.
+    #[cfg(feature = "indexing")]
     pub(crate) fn put_words_prefixes_fst<A: AsRef<[u8]>>(
         &self,
         wtxn: &mut RwTxn,
@@ -790,11 +2240,15 @@ impl Index {
     /* documents */
 
     /// Returns a [`Vec`] of the requested documents. Returns an error if a document is missing.
+    ///
+    /// The documents of an encrypted index can't be read without a copy,
+    /// they must be read with [`Index::document_bytes`].
     pub fn documents<'t>(
         &self,
         rtxn: &'t RoTxn,
         ids: impl IntoIterator<Item = DocumentId>,
     ) -> Result<Vec<(DocumentId, obkv::KvReaderU16<'t>)>> {
+        self.ensure_documents_unencrypted()?;
         let mut documents = Vec::new();
 
         for id in ids {
@@ -808,11 +2262,37 @@ impl Index {
         Ok(documents)
     }
 
+    /// Returns a zero-copy reader over the requested document, `None` if it doesn't exist.
+    pub fn document_reader<'t>(
+        &self,
+        rtxn: &'t RoTxn,
+        id: DocumentId,
+    ) -> Result<Option<DocumentReader<'t>>> {
+        self.ensure_documents_unencrypted()?;
+        Ok(self.documents.get(rtxn, &BEU32::new(id))?.map(DocumentReader::new))
+    }
+
+    /// Returns the obkv store of the requested document, `None` if it doesn't exist.
+    ///
+    /// The document is decrypted when the index is encrypted, it is borrowed otherwise.
+    pub fn document_bytes<'t>(
+        &self,
+        rtxn: &'t RoTxn,
+        id: DocumentId,
+    ) -> Result<Option<Cow<'t, [u8]>>> {
+        let documents = self.documents.remap_data_type::<ByteSlice>();
+        match documents.get(rtxn, &BEU32::new(id))? {
+            Some(bytes) => self.decrypt_document(bytes).map(Some),
+            None => Ok(None),
+        }
+    }
+
     /// Returns an iterator over all the documents in the index.
     pub fn all_documents<'t>(
         &self,
         rtxn: &'t RoTxn,
     ) -> Result<impl Iterator<Item = heed::Result<(DocumentId, obkv::KvReaderU16<'t>)>>> {
+        self.ensure_documents_unencrypted()?;
         Ok(self
             .documents
             .iter(rtxn)?
@@ -820,6 +2300,53 @@ impl Index {
             .map(|document| document.map(|(id, obkv)| (id.get(), obkv))))
     }
 
+    /// Returns the ratio of the documents that, in the primary key order, directly follow the
+    /// previous one in the documents database. A scan in the primary key order reads the
+    /// documents sequentially when it is 1.
+    pub fn primary_key_locality(&self, rtxn: &RoTxn) -> Result<f64> {
+        let external_documents_ids = self.external_documents_ids(rtxn)?;
+        let mut previous = None;
+        let mut documents = 0;
+        let mut sequential = 0;
+        for (_, docid) in external_documents_ids.iter() {
+            if previous.map_or(false, |previous| previous + 1 == docid) {
+                sequential += 1;
+            }
+            previous = Some(docid);
+            documents += 1;
+        }
+
+        if documents <= 1 {
+            Ok(1.0)
+        } else {
+            Ok(sequential as f64 / (documents - 1) as f64)
+        }
+    }
+
+    /// Encrypts the obkv store of a document if the index is encrypted.
+    #[cfg(feature = "indexing")]
+    pub(crate) fn encrypt_document<'a>(&self, obkv: &'a [u8]) -> Cow<'a, [u8]> {
+        match &self.documents_cipher {
+            Some(cipher) => Cow::Owned(cipher.encrypt(obkv)),
+            None => Cow::Borrowed(obkv),
+        }
+    }
+
+    /// Decrypts a value of the documents database if the index is encrypted.
+    pub(crate) fn decrypt_document<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+        match &self.documents_cipher {
+            Some(cipher) => cipher.decrypt(bytes).map(Cow::Owned),
+            None => Ok(Cow::Borrowed(bytes)),
+        }
+    }
+
+    fn ensure_documents_unencrypted(&self) -> Result<()> {
+        match self.documents_cipher {
+            Some(_) => Err(UserError::EncryptedDocuments.into()),
+            None => Ok(()),
+        }
+    }
+
     pub fn facets_distribution<'a>(&'a self, rtxn: &'a RoTxn) -> FacetDistribution<'a> {
         FacetDistribution::new(rtxn, self)
     }
@@ -828,6 +2355,81 @@ impl Index {
         Search::new(rtxn, self)
     }
 
+    /// Returns the tokens produced by the analyzer of the index for the given text, as if it
+    /// were the value of the given attribute of a document. The stop words that the stop words
+    /// mode of the index removes from the documents are reported but not indexed, like when
+    /// the documents are indexed.
+    ///
+    /// It helps understanding why a word of a query does or doesn't match a document.
+    pub fn tokenize_debug(
+        &self,
+        rtxn: &RoTxn,
+        field: Option<&str>,
+        text: &str,
+    ) -> Result<Vec<DebugToken>> {
+        let searchable = match (field, self.searchable_fields(rtxn)?) {
+            (Some(field), Some(searchable_fields)) => searchable_fields.contains(&field),
+            _ => true,
+        };
+        let one_attribute = self.position_stride(rtxn)?;
+
+        let mut config = AnalyzerConfig::default();
+        let stop_words = self.documents_stop_words(rtxn)?;
+        if let Some(ref stop_words) = stop_words {
+            config.stop_words(stop_words);
+        }
+        let analyzer = Analyzer::new(config);
+        let analyzed = analyzer.analyze(text);
+
+        let positions: HashMap<_, _> = process_tokens(analyzed.tokens())
+            .map(|(position, token)| (token.byte_start, position as Position))
+            .collect();
+
+        let tokens = analyzed
+            .tokens()
+            .map(|token| {
+                let position = positions.get(&token.byte_start).copied();
+                let indexed = searchable
+                    && token.kind != TokenKind::StopWord
+                    && !token.text().trim().is_empty()
+                    && position.map_or(false, |position| position < one_attribute);
+                DebugToken {
+                    original: text[token.byte_start..token.byte_end].to_string(),
+                    normalized: token.text().to_string(),
+                    exact: exact_word(&text[token.byte_start..token.byte_end]),
+                    kind: token.kind.into(),
+                    byte_start: token.byte_start,
+                    byte_end: token.byte_end,
+                    position,
+                    indexed,
+                }
+            })
+            .collect();
+
+        Ok(tokens)
+    }
+
+    /* metadata */
+
+    /// Returns the unique identifier of the index, it is generated when the index is
+    /// created and is kept by the copies of the index.
+    pub fn uuid(&self, rtxn: &RoTxn) -> Result<Uuid> {
+        let bytes = self.main.get::<_, Str, ByteSlice>(rtxn, main_key::UUID_KEY)?.ok_or(
+            InternalError::DatabaseMissingEntry {
+                db_name: db_name::MAIN,
+                key: Some(main_key::UUID_KEY),
+            },
+        )?;
+        Uuid::from_slice(bytes)
+            .map_err(|_| SerializationError::Decoding { db_name: Some(db_name::MAIN) }.into())
+    }
+
+    /// Returns the version of milli that created the index, `None` if the index
+    /// was created before the version was stored.
+    pub fn version<'t>(&self, rtxn: &'t RoTxn) -> heed::Result<Option<&'t str>> {
+        self.main.get::<_, Str, Str>(rtxn, main_key::VERSION_KEY)
+    }
+
     /// Returns the index creation time.
     pub fn created_at(&self, rtxn: &RoTxn) -> Result<DateTime<Utc>> {
         Ok(self
@@ -839,36 +2441,244 @@ impl Index {
             })?)
     }
 
-    /// Returns the index last updated time.
-    pub fn updated_at(&self, rtxn: &RoTxn) -> Result<DateTime<Utc>> {
-        Ok(self
-            .main
-            .get::<_, Str, SerdeJson<DateTime<Utc>>>(rtxn, main_key::UPDATED_AT_KEY)?
-            .ok_or(InternalError::DatabaseMissingEntry {
-                db_name: db_name::MAIN,
-                key: Some(main_key::UPDATED_AT_KEY),
-            })?)
+    /// Returns the index last updated time.
+    pub fn updated_at(&self, rtxn: &RoTxn) -> Result<DateTime<Utc>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<DateTime<Utc>>>(rtxn, main_key::UPDATED_AT_KEY)?
+            .ok_or(InternalError::DatabaseMissingEntry {
+                db_name: db_name::MAIN,
+                key: Some(main_key::UPDATED_AT_KEY),
+            })?)
+    }
+
+    #[cfg(feature = "indexing")]
+    pub(crate) fn set_updated_at(
+        &self,
+        wtxn: &mut RwTxn,
+        time: &DateTime<Utc>,
+    ) -> heed::Result<()> {
+        // The index is updated by this transaction, it must be counted as a new commit.
+        self.increment_commits_count(wtxn)?;
+        self.main.put::<_, Str, SerdeJson<DateTime<Utc>>>(wtxn, main_key::UPDATED_AT_KEY, &time)
+    }
+
+    /* commits count */
+
+    /// Increments the commits counter the first time the given write transaction updates the
+    /// index, the transactions that don't update it are not counted.
+    #[cfg(feature = "indexing")]
+    fn increment_commits_count(&self, wtxn: &mut RwTxn) -> heed::Result<()> {
+        let committed = self.commits_count(&self.env.read_txn()?)?;
+        if self.commits_count(wtxn)? == committed {
+            self.main.put::<_, Str, OwnedType<BEU64>>(
+                wtxn,
+                main_key::COMMITS_COUNT_KEY,
+                &BEU64::new(committed + 1),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of write transactions that updated the index and were committed
+    /// that are visible from the snapshot of the given transaction.
+    pub fn commits_count(&self, rtxn: &RoTxn) -> heed::Result<u64> {
+        let count = self.main.get::<_, Str, OwnedType<BEU64>>(rtxn, main_key::COMMITS_COUNT_KEY)?;
+        Ok(count.map_or(0, |count| count.get()))
+    }
+
+    /// Returns `true` if the given transaction is a write transaction of the index that is
+    /// not committed yet, its content can still change or be aborted.
+    pub(crate) fn is_uncommitted(&self, rtxn: &RoTxn) -> heed::Result<bool> {
+        let committed = self.commits_count(&self.env.read_txn()?)?;
+        Ok(self.commits_count(rtxn)? > committed)
+    }
+
+    /// Returns the number of write transactions that were committed on the index since
+    /// the snapshot of the given transaction was taken, `0` means that the snapshot is fresh.
+    ///
+    /// It can be used to detect long-running read transactions that must be refreshed.
+    pub fn commits_since(&self, rtxn: &RoTxn) -> heed::Result<u64> {
+        let latest = self.commits_count(&self.env.read_txn()?)?;
+        Ok(latest.saturating_sub(self.commits_count(rtxn)?))
+    }
+}
+
+/// A write transaction on an index, it is used like a `heed::RwTxn` but invokes the
+/// commit hooks of the index with a summary of the changes once it is committed.
+pub struct IndexRwTxn<'e> {
+    wtxn: RwTxn<'e, 'e>,
+    index: &'e Index,
+    previous_documents_ids: RoaringBitmap,
+    _written_documents_tracking: WrittenDocumentsTracking<'e>,
+}
+
+impl<'e> IndexRwTxn<'e> {
+    /// Commits the transaction and invokes the commit hooks of the index.
+    pub fn commit(self) -> Result<()> {
+        self.commit_with_summary().map(drop)
+    }
+
+    /// Aborts the transaction, the commit hooks are not invoked.
+    pub fn abort(self) -> heed::Result<()> {
+        self.wtxn.abort()
+    }
+
+    fn commit_with_summary(self) -> Result<CommitSummary> {
+        let IndexRwTxn { wtxn, index, previous_documents_ids, _written_documents_tracking } = self;
+
+        let documents_ids = index.documents_ids(&wtxn)?;
+        let written_documents_ids =
+            index.written_documents_ids.lock().unwrap().take().unwrap_or_default();
+        let summary = CommitSummary {
+            commits_count: index.commits_count(&wtxn)?,
+            added_documents_ids: &documents_ids - &previous_documents_ids,
+            updated_documents_ids: &(&written_documents_ids & &previous_documents_ids)
+                & &documents_ids,
+            deleted_documents_ids: &previous_documents_ids - &documents_ids,
+            documents_count: documents_ids.len(),
+            updated_at: index.updated_at(&wtxn)?,
+        };
+        wtxn.commit()?;
+
+        // The hooks are invoked without holding the lock, they can register other hooks.
+        let hooks = index.commit_hooks.lock().unwrap().hooks();
+        for hook in hooks {
+            hook(&summary);
+        }
+
+        Ok(summary)
+    }
+}
+
+/// Stops tracking the documents written by a transaction once it is committed or dropped.
+struct WrittenDocumentsTracking<'e> {
+    index: &'e Index,
+}
+
+impl Drop for WrittenDocumentsTracking<'_> {
+    fn drop(&mut self) {
+        *self.index.written_documents_ids.lock().unwrap() = None;
+    }
+}
+
+impl<'e> Deref for IndexRwTxn<'e> {
+    type Target = RwTxn<'e, 'e>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.wtxn
+    }
+}
+
+impl<'e> DerefMut for IndexRwTxn<'e> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.wtxn
+    }
+}
+
+/// The directory of the shared memory of Linux, it is a tmpfs and its files are kept in RAM.
+const SHARED_MEMORY_DIR: &str = "/dev/shm";
+
+/// An index stored in a temporary directory that is only meant to live as long as this value,
+/// it can be used by unit tests and by embedded consumers that don't need to persist the index
+/// between runs. The environment is closed, then its directory removed, when it is dropped.
+///
+/// It is not an in-memory index, it is an LMDB environment on a filesystem like any other.
+/// The directory is created in the shared memory tmpfs when there is one, `/dev/shm` on Linux,
+/// its files are then kept in RAM. On the platforms without it, or when it can't be written,
+/// the directory is created in the temporary directory of the system, the writes are never
+/// synced but they end up on the disk when the system lacks memory.
+pub struct TempDirIndex {
+    // The fields are dropped in this order, the environment before its directory.
+    inner: Index,
+    _tempdir: TempDir,
+}
+
+impl TempDirIndex {
+    pub fn new(mut options: heed::EnvOpenOptions) -> Result<TempDirIndex> {
+        let shared_memory = Path::new(SHARED_MEMORY_DIR);
+        let tempdir = if shared_memory.is_dir() {
+            tempfile::tempdir_in(shared_memory).or_else(|_| tempfile::tempdir())?
+        } else {
+            tempfile::tempdir()?
+        };
+        unsafe {
+            options.flag(Flags::MdbNoSync);
+            options.flag(Flags::MdbNoMetaSync);
+        }
+        let inner = Index::new(options, tempdir.path())?;
+        Ok(TempDirIndex { inner, _tempdir: tempdir })
+    }
+}
+
+impl Deref for TempDirIndex {
+    type Target = Index;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+/// Reads a byte of every page of the slice to make the kernel load them, returns the length.
+fn touch_pages(bytes: &[u8]) -> u64 {
+    const PAGE_SIZE: usize = 4096;
+    for byte in bytes.iter().step_by(PAGE_SIZE) {
+        // The volatile read can't be optimized away.
+        unsafe { std::ptr::read_volatile(byte) };
     }
+    bytes.len() as u64
+}
 
-    pub(crate) fn set_updated_at(
-        &self,
-        wtxn: &mut RwTxn,
-        time: &DateTime<Utc>,
-    ) -> heed::Result<()> {
-        self.main.put::<_, Str, SerdeJson<DateTime<Utc>>>(wtxn, main_key::UPDATED_AT_KEY, &time)
+/// Reads the entries of the highest level of a facet database for the given field,
+/// the keys of every level start with the field id and the level.
+fn warmup_facet_root(
+    rtxn: &RoTxn,
+    db: Database<ByteSlice, ByteSlice>,
+    field_id: FieldId,
+) -> heed::Result<(usize, u64)> {
+    let [a, b] = field_id.to_be_bytes();
+    let mut highest_level = None;
+    for level in 1..=u8::MAX {
+        if db.prefix_iter(rtxn, &[a, b, level])?.next().transpose()?.is_none() {
+            break;
+        }
+        highest_level = Some(level);
     }
+
+    let mut entries = 0;
+    let mut bytes_read = 0;
+    if let Some(level) = highest_level {
+        for result in db.prefix_iter(rtxn, &[a, b, level])? {
+            let (_key, bytes) = result?;
+            bytes_read += touch_pages(bytes);
+            entries += 1;
+        }
+    }
+    Ok((entries, bytes_read))
 }
 
 #[cfg(test)]
 pub(crate) mod tests {
-    use std::ops::Deref;
+    use std::io::Cursor;
+    use std::ops::{Deref, DerefMut};
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
 
+    use big_s::S;
+    use heed::types::{ByteSlice, Str};
     use heed::EnvOpenOptions;
-    use maplit::btreemap;
+    use maplit::{btreemap, btreeset, hashset};
+    use serde_json::json;
     use tempfile::TempDir;
 
-    use crate::update::IndexDocuments;
-    use crate::Index;
+    use super::{main_key, TempDirIndex, WarmupPlan, WarmupStats, SHARED_MEMORY_DIR};
+    use crate::documents::{DocumentBatchBuilder, DocumentBatchReader};
+    use crate::error::{Error, UserError};
+    use crate::update::{DeleteDocuments, IndexDocuments, IndexDocumentsMethod, Settings};
+    use crate::{
+        AscDesc, DebugTokenKind, DocumentReader, EncryptionKey, FieldType, FieldTypeWarning,
+        FilterCondition, Index, Member, Search, StopWordsMode, WriterLock, BEU32,
+    };
 
     pub(crate) struct TempIndex {
         inner: Index,
@@ -883,6 +2693,12 @@ pub(crate) mod tests {
         }
     }
 
+    impl DerefMut for TempIndex {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.inner
+        }
+    }
+
     impl TempIndex {
         /// Creates a temporary index, with a default `4096 * 100` size. This should be enough for
         /// most tests.
@@ -970,4 +2786,648 @@ pub(crate) mod tests {
             }
         );
     }
+
+    #[test]
+    fn field_type_warnings() {
+        let index = TempIndex::new();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "name": "kevin", "age": 20 },
+            { "id": 2, "name": "bob", "age": null }
+        ]);
+        let result = IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        assert!(result.warnings.is_empty());
+        wtxn.commit().unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 3, "name": "alice", "age": "twenty" },
+            { "id": 4, "name": "john", "age": "thirty" }
+        ]);
+        let result = IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        assert_eq!(
+            result.warnings,
+            vec![FieldTypeWarning {
+                field: "age".to_string(),
+                previous_types: btreeset! { FieldType::Number },
+                new_type: FieldType::String,
+            }]
+        );
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(
+            index.conflicting_field_types(&rtxn).unwrap(),
+            btreemap! { "age".to_string() => btreeset! { FieldType::Number, FieldType::String } }
+        );
+        assert_eq!(index.field_types(&rtxn).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn facet_values_iterators() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("age"), S("name") });
+        builder.execute(|_, _| ()).unwrap();
+
+        let content = documents!([
+            { "id": 1, "name": "Kevin", "age": 32 },
+            { "id": 2, "name": "bob", "age": 20 },
+            { "id": 3, "name": "kevin", "age": 20 }
+        ]);
+        let builder = IndexDocuments::new(&mut wtxn, &index, 1);
+        builder.execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+
+        let age = fields_ids_map.id("age").unwrap();
+        let numbers: Vec<_> = index
+            .facet_number_iter(&rtxn, age)
+            .unwrap()
+            .map(|result| result.map(|(value, docids)| (value, docids.len())))
+            .collect::<heed::Result<_>>()
+            .unwrap();
+        assert_eq!(numbers, vec![(20.0, 2), (32.0, 1)]);
+
+        let name = fields_ids_map.id("name").unwrap();
+        let strings: Vec<_> = index
+            .facet_string_iter(&rtxn, name)
+            .unwrap()
+            .map(|result| result.map(|(normalized, _original, docids)| (normalized, docids.len())))
+            .collect::<heed::Result<_>>()
+            .unwrap();
+        assert_eq!(strings, vec![("bob", 1), ("kevin", 2)]);
+    }
+
+    #[test]
+    fn commits_since_snapshot() {
+        let index = TempIndex::new();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.commits_since(&rtxn).unwrap(), 0);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 1, "name": "kevin" }]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(index.commits_since(&rtxn).unwrap(), 1);
+
+        // An aborted transaction is not counted.
+        let wtxn = index.write_txn().unwrap();
+        wtxn.abort().unwrap();
+        assert_eq!(index.commits_since(&rtxn).unwrap(), 1);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 2, "name": "bob" }]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(index.commits_since(&rtxn).unwrap(), 2);
+        drop(rtxn);
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.commits_since(&rtxn).unwrap(), 0);
+        assert_eq!(index.commits_count(&rtxn).unwrap(), 2);
+    }
+
+    #[test]
+    fn commit_hooks() {
+        let index = TempIndex::new();
+        let summaries = Arc::new(Mutex::new(Vec::new()));
+        let cloned = summaries.clone();
+        let id = index.register_commit_hook(move |summary| {
+            cloned.lock().unwrap().push(summary.clone());
+        });
+
+        let mut wtxn = index.write_txn_with_hooks().unwrap();
+        let content = documents!([{ "id": 1, "name": "kevin" }, { "id": 2, "name": "bob" }]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        let summary = index.commit(wtxn).unwrap();
+        assert_eq!(summary.commits_count, 1);
+        assert_eq!(summary.added_documents_ids.len(), 2);
+        assert!(summary.updated_documents_ids.is_empty());
+        assert_eq!(summary.documents_count, 2);
+
+        let mut wtxn = index.write_txn_with_hooks().unwrap();
+        let content = documents!([{ "id": 2, "name": "bobby" }, { "id": 3, "name": "tamo" }]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // The aborted transactions don't invoke the hooks.
+        let wtxn = index.write_txn_with_hooks().unwrap();
+        wtxn.abort().unwrap();
+
+        // Neither do the transactions that didn't opt in.
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 4, "name": "many" }]);
+        IndexDocuments::new(&mut wtxn, &index, 2).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let mut wtxn = index.write_txn_with_hooks().unwrap();
+        let mut builder = DeleteDocuments::new(&mut wtxn, &index, 3).unwrap();
+        builder.delete_external_id("1");
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let external_ids = index.external_documents_ids(&rtxn).unwrap();
+        let bob = external_ids.get("2").unwrap();
+        let tamo = external_ids.get("3").unwrap();
+        drop(rtxn);
+
+        {
+            let summaries = summaries.lock().unwrap();
+            assert_eq!(summaries.len(), 3);
+
+            assert_eq!(summaries[1].commits_count, 2);
+            assert_eq!(summaries[1].added_documents_ids.iter().collect::<Vec<_>>(), vec![tamo]);
+            assert_eq!(summaries[1].updated_documents_ids.iter().collect::<Vec<_>>(), vec![bob]);
+            assert!(summaries[1].deleted_documents_ids.is_empty());
+            assert_eq!(summaries[1].documents_count, 3);
+
+            assert_eq!(summaries[2].commits_count, 4);
+            assert!(summaries[2].added_documents_ids.is_empty());
+            assert!(summaries[2].updated_documents_ids.is_empty());
+            assert_eq!(summaries[2].deleted_documents_ids.len(), 1);
+            assert_eq!(summaries[2].documents_count, 3);
+        }
+
+        assert!(index.unregister_commit_hook(id));
+        assert!(!index.unregister_commit_hook(id));
+        let wtxn = index.write_txn_with_hooks().unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(summaries.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn write_txn_without_changes_is_not_counted() {
+        let index = TempIndex::new();
+        let wtxn = index.write_txn().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.commits_count(&rtxn).unwrap(), 0);
+        drop(rtxn);
+
+        // The commits counter is only incremented once by a transaction.
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 1, "name": "kevin" }]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        let content = documents!([{ "id": 2, "name": "bob" }]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        assert!(index.is_uncommitted(&wtxn).unwrap());
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.commits_count(&rtxn).unwrap(), 1);
+        assert!(!index.is_uncommitted(&rtxn).unwrap());
+    }
+
+    #[test]
+    fn temp_dir_index_removes_its_directory() {
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = TempDirIndex::new(options).unwrap();
+        let path = index.path().to_owned();
+        if Path::new(SHARED_MEMORY_DIR).is_dir() {
+            assert!(path.starts_with(SHARED_MEMORY_DIR));
+        }
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 1, "name": "kevin" }]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let results = index.search(&rtxn).query("kevin").execute().unwrap();
+        assert_eq!(results.documents_ids, vec![0]);
+        drop(rtxn);
+
+        assert!(path.exists());
+        drop(index);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn copy_to_path_can_be_opened() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 1, "name": "kevin" }]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        index.copy_to_path(dir.path().join("data.mdb")).unwrap();
+
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let copy = Index::new(options, dir.path()).unwrap();
+        let rtxn = copy.read_txn().unwrap();
+        let results = copy.search(&rtxn).query("kevin").execute().unwrap();
+        assert_eq!(results.documents_ids, vec![0]);
+    }
+
+    #[test]
+    fn index_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options.clone(), dir.path()).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let uuid = index.uuid(&rtxn).unwrap();
+        let created_at = index.created_at(&rtxn).unwrap();
+        assert_eq!(index.version(&rtxn).unwrap(), Some(env!("CARGO_PKG_VERSION")));
+        assert_eq!(index.updated_at(&rtxn).unwrap(), created_at);
+        assert_eq!(index.primary_key(&rtxn).unwrap(), None);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 1, "name": "kevin" }]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+        drop(index);
+
+        // The metadata is kept when the index is opened again.
+        let index = Index::new(options.clone(), dir.path()).unwrap();
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.uuid(&rtxn).unwrap(), uuid);
+        assert_eq!(index.created_at(&rtxn).unwrap(), created_at);
+        assert!(index.updated_at(&rtxn).unwrap() >= created_at);
+        assert_eq!(index.primary_key(&rtxn).unwrap(), Some("id"));
+        drop(rtxn);
+
+        // An index without uuid is given a new one when it is opened.
+        let mut wtxn = index.write_txn().unwrap();
+        index.main.delete::<_, Str>(&mut wtxn, main_key::UUID_KEY).unwrap();
+        wtxn.commit().unwrap();
+        drop(index);
+
+        let index = Index::new(options, dir.path()).unwrap();
+        let rtxn = index.read_txn().unwrap();
+        assert_ne!(index.uuid(&rtxn).unwrap(), uuid);
+    }
+
+    #[test]
+    fn tokenize_debug() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_searchable_fields(vec![S("title")]);
+        builder.set_stop_words(vec![S("the")].into_iter().collect());
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let tokens = index.tokenize_debug(&rtxn, Some("title"), "The Café. Hello").unwrap();
+
+        // The stop word is removed from the documents but still shifts the next positions.
+        let the = tokens.iter().find(|token| token.normalized == "the").unwrap();
+        assert_eq!(the.kind, DebugTokenKind::StopWord);
+        assert!(!the.indexed);
+
+        let words: Vec<_> = tokens
+            .iter()
+            .filter(|token| token.indexed)
+            .map(|token| {
+                (token.normalized.as_str(), token.exact.as_str(), token.position, token.kind)
+            })
+            .collect();
+        assert_eq!(
+            words,
+            vec![
+                ("cafe", "café", Some(1), DebugTokenKind::Word),
+                ("hello", "hello", Some(9), DebugTokenKind::Word),
+            ]
+        );
+
+        // The indexed tokens are the ones stored in the word databases.
+        let content = documents!([{ "id": 0, "title": "The Café. Hello" }]);
+        let mut wtxn = index.write_txn().unwrap();
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+        let rtxn = index.read_txn().unwrap();
+        for token in tokens.iter().filter(|token| token.position.is_some()) {
+            let stored = index.word_docids.get(&rtxn, &token.normalized).unwrap().is_some();
+            assert_eq!(stored, token.indexed, "{:?}", token);
+        }
+
+        // When the stop words are kept in the documents they are indexed.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.set_stop_words_mode(StopWordsMode::QueryOnly);
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let tokens = index.tokenize_debug(&rtxn, Some("title"), "The Café. Hello").unwrap();
+        let the = tokens.iter().find(|token| token.normalized == "the").unwrap();
+        assert_eq!(the.kind, DebugTokenKind::Word);
+        assert_eq!(the.position, Some(0));
+        assert!(the.indexed);
+
+        // The words of an attribute that is not searchable are not indexed.
+        let tokens = index.tokenize_debug(&rtxn, Some("author"), "hello").unwrap();
+        assert!(tokens.iter().all(|token| !token.indexed));
+    }
+
+    #[test]
+    fn stop_words_candidates() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 0, "text": "the cat and the dog" },
+            { "id": 1, "text": "the bird" },
+            { "id": 2, "text": "a cat and a mouse" },
+            { "id": 3, "text": "the end" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let candidates = index.stop_words_candidates(&rtxn, 0.4).unwrap();
+        assert_eq!(candidates, vec![(S("the"), 3), (S("and"), 2), (S("cat"), 2)]);
+        assert!(index.stop_words_candidates(&rtxn, 0.9).unwrap().is_empty());
+    }
+
+    #[test]
+    fn near_duplicate_documents() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_documents_fingerprinting(true);
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": 0, "text": "the quick brown fox jumps over the lazy dog near the river bank" },
+            { "id": 1, "text": "the quick brown fox jumps over the lazy dog near the river bank!" },
+            { "id": 2, "text": "a completely different sentence about rust and databases" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.document_fingerprint(&rtxn, 2).unwrap().is_some());
+        let duplicates = index.near_duplicates(&rtxn, 0, 3).unwrap();
+        assert_eq!(duplicates.into_iter().collect::<Vec<_>>(), vec![1]);
+        assert!(index.near_duplicates(&rtxn, 2, 3).unwrap().is_empty());
+        // The larger distances compare all the fingerprints.
+        let duplicates = index.near_duplicates(&rtxn, 0, 64).unwrap();
+        assert_eq!(duplicates.into_iter().collect::<Vec<_>>(), vec![1, 2]);
+        drop(rtxn);
+
+        // A deleted document is removed from the bands of its fingerprint.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = DeleteDocuments::new(&mut wtxn, &index, 2).unwrap();
+        builder.delete_external_id("1");
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.near_duplicates(&rtxn, 0, 3).unwrap().is_empty());
+        for result in index.fingerprint_band_docids.iter(&rtxn).unwrap() {
+            let (_band, docids) = result.unwrap();
+            assert!(!docids.contains(1));
+        }
+        drop(rtxn);
+
+        // Disabling the fingerprinting removes the fingerprints.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 3);
+        builder.reset_documents_fingerprinting();
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.document_fingerprint(&rtxn, 0).unwrap().is_none());
+    }
+
+    #[test]
+    fn single_writer_process() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+
+        assert_eq!(WriterLock::current_writer(&path).unwrap(), None);
+        let index = Index::open_writer(options.clone(), &path).unwrap();
+        assert!(WriterLock::is_locked(&path).unwrap());
+        assert_eq!(WriterLock::current_writer(&path).unwrap(), Some(std::process::id()));
+
+        // A second writer can't open the index, but readers can.
+        let error = Index::open_writer(options.clone(), &path).unwrap_err();
+        assert!(matches!(error, Error::UserError(UserError::IndexAlreadyHasWriter { .. })));
+        let reader = Index::new(options.clone(), &path).unwrap();
+        drop(reader);
+
+        drop(index);
+        assert!(!WriterLock::is_locked(&path).unwrap());
+        assert_eq!(WriterLock::current_writer(&path).unwrap(), None);
+        let index = Index::open_writer(options, &path).unwrap();
+        drop(index);
+    }
+
+    #[test]
+    fn writer_lock_ignores_the_pid_file_content() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+
+        // A lock file left by a crashed writer, or one that is empty
+        // because a writer is starting, isn't a lock by itself.
+        std::fs::write(path.path().join(crate::WRITER_LOCK_FILE), "").unwrap();
+        assert!(!WriterLock::is_locked(&path).unwrap());
+        let index = Index::open_writer(options.clone(), &path).unwrap();
+
+        // Another writer is refused even if the pid can't be read.
+        std::fs::write(path.path().join(crate::WRITER_LOCK_FILE), "").unwrap();
+        let error = Index::open_writer(options, &path).unwrap_err();
+        assert!(matches!(error, Error::UserError(UserError::IndexAlreadyHasWriter { pid: None })));
+        drop(index);
+    }
+
+    #[test]
+    fn encrypted_documents() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let key = EncryptionKey::from_bytes([42; 32]);
+        let index =
+            Index::new_with_encryption_key(options.clone(), dir.path(), key.clone()).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 1, "name": "kevin", "age": 20 }]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        let content = documents!([{ "id": 1, "name": "kevina" }]);
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, 1);
+        builder.index_documents_method(IndexDocumentsMethod::UpdateDocuments);
+        builder.execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let results = index.search(&rtxn).query("kevina").execute().unwrap();
+        assert_eq!(results.documents_ids, vec![0]);
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let bytes = index.document_bytes(&rtxn, 0).unwrap().unwrap();
+        let document = DocumentReader::new(obkv::KvReaderU16::new(&bytes));
+        let name = fields_ids_map.id("name").unwrap();
+        let age = fields_ids_map.id("age").unwrap();
+        assert_eq!(document.raw_value(name), Some(&b"\"kevina\""[..]));
+        assert_eq!(document.raw_value(age), Some(&b"20"[..]));
+
+        // The documents stored in the database can't be read without the key.
+        let raw = index.documents.remap_data_type::<ByteSlice>().get(&rtxn, &BEU32::new(0));
+        assert!(!raw.unwrap().unwrap().windows(6).any(|window| window == b"kevina"));
+        let error = index.documents(&rtxn, Some(0)).unwrap_err();
+        assert!(matches!(error, Error::UserError(UserError::EncryptedDocuments)));
+        drop(rtxn);
+        drop(index);
+
+        let error = Index::new(options.clone(), dir.path()).unwrap_err();
+        assert!(matches!(error, Error::UserError(UserError::MissingEncryptionKey)));
+        let wrong_key = EncryptionKey::from_bytes([0; 32]);
+        let error =
+            Index::new_with_encryption_key(options.clone(), dir.path(), wrong_key).unwrap_err();
+        assert!(matches!(error, Error::UserError(UserError::InvalidEncryptionKey)));
+        Index::new_with_encryption_key(options, dir.path(), key).unwrap();
+    }
+
+    #[test]
+    fn document_word_counts() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_stop_words(vec![S("the")].into_iter().collect());
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": 0, "title": "the quick brown fox", "desc": "hello" },
+            { "id": 1, "title": "hello. world" },
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let counts = index.document_word_counts(&rtxn, 0).unwrap().unwrap();
+        let title = fields_ids_map.id("title").unwrap();
+        let desc = fields_ids_map.id("desc").unwrap();
+        // The stop words aren't counted.
+        assert_eq!(counts.fields.get(&title), Some(&3));
+        assert_eq!(counts.fields.get(&desc), Some(&1));
+        assert_eq!(counts.total, counts.fields.values().sum::<u32>());
+        // The hard separators don't count as words.
+        let counts = index.document_word_counts(&rtxn, 1).unwrap().unwrap();
+        assert_eq!(counts.fields.get(&title), Some(&2));
+        assert_eq!(counts.total, 2);
+        assert!(index.document_word_counts(&rtxn, 2).unwrap().is_none());
+        drop(rtxn);
+
+        // The new stop words are removed from the counts without indexing the documents again.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.set_stop_words(vec![S("the"), S("quick")].into_iter().collect());
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let counts = index.document_word_counts(&rtxn, 0).unwrap().unwrap();
+        assert_eq!(counts.fields.get(&title), Some(&2));
+        assert_eq!(counts.fields.get(&desc), Some(&1));
+    }
+
+    #[test]
+    fn warmup() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("price") });
+        builder.execute(|_, _| ()).unwrap();
+
+        // Enough facet values to build the facet levels.
+        let mut cursor = Cursor::new(Vec::new());
+        let mut builder = DocumentBatchBuilder::new(&mut cursor).unwrap();
+        for id in 0..1000 {
+            builder.add_documents(json!({ "id": id, "title": "hello", "price": id })).unwrap();
+        }
+        builder.finish().unwrap();
+        cursor.set_position(0);
+        let content = DocumentBatchReader::from_reader(cursor).unwrap();
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let stats = index.warmup(&rtxn, &WarmupPlan::default()).unwrap();
+        assert!(stats.bytes_read > 0);
+        assert!(stats.facet_entries > 0);
+        assert!(stats.prefixes <= WarmupPlan::default().top_prefixes);
+
+        let plan = WarmupPlan { words_fst: false, top_prefixes: 0, facet_roots: false };
+        assert_eq!(index.warmup(&rtxn, &plan).unwrap(), WarmupStats::default());
+    }
+
+    #[test]
+    fn fresh_index_read_apis() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("color"), S("_geo") });
+        builder.set_sortable_fields(hashset! { S("price"), S("_geo") });
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // An index whose settings are updated but that never received any document.
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.number_of_documents(&rtxn).unwrap(), 0);
+        assert!(index.documents_ids(&rtxn).unwrap().is_empty());
+        assert!(index.all_documents(&rtxn).unwrap().next().is_none());
+        assert!(index.field_distribution(&rtxn).unwrap().is_empty());
+        assert!(index.words_fst(&rtxn).unwrap().is_empty());
+        assert!(index.words_prefixes_fst(&rtxn).unwrap().is_empty());
+        assert!(index.geo_rtree(&rtxn).unwrap().is_none());
+
+        let assert_empty = |search: &mut Search| {
+            let result = search.execute().unwrap();
+            assert!(result.documents_ids.is_empty());
+            assert!(result.candidates.is_empty());
+            result
+        };
+        let filter =
+            |expression: &str| FilterCondition::from_str(&rtxn, &index, expression).unwrap();
+
+        assert_empty(&mut index.search(&rtxn));
+        assert_empty(index.search(&rtxn).query("hello world"));
+        assert_empty(
+            index.search(&rtxn).sort_criteria(vec![AscDesc::Asc(Member::Field(S("price")))]),
+        );
+        assert_empty(
+            index
+                .search(&rtxn)
+                .query("hello")
+                .sort_criteria(vec![AscDesc::Desc(Member::Geo([0., 0.]))]),
+        );
+        assert_empty(index.search(&rtxn).filter(filter("color = red")));
+        assert_empty(index.search(&rtxn).query("hello").filter(filter("NOT _geoRadius(0, 0, 10)")));
+        assert_empty(index.search(&rtxn).dedup_by("color"));
+        let result = assert_empty(index.search(&rtxn).group_by("color", 2));
+        assert_eq!(result.groups, Some(Default::default()));
+
+        let distribution = index.facets_distribution(&rtxn).execute().unwrap();
+        assert!(distribution.is_empty());
+        drop(rtxn);
+
+        // The distinct field doesn't change anything either.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 1);
+        builder.set_distinct_field(S("color"));
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let result = index.search(&rtxn).query("hello").execute().unwrap();
+        assert!(result.documents_ids.is_empty());
+    }
 }
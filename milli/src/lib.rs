@@ -5,39 +5,83 @@ extern crate pest_derive;
 pub mod documents;
 
 mod asc_desc;
+#[cfg(feature = "bench")]
+pub mod bench;
+mod commit_hooks;
 mod criterion;
+mod document_projection;
+mod document_reader;
+mod encryption;
 mod error;
 mod external_documents_ids;
 pub mod facet;
+mod facet_range_cache;
+mod field_types;
 mod fields_ids_map;
+mod filter_attempts;
+mod filter_capabilities;
+mod fingerprint;
+mod first_letter_typo;
 pub mod heed_codec;
 pub mod index;
 pub mod proximity;
+mod query_statistics;
+#[cfg(feature = "indexing")]
+pub mod relevancy;
 mod search;
+mod stop_words_mode;
+mod token_debug;
+mod typo_distance;
+#[cfg(feature = "indexing")]
 pub mod update;
+mod writer_lock;
 
 use std::collections::{BTreeMap, HashMap};
 use std::convert::{TryFrom, TryInto};
 use std::hash::BuildHasherDefault;
 
+use chrono::DateTime;
 use fxhash::{FxHasher32, FxHasher64};
 pub use grenad::CompressionType;
+use meilisearch_tokenizer::token::SeparatorKind;
+use meilisearch_tokenizer::{Analyzer, Token, TokenKind};
 use serde_json::{Map, Value};
 
 pub use self::asc_desc::{AscDesc, AscDescError, Member, SortError};
-pub use self::criterion::{default_criteria, Criterion, CriterionError};
+pub use self::commit_hooks::{CommitHookId, CommitSummary};
+pub use self::criterion::{default_criteria, Criterion, CriterionError, CriterionWarning};
+pub use self::document_projection::{DocumentProjection, MaskingMethod, REDACTED_VALUE};
+pub use self::document_reader::DocumentReader;
+pub use self::encryption::EncryptionKey;
 pub use self::error::{
     Error, FieldIdMapMissingEntry, InternalError, SerializationError, UserError,
 };
-pub use self::external_documents_ids::ExternalDocumentsIds;
+pub use self::external_documents_ids::{ExternalDocumentsIds, ExternalDocumentsIdsIter};
+pub use self::field_types::{FieldType, FieldTypeWarning, FieldTypes};
 pub use self::fields_ids_map::FieldsIdsMap;
+pub use self::filter_attempts::MAX_TRACKED_FILTER_ATTEMPTS;
+pub use self::filter_capabilities::FilterCapabilities;
+pub use self::fingerprint::fingerprints_distance;
+pub use self::first_letter_typo::FirstLetterTypo;
 pub use self::heed_codec::{
     BEU32StrCodec, BoRoaringBitmapCodec, BoRoaringBitmapLenCodec, CboRoaringBitmapCodec,
-    CboRoaringBitmapLenCodec, FieldIdWordCountCodec, ObkvCodec, RoaringBitmapCodec,
-    RoaringBitmapLenCodec, StrBEU32Codec, StrStrU8Codec,
+    CboRoaringBitmapLenCodec, FieldIdStrCodec, FieldIdWordCountCodec, ObkvCodec,
+    RoaringBitmapCodec, RoaringBitmapLenCodec, StrBEU32Codec, StrStrU8Codec,
 };
-pub use self::index::Index;
-pub use self::search::{FacetDistribution, FilterCondition, MatchingWords, Search, SearchResult};
+pub use self::index::{
+    DocumentWordCounts, Index, IndexRwTxn, TempDirIndex, WarmupPlan, WarmupStats,
+};
+pub use self::query_statistics::{QueryStatistics, MAX_TRACKED_QUERIES, QUERY_FREQUENCY_HALF_LIFE};
+pub use self::search::{
+    CandidatesExpr, FacetDistribution, FilterCondition, Locale, Lookup, MatchingWords,
+    NonFilterableAttributeError, OrderBy, RankingRuleDetail, Relationship, Search, SearchLog,
+    SearchLogHook, SearchResult, SearchSession, TermsMatchingStrategy, APPROXIMATE_SAMPLE_SIZE,
+    DEDUP_MAX_HASHES, DEFAULT_VALUES_PER_FACET,
+};
+pub use self::stop_words_mode::StopWordsMode;
+pub use self::token_debug::{DebugToken, DebugTokenKind};
+pub use self::typo_distance::TypoDistance;
+pub use self::writer_lock::{WriterLock, WRITER_LOCK_FILE};
 
 pub type Result<T> = std::result::Result<T, error::Error>;
 
@@ -47,6 +91,7 @@ pub type SmallString32 = smallstr::SmallString<[u8; 32]>;
 pub type SmallVec16<T> = smallvec::SmallVec<[T; 16]>;
 pub type SmallVec32<T> = smallvec::SmallVec<[T; 32]>;
 pub type SmallVec8<T> = smallvec::SmallVec<[T; 8]>;
+pub type BEU16 = heed::zerocopy::U16<heed::byteorder::BE>;
 pub type BEU32 = heed::zerocopy::U32<heed::byteorder::BE>;
 pub type BEU64 = heed::zerocopy::U64<heed::byteorder::BE>;
 pub type Attribute = u32;
@@ -125,6 +170,89 @@ pub fn json_to_string(value: &Value) -> Option<String> {
     }
 }
 
+/// Returns the exact version of a word from its original text, it is only lowercased
+/// and isn't folded by the normalizers of the analyzer, accents are kept for example.
+fn exact_word(original: &str) -> String {
+    original.trim().to_lowercase()
+}
+
+/// Parses an RFC 3339 date, like `2021-06-01T00:00:00Z`, into the number of seconds since
+/// the Unix epoch, the dates are faceted and filtered as these numbers.
+pub(crate) fn rfc3339_timestamp(date: &str) -> Option<f64> {
+    let date = DateTime::parse_from_rfc3339(date.trim()).ok()?;
+    Some(date.timestamp() as f64 + date.timestamp_subsec_nanos() as f64 / 1_000_000_000.0)
+}
+
+/// take an iterator on tokens and compute their relative position depending on separator kinds
+/// if it's an `Hard` separator we add an additional relative proximity of 8 between words,
+/// else we keep the standart proximity of 1 between words.
+pub(crate) fn process_tokens<'a>(
+    tokens: impl Iterator<Item = Token<'a>>,
+) -> impl Iterator<Item = (usize, Token<'a>)> {
+    tokens
+        .skip_while(|token| token.is_separator().is_some())
+        .scan((0, None), |(offset, prev_kind), token| {
+            match token.kind {
+                TokenKind::Word | TokenKind::StopWord | TokenKind::Unknown => {
+                    *offset += match *prev_kind {
+                        Some(TokenKind::Separator(SeparatorKind::Hard)) => 8,
+                        Some(_) => 1,
+                        None => 0,
+                    };
+                    *prev_kind = Some(token.kind)
+                }
+                TokenKind::Separator(SeparatorKind::Hard) => {
+                    *prev_kind = Some(token.kind);
+                }
+                TokenKind::Separator(SeparatorKind::Soft)
+                    if *prev_kind != Some(TokenKind::Separator(SeparatorKind::Hard)) =>
+                {
+                    *prev_kind = Some(token.kind);
+                }
+                _ => (),
+            }
+            Some((*offset, token))
+        })
+        .filter(|(_, t)| t.is_word())
+}
+
+/// Normalizes the words of a synonym with the analyzer, the way they are stored in the index.
+pub(crate) fn normalize_synonym<A: AsRef<[u8]>>(analyzer: &Analyzer<A>, text: &str) -> Vec<String> {
+    analyzer
+        .analyze(text)
+        .tokens()
+        .filter_map(|token| if token.is_word() { Some(token.text().to_string()) } else { None })
+        .collect::<Vec<_>>()
+}
+
+/// Normalizes the words and the synonyms of a synonyms map with the analyzer, the synonyms
+/// of the words that have the same normalized form are merged and deduplicated.
+pub(crate) fn normalize_synonyms<A: AsRef<[u8]>>(
+    analyzer: &Analyzer<A>,
+    synonyms: &HashMap<String, Vec<String>>,
+) -> HashMap<Vec<String>, Vec<Vec<String>>> {
+    let mut new_synonyms = HashMap::new();
+    for (word, synonyms) in synonyms {
+        // Normalize both the word and associated synonyms.
+        let normalized_word = normalize_synonym(analyzer, word);
+        let normalized_synonyms =
+            synonyms.iter().map(|synonym| normalize_synonym(analyzer, synonym));
+
+        // Store the normalized synonyms under the normalized word,
+        // merging the possible duplicate words.
+        let entry = new_synonyms.entry(normalized_word).or_insert_with(Vec::new);
+        entry.extend(normalized_synonyms);
+    }
+
+    // Make sure that we don't have duplicate synonyms.
+    new_synonyms.iter_mut().for_each(|(_, synonyms)| {
+        synonyms.sort_unstable();
+        synonyms.dedup();
+    });
+
+    new_synonyms
+}
+
 /// Divides one slice into two at an index, returns `None` if mid is out of bounds.
 fn try_split_at<T>(slice: &[T], mid: usize) -> Option<(&[T], &[T])> {
     if mid <= slice.len() {
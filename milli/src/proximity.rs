@@ -2,7 +2,11 @@ use std::cmp;
 
 use crate::{Attribute, Position};
 
+/// The number of positions reserved for every attribute of a document, it is the stride used by
+/// the indexes that were created before it was possible to configure it and the default one.
 pub const ONE_ATTRIBUTE: u32 = 1000;
+/// The biggest stride that can be used without overflowing the positions of the last field id.
+pub const MAX_ONE_ATTRIBUTE: u32 = u32::MAX / (u16::MAX as u32 + 1);
 pub const MAX_DISTANCE: u32 = 8;
 
 pub fn index_proximity(lhs: u32, rhs: u32) -> u32 {
@@ -13,9 +17,9 @@ pub fn index_proximity(lhs: u32, rhs: u32) -> u32 {
     }
 }
 
-pub fn positions_proximity(lhs: Position, rhs: Position) -> u32 {
-    let (lhs_attr, lhs_index) = extract_position(lhs);
-    let (rhs_attr, rhs_index) = extract_position(rhs);
+pub fn positions_proximity(lhs: Position, rhs: Position, one_attribute: u32) -> u32 {
+    let (lhs_attr, lhs_index) = extract_position(lhs, one_attribute);
+    let (rhs_attr, rhs_index) = extract_position(rhs, one_attribute);
     if lhs_attr != rhs_attr {
         MAX_DISTANCE
     } else {
@@ -23,10 +27,10 @@ pub fn positions_proximity(lhs: Position, rhs: Position) -> u32 {
     }
 }
 
-pub fn extract_position(position: Position) -> (Attribute, Position) {
-    (position / ONE_ATTRIBUTE, position % ONE_ATTRIBUTE)
+pub fn extract_position(position: Position, one_attribute: u32) -> (Attribute, Position) {
+    (position / one_attribute, position % one_attribute)
 }
 
-pub fn path_proximity(path: &[Position]) -> u32 {
-    path.windows(2).map(|w| positions_proximity(w[0], w[1])).sum::<u32>()
+pub fn path_proximity(path: &[Position], one_attribute: u32) -> u32 {
+    path.windows(2).map(|w| positions_proximity(w[0], w[1], one_attribute)).sum::<u32>()
 }
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The maximum number of distinct queries tracked by the statistics,
+/// the queries with the lowest frequency are forgotten first.
+pub const MAX_TRACKED_QUERIES: usize = 1000;
+/// The number of seconds after which the frequency of a query is divided by two.
+pub const QUERY_FREQUENCY_HALF_LIFE: i64 = 7 * 24 * 60 * 60;
+
+/// The frequency of the queries executed on an index and of the ones that returned
+/// no results, the frequencies decay over time to reflect the recent trends.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryStatistics {
+    queries: HashMap<String, QueryFrequency>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct QueryFrequency {
+    frequency: f64,
+    zero_results_frequency: f64,
+    updated_at: DateTime<Utc>,
+}
+
+impl QueryFrequency {
+    fn decay(&mut self, now: DateTime<Utc>) {
+        let elapsed = (now - self.updated_at).num_seconds().max(0) as f64;
+        let factor = 0.5f64.powf(elapsed / QUERY_FREQUENCY_HALF_LIFE as f64);
+        self.frequency *= factor;
+        self.zero_results_frequency *= factor;
+        self.updated_at = now;
+    }
+}
+
+impl QueryStatistics {
+    /// Records a query that was executed at the given time and the number of documents it matched.
+    pub fn record(&mut self, query: &str, hits: u64, now: DateTime<Utc>) {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return;
+        }
+
+        if !self.queries.contains_key(&query) && self.queries.len() >= MAX_TRACKED_QUERIES {
+            self.evict_least_frequent(now);
+        }
+
+        let entry = self.queries.entry(query).or_insert(QueryFrequency {
+            frequency: 0.0,
+            zero_results_frequency: 0.0,
+            updated_at: now,
+        });
+        entry.decay(now);
+        entry.frequency += 1.0;
+        if hits == 0 {
+            entry.zero_results_frequency += 1.0;
+        }
+    }
+
+    /// Returns the `n` most frequent queries with their decayed frequency.
+    pub fn popular_queries(&self, n: usize, now: DateTime<Utc>) -> Vec<(String, f64)> {
+        self.top(n, now, |frequency| frequency.frequency)
+    }
+
+    /// Returns the `n` most frequent queries that matched no document with their decayed frequency.
+    pub fn zero_results_queries(&self, n: usize, now: DateTime<Utc>) -> Vec<(String, f64)> {
+        self.top(n, now, |frequency| frequency.zero_results_frequency)
+    }
+
+    pub fn len(&self) -> usize {
+        self.queries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
+    }
+
+    fn top<F>(&self, n: usize, now: DateTime<Utc>, score: F) -> Vec<(String, f64)>
+    where
+        F: Fn(&QueryFrequency) -> f64,
+    {
+        let mut queries: Vec<_> = self
+            .queries
+            .iter()
+            .map(|(query, frequency)| {
+                let mut frequency = frequency.clone();
+                frequency.decay(now);
+                (query.clone(), score(&frequency))
+            })
+            .filter(|(_, score)| *score > 0.0)
+            .collect();
+
+        queries.sort_by(|(qa, a), (qb, b)| b.partial_cmp(a).unwrap().then_with(|| qa.cmp(qb)));
+        queries.truncate(n);
+        queries
+    }
+
+    fn evict_least_frequent(&mut self, now: DateTime<Utc>) {
+        let least_frequent = self
+            .queries
+            .iter_mut()
+            .map(|(query, frequency)| {
+                frequency.decay(now);
+                (query, frequency.frequency)
+            })
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .map(|(query, _)| query.clone());
+
+        if let Some(query) = least_frequent {
+            self.queries.remove(&query);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn popular_and_zero_results_queries() {
+        let now = Utc::now();
+        let mut statistics = QueryStatistics::default();
+        statistics.record("hello", 10, now);
+        statistics.record(" Hello ", 10, now);
+        statistics.record("world", 3, now);
+        statistics.record("kiwi", 0, now);
+
+        let popular = statistics.popular_queries(2, now);
+        assert_eq!(popular, vec![("hello".to_string(), 2.0), ("kiwi".to_string(), 1.0)]);
+        let zero_results = statistics.zero_results_queries(10, now);
+        assert_eq!(zero_results, vec![("kiwi".to_string(), 1.0)]);
+
+        // The frequencies are divided by two after a half-life.
+        let later = now + Duration::seconds(QUERY_FREQUENCY_HALF_LIFE);
+        statistics.record("world", 3, later);
+        let popular = statistics.popular_queries(1, later);
+        assert_eq!(popular, vec![("world".to_string(), 1.5)]);
+    }
+
+    #[test]
+    fn bounded_number_of_queries() {
+        let now = Utc::now();
+        let mut statistics = QueryStatistics::default();
+        statistics.record("popular", 1, now);
+        statistics.record("popular", 1, now);
+        for i in 0..MAX_TRACKED_QUERIES * 2 {
+            statistics.record(&format!("query {}", i), 1, now);
+        }
+
+        assert_eq!(statistics.len(), MAX_TRACKED_QUERIES);
+        assert_eq!(statistics.popular_queries(1, now)[0].0, "popular");
+    }
+}
@@ -0,0 +1,235 @@
+//! Helpers to write relevancy regression tests.
+//!
+//! A corpus is indexed in a temporary index and the rankings of a set of queries are compared
+//! to the golden rankings, the orderings that were reviewed and are expected to stay stable
+//! across code changes. The golden rankings are stored in JSON files next to the tests:
+//!
+//! ```json
+//! [
+//!     { "query": "hello world", "expected": ["2", "1", "5"] },
+//!     { "query": "hello", "filter": "genre = drama", "sort": ["year:desc"], "expected": ["4"] }
+//! ]
+//! ```
+//!
+//! When the `UPDATE_GOLDEN_RANKINGS` environment variable is set, the golden files are
+//! rewritten with the current rankings instead of being checked, the diff of the files
+//! can then be reviewed like any other change.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek};
+use std::path::Path;
+
+use heed::EnvOpenOptions;
+use serde::{Deserialize, Serialize};
+
+use crate::documents::DocumentBatchReader;
+use crate::update::{IndexDocuments, Settings};
+use crate::{AscDesc, FilterCondition, Index, Result, SortError, TempDirIndex};
+
+/// The environment variable that makes [`RelevancyHarness::assert_golden_file`]
+/// rewrite the golden files with the current rankings.
+pub const UPDATE_GOLDEN_RANKINGS: &str = "UPDATE_GOLDEN_RANKINGS";
+
+/// A query and the external ids of the documents it is expected to return, in order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoldenRanking {
+    pub query: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sort: Vec<String>,
+    /// The maximum number of documents retrieved and compared to the expected ones.
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+    pub expected: Vec<String>,
+}
+
+fn default_limit() -> usize {
+    20
+}
+
+/// A query that doesn't return the documents of its golden ranking anymore.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankingRegression {
+    pub query: String,
+    pub expected: Vec<String>,
+    pub found: Vec<String>,
+}
+
+impl fmt::Display for RankingRegression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the query {:?} returned {:?} instead of {:?}",
+            self.query, self.found, self.expected
+        )
+    }
+}
+
+/// A temporary index containing the corpus the golden rankings are computed on.
+pub struct RelevancyHarness {
+    index: TempDirIndex,
+}
+
+impl RelevancyHarness {
+    /// Indexes the corpus in a temporary index, the settings are applied before the documents
+    /// are indexed. The documents must have an external id, it identifies them in the rankings.
+    pub fn new<R, F>(corpus: DocumentBatchReader<R>, settings: F) -> Result<RelevancyHarness>
+    where
+        R: Read + Seek,
+        F: FnOnce(&mut Settings),
+    {
+        let mut options = EnvOpenOptions::new();
+        options.map_size(100 * 1024 * 1024); // 100 MB
+        let index = TempDirIndex::new(options)?;
+
+        let mut wtxn = index.write_txn()?;
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        settings(&mut builder);
+        builder.execute(|_, _| ())?;
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(corpus, |_, _| ())?;
+        wtxn.commit()?;
+
+        Ok(RelevancyHarness { index })
+    }
+
+    pub fn index(&self) -> &Index {
+        &self.index
+    }
+
+    /// Returns the external ids of the documents returned for the query of the golden ranking.
+    pub fn ranking(&self, golden: &GoldenRanking) -> Result<Vec<String>> {
+        let rtxn = self.index.read_txn()?;
+        let mut search = self.index.search(&rtxn);
+        search.query(&golden.query);
+        search.limit(golden.limit);
+        if let Some(filter) = &golden.filter {
+            search.filter(FilterCondition::from_str(&rtxn, &self.index, filter)?);
+        }
+        if !golden.sort.is_empty() {
+            let mut sort_criteria = Vec::new();
+            for text in &golden.sort {
+                let asc_desc: AscDesc = text.parse().map_err(SortError::from)?;
+                sort_criteria.push(asc_desc);
+            }
+            search.sort_criteria(sort_criteria);
+        }
+        let result = search.execute()?;
+
+        let external_ids: HashMap<_, _> = self
+            .index
+            .external_documents_ids(&rtxn)?
+            .iter()
+            .map(|(external_id, docid)| (docid, external_id))
+            .collect();
+        Ok(result
+            .documents_ids
+            .iter()
+            .filter_map(|docid| external_ids.get(docid).cloned())
+            .collect())
+    }
+
+    /// Returns the queries whose ranking differs from their golden ranking.
+    pub fn regressions(&self, goldens: &[GoldenRanking]) -> Result<Vec<RankingRegression>> {
+        let mut regressions = Vec::new();
+        for golden in goldens {
+            let found = self.ranking(golden)?;
+            if found != golden.expected {
+                regressions.push(RankingRegression {
+                    query: golden.query.clone(),
+                    expected: golden.expected.clone(),
+                    found,
+                });
+            }
+        }
+        Ok(regressions)
+    }
+
+    /// Panics with the list of the regressions if a ranking differs from the golden file.
+    ///
+    /// The golden file is rewritten with the current rankings when the
+    /// [`UPDATE_GOLDEN_RANKINGS`] environment variable is set.
+    pub fn assert_golden_file(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .unwrap_or_else(|e| panic!("cannot open the golden file {}: {}", path.display(), e));
+        let mut goldens: Vec<GoldenRanking> = serde_json::from_reader(BufReader::new(file))
+            .unwrap_or_else(|e| panic!("invalid golden file {}: {}", path.display(), e));
+
+        if std::env::var_os(UPDATE_GOLDEN_RANKINGS).is_some() {
+            for golden in &mut goldens {
+                golden.expected = self.ranking(golden).unwrap();
+            }
+            let file = File::create(path).unwrap();
+            serde_json::to_writer_pretty(BufWriter::new(file), &goldens).unwrap();
+            return;
+        }
+
+        let regressions = self.regressions(&goldens).unwrap();
+        if !regressions.is_empty() {
+            let regressions: Vec<_> = regressions.iter().map(ToString::to_string).collect();
+            panic!(
+                "{} rankings differ from the golden file {}:\n{}",
+                regressions.len(),
+                path.display(),
+                regressions.join("\n")
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+
+    use super::*;
+
+    fn harness() -> RelevancyHarness {
+        let corpus = documents!([
+            { "id": "1", "title": "hello" },
+            { "id": "2", "title": "hello world" },
+            { "id": "3", "title": "world" }
+        ]);
+        RelevancyHarness::new(corpus, |_| ()).unwrap()
+    }
+
+    fn golden(query: &str, expected: &[&str]) -> GoldenRanking {
+        GoldenRanking {
+            query: query.to_string(),
+            filter: None,
+            sort: Vec::new(),
+            limit: default_limit(),
+            expected: expected.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn detect_regressions() {
+        let harness = harness();
+
+        let goldens = vec![golden("hello world", &["2", "1"]), golden("world", &["3", "2"])];
+        assert!(harness.regressions(&goldens).unwrap().is_empty());
+
+        let goldens = vec![golden("hello world", &["1", "2"])];
+        let regressions = harness.regressions(&goldens).unwrap();
+        assert_eq!(
+            regressions,
+            vec![RankingRegression {
+                query: S("hello world"),
+                expected: vec![S("1"), S("2")],
+                found: vec![S("2"), S("1")],
+            }]
+        );
+    }
+
+    #[test]
+    fn check_golden_file() {
+        let harness = harness();
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        serde_json::to_writer(&mut file, &[golden("hello world", &["2", "1"])]).unwrap();
+        harness.assert_golden_file(file.path());
+    }
+}
@@ -0,0 +1,222 @@
+use roaring::RoaringBitmap;
+
+use super::criteria::{resolve_query_tree, Context, CriteriaBuilder};
+use super::query_tree::{Operation, Query, QueryKind};
+use super::WordDerivationsCache;
+use crate::error::UserError;
+use crate::facet::facet_string_key;
+use crate::{FilterCondition, Index, Result};
+
+/// An expression combining sets of documents ids, the candidates are computed
+/// from the databases of the index without ranking them.
+///
+/// The words must be normalized like the words of the documents, e.g. lowercased.
+#[derive(Debug, Clone)]
+pub enum CandidatesExpr {
+    /// All the documents of the index.
+    All,
+    /// The documents that contain the word, or one of its derivations
+    /// when it is a prefix or when typos are allowed.
+    Word { word: String, prefix: bool, typos: u8 },
+    /// The documents that contain the words next to each other.
+    Phrase(Vec<String>),
+    /// The documents that match the filter.
+    Filter(FilterCondition),
+    /// The documents that have the string or number value in the faceted field.
+    FacetValue { field: String, value: String },
+    /// The documents that are in all the sets, all the documents when there is no set.
+    And(Vec<CandidatesExpr>),
+    /// The documents that are in any of the sets.
+    Or(Vec<CandidatesExpr>),
+    /// The documents of the first set that are not in the second one.
+    Difference(Box<CandidatesExpr>, Box<CandidatesExpr>),
+}
+
+impl CandidatesExpr {
+    pub fn word(word: impl Into<String>) -> CandidatesExpr {
+        CandidatesExpr::Word { word: word.into(), prefix: false, typos: 0 }
+    }
+
+    pub fn facet_value(field: impl Into<String>, value: impl Into<String>) -> CandidatesExpr {
+        CandidatesExpr::FacetValue { field: field.into(), value: value.into() }
+    }
+
+    pub fn difference(self, other: CandidatesExpr) -> CandidatesExpr {
+        CandidatesExpr::Difference(Box::new(self), Box::new(other))
+    }
+
+    /// Computes the documents ids of the expression.
+    ///
+    /// The sets of an intersection are computed one after the other and the computation stops
+    /// as soon as the intersection is empty, the cheapest and most selective sets should
+    /// therefore be given first. The second set of a difference is only computed when the
+    /// first one isn't empty.
+    pub fn evaluate(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<RoaringBitmap> {
+        let ctx = CriteriaBuilder::new(rtxn, index)?;
+        let mut wdcache = WordDerivationsCache::with_first_letter_typo(ctx.first_letter_typo())
+            .with_typo_distance(ctx.typo_distance());
+        self.evaluate_with(rtxn, index, &ctx, &mut wdcache)
+    }
+
+    fn evaluate_with(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        ctx: &CriteriaBuilder,
+        wdcache: &mut WordDerivationsCache,
+    ) -> Result<RoaringBitmap> {
+        match self {
+            CandidatesExpr::All => Ok(index.documents_ids(rtxn)?),
+            CandidatesExpr::Word { word, prefix, typos } => {
+                let kind = match typos {
+                    0 => QueryKind::exact(word.clone()),
+                    typos => QueryKind::tolerant(*typos, word.clone()),
+                };
+                let operation = Operation::Query(Query { prefix: *prefix, kind, field: None });
+                resolve_query_tree(ctx, &operation, wdcache)
+            }
+            CandidatesExpr::Phrase(words) => {
+                resolve_query_tree(ctx, &Operation::phrase(words.clone()), wdcache)
+            }
+            CandidatesExpr::Filter(condition) => condition.evaluate(rtxn, index),
+            CandidatesExpr::FacetValue { field, value } => {
+                facet_value_docids(rtxn, index, field, value)
+            }
+            CandidatesExpr::And(exprs) => {
+                let mut candidates: Option<RoaringBitmap> = None;
+                // All the documents is the identity of the intersection.
+                for expr in exprs.iter().filter(|expr| !matches!(expr, CandidatesExpr::All)) {
+                    let docids = expr.evaluate_with(rtxn, index, ctx, wdcache)?;
+                    let intersection = match candidates.take() {
+                        Some(candidates) => candidates & docids,
+                        None => docids,
+                    };
+                    let is_empty = intersection.is_empty();
+                    candidates = Some(intersection);
+                    if is_empty {
+                        break;
+                    }
+                }
+                match candidates {
+                    Some(candidates) => Ok(candidates),
+                    None => Ok(index.documents_ids(rtxn)?),
+                }
+            }
+            CandidatesExpr::Or(exprs) => {
+                if exprs.iter().any(|expr| matches!(expr, CandidatesExpr::All)) {
+                    return Ok(index.documents_ids(rtxn)?);
+                }
+                let mut candidates = RoaringBitmap::new();
+                for expr in exprs {
+                    candidates |= expr.evaluate_with(rtxn, index, ctx, wdcache)?;
+                }
+                Ok(candidates)
+            }
+            CandidatesExpr::Difference(left, right) => {
+                let candidates = left.evaluate_with(rtxn, index, ctx, wdcache)?;
+                if candidates.is_empty() {
+                    return Ok(candidates);
+                }
+                Ok(candidates - right.evaluate_with(rtxn, index, ctx, wdcache)?)
+            }
+        }
+    }
+}
+
+/// Returns the documents that have the value in the faceted field, the
+/// string values are matched like the filters do, ignoring the case.
+fn facet_value_docids(
+    rtxn: &heed::RoTxn,
+    index: &Index,
+    field: &str,
+    value: &str,
+) -> Result<RoaringBitmap> {
+    let faceted_fields = index.faceted_fields(rtxn)?;
+    if !faceted_fields.contains(field) {
+        return Err(UserError::InvalidCandidatesFacetAttribute {
+            field: field.to_string(),
+            valid_fields: faceted_fields,
+        })?;
+    }
+
+    let field_id = match index.fields_ids_map(rtxn)?.id(field) {
+        Some(field_id) => field_id,
+        None => return Ok(RoaringBitmap::new()),
+    };
+
+    let normalized = value.trim().to_lowercase();
+    let normalized = facet_string_key(&normalized);
+    let mut docids =
+        match index.facet_id_string_docids.get(rtxn, &(field_id, normalized.as_ref()))? {
+            Some((_original, docids)) => docids,
+            None => RoaringBitmap::new(),
+        };
+
+    if let Ok(number) = value.trim().parse::<f64>() {
+        if let Some(number_docids) =
+            index.facet_id_f64_docids.get(rtxn, &(field_id, 0, number, number))?
+        {
+            docids |= number_docids;
+        }
+    }
+
+    Ok(docids)
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::update::{IndexDocuments, Settings};
+
+    #[test]
+    fn combine_candidates() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("genre"), S("year") });
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": 0, "title": "hello world", "genre": "Drama", "year": 2000 },
+            { "id": 1, "title": "hello kevin", "genre": "Comedy", "year": 2010 },
+            { "id": 2, "title": "world of warcraft", "genre": "drama", "year": 2010 },
+            { "id": 3, "title": "worlds apart", "genre": "Horror", "year": 2020 }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |expr: CandidatesExpr| -> Vec<u32> {
+            expr.evaluate(&rtxn, &index).unwrap().into_iter().collect()
+        };
+
+        assert_eq!(evaluate(CandidatesExpr::word("world")), vec![0, 2]);
+        let prefix = CandidatesExpr::Word { word: S("world"), prefix: true, typos: 0 };
+        assert_eq!(evaluate(prefix), vec![0, 2, 3]);
+        let typo = CandidatesExpr::Word { word: S("wordl"), prefix: false, typos: 1 };
+        assert_eq!(evaluate(typo), vec![0, 2]);
+        assert_eq!(evaluate(CandidatesExpr::Phrase(vec![S("hello"), S("world")])), vec![0]);
+        assert_eq!(evaluate(CandidatesExpr::facet_value("genre", "DRAMA")), vec![0, 2]);
+        assert_eq!(evaluate(CandidatesExpr::facet_value("year", "2010")), vec![1, 2]);
+
+        let expr = CandidatesExpr::And(vec![
+            CandidatesExpr::All,
+            CandidatesExpr::word("world"),
+            CandidatesExpr::facet_value("year", "2010"),
+        ]);
+        assert_eq!(evaluate(expr), vec![2]);
+
+        let filter = FilterCondition::from_str(&rtxn, &index, "year > 2005").unwrap();
+        let expr =
+            CandidatesExpr::Or(vec![CandidatesExpr::Filter(filter), CandidatesExpr::word("hello")])
+                .difference(CandidatesExpr::facet_value("genre", "comedy"));
+        assert_eq!(evaluate(expr), vec![0, 2, 3]);
+
+        assert_eq!(evaluate(CandidatesExpr::And(Vec::new())), vec![0, 1, 2, 3]);
+        let error = CandidatesExpr::facet_value("title", "hello").evaluate(&rtxn, &index);
+        assert!(error.is_err());
+    }
+}
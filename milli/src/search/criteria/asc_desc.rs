@@ -5,7 +5,7 @@ use log::debug;
 use ordered_float::OrderedFloat;
 use roaring::RoaringBitmap;
 
-use super::{Criterion, CriterionParameters, CriterionResult};
+use super::{BucketsDetails, Criterion, CriterionParameters, CriterionResult};
 use crate::search::criteria::{resolve_query_tree, CriteriaBuilder};
 use crate::search::facet::{FacetNumberIter, FacetStringIter};
 use crate::search::query_tree::Operation;
@@ -26,6 +26,7 @@ pub struct AscDesc<'t> {
     allowed_candidates: RoaringBitmap,
     bucket_candidates: RoaringBitmap,
     faceted_candidates: RoaringBitmap,
+    buckets_details: BucketsDetails,
     parent: Box<dyn Criterion + 't>,
 }
 
@@ -65,6 +66,8 @@ impl<'t> AscDesc<'t> {
             }
             None => RoaringBitmap::default(),
         };
+        let order = if is_ascending { "asc" } else { "desc" };
+        let buckets_details = BucketsDetails::new(format!("{}:{}", field_name, order));
 
         Ok(AscDesc {
             index,
@@ -77,6 +80,7 @@ impl<'t> AscDesc<'t> {
             allowed_candidates: RoaringBitmap::new(),
             faceted_candidates,
             bucket_candidates: RoaringBitmap::new(),
+            buckets_details,
             parent,
         })
     }
@@ -102,6 +106,7 @@ impl<'t> Criterion for AscDesc<'t> {
                         candidates: Some(take(&mut self.allowed_candidates)),
                         filtered_candidates: None,
                         bucket_candidates: Some(take(&mut self.bucket_candidates)),
+                        ranking_details: self.buckets_details.next_bucket(),
                     }));
                 }
                 None => match self.parent.next(params)? {
@@ -110,7 +115,9 @@ impl<'t> Criterion for AscDesc<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        ranking_details,
                     }) => {
+                        self.buckets_details.parent_bucket(ranking_details);
                         self.query_tree = query_tree;
                         let mut candidates = match (&self.query_tree, candidates) {
                             (_, Some(candidates)) => candidates,
@@ -156,6 +163,7 @@ impl<'t> Criterion for AscDesc<'t> {
                         candidates: Some(candidates),
                         filtered_candidates: None,
                         bucket_candidates: Some(take(&mut self.bucket_candidates)),
+                        ranking_details: self.buckets_details.next_bucket(),
                     }));
                 }
             }
@@ -220,8 +228,14 @@ fn iterative_facet_number_ordered_iter<'t>(
     for docid in candidates.iter() {
         let left = (field_id, docid, f64::MIN);
         let right = (field_id, docid, f64::MAX);
-        let mut iter = index.field_id_docid_facet_f64s.range(rtxn, &(left..=right))?;
-        let entry = if is_ascending { iter.next() } else { iter.last() };
+        // We directly fetch the highest value of the document when sorting in
+        // descending order instead of iterating over all of its values.
+        let db = index.field_id_docid_facet_f64s;
+        let entry = if is_ascending {
+            db.range(rtxn, &(left..=right))?.next()
+        } else {
+            db.rev_range(rtxn, &(left..=right))?.next()
+        };
         if let Some(((_, _, value), ())) = entry.transpose()? {
             docids_values.push((docid, OrderedFloat(value)));
         }
@@ -262,8 +276,12 @@ fn iterative_facet_string_ordered_iter<'t>(
         let right = (field_id, docid.saturating_add(1), "");
         // FIXME Doing this means that it will never be possible to retrieve
         //       the document with id 2^32, not sure this is a real problem.
-        let mut iter = index.field_id_docid_facet_strings.range(rtxn, &(left..right))?;
-        let entry = if is_ascending { iter.next() } else { iter.last() };
+        let db = index.field_id_docid_facet_strings;
+        let entry = if is_ascending {
+            db.range(rtxn, &(left..right))?.next()
+        } else {
+            db.rev_range(rtxn, &(left..right))?.next()
+        };
         if let Some(((_, _, value), _)) = entry.transpose()? {
             docids_values.push((docid, value));
         }
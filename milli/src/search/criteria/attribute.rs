@@ -1,16 +1,19 @@
 use std::cmp::{self, Ordering};
 use std::collections::binary_heap::PeekMut;
 use std::collections::{btree_map, BTreeMap, BinaryHeap, HashMap};
+use std::convert::TryFrom;
 use std::iter::Peekable;
 use std::mem::take;
 
 use roaring::RoaringBitmap;
 
-use super::{resolve_query_tree, Context, Criterion, CriterionParameters, CriterionResult};
+use super::{
+    resolve_query_tree, BucketsDetails, Context, Criterion, CriterionParameters, CriterionResult,
+};
 use crate::search::criteria::Query;
 use crate::search::query_tree::{Operation, QueryKind};
-use crate::search::{build_dfa, word_derivations, WordDerivationsCache};
-use crate::Result;
+use crate::search::{build_dfa_with_distance, word_derivations, WordDerivationsCache};
+use crate::{Result, TypoDistance};
 
 /// To be able to divide integers by the number of words in the query
 /// we want to find a multiplier that allow us to divide by any number between 1 and 10.
@@ -27,6 +30,7 @@ pub struct Attribute<'t> {
     ctx: &'t dyn Context<'t>,
     state: Option<(Operation, FlattenedQueryTree, RoaringBitmap)>,
     bucket_candidates: RoaringBitmap,
+    buckets_details: BucketsDetails,
     parent: Box<dyn Criterion + 't>,
     linear_buckets: Option<btree_map::IntoIter<u64, RoaringBitmap>>,
     set_buckets: Option<BinaryHeap<Branch<'t>>>,
@@ -38,6 +42,7 @@ impl<'t> Attribute<'t> {
             ctx,
             state: None,
             bucket_candidates: RoaringBitmap::new(),
+            buckets_details: BucketsDetails::new("attribute"),
             parent,
             linear_buckets: None,
             set_buckets: None,
@@ -61,6 +66,7 @@ impl<'t> Criterion for Attribute<'t> {
                         candidates: Some(RoaringBitmap::new()),
                         filtered_candidates: None,
                         bucket_candidates: Some(take(&mut self.bucket_candidates)),
+                        ranking_details: self.buckets_details.next_bucket(),
                     }));
                 }
                 Some((query_tree, flattened_query_tree, mut allowed_candidates)) => {
@@ -85,6 +91,7 @@ impl<'t> Criterion for Attribute<'t> {
                                     candidates: Some(RoaringBitmap::new()),
                                     filtered_candidates: None,
                                     bucket_candidates: Some(take(&mut self.bucket_candidates)),
+                                    ranking_details: self.buckets_details.next_bucket(),
                                 }));
                             }
                         }
@@ -105,11 +112,19 @@ impl<'t> Criterion for Attribute<'t> {
                         match set_compute_candidates(&mut set_buckets, &allowed_candidates)? {
                             Some((_score, candidates)) => candidates,
                             None => {
+                                // When the number of ranked attributes is limited, the remaining
+                                // candidates only match the query in the attributes that aren't
+                                // ranked, they form the last bucket.
+                                let candidates = match self.ctx.max_attributes_to_rank() {
+                                    Some(_) => allowed_candidates,
+                                    None => RoaringBitmap::new(),
+                                };
                                 return Ok(Some(CriterionResult {
                                     query_tree: Some(query_tree),
-                                    candidates: Some(RoaringBitmap::new()),
+                                    candidates: Some(candidates),
                                     filtered_candidates: None,
                                     bucket_candidates: Some(take(&mut self.bucket_candidates)),
+                                    ranking_details: self.buckets_details.next_bucket(),
                                 }));
                             }
                         }
@@ -125,6 +140,7 @@ impl<'t> Criterion for Attribute<'t> {
                         candidates: Some(found_candidates),
                         filtered_candidates: None,
                         bucket_candidates: Some(take(&mut self.bucket_candidates)),
+                        ranking_details: self.buckets_details.next_bucket(),
                     }));
                 }
                 None => match self.parent.next(params)? {
@@ -133,7 +149,9 @@ impl<'t> Criterion for Attribute<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        ranking_details,
                     }) => {
+                        self.buckets_details.parent_bucket(ranking_details);
                         let mut candidates = match candidates {
                             Some(candidates) => candidates,
                             None => {
@@ -161,12 +179,15 @@ impl<'t> Criterion for Attribute<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        ranking_details,
                     }) => {
+                        self.buckets_details.parent_bucket(ranking_details);
                         return Ok(Some(CriterionResult {
                             query_tree: None,
                             candidates,
                             filtered_candidates,
                             bucket_candidates,
+                            ranking_details: self.buckets_details.next_bucket(),
                         }));
                     }
                     None => return Ok(None),
@@ -196,12 +217,12 @@ impl<'t> QueryPositionIterator<'t> {
                 QueryKind::Exact { word, .. } => {
                     if !query.prefix || in_prefix_cache {
                         let word = query.kind.word();
-                        let iter = ctx.word_position_iterator(word, in_prefix_cache)?;
+                        let iter = ranked_position_iterator(ctx, word, in_prefix_cache)?;
                         inner.push(iter.peekable());
                     } else {
                         for (word, _) in word_derivations(&word, true, 0, ctx.words_fst(), wdcache)?
                         {
-                            let iter = ctx.word_position_iterator(&word, in_prefix_cache)?;
+                            let iter = ranked_position_iterator(ctx, &word, in_prefix_cache)?;
                             inner.push(iter.peekable());
                         }
                     }
@@ -210,7 +231,7 @@ impl<'t> QueryPositionIterator<'t> {
                     for (word, _) in
                         word_derivations(&word, query.prefix, *typo, ctx.words_fst(), wdcache)?
                     {
-                        let iter = ctx.word_position_iterator(&word, in_prefix_cache)?;
+                        let iter = ranked_position_iterator(ctx, &word, in_prefix_cache)?;
                         inner.push(iter.peekable());
                     }
                 }
@@ -458,6 +479,7 @@ fn initialize_linear_buckets(
     fn compute_candidate_rank(
         branches: &FlattenedQueryTree,
         words_positions: HashMap<String, RoaringBitmap>,
+        typo_distance: TypoDistance,
     ) -> u64 {
         let mut min_rank = u64::max_value();
         for branch in branches {
@@ -465,12 +487,12 @@ fn initialize_linear_buckets(
             let mut branch_rank = Vec::with_capacity(branch_len);
             for derivates in branch {
                 let mut position = None;
-                for Query { prefix, kind } in derivates {
+                for Query { prefix, kind, .. } in derivates {
                     // find the best position of the current word in the document.
                     let current_position = match kind {
                         QueryKind::Exact { word, .. } => {
                             if *prefix {
-                                word_derivations(word, true, 0, &words_positions)
+                                word_derivations(word, true, 0, typo_distance, &words_positions)
                                     .flat_map(|positions| positions.iter().next())
                                     .min()
                             } else {
@@ -481,7 +503,7 @@ fn initialize_linear_buckets(
                             }
                         }
                         QueryKind::Tolerant { typo, word } => {
-                            word_derivations(word, *prefix, *typo, &words_positions)
+                            word_derivations(word, *prefix, *typo, typo_distance, &words_positions)
                                 .flat_map(|positions| positions.iter().next())
                                 .min()
                         }
@@ -523,9 +545,10 @@ fn initialize_linear_buckets(
         word: &str,
         is_prefix: bool,
         max_typo: u8,
+        typo_distance: TypoDistance,
         words_positions: &'a HashMap<String, RoaringBitmap>,
     ) -> impl Iterator<Item = &'a RoaringBitmap> {
-        let dfa = build_dfa(word, max_typo, is_prefix);
+        let dfa = build_dfa_with_distance(word, max_typo, is_prefix, typo_distance);
         words_positions.iter().filter_map(move |(document_word, positions)| {
             use levenshtein_automata::Distance;
             match dfa.eval(document_word) {
@@ -535,19 +558,52 @@ fn initialize_linear_buckets(
         })
     }
 
+    let positions_limit = ranked_positions_limit(ctx);
     let mut candidates = BTreeMap::new();
     for docid in allowed_candidates {
-        let words_positions = ctx.docid_words_positions(docid)?;
-        let rank = compute_candidate_rank(branches, words_positions);
+        let mut words_positions = ctx.docid_words_positions(docid)?;
+        if let Some(limit) = positions_limit {
+            for positions in words_positions.values_mut() {
+                *positions = positions.iter().take_while(|pos| *pos < limit).collect();
+            }
+        }
+        let rank = compute_candidate_rank(branches, words_positions, ctx.typo_distance());
         candidates.entry(rank).or_insert_with(RoaringBitmap::new).insert(docid);
     }
 
     Ok(candidates)
 }
 
+/// Returns the first position that is in an attribute that isn't ranked, the positions
+/// are ordered by the ranking order of the attributes.
+fn ranked_positions_limit(ctx: &dyn Context) -> Option<u32> {
+    ctx.max_attributes_to_rank().map(|max| {
+        let max = u32::try_from(max).unwrap_or(u32::max_value());
+        max.saturating_mul(ctx.position_stride())
+    })
+}
+
+/// Returns the positions of the word in the ranked attributes only, the iteration
+/// stops at the first position of the attributes that aren't ranked.
+fn ranked_position_iterator<'t>(
+    ctx: &'t dyn Context<'t>,
+    word: &str,
+    in_prefix_cache: bool,
+) -> heed::Result<Box<dyn Iterator<Item = heed::Result<((&'t str, u32), RoaringBitmap)>> + 't>> {
+    let iter = ctx.word_position_iterator(word, in_prefix_cache)?;
+    match ranked_positions_limit(ctx) {
+        Some(limit) => {
+            Ok(Box::new(iter.take_while(move |result| {
+                result.as_ref().map_or(true, |((_, pos), _)| *pos < limit)
+            })))
+        }
+        None => Ok(iter),
+    }
+}
+
 // TODO can we keep refs of Query
 fn flatten_query_tree(query_tree: &Operation) -> FlattenedQueryTree {
-    use crate::search::criteria::Operation::{And, Or, Phrase};
+    use crate::search::criteria::Operation::{And, Or, Phrase, Split};
 
     fn and_recurse(head: &Operation, tail: &[Operation]) -> FlattenedQueryTree {
         match tail.split_first() {
@@ -577,10 +633,16 @@ fn flatten_query_tree(query_tree: &Operation) -> FlattenedQueryTree {
                     ops.iter().map(recurse).flatten().collect()
                 }
             }
-            Phrase(words) => {
+            Phrase(words) | Split(words) => {
                 let queries = words
                     .iter()
-                    .map(|word| vec![Query { prefix: false, kind: QueryKind::exact(word.clone()) }])
+                    .map(|word| {
+                        vec![Query {
+                            prefix: false,
+                            kind: QueryKind::exact(word.clone()),
+                            field: None,
+                        }]
+                    })
                     .collect();
                 vec![queries]
             }
@@ -603,28 +665,47 @@ mod tests {
         let query_tree = Operation::Or(
             false,
             vec![
-                Operation::Query(Query { prefix: false, kind: QueryKind::exact(S("manythefish")) }),
+                Operation::Query(Query {
+                    prefix: false,
+                    kind: QueryKind::exact(S("manythefish")),
+                    field: None,
+                }),
                 Operation::And(vec![
-                    Operation::Query(Query { prefix: false, kind: QueryKind::exact(S("manythe")) }),
-                    Operation::Query(Query { prefix: false, kind: QueryKind::exact(S("fish")) }),
+                    Operation::Query(Query {
+                        prefix: false,
+                        kind: QueryKind::exact(S("manythe")),
+                        field: None,
+                    }),
+                    Operation::Query(Query {
+                        prefix: false,
+                        kind: QueryKind::exact(S("fish")),
+                        field: None,
+                    }),
                 ]),
                 Operation::And(vec![
-                    Operation::Query(Query { prefix: false, kind: QueryKind::exact(S("many")) }),
+                    Operation::Query(Query {
+                        prefix: false,
+                        kind: QueryKind::exact(S("many")),
+                        field: None,
+                    }),
                     Operation::Or(
                         false,
                         vec![
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::exact(S("thefish")),
+                                field: None,
                             }),
                             Operation::And(vec![
                                 Operation::Query(Query {
                                     prefix: false,
                                     kind: QueryKind::exact(S("the")),
+                                    field: None,
                                 }),
                                 Operation::Query(Query {
                                     prefix: false,
                                     kind: QueryKind::exact(S("fish")),
+                                    field: None,
                                 }),
                             ]),
                         ],
@@ -634,19 +715,23 @@ mod tests {
         );
 
         let expected = vec![
-            vec![vec![Query { prefix: false, kind: QueryKind::exact(S("manythefish")) }]],
+            vec![vec![Query {
+                prefix: false,
+                kind: QueryKind::exact(S("manythefish")),
+                field: None,
+            }]],
             vec![
-                vec![Query { prefix: false, kind: QueryKind::exact(S("manythe")) }],
-                vec![Query { prefix: false, kind: QueryKind::exact(S("fish")) }],
+                vec![Query { prefix: false, kind: QueryKind::exact(S("manythe")), field: None }],
+                vec![Query { prefix: false, kind: QueryKind::exact(S("fish")), field: None }],
             ],
             vec![
-                vec![Query { prefix: false, kind: QueryKind::exact(S("many")) }],
-                vec![Query { prefix: false, kind: QueryKind::exact(S("thefish")) }],
+                vec![Query { prefix: false, kind: QueryKind::exact(S("many")), field: None }],
+                vec![Query { prefix: false, kind: QueryKind::exact(S("thefish")), field: None }],
             ],
             vec![
-                vec![Query { prefix: false, kind: QueryKind::exact(S("many")) }],
-                vec![Query { prefix: false, kind: QueryKind::exact(S("the")) }],
-                vec![Query { prefix: false, kind: QueryKind::exact(S("fish")) }],
+                vec![Query { prefix: false, kind: QueryKind::exact(S("many")), field: None }],
+                vec![Query { prefix: false, kind: QueryKind::exact(S("the")), field: None }],
+                vec![Query { prefix: false, kind: QueryKind::exact(S("fish")), field: None }],
             ],
         ];
 
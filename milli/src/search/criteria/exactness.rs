@@ -7,7 +7,7 @@ use log::debug;
 use roaring::RoaringBitmap;
 
 use crate::search::criteria::{
-    resolve_query_tree, Context, Criterion, CriterionParameters, CriterionResult,
+    resolve_query_tree, BucketsDetails, Context, Criterion, CriterionParameters, CriterionResult,
 };
 use crate::search::query_tree::{Operation, PrimitiveQueryPart};
 use crate::Result;
@@ -17,6 +17,7 @@ pub struct Exactness<'t> {
     query_tree: Option<Operation>,
     state: Option<State>,
     bucket_candidates: RoaringBitmap,
+    buckets_details: BucketsDetails,
     parent: Box<dyn Criterion + 't>,
     query: Vec<ExactQueryPart>,
 }
@@ -37,6 +38,7 @@ impl<'t> Exactness<'t> {
             query_tree: None,
             state: None,
             bucket_candidates: RoaringBitmap::new(),
+            buckets_details: BucketsDetails::new("exactness"),
             parent,
             query,
         })
@@ -69,6 +71,7 @@ impl<'t> Criterion for Exactness<'t> {
                         candidates: Some(candidates),
                         filtered_candidates: None,
                         bucket_candidates: Some(take(&mut self.bucket_candidates)),
+                        ranking_details: self.buckets_details.next_bucket(),
                     }));
                 }
                 None => match self.parent.next(params)? {
@@ -77,7 +80,9 @@ impl<'t> Criterion for Exactness<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        ranking_details,
                     }) => {
+                        self.buckets_details.parent_bucket(ranking_details);
                         let mut candidates = match candidates {
                             Some(candidates) => candidates,
                             None => {
@@ -103,12 +108,15 @@ impl<'t> Criterion for Exactness<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        ranking_details,
                     }) => {
+                        self.buckets_details.parent_bucket(ranking_details);
                         return Ok(Some(CriterionResult {
                             query_tree: None,
                             candidates,
                             filtered_candidates,
                             bucket_candidates,
+                            ranking_details: self.buckets_details.next_bucket(),
                         }));
                     }
                     None => return Ok(None),
@@ -120,6 +128,8 @@ impl<'t> Criterion for Exactness<'t> {
 
 #[derive(Debug)]
 enum State {
+    /// Extract the documents that have an exact attribute whose whole value is the query.
+    ExactValue(RoaringBitmap),
     /// Extract the documents that have an attribute that contains exactly the query.
     ExactAttribute(RoaringBitmap),
     /// Extract the documents that have an attribute that starts with exactly the query.
@@ -131,12 +141,13 @@ enum State {
 
 impl State {
     fn new(candidates: RoaringBitmap) -> Self {
-        Self::ExactAttribute(candidates)
+        Self::ExactValue(candidates)
     }
 
     fn difference_with(&mut self, lhs: &RoaringBitmap) {
         match self {
-            Self::ExactAttribute(candidates)
+            Self::ExactValue(candidates)
+            | Self::ExactAttribute(candidates)
             | Self::AttributeStartsWith(candidates)
             | Self::ExactWords(candidates) => *candidates -= lhs,
             Self::Remainings(candidates_array) => {
@@ -148,7 +159,8 @@ impl State {
 
     fn is_empty(&self) -> bool {
         match self {
-            Self::ExactAttribute(candidates)
+            Self::ExactValue(candidates)
+            | Self::ExactAttribute(candidates)
             | Self::AttributeStartsWith(candidates)
             | Self::ExactWords(candidates) => candidates.is_empty(),
             Self::Remainings(candidates_array) => {
@@ -172,6 +184,27 @@ fn resolve_state(
 ) -> Result<(RoaringBitmap, Option<State>)> {
     use State::*;
     match state {
+        ExactValue(mut allowed_candidates) => {
+            let value = query
+                .iter()
+                .flat_map(|part| match part {
+                    ExactQueryPart::Word { word, .. } => std::slice::from_ref(word),
+                    ExactQueryPart::Phrase(phrase) => phrase.as_slice(),
+                })
+                .join(" ");
+
+            let mut candidates = ctx.exact_value_docids(&value)?;
+            // only keep allowed candidates
+            candidates &= &allowed_candidates;
+            if candidates.is_empty() {
+                // don't return an empty bucket when there is no exact attribute to match.
+                return resolve_state(ctx, ExactAttribute(allowed_candidates), query);
+            }
+            // remove current candidates from allowed candidates
+            allowed_candidates -= &candidates;
+
+            Ok((candidates, Some(ExactAttribute(allowed_candidates))))
+        }
         ExactAttribute(mut allowed_candidates) => {
             let mut candidates = RoaringBitmap::new();
             if let Ok(query_len) = u8::try_from(query.len()) {
@@ -218,7 +251,12 @@ fn resolve_state(
                 let mut candidates = RoaringBitmap::new();
                 use ExactQueryPart::*;
                 match part {
-                    Synonyms(synonyms) => {
+                    Word { exact, synonyms, .. } => {
+                        // the word itself must appear exactly as it is written in the query,
+                        // the synonyms are normalized by the analyzer and are considered exact.
+                        if let Some(exact_candidates) = ctx.exact_word_docids(exact)? {
+                            candidates |= exact_candidates;
+                        }
                         for synonym in synonyms {
                             if let Some(synonym_candidates) = ctx.word_docids(synonym)? {
                                 candidates |= synonym_candidates;
@@ -295,13 +333,13 @@ fn attribute_start_with_docids(
 ) -> heed::Result<Vec<RoaringBitmap>> {
     let mut attribute_candidates_array = Vec::new();
     // start from attribute first position
-    let mut pos = attribute_id * 1000;
+    let mut pos = attribute_id * ctx.position_stride();
     for part in query {
         use ExactQueryPart::*;
         match part {
-            Synonyms(synonyms) => {
+            Word { word, synonyms, .. } => {
                 let mut synonyms_candidates = RoaringBitmap::new();
-                for word in synonyms {
+                for word in synonyms.iter().chain(Some(word)) {
                     let wc = ctx.word_position_docids(word, pos)?;
                     if let Some(word_candidates) = wc {
                         synonyms_candidates |= word_candidates;
@@ -337,7 +375,12 @@ fn intersection_of(mut rbs: Vec<&RoaringBitmap>) -> RoaringBitmap {
 #[derive(Debug, Clone)]
 pub enum ExactQueryPart {
     Phrase(Vec<String>),
-    Synonyms(Vec<String>),
+    /// A normalized word with its exact version and its one word synonyms.
+    Word {
+        word: String,
+        exact: String,
+        synonyms: Vec<String>,
+    },
 }
 
 impl ExactQueryPart {
@@ -346,24 +389,21 @@ impl ExactQueryPart {
         part: &PrimitiveQueryPart,
     ) -> heed::Result<Self> {
         let part = match part {
-            PrimitiveQueryPart::Word(word, _) => {
-                match ctx.synonyms(word)? {
-                    Some(synonyms) => {
-                        let mut synonyms: Vec<_> = synonyms
-                            .into_iter()
-                            .filter_map(|mut array| {
-                                // keep 1 word synonyms only.
-                                match array.pop() {
-                                    Some(word) if array.is_empty() => Some(word),
-                                    _ => None,
-                                }
-                            })
-                            .collect();
-                        synonyms.push(word.clone());
-                        ExactQueryPart::Synonyms(synonyms)
-                    }
-                    None => ExactQueryPart::Synonyms(vec![word.clone()]),
-                }
+            PrimitiveQueryPart::Word(word, _, exact, _) => {
+                let synonyms = match ctx.synonyms(word)? {
+                    Some(synonyms) => synonyms
+                        .into_iter()
+                        .filter_map(|mut array| {
+                            // keep 1 word synonyms only.
+                            match array.pop() {
+                                Some(word) if array.is_empty() => Some(word),
+                                _ => None,
+                            }
+                        })
+                        .collect(),
+                    None => Vec::new(),
+                };
+                ExactQueryPart::Word { word: word.clone(), exact: exact.clone(), synonyms }
             }
             PrimitiveQueryPart::Phrase(phrase) => ExactQueryPart::Phrase(phrase.clone()),
         };
@@ -371,3 +411,40 @@ impl ExactQueryPart {
         Ok(part)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+
+    use crate::index::tests::TempIndex;
+    use crate::update::{IndexDocuments, Settings};
+    use crate::Search;
+
+    #[test]
+    fn exact_words_are_not_normalized() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_criteria(vec![S("words"), S("exactness")]);
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": 0, "title": "the best cafe" },
+            { "id": 1, "title": "the best Café" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.word_docids.get(&rtxn, "cafe").unwrap().unwrap().len(), 2);
+        assert_eq!(index.exact_word_docids.get(&rtxn, "cafe").unwrap().unwrap().len(), 1);
+        assert_eq!(index.exact_word_docids.get(&rtxn, "café").unwrap().unwrap().len(), 1);
+
+        let mut search = Search::new(&rtxn, &index);
+        search.query("café");
+        assert_eq!(search.execute().unwrap().documents_ids, vec![1, 0]);
+
+        let mut search = Search::new(&rtxn, &index);
+        search.query("CAFE");
+        assert_eq!(search.execute().unwrap().documents_ids, vec![0, 1]);
+    }
+}
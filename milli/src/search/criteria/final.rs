@@ -1,7 +1,9 @@
 use log::debug;
 use roaring::RoaringBitmap;
 
-use super::{resolve_query_tree, Context, Criterion, CriterionParameters, CriterionResult};
+use super::{
+    resolve_query_tree, Context, Criterion, CriterionParameters, CriterionResult, RankingRuleDetail,
+};
 use crate::search::query_tree::Operation;
 use crate::search::WordDerivationsCache;
 use crate::Result;
@@ -15,6 +17,8 @@ pub struct FinalResult {
     pub candidates: RoaringBitmap,
     /// Candidates that comes from the current bucket of the initial criterion.
     pub bucket_candidates: RoaringBitmap,
+    /// The buckets every criterion placed the candidates in.
+    pub ranking_details: Vec<RankingRuleDetail>,
 }
 
 pub struct Final<'t> {
@@ -29,7 +33,8 @@ impl<'t> Final<'t> {
         Final {
             ctx,
             parent,
-            wdcache: WordDerivationsCache::new(),
+            wdcache: WordDerivationsCache::with_first_letter_typo(ctx.first_letter_typo())
+                .with_typo_distance(ctx.typo_distance()),
             returned_candidates: RoaringBitmap::new(),
         }
     }
@@ -50,6 +55,7 @@ impl<'t> Final<'t> {
                 candidates,
                 filtered_candidates,
                 bucket_candidates,
+                ranking_details,
             }) => {
                 let mut candidates = match (candidates, query_tree.as_ref()) {
                     (Some(candidates), _) => candidates,
@@ -67,7 +73,7 @@ impl<'t> Final<'t> {
 
                 self.returned_candidates |= &candidates;
 
-                Ok(Some(FinalResult { query_tree, candidates, bucket_candidates }))
+                Ok(Some(FinalResult { query_tree, candidates, bucket_candidates, ranking_details }))
             }
             None => Ok(None),
         }
@@ -3,7 +3,7 @@ use std::iter;
 use roaring::RoaringBitmap;
 use rstar::RTree;
 
-use super::{Criterion, CriterionParameters, CriterionResult};
+use super::{BucketsDetails, Criterion, CriterionParameters, CriterionResult};
 use crate::search::criteria::{resolve_query_tree, CriteriaBuilder};
 use crate::{GeoPoint, Index, Result};
 
@@ -15,6 +15,7 @@ pub struct Geo<'t> {
     candidates: Box<dyn Iterator<Item = RoaringBitmap>>,
     allowed_candidates: RoaringBitmap,
     bucket_candidates: RoaringBitmap,
+    buckets_details: BucketsDetails,
     rtree: Option<RTree<GeoPoint>>,
     point: [f64; 2],
 }
@@ -49,6 +50,9 @@ impl<'t> Geo<'t> {
         let allowed_candidates = index.geo_faceted_documents_ids(rtxn)?;
         let bucket_candidates = RoaringBitmap::new();
         let rtree = index.geo_rtree(rtxn)?;
+        let order = if ascending { "asc" } else { "desc" };
+        let ranking_rule = format!("_geoPoint({}, {}):{}", point[0], point[1], order);
+        let buckets_details = BucketsDetails::new(ranking_rule);
 
         Ok(Self {
             index,
@@ -58,6 +62,7 @@ impl<'t> Geo<'t> {
             candidates,
             allowed_candidates,
             bucket_candidates,
+            buckets_details,
             rtree,
             point,
         })
@@ -78,6 +83,7 @@ impl Criterion for Geo<'_> {
                         candidates: Some(candidates),
                         filtered_candidates: None,
                         bucket_candidates: Some(self.bucket_candidates.clone()),
+                        ranking_details: self.buckets_details.next_bucket(),
                     }));
                 }
                 None => match self.parent.next(params)? {
@@ -86,7 +92,9 @@ impl Criterion for Geo<'_> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        ranking_details,
                     }) => {
+                        self.buckets_details.parent_bucket(ranking_details);
                         let mut candidates = match (&query_tree, candidates) {
                             (_, Some(candidates)) => candidates,
                             (Some(qt), None) => {
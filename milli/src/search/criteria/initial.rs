@@ -18,6 +18,7 @@ impl Initial {
             candidates: None,
             filtered_candidates,
             bucket_candidates: None,
+            ranking_details: Vec::new(),
         };
         Initial { answer: Some(answer) }
     }
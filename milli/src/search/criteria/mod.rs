@@ -2,6 +2,7 @@ use std::borrow::Cow;
 use std::collections::HashMap;
 
 use roaring::RoaringBitmap;
+use serde::{Deserialize, Serialize};
 
 use self::asc_desc::AscDesc;
 use self::attribute::Attribute;
@@ -12,9 +13,13 @@ use self::r#final::Final;
 use self::typo::Typo;
 use self::words::Words;
 use super::query_tree::{Operation, PrimitiveQueryPart, Query, QueryKind};
+use crate::proximity::extract_position;
 use crate::search::criteria::geo::Geo;
-use crate::search::{word_derivations, WordDerivationsCache};
-use crate::{AscDesc as AscDescName, DocumentId, FieldId, Index, Member, Result};
+use crate::search::{word_derivations, SearchSession, WordDerivationsCache};
+use crate::{
+    AscDesc as AscDescName, DocumentId, FieldId, FirstLetterTypo, Index, Member, Result,
+    RoaringBitmapLenCodec, TypoDistance,
+};
 
 mod asc_desc;
 mod attribute;
@@ -42,6 +47,49 @@ pub struct CriterionResult {
     filtered_candidates: Option<RoaringBitmap>,
     /// Candidates that comes from the current bucket of the initial criterion.
     bucket_candidates: Option<RoaringBitmap>,
+    /// The buckets the criteria, up to this one, placed these candidates in.
+    ranking_details: Vec<RankingRuleDetail>,
+}
+
+/// The bucket a ranking rule placed a document in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RankingRuleDetail {
+    /// The name of the ranking rule, e.g. `typo` or `price:asc`.
+    pub ranking_rule: String,
+    /// The rank of the bucket among the buckets the ranking rule split the bucket
+    /// of its parent into, the most relevant documents are in the bucket zero.
+    pub bucket: u32,
+}
+
+/// Keeps track of the buckets a criterion returns for the current bucket of its parent.
+#[derive(Debug, Clone, PartialEq)]
+struct BucketsDetails {
+    ranking_rule: String,
+    parent: Vec<RankingRuleDetail>,
+    bucket: u32,
+}
+
+impl BucketsDetails {
+    fn new(ranking_rule: impl Into<String>) -> BucketsDetails {
+        BucketsDetails { ranking_rule: ranking_rule.into(), parent: Vec::new(), bucket: 0 }
+    }
+
+    /// Starts splitting a new bucket of the parent criterion.
+    fn parent_bucket(&mut self, parent: Vec<RankingRuleDetail>) {
+        self.parent = parent;
+        self.bucket = 0;
+    }
+
+    /// Returns the details of the next bucket returned by the criterion.
+    fn next_bucket(&mut self) -> Vec<RankingRuleDetail> {
+        let mut details = self.parent.clone();
+        details.push(RankingRuleDetail {
+            ranking_rule: self.ranking_rule.clone(),
+            bucket: self.bucket,
+        });
+        self.bucket += 1;
+        details
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -68,6 +116,8 @@ impl Default for Candidates {
 pub trait Context<'c> {
     fn documents_ids(&self) -> heed::Result<RoaringBitmap>;
     fn word_docids(&self, word: &str) -> heed::Result<Option<RoaringBitmap>>;
+    fn word_documents_count(&self, word: &str) -> heed::Result<Option<u64>>;
+    fn exact_word_docids(&self, word: &str) -> heed::Result<Option<RoaringBitmap>>;
     fn word_prefix_docids(&self, word: &str) -> heed::Result<Option<RoaringBitmap>>;
     fn word_pair_proximity_docids(
         &self,
@@ -100,6 +150,47 @@ pub trait Context<'c> {
         word_count: u8,
     ) -> heed::Result<Option<RoaringBitmap>>;
     fn word_position_docids(&self, word: &str, pos: u32) -> heed::Result<Option<RoaringBitmap>>;
+    /// Returns the documents that contain the word in the given field.
+    fn field_word_docids(&self, word: &str, field_id: FieldId) -> heed::Result<RoaringBitmap>;
+    fn position_stride(&self) -> u32;
+    /// Returns the number of attributes, in their ranking order, whose positions are
+    /// used by the attribute criterion.
+    fn max_attributes_to_rank(&self) -> Option<usize> {
+        None
+    }
+    /// Returns the documents ids of a query that were already resolved by the search session.
+    fn cached_query_docids(&self, _query: &Query) -> Option<RoaringBitmap> {
+        None
+    }
+    fn cache_query_docids(&self, _query: &Query, _docids: &RoaringBitmap) {}
+    /// Returns the documents ids the words documents ids can be restricted to when they are
+    /// resolved, all the other documents are removed by the filter of the search anyway.
+    fn candidates_restriction(&self) -> Option<&RoaringBitmap> {
+        None
+    }
+    /// Returns the maximum number of typos allowed in the fields that restrict them,
+    /// the derivations with more typos must not match in these fields.
+    fn fields_max_typos(&self) -> &[(FieldId, u8)] {
+        &[]
+    }
+    /// Returns how the typos on the first letter of the words are counted.
+    fn first_letter_typo(&self) -> FirstLetterTypo {
+        FirstLetterTypo::default()
+    }
+    /// Returns the edit distance used to count the typos of the derivations of the words.
+    fn typo_distance(&self) -> TypoDistance {
+        TypoDistance::default()
+    }
+    /// Returns the documents that have an exact attribute whose value is the given words
+    /// separated by spaces.
+    fn exact_value_docids(&self, _value: &str) -> heed::Result<RoaringBitmap> {
+        Ok(RoaringBitmap::new())
+    }
+    /// Returns whether some fields are excluded from the word pairs proximities,
+    /// the word pairs of these fields are not in the proximity databases.
+    fn has_proximity_excluded_fields(&self) -> bool {
+        false
+    }
 }
 
 pub struct CriteriaBuilder<'t> {
@@ -107,6 +198,22 @@ pub struct CriteriaBuilder<'t> {
     index: &'t Index,
     words_fst: fst::Set<Cow<'t, [u8]>>,
     words_prefixes_fst: fst::Set<Cow<'t, [u8]>>,
+    /// The searchable fields ids in their ranking order, only defined when this
+    /// order differs from the one used to compute the positions stored in the databases.
+    attributes_ranks: Option<Vec<FieldId>>,
+    one_attribute: u32,
+    session: Option<&'t SearchSession>,
+    candidates_restriction: Option<RoaringBitmap>,
+    fields_max_typos: Vec<(FieldId, u8)>,
+    first_letter_typo: FirstLetterTypo,
+    typo_distance: TypoDistance,
+    exact_attributes_ids: Vec<FieldId>,
+    /// Whether the word prefix pair proximity database is computed, the proximity is
+    /// computed from the derivations of the prefixes when it isn't.
+    prefix_pair_proximity: bool,
+    max_attributes_to_rank: Option<usize>,
+    synonyms: HashMap<Vec<String>, Vec<Vec<String>>>,
+    has_proximity_excluded_fields: bool,
 }
 
 impl<'c> Context<'c> for CriteriaBuilder<'c> {
@@ -118,6 +225,14 @@ impl<'c> Context<'c> for CriteriaBuilder<'c> {
         self.index.word_docids.get(self.rtxn, &word)
     }
 
+    fn word_documents_count(&self, word: &str) -> heed::Result<Option<u64>> {
+        self.index.word_docids.remap_data_type::<RoaringBitmapLenCodec>().get(self.rtxn, &word)
+    }
+
+    fn exact_word_docids(&self, word: &str) -> heed::Result<Option<RoaringBitmap>> {
+        self.index.exact_word_docids.get(self.rtxn, &word)
+    }
+
     fn word_prefix_docids(&self, word: &str) -> heed::Result<Option<RoaringBitmap>> {
         self.index.word_prefix_docids.get(self.rtxn, &word)
     }
@@ -138,6 +253,9 @@ impl<'c> Context<'c> for CriteriaBuilder<'c> {
         right: &str,
         proximity: u8,
     ) -> heed::Result<Option<RoaringBitmap>> {
+        if !self.prefix_pair_proximity {
+            return Ok(None);
+        }
         let key = (left, right, proximity);
         self.index.word_prefix_pair_proximity_docids.get(self.rtxn, &key)
     }
@@ -157,6 +275,10 @@ impl<'c> Context<'c> for CriteriaBuilder<'c> {
         let mut words_positions = HashMap::new();
         for result in self.index.docid_word_positions.prefix_iter(self.rtxn, &(docid, ""))? {
             let ((_, word), positions) = result?;
+            let positions = match self.attributes_ranks {
+                Some(ref ranks) => rank_positions(ranks, &positions, self.one_attribute),
+                None => positions,
+            };
             words_positions.insert(word.to_string(), positions);
         }
         Ok(words_positions)
@@ -168,23 +290,42 @@ impl<'c> Context<'c> for CriteriaBuilder<'c> {
         in_prefix_cache: bool,
     ) -> heed::Result<Box<dyn Iterator<Item = heed::Result<((&'c str, u32), RoaringBitmap)>> + 'c>>
     {
-        let range = {
-            let left = u32::min_value();
-            let right = u32::max_value();
-            let left = (word, left);
-            let right = (word, right);
-            left..=right
-        };
         let db = match in_prefix_cache {
             true => self.index.word_prefix_position_docids,
             false => self.index.word_position_docids,
         };
 
-        Ok(Box::new(db.range(self.rtxn, &range)?))
+        match self.attributes_ranks {
+            Some(ref ranks) => {
+                // The attributes have been reordered, we iterate over the positions of
+                // every attribute in the ranking order and shift them to their new rank.
+                let mut iters = Vec::with_capacity(ranks.len());
+                for (rank, field_id) in ranks.iter().enumerate() {
+                    let start = *field_id as u32 * self.one_attribute;
+                    let shift = rank as u32 * self.one_attribute;
+                    let range = (word, start)..=(word, start + self.one_attribute - 1);
+                    let iter = db.range(self.rtxn, &range)?.map(move |result| {
+                        result.map(|((word, pos), docids)| ((word, pos - start + shift), docids))
+                    });
+                    iters.push(iter);
+                }
+                Ok(Box::new(iters.into_iter().flatten()))
+            }
+            None => {
+                let range = {
+                    let left = u32::min_value();
+                    let right = u32::max_value();
+                    let left = (word, left);
+                    let right = (word, right);
+                    left..=right
+                };
+                Ok(Box::new(db.range(self.rtxn, &range)?))
+            }
+        }
     }
 
     fn synonyms(&self, word: &str) -> heed::Result<Option<Vec<Vec<String>>>> {
-        self.index.words_synonyms(self.rtxn, &[word])
+        Ok(self.synonyms.get(&[word.to_string()][..]).cloned())
     }
 
     fn searchable_fields_ids(&self) -> Result<Vec<FieldId>> {
@@ -207,13 +348,122 @@ impl<'c> Context<'c> for CriteriaBuilder<'c> {
         let key = (word, pos);
         self.index.word_position_docids.get(self.rtxn, &key)
     }
+
+    fn field_word_docids(&self, word: &str, field_id: FieldId) -> heed::Result<RoaringBitmap> {
+        // The positions of the words of a field are in the range reserved for its field id.
+        let start = field_id as u32 * self.one_attribute;
+        let range = (word, start)..=(word, start + self.one_attribute - 1);
+        let mut docids = RoaringBitmap::new();
+        for result in self.index.word_position_docids.range(self.rtxn, &range)? {
+            let (_, positions_docids) = result?;
+            docids |= positions_docids;
+        }
+        Ok(docids)
+    }
+
+    fn position_stride(&self) -> u32 {
+        self.one_attribute
+    }
+
+    fn max_attributes_to_rank(&self) -> Option<usize> {
+        self.max_attributes_to_rank
+    }
+
+    fn cached_query_docids(&self, query: &Query) -> Option<RoaringBitmap> {
+        self.session.and_then(|session| session.query_docids(query))
+    }
+
+    fn cache_query_docids(&self, query: &Query, docids: &RoaringBitmap) {
+        if let Some(session) = self.session {
+            session.insert_query_docids(query, docids);
+        }
+    }
+
+    fn candidates_restriction(&self) -> Option<&RoaringBitmap> {
+        self.candidates_restriction.as_ref()
+    }
+
+    fn fields_max_typos(&self) -> &[(FieldId, u8)] {
+        &self.fields_max_typos
+    }
+
+    fn first_letter_typo(&self) -> FirstLetterTypo {
+        self.first_letter_typo
+    }
+
+    fn typo_distance(&self) -> TypoDistance {
+        self.typo_distance
+    }
+
+    fn has_proximity_excluded_fields(&self) -> bool {
+        self.has_proximity_excluded_fields
+    }
+
+    fn exact_value_docids(&self, value: &str) -> heed::Result<RoaringBitmap> {
+        let mut docids = RoaringBitmap::new();
+        for field_id in &self.exact_attributes_ids {
+            let key = (*field_id, value);
+            if let Some(field_docids) =
+                self.index.field_id_exact_value_docids.get(self.rtxn, &key)?
+            {
+                docids |= field_docids;
+            }
+        }
+        Ok(docids)
+    }
 }
 
 impl<'t> CriteriaBuilder<'t> {
     pub fn new(rtxn: &'t heed::RoTxn<'t>, index: &'t Index) -> Result<Self> {
         let words_fst = index.words_fst(rtxn)?;
         let words_prefixes_fst = index.words_prefixes_fst(rtxn)?;
-        Ok(Self { rtxn, index, words_fst, words_prefixes_fst })
+        // When the searchable fields have been reordered without reindexing the documents
+        // the positions are still computed from the fields ids and must be remapped.
+        let attributes_ranks = match index.searchable_fields_ids(rtxn)? {
+            Some(ids) if ids.iter().enumerate().any(|(rank, id)| *id as usize != rank) => Some(ids),
+            _otherwise => None,
+        };
+        let one_attribute = index.position_stride(rtxn)?;
+        let fields_max_typos = index.fields_max_typos(rtxn)?.into_iter().collect();
+        let first_letter_typo = index.first_letter_typo(rtxn)?;
+        let typo_distance = index.typo_distance(rtxn)?;
+        let exact_attributes_ids = index.exact_attributes_ids(rtxn)?.into_iter().collect();
+        let prefix_pair_proximity = index.prefix_pair_proximity(rtxn)?;
+        let max_attributes_to_rank = index.max_attributes_to_rank(rtxn)?;
+        let synonyms = index.query_synonyms(rtxn)?;
+        let has_proximity_excluded_fields = !index.proximity_excluded_fields_ids(rtxn)?.is_empty();
+        Ok(Self {
+            rtxn,
+            index,
+            words_fst,
+            words_prefixes_fst,
+            attributes_ranks,
+            one_attribute,
+            session: None,
+            candidates_restriction: None,
+            fields_max_typos,
+            first_letter_typo,
+            typo_distance,
+            exact_attributes_ids,
+            prefix_pair_proximity,
+            max_attributes_to_rank,
+            synonyms,
+            has_proximity_excluded_fields,
+        })
+    }
+
+    /// Intersects the documents ids of the words with the given candidates as soon as they are
+    /// resolved, it must only be used with the candidates that the criteria are filtered with.
+    pub fn candidates_restriction(&mut self, candidates: RoaringBitmap) -> &mut Self {
+        self.candidates_restriction = Some(candidates);
+        self
+    }
+
+    /// Reuses the documents ids of the queries resolved by the previous searches of the session.
+    pub fn session(&mut self, session: &'t SearchSession) -> Result<&mut Self> {
+        session.refresh(self.rtxn, self.index)?;
+        self.session = Some(session);
+        Ok(self)
     }
 
     pub fn build(
@@ -226,11 +476,19 @@ impl<'t> CriteriaBuilder<'t> {
         use crate::criterion::Criterion as Name;
 
         let primitive_query = primitive_query.unwrap_or_default();
+        // Without a query tree the criteria based on the words of the query
+        // return the bucket of their parent as is, they can be skipped.
+        let placeholder = query_tree.is_none();
 
         let mut criterion =
             Box::new(Initial::new(query_tree, filtered_candidates)) as Box<dyn Criterion>;
         for name in self.index.criteria(&self.rtxn)? {
             criterion = match name {
+                Name::Words | Name::Typo | Name::Proximity | Name::Attribute | Name::Exactness
+                    if placeholder =>
+                {
+                    criterion
+                }
                 Name::Words => Box::new(Words::new(self, criterion)),
                 Name::Typo => Box::new(Typo::new(self, criterion)),
                 Name::Sort => match sort_criteria {
@@ -267,7 +525,10 @@ impl<'t> CriteriaBuilder<'t> {
                     }
                     None => criterion,
                 },
-                Name::Proximity => Box::new(Proximity::new(self, criterion)),
+                Name::Proximity => {
+                    let threshold = self.index.proximity_approximation_threshold(self.rtxn)?;
+                    Box::new(Proximity::with_approximation_threshold(self, criterion, threshold))
+                }
                 Name::Attribute => Box::new(Attribute::new(self, criterion)),
                 Name::Exactness => Box::new(Exactness::new(self, criterion, &primitive_query)?),
                 Name::Asc(field) => {
@@ -283,6 +544,23 @@ impl<'t> CriteriaBuilder<'t> {
     }
 }
 
+/// Converts positions based on the fields ids into positions based on the attributes ranks,
+/// positions of fields that are no more searchable are ignored.
+fn rank_positions(
+    ranks: &[FieldId],
+    positions: &RoaringBitmap,
+    one_attribute: u32,
+) -> RoaringBitmap {
+    positions
+        .iter()
+        .filter_map(|position| {
+            let (field_id, index) = extract_position(position, one_attribute);
+            let rank = ranks.iter().position(|id| *id as u32 == field_id)?;
+            Some(rank as u32 * one_attribute + index)
+        })
+        .collect()
+}
+
 pub fn resolve_query_tree<'t>(
     ctx: &'t dyn Context,
     query_tree: &Operation,
@@ -293,7 +571,7 @@ pub fn resolve_query_tree<'t>(
         query_tree: &Operation,
         wdcache: &mut WordDerivationsCache,
     ) -> Result<RoaringBitmap> {
-        use Operation::{And, Or, Phrase, Query};
+        use Operation::{And, Or, Phrase, Query, Split};
 
         match query_tree {
             And(ops) => {
@@ -316,27 +594,7 @@ pub fn resolve_query_tree<'t>(
                 }
                 Ok(candidates)
             }
-            Phrase(words) => {
-                let mut candidates = RoaringBitmap::new();
-                let mut first_loop = true;
-                for slice in words.windows(2) {
-                    let (left, right) = (&slice[0], &slice[1]);
-                    match ctx.word_pair_proximity_docids(left, right, 1)? {
-                        Some(pair_docids) => {
-                            if pair_docids.is_empty() {
-                                return Ok(RoaringBitmap::new());
-                            } else if first_loop {
-                                candidates = pair_docids;
-                                first_loop = false;
-                            } else {
-                                candidates &= pair_docids;
-                            }
-                        }
-                        None => return Ok(RoaringBitmap::new()),
-                    }
-                }
-                Ok(candidates)
-            }
+            Phrase(words) | Split(words) => resolve_phrase(ctx, words),
             Or(_, ops) => {
                 let mut candidates = RoaringBitmap::new();
                 for op in ops {
@@ -352,6 +610,69 @@ pub fn resolve_query_tree<'t>(
     resolve_operation(ctx, query_tree, wdcache)
 }
 
+/// Returns the documents in which the words of the phrase appear consecutively and in order.
+pub(crate) fn resolve_phrase(ctx: &dyn Context, words: &[String]) -> Result<RoaringBitmap> {
+    let has_excluded_fields = ctx.has_proximity_excluded_fields();
+    let mut candidates: Option<RoaringBitmap> = None;
+    for slice in words.windows(2) {
+        let (left, right) = (&slice[0], &slice[1]);
+        let pair_docids = ctx.word_pair_proximity_docids(left, right, 1)?.unwrap_or_default();
+        match candidates.as_mut() {
+            Some(candidates) => *candidates &= pair_docids,
+            None => candidates = Some(pair_docids),
+        }
+        if !has_excluded_fields && candidates.as_ref().map_or(false, |c| c.is_empty()) {
+            return Ok(RoaringBitmap::new());
+        }
+    }
+
+    // The consecutive pairs of a longer phrase can be in different places
+    // of a document, the positions of the whole phrase must be verified.
+    let mut candidates = candidates.unwrap_or_default();
+    if words.len() > 2 {
+        candidates = consecutive_words_docids(ctx, words, candidates)?;
+    }
+
+    // The word pairs of the fields excluded from the proximities are not indexed, the other
+    // documents that contain all the words are verified with the positions of the words.
+    if has_excluded_fields && words.len() > 1 {
+        let mut words_docids: Option<RoaringBitmap> = None;
+        for word in words {
+            let docids = ctx.word_docids(word)?.unwrap_or_default();
+            match words_docids.as_mut() {
+                Some(words_docids) => *words_docids &= docids,
+                None => words_docids = Some(docids),
+            }
+        }
+        let others = words_docids.unwrap_or_default() - &candidates;
+        candidates |= consecutive_words_docids(ctx, words, others)?;
+    }
+
+    Ok(candidates)
+}
+
+/// Returns the candidates in which the words appear consecutively and in order.
+fn consecutive_words_docids(
+    ctx: &dyn Context,
+    words: &[String],
+    candidates: RoaringBitmap,
+) -> Result<RoaringBitmap> {
+    let mut docids = RoaringBitmap::new();
+    for docid in candidates {
+        let words_positions = ctx.docid_words_positions(docid)?;
+        let positions: Option<Vec<_>> = words.iter().map(|w| words_positions.get(w)).collect();
+        if let Some((first, following)) = positions.as_ref().and_then(|p| p.split_first()) {
+            let is_consecutive = first.iter().any(|position| {
+                following.iter().zip(1..).all(|(positions, i)| positions.contains(position + i))
+            });
+            if is_consecutive {
+                docids.insert(docid);
+            }
+        }
+    }
+    Ok(docids)
+}
+
 fn all_word_pair_proximity_docids<T: AsRef<str>, U: AsRef<str>>(
     ctx: &dyn Context,
     left_words: &[(T, u8)],
@@ -370,39 +691,155 @@ fn all_word_pair_proximity_docids<T: AsRef<str>, U: AsRef<str>>(
     Ok(docids)
 }
 
+/// Keeps the derivations of a word that are frequent in the index first, the derivations
+/// with typos that appear in a single document are only kept up to this budget.
+pub const HAPAX_DERIVATIONS_BUDGET: usize = 10;
+
+/// Sorts the derivations by number of typos and by decreasing document frequency
+/// and drops the derivations with typos that are hapax legomena beyond the budget.
+pub(crate) fn prune_rare_derivations(
+    ctx: &dyn Context,
+    derivations: &[(String, u8)],
+) -> Result<Vec<(String, u8)>> {
+    let mut derivations = derivations
+        .iter()
+        .map(|(word, typo)| {
+            let count = ctx.word_documents_count(word)?.unwrap_or_default();
+            Ok((word, *typo, count))
+        })
+        .collect::<heed::Result<Vec<_>>>()?;
+    derivations.sort_by(|(_, ta, ca), (_, tb, cb)| ta.cmp(tb).then_with(|| cb.cmp(ca)));
+
+    let mut hapaxes = 0;
+    derivations.retain(|(_, typo, count)| {
+        if *typo == 0 || *count > 1 {
+            true
+        } else {
+            hapaxes += 1;
+            hapaxes <= HAPAX_DERIVATIONS_BUDGET
+        }
+    });
+
+    Ok(derivations.into_iter().map(|(word, typo, _)| (word.clone(), typo)).collect())
+}
+
 fn query_docids(
     ctx: &dyn Context,
     query: &Query,
     wdcache: &mut WordDerivationsCache,
 ) -> Result<RoaringBitmap> {
+    // Only the queries that are resolved from the derivations of the word are worth caching,
+    // the documents ids that are restricted to the candidates of a filter can't be reused.
+    let derived = query.prefix || matches!(query.kind, QueryKind::Tolerant { .. });
+    let derived = derived && ctx.candidates_restriction().is_none();
+    if derived {
+        if let Some(docids) = ctx.cached_query_docids(query) {
+            return Ok(docids);
+        }
+    }
+
+    let docids = resolve_query_docids(ctx, query, wdcache)?;
+    if derived {
+        ctx.cache_query_docids(query, &docids);
+    }
+    Ok(docids)
+}
+
+fn resolve_query_docids(
+    ctx: &dyn Context,
+    query: &Query,
+    wdcache: &mut WordDerivationsCache,
+) -> Result<RoaringBitmap> {
+    if let Some(field_id) = query.field {
+        return field_query_docids(ctx, query, field_id, wdcache);
+    }
+
     match &query.kind {
         QueryKind::Exact { word, .. } => {
             if query.prefix && ctx.in_prefix_cache(&word) {
-                Ok(ctx.word_prefix_docids(&word)?.unwrap_or_default())
+                let docids = ctx.word_prefix_docids(&word)?.unwrap_or_default();
+                Ok(restrict_candidates(ctx, docids))
             } else if query.prefix {
                 let words = word_derivations(&word, true, 0, ctx.words_fst(), wdcache)?;
                 let mut docids = RoaringBitmap::new();
                 for (word, _typo) in words {
                     let current_docids = ctx.word_docids(&word)?.unwrap_or_default();
-                    docids |= current_docids;
+                    docids |= restrict_candidates(ctx, current_docids);
                 }
                 Ok(docids)
             } else {
-                Ok(ctx.word_docids(&word)?.unwrap_or_default())
+                let docids = ctx.word_docids(&word)?.unwrap_or_default();
+                Ok(restrict_candidates(ctx, docids))
             }
         }
         QueryKind::Tolerant { typo, word } => {
             let words = word_derivations(&word, query.prefix, *typo, ctx.words_fst(), wdcache)?;
+            let words = prune_rare_derivations(ctx, words)?;
             let mut docids = RoaringBitmap::new();
-            for (word, _typo) in words {
-                let current_docids = ctx.word_docids(&word)?.unwrap_or_default();
-                docids |= current_docids;
+            for (word, typo) in words {
+                let current_docids = derivation_docids(ctx, &word, typo)?;
+                docids |= restrict_candidates(ctx, current_docids);
             }
             Ok(docids)
         }
     }
 }
 
+/// Resolves the documents ids of a query that is scoped to a field,
+/// only the documents that contain the derivations of the word in this field are returned.
+fn field_query_docids(
+    ctx: &dyn Context,
+    query: &Query,
+    field_id: FieldId,
+    wdcache: &mut WordDerivationsCache,
+) -> Result<RoaringBitmap> {
+    let typo = match query.kind {
+        QueryKind::Exact { .. } => 0,
+        QueryKind::Tolerant { typo, .. } => typo,
+    };
+    let words = word_derivations(query.kind.word(), query.prefix, typo, ctx.words_fst(), wdcache)?;
+    let max_typos = field_max_typos(ctx, field_id);
+    let mut docids = RoaringBitmap::new();
+    for (word, typo) in words {
+        if max_typos.map_or(false, |max| *typo > max) {
+            continue;
+        }
+        let current_docids = ctx.field_word_docids(word, field_id)?;
+        docids |= restrict_candidates(ctx, current_docids);
+    }
+    Ok(docids)
+}
+
+/// Returns the documents that contain the derivation of a word, the derivations with
+/// typos are only searched in the fields that allow this number of typos.
+fn derivation_docids(ctx: &dyn Context, word: &str, typo: u8) -> Result<RoaringBitmap> {
+    let restricted = ctx.fields_max_typos().iter().any(|(_, max)| typo > *max);
+    if !restricted {
+        return Ok(ctx.word_docids(word)?.unwrap_or_default());
+    }
+
+    let mut docids = RoaringBitmap::new();
+    for field_id in ctx.searchable_fields_ids()? {
+        if field_max_typos(ctx, field_id).map_or(true, |max| typo <= max) {
+            docids |= ctx.field_word_docids(word, field_id)?;
+        }
+    }
+    Ok(docids)
+}
+
+fn field_max_typos(ctx: &dyn Context, field_id: FieldId) -> Option<u8> {
+    ctx.fields_max_typos().iter().find(|(id, _)| *id == field_id).map(|(_, max)| *max)
+}
+
+/// Intersects the documents ids of a word with the candidates restriction of the context,
+/// the unions of the derivations of a word are then done on small bitmaps.
+fn restrict_candidates(ctx: &dyn Context, docids: RoaringBitmap) -> RoaringBitmap {
+    match ctx.candidates_restriction() {
+        Some(restriction) => docids & restriction,
+        None => docids,
+    }
+}
+
 fn query_pair_proximity_docids(
     ctx: &dyn Context,
     left: &Query,
@@ -482,12 +919,16 @@ fn query_pair_proximity_docids(
 #[cfg(test)]
 pub mod test {
     use std::collections::HashMap;
+    use std::iter::FromIterator;
 
     use maplit::hashmap;
     use rand::rngs::StdRng;
     use rand::{Rng, SeedableRng};
 
     use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::proximity::ONE_ATTRIBUTE;
+    use crate::update::IndexDocuments;
 
     fn s(s: &str) -> String {
         s.to_string()
@@ -510,6 +951,15 @@ pub mod test {
             Ok(self.word_docids.get(&word.to_string()).cloned())
         }
 
+        fn word_documents_count(&self, word: &str) -> heed::Result<Option<u64>> {
+            Ok(self.word_docids.get(&word.to_string()).map(RoaringBitmap::len))
+        }
+
+        fn exact_word_docids(&self, word: &str) -> heed::Result<Option<RoaringBitmap>> {
+            // The words of the test context are not normalized, they are all exact.
+            Ok(self.word_docids.get(&word.to_string()).cloned())
+        }
+
         fn word_prefix_docids(&self, word: &str) -> heed::Result<Option<RoaringBitmap>> {
             Ok(self.word_prefix_docids.get(&word.to_string()).cloned())
         }
@@ -592,6 +1042,18 @@ pub mod test {
         ) -> heed::Result<Option<RoaringBitmap>> {
             todo!()
         }
+
+        fn field_word_docids(
+            &self,
+            _word: &str,
+            _field_id: FieldId,
+        ) -> heed::Result<RoaringBitmap> {
+            todo!()
+        }
+
+        fn position_stride(&self) -> u32 {
+            ONE_ATTRIBUTE
+        }
     }
 
     impl<'a> Default for TestContext<'a> {
@@ -701,4 +1163,43 @@ pub mod test {
             }
         }
     }
+
+    #[test]
+    fn prune_hapax_derivations() {
+        let mut word_docids = hashmap! {
+            s("hello") => RoaringBitmap::from_iter(0..10),
+            s("hallo") => RoaringBitmap::from_iter(0..3),
+        };
+        let mut derivations = vec![(s("hello"), 0), (s("hallo"), 1)];
+        for i in 0..HAPAX_DERIVATIONS_BUDGET + 5 {
+            let word = format!("hapax{}", i);
+            word_docids.insert(word.clone(), RoaringBitmap::from_iter(Some(i as u32)));
+            derivations.push((word, 1));
+        }
+        let ctx = TestContext { word_docids, ..TestContext::default() };
+
+        let pruned = prune_rare_derivations(&ctx, &derivations).unwrap();
+        assert_eq!(pruned.len(), 2 + HAPAX_DERIVATIONS_BUDGET);
+        assert_eq!(pruned[0], (s("hello"), 0));
+        assert_eq!(pruned[1], (s("hallo"), 1));
+    }
+
+    #[test]
+    fn phrase_words_are_consecutive() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 0, "text": "the quick brown fox jumps" },
+            { "id": 1, "text": "a quick brown dog and a brown fox" },
+            { "id": 2, "text": "brown fox quick brown" },
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = index.search(&rtxn);
+        search.query("\"quick brown fox\"");
+        let result = search.execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+    }
 }
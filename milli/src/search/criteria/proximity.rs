@@ -6,12 +6,12 @@ use log::debug;
 use roaring::RoaringBitmap;
 
 use super::{
-    query_docids, query_pair_proximity_docids, resolve_query_tree, Context, Criterion,
-    CriterionParameters, CriterionResult,
+    query_docids, query_pair_proximity_docids, resolve_phrase, resolve_query_tree, BucketsDetails,
+    Context, Criterion, CriterionParameters, CriterionResult,
 };
 use crate::search::query_tree::{maximum_proximity, Operation, Query, QueryKind};
-use crate::search::{build_dfa, WordDerivationsCache};
-use crate::{DocumentId, Position, Result};
+use crate::search::{build_dfa_with_distance, WordDerivationsCache};
+use crate::{DocumentId, Position, Result, TypoDistance};
 
 type Cache = HashMap<(Operation, u8), Vec<(Query, Query, RoaringBitmap)>>;
 
@@ -29,21 +29,37 @@ pub struct Proximity<'t> {
     state: Option<(u8, Operation, RoaringBitmap)>,
     proximity: u8,
     bucket_candidates: RoaringBitmap,
+    buckets_details: BucketsDetails,
     parent: Box<dyn Criterion + 't>,
     candidates_cache: Cache,
     plane_sweep_cache: Option<btree_map::IntoIter<u8, RoaringBitmap>>,
+    /// The number of candidates above which the candidates are only bucketed
+    /// by the minimal proximity of the pairs of words of the query.
+    approximation_threshold: Option<u64>,
+    approximation_cache: Option<btree_map::IntoIter<u8, RoaringBitmap>>,
 }
 
 impl<'t> Proximity<'t> {
     pub fn new(ctx: &'t dyn Context<'t>, parent: Box<dyn Criterion + 't>) -> Self {
+        Proximity::with_approximation_threshold(ctx, parent, None)
+    }
+
+    pub fn with_approximation_threshold(
+        ctx: &'t dyn Context<'t>,
+        parent: Box<dyn Criterion + 't>,
+        approximation_threshold: Option<u64>,
+    ) -> Self {
         Proximity {
             ctx,
             state: None,
             proximity: 0,
             bucket_candidates: RoaringBitmap::new(),
+            buckets_details: BucketsDetails::new("proximity"),
             parent,
             candidates_cache: Cache::new(),
             plane_sweep_cache: None,
+            approximation_threshold,
+            approximation_cache: None,
         }
     }
 }
@@ -71,41 +87,69 @@ impl<'t> Criterion for Proximity<'t> {
                     self.state = None; // reset state
                 }
                 Some((_, query_tree, allowed_candidates)) => {
-                    let mut new_candidates = if allowed_candidates.len() <= CANDIDATES_THRESHOLD
-                        && self.proximity > PROXIMITY_THRESHOLD
-                    {
-                        if let Some(cache) = self.plane_sweep_cache.as_mut() {
-                            match cache.next() {
-                                Some((p, candidates)) => {
-                                    self.proximity = p;
-                                    candidates
+                    let must_approximate = self
+                        .approximation_threshold
+                        .map_or(false, |threshold| allowed_candidates.len() > threshold);
+
+                    let mut new_candidates =
+                        if self.approximation_cache.is_some() || must_approximate {
+                            if let Some(cache) = self.approximation_cache.as_mut() {
+                                match cache.next() {
+                                    Some((p, candidates)) => {
+                                        self.proximity = p;
+                                        candidates
+                                    }
+                                    None => {
+                                        self.state = None; // reset state
+                                        continue;
+                                    }
                                 }
-                                None => {
-                                    self.state = None; // reset state
-                                    continue;
+                            } else {
+                                let cache = resolve_approximate_candidates(
+                                    self.ctx,
+                                    query_tree,
+                                    allowed_candidates,
+                                    params.wdcache,
+                                )?;
+                                self.approximation_cache = Some(cache.into_iter());
+
+                                continue;
+                            }
+                        } else if allowed_candidates.len() <= CANDIDATES_THRESHOLD
+                            && self.proximity > PROXIMITY_THRESHOLD
+                        {
+                            if let Some(cache) = self.plane_sweep_cache.as_mut() {
+                                match cache.next() {
+                                    Some((p, candidates)) => {
+                                        self.proximity = p;
+                                        candidates
+                                    }
+                                    None => {
+                                        self.state = None; // reset state
+                                        continue;
+                                    }
                                 }
+                            } else {
+                                let cache = resolve_plane_sweep_candidates(
+                                    self.ctx,
+                                    query_tree,
+                                    allowed_candidates,
+                                    params.wdcache,
+                                )?;
+                                self.plane_sweep_cache = Some(cache.into_iter());
+
+                                continue;
                             }
                         } else {
-                            let cache = resolve_plane_sweep_candidates(
+                            // use set theory based algorithm
+                            resolve_candidates(
                                 self.ctx,
-                                query_tree,
-                                allowed_candidates,
+                                &query_tree,
+                                self.proximity,
+                                &mut self.candidates_cache,
                                 params.wdcache,
-                            )?;
-                            self.plane_sweep_cache = Some(cache.into_iter());
-
-                            continue;
-                        }
-                    } else {
-                        // use set theory based algorithm
-                        resolve_candidates(
-                            self.ctx,
-                            &query_tree,
-                            self.proximity,
-                            &mut self.candidates_cache,
-                            params.wdcache,
-                        )?
-                    };
+                            )?
+                        };
 
                     new_candidates &= &*allowed_candidates;
                     *allowed_candidates -= &new_candidates;
@@ -116,6 +160,7 @@ impl<'t> Criterion for Proximity<'t> {
                         candidates: Some(new_candidates),
                         filtered_candidates: None,
                         bucket_candidates: Some(take(&mut self.bucket_candidates)),
+                        ranking_details: self.buckets_details.next_bucket(),
                     }));
                 }
                 None => match self.parent.next(params)? {
@@ -124,7 +169,9 @@ impl<'t> Criterion for Proximity<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        ranking_details,
                     }) => {
+                        self.buckets_details.parent_bucket(ranking_details);
                         let mut candidates = match candidates {
                             Some(candidates) => candidates,
                             None => {
@@ -146,18 +193,22 @@ impl<'t> Criterion for Proximity<'t> {
                         self.state = Some((maximum_proximity as u8, query_tree, candidates));
                         self.proximity = 0;
                         self.plane_sweep_cache = None;
+                        self.approximation_cache = None;
                     }
                     Some(CriterionResult {
                         query_tree: None,
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        ranking_details,
                     }) => {
+                        self.buckets_details.parent_bucket(ranking_details);
                         return Ok(Some(CriterionResult {
                             query_tree: None,
                             candidates,
                             filtered_candidates,
                             bucket_candidates,
+                            ranking_details: self.buckets_details.next_bucket(),
                         }));
                     }
                     None => return Ok(None),
@@ -181,34 +232,25 @@ fn resolve_candidates<'t>(
         cache: &mut Cache,
         wdcache: &mut WordDerivationsCache,
     ) -> Result<Vec<(Query, Query, RoaringBitmap)>> {
-        use Operation::{And, Or, Phrase};
+        use Operation::{And, Or, Phrase, Split};
 
         let result = match query_tree {
             And(ops) => mdfs(ctx, ops, proximity, cache, wdcache)?,
-            Phrase(words) => {
+            Phrase(words) | Split(words) => {
                 if proximity == 0 {
-                    let most_left = words
-                        .first()
-                        .map(|w| Query { prefix: false, kind: QueryKind::exact(w.clone()) });
-                    let most_right = words
-                        .last()
-                        .map(|w| Query { prefix: false, kind: QueryKind::exact(w.clone()) });
-                    let mut candidates = None;
-                    for slice in words.windows(2) {
-                        let (left, right) = (&slice[0], &slice[1]);
-                        match ctx.word_pair_proximity_docids(left, right, 1)? {
-                            Some(pair_docids) => match candidates.as_mut() {
-                                Some(candidates) => *candidates &= pair_docids,
-                                None => candidates = Some(pair_docids),
-                            },
-                            None => {
-                                candidates = None;
-                                break;
-                            }
-                        }
-                    }
-                    match (most_left, most_right, candidates) {
-                        (Some(l), Some(r), Some(c)) => vec![(l, r, c)],
+                    let most_left = words.first().map(|w| Query {
+                        prefix: false,
+                        kind: QueryKind::exact(w.clone()),
+                        field: None,
+                    });
+                    let most_right = words.last().map(|w| Query {
+                        prefix: false,
+                        kind: QueryKind::exact(w.clone()),
+                        field: None,
+                    });
+                    let candidates = resolve_phrase(ctx, words)?;
+                    match (most_left, most_right) {
+                        (Some(l), Some(r)) if !candidates.is_empty() => vec![(l, r, candidates)],
                         _otherwise => Default::default(),
                     }
                 } else {
@@ -474,7 +516,7 @@ fn resolve_plane_sweep_candidates(
         words_positions: &HashMap<String, RoaringBitmap>,
         wdcache: &mut WordDerivationsCache,
     ) -> Result<Vec<(Position, u8, Position)>> {
-        use Operation::{And, Or, Phrase};
+        use Operation::{And, Or, Phrase, Split};
 
         if let Some(result) = rocache.get(query_tree) {
             return Ok(result.clone());
@@ -496,7 +538,7 @@ fn resolve_plane_sweep_candidates(
                 }
                 plane_sweep(groups_positions, false)?
             }
-            Phrase(words) => {
+            Phrase(words) | Split(words) => {
                 let mut groups_positions = Vec::with_capacity(words.len());
                 for word in words {
                     let positions = match words_positions.get(word) {
@@ -523,12 +565,13 @@ fn resolve_plane_sweep_candidates(
                 result.sort_unstable();
                 result
             }
-            Operation::Query(Query { prefix, kind }) => {
+            Operation::Query(Query { prefix, kind, .. }) => {
                 let mut result = Vec::new();
+                let distance = ctx.typo_distance();
                 match kind {
                     QueryKind::Exact { word, .. } => {
                         if *prefix {
-                            let iter = word_derivations(word, true, 0, &words_positions)
+                            let iter = word_derivations(word, true, 0, distance, &words_positions)
                                 .flat_map(|positions| positions.iter().map(|p| (p, 0, p)));
                             result.extend(iter);
                         } else if let Some(positions) = words_positions.get(word) {
@@ -536,8 +579,9 @@ fn resolve_plane_sweep_candidates(
                         }
                     }
                     QueryKind::Tolerant { typo, word } => {
-                        let iter = word_derivations(word, *prefix, *typo, &words_positions)
-                            .flat_map(|positions| positions.iter().map(|p| (p, 0, p)));
+                        let iter =
+                            word_derivations(word, *prefix, *typo, distance, &words_positions)
+                                .flat_map(|positions| positions.iter().map(|p| (p, 0, p)));
                         result.extend(iter);
                     }
                 }
@@ -555,9 +599,10 @@ fn resolve_plane_sweep_candidates(
         word: &str,
         is_prefix: bool,
         max_typo: u8,
+        typo_distance: TypoDistance,
         words_positions: &'a HashMap<String, RoaringBitmap>,
     ) -> impl Iterator<Item = &'a RoaringBitmap> {
-        let dfa = build_dfa(word, max_typo, is_prefix);
+        let dfa = build_dfa_with_distance(word, max_typo, is_prefix, typo_distance);
         words_positions.iter().filter_map(move |(document_word, positions)| {
             use levenshtein_automata::Distance;
             match dfa.eval(document_word) {
@@ -587,3 +632,103 @@ fn resolve_plane_sweep_candidates(
 
     Ok(candidates)
 }
+
+/// Buckets the candidates by the minimal proximity between the pairs of consecutive words of the
+/// query instead of the sum of the proximities of all the pairs, it is much cheaper to compute.
+fn resolve_approximate_candidates(
+    ctx: &dyn Context,
+    query_tree: &Operation,
+    allowed_candidates: &RoaringBitmap,
+    wdcache: &mut WordDerivationsCache,
+) -> Result<BTreeMap<u8, RoaringBitmap>> {
+    let pairs = consecutive_query_pairs(query_tree);
+    let maximum_proximity = maximum_proximity(query_tree) as u8;
+
+    let mut output = BTreeMap::new();
+    let mut remaining = allowed_candidates.clone();
+    if !pairs.is_empty() {
+        for pair_proximity in 1..=7 {
+            let mut candidates = RoaringBitmap::new();
+            for (left, right) in &pairs {
+                candidates |=
+                    query_pair_proximity_docids(ctx, left, right, pair_proximity, wdcache)?;
+            }
+
+            candidates &= &remaining;
+            if !candidates.is_empty() {
+                remaining -= &candidates;
+                output.insert(pair_proximity - 1, candidates);
+            }
+        }
+    }
+
+    // The candidates that don't contain any pair of close words are returned last.
+    if !remaining.is_empty() {
+        *output.entry(maximum_proximity).or_insert_with(RoaringBitmap::new) |= remaining;
+    }
+
+    Ok(output)
+}
+
+/// Returns all the pairs of queries that follow each other in the query tree.
+fn consecutive_query_pairs(query_tree: &Operation) -> Vec<(Query, Query)> {
+    /// Returns the queries that can start and end the given operation.
+    fn edges(operation: &Operation) -> (Vec<Query>, Vec<Query>) {
+        use Operation::{And, Or, Phrase, Split};
+
+        match operation {
+            And(ops) => {
+                let firsts = ops.first().map_or_else(Vec::new, |op| edges(op).0);
+                let lasts = ops.last().map_or_else(Vec::new, |op| edges(op).1);
+                (firsts, lasts)
+            }
+            Phrase(words) | Split(words) => {
+                let exact = |w: &String| Query {
+                    prefix: false,
+                    kind: QueryKind::exact(w.clone()),
+                    field: None,
+                };
+                (
+                    words.first().map(exact).into_iter().collect(),
+                    words.last().map(exact).into_iter().collect(),
+                )
+            }
+            Or(_, ops) => {
+                let mut firsts = Vec::new();
+                let mut lasts = Vec::new();
+                for op in ops {
+                    let (op_firsts, op_lasts) = edges(op);
+                    firsts.extend(op_firsts);
+                    lasts.extend(op_lasts);
+                }
+                (firsts, lasts)
+            }
+            Operation::Query(query) => (vec![query.clone()], vec![query.clone()]),
+        }
+    }
+
+    fn pairs(operation: &Operation, output: &mut Vec<(Query, Query)>) {
+        use Operation::{And, Or, Phrase, Split};
+
+        match operation {
+            And(ops) => {
+                ops.iter().for_each(|op| pairs(op, output));
+                for window in ops.windows(2) {
+                    let (_, lasts) = edges(&window[0]);
+                    let (firsts, _) = edges(&window[1]);
+                    for left in &lasts {
+                        for right in &firsts {
+                            output.push((left.clone(), right.clone()));
+                        }
+                    }
+                }
+            }
+            Or(_, ops) => ops.iter().for_each(|op| pairs(op, output)),
+            Phrase(_) | Split(_) | Operation::Query(_) => (),
+        }
+    }
+
+    let mut output = Vec::new();
+    pairs(query_tree, &mut output);
+    output
+}
@@ -1,4 +1,3 @@
-use std::borrow::Cow;
 use std::collections::HashMap;
 use std::mem::take;
 
@@ -6,10 +5,10 @@ use log::debug;
 use roaring::RoaringBitmap;
 
 use super::{
-    query_docids, resolve_query_tree, Candidates, Context, Criterion, CriterionParameters,
-    CriterionResult,
+    prune_rare_derivations, query_docids, resolve_query_tree, BucketsDetails, Candidates, Context,
+    Criterion, CriterionParameters, CriterionResult,
 };
-use crate::search::query_tree::{maximum_typo, Operation, Query, QueryKind};
+use crate::search::query_tree::{maximum_typo, Operation, Query, QueryKind, SPLIT_WORD_TYPO};
 use crate::search::{word_derivations, WordDerivationsCache};
 use crate::Result;
 
@@ -22,6 +21,7 @@ pub struct Typo<'t> {
     state: Option<(u8, Operation, Candidates)>,
     typos: u8,
     bucket_candidates: Option<RoaringBitmap>,
+    buckets_details: BucketsDetails,
     parent: Box<dyn Criterion + 't>,
     candidates_cache: HashMap<(Operation, u8), RoaringBitmap>,
 }
@@ -33,6 +33,7 @@ impl<'t> Typo<'t> {
             state: None,
             typos: 0,
             bucket_candidates: None,
+            buckets_details: BucketsDetails::new("typo"),
             parent,
             candidates_cache: HashMap::new(),
         }
@@ -66,10 +67,9 @@ impl<'t> Criterion for Typo<'t> {
                     self.state = None; // reset state
                 }
                 Some((_, query_tree, candidates_authorization)) => {
-                    let fst = self.ctx.words_fst();
                     let new_query_tree = match self.typos {
                         typos if typos < MAX_TYPOS_PER_WORD => alterate_query_tree(
-                            &fst,
+                            self.ctx,
                             query_tree.clone(),
                             self.typos,
                             params.wdcache,
@@ -78,7 +78,7 @@ impl<'t> Criterion for Typo<'t> {
                             // When typos >= MAX_TYPOS_PER_WORD, no more alteration of the query tree is possible,
                             // we keep the altered query tree
                             *query_tree = alterate_query_tree(
-                                &fst,
+                                self.ctx,
                                 query_tree.clone(),
                                 self.typos,
                                 params.wdcache,
@@ -131,6 +131,7 @@ impl<'t> Criterion for Typo<'t> {
                         candidates: Some(candidates),
                         filtered_candidates: None,
                         bucket_candidates: Some(bucket_candidates),
+                        ranking_details: self.buckets_details.next_bucket(),
                     }));
                 }
                 None => match self.parent.next(params)? {
@@ -139,7 +140,9 @@ impl<'t> Criterion for Typo<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        ranking_details,
                     }) => {
+                        self.buckets_details.parent_bucket(ranking_details);
                         self.bucket_candidates =
                             match (self.bucket_candidates.take(), bucket_candidates) {
                                 (Some(self_bc), Some(parent_bc)) => Some(self_bc | parent_bc),
@@ -162,12 +165,15 @@ impl<'t> Criterion for Typo<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        ranking_details,
                     }) => {
+                        self.buckets_details.parent_bucket(ranking_details);
                         return Ok(Some(CriterionResult {
                             query_tree: None,
                             candidates,
                             filtered_candidates,
                             bucket_candidates,
+                            ranking_details: self.buckets_details.next_bucket(),
                         }));
                     }
                     None => return Ok(None),
@@ -181,25 +187,25 @@ impl<'t> Criterion for Typo<'t> {
 /// containing all of the corresponding exact words in the words FST. Each tolerant
 /// query will only be replaced by exact query with up to `number_typos` maximum typos.
 fn alterate_query_tree(
-    words_fst: &fst::Set<Cow<[u8]>>,
+    ctx: &dyn Context,
     mut query_tree: Operation,
     number_typos: u8,
     wdcache: &mut WordDerivationsCache,
 ) -> Result<Operation> {
     fn recurse(
-        words_fst: &fst::Set<Cow<[u8]>>,
+        ctx: &dyn Context,
         operation: &mut Operation,
         number_typos: u8,
         wdcache: &mut WordDerivationsCache,
     ) -> Result<()> {
-        use Operation::{And, Or, Phrase};
+        use Operation::{And, Or, Phrase, Split};
 
         match operation {
             And(ops) | Or(_, ops) => {
-                ops.iter_mut().try_for_each(|op| recurse(words_fst, op, number_typos, wdcache))
+                ops.iter_mut().try_for_each(|op| recurse(ctx, op, number_typos, wdcache))
             }
             // Because Phrases don't allow typos, no alteration can be done.
-            Phrase(_words) => return Ok(()),
+            Phrase(_words) | Split(_words) => return Ok(()),
             Operation::Query(q) => {
                 if let QueryKind::Tolerant { typo, word } = &q.kind {
                     // if no typo is allowed we don't call word_derivations function,
@@ -208,10 +214,13 @@ fn alterate_query_tree(
                         *operation = Operation::Query(Query {
                             prefix: q.prefix,
                             kind: QueryKind::Exact { original_typo: 0, word: word.clone() },
+                            field: q.field,
                         });
                     } else {
                         let typo = *typo.min(&number_typos);
-                        let words = word_derivations(word, q.prefix, typo, words_fst, wdcache)?;
+                        let words =
+                            word_derivations(word, q.prefix, typo, ctx.words_fst(), wdcache)?;
+                        let words = prune_rare_derivations(ctx, words)?;
                         let queries = words
                             .iter()
                             .map(|(word, typo)| {
@@ -221,6 +230,7 @@ fn alterate_query_tree(
                                         original_typo: *typo,
                                         word: word.to_string(),
                                     },
+                                    field: q.field,
                                 })
                             })
                             .collect();
@@ -234,7 +244,7 @@ fn alterate_query_tree(
         }
     }
 
-    recurse(words_fst, &mut query_tree, number_typos, wdcache)?;
+    recurse(ctx, &mut query_tree, number_typos, wdcache)?;
     Ok(query_tree)
 }
 
@@ -252,11 +262,13 @@ fn resolve_candidates<'t>(
         cache: &mut HashMap<(Operation, u8), RoaringBitmap>,
         wdcache: &mut WordDerivationsCache,
     ) -> Result<RoaringBitmap> {
-        use Operation::{And, Or, Phrase, Query};
+        use Operation::{And, Or, Phrase, Query, Split};
 
         match query_tree {
             And(ops) => mdfs(ctx, ops, number_typos, cache, wdcache),
-            Phrase(words) => {
+            // The split words are only returned with the number of typos they cost.
+            Split(_) if number_typos != SPLIT_WORD_TYPO => Ok(RoaringBitmap::new()),
+            Phrase(words) | Split(words) => {
                 let mut candidates = RoaringBitmap::new();
                 let mut first_loop = true;
                 for slice in words.windows(2) {
@@ -347,6 +359,7 @@ fn resolve_candidates<'t>(
 mod test {
     use super::super::initial::Initial;
     use super::super::test::TestContext;
+    use super::super::RankingRuleDetail;
     use super::*;
 
     #[test]
@@ -376,14 +389,17 @@ mod test {
                 Operation::Query(Query {
                     prefix: false,
                     kind: QueryKind::exact("split".to_string()),
+                    field: None,
                 }),
                 Operation::Query(Query {
                     prefix: false,
                     kind: QueryKind::exact("this".to_string()),
+                    field: None,
                 }),
                 Operation::Query(Query {
                     prefix: false,
                     kind: QueryKind::tolerant(1, "world".to_string()),
+                    field: None,
                 }),
             ])],
         );
@@ -407,20 +423,27 @@ mod test {
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("split".to_string()),
+                        field: None,
                     }),
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("this".to_string()),
+                        field: None,
                     }),
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("world".to_string()),
+                        field: None,
                     }),
                 ])],
             )),
             candidates: Some(candidates_1.clone()),
             bucket_candidates: Some(candidates_1),
             filtered_candidates: None,
+            ranking_details: vec![RankingRuleDetail {
+                ranking_rule: "typo".to_string(),
+                bucket: 0,
+            }],
         };
 
         assert_eq!(criteria.next(&mut criterion_parameters).unwrap(), Some(expected_1));
@@ -436,10 +459,12 @@ mod test {
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("split".to_string()),
+                        field: None,
                     }),
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("this".to_string()),
+                        field: None,
                     }),
                     Operation::Or(
                         false,
@@ -447,10 +472,12 @@ mod test {
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::exact_with_typo(1, "word".to_string()),
+                                field: None,
                             }),
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::exact("world".to_string()),
+                                field: None,
                             }),
                         ],
                     ),
@@ -459,6 +486,10 @@ mod test {
             candidates: Some(candidates_2.clone()),
             bucket_candidates: Some(candidates_2),
             filtered_candidates: None,
+            ranking_details: vec![RankingRuleDetail {
+                ranking_rule: "typo".to_string(),
+                bucket: 1,
+            }],
         };
 
         assert_eq!(criteria.next(&mut criterion_parameters).unwrap(), Some(expected_2));
@@ -482,6 +513,10 @@ mod test {
             candidates: None,
             bucket_candidates: None,
             filtered_candidates: Some(facet_candidates.clone()),
+            ranking_details: vec![RankingRuleDetail {
+                ranking_rule: "typo".to_string(),
+                bucket: 0,
+            }],
         };
 
         // first iteration, returns the facet candidates
@@ -500,14 +535,17 @@ mod test {
                 Operation::Query(Query {
                     prefix: false,
                     kind: QueryKind::exact("split".to_string()),
+                    field: None,
                 }),
                 Operation::Query(Query {
                     prefix: false,
                     kind: QueryKind::exact("this".to_string()),
+                    field: None,
                 }),
                 Operation::Query(Query {
                     prefix: false,
                     kind: QueryKind::tolerant(1, "world".to_string()),
+                    field: None,
                 }),
             ])],
         );
@@ -531,20 +569,27 @@ mod test {
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("split".to_string()),
+                        field: None,
                     }),
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("this".to_string()),
+                        field: None,
                     }),
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("world".to_string()),
+                        field: None,
                     }),
                 ])],
             )),
             candidates: Some(&candidates_1 & &facet_candidates),
             bucket_candidates: Some(&candidates_1 & &facet_candidates),
             filtered_candidates: None,
+            ranking_details: vec![RankingRuleDetail {
+                ranking_rule: "typo".to_string(),
+                bucket: 0,
+            }],
         };
 
         assert_eq!(criteria.next(&mut criterion_parameters).unwrap(), Some(expected_1));
@@ -560,10 +605,12 @@ mod test {
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("split".to_string()),
+                        field: None,
                     }),
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("this".to_string()),
+                        field: None,
                     }),
                     Operation::Or(
                         false,
@@ -571,10 +618,12 @@ mod test {
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::exact_with_typo(1, "word".to_string()),
+                                field: None,
                             }),
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::exact("world".to_string()),
+                                field: None,
                             }),
                         ],
                     ),
@@ -583,8 +632,41 @@ mod test {
             candidates: Some(&candidates_2 & &facet_candidates),
             bucket_candidates: Some(&candidates_2 & &facet_candidates),
             filtered_candidates: None,
+            ranking_details: vec![RankingRuleDetail {
+                ranking_rule: "typo".to_string(),
+                bucket: 1,
+            }],
         };
 
         assert_eq!(criteria.next(&mut criterion_parameters).unwrap(), Some(expected_2));
     }
+
+    #[test]
+    fn concatenated_words_are_typo_tolerant() {
+        use big_s::S;
+
+        use crate::index::tests::TempIndex;
+        use crate::update::{IndexDocuments, Settings};
+        use crate::Search;
+
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_criteria(vec![S("words"), S("typo")]);
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": 0, "title": "spider man" },
+            { "id": 1, "title": "spiderman" },
+            { "id": 2, "title": "spidermen" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // The compound form matches the concatenated query words without any typo,
+        // and it keeps the typo tolerance of a word of this length.
+        let rtxn = index.read_txn().unwrap();
+        let mut search = Search::new(&rtxn, &index);
+        search.query("spider man");
+        assert_eq!(search.execute().unwrap().documents_ids, vec![0, 1, 2]);
+    }
 }
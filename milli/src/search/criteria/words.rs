@@ -3,7 +3,9 @@ use std::mem::take;
 use log::debug;
 use roaring::RoaringBitmap;
 
-use super::{resolve_query_tree, Context, Criterion, CriterionParameters, CriterionResult};
+use super::{
+    resolve_query_tree, BucketsDetails, Context, Criterion, CriterionParameters, CriterionResult,
+};
 use crate::search::query_tree::Operation;
 use crate::Result;
 
@@ -13,6 +15,7 @@ pub struct Words<'t> {
     candidates: Option<RoaringBitmap>,
     bucket_candidates: Option<RoaringBitmap>,
     filtered_candidates: Option<RoaringBitmap>,
+    buckets_details: BucketsDetails,
     parent: Box<dyn Criterion + 't>,
 }
 
@@ -23,6 +26,7 @@ impl<'t> Words<'t> {
             query_trees: Vec::default(),
             candidates: None,
             bucket_candidates: None,
+            buckets_details: BucketsDetails::new("words"),
             parent,
             filtered_candidates: None,
         }
@@ -63,6 +67,7 @@ impl<'t> Criterion for Words<'t> {
                         candidates,
                         filtered_candidates: self.filtered_candidates.clone(),
                         bucket_candidates,
+                        ranking_details: self.buckets_details.next_bucket(),
                     }));
                 }
                 None => match self.parent.next(params)? {
@@ -71,8 +76,10 @@ impl<'t> Criterion for Words<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        ranking_details,
                     }) => {
                         self.query_trees = explode_query_tree(query_tree);
+                        self.buckets_details.parent_bucket(ranking_details);
                         self.candidates = candidates;
                         self.filtered_candidates = filtered_candidates;
 
@@ -87,12 +94,15 @@ impl<'t> Criterion for Words<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        ranking_details,
                     }) => {
+                        self.buckets_details.parent_bucket(ranking_details);
                         return Ok(Some(CriterionResult {
                             query_tree: None,
                             candidates,
                             filtered_candidates,
                             bucket_candidates,
+                            ranking_details: self.buckets_details.next_bucket(),
                         }));
                     }
                     None => return Ok(None),
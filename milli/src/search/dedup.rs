@@ -0,0 +1,161 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use roaring::RoaringBitmap;
+
+use super::distinct::{Distinct, DocIter};
+use crate::error::UserError;
+use crate::{DocumentId, FieldId, Index, Result};
+
+/// The maximum number of field value hashes remembered by a deduplicated search,
+/// a hash is 8 bytes long which bounds the memory used to a few megabytes.
+pub const DEDUP_MAX_HASHES: usize = 100_000;
+
+/// The hashes of the values of the field the documents are deduplicated on.
+///
+/// The field doesn't need to be faceted, its value is read from the stored documents
+/// when they are returned, the documents that don't contain the field are never collapsed.
+pub(crate) struct FieldHashes<'a> {
+    field_id: FieldId,
+    seen: HashSet<u64>,
+    index: &'a Index,
+    rtxn: &'a heed::RoTxn<'a>,
+}
+
+impl<'a> FieldHashes<'a> {
+    pub fn new(field_id: FieldId, index: &'a Index, rtxn: &'a heed::RoTxn) -> FieldHashes<'a> {
+        FieldHashes { field_id, seen: HashSet::new(), index, rtxn }
+    }
+
+    /// Returns `true` if a document with the same value was already returned.
+    ///
+    /// Once the budget is reached the new values are no longer remembered, the documents
+    /// are still collapsed with the ones seen before but not between themselves.
+    fn is_duplicate(&mut self, docid: DocumentId) -> Result<bool> {
+        let document = self
+            .index
+            .document_bytes(self.rtxn, docid)?
+            .ok_or(UserError::UnknownInternalDocumentId { document_id: docid })?;
+        let document = obkv::KvReaderU16::new(&document);
+        let hash = match document.get(self.field_id) {
+            Some(bytes) => fxhash::hash64(bytes),
+            None => return Ok(false),
+        };
+
+        if self.seen.contains(&hash) {
+            Ok(true)
+        } else {
+            if self.seen.len() < DEDUP_MAX_HASHES {
+                self.seen.insert(hash);
+            }
+            Ok(false)
+        }
+    }
+}
+
+/// A distinct implementer that skips the documents returned by another distinct
+/// implementer when a document with the same value of the field was already returned.
+pub(crate) struct DedupDistinct<'a, D> {
+    inner: D,
+    hashes: Rc<RefCell<FieldHashes<'a>>>,
+}
+
+impl<'a, D> DedupDistinct<'a, D> {
+    pub fn new(inner: D, hashes: FieldHashes<'a>) -> Self {
+        DedupDistinct { inner, hashes: Rc::new(RefCell::new(hashes)) }
+    }
+}
+
+pub(crate) struct DedupIter<'a, I> {
+    inner: I,
+    hashes: Rc<RefCell<FieldHashes<'a>>>,
+}
+
+impl<I: DocIter> DedupIter<'_, I> {
+    fn next_inner(&mut self) -> Result<Option<DocumentId>> {
+        while let Some(result) = self.inner.next() {
+            let docid = result?;
+            if !self.hashes.borrow_mut().is_duplicate(docid)? {
+                return Ok(Some(docid));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<I: DocIter> Iterator for DedupIter<'_, I> {
+    type Item = Result<DocumentId>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_inner().transpose()
+    }
+}
+
+impl<I: DocIter> DocIter for DedupIter<'_, I> {
+    fn into_excluded(self) -> RoaringBitmap {
+        self.inner.into_excluded()
+    }
+}
+
+impl<'a, D: Distinct> Distinct for DedupDistinct<'a, D> {
+    type Iter = DedupIter<'a, D::Iter>;
+
+    fn distinct(&mut self, candidates: RoaringBitmap, excluded: RoaringBitmap) -> Self::Iter {
+        DedupIter { inner: self.inner.distinct(candidates, excluded), hashes: self.hashes.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+
+    use crate::index::tests::TempIndex;
+    use crate::update::IndexDocuments;
+    use crate::Search;
+
+    #[test]
+    fn dedup_by_field_value() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": "a", "title": "hello world", "body": "the original article" },
+            { "id": "b", "title": "hello world", "body": "the original article" },
+            { "id": "c", "title": "hello there", "body": "another article" },
+            { "id": "d", "title": "hello", "body": "the original article" },
+            { "id": "e", "title": "hello" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let external_documents_ids = index.external_documents_ids(&rtxn).unwrap();
+        let docid = |id: &str| external_documents_ids.get(id).unwrap();
+
+        let mut search = Search::new(&rtxn, &index);
+        search.query("hello");
+        let result = search.execute().unwrap();
+        assert_eq!(result.documents_ids.len(), 5);
+
+        search.dedup_by(S("body"));
+        let result = search.execute().unwrap();
+        let mut documents_ids = result.documents_ids;
+        documents_ids.sort_unstable();
+        // Only one of the mirrored documents is kept, the one without a body is always kept.
+        assert_eq!(documents_ids.len(), 3);
+        assert!(documents_ids.contains(&docid("c")));
+        assert!(documents_ids.contains(&docid("e")));
+
+        // A placeholder search is deduplicated too.
+        let mut search = Search::new(&rtxn, &index);
+        search.dedup_by("body");
+        let result = search.execute().unwrap();
+        assert_eq!(result.documents_ids.len(), 3);
+
+        // No document contains an unknown field, none of them are collapsed.
+        let mut search = Search::new(&rtxn, &index);
+        search.query("hello").dedup_by("unknown");
+        let result = search.execute().unwrap();
+        assert_eq!(result.documents_ids.len(), 5);
+    }
+}
@@ -123,7 +123,7 @@ fn facet_values_prefix_key(distinct: FieldId, id: DocumentId) -> [u8; FID_SIZE +
     concat_arrays!(distinct.to_be_bytes(), id.to_be_bytes())
 }
 
-fn facet_number_values<'a>(
+pub(crate) fn facet_number_values<'a>(
     id: DocumentId,
     distinct: FieldId,
     index: &Index,
@@ -140,7 +140,7 @@ fn facet_number_values<'a>(
     Ok(iter)
 }
 
-fn facet_string_values<'a>(
+pub(crate) fn facet_string_values<'a>(
     id: DocumentId,
     distinct: FieldId,
     index: &Index,
@@ -0,0 +1,225 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use roaring::RoaringBitmap;
+
+use super::facet_distinct::{facet_number_values, facet_string_values};
+use super::{Distinct, DocIter};
+use crate::error::InternalError;
+use crate::facet::FacetValue;
+use crate::index::db_name;
+use crate::{DocumentId, FieldId, Index, Result};
+
+/// A distinct implementer that is backed by facets and that keeps several documents
+/// for each one of the facet values.
+///
+/// It works like the `FacetDistinct` but the documents that have the same facet value than
+/// the current document are only excluded once `group_size` documents have been returned
+/// with this facet value. The number of documents returned for each facet value is kept
+/// between the iterations as the same group can appear in multiple buckets.
+pub struct FacetGroup<'a> {
+    field: FieldId,
+    group_size: usize,
+    counts: Rc<RefCell<HashMap<FacetValue, usize>>>,
+    index: &'a Index,
+    txn: &'a heed::RoTxn<'a>,
+}
+
+impl<'a> FacetGroup<'a> {
+    pub fn new(
+        field: FieldId,
+        group_size: usize,
+        index: &'a Index,
+        txn: &'a heed::RoTxn<'a>,
+    ) -> Self {
+        let group_size = group_size.max(1);
+        Self { field, group_size, counts: Rc::default(), index, txn }
+    }
+}
+
+pub struct FacetGroupIter<'a> {
+    candidates: RoaringBitmap,
+    field: FieldId,
+    group_size: usize,
+    counts: Rc<RefCell<HashMap<FacetValue, usize>>>,
+    kept: RoaringBitmap,
+    excluded: RoaringBitmap,
+    index: &'a Index,
+    iter_offset: usize,
+    txn: &'a heed::RoTxn<'a>,
+}
+
+impl<'a> FacetGroupIter<'a> {
+    fn facet_string_docids(&self, key: &str) -> heed::Result<Option<RoaringBitmap>> {
+        self.index
+            .facet_id_string_docids
+            .get(self.txn, &(self.field, key))
+            .map(|result| result.map(|(_original, docids)| docids))
+    }
+
+    fn facet_number_docids(&self, key: f64) -> heed::Result<Option<RoaringBitmap>> {
+        // get facet docids on level 0
+        self.index.facet_id_f64_docids.get(self.txn, &(self.field, 0, key, key))
+    }
+
+    /// Increments the number of documents returned for this facet value
+    /// and returns `true` if the group is now full.
+    fn increment(&self, value: FacetValue) -> bool {
+        let mut counts = self.counts.borrow_mut();
+        let count = counts.entry(value).or_insert(0);
+        *count += 1;
+        *count >= self.group_size
+    }
+
+    fn group_string(&mut self, id: DocumentId) -> Result<()> {
+        let iter = facet_string_values(id, self.field, self.index, self.txn)?;
+
+        for item in iter {
+            let ((_, _, value), _) = item?;
+            if self.increment(FacetValue::from(value)) {
+                let facet_docids = self.facet_string_docids(value)?.ok_or(
+                    InternalError::DatabaseMissingEntry {
+                        db_name: db_name::FACET_ID_STRING_DOCIDS,
+                        key: None,
+                    },
+                )?;
+                self.excluded |= facet_docids;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn group_number(&mut self, id: DocumentId) -> Result<()> {
+        let iter = facet_number_values(id, self.field, self.index, self.txn)?;
+
+        for item in iter {
+            let ((_, _, value), _) = item?;
+            if self.increment(FacetValue::from(value)) {
+                let facet_docids = self.facet_number_docids(value)?.ok_or(
+                    InternalError::DatabaseMissingEntry {
+                        db_name: db_name::FACET_ID_F64_DOCIDS,
+                        key: None,
+                    },
+                )?;
+                self.excluded |= facet_docids;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Performs the next iteration of the facet group. This is a convenience method that is
+    /// called by the Iterator::next implementation that transposes the result.
+    fn next_inner(&mut self) -> Result<Option<DocumentId>> {
+        // The documents that were already returned must not be excluded from the candidates,
+        // this would shift the offset and make us skip documents.
+        self.excluded -= &self.kept;
+        self.candidates -= &self.excluded;
+
+        let mut candidates_iter = self.candidates.iter().skip(self.iter_offset);
+        match candidates_iter.next() {
+            Some(id) => {
+                self.kept.insert(id);
+                self.group_string(id)?;
+                self.group_number(id)?;
+                self.iter_offset += 1;
+
+                Ok(Some(id))
+            }
+            // no more candidate at this offset, return.
+            None => Ok(None),
+        }
+    }
+}
+
+impl Iterator for FacetGroupIter<'_> {
+    type Item = Result<DocumentId>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_inner().transpose()
+    }
+}
+
+impl DocIter for FacetGroupIter<'_> {
+    fn into_excluded(mut self) -> RoaringBitmap {
+        self.excluded -= self.kept;
+        self.excluded
+    }
+}
+
+impl<'a> Distinct for FacetGroup<'a> {
+    type Iter = FacetGroupIter<'a>;
+
+    fn distinct(&mut self, candidates: RoaringBitmap, excluded: RoaringBitmap) -> Self::Iter {
+        FacetGroupIter {
+            candidates,
+            field: self.field,
+            group_size: self.group_size,
+            counts: self.counts.clone(),
+            kept: RoaringBitmap::new(),
+            excluded,
+            index: self.index,
+            iter_offset: 0,
+            txn: self.txn,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use big_s::S;
+    use maplit::{btreemap, hashset};
+
+    use crate::index::tests::TempIndex;
+    use crate::update::{IndexDocuments, Settings};
+    use crate::Search;
+
+    #[test]
+    fn keeps_group_size_documents_per_value() {
+        let index = TempIndex::new();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_primary_key(S("id"));
+        builder.set_filterable_fields(hashset! { S("color") });
+        builder.execute(|_, _| ()).unwrap();
+
+        let content = documents!([
+            { "id": 0, "color": "red" },
+            { "id": 1, "color": "red" },
+            { "id": 2, "color": "red" },
+            { "id": 3, "color": "blue" },
+            { "id": 4, "color": "blue" },
+            { "id": 5, "color": "green" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        // The third red document is dropped, every group keeps at most two documents.
+        let mut search = Search::new(&rtxn, &index);
+        search.group_by("color", 2);
+        let result = search.execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0, 1, 3, 4, 5]);
+        assert_eq!(
+            result.groups,
+            Some(btreemap! { S("blue") => 2, S("green") => 1, S("red") => 3 })
+        );
+
+        // The limit applies to the documents of all the groups.
+        let mut search = Search::new(&rtxn, &index);
+        search.group_by("color", 2);
+        search.limit(3);
+        let result = search.execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0, 1, 3]);
+
+        // A single document per group.
+        let mut search = Search::new(&rtxn, &index);
+        search.group_by("color", 1);
+        let result = search.execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0, 3, 5]);
+    }
+}
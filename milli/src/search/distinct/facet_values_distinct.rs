@@ -0,0 +1,149 @@
+use std::rc::Rc;
+
+use roaring::RoaringBitmap;
+
+use super::{Distinct, DocIter};
+use crate::{DocumentId, FieldId, Index, Result};
+
+/// The maximum number of facet values of the distinct attribute for which the
+/// `FacetValuesDistinct` is used instead of the `FacetDistinct`.
+pub const FACET_VALUES_DISTINCT_MAX_VALUES: u64 = 1000;
+
+/// A distinct implementer that is backed by the documents ids of every facet value.
+///
+/// It returns the same documents as the `FacetDistinct` but the documents ids of all the facet
+/// values of the distinct attribute are loaded once, instead of retrieving the facet values of
+/// every returned document. The facet values that contain the current document are found in
+/// memory and their documents are added to the excluded set, it is much faster when the
+/// distinct attribute only has a few values.
+pub struct FacetValuesDistinct {
+    values: Rc<Vec<RoaringBitmap>>,
+}
+
+impl FacetValuesDistinct {
+    pub fn new(distinct: FieldId, index: &Index, txn: &heed::RoTxn) -> Result<Self> {
+        let mut values = Vec::new();
+        for result in index.facet_string_iter(txn, distinct)? {
+            let (_normalized, _original, docids) = result?;
+            values.push(docids);
+        }
+        for result in index.facet_number_iter(txn, distinct)? {
+            let (_value, docids) = result?;
+            values.push(docids);
+        }
+        Ok(Self { values: Rc::new(values) })
+    }
+}
+
+pub struct FacetValuesDistinctIter {
+    candidates: RoaringBitmap,
+    values: Rc<Vec<RoaringBitmap>>,
+    /// The indexes of the facet values that contain candidates and that weren't excluded yet.
+    remaining: Vec<usize>,
+    excluded: RoaringBitmap,
+    iter_offset: usize,
+}
+
+impl FacetValuesDistinctIter {
+    fn next_inner(&mut self) -> Result<Option<DocumentId>> {
+        self.candidates -= &self.excluded;
+
+        let mut candidates_iter = self.candidates.iter().skip(self.iter_offset);
+        match candidates_iter.next() {
+            Some(id) => {
+                // The documents of the facet values of this document are excluded, these facet
+                // values will never be found in the remaining candidates again.
+                let values = &self.values;
+                let excluded = &mut self.excluded;
+                self.remaining.retain(|&value| {
+                    let docids = &values[value];
+                    if docids.contains(id) {
+                        *excluded |= docids;
+                        false
+                    } else {
+                        true
+                    }
+                });
+                self.excluded.remove(id);
+                self.iter_offset += 1;
+
+                Ok(Some(id))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl Iterator for FacetValuesDistinctIter {
+    type Item = Result<DocumentId>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_inner().transpose()
+    }
+}
+
+impl DocIter for FacetValuesDistinctIter {
+    fn into_excluded(self) -> RoaringBitmap {
+        self.excluded
+    }
+}
+
+impl Distinct for FacetValuesDistinct {
+    type Iter = FacetValuesDistinctIter;
+
+    fn distinct(&mut self, candidates: RoaringBitmap, excluded: RoaringBitmap) -> Self::Iter {
+        let remaining = self
+            .values
+            .iter()
+            .enumerate()
+            .filter(|(_, docids)| !docids.is_disjoint(&candidates))
+            .map(|(value, _)| value)
+            .collect();
+
+        FacetValuesDistinctIter {
+            candidates,
+            values: self.values.clone(),
+            remaining,
+            excluded,
+            iter_offset: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test::{generate_index, validate_distinct_candidates};
+    use super::super::FacetDistinct;
+    use super::*;
+
+    macro_rules! test_facet_values_distinct {
+        ($name:ident, $distinct:literal) => {
+            #[test]
+            fn $name() {
+                let (index, fid, candidates) = generate_index($distinct);
+                let txn = index.read_txn().unwrap();
+                let mut values_distinct = FacetValuesDistinct::new(fid, &index, &txn).unwrap();
+                let excluded = RoaringBitmap::new();
+                let mut iter = values_distinct.distinct(candidates.clone(), excluded.clone());
+                let count = validate_distinct_candidates(iter.by_ref(), fid, &index);
+                let excluded_values = iter.into_excluded();
+                assert_eq!(count as u64 + excluded_values.len(), candidates.len());
+
+                // The same documents are returned than with the facet distinct.
+                let values_distinct: Vec<_> = values_distinct
+                    .distinct(candidates.clone(), excluded.clone())
+                    .map(|id| id.unwrap())
+                    .collect();
+                let facet_distinct: Vec<_> = FacetDistinct::new(fid, &index, &txn)
+                    .distinct(candidates, excluded)
+                    .map(|id| id.unwrap())
+                    .collect();
+                assert_eq!(values_distinct, facet_distinct);
+            }
+        };
+    }
+
+    test_facet_values_distinct!(test_string, "txt");
+    test_facet_values_distinct!(test_strings, "txts");
+    test_facet_values_distinct!(test_number, "cat-int");
+}
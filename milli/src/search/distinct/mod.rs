@@ -1,7 +1,12 @@
 mod facet_distinct;
+mod facet_group;
+mod facet_values_distinct;
 mod noop_distinct;
 
 pub use facet_distinct::FacetDistinct;
+pub(crate) use facet_distinct::{facet_number_values, facet_string_values};
+pub use facet_group::FacetGroup;
+pub use facet_values_distinct::{FacetValuesDistinct, FACET_VALUES_DISTINCT_MAX_VALUES};
 pub use noop_distinct::NoopDistinct;
 use roaring::RoaringBitmap;
 
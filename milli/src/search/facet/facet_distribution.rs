@@ -1,4 +1,5 @@
-use std::collections::{BTreeMap, HashSet};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashSet};
 use std::ops::Bound::Unbounded;
 use std::{fmt, mem};
 
@@ -17,16 +18,51 @@ use crate::{FieldId, Index, Result};
 /// the system to choose between one algorithm or another.
 const CANDIDATES_THRESHOLD: u64 = 3000;
 
+/// The maximum number of values returned for every facet when
+/// neither the index settings nor the distribution specify one.
+pub const DEFAULT_VALUES_PER_FACET: usize = 100;
+
+/// The number of candidates the facet values are counted on when the distribution
+/// is approximate, the counts are then scaled to the number of candidates.
+pub const APPROXIMATE_SAMPLE_SIZE: u64 = 100_000;
+
+/// Defines which values are kept when a facet has more values than
+/// the maximum number of values per facet, the selection is deterministic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderBy {
+    /// The first values in the lexicographic order are kept.
+    Lexicographic,
+    /// The values with the highest counts are kept, ties are broken lexicographically.
+    Count,
+}
+
+impl Default for OrderBy {
+    fn default() -> OrderBy {
+        OrderBy::Lexicographic
+    }
+}
+
 pub struct FacetDistribution<'a> {
     facets: Option<HashSet<String>>,
     candidates: Option<RoaringBitmap>,
+    max_values_per_facet: Option<usize>,
+    order_by: OrderBy,
+    approximate: bool,
     rtxn: &'a heed::RoTxn<'a>,
     index: &'a Index,
 }
 
 impl<'a> FacetDistribution<'a> {
     pub fn new(rtxn: &'a heed::RoTxn, index: &'a Index) -> FacetDistribution<'a> {
-        FacetDistribution { facets: None, candidates: None, rtxn, index }
+        FacetDistribution {
+            facets: None,
+            candidates: None,
+            max_values_per_facet: None,
+            order_by: OrderBy::default(),
+            approximate: false,
+            rtxn,
+            index,
+        }
     }
 
     pub fn facets<I: IntoIterator<Item = A>, A: AsRef<str>>(&mut self, names: I) -> &mut Self {
@@ -39,6 +75,27 @@ impl<'a> FacetDistribution<'a> {
         self
     }
 
+    /// Overrides the maximum number of values per facet defined in the settings of the index.
+    pub fn max_values_per_facet(&mut self, max: usize) -> &mut Self {
+        self.max_values_per_facet = Some(max);
+        self
+    }
+
+    pub fn order_by(&mut self, order_by: OrderBy) -> &mut Self {
+        self.order_by = order_by;
+        self
+    }
+
+    /// Counts the facet values on a sample of the candidates when there are more than
+    /// [`APPROXIMATE_SAMPLE_SIZE`] of them, the counts are scaled to the number of candidates.
+    ///
+    /// The counts are estimations and the values that are rare among the candidates can
+    /// be missing, the facet levels are explored with a much smaller set of candidates.
+    pub fn approximate(&mut self, approximate: bool) -> &mut Self {
+        self.approximate = approximate;
+        self
+    }
+
     /// There is a small amount of candidates OR we ask for facet string values so we
     /// decide to iterate over the facet values of each one of them, one by one.
     fn facet_distribution_from_documents(
@@ -46,10 +103,11 @@ impl<'a> FacetDistribution<'a> {
         field_id: FieldId,
         facet_type: FacetType,
         candidates: &RoaringBitmap,
-        distribution: &mut BTreeMap<String, u64>,
+        selector: &mut FacetValuesSelector,
     ) -> heed::Result<()> {
         match facet_type {
             FacetType::Number => {
+                let mut distribution = BTreeMap::new();
                 let mut key_buffer: Vec<_> = field_id.to_be_bytes().iter().copied().collect();
 
                 let db = self.index.field_id_docid_facet_f64s;
@@ -66,6 +124,10 @@ impl<'a> FacetDistribution<'a> {
                         *distribution.entry(value.to_string()).or_insert(0) += 1;
                     }
                 }
+
+                for (value, count) in distribution {
+                    selector.insert(value, count);
+                }
             }
             FacetType::String => {
                 let mut normalized_distribution = BTreeMap::new();
@@ -89,10 +151,9 @@ impl<'a> FacetDistribution<'a> {
                     }
                 }
 
-                let iter = normalized_distribution
-                    .into_iter()
-                    .map(|(_normalized, (original, count))| (original.to_string(), count));
-                distribution.extend(iter);
+                for (_normalized, (original, count)) in normalized_distribution {
+                    selector.insert(original.to_string(), count);
+                }
             }
         }
 
@@ -105,7 +166,7 @@ impl<'a> FacetDistribution<'a> {
         &self,
         field_id: FieldId,
         candidates: &RoaringBitmap,
-        distribution: &mut BTreeMap<String, u64>,
+        selector: &mut FacetValuesSelector,
     ) -> heed::Result<()> {
         let iter =
             FacetNumberIter::new_non_reducing(self.rtxn, self.index, field_id, candidates.clone())?;
@@ -114,7 +175,7 @@ impl<'a> FacetDistribution<'a> {
             let (value, mut docids) = result?;
             docids &= candidates;
             if !docids.is_empty() {
-                distribution.insert(value.to_string(), docids.len());
+                selector.insert(value.to_string(), docids.len());
             }
         }
 
@@ -125,7 +186,7 @@ impl<'a> FacetDistribution<'a> {
         &self,
         field_id: FieldId,
         candidates: &RoaringBitmap,
-        distribution: &mut BTreeMap<String, u64>,
+        selector: &mut FacetValuesSelector,
     ) -> heed::Result<()> {
         let iter =
             FacetStringIter::new_non_reducing(self.rtxn, self.index, field_id, candidates.clone())?;
@@ -134,7 +195,7 @@ impl<'a> FacetDistribution<'a> {
             let (_normalized, original, mut docids) = result?;
             docids &= candidates;
             if !docids.is_empty() {
-                distribution.insert(original.to_string(), docids.len());
+                selector.insert(original.to_string(), docids.len());
             }
         }
 
@@ -146,15 +207,14 @@ impl<'a> FacetDistribution<'a> {
     fn facet_values_from_raw_facet_database(
         &self,
         field_id: FieldId,
-    ) -> heed::Result<BTreeMap<String, u64>> {
-        let mut distribution = BTreeMap::new();
-
+        selector: &mut FacetValuesSelector,
+    ) -> heed::Result<()> {
         let db = self.index.facet_id_f64_docids;
         let range = FacetNumberRange::new(self.rtxn, db, field_id, 0, Unbounded, Unbounded)?;
 
         for result in range {
             let ((_, _, value, _), docids) = result?;
-            distribution.insert(value.to_string(), docids.len());
+            selector.insert(value.to_string(), docids.len());
         }
 
         let iter = self
@@ -164,56 +224,59 @@ impl<'a> FacetDistribution<'a> {
             .prefix_iter(self.rtxn, &field_id.to_be_bytes())?
             .remap_key_type::<FacetStringLevelZeroCodec>();
 
-        let mut normalized_distribution = BTreeMap::new();
+        // The facet strings are ordered by their normalized values, the count of each value
+        // is final as soon as it is read, it is directly given to the selector.
         for result in iter {
-            let ((_, normalized_value), (original_value, docids)) = result?;
-            normalized_distribution.insert(normalized_value, (original_value, docids.len()));
+            let ((_, _normalized_value), (original_value, docids)) = result?;
+            selector.insert(original_value.to_string(), docids.len());
         }
 
-        let iter = normalized_distribution
-            .into_iter()
-            .map(|(_normalized, (original, count))| (original.to_string(), count));
-        distribution.extend(iter);
+        Ok(())
+    }
 
-        Ok(distribution)
+    /// Returns all the facet values of the field with their counts.
+    pub(crate) fn facet_values(&self, field_id: FieldId) -> heed::Result<BTreeMap<String, u64>> {
+        let mut selector = FacetValuesSelector::new(usize::MAX, OrderBy::Lexicographic);
+        self.select_facet_values(field_id, &mut selector)?;
+        Ok(selector.into_values())
     }
 
-    fn facet_values(&self, field_id: FieldId) -> heed::Result<BTreeMap<String, u64>> {
+    /// Gives the facet values of the field to the selector, one at a time, the values
+    /// are never all kept in memory when there are many candidates.
+    fn select_facet_values(
+        &self,
+        field_id: FieldId,
+        selector: &mut FacetValuesSelector,
+    ) -> heed::Result<()> {
         use FacetType::{Number, String};
 
         match self.candidates {
+            Some(ref candidates)
+                if self.approximate && candidates.len() > APPROXIMATE_SAMPLE_SIZE =>
+            {
+                let sample = sample_candidates(candidates, APPROXIMATE_SAMPLE_SIZE);
+                selector.scale = candidates.len() as f64 / sample.len() as f64;
+                self.facet_numbers_distribution_from_facet_levels(field_id, &sample, selector)?;
+                self.facet_strings_distribution_from_facet_levels(field_id, &sample, selector)?;
+                Ok(())
+            }
             Some(ref candidates) => {
                 // Classic search, candidates were specified, we must return facet values only related
                 // to those candidates. We also enter here for facet strings for performance reasons.
-                let mut distribution = BTreeMap::new();
                 if candidates.len() <= CANDIDATES_THRESHOLD {
-                    self.facet_distribution_from_documents(
-                        field_id,
-                        Number,
-                        candidates,
-                        &mut distribution,
-                    )?;
-                    self.facet_distribution_from_documents(
-                        field_id,
-                        String,
-                        candidates,
-                        &mut distribution,
-                    )?;
+                    self.facet_distribution_from_documents(field_id, Number, candidates, selector)?;
+                    self.facet_distribution_from_documents(field_id, String, candidates, selector)?;
                 } else {
                     self.facet_numbers_distribution_from_facet_levels(
-                        field_id,
-                        candidates,
-                        &mut distribution,
+                        field_id, candidates, selector,
                     )?;
                     self.facet_strings_distribution_from_facet_levels(
-                        field_id,
-                        candidates,
-                        &mut distribution,
+                        field_id, candidates, selector,
                     )?;
                 }
-                Ok(distribution)
+                Ok(())
             }
-            None => self.facet_values_from_raw_facet_database(field_id),
+            None => self.facet_values_from_raw_facet_database(field_id, selector),
         }
     }
 
@@ -235,11 +298,17 @@ impl<'a> FacetDistribution<'a> {
             None => filterable_fields,
         };
 
+        let max_values_per_facet = match self.max_values_per_facet {
+            Some(max) => max,
+            None => self.index.max_values_per_facet(self.rtxn)?.unwrap_or(DEFAULT_VALUES_PER_FACET),
+        };
+
         let mut distribution = BTreeMap::new();
         for name in fields {
             if let Some(fid) = fields_ids_map.id(&name) {
-                let values = self.facet_values(fid)?;
-                distribution.insert(name, values);
+                let mut selector = FacetValuesSelector::new(max_values_per_facet, self.order_by);
+                self.select_facet_values(fid, &mut selector)?;
+                distribution.insert(name, selector.into_values());
             }
         }
 
@@ -247,13 +316,182 @@ impl<'a> FacetDistribution<'a> {
     }
 }
 
+/// Keeps at most `max` facet values while they are given one at a time, in any order,
+/// the memory used only depends on `max` and not on the number of values of the facet.
+struct FacetValuesSelector {
+    max: usize,
+    /// The ratio between the number of candidates and the number of candidates
+    /// the values were counted on, it is one when the counts are exact.
+    scale: f64,
+    selected: Selected,
+}
+
+/// The selected values in heaps whose greatest entry is the first to be evicted.
+enum Selected {
+    /// The lexicographically greatest value is evicted.
+    Lexicographic(BinaryHeap<(String, u64)>),
+    /// The value with the lowest count is evicted, the lexicographically greatest
+    /// value is evicted between values with the same count.
+    Count(BinaryHeap<Reverse<(u64, Reverse<String>)>>),
+}
+
+impl FacetValuesSelector {
+    fn new(max: usize, order_by: OrderBy) -> FacetValuesSelector {
+        let selected = match order_by {
+            OrderBy::Lexicographic => Selected::Lexicographic(BinaryHeap::new()),
+            OrderBy::Count => Selected::Count(BinaryHeap::new()),
+        };
+        FacetValuesSelector { max, scale: 1.0, selected }
+    }
+
+    fn insert(&mut self, value: String, count: u64) {
+        let count = (count as f64 * self.scale).round() as u64;
+        match &mut self.selected {
+            Selected::Lexicographic(heap) => {
+                heap.push((value, count));
+                if heap.len() > self.max {
+                    heap.pop();
+                }
+            }
+            Selected::Count(heap) => {
+                heap.push(Reverse((count, Reverse(value))));
+                if heap.len() > self.max {
+                    heap.pop();
+                }
+            }
+        }
+    }
+
+    fn into_values(self) -> BTreeMap<String, u64> {
+        match self.selected {
+            Selected::Lexicographic(heap) => heap.into_iter().collect(),
+            Selected::Count(heap) => {
+                heap.into_iter().map(|Reverse((count, Reverse(value)))| (value, count)).collect()
+            }
+        }
+    }
+}
+
+/// Returns at most `size` candidates evenly spread among the given ones.
+fn sample_candidates(candidates: &RoaringBitmap, size: u64) -> RoaringBitmap {
+    let step = ((candidates.len() + size - 1) / size) as usize;
+    candidates.iter().step_by(step.max(1)).collect()
+}
+
 impl fmt::Debug for FacetDistribution<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let FacetDistribution { facets, candidates, rtxn: _, index: _ } = self;
+        let FacetDistribution {
+            facets,
+            candidates,
+            max_values_per_facet,
+            order_by,
+            approximate,
+            rtxn: _,
+            index: _,
+        } = self;
 
         f.debug_struct("FacetDistribution")
             .field("facets", facets)
             .field("candidates", candidates)
+            .field("max_values_per_facet", max_values_per_facet)
+            .field("order_by", order_by)
+            .field("approximate", approximate)
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::{btreemap, hashset};
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::update::{IndexDocuments, Settings};
+
+    #[test]
+    fn max_values_per_facet() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("color") });
+        builder.set_max_values_per_facet(2);
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": 0, "color": "red" },
+            { "id": 1, "color": "red" },
+            { "id": 2, "color": "red" },
+            { "id": 3, "color": "blue" },
+            { "id": 4, "color": "green" },
+            { "id": 5, "color": "green" },
+            { "id": 6, "color": "yellow" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let distribution = index.facets_distribution(&rtxn).execute().unwrap();
+        let expected = btreemap! { S("blue") => 1, S("green") => 2 };
+        assert_eq!(distribution["color"], expected);
+
+        let distribution =
+            index.facets_distribution(&rtxn).order_by(OrderBy::Count).execute().unwrap();
+        let expected = btreemap! { S("green") => 2, S("red") => 3 };
+        assert_eq!(distribution["color"], expected);
+
+        let distribution = index
+            .facets_distribution(&rtxn)
+            .candidates((0..6).collect())
+            .max_values_per_facet(1)
+            .order_by(OrderBy::Count)
+            .execute()
+            .unwrap();
+        let expected = btreemap! { S("red") => 3 };
+        assert_eq!(distribution["color"], expected);
+    }
+
+    #[test]
+    fn bounded_values_selection() {
+        let values = [("d", 2), ("b", 5), ("e", 5), ("a", 1), ("f", 9), ("c", 2), ("g", 1)];
+
+        let mut selector = FacetValuesSelector::new(3, OrderBy::Lexicographic);
+        for (value, count) in values.iter() {
+            selector.insert(value.to_string(), *count);
+        }
+        let expected = btreemap! { S("a") => 1, S("b") => 5, S("c") => 2 };
+        assert_eq!(selector.into_values(), expected);
+
+        // The values with the same count are kept in the lexicographic order.
+        let mut selector = FacetValuesSelector::new(3, OrderBy::Count);
+        for (value, count) in values.iter() {
+            selector.insert(value.to_string(), *count);
+        }
+        let expected = btreemap! { S("b") => 5, S("e") => 5, S("f") => 9 };
+        assert_eq!(selector.into_values(), expected);
+
+        let mut selector = FacetValuesSelector::new(4, OrderBy::Count);
+        for (value, count) in values.iter().rev() {
+            selector.insert(value.to_string(), *count);
+        }
+        let expected = btreemap! { S("b") => 5, S("c") => 2, S("e") => 5, S("f") => 9 };
+        assert_eq!(selector.into_values(), expected);
+
+        // The counts of a sample are scaled to the number of candidates.
+        let mut selector = FacetValuesSelector::new(10, OrderBy::Count);
+        selector.scale = 2.5;
+        selector.insert(S("a"), 3);
+        assert_eq!(selector.into_values(), btreemap! { S("a") => 8 });
+    }
+
+    #[test]
+    fn sample_candidates_evenly() {
+        let candidates: RoaringBitmap = (0..1000).collect();
+        let sample = sample_candidates(&candidates, 100);
+        assert_eq!(sample.len(), 100);
+        assert_eq!(sample.iter().take(3).collect::<Vec<_>>(), vec![0, 10, 20]);
+
+        let sample = sample_candidates(&candidates, 300);
+        assert!(sample.len() <= 300);
+        assert_eq!(sample_candidates(&candidates, 2000), candidates);
+    }
+}
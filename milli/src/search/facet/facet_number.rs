@@ -79,7 +79,13 @@ impl<'t> FacetNumberRevRange<'t> {
             Excluded(left) => Excluded((field_id, level, left, f64::MIN)),
             Unbounded => Included((field_id, level, f64::MIN, f64::MIN)),
         };
-        let right_bound = Included((field_id, level, f64::MAX, f64::MAX));
+        // The iteration starts from the right bound, we must bound it to avoid
+        // skipping all the entries that are greater than the right bound.
+        let right_bound = match right {
+            Included(right) => Included((field_id, level, right, f64::MAX)),
+            Excluded(right) => Excluded((field_id, level, right, f64::MIN)),
+            Unbounded => Included((field_id, level, f64::MAX, f64::MAX)),
+        };
         let iter = db.lazily_decode_data().rev_range(rtxn, &(left_bound, right_bound))?;
         Ok(FacetNumberRevRange { iter, end: right })
     }
@@ -246,3 +252,67 @@ impl<'t> Iterator for FacetNumberIter<'t> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use big_s::S;
+    use maplit::hashset;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::documents::{DocumentBatchBuilder, DocumentBatchReader};
+    use crate::index::tests::TempIndex;
+    use crate::update::{IndexDocuments, Settings};
+
+    #[test]
+    fn reverse_iteration_matches_ascending_iteration() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("price") });
+        builder.execute(|_, _| ()).unwrap();
+
+        let documents: Vec<Value> = (0..100).map(|i| json!({ "id": i, "price": i })).collect();
+        let mut cursor = Cursor::new(Vec::new());
+        let mut builder = DocumentBatchBuilder::new(&mut cursor).unwrap();
+        builder.add_documents(documents).unwrap();
+        builder.finish().unwrap();
+        cursor.set_position(0);
+        let content = DocumentBatchReader::from_reader(cursor).unwrap();
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let fid = index.fields_ids_map(&rtxn).unwrap().id("price").unwrap();
+        let db = index.facet_id_f64_docids;
+
+        let values = |left, right| -> Vec<f64> {
+            FacetNumberRevRange::new(&rtxn, db, fid, 0, left, right)
+                .unwrap()
+                .map(|result| result.unwrap().0)
+                .map(|(_fid, _level, left, _right)| left)
+                .collect()
+        };
+        let expected: Vec<_> = (10..=20).rev().map(f64::from).collect();
+        assert_eq!(values(Included(10.), Included(20.)), expected);
+        let expected: Vec<_> = (10..20).rev().map(f64::from).collect();
+        assert_eq!(values(Included(10.), Excluded(20.)), expected);
+
+        let documents_ids = index.documents_ids(&rtxn).unwrap();
+        let ascending: Vec<_> =
+            FacetNumberIter::new_reducing(&rtxn, &index, fid, documents_ids.clone())
+                .unwrap()
+                .map(|r| r.unwrap().0)
+                .collect();
+        let mut descending: Vec<_> =
+            FacetNumberIter::new_reverse_reducing(&rtxn, &index, fid, documents_ids)
+                .unwrap()
+                .map(|r| r.unwrap().0)
+                .collect();
+        descending.reverse();
+        assert_eq!(ascending.len(), 100);
+        assert_eq!(ascending, descending);
+    }
+}
@@ -233,7 +233,13 @@ impl<'t> FacetStringGroupRevRange<'t> {
             Excluded(left) => Excluded((field_id, level, left, u32::MIN)),
             Unbounded => Included((field_id, level, u32::MIN, u32::MIN)),
         };
-        let right_bound = Included((field_id, level, u32::MAX, u32::MAX));
+        // The iteration starts from the right bound, we must bound it to avoid
+        // skipping all the entries that are greater than the right bound.
+        let right_bound = match right {
+            Included(right) => Included((field_id, level, right, u32::MAX)),
+            Excluded(right) => Excluded((field_id, level, right, u32::MIN)),
+            Unbounded => Included((field_id, level, u32::MAX, u32::MAX)),
+        };
         let iter = db.lazily_decode_data().rev_range(rtxn, &(left_bound, right_bound))?;
         Ok(FacetStringGroupRevRange { iter, end: right })
     }
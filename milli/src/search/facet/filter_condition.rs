@@ -1,57 +1,134 @@
-use std::collections::HashSet;
-use std::fmt::Debug;
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug};
 use std::ops::Bound::{self, Excluded, Included};
 use std::result::Result as StdResult;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use either::Either;
-use heed::types::DecodeIgnore;
+use heed::types::{DecodeIgnore, OwnedType};
 use itertools::Itertools;
+use levenshtein_automata::Distance;
 use log::debug;
 use pest::error::{Error as PestError, ErrorVariant};
 use pest::iterators::{Pair, Pairs};
-use pest::Parser;
+use pest::{Parser, Span};
 use roaring::RoaringBitmap;
+use rstar::AABB;
 
 use self::FilterCondition::*;
 use self::Operator::*;
 use super::parser::{FilterParser, Rule, PREC_CLIMBER};
 use super::FacetNumberRange;
-use crate::error::UserError;
+use crate::error::{Error, UserError};
+use crate::facet::{facet_string_from_key, facet_string_key};
+use crate::facet_range_cache::FacetLevelCumulatives;
 use crate::heed_codec::facet::{
     FacetLevelValueF64Codec, FacetStringLevelZeroCodec, FacetStringLevelZeroValueCodec,
+    StringValueCodec,
 };
+use crate::search::build_dfa;
 use crate::{
-    distance_between_two_points, CboRoaringBitmapCodec, FieldId, FieldsIdsMap, Index, Result,
+    distance_between_two_points, rfc3339_timestamp, CboRoaringBitmapCodec,
+    CboRoaringBitmapLenCodec, FieldId, FieldsIdsMap, FilterCapabilities, Index, Result,
+    RoaringBitmapLenCodec, BEU16,
 };
 
+/// The error returned when a filter is made on a field that is not filterable, it keeps
+/// the available filterable fields and the one that is the nearest to the given field.
+#[derive(Debug)]
+pub struct NonFilterableAttributeError {
+    pub field: String,
+    pub valid_fields: HashSet<String>,
+    pub suggestion: Option<String>,
+    error: PestError<Rule>,
+}
+
+impl fmt::Display for NonFilterableAttributeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Operator {
     GreaterThan(f64),
     GreaterThanOrEqual(f64),
     Equal(Option<f64>, String),
     NotEqual(Option<f64>, String),
+    /// Equal to any one of the values.
+    In(Vec<(Option<f64>, String)>),
+    NotIn(Vec<(Option<f64>, String)>),
+    /// The field is in the document, whatever its value is.
+    Exists,
+    NotExists,
+    /// The field is in the document and its value is null.
+    IsNull,
+    IsNotNull,
+    /// The field is in the document and its value is an empty string, array or object.
+    IsEmpty,
+    IsNotEmpty,
+    Fuzzy(String),
+    NotFuzzy(String),
+    /// The string value contains the given substring, every string facet value of
+    /// the field is scanned which makes it slow on the fields with many distinct values.
+    Contains(String),
+    NotContains(String),
+    /// The string value starts with the given prefix.
+    StartsWith(String),
+    NotStartsWith(String),
     LowerThan(f64),
     LowerThanOrEqual(f64),
     Between(f64, f64),
     GeoLowerThan([f64; 2], f64),
     GeoGreaterThan([f64; 2], f64),
+    /// The point is in the box delimited by its top left and bottom right corners,
+    /// the box crosses the antimeridian when its left longitude is the greater one.
+    GeoBoundingBox([f64; 2], [f64; 2]),
+    /// The point is inside the polygon delimited by the vertices, the polygon
+    /// is closed between its last and first vertices.
+    GeoPolygon(Vec<[f64; 2]>),
 }
 
 impl Operator {
     /// This method can return two operations in case it must express
     /// an OR operation for the between case (i.e. `TO`).
-    fn negate(self) -> (Self, Option<Self>) {
-        match self {
+    fn negate(self, fid: FieldId) -> FilterCondition {
+        let (a, b) = match self {
             GreaterThan(n) => (LowerThanOrEqual(n), None),
             GreaterThanOrEqual(n) => (LowerThan(n), None),
             Equal(n, s) => (NotEqual(n, s), None),
             NotEqual(n, s) => (Equal(n, s), None),
+            In(values) => (NotIn(values), None),
+            NotIn(values) => (In(values), None),
+            Exists => (NotExists, None),
+            NotExists => (Exists, None),
+            IsNull => (IsNotNull, None),
+            IsNotNull => (IsNull, None),
+            IsEmpty => (IsNotEmpty, None),
+            IsNotEmpty => (IsEmpty, None),
+            Fuzzy(s) => (NotFuzzy(s), None),
+            NotFuzzy(s) => (Fuzzy(s), None),
+            Contains(s) => (NotContains(s), None),
+            NotContains(s) => (Contains(s), None),
+            StartsWith(s) => (NotStartsWith(s), None),
+            NotStartsWith(s) => (StartsWith(s), None),
             LowerThan(n) => (GreaterThanOrEqual(n), None),
             LowerThanOrEqual(n) => (GreaterThan(n), None),
             Between(n, m) => (LowerThan(n), Some(GreaterThan(m))),
             GeoLowerThan(point, distance) => (GeoGreaterThan(point, distance), None),
             GeoGreaterThan(point, distance) => (GeoLowerThan(point, distance), None),
+            // The documents outside of an area are the documents that are not inside of it.
+            op @ GeoBoundingBox(..) | op @ GeoPolygon(_) => {
+                return Not(Box::new(Operator(fid, op)))
+            }
+        };
+
+        match b {
+            None => Operator(fid, a),
+            Some(b) => Or(Box::new(Operator(fid, a)), Box::new(Operator(fid, b))),
         }
     }
 }
@@ -61,6 +138,9 @@ pub enum FilterCondition {
     Operator(FieldId, Operator),
     Or(Box<Self>, Box<Self>),
     And(Box<Self>, Box<Self>),
+    /// The documents that don't match the condition, including the documents
+    /// that don't contain the fields of the condition.
+    Not(Box<Self>),
     Empty,
 }
 
@@ -119,7 +199,16 @@ impl FilterCondition {
         let filterable_fields = index.filterable_fields(rtxn)?;
         let lexed =
             FilterParser::parse(Rule::prgm, expression).map_err(UserError::InvalidFilter)?;
-        FilterCondition::from_pairs(&fields_ids_map, &filterable_fields, lexed)
+        let result =
+            FilterCondition::from_pairs(&fields_ids_map, &filterable_fields, lexed.clone());
+        // We keep track of the fields the users try to filter on to help the operators
+        // to discover which fields should be declared as filterable.
+        if let Err(Error::UserError(UserError::NonFilterableAttribute(error))) = &result {
+            index.record_filter_attempt(&error.field);
+        }
+        let condition = result?;
+        check_filter_capabilities(&index.filter_capabilities(rtxn)?, lexed)?;
+        Ok(condition)
     }
 
     fn from_pairs(
@@ -134,11 +223,23 @@ impl FilterCondition {
                 Rule::geq => Ok(Self::greater_than_or_equal(fim, ff, pair)?),
                 Rule::eq => Ok(Self::equal(fim, ff, pair)?),
                 Rule::neq => Ok(Self::equal(fim, ff, pair)?.negate()),
+                Rule::in_list => Ok(Self::in_list(fim, ff, pair)?),
+                Rule::exists => Ok(Self::without_value(fim, ff, pair, Exists)?),
+                Rule::not_exists => Ok(Self::without_value(fim, ff, pair, NotExists)?),
+                Rule::is_null => Ok(Self::without_value(fim, ff, pair, IsNull)?),
+                Rule::is_not_null => Ok(Self::without_value(fim, ff, pair, IsNotNull)?),
+                Rule::is_empty => Ok(Self::without_value(fim, ff, pair, IsEmpty)?),
+                Rule::is_not_empty => Ok(Self::without_value(fim, ff, pair, IsNotEmpty)?),
+                Rule::fuzzy => Ok(Self::fuzzy(fim, ff, pair)?),
+                Rule::contains => Ok(Self::contains(fim, ff, pair)?),
+                Rule::starts_with => Ok(Self::starts_with(fim, ff, pair)?),
                 Rule::leq => Ok(Self::lower_than_or_equal(fim, ff, pair)?),
                 Rule::less => Ok(Self::lower_than(fim, ff, pair)?),
                 Rule::between => Ok(Self::between(fim, ff, pair)?),
                 Rule::geo_radius => Ok(Self::geo_radius(fim, ff, pair)?),
-                Rule::not => Ok(Self::from_pairs(fim, ff, pair.into_inner())?.negate()),
+                Rule::geo_bounding_box => Ok(Self::geo_bounding_box(fim, ff, pair)?),
+                Rule::geo_polygon => Ok(Self::geo_polygon(fim, ff, pair)?),
+                Rule::not => Ok(Self::from_pairs(fim, ff, pair.into_inner())?.complement()),
                 Rule::prgm => Self::from_pairs(fim, ff, pair.into_inner()),
                 Rule::term => Self::from_pairs(fim, ff, pair.into_inner()),
                 _ => unreachable!(),
@@ -153,31 +254,35 @@ impl FilterCondition {
 
     fn negate(self) -> FilterCondition {
         match self {
-            Operator(fid, op) => match op.negate() {
-                (op, None) => Operator(fid, op),
-                (a, Some(b)) => Or(Box::new(Operator(fid, a)), Box::new(Operator(fid, b))),
-            },
+            Operator(fid, op) => op.negate(fid),
             Or(a, b) => And(Box::new(a.negate()), Box::new(b.negate())),
             And(a, b) => Or(Box::new(a.negate()), Box::new(b.negate())),
+            Not(condition) => *condition,
             Empty => Empty,
         }
     }
 
+    /// Negates the condition against all the documents of the index. The operators
+    /// whose negation is already evaluated against all the documents are negated directly.
+    fn complement(self) -> FilterCondition {
+        match self {
+            Operator(fid, op)
+                if matches!(op, Exists | NotExists | IsNull | IsNotNull | IsEmpty | IsNotEmpty) =>
+            {
+                op.negate(fid)
+            }
+            Not(condition) => *condition,
+            condition => Not(Box::new(condition)),
+        }
+    }
+
     fn geo_radius(
         fields_ids_map: &FieldsIdsMap,
         filterable_fields: &HashSet<String>,
         item: Pair<Rule>,
     ) -> Result<FilterCondition> {
         if !filterable_fields.contains("_geo") {
-            return Err(UserError::InvalidFilterAttribute(PestError::new_from_span(
-                ErrorVariant::CustomError {
-                    message: format!(
-                    "attribute `_geo` is not filterable, available filterable attributes are: {}",
-                    filterable_fields.iter().join(", "),
-                ),
-                },
-                item.as_span(),
-            )))?;
+            return Err(non_filterable_attribute("_geo", filterable_fields, item.as_span()))?;
         }
         let mut items = item.into_inner();
         let fid = match fields_ids_map.id("_geo") {
@@ -206,22 +311,70 @@ impl FilterCondition {
             )))?;
         }
         let (lat, lng, distance) = (&parameters[0], &parameters[1], parameters[2].0);
-        if !(-90.0..=90.0).contains(&lat.0) {
+        let point = validate_geo_point(lat.0, lat.1.clone(), lng.0, lng.1.clone())?;
+        Ok(Operator(fid, GeoLowerThan(point, distance)))
+    }
+
+    fn geo_bounding_box(
+        fields_ids_map: &FieldsIdsMap,
+        filterable_fields: &HashSet<String>,
+        item: Pair<Rule>,
+    ) -> Result<FilterCondition> {
+        if !filterable_fields.contains("_geo") {
+            return Err(non_filterable_attribute("_geo", filterable_fields, item.as_span()))?;
+        }
+        let items = item.into_inner();
+        let fid = match fields_ids_map.id("_geo") {
+            Some(fid) => fid,
+            None => return Ok(Empty),
+        };
+
+        // lexing ensures that we have two points
+        let mut points = items.take(2);
+        let top_left = geo_point(points.next().unwrap())?;
+        let bottom_right = points.next().unwrap();
+        let bottom_span = bottom_right.as_span();
+        let bottom_right = geo_point(bottom_right)?;
+        if top_left[0] < bottom_right[0] {
             return Err(UserError::InvalidFilter(PestError::new_from_span(
                 ErrorVariant::CustomError {
-                    message: format!("Latitude must be contained between -90 and 90 degrees."),
+                    message: "The top latitude of the `_geoBoundingBox` filter must be \
+                        greater than or equal to its bottom latitude."
+                        .to_string(),
                 },
-                lat.1.clone(),
+                bottom_span,
             )))?;
-        } else if !(-180.0..=180.0).contains(&lng.0) {
+        }
+        Ok(Operator(fid, GeoBoundingBox(top_left, bottom_right)))
+    }
+
+    fn geo_polygon(
+        fields_ids_map: &FieldsIdsMap,
+        filterable_fields: &HashSet<String>,
+        item: Pair<Rule>,
+    ) -> Result<FilterCondition> {
+        if !filterable_fields.contains("_geo") {
+            return Err(non_filterable_attribute("_geo", filterable_fields, item.as_span()))?;
+        }
+        let span = item.as_span();
+        let items = item.into_inner();
+        let fid = match fields_ids_map.id("_geo") {
+            Some(fid) => fid,
+            None => return Ok(Empty),
+        };
+
+        let vertices = items.map(geo_point).collect::<StdResult<Vec<_>, _>>()?;
+        if vertices.len() < 3 {
             return Err(UserError::InvalidFilter(PestError::new_from_span(
                 ErrorVariant::CustomError {
-                    message: format!("Longitude must be contained between -180 and 180 degrees."),
+                    message: "The `_geoPolygon` filter expect at least three vertices: \
+                        `_geoPolygon([latitude, longitude], [latitude, longitude], ...)`"
+                        .to_string(),
                 },
-                lng.1.clone(),
+                span,
             )))?;
         }
-        Ok(Operator(fid, GeoLowerThan([lat.0, lng.0], distance)))
+        Ok(Operator(fid, GeoPolygon(vertices)))
     }
 
     fn between(
@@ -230,15 +383,13 @@ impl FilterCondition {
         item: Pair<Rule>,
     ) -> Result<FilterCondition> {
         let mut items = item.into_inner();
-        let fid = match field_id(fields_ids_map, filterable_fields, &mut items)
-            .map_err(UserError::InvalidFilterAttribute)?
-        {
+        let fid = match field_id(fields_ids_map, filterable_fields, &mut items)? {
             Some(fid) => fid,
             None => return Ok(Empty),
         };
 
-        let (lresult, _) = pest_parse(items.next().unwrap());
-        let (rresult, _) = pest_parse(items.next().unwrap());
+        let (lresult, _) = pest_parse_number(items.next().unwrap());
+        let (rresult, _) = pest_parse_number(items.next().unwrap());
 
         let lvalue = lresult.map_err(UserError::InvalidFilter)?;
         let rvalue = rresult.map_err(UserError::InvalidFilter)?;
@@ -252,35 +403,112 @@ impl FilterCondition {
         item: Pair<Rule>,
     ) -> Result<FilterCondition> {
         let mut items = item.into_inner();
-        let fid = match field_id(fields_ids_map, filterable_fields, &mut items)
-            .map_err(UserError::InvalidFilterAttribute)?
-        {
+        let fid = match field_id(fields_ids_map, filterable_fields, &mut items)? {
             Some(fid) => fid,
             None => return Ok(Empty),
         };
 
         let value = items.next().unwrap();
-        let (result, svalue) = pest_parse(value);
+        let (result, svalue) = pest_parse_number(value);
 
-        let svalue = svalue.to_lowercase();
+        let svalue = facet_string_key(&svalue.to_lowercase()).into_owned();
         Ok(Operator(fid, Equal(result.ok(), svalue)))
     }
 
+    /// Compiles the list of values into a single operator, a long list doesn't
+    /// nest the conditions like a chain of `OR` does.
+    fn in_list(
+        fields_ids_map: &FieldsIdsMap,
+        filterable_fields: &HashSet<String>,
+        item: Pair<Rule>,
+    ) -> Result<FilterCondition> {
+        let mut items = item.into_inner();
+        let fid = match field_id(fields_ids_map, filterable_fields, &mut items)? {
+            Some(fid) => fid,
+            None => return Ok(Empty),
+        };
+
+        let values = items
+            .map(|value| {
+                let (result, svalue) = pest_parse_number(value);
+                (result.ok(), facet_string_key(&svalue.to_lowercase()).into_owned())
+            })
+            .collect();
+        Ok(Operator(fid, In(values)))
+    }
+
+    /// Parses the conditions that only have a field, like `field EXISTS` or `field IS NULL`.
+    fn without_value(
+        fields_ids_map: &FieldsIdsMap,
+        filterable_fields: &HashSet<String>,
+        item: Pair<Rule>,
+        operator: Operator,
+    ) -> Result<FilterCondition> {
+        let mut items = item.into_inner();
+        match field_id(fields_ids_map, filterable_fields, &mut items)? {
+            Some(fid) => Ok(Operator(fid, operator)),
+            None => Ok(Empty),
+        }
+    }
+
+    fn fuzzy(
+        fields_ids_map: &FieldsIdsMap,
+        filterable_fields: &HashSet<String>,
+        item: Pair<Rule>,
+    ) -> Result<FilterCondition> {
+        let mut items = item.into_inner();
+        let fid = match field_id(fields_ids_map, filterable_fields, &mut items)? {
+            Some(fid) => fid,
+            None => return Ok(Empty),
+        };
+
+        let value = items.next().unwrap().as_str().to_lowercase();
+        Ok(Operator(fid, Fuzzy(value)))
+    }
+
+    fn contains(
+        fields_ids_map: &FieldsIdsMap,
+        filterable_fields: &HashSet<String>,
+        item: Pair<Rule>,
+    ) -> Result<FilterCondition> {
+        let mut items = item.into_inner();
+        let fid = match field_id(fields_ids_map, filterable_fields, &mut items)? {
+            Some(fid) => fid,
+            None => return Ok(Empty),
+        };
+
+        let value = items.next().unwrap().as_str().to_lowercase();
+        Ok(Operator(fid, Contains(value)))
+    }
+
+    fn starts_with(
+        fields_ids_map: &FieldsIdsMap,
+        filterable_fields: &HashSet<String>,
+        item: Pair<Rule>,
+    ) -> Result<FilterCondition> {
+        let mut items = item.into_inner();
+        let fid = match field_id(fields_ids_map, filterable_fields, &mut items)? {
+            Some(fid) => fid,
+            None => return Ok(Empty),
+        };
+
+        let value = items.next().unwrap().as_str().to_lowercase();
+        Ok(Operator(fid, StartsWith(value)))
+    }
+
     fn greater_than(
         fields_ids_map: &FieldsIdsMap,
         filterable_fields: &HashSet<String>,
         item: Pair<Rule>,
     ) -> Result<FilterCondition> {
         let mut items = item.into_inner();
-        let fid = match field_id(fields_ids_map, filterable_fields, &mut items)
-            .map_err(UserError::InvalidFilterAttribute)?
-        {
+        let fid = match field_id(fields_ids_map, filterable_fields, &mut items)? {
             Some(fid) => fid,
             None => return Ok(Empty),
         };
 
         let value = items.next().unwrap();
-        let (result, _svalue) = pest_parse(value);
+        let (result, _svalue) = pest_parse_number(value);
         let value = result.map_err(UserError::InvalidFilter)?;
 
         Ok(Operator(fid, GreaterThan(value)))
@@ -292,15 +520,13 @@ impl FilterCondition {
         item: Pair<Rule>,
     ) -> Result<FilterCondition> {
         let mut items = item.into_inner();
-        let fid = match field_id(fields_ids_map, filterable_fields, &mut items)
-            .map_err(UserError::InvalidFilterAttribute)?
-        {
+        let fid = match field_id(fields_ids_map, filterable_fields, &mut items)? {
             Some(fid) => fid,
             None => return Ok(Empty),
         };
 
         let value = items.next().unwrap();
-        let (result, _svalue) = pest_parse(value);
+        let (result, _svalue) = pest_parse_number(value);
         let value = result.map_err(UserError::InvalidFilter)?;
 
         Ok(Operator(fid, GreaterThanOrEqual(value)))
@@ -312,15 +538,13 @@ impl FilterCondition {
         item: Pair<Rule>,
     ) -> Result<FilterCondition> {
         let mut items = item.into_inner();
-        let fid = match field_id(fields_ids_map, filterable_fields, &mut items)
-            .map_err(UserError::InvalidFilterAttribute)?
-        {
+        let fid = match field_id(fields_ids_map, filterable_fields, &mut items)? {
             Some(fid) => fid,
             None => return Ok(Empty),
         };
 
         let value = items.next().unwrap();
-        let (result, _svalue) = pest_parse(value);
+        let (result, _svalue) = pest_parse_number(value);
         let value = result.map_err(UserError::InvalidFilter)?;
 
         Ok(Operator(fid, LowerThan(value)))
@@ -332,15 +556,13 @@ impl FilterCondition {
         item: Pair<Rule>,
     ) -> Result<FilterCondition> {
         let mut items = item.into_inner();
-        let fid = match field_id(fields_ids_map, filterable_fields, &mut items)
-            .map_err(UserError::InvalidFilterAttribute)?
-        {
+        let fid = match field_id(fields_ids_map, filterable_fields, &mut items)? {
             Some(fid) => fid,
             None => return Ok(Empty),
         };
 
         let value = items.next().unwrap();
-        let (result, _svalue) = pest_parse(value);
+        let (result, _svalue) = pest_parse_number(value);
         let value = result.map_err(UserError::InvalidFilter)?;
 
         Ok(Operator(fid, LowerThanOrEqual(value)))
@@ -456,6 +678,30 @@ impl FilterCondition {
         Ok(())
     }
 
+    /// Returns the cumulative documents ids of the groups of the highest level of the field,
+    /// they are computed once for every commit of the index.
+    fn facet_cumulatives(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        numbers_db: heed::Database<FacetLevelValueF64Codec, CboRoaringBitmapCodec>,
+        field_id: FieldId,
+        level: u8,
+    ) -> Result<Arc<FacetLevelCumulatives>> {
+        let commits_count = index.commits_count(rtxn)?;
+        if let Some(cumulatives) = index.cached_facet_cumulatives(commits_count, field_id) {
+            return Ok(cumulatives);
+        }
+
+        let cumulatives =
+            Arc::new(FacetLevelCumulatives::compute(rtxn, numbers_db, field_id, level)?);
+        // A write transaction sees the count of its own commit, but its content can still
+        // change or be aborted, only the committed snapshots are cached.
+        if !index.is_uncommitted(rtxn)? {
+            index.cache_facet_cumulatives(commits_count, field_id, cumulatives.clone());
+        }
+        Ok(cumulatives)
+    }
+
     fn evaluate_operator(
         rtxn: &heed::RoTxn,
         index: &Index,
@@ -505,6 +751,116 @@ impl FilterCondition {
                 )?;
                 return Ok((all_numbers_ids | all_strings_ids) - docids);
             }
+            In(values) => {
+                let mut docids = RoaringBitmap::new();
+                for (number, string) in values {
+                    let operator = Equal(*number, string.clone());
+                    docids |= Self::evaluate_operator(
+                        rtxn, index, numbers_db, strings_db, field_id, &operator,
+                    )?;
+                }
+                return Ok(docids);
+            }
+            NotIn(values) => {
+                let all_numbers_ids = if values.iter().any(|(number, _)| number.is_some()) {
+                    index.number_faceted_documents_ids(rtxn, field_id)?
+                } else {
+                    RoaringBitmap::new()
+                };
+                let all_strings_ids = index.string_faceted_documents_ids(rtxn, field_id)?;
+                let operator = In(values.clone());
+                let docids = Self::evaluate_operator(
+                    rtxn, index, numbers_db, strings_db, field_id, &operator,
+                )?;
+                return Ok((all_numbers_ids | all_strings_ids) - docids);
+            }
+            Exists => return Ok(index.exists_faceted_documents_ids(rtxn, field_id)?),
+            NotExists => {
+                let all_ids = index.documents_ids(rtxn)?;
+                let docids = index.exists_faceted_documents_ids(rtxn, field_id)?;
+                return Ok(all_ids - docids);
+            }
+            IsNull => return Ok(index.null_faceted_documents_ids(rtxn, field_id)?),
+            IsNotNull => {
+                let all_ids = index.documents_ids(rtxn)?;
+                let docids = index.null_faceted_documents_ids(rtxn, field_id)?;
+                return Ok(all_ids - docids);
+            }
+            IsEmpty => return Ok(index.empty_faceted_documents_ids(rtxn, field_id)?),
+            IsNotEmpty => {
+                let all_ids = index.documents_ids(rtxn)?;
+                let docids = index.empty_faceted_documents_ids(rtxn, field_id)?;
+                return Ok(all_ids - docids);
+            }
+            Fuzzy(string) => {
+                // The facet string values are stored normalized, in lowercase, we accept
+                // the values that starts with the given string with some typos allowed,
+                // as many as for a query word of the same length.
+                let len = string.chars().count();
+                let typos = if len < index.min_word_size_for_one_typo(rtxn)? as usize {
+                    0
+                } else if len < index.min_word_size_for_two_typos(rtxn)? as usize {
+                    1
+                } else {
+                    2
+                };
+                let dfa = build_dfa(string, typos, true);
+                let mut docids = RoaringBitmap::new();
+                for result in index.facet_string_iter(rtxn, field_id)? {
+                    let (normalized, _original, string_docids) = result?;
+                    if let Distance::Exact(_) = dfa.eval(normalized) {
+                        docids |= string_docids;
+                    }
+                }
+                return Ok(docids);
+            }
+            NotFuzzy(string) => {
+                let all_strings_ids = index.string_faceted_documents_ids(rtxn, field_id)?;
+                let operator = Fuzzy(string.clone());
+                let docids = Self::evaluate_operator(
+                    rtxn, index, numbers_db, strings_db, field_id, &operator,
+                )?;
+                return Ok(all_strings_ids - docids);
+            }
+            Contains(string) => {
+                // There is no index of the substrings of the facet values,
+                // all the normalized values of the field are scanned.
+                let mut docids = RoaringBitmap::new();
+                for result in index.facet_string_iter(rtxn, field_id)? {
+                    let (normalized, original, string_docids) = result?;
+                    // The values that are too long are truncated and suffixed by a hash.
+                    if facet_string_from_key(normalized, original).contains(string.as_str()) {
+                        docids |= string_docids;
+                    }
+                }
+                return Ok(docids);
+            }
+            NotContains(string) => {
+                let all_strings_ids = index.string_faceted_documents_ids(rtxn, field_id)?;
+                let operator = Contains(string.clone());
+                let docids = Self::evaluate_operator(
+                    rtxn, index, numbers_db, strings_db, field_id, &operator,
+                )?;
+                return Ok(all_strings_ids - docids);
+            }
+            StartsWith(string) => {
+                // The facet strings are sorted, only the values that start with the prefix
+                // are read from the database.
+                let mut docids = RoaringBitmap::new();
+                for result in index.facet_string_prefix_iter(rtxn, field_id, string)? {
+                    let (_normalized, _original, string_docids) = result?;
+                    docids |= string_docids;
+                }
+                return Ok(docids);
+            }
+            NotStartsWith(string) => {
+                let all_strings_ids = index.string_faceted_documents_ids(rtxn, field_id)?;
+                let operator = StartsWith(string.clone());
+                let docids = Self::evaluate_operator(
+                    rtxn, index, numbers_db, strings_db, field_id, &operator,
+                )?;
+                return Ok(all_strings_ids - docids);
+            }
             LowerThan(val) => (Included(f64::MIN), Excluded(*val)),
             LowerThanOrEqual(val) => (Included(f64::MIN), Included(*val)),
             Between(left, right) => (Included(*left), Included(*right)),
@@ -524,6 +880,47 @@ impl FilterCondition {
 
                 return Ok(result);
             }
+            GeoBoundingBox([top, left], [bottom, right]) => {
+                let rtree = match index.geo_rtree(rtxn)? {
+                    Some(rtree) => rtree,
+                    None => return Ok(RoaringBitmap::new()),
+                };
+
+                // The points are stored as latitude and longitude, a box that crosses
+                // the antimeridian is split in two boxes, one on each side of it.
+                let envelopes = if left <= right {
+                    vec![AABB::from_corners([*bottom, *left], [*top, *right])]
+                } else {
+                    vec![
+                        AABB::from_corners([*bottom, *left], [*top, 180.0]),
+                        AABB::from_corners([*bottom, -180.0], [*top, *right]),
+                    ]
+                };
+
+                let result = envelopes
+                    .iter()
+                    .flat_map(|envelope| rtree.locate_in_envelope(envelope))
+                    .map(|point| point.data)
+                    .collect();
+
+                return Ok(result);
+            }
+            GeoPolygon(vertices) => {
+                let rtree = match index.geo_rtree(rtxn)? {
+                    Some(rtree) => rtree,
+                    None => return Ok(RoaringBitmap::new()),
+                };
+
+                // Only the points in the bounding box of the polygon are tested.
+                let envelope = AABB::from_points(vertices);
+                let result = rtree
+                    .locate_in_envelope(&envelope)
+                    .filter(|point| point_in_polygon(point.geom(), vertices))
+                    .map(|point| point.data)
+                    .collect();
+
+                return Ok(result);
+            }
             GeoGreaterThan(point, distance) => {
                 let result = Self::evaluate_operator(
                     rtxn,
@@ -548,6 +945,34 @@ impl FilterCondition {
         match biggest_level {
             Some(level) => {
                 let mut output = RoaringBitmap::new();
+                // The open-ended ranges take the cumulative documents ids of the groups of the
+                // highest level that are entirely in the range, only the part of the range
+                // that is before, or after, these groups must be explored.
+                let (left, right) = match (left, right) {
+                    (left, Included(r)) if r == f64::MAX => {
+                        let cumulatives =
+                            Self::facet_cumulatives(rtxn, index, numbers_db, field_id, level)?;
+                        match cumulatives.greater_than(left) {
+                            Some((docids, first_left)) => {
+                                output |= docids;
+                                (left, Excluded(first_left))
+                            }
+                            None => (left, right),
+                        }
+                    }
+                    (Included(l), right) if l == f64::MIN => {
+                        let cumulatives =
+                            Self::facet_cumulatives(rtxn, index, numbers_db, field_id, level)?;
+                        match cumulatives.lower_than(right) {
+                            Some((docids, last_right)) => {
+                                output |= docids;
+                                (Excluded(last_right), right)
+                            }
+                            None => (left, right),
+                        }
+                    }
+                    bounds => bounds,
+                };
                 Self::explore_facet_number_levels(
                     rtxn,
                     numbers_db,
@@ -563,7 +988,34 @@ impl FilterCondition {
         }
     }
 
+    /// Returns the internal ids of the documents that match the condition, without running
+    /// a search, it can be used to select the documents to export or to delete.
+    ///
+    /// An operator only matches the documents that contain its field, `x != 1` doesn't match
+    /// the documents without an `x` field. The `NOT` of a condition is evaluated against all
+    /// the documents of the index, `NOT x = 1` matches the documents without an `x` field.
+    /// A condition on a filterable field that no document contains yet matches no document.
+    ///
+    /// The string values that are RFC 3339 dates are also faceted as their number of seconds
+    /// since the Unix epoch, in the same space as the numbers of the field. A number matches
+    /// these dates, `x = 1622505600` matches `"2021-06-01T00:00:00Z"`, and the timestamps of
+    /// the dates are counted in the number distribution of the field.
     pub fn evaluate(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<RoaringBitmap> {
+        self.evaluate_abortable(rtxn, index, None)
+    }
+
+    /// Evaluates the condition and returns [`Error::SearchAborted`] as soon as
+    /// the abort signal is raised, it is polled before every operator.
+    pub(crate) fn evaluate_abortable(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        abort_signal: Option<&AtomicBool>,
+    ) -> Result<RoaringBitmap> {
+        if abort_signal.map_or(false, |signal| signal.load(Ordering::Relaxed)) {
+            return Err(Error::SearchAborted);
+        }
+
         let numbers_db = index.facet_id_f64_docids;
         let strings_db = index.facet_id_string_docids;
 
@@ -572,18 +1024,146 @@ impl FilterCondition {
                 Self::evaluate_operator(rtxn, index, numbers_db, strings_db, *fid, op)
             }
             Or(lhs, rhs) => {
-                let lhs = lhs.evaluate(rtxn, index)?;
-                let rhs = rhs.evaluate(rtxn, index)?;
+                let lhs = lhs.evaluate_abortable(rtxn, index, abort_signal)?;
+                let rhs = rhs.evaluate_abortable(rtxn, index, abort_signal)?;
                 Ok(lhs | rhs)
             }
-            And(lhs, rhs) => {
-                let lhs = lhs.evaluate(rtxn, index)?;
-                let rhs = rhs.evaluate(rtxn, index)?;
-                Ok(lhs & rhs)
+            And(..) => {
+                // The operands of a chain of ANDs are evaluated from the one that is estimated
+                // to match the fewest documents and the evaluation stops as soon as the
+                // intersection is empty. The negated operands come last, they are removed
+                // from the candidates.
+                let mut operands = Vec::new();
+                self.and_operands(&mut operands);
+                let mut operands = operands
+                    .into_iter()
+                    .map(|operand| Ok((operand.estimated_cardinality(rtxn, index)?, operand)))
+                    .collect::<Result<Vec<_>>>()?;
+                operands.sort_by_key(|(cardinality, operand)| {
+                    (matches!(operand, Not(_)), *cardinality)
+                });
+
+                let mut candidates: Option<RoaringBitmap> = None;
+                for (_, operand) in operands {
+                    // The complement against all the documents is only computed when
+                    // all the operands are negated.
+                    let intersection = match (candidates.take(), operand) {
+                        (Some(candidates), Not(condition)) => {
+                            candidates - condition.evaluate_abortable(rtxn, index, abort_signal)?
+                        }
+                        (Some(candidates), operand) => {
+                            candidates & operand.evaluate_abortable(rtxn, index, abort_signal)?
+                        }
+                        (None, operand) => operand.evaluate_abortable(rtxn, index, abort_signal)?,
+                    };
+                    if intersection.is_empty() {
+                        return Ok(intersection);
+                    }
+                    candidates = Some(intersection);
+                }
+
+                Ok(candidates.unwrap_or_default())
+            }
+            Not(condition) => {
+                let all_ids = index.documents_ids(rtxn)?;
+                let docids = condition.evaluate_abortable(rtxn, index, abort_signal)?;
+                Ok(all_ids - docids)
             }
             Empty => Ok(RoaringBitmap::new()),
         }
     }
+
+    /// Pushes the operands of the chain of ANDs, the nested ANDs are flattened.
+    fn and_operands<'a>(&'a self, operands: &mut Vec<&'a FilterCondition>) {
+        match self {
+            And(lhs, rhs) => {
+                lhs.and_operands(operands);
+                rhs.and_operands(operands);
+            }
+            condition => operands.push(condition),
+        }
+    }
+
+    /// An estimation of the number of documents matching the condition that only reads the
+    /// lengths of the bitmaps. The lookups of a single value are exact, or almost, the other
+    /// operators are bounded by the number of documents that contain their field.
+    fn estimated_cardinality(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<u64> {
+        let documents_count = index.number_of_documents(rtxn)?;
+        let cardinality = match self {
+            Empty => 0,
+            Operator(fid, operator) => {
+                let field_count = |db: heed::Database<OwnedType<BEU16>, CboRoaringBitmapCodec>| {
+                    db.remap_data_type::<CboRoaringBitmapLenCodec>()
+                        .get(rtxn, &BEU16::new(*fid))
+                        .map(Option::unwrap_or_default)
+                };
+                let value_count = |number: &Option<f64>, string: &str| -> heed::Result<u64> {
+                    let strings_db = index
+                        .facet_id_string_docids
+                        .remap_data_type::<StringValueCodec<RoaringBitmapLenCodec>>();
+                    let strings_count =
+                        strings_db.get(rtxn, &(*fid, string))?.map_or(0, |(_, count)| count);
+                    let numbers_count = match number {
+                        Some(n) => index
+                            .facet_id_f64_docids
+                            .remap_data_type::<CboRoaringBitmapLenCodec>()
+                            .get(rtxn, &(*fid, 0, *n, *n))?
+                            .unwrap_or_default(),
+                        None => 0,
+                    };
+                    Ok(strings_count + numbers_count)
+                };
+                let exists_count = field_count(index.facet_id_exists_docids)?;
+                match operator {
+                    Equal(number, string) => value_count(number, string)?,
+                    NotEqual(number, string) => {
+                        exists_count.saturating_sub(value_count(number, string)?)
+                    }
+                    In(values) => values.iter().try_fold(0, |sum, (number, string)| {
+                        value_count(number, string).map(|count| sum + count)
+                    })?,
+                    Exists => exists_count,
+                    NotExists => documents_count.saturating_sub(exists_count),
+                    IsNull => field_count(index.facet_id_is_null_docids)?,
+                    IsNotNull => {
+                        documents_count.saturating_sub(field_count(index.facet_id_is_null_docids)?)
+                    }
+                    IsEmpty => field_count(index.facet_id_is_empty_docids)?,
+                    IsNotEmpty => {
+                        documents_count.saturating_sub(field_count(index.facet_id_is_empty_docids)?)
+                    }
+                    GreaterThan(_)
+                    | GreaterThanOrEqual(_)
+                    | LowerThan(_)
+                    | LowerThanOrEqual(_)
+                    | Between(..)
+                    | NotIn(_)
+                    | Fuzzy(_)
+                    | NotFuzzy(_)
+                    | Contains(_)
+                    | NotContains(_)
+                    | StartsWith(_)
+                    | NotStartsWith(_)
+                    | GeoLowerThan(..)
+                    | GeoGreaterThan(..)
+                    | GeoBoundingBox(..)
+                    | GeoPolygon(_) => exists_count,
+                }
+            }
+            Or(lhs, rhs) => {
+                lhs.estimated_cardinality(rtxn, index)? + rhs.estimated_cardinality(rtxn, index)?
+            }
+            And(lhs, rhs) => cmp::min(
+                lhs.estimated_cardinality(rtxn, index)?,
+                rhs.estimated_cardinality(rtxn, index)?,
+            ),
+            Not(condition) => {
+                documents_count.saturating_sub(condition.estimated_cardinality(rtxn, index)?)
+            }
+        };
+
+        Ok(cmp::min(cardinality, documents_count))
+    }
 }
 
 /// Retrieve the field id base on the pest value.
@@ -598,7 +1178,7 @@ fn field_id(
     fields_ids_map: &FieldsIdsMap,
     filterable_fields: &HashSet<String>,
     items: &mut Pairs<Rule>,
-) -> StdResult<Option<FieldId>, PestError<Rule>> {
+) -> StdResult<Option<FieldId>, UserError> {
     // lexing ensures that we at least have a key
     let key = items.next().unwrap();
     if key.as_rule() == Rule::reserved {
@@ -621,23 +1201,151 @@ fn field_id(
                 key
             ),
         };
-        return Err(PestError::new_from_span(ErrorVariant::CustomError { message }, key.as_span()));
+        let error = PestError::new_from_span(ErrorVariant::CustomError { message }, key.as_span());
+        return Err(UserError::InvalidFilterAttribute(error));
     }
 
     if !filterable_fields.contains(key.as_str()) {
-        return Err(PestError::new_from_span(
+        return Err(non_filterable_attribute(key.as_str(), filterable_fields, key.as_span()));
+    }
+
+    Ok(fields_ids_map.id(key.as_str()))
+}
+
+/// Parses the latitude and the longitude of a `[latitude, longitude]` point.
+fn geo_point(pair: Pair<Rule>) -> StdResult<[f64; 2], UserError> {
+    // lexing ensures that we have two coordinates
+    let mut coordinates = pair.into_inner();
+    let lat = coordinates.next().unwrap();
+    let lng = coordinates.next().unwrap();
+    let (lat_span, lng_span) = (lat.as_span(), lng.as_span());
+    let lat = pest_parse::<f64>(lat).0.map_err(UserError::InvalidFilter)?;
+    let lng = pest_parse::<f64>(lng).0.map_err(UserError::InvalidFilter)?;
+    validate_geo_point(lat, lat_span, lng, lng_span)
+}
+
+/// Checks that the latitude and the longitude are in the ranges of the valid coordinates.
+fn validate_geo_point(
+    lat: f64,
+    lat_span: Span,
+    lng: f64,
+    lng_span: Span,
+) -> StdResult<[f64; 2], UserError> {
+    if !(-90.0..=90.0).contains(&lat) {
+        return Err(UserError::InvalidFilter(PestError::new_from_span(
             ErrorVariant::CustomError {
-                message: format!(
-                    "attribute `{}` is not filterable, available filterable attributes are: {}.",
-                    key.as_str(),
-                    filterable_fields.iter().join(", "),
-                ),
+                message: "Latitude must be contained between -90 and 90 degrees.".to_string(),
             },
-            key.as_span(),
-        ));
+            lat_span,
+        )));
+    } else if !(-180.0..=180.0).contains(&lng) {
+        return Err(UserError::InvalidFilter(PestError::new_from_span(
+            ErrorVariant::CustomError {
+                message: "Longitude must be contained between -180 and 180 degrees.".to_string(),
+            },
+            lng_span,
+        )));
     }
+    Ok([lat, lng])
+}
 
-    Ok(fields_ids_map.id(key.as_str()))
+/// Returns `true` if the point is inside the polygon, the latitudes and the longitudes
+/// are used as plane coordinates and the polygon is closed between its last and first vertices.
+fn point_in_polygon(point: &[f64; 2], vertices: &[[f64; 2]]) -> bool {
+    let [lat, lng] = *point;
+    let mut inside = false;
+    let mut previous = vertices[vertices.len() - 1];
+    for &vertex in vertices {
+        let ([lat_a, lng_a], [lat_b, lng_b]) = (vertex, previous);
+        // Counts the edges crossed by a ray going from the point toward the greater longitudes.
+        if (lat_a > lat) != (lat_b > lat)
+            && lng < (lng_b - lng_a) * (lat - lat_a) / (lat_b - lat_a) + lng_a
+        {
+            inside = !inside;
+        }
+        previous = vertex;
+    }
+    inside
+}
+
+/// Returns an error if one of the conditions uses a filter that is not supported
+/// by the capabilities of its field.
+fn check_filter_capabilities(
+    capabilities: &HashMap<String, FilterCapabilities>,
+    pairs: Pairs<Rule>,
+) -> StdResult<(), UserError> {
+    if capabilities.is_empty() {
+        return Ok(());
+    }
+
+    for pair in pairs.flatten() {
+        let comparison = match pair.as_rule() {
+            Rule::eq | Rule::neq | Rule::in_list => false,
+            Rule::greater | Rule::geq | Rule::less | Rule::leq | Rule::between => true,
+            _ => continue,
+        };
+
+        // lexing ensures that we at least have a key
+        let key = pair.into_inner().next().unwrap();
+        if let Some(field_capabilities) = capabilities.get(key.as_str()) {
+            let (supported, kind) = if comparison {
+                (field_capabilities.comparison, "comparison")
+            } else {
+                (field_capabilities.equality, "equality")
+            };
+            if !supported {
+                let message =
+                    format!("The `{}` field doesn't support the {} filters.", key.as_str(), kind);
+                let error =
+                    PestError::new_from_span(ErrorVariant::CustomError { message }, key.as_span());
+                return Err(UserError::InvalidFilterAttribute(error));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Generates the error returned when a filter is made on a field that is not filterable,
+/// it keeps the available filterable fields and the one that is the nearest to the given
+/// field, if any, so that the error can be displayed in a richer way.
+fn non_filterable_attribute(
+    field: &str,
+    filterable_fields: &HashSet<String>,
+    span: Span,
+) -> UserError {
+    let suggestion = nearest_field(field, filterable_fields);
+    let mut message = format!(
+        "attribute `{}` is not filterable, available filterable attributes are: {}.",
+        field,
+        filterable_fields.iter().sorted().join(", "),
+    );
+    if let Some(suggestion) = &suggestion {
+        message.push_str(&format!(" Did you mean `{}`?", suggestion));
+    }
+
+    UserError::NonFilterableAttribute(NonFilterableAttributeError {
+        field: field.to_string(),
+        valid_fields: filterable_fields.clone(),
+        suggestion,
+        error: PestError::new_from_span(ErrorVariant::CustomError { message }, span),
+    })
+}
+
+/// Returns the field that is the nearest to the given one, at most two typos away.
+///
+/// The comparison is case insensitive and when multiple fields are at the same
+/// distance the smallest one in lexicographic order is returned.
+fn nearest_field(field: &str, fields: &HashSet<String>) -> Option<String> {
+    let dfa = build_dfa(&field.to_lowercase(), 2, false);
+    fields
+        .iter()
+        .filter_map(|candidate| match dfa.eval(candidate.to_lowercase()) {
+            Distance::Exact(distance) => Some((distance, candidate)),
+            Distance::AtLeast(_) => None,
+        })
+        .min()
+        .map(|(_, candidate)| candidate.clone())
 }
 
 /// Tries to parse the pest pair into the type `T` specified, always returns
@@ -660,6 +1368,16 @@ where
     (result, pair.as_str().to_string())
 }
 
+/// Parses a number or an RFC 3339 date, the dates are compared as
+/// their number of seconds since the Unix epoch.
+fn pest_parse_number(pair: Pair<Rule>) -> (StdResult<f64, pest::error::Error<Rule>>, String) {
+    let (result, svalue) = pest_parse(pair);
+    match rfc3339_timestamp(&svalue) {
+        Some(timestamp) if result.is_err() => (Ok(timestamp), svalue),
+        _otherwise => (result, svalue),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use big_s::S;
@@ -667,7 +1385,9 @@ mod tests {
     use maplit::hashset;
 
     use super::*;
-    use crate::update::Settings;
+    use crate::facet::MAX_FACET_STRING_LENGTH;
+    use crate::update::{DeleteDocuments, IndexDocuments, Settings};
+    use crate::Error;
 
     #[test]
     fn string() {
@@ -697,12 +1417,392 @@ mod tests {
         assert_eq!(condition, expected);
 
         let condition = FilterCondition::from_str(&rtxn, &index, "NOT channel = ponce").unwrap();
-        let expected = Operator(0, Operator::NotEqual(None, S("ponce")));
+        let expected = Not(Box::new(Operator(0, Operator::Equal(None, S("ponce")))));
         assert_eq!(condition, expected);
     }
 
     #[test]
-    fn number() {
+    fn in_list() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("color"), S("size") });
+        builder.execute(|_, _| ()).unwrap();
+
+        let content = documents!([
+            { "id": 0, "color": "Red", "size": 38 },
+            { "id": 1, "color": "blue", "size": 40 },
+            { "id": 2, "color": "green", "size": 42 },
+            { "id": 3, "color": "yellow", "size": 44 },
+            { "id": 4, "size": 38 }
+        ]);
+        let builder = IndexDocuments::new(&mut wtxn, &index, 1);
+        builder.execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let color = index.fields_ids_map(&rtxn).unwrap().id("color").unwrap();
+        let condition =
+            FilterCondition::from_str(&rtxn, &index, "color IN [Red, \"blue\", 'light green']")
+                .unwrap();
+        let values = vec![(None, S("red")), (None, S("blue")), (None, S("light green"))];
+        assert_eq!(condition, Operator(color, Operator::In(values.clone())));
+
+        let condition =
+            FilterCondition::from_str(&rtxn, &index, "NOT color IN [Red, blue, 'light green']")
+                .unwrap();
+        assert_eq!(condition, Not(Box::new(Operator(color, Operator::In(values)))));
+
+        let evaluate = |filter: &str| -> Vec<u32> {
+            let condition = FilterCondition::from_str(&rtxn, &index, filter).unwrap();
+            condition.evaluate(&rtxn, &index).unwrap().into_iter().collect()
+        };
+        assert_eq!(evaluate("color IN [red, blue, green]"), vec![0, 1, 2]);
+        assert_eq!(evaluate("color IN [red]"), vec![0]);
+        assert_eq!(evaluate("color IN []"), Vec::<u32>::new());
+        assert_eq!(evaluate("size IN [38, 42]"), vec![0, 2, 4]);
+        assert_eq!(evaluate("NOT color IN [red, blue]"), vec![2, 3, 4]);
+        assert_eq!(evaluate("color IN [red, blue] AND size IN [40, 44]"), vec![1]);
+    }
+
+    #[test]
+    fn not_expression() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("color"), S("size"), S("brand") });
+        builder.execute(|_, _| ()).unwrap();
+
+        let content = documents!([
+            { "id": 0, "color": "red", "size": 38 },
+            { "id": 1, "color": "blue", "size": 40 },
+            { "id": 2, "color": "red", "size": 42 },
+            { "id": 3, "size": 44 },
+            { "id": 4 }
+        ]);
+        let builder = IndexDocuments::new(&mut wtxn, &index, 1);
+        builder.execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| -> Vec<u32> {
+            let condition = FilterCondition::from_str(&rtxn, &index, filter).unwrap();
+            condition.evaluate(&rtxn, &index).unwrap().into_iter().collect()
+        };
+
+        // The documents without the field are part of the complement.
+        assert_eq!(evaluate("color != red"), vec![1]);
+        assert_eq!(evaluate("NOT color = red"), vec![1, 3, 4]);
+        assert_eq!(evaluate("NOT size > 40"), vec![0, 1, 4]);
+        assert_eq!(evaluate("NOT size 39 TO 43"), vec![0, 3, 4]);
+
+        // Any expression can be negated, even a negated one.
+        assert_eq!(evaluate("NOT (color = red OR size = 44)"), vec![1, 4]);
+        assert_eq!(evaluate("NOT (color = red AND size = 38)"), vec![1, 2, 3, 4]);
+        assert_eq!(evaluate("NOT NOT color = red"), vec![0, 2]);
+        assert_eq!(evaluate("NOT (NOT color = red OR size = 42)"), vec![0]);
+        assert_eq!(evaluate("size >= 40 AND NOT color = red"), vec![1, 3]);
+        assert_eq!(evaluate("NOT color = red AND NOT size = 44"), vec![1, 4]);
+
+        // No document contains a field that was never indexed.
+        assert_eq!(evaluate("brand = nike"), Vec::<u32>::new());
+        assert_eq!(evaluate("NOT brand = nike"), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn exists() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("color") });
+        builder.execute(|_, _| ()).unwrap();
+
+        let content = documents!([
+            { "id": 0, "color": "red" },
+            { "id": 1, "color": null },
+            { "id": 2, "color": [] },
+            { "id": 3, "size": 40 },
+            { "id": 4, "color": { "name": "blue" } }
+        ]);
+        let builder = IndexDocuments::new(&mut wtxn, &index, 1);
+        builder.execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let color = index.fields_ids_map(&rtxn).unwrap().id("color").unwrap();
+        let condition = FilterCondition::from_str(&rtxn, &index, "color EXISTS").unwrap();
+        assert_eq!(condition, Operator(color, Operator::Exists));
+        let condition = FilterCondition::from_str(&rtxn, &index, "NOT color EXISTS").unwrap();
+        assert_eq!(condition, Operator(color, Operator::NotExists));
+        let condition = FilterCondition::from_str(&rtxn, &index, "color NOT EXISTS").unwrap();
+        assert_eq!(condition, Operator(color, Operator::NotExists));
+
+        let evaluate = |filter: &str| -> Vec<u32> {
+            let condition = FilterCondition::from_str(&rtxn, &index, filter).unwrap();
+            condition.evaluate(&rtxn, &index).unwrap().into_iter().collect()
+        };
+        // The field exists whatever its value is, even null or empty.
+        assert_eq!(evaluate("color EXISTS"), vec![0, 1, 2, 4]);
+        assert_eq!(evaluate("color NOT EXISTS"), vec![3]);
+        assert_eq!(evaluate("color EXISTS AND color != red"), vec![]);
+        assert!(FilterCondition::from_str(&rtxn, &index, "size EXISTS").is_err());
+        drop(rtxn);
+
+        // The deleted documents are removed from the existing fields.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = DeleteDocuments::new(&mut wtxn, &index, 2).unwrap();
+        builder.delete_external_id("0");
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let condition = FilterCondition::from_str(&rtxn, &index, "color EXISTS").unwrap();
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![1, 2, 4]);
+    }
+
+    #[test]
+    fn is_null_and_is_empty() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("color") });
+        builder.execute(|_, _| ()).unwrap();
+
+        let content = documents!([
+            { "id": 0, "color": "red" },
+            { "id": 1, "color": null },
+            { "id": 2, "color": "" },
+            { "id": 3, "color": [] },
+            { "id": 4, "color": {} },
+            { "id": 5, "size": 40 },
+            { "id": 6, "color": " " }
+        ]);
+        let builder = IndexDocuments::new(&mut wtxn, &index, 1);
+        builder.execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let color = index.fields_ids_map(&rtxn).unwrap().id("color").unwrap();
+        let condition = FilterCondition::from_str(&rtxn, &index, "color IS NULL").unwrap();
+        assert_eq!(condition, Operator(color, Operator::IsNull));
+        let condition = FilterCondition::from_str(&rtxn, &index, "NOT color IS NULL").unwrap();
+        assert_eq!(condition, Operator(color, Operator::IsNotNull));
+        let condition = FilterCondition::from_str(&rtxn, &index, "color IS NOT EMPTY").unwrap();
+        assert_eq!(condition, Operator(color, Operator::IsNotEmpty));
+
+        let evaluate = |filter: &str| -> Vec<u32> {
+            let condition = FilterCondition::from_str(&rtxn, &index, filter).unwrap();
+            condition.evaluate(&rtxn, &index).unwrap().into_iter().collect()
+        };
+        assert_eq!(evaluate("color IS NULL"), vec![1]);
+        assert_eq!(evaluate("color IS EMPTY"), vec![2, 3, 4]);
+        // The documents without the field are neither null nor empty.
+        assert_eq!(evaluate("color IS NOT NULL"), vec![0, 2, 3, 4, 5, 6]);
+        assert_eq!(evaluate("color IS NOT EMPTY"), vec![0, 1, 5, 6]);
+        assert_eq!(
+            evaluate("color IS NULL OR color IS EMPTY OR color NOT EXISTS"),
+            vec![1, 2, 3, 4, 5]
+        );
+        drop(rtxn);
+
+        // The deleted documents are removed from the null and empty fields.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = DeleteDocuments::new(&mut wtxn, &index, 2).unwrap();
+        builder.delete_external_id("1");
+        builder.delete_external_id("2");
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let condition = FilterCondition::from_str(&rtxn, &index, "color IS NULL").unwrap();
+        assert!(condition.evaluate(&rtxn, &index).unwrap().is_empty());
+        let condition = FilterCondition::from_str(&rtxn, &index, "color IS EMPTY").unwrap();
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![3, 4]);
+    }
+
+    #[test]
+    fn fuzzy() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        // Set the filterable fields to be the brand.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("brand") });
+        builder.execute(|_, _| ()).unwrap();
+
+        let content = documents!([
+            { "id": 0, "brand": "Adidas Originals" },
+            { "id": 1, "brand": "adidas" },
+            { "id": 2, "brand": "Nike" },
+            { "id": 3, "brand": "Puma" }
+        ]);
+        let builder = IndexDocuments::new(&mut wtxn, &index, 1);
+        builder.execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // Test that the facet condition is correctly generated.
+        let rtxn = index.read_txn().unwrap();
+        let brand = index.fields_ids_map(&rtxn).unwrap().id("brand").unwrap();
+        let condition = FilterCondition::from_str(&rtxn, &index, "brand ~ Addidas").unwrap();
+        let expected = Operator(brand, Operator::Fuzzy(S("addidas")));
+        assert_eq!(condition, expected);
+
+        let condition = FilterCondition::from_str(&rtxn, &index, "NOT brand ~ addidas").unwrap();
+        let expected = Not(Box::new(Operator(brand, Operator::Fuzzy(S("addidas")))));
+        assert_eq!(condition, expected);
+
+        // Test that the facet values are matched with the typos.
+        let condition = FilterCondition::from_str(&rtxn, &index, "brand ~ \"addidas\"").unwrap();
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![0, 1]);
+
+        let condition = FilterCondition::from_str(&rtxn, &index, "brand ~ nike").unwrap();
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![2]);
+
+        let condition = FilterCondition::from_str(&rtxn, &index, "NOT brand ~ addidas").unwrap();
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![2, 3]);
+        drop(rtxn);
+
+        // Test that the typos follow the min word size settings.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.set_min_word_size_for_one_typo(8);
+        builder.set_min_word_size_for_two_typos(10);
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let condition = FilterCondition::from_str(&rtxn, &index, "brand ~ addidas").unwrap();
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert!(docids.is_empty());
+
+        let condition = FilterCondition::from_str(&rtxn, &index, "brand ~ adidas").unwrap();
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![0, 1]);
+    }
+
+    #[test]
+    fn contains() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("brand") });
+        builder.execute(|_, _| ()).unwrap();
+
+        let long_brand = format!("{} Originals", "a".repeat(MAX_FACET_STRING_LENGTH));
+        let content = documents!([
+            { "id": 0, "brand": "Adidas Originals" },
+            { "id": 1, "brand": "adidas" },
+            { "id": 2, "brand": "Nike" },
+            { "id": 3, "brand": 42 },
+            { "id": 4, "brand": long_brand }
+        ]);
+        let builder = IndexDocuments::new(&mut wtxn, &index, 1);
+        builder.execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let brand = index.fields_ids_map(&rtxn).unwrap().id("brand").unwrap();
+        let condition = FilterCondition::from_str(&rtxn, &index, "brand CONTAINS DAS").unwrap();
+        let expected = Operator(brand, Operator::Contains(S("das")));
+        assert_eq!(condition, expected);
+
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![0, 1]);
+
+        // The values that are truncated in the database are matched on their whole value.
+        let condition =
+            FilterCondition::from_str(&rtxn, &index, "brand CONTAINS \"originals\"").unwrap();
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![0, 4]);
+
+        // The numbers are never matched, the documents without the value are.
+        let condition = FilterCondition::from_str(&rtxn, &index, "NOT brand CONTAINS das").unwrap();
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn starts_with() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("brand") });
+        builder.execute(|_, _| ()).unwrap();
+
+        let long_prefix = "a".repeat(MAX_FACET_STRING_LENGTH);
+        let content = documents!([
+            { "id": 0, "brand": "Adidas Originals" },
+            { "id": 1, "brand": "adidas" },
+            { "id": 2, "brand": "Nike" },
+            { "id": 3, "brand": 42 },
+            { "id": 4, "brand": format!("{}bc", long_prefix) },
+            { "id": 5, "brand": format!("{}cd", long_prefix) }
+        ]);
+        let builder = IndexDocuments::new(&mut wtxn, &index, 1);
+        builder.execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let brand = index.fields_ids_map(&rtxn).unwrap().id("brand").unwrap();
+        let condition =
+            FilterCondition::from_str(&rtxn, &index, "brand STARTS WITH \"ADI\"").unwrap();
+        let expected = Operator(brand, Operator::StartsWith(S("adi")));
+        assert_eq!(condition, expected);
+
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![0, 1]);
+
+        let condition =
+            FilterCondition::from_str(&rtxn, &index, "brand STARTS WITH nikes").unwrap();
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert!(docids.is_empty());
+
+        // The values that are truncated in the database are matched on their whole value.
+        let expression = format!("brand STARTS WITH {}b", long_prefix);
+        let condition = FilterCondition::from_str(&rtxn, &index, &expression).unwrap();
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![4]);
+
+        let condition =
+            FilterCondition::from_str(&rtxn, &index, "NOT brand STARTS WITH adi").unwrap();
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn number() {
         let path = tempfile::tempdir().unwrap();
         let mut options = EnvOpenOptions::new();
         options.map_size(10 * 1024 * 1024); // 10 MB
@@ -725,11 +1825,81 @@ mod tests {
         assert_eq!(condition, expected);
 
         let condition = FilterCondition::from_str(&rtxn, &index, "NOT timestamp 22 TO 44").unwrap();
-        let expected =
-            Or(Box::new(Operator(0, LowerThan(22.0))), Box::new(Operator(0, GreaterThan(44.0))));
+        let expected = Not(Box::new(Operator(0, Between(22.0, 44.0))));
         assert_eq!(condition, expected);
     }
 
+    #[test]
+    fn date() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("published_at") });
+        builder.execute(|_, _| ()).unwrap();
+
+        let content = documents!([
+            { "id": 0, "published_at": "2021-05-31T23:59:59Z" },
+            { "id": 1, "published_at": "2021-06-01T00:00:00Z" },
+            { "id": 2, "published_at": "2021-06-01T03:00:00+02:00" },
+            { "id": 3, "published_at": "yesterday" },
+            { "id": 4, "published_at": 1622505600 }
+        ]);
+        let builder = IndexDocuments::new(&mut wtxn, &index, 1);
+        builder.execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let published_at = index.fields_ids_map(&rtxn).unwrap().id("published_at").unwrap();
+        let condition =
+            FilterCondition::from_str(&rtxn, &index, "published_at > 2021-06-01T00:00:00Z")
+                .unwrap();
+        let expected = Operator(published_at, GreaterThan(1622505600.0));
+        assert_eq!(condition, expected);
+
+        // The dates are compared with their timezone and the timestamps are numbers.
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![2]);
+
+        let condition =
+            FilterCondition::from_str(&rtxn, &index, "published_at >= \"2021-06-01T00:00:00Z\"")
+                .unwrap();
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![1, 2, 4]);
+
+        let condition = FilterCondition::from_str(
+            &rtxn,
+            &index,
+            "published_at 2021-05-01T00:00:00Z TO 2021-05-31T23:59:59Z",
+        )
+        .unwrap();
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![0]);
+
+        // The dates are still strings.
+        let condition =
+            FilterCondition::from_str(&rtxn, &index, "published_at = yesterday").unwrap();
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![3]);
+
+        let error = FilterCondition::from_str(&rtxn, &index, "published_at > yesterday");
+        assert!(error.is_err());
+
+        // The dates share the number facets of the field, a number matches a date.
+        let condition =
+            FilterCondition::from_str(&rtxn, &index, "published_at = 1622505600").unwrap();
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![1, 4]);
+
+        let distribution = index.facets_distribution(&rtxn).execute().unwrap();
+        let published_at = &distribution["published_at"];
+        assert_eq!(published_at.get("1622505600"), Some(&2));
+        assert_eq!(published_at.get("2021-06-01T00:00:00Z"), Some(&1));
+    }
+
     #[test]
     fn parentheses() {
         let path = tempfile::tempdir().unwrap();
@@ -770,17 +1940,292 @@ mod tests {
         .unwrap();
         let expected = Or(
             Box::new(Operator(0, Operator::Equal(None, S("gotaga")))),
-            Box::new(Or(
-                Box::new(Or(
-                    Box::new(Operator(1, LowerThan(22.0))),
-                    Box::new(Operator(1, GreaterThan(44.0))),
-                )),
-                Box::new(Operator(0, Operator::Equal(None, S("ponce")))),
-            )),
+            Box::new(Not(Box::new(And(
+                Box::new(Operator(1, Between(22.0, 44.0))),
+                Box::new(Operator(0, Operator::NotEqual(None, S("ponce")))),
+            )))),
         );
         assert_eq!(condition, expected);
     }
 
+    #[test]
+    fn open_ended_ranges() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("price") });
+        builder.execute(|_, _| ()).unwrap();
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let mut builder = crate::documents::DocumentBatchBuilder::new(&mut cursor).unwrap();
+        for id in 0..200 {
+            builder.add_documents(serde_json::json!({ "id": id, "price": id % 97 })).unwrap();
+        }
+        builder.finish().unwrap();
+        cursor.set_position(0);
+        let content = crate::documents::DocumentBatchReader::from_reader(cursor).unwrap();
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // Test that the cumulative bitmaps return the same documents as a scan of the prices.
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| -> Vec<u32> {
+            let condition = FilterCondition::from_str(&rtxn, &index, filter).unwrap();
+            condition.evaluate(&rtxn, &index).unwrap().into_iter().collect()
+        };
+        for price in &[0, 1, 13, 48, 50, 95, 96, 97] {
+            let matching = |f: fn(u32, i32) -> bool| -> Vec<u32> {
+                (0..200).filter(|id| f(id % 97, *price)).collect()
+            };
+            let greater = evaluate(&format!("price > {}", price));
+            assert_eq!(greater, matching(|p, price| p as i32 > price));
+            let greater_or_equal = evaluate(&format!("price >= {}", price));
+            assert_eq!(greater_or_equal, matching(|p, price| p as i32 >= price));
+            let lower = evaluate(&format!("price < {}", price));
+            assert_eq!(lower, matching(|p, price| (p as i32) < price));
+            let lower_or_equal = evaluate(&format!("price <= {}", price));
+            assert_eq!(lower_or_equal, matching(|p, price| p as i32 <= price));
+        }
+        drop(rtxn);
+
+        // Test that the bitmaps cached for the previous commit are not used anymore.
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 200, "price": 1000 }]);
+        IndexDocuments::new(&mut wtxn, &index, 2).execute(content, |_, _| ()).unwrap();
+        let condition = FilterCondition::from_str(&wtxn, &index, "price > 96").unwrap();
+        assert_eq!(
+            condition.evaluate(&wtxn, &index).unwrap().into_iter().collect::<Vec<_>>(),
+            vec![200]
+        );
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let condition = FilterCondition::from_str(&rtxn, &index, "price > 96").unwrap();
+        assert_eq!(
+            condition.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>(),
+            vec![200]
+        );
+        drop(rtxn);
+
+        // Test that the bitmaps of an aborted transaction are not cached for the next commit.
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 201, "price": 2000 }]);
+        IndexDocuments::new(&mut wtxn, &index, 3).execute(content, |_, _| ()).unwrap();
+        let condition = FilterCondition::from_str(&wtxn, &index, "price > 96").unwrap();
+        assert_eq!(
+            condition.evaluate(&wtxn, &index).unwrap().into_iter().collect::<Vec<_>>(),
+            vec![200, 201]
+        );
+        wtxn.abort().unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 202, "price": 50 }]);
+        IndexDocuments::new(&mut wtxn, &index, 4).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let condition = FilterCondition::from_str(&rtxn, &index, "price > 96").unwrap();
+        assert_eq!(
+            condition.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>(),
+            vec![200]
+        );
+    }
+
+    #[test]
+    fn and_chain() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("channel"), S("timestamp") });
+        builder.execute(|_, _| ()).unwrap();
+
+        let content = documents!([
+            { "id": 0, "channel": "gotaga", "timestamp": 10 },
+            { "id": 1, "channel": "ponce", "timestamp": 20 },
+            { "id": 2, "channel": "gotaga", "timestamp": 30 },
+            { "id": 3, "channel": "kamet0", "timestamp": 40 }
+        ]);
+        let builder = IndexDocuments::new(&mut wtxn, &index, 1);
+        builder.execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // Test that the nested ANDs are flattened and sorted by their estimated cardinality.
+        let rtxn = index.read_txn().unwrap();
+        let channel = index.fields_ids_map(&rtxn).unwrap().id("channel").unwrap();
+        let timestamp = index.fields_ids_map(&rtxn).unwrap().id("timestamp").unwrap();
+        let condition = FilterCondition::from_str(
+            &rtxn,
+            &index,
+            "(channel = ponce OR timestamp > 35) AND channel != kamet0 AND timestamp >= 15 AND channel = gotaga",
+        )
+        .unwrap();
+        let mut operands = Vec::new();
+        condition.and_operands(&mut operands);
+        let mut operands: Vec<_> = operands
+            .into_iter()
+            .map(|operand| (operand.estimated_cardinality(&rtxn, &index).unwrap(), operand))
+            .collect();
+        operands.sort_by_key(|(cardinality, _)| *cardinality);
+        let or = Or(
+            Box::new(Operator(channel, Operator::Equal(None, S("ponce")))),
+            Box::new(Operator(timestamp, GreaterThan(35.0))),
+        );
+        let expected = vec![
+            (2, &Operator(channel, Operator::Equal(None, S("gotaga")))),
+            (3, &Operator(channel, Operator::NotEqual(None, S("kamet0")))),
+            (4, &or),
+            (4, &Operator(timestamp, GreaterThanOrEqual(15.0))),
+        ];
+        assert_eq!(operands, expected);
+
+        // Test that the evaluation of the chain is the intersection of its operands.
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert!(docids.is_empty());
+
+        let condition = FilterCondition::from_str(
+            &rtxn,
+            &index,
+            "channel != kamet0 AND timestamp >= 15 AND channel = gotaga",
+        )
+        .unwrap();
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![2]);
+
+        // Test that an operand matching no documents empties the whole chain.
+        let condition =
+            FilterCondition::from_str(&rtxn, &index, "channel = unknown AND timestamp > 0")
+                .unwrap();
+        let docids = condition.evaluate(&rtxn, &index).unwrap();
+        assert!(docids.is_empty());
+    }
+
+    #[test]
+    fn and_chain_short_circuit() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("channel"), S("timestamp") });
+        builder.execute(|_, _| ()).unwrap();
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let mut builder = crate::documents::DocumentBatchBuilder::new(&mut cursor).unwrap();
+        for id in 0..200 {
+            let document = serde_json::json!({ "id": id, "channel": "gotaga", "timestamp": id });
+            builder.add_documents(document).unwrap();
+        }
+        builder.finish().unwrap();
+        cursor.set_position(0);
+        let content = crate::documents::DocumentBatchReader::from_reader(cursor).unwrap();
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let timestamp = index.fields_ids_map(&rtxn).unwrap().id("timestamp").unwrap();
+        let commits_count = index.commits_count(&rtxn).unwrap();
+
+        // Test that the range is never evaluated, and its cumulative bitmaps never computed,
+        // when the operand estimated to match no documents empties the chain first.
+        let condition =
+            FilterCondition::from_str(&rtxn, &index, "timestamp > 10 AND channel = unknown")
+                .unwrap();
+        assert!(condition.evaluate(&rtxn, &index).unwrap().is_empty());
+        assert!(index.cached_facet_cumulatives(commits_count, timestamp).is_none());
+
+        let condition =
+            FilterCondition::from_str(&rtxn, &index, "timestamp > 10 AND channel = gotaga")
+                .unwrap();
+        assert_eq!(condition.evaluate(&rtxn, &index).unwrap().len(), 189);
+        assert!(index.cached_facet_cumulatives(commits_count, timestamp).is_some());
+    }
+
+    #[test]
+    fn non_filterable_field() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        // Set the filterable fields to be the channel and the timestamp.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("channel"), S("timestamp") });
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let error = FilterCondition::from_str(&rtxn, &index, "chanel = ponce").unwrap_err();
+        match error {
+            Error::UserError(UserError::NonFilterableAttribute(error)) => {
+                assert_eq!(error.field, "chanel");
+                assert_eq!(error.valid_fields, hashset! { S("channel"), S("timestamp") });
+                assert_eq!(error.suggestion.as_deref(), Some("channel"));
+            }
+            error => panic!("unexpected error {:?}", error),
+        }
+
+        let error = FilterCondition::from_str(&rtxn, &index, "dog = ponce").unwrap_err();
+        assert!(error.to_string().contains(
+            "attribute `dog` is not filterable, available filterable attributes are: channel, timestamp."
+        ));
+        match error {
+            Error::UserError(UserError::NonFilterableAttribute(error)) => {
+                assert_eq!(error.suggestion, None)
+            }
+            error => panic!("unexpected error {:?}", error),
+        }
+
+        // The fields the users tried to filter on are tracked.
+        FilterCondition::from_str(&rtxn, &index, "dog = milou").unwrap_err();
+        let attempts = index.non_filterable_attempts();
+        assert_eq!(attempts, vec![(S("dog"), 2), (S("chanel"), 1)]);
+        index.clear_non_filterable_attempts();
+        assert!(index.non_filterable_attempts().is_empty());
+    }
+
+    #[test]
+    fn long_facet_strings() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("title") });
+        builder.execute(|_, _| ()).unwrap();
+        let long = "Lorem ipsum ".repeat(100);
+        let longer = format!("{}dolor", long);
+        let content = documents!([
+            { "id": 0, "title": long },
+            { "id": 1, "title": longer },
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let filter = format!("title = {:?}", long.trim());
+        let condition = FilterCondition::from_str(&rtxn, &index, &filter).unwrap();
+        assert_eq!(condition.evaluate(&rtxn, &index).unwrap(), (0..1).collect());
+
+        // The distribution returns the original values.
+        let distribution = index.facets_distribution(&rtxn).execute().unwrap();
+        let titles = &distribution["title"];
+        assert_eq!(titles.get(&long), Some(&1));
+        assert_eq!(titles.get(&longer), Some(&1));
+    }
+
     #[test]
     fn reserved_field_names() {
         let path = tempfile::tempdir().unwrap();
@@ -875,7 +2320,7 @@ mod tests {
         // test the negation of the GeoLowerThan
         let condition =
             FilterCondition::from_str(&rtxn, &index, "NOT _geoRadius(50, 18, 2000.500)").unwrap();
-        let expected = Operator(0, GeoGreaterThan([50., 18.], 2000.500));
+        let expected = Not(Box::new(Operator(0, GeoLowerThan([50., 18.], 2000.500))));
         assert_eq!(condition, expected);
 
         // composition of multiple operations
@@ -887,7 +2332,7 @@ mod tests {
         .unwrap();
         let expected = Or(
             Box::new(And(
-                Box::new(Operator(0, GeoGreaterThan([1., 2.], 300.))),
+                Box::new(Not(Box::new(Operator(0, GeoLowerThan([1., 2.], 300.))))),
                 Box::new(Operator(0, GeoLowerThan([1.001, 2.002], 1000.300))),
             )),
             Box::new(Operator(1, LowerThanOrEqual(10.))),
@@ -952,6 +2397,163 @@ mod tests {
             .contains("Longitude must be contained between -180 and 180 degrees."));
     }
 
+    #[test]
+    fn geo_bounding_box() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("_geo") });
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": 0, "_geo": { "lat": 48.85, "lng": 2.35 } },
+            { "id": 1, "_geo": { "lat": 45.76, "lng": 4.83 } },
+            { "id": 2, "_geo": { "lat": -17.53, "lng": -149.56 } },
+            { "id": 3, "_geo": { "lat": -18.14, "lng": 178.44 } },
+            { "id": 4 }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| -> Vec<u32> {
+            let condition = FilterCondition::from_str(&rtxn, &index, filter).unwrap();
+            condition.evaluate(&rtxn, &index).unwrap().into_iter().collect()
+        };
+
+        let condition =
+            FilterCondition::from_str(&rtxn, &index, "_geoBoundingBox([50, 2], [48, 3])").unwrap();
+        let geo = index.fields_ids_map(&rtxn).unwrap().id("_geo").unwrap();
+        let expected = Operator(geo, GeoBoundingBox([50., 2.], [48., 3.]));
+        assert_eq!(condition, expected);
+
+        let condition =
+            FilterCondition::from_str(&rtxn, &index, "NOT _geoBoundingBox([50, 2], [48, 3])")
+                .unwrap();
+        let expected = Not(Box::new(Operator(geo, GeoBoundingBox([50., 2.], [48., 3.]))));
+        assert_eq!(condition, expected);
+
+        assert_eq!(evaluate("_geoBoundingBox([50, 2], [48, 3])"), vec![0]);
+        assert_eq!(evaluate("_geoBoundingBox([50, 0], [40, 10])"), vec![0, 1]);
+        assert_eq!(evaluate("NOT _geoBoundingBox([50, 2], [48, 3])"), vec![1, 2, 3, 4]);
+        // A box that crosses the antimeridian.
+        assert_eq!(evaluate("_geoBoundingBox([0, 170], [-20, -140])"), vec![2, 3]);
+        assert!(evaluate("_geoBoundingBox([0, 170], [-20, 175])").is_empty());
+
+        let error = FilterCondition::from_str(&rtxn, &index, "_geoBoundingBox([40, 2], [48, 3])")
+            .unwrap_err();
+        assert!(error.to_string().contains("must be greater than or equal to its bottom latitude"));
+        let error = FilterCondition::from_str(&rtxn, &index, "_geoBoundingBox([91, 2], [48, 3])")
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Latitude must be contained between -90 and 90 degrees."));
+        let error = FilterCondition::from_str(&rtxn, &index, "_geoBoundingBox([50, 2], [48, 181])")
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Longitude must be contained between -180 and 180 degrees."));
+    }
+
+    #[test]
+    fn geo_polygon() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("_geo") });
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": 0, "_geo": { "lat": 1, "lng": 1 } },
+            { "id": 1, "_geo": { "lat": 0.25, "lng": 3 } },
+            { "id": 2, "_geo": { "lat": 3, "lng": 3 } },
+            { "id": 3, "_geo": { "lat": 5, "lng": 5 } },
+            { "id": 4 }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| -> Vec<u32> {
+            let condition = FilterCondition::from_str(&rtxn, &index, filter).unwrap();
+            condition.evaluate(&rtxn, &index).unwrap().into_iter().collect()
+        };
+
+        let condition =
+            FilterCondition::from_str(&rtxn, &index, "_geoPolygon([0, 0], [0, 4], [4, 0])")
+                .unwrap();
+        let geo = index.fields_ids_map(&rtxn).unwrap().id("_geo").unwrap();
+        let expected = Operator(geo, GeoPolygon(vec![[0., 0.], [0., 4.], [4., 0.]]));
+        assert_eq!(condition, expected);
+
+        let condition =
+            FilterCondition::from_str(&rtxn, &index, "NOT _geoPolygon([0, 0], [0, 4], [4, 0])")
+                .unwrap();
+        let expected = Not(Box::new(Operator(geo, GeoPolygon(vec![[0., 0.], [0., 4.], [4., 0.]]))));
+        assert_eq!(condition, expected);
+
+        // A triangle, the points 2 and 3 are in its bounding box but not inside it.
+        assert_eq!(evaluate("_geoPolygon([0, 0], [0, 4], [4, 0])"), vec![0, 1]);
+        assert_eq!(evaluate("NOT _geoPolygon([0, 0], [0, 4], [4, 0])"), vec![2, 3, 4]);
+        // A concave polygon shaped like a U, the point 2 is in its hollow.
+        assert_eq!(
+            evaluate(
+                "_geoPolygon([0, 0], [4, 0], [4, 2], [0.5, 2], [0.5, 4], [4, 4], [4, 6], [0, 6])"
+            ),
+            vec![0, 1]
+        );
+
+        let error =
+            FilterCondition::from_str(&rtxn, &index, "_geoPolygon([0, 0], [0, 4])").unwrap_err();
+        assert!(error.to_string().contains("expect at least three vertices"));
+        let error =
+            FilterCondition::from_str(&rtxn, &index, "_geoPolygon([0, 0], [0, 4], [95, 0])")
+                .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Latitude must be contained between -90 and 90 degrees."));
+    }
+
+    #[test]
+    fn evaluate_missing_fields() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("color"), S("size") });
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": 0, "color": "red" },
+            { "id": 1, "color": "blue" },
+            { "id": 2 }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| -> Vec<u32> {
+            let condition = FilterCondition::from_str(&rtxn, &index, filter).unwrap();
+            condition.evaluate(&rtxn, &index).unwrap().into_iter().collect()
+        };
+        // The documents without the field never match an operator.
+        assert_eq!(evaluate("color = red"), vec![0]);
+        assert_eq!(evaluate("color != red"), vec![1]);
+        // The NOT is evaluated against all the documents.
+        assert_eq!(evaluate("NOT color = red"), vec![1, 2]);
+        // No document contains the size field yet.
+        assert!(evaluate("size = 12").is_empty());
+        assert_eq!(evaluate("NOT size = 12"), vec![0, 1, 2]);
+    }
+
     #[test]
     fn from_array() {
         let path = tempfile::tempdir().unwrap();
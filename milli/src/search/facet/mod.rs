@@ -1,7 +1,9 @@
-pub use self::facet_distribution::FacetDistribution;
+pub use self::facet_distribution::{
+    FacetDistribution, OrderBy, APPROXIMATE_SAMPLE_SIZE, DEFAULT_VALUES_PER_FACET,
+};
 pub use self::facet_number::{FacetNumberIter, FacetNumberRange, FacetNumberRevRange};
 pub use self::facet_string::FacetStringIter;
-pub use self::filter_condition::{FilterCondition, Operator};
+pub use self::filter_condition::{FilterCondition, NonFilterableAttributeError, Operator};
 pub(crate) use self::parser::Rule as ParserRule;
 
 mod facet_distribution;
@@ -0,0 +1,61 @@
+use std::fmt;
+use std::str::FromStr;
+
+use meilisearch_tokenizer::{Language, Script};
+
+use crate::error::UserError;
+
+/// The language of a query, used to force the analyzer to segment the query words
+/// in the way the documents written in this language have been segmented at indexing.
+///
+/// The language of a short query can't be reliably detected by the analyzer, a two
+/// characters chinese query can be detected as japanese and be wrongly segmented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    Chinese,
+    Japanese,
+    Korean,
+}
+
+impl Locale {
+    /// The script the analyzer segments the text of this language with.
+    pub(crate) fn script(&self) -> Script {
+        match self {
+            Locale::Chinese => Script::Mandarin,
+            Locale::Japanese => Script::Hiragana,
+            Locale::Korean => Script::Hangul,
+        }
+    }
+
+    /// The language the analyzer segments the text of this language with.
+    pub(crate) fn language(&self) -> Language {
+        match self {
+            Locale::Chinese => Language::Cmn,
+            Locale::Japanese => Language::Jpn,
+            Locale::Korean => Language::Kor,
+        }
+    }
+}
+
+impl FromStr for Locale {
+    type Err = UserError;
+
+    fn from_str(text: &str) -> Result<Locale, Self::Err> {
+        match text {
+            "zh" | "cmn" | "zho" => Ok(Locale::Chinese),
+            "ja" | "jpn" => Ok(Locale::Japanese),
+            "ko" | "kor" => Ok(Locale::Korean),
+            text => Err(UserError::InvalidLocale { locale: text.to_string() }),
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Locale::Chinese => f.write_str("zh"),
+            Locale::Japanese => f.write_str("ja"),
+            Locale::Korean => f.write_str("ko"),
+        }
+    }
+}
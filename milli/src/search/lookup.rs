@@ -0,0 +1,216 @@
+use std::fmt;
+
+use roaring::RoaringBitmap;
+
+use super::distinct::{facet_number_values, facet_string_values};
+use crate::error::UserError;
+use crate::{DocumentId, FieldId, FilterCondition, Index, Result};
+
+/// A semi-join between the documents of a searched index and the documents of another index.
+///
+/// The documents of the searched index are kept when one of the values of their local field
+/// is also a value of the foreign field of the documents of the foreign index. Both fields
+/// must be faceted, the values are compared using the facet databases of both indexes.
+pub struct Lookup<'a> {
+    local_field: String,
+    foreign_field: String,
+    foreign_filter: Option<FilterCondition>,
+    rtxn: &'a heed::RoTxn<'a>,
+    index: &'a Index,
+}
+
+impl<'a> Lookup<'a> {
+    pub fn new(
+        local_field: impl Into<String>,
+        rtxn: &'a heed::RoTxn,
+        index: &'a Index,
+        foreign_field: impl Into<String>,
+    ) -> Lookup<'a> {
+        Lookup {
+            local_field: local_field.into(),
+            foreign_field: foreign_field.into(),
+            foreign_filter: None,
+            rtxn,
+            index,
+        }
+    }
+
+    /// Only considers the documents of the foreign index that match this filter.
+    pub fn filter(&mut self, condition: FilterCondition) -> &mut Self {
+        self.foreign_filter = Some(condition);
+        self
+    }
+
+    /// Returns the documents ids of the local index that are associated
+    /// with at least one document of the foreign index.
+    pub fn execute(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<RoaringBitmap> {
+        let local_fid = match faceted_field_id(rtxn, index, &self.local_field)? {
+            Some(fid) => fid,
+            None => return Ok(RoaringBitmap::new()),
+        };
+        let foreign_fid = match faceted_field_id(self.rtxn, self.index, &self.foreign_field)? {
+            Some(fid) => fid,
+            None => return Ok(RoaringBitmap::new()),
+        };
+
+        let foreign_candidates = match &self.foreign_filter {
+            Some(condition) => Some(condition.evaluate(self.rtxn, self.index)?),
+            None => None,
+        };
+        let is_candidate = |docids: &RoaringBitmap| {
+            foreign_candidates.as_ref().map_or(true, |candidates| !candidates.is_disjoint(docids))
+        };
+
+        let mut documents_ids = RoaringBitmap::new();
+
+        for result in self.index.facet_string_iter(self.rtxn, foreign_fid)? {
+            let (normalized, _original, foreign_docids) = result?;
+            if is_candidate(&foreign_docids) {
+                if let Some((_, docids)) =
+                    index.facet_id_string_docids.get(rtxn, &(local_fid, normalized))?
+                {
+                    documents_ids |= docids;
+                }
+            }
+        }
+
+        for result in self.index.facet_number_iter(self.rtxn, foreign_fid)? {
+            let (value, foreign_docids) = result?;
+            if is_candidate(&foreign_docids) {
+                let key = (local_fid, 0, value, value);
+                if let Some(docids) = index.facet_id_f64_docids.get(rtxn, &key)? {
+                    documents_ids |= docids;
+                }
+            }
+        }
+
+        Ok(documents_ids)
+    }
+
+    /// Returns the documents ids of the foreign index that are associated with the given
+    /// document of the local index, it can be used to enrich the hits of a search.
+    pub fn foreign_documents(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        docid: DocumentId,
+    ) -> Result<RoaringBitmap> {
+        let local_fid = match faceted_field_id(rtxn, index, &self.local_field)? {
+            Some(fid) => fid,
+            None => return Ok(RoaringBitmap::new()),
+        };
+        let foreign_fid = match faceted_field_id(self.rtxn, self.index, &self.foreign_field)? {
+            Some(fid) => fid,
+            None => return Ok(RoaringBitmap::new()),
+        };
+
+        let mut documents_ids = RoaringBitmap::new();
+
+        for result in facet_string_values(docid, local_fid, index, rtxn)? {
+            let ((_, _, normalized), _original) = result?;
+            let key = (foreign_fid, normalized);
+            if let Some((_, docids)) = self.index.facet_id_string_docids.get(self.rtxn, &key)? {
+                documents_ids |= docids;
+            }
+        }
+
+        for result in facet_number_values(docid, local_fid, index, rtxn)? {
+            let ((_, _, value), ()) = result?;
+            let key = (foreign_fid, 0, value, value);
+            if let Some(docids) = self.index.facet_id_f64_docids.get(self.rtxn, &key)? {
+                documents_ids |= docids;
+            }
+        }
+
+        if let Some(condition) = &self.foreign_filter {
+            documents_ids &= condition.evaluate(self.rtxn, self.index)?;
+        }
+
+        Ok(documents_ids)
+    }
+}
+
+/// Returns the field id of the given field, `None` if no document contains
+/// this field and an error if the field is not faceted.
+fn faceted_field_id(rtxn: &heed::RoTxn, index: &Index, field: &str) -> Result<Option<FieldId>> {
+    let faceted_fields = index.faceted_fields(rtxn)?;
+    if !faceted_fields.contains(field) {
+        return Err(UserError::InvalidLookupAttribute {
+            field: field.to_string(),
+            valid_fields: faceted_fields,
+        })?;
+    }
+
+    Ok(index.fields_ids_map(rtxn)?.id(field))
+}
+
+impl fmt::Debug for Lookup<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Lookup { local_field, foreign_field, foreign_filter, rtxn: _, index: _ } = self;
+
+        f.debug_struct("Lookup")
+            .field("local_field", local_field)
+            .field("foreign_field", foreign_field)
+            .field("foreign_filter", foreign_filter)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::update::{IndexDocuments, Settings};
+
+    #[test]
+    fn semi_join_on_facet_values() {
+        let brands = TempIndex::new();
+        let mut wtxn = brands.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &brands, 0);
+        builder.set_filterable_fields(hashset! { S("name"), S("country") });
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": 0, "name": "Adidas", "country": "germany" },
+            { "id": 1, "name": "Nike", "country": "usa" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &brands, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let shoes = TempIndex::new();
+        let mut wtxn = shoes.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &shoes, 0);
+        builder.set_filterable_fields(hashset! { S("brand") });
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": 0, "brand": "adidas" },
+            { "id": 1, "brand": "nike" },
+            { "id": 2, "brand": "puma" },
+            { "id": 3, "brand": "Adidas" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &shoes, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let brands_rtxn = brands.read_txn().unwrap();
+        let shoes_rtxn = shoes.read_txn().unwrap();
+
+        let lookup = Lookup::new("brand", &brands_rtxn, &brands, "name");
+        let docids = lookup.execute(&shoes_rtxn, &shoes).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![0, 1, 3]);
+
+        let mut lookup = Lookup::new("brand", &brands_rtxn, &brands, "name");
+        let condition = FilterCondition::from_str(&brands_rtxn, &brands, "country = usa").unwrap();
+        lookup.filter(condition);
+        let docids = lookup.execute(&shoes_rtxn, &shoes).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![1]);
+
+        let lookup = Lookup::new("brand", &brands_rtxn, &brands, "name");
+        let docids = lookup.foreign_documents(&shoes_rtxn, &shoes, 3).unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![0]);
+
+        let lookup = Lookup::new("brand", &brands_rtxn, &brands, "id");
+        assert!(lookup.execute(&shoes_rtxn, &shoes).is_err());
+    }
+}
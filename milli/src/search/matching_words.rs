@@ -4,8 +4,9 @@ use std::ops::{Index, IndexMut};
 
 use levenshtein_automata::{Distance, DFA};
 
-use super::build_dfa;
+use super::build_dfa_with_distance;
 use crate::search::query_tree::{Operation, Query};
+use crate::TypoDistance;
 
 type IsPrefix = bool;
 
@@ -18,11 +19,16 @@ pub struct MatchingWords {
 
 impl MatchingWords {
     pub fn from_query_tree(tree: &Operation) -> Self {
+        MatchingWords::from_query_tree_with_distance(tree, TypoDistance::default())
+    }
+
+    /// Matches the words with the typos counted with the given edit distance.
+    pub fn from_query_tree_with_distance(tree: &Operation, typo_distance: TypoDistance) -> Self {
         // fetch matchable words from the query tree
         let mut dfas: Vec<_> = fetch_queries(tree)
             .into_iter()
             // create DFAs for each word
-            .map(|(w, t, p)| (build_dfa(w, t, p), w.to_string(), t, p))
+            .map(|(w, t, p)| (build_dfa_with_distance(w, t, p, typo_distance), w.to_string(), t, p))
             .collect();
         // Sort word by len in DESC order prioritizing the longuest word,
         // in order to highlight the longuest part of the matched word.
@@ -57,11 +63,11 @@ fn fetch_queries(tree: &Operation) -> HashSet<(&str, u8, IsPrefix)> {
             Operation::Or(_, ops) | Operation::And(ops) => {
                 ops.as_slice().iter().for_each(|op| resolve_ops(op, out));
             }
-            Operation::Query(Query { prefix, kind }) => {
+            Operation::Query(Query { prefix, kind, .. }) => {
                 let typo = if kind.is_exact() { 0 } else { kind.typo() };
                 out.insert((kind.word(), typo, *prefix));
             }
-            Operation::Phrase(words) => {
+            Operation::Phrase(words) | Operation::Split(words) => {
                 for word in words {
                     out.insert((word, 0, false));
                 }
@@ -255,14 +261,17 @@ mod tests {
                 Operation::Query(Query {
                     prefix: true,
                     kind: QueryKind::exact("split".to_string()),
+                    field: None,
                 }),
                 Operation::Query(Query {
                     prefix: false,
                     kind: QueryKind::exact("this".to_string()),
+                    field: None,
                 }),
                 Operation::Query(Query {
                     prefix: true,
                     kind: QueryKind::tolerant(1, "world".to_string()),
+                    field: None,
                 }),
             ])],
         );
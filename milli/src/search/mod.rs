@@ -1,37 +1,87 @@
 use std::borrow::Cow;
 use std::collections::hash_map::{Entry, HashMap};
+use std::collections::BTreeMap;
 use std::fmt;
 use std::mem::take;
+use std::rc::Rc;
 use std::result::Result as StdResult;
 use std::str::Utf8Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Instant;
 
-use distinct::{Distinct, DocIter, FacetDistinct, NoopDistinct};
+use distinct::{
+    Distinct, DocIter, FacetDistinct, FacetGroup, FacetValuesDistinct, NoopDistinct,
+    FACET_VALUES_DISTINCT_MAX_VALUES,
+};
 use fst::{IntoStreamer, Streamer};
 use levenshtein_automata::{LevenshteinAutomatonBuilder as LevBuilder, DFA};
 use log::debug;
 use meilisearch_tokenizer::{Analyzer, AnalyzerConfig};
 use once_cell::sync::Lazy;
 use roaring::bitmap::RoaringBitmap;
+use serde::{Deserialize, Serialize};
 
+pub use self::candidates::CandidatesExpr;
+pub use self::criteria::RankingRuleDetail;
+pub use self::dedup::DEDUP_MAX_HASHES;
+use self::dedup::{DedupDistinct, FieldHashes};
 pub(crate) use self::facet::ParserRule;
-pub use self::facet::{FacetDistribution, FacetNumberIter, FilterCondition, Operator};
+pub use self::facet::{
+    FacetDistribution, FacetNumberIter, FacetNumberRange, FilterCondition,
+    NonFilterableAttributeError, Operator, OrderBy, APPROXIMATE_SAMPLE_SIZE,
+    DEFAULT_VALUES_PER_FACET,
+};
+pub use self::locale::Locale;
+pub use self::lookup::Lookup;
 pub use self::matching_words::MatchingWords;
 use self::query_tree::QueryTreeBuilder;
-use crate::error::UserError;
+pub use self::relationship::Relationship;
+use self::relationship::{Relatives, RelativesDistinct};
+pub use self::search_log::{SearchLog, SearchLogHook};
+pub use self::session::SearchSession;
+use crate::error::{Error, UserError};
 use crate::search::criteria::r#final::{Final, FinalResult};
-use crate::{AscDesc, Criterion, DocumentId, Index, Member, Result};
+use crate::{
+    normalize_synonyms, AscDesc, Criterion, DocumentId, FirstLetterTypo, Index, Member, Result,
+    TypoDistance,
+};
 
 // Building these factories is not free.
 static LEVDIST0: Lazy<LevBuilder> = Lazy::new(|| LevBuilder::new(0, true));
 static LEVDIST1: Lazy<LevBuilder> = Lazy::new(|| LevBuilder::new(1, true));
 static LEVDIST2: Lazy<LevBuilder> = Lazy::new(|| LevBuilder::new(2, true));
+static LEVDIST1_NO_TRANSPOSITION: Lazy<LevBuilder> = Lazy::new(|| LevBuilder::new(1, false));
+static LEVDIST2_NO_TRANSPOSITION: Lazy<LevBuilder> = Lazy::new(|| LevBuilder::new(2, false));
 
+mod candidates;
 mod criteria;
+mod dedup;
 mod distinct;
 mod facet;
+mod locale;
+mod lookup;
 mod matching_words;
 mod query_tree;
+mod relationship;
+mod search_log;
+mod session;
+
+/// How the words of the query must match the documents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TermsMatchingStrategy {
+    /// The last words of the query are removed one by one when there
+    /// aren't enough documents that contain all of them.
+    Last,
+    /// The documents must contain all the words of the query.
+    All,
+}
+
+impl Default for TermsMatchingStrategy {
+    fn default() -> TermsMatchingStrategy {
+        TermsMatchingStrategy::Last
+    }
+}
 
 pub struct Search<'a> {
     query: Option<String>,
@@ -39,9 +89,22 @@ pub struct Search<'a> {
     offset: usize,
     limit: usize,
     sort_criteria: Option<Vec<AscDesc>>,
-    optional_words: bool,
+    terms_matching_strategy: TermsMatchingStrategy,
     authorize_typos: bool,
+    last_word_prefix: bool,
     words_limit: usize,
+    locale: Option<Locale>,
+    group_by: Option<(String, usize)>,
+    lookup: Option<Lookup<'a>>,
+    relationship: Option<Relationship>,
+    dedup_by: Option<String>,
+    session: Option<&'a SearchSession>,
+    extra_synonyms: HashMap<String, Vec<String>>,
+    term_boosts: HashMap<String, f32>,
+    ranking_score: bool,
+    ranking_score_threshold: Option<f64>,
+    show_ranking_score_details: bool,
+    abort_signal: Option<Arc<AtomicBool>>,
     rtxn: &'a heed::RoTxn<'a>,
     index: &'a Index,
 }
@@ -54,9 +117,22 @@ impl<'a> Search<'a> {
             offset: 0,
             limit: 20,
             sort_criteria: None,
-            optional_words: true,
+            terms_matching_strategy: TermsMatchingStrategy::default(),
             authorize_typos: true,
+            last_word_prefix: true,
             words_limit: 10,
+            locale: None,
+            group_by: None,
+            lookup: None,
+            relationship: None,
+            dedup_by: None,
+            session: None,
+            extra_synonyms: HashMap::new(),
+            term_boosts: HashMap::new(),
+            ranking_score: false,
+            ranking_score_threshold: None,
+            show_ranking_score_details: false,
+            abort_signal: None,
             rtxn,
             index,
         }
@@ -83,68 +159,228 @@ impl<'a> Search<'a> {
     }
 
     pub fn optional_words(&mut self, value: bool) -> &mut Search<'a> {
-        self.optional_words = value;
+        self.terms_matching_strategy =
+            if value { TermsMatchingStrategy::Last } else { TermsMatchingStrategy::All };
         self
     }
 
+    pub fn terms_matching_strategy(&mut self, value: TermsMatchingStrategy) -> &mut Search<'a> {
+        self.terms_matching_strategy = value;
+        self
+    }
+
+    /// Disabling the typos only affects this search, the words of the query then
+    /// match exactly whatever the typo settings of the index, e.g. for an exact mode.
     pub fn authorize_typos(&mut self, value: bool) -> &mut Search<'a> {
         self.authorize_typos = value;
         self
     }
 
+    /// Disables the prefix search on the last word of the query, it then only matches
+    /// the documents that contain the whole word, e.g. when the query isn't typed live.
+    pub fn last_word_prefix(&mut self, value: bool) -> &mut Search<'a> {
+        self.last_word_prefix = value;
+        self
+    }
+
     pub fn words_limit(&mut self, value: usize) -> &mut Search<'a> {
         self.words_limit = value;
         self
     }
 
+    /// Forces the language used to segment the query words instead of detecting it.
+    pub fn locale(&mut self, locale: Locale) -> &mut Search<'a> {
+        self.locale = Some(locale);
+        self
+    }
+
+    /// Returns at most `group_size` documents for each facet value of the given field,
+    /// the distinct attribute of the index is ignored when a grouping is specified.
+    pub fn group_by(&mut self, field: impl Into<String>, group_size: usize) -> &mut Search<'a> {
+        self.group_by = Some((field.into(), group_size));
+        self
+    }
+
+    /// Only returns the documents that are associated with documents of another index.
+    pub fn lookup(&mut self, lookup: Lookup<'a>) -> &mut Search<'a> {
+        self.lookup = Some(lookup);
+        self
+    }
+
+    /// Replaces the matching documents by their relatives, the documents are
+    /// linked together by the parent field of the index.
+    pub fn relationship(&mut self, relationship: Relationship) -> &mut Search<'a> {
+        self.relationship = Some(relationship);
+        self
+    }
+
+    /// Collapses the documents that have the same value in the given field, only the best
+    /// ranked one is returned. The field doesn't need to be faceted, at most
+    /// `DEDUP_MAX_HASHES` distinct values are remembered during a search.
+    pub fn dedup_by(&mut self, field: impl Into<String>) -> &mut Search<'a> {
+        self.dedup_by = Some(field.into());
+        self
+    }
+
+    /// Reuses the work done by the previous searches of a search-as-you-type session,
+    /// the session must only be used to search in this index.
+    pub fn session(&mut self, session: &'a SearchSession) -> &mut Search<'a> {
+        self.session = Some(session);
+        self
+    }
+
+    /// Adds synonyms to the ones of the index for this search only, the words
+    /// and synonyms are normalized like the synonyms of the settings.
+    pub fn extra_synonyms(&mut self, synonyms: HashMap<String, Vec<String>>) -> &mut Search<'a> {
+        self.extra_synonyms = synonyms;
+        self
+    }
+
+    /// Weights the terms of the query, the terms are weighted `1.0` by default.
+    ///
+    /// When there aren't enough documents that contain all the terms of the query
+    /// the terms with the lowest weights are the first ones to become optional.
+    pub fn term_boosts(&mut self, boosts: HashMap<String, f32>) -> &mut Search<'a> {
+        self.term_boosts = boosts;
+        self
+    }
+
+    /// Computes a relevance score, between 0 and 1, for each of the returned documents.
+    ///
+    /// The score only depends on the buckets the ranking rules placed the document in,
+    /// the scores of different searches and indexes can be compared.
+    pub fn ranking_score(&mut self, value: bool) -> &mut Search<'a> {
+        self.ranking_score = value;
+        self
+    }
+
+    /// Stops returning documents as soon as their ranking score is under the threshold.
+    pub fn ranking_score_threshold(&mut self, threshold: f64) -> &mut Search<'a> {
+        self.ranking_score_threshold = Some(threshold);
+        self
+    }
+
+    /// Returns, for each of the returned documents, the bucket every ranking rule placed it in.
+    pub fn show_ranking_score_details(&mut self, value: bool) -> &mut Search<'a> {
+        self.show_ranking_score_details = value;
+        self
+    }
+
     pub fn filter(&mut self, condition: FilterCondition) -> &mut Search<'a> {
         self.filter = Some(condition);
         self
     }
 
+    /// Aborts the search with [`Error::SearchAborted`] when the signal is raised from
+    /// another thread, the signal is polled while the filters and the buckets are resolved.
+    pub fn abort_signal(&mut self, signal: Arc<AtomicBool>) -> &mut Search<'a> {
+        self.abort_signal = Some(signal);
+        self
+    }
+
     pub fn execute(&self) -> Result<SearchResult> {
+        let before = Instant::now();
+        let result = self.execute_search()?;
+        if let Some(hook) = self.index.search_log_hook() {
+            hook.log(self, &result, before.elapsed());
+        }
+        Ok(result)
+    }
+
+    fn execute_search(&self) -> Result<SearchResult> {
         // We create the query tree by spliting the query into tokens.
         let before = Instant::now();
-        let (query_tree, primitive_query) = match self.query.as_ref() {
+        let (query_tree, primitive_query, excluded_candidates) = match self.query.as_ref() {
             Some(query) => {
                 let mut builder = QueryTreeBuilder::new(self.rtxn, self.index);
-                builder.optional_words(self.optional_words);
+                builder.terms_matching_strategy(self.terms_matching_strategy);
                 builder.authorize_typos(self.authorize_typos);
+                builder.last_word_prefix(self.last_word_prefix);
                 builder.words_limit(self.words_limit);
                 // We make sure that the analyzer is aware of the stop words
                 // this ensures that the query builder is able to properly remove them.
                 let mut config = AnalyzerConfig::default();
-                let stop_words = self.index.stop_words(self.rtxn)?;
+                let stop_words = self.index.query_stop_words(self.rtxn)?;
                 if let Some(ref stop_words) = stop_words {
                     config.stop_words(stop_words);
                 }
+                // The language of a short query can't be reliably detected, the analyzer
+                // is given the script and the language of the locale instead.
+                if let Some(locale) = self.locale {
+                    config.script(locale.script());
+                    config.language(locale.language());
+                }
                 let analyzer = Analyzer::new(config);
+                if !self.extra_synonyms.is_empty() {
+                    builder.extra_synonyms(normalize_synonyms(&analyzer, &self.extra_synonyms));
+                }
+                if !self.term_boosts.is_empty() {
+                    builder.term_boosts(normalize_term_boosts(&analyzer, &self.term_boosts));
+                }
                 let result = analyzer.analyze(query);
-                let tokens = result.tokens();
-                builder.build(tokens)?.map_or((None, None), |(qt, pq)| (Some(qt), Some(pq)))
+                let tokens = || result.tokens();
+                let excluded_candidates = builder.build_exclusion(query, tokens())?;
+                match builder.build(query, tokens())? {
+                    Some((qt, pq)) => (Some(qt), Some(pq), excluded_candidates),
+                    None => (None, None, excluded_candidates),
+                }
             }
-            None => (None, None),
+            None => (None, None, None),
         };
 
         debug!("query tree: {:?} took {:.02?}", query_tree, before.elapsed());
 
         // We create the original candidates with the facet conditions results.
         let before = Instant::now();
-        let filtered_candidates = match &self.filter {
-            Some(condition) => Some(condition.evaluate(self.rtxn, self.index)?),
+        let mut filtered_candidates = match &self.filter {
+            Some(condition) => Some(condition.evaluate_abortable(
+                self.rtxn,
+                self.index,
+                self.abort_signal.as_deref(),
+            )?),
             None => None,
         };
 
+        self.check_abort()?;
+
+        if let Some(lookup) = &self.lookup {
+            let documents_ids = lookup.execute(self.rtxn, self.index)?;
+            filtered_candidates = match filtered_candidates {
+                Some(candidates) => Some(candidates & documents_ids),
+                None => Some(documents_ids),
+            };
+        }
+
+        // The documents that contain the negated words of the query are never returned.
+        if let Some(excluded_candidates) = excluded_candidates {
+            let candidates = match filtered_candidates {
+                Some(candidates) => candidates,
+                None => self.index.documents_ids(self.rtxn)?,
+            };
+            filtered_candidates = Some(candidates - excluded_candidates);
+        }
+
         debug!("facet candidates: {:?} took {:.02?}", filtered_candidates, before.elapsed());
 
         let matching_words = match query_tree.as_ref() {
-            Some(query_tree) => MatchingWords::from_query_tree(&query_tree),
+            Some(query_tree) => {
+                let typo_distance = self.index.typo_distance(self.rtxn)?;
+                MatchingWords::from_query_tree_with_distance(&query_tree, typo_distance)
+            }
             None => MatchingWords::default(),
         };
 
+        // When the documents are browsed, without any query nor sort,
+        // we sort them with the default sort of the index if there is one.
+        let browsing = self.query.as_ref().map_or(true, |query| query.trim().is_empty());
+        let sort_criteria = match &self.sort_criteria {
+            None if browsing => self.index.default_sort(self.rtxn)?,
+            sort_criteria => sort_criteria.clone(),
+        };
+
         // We check that we are allowed to use the sort criteria, we check
         // that they are declared in the sortable fields.
-        if let Some(sort_criteria) = &self.sort_criteria {
+        if let Some(sort_criteria) = &sort_criteria {
             let sortable_fields = self.index.sortable_fields(self.rtxn)?;
             for asc_desc in sort_criteria {
                 match asc_desc.member() {
@@ -167,36 +403,180 @@ impl<'a> Search<'a> {
 
         // We check that the sort ranking rule exists and throw an
         // error if we try to use it and that it doesn't.
-        let sort_ranking_rule_missing = !self.index.criteria(self.rtxn)?.contains(&Criterion::Sort);
-        let empty_sort_criteria = self.sort_criteria.as_ref().map_or(true, |s| s.is_empty());
+        let ranking_rules = self.index.criteria(self.rtxn)?;
+        let sort_ranking_rule_missing = !ranking_rules.contains(&Criterion::Sort);
+        let empty_sort_criteria = sort_criteria.as_ref().map_or(true, |s| s.is_empty());
         if sort_ranking_rule_missing && !empty_sort_criteria {
             return Err(UserError::SortRankingRuleMissing.into());
         }
 
-        let criteria_builder = criteria::CriteriaBuilder::new(self.rtxn, self.index)?;
+        // A placeholder search that doesn't sort, group or deduplicate the documents returns
+        // them in the order of their ids, we paginate the candidates without any criterion.
+        if query_tree.is_none()
+            && empty_sort_criteria
+            && self.group_by.is_none()
+            && self.relationship.is_none()
+            && self.dedup_by.is_none()
+            && !ranking_rules.iter().any(|c| matches!(c, Criterion::Asc(_) | Criterion::Desc(_)))
+            && self.index.distinct_field(self.rtxn)?.is_none()
+        {
+            let candidates = match filtered_candidates {
+                Some(candidates) => candidates,
+                None => self.index.documents_ids(self.rtxn)?,
+            };
+            return Ok(self.placeholder_result(candidates));
+        }
+
+        let mut criteria_builder = criteria::CriteriaBuilder::new(self.rtxn, self.index)?;
+        if let Some(session) = self.session {
+            criteria_builder.session(session)?;
+        }
+        // When the filter is selective we intersect it with the documents ids of the words
+        // as soon as they are resolved instead of intersecting it with the union of them.
+        if let Some(candidates) = &filtered_candidates {
+            let documents_count = self.index.number_of_documents(self.rtxn)?;
+            if is_selective_filter(candidates.len(), documents_count) {
+                criteria_builder.candidates_restriction(candidates.clone());
+            }
+        }
         let criteria = criteria_builder.build(
             query_tree,
             primitive_query,
             filtered_candidates,
-            self.sort_criteria.clone(),
+            sort_criteria,
         )?;
 
+        if let Some((name, group_size)) = &self.group_by {
+            let faceted_fields = self.index.faceted_fields(self.rtxn)?;
+            if !faceted_fields.contains(name) {
+                return Err(UserError::InvalidGroupByAttribute {
+                    field: name.to_string(),
+                    valid_fields: faceted_fields,
+                })?;
+            }
+
+            let field_ids_map = self.index.fields_ids_map(self.rtxn)?;
+            return match field_ids_map.id(name) {
+                Some(fid) => {
+                    let group = FacetGroup::new(fid, *group_size, self.index, self.rtxn);
+                    let mut result = self.perform_sort(group, matching_words, criteria)?;
+                    self.check_abort()?;
+                    let mut distribution = FacetDistribution::new(self.rtxn, self.index);
+                    distribution.candidates(result.candidates.clone());
+                    result.groups = Some(distribution.facet_values(fid)?);
+                    Ok(result)
+                }
+                // No document contains the field yet, there is no group to return.
+                None => {
+                    let mut result = self.perform_sort(NoopDistinct, matching_words, criteria)?;
+                    result.groups = Some(BTreeMap::new());
+                    Ok(result)
+                }
+            };
+        }
+
         match self.index.distinct_field(self.rtxn)? {
             None => self.perform_sort(NoopDistinct, matching_words, criteria),
             Some(name) => {
                 let field_ids_map = self.index.fields_ids_map(self.rtxn)?;
                 match field_ids_map.id(name) {
-                    Some(fid) => {
-                        let distinct = FacetDistinct::new(fid, self.index, self.rtxn);
-                        self.perform_sort(distinct, matching_words, criteria)
-                    }
+                    Some(fid) => match self.index.facet_values_count(self.rtxn, fid)? {
+                        // The documents ids of the values are loaded at once when there are
+                        // only a few of them, an unknown count falls back to the facet distinct.
+                        1..=FACET_VALUES_DISTINCT_MAX_VALUES => {
+                            let distinct = FacetValuesDistinct::new(fid, self.index, self.rtxn)?;
+                            self.perform_sort(distinct, matching_words, criteria)
+                        }
+                        _ => {
+                            let distinct = FacetDistinct::new(fid, self.index, self.rtxn);
+                            self.perform_sort(distinct, matching_words, criteria)
+                        }
+                    },
                     None => Ok(SearchResult::default()),
                 }
             }
         }
     }
 
+    fn check_abort(&self) -> Result<()> {
+        match &self.abort_signal {
+            Some(signal) if signal.load(Ordering::Relaxed) => Err(Error::SearchAborted),
+            _otherwise => Ok(()),
+        }
+    }
+
+    fn placeholder_result(&self, candidates: RoaringBitmap) -> SearchResult {
+        let mut documents_ids: Vec<_> =
+            candidates.iter().skip(self.offset).take(self.limit).collect();
+
+        // All the documents are in the same bucket, the single one of the placeholder search,
+        // they all get the maximum score.
+        if self.ranking_score_threshold.map_or(false, |threshold| threshold > 1.0) {
+            documents_ids.clear();
+        }
+        let ranking_scores =
+            if self.ranking_score { Some(vec![1.0; documents_ids.len()]) } else { None };
+        let ranking_score_details = if self.show_ranking_score_details {
+            Some(vec![Vec::new(); documents_ids.len()])
+        } else {
+            None
+        };
+
+        SearchResult {
+            matching_words: MatchingWords::default(),
+            candidates,
+            documents_ids,
+            ranking_scores,
+            ranking_score_details,
+            groups: None,
+        }
+    }
+
     fn perform_sort<D: Distinct>(
+        &self,
+        distinct: D,
+        matching_words: MatchingWords,
+        criteria: Final,
+    ) -> Result<SearchResult> {
+        let relationship = match self.relationship {
+            Some(relationship) => relationship,
+            None => return self.sort_documents(distinct, matching_words, criteria),
+        };
+
+        match Relatives::new(self.rtxn, self.index)? {
+            Some(relatives) => {
+                let relatives = Rc::new(relatives);
+                let distinct = RelativesDistinct::new(distinct, relationship, relatives.clone());
+                let mut result = self.sort_documents(distinct, matching_words, criteria)?;
+                result.candidates = relatives.map_candidates(relationship, &result.candidates)?;
+                Ok(result)
+            }
+            None => self.sort_documents(distinct, matching_words, criteria),
+        }
+    }
+
+    fn sort_documents<D: Distinct>(
+        &self,
+        distinct: D,
+        matching_words: MatchingWords,
+        criteria: Final,
+    ) -> Result<SearchResult> {
+        let field_id = match &self.dedup_by {
+            Some(field) => self.index.fields_ids_map(self.rtxn)?.id(field),
+            None => None,
+        };
+
+        match field_id {
+            Some(fid) => {
+                let hashes = FieldHashes::new(fid, self.index, self.rtxn);
+                let distinct = DedupDistinct::new(distinct, hashes);
+                self.rank_documents(distinct, matching_words, criteria)
+            }
+            None => self.rank_documents(distinct, matching_words, criteria),
+        }
+    }
+
+    fn rank_documents<D: Distinct>(
         &self,
         mut distinct: D,
         matching_words: MatchingWords,
@@ -206,12 +586,24 @@ impl<'a> Search<'a> {
         let mut initial_candidates = RoaringBitmap::new();
         let mut excluded_candidates = RoaringBitmap::new();
         let mut documents_ids = Vec::new();
-
-        while let Some(FinalResult { candidates, bucket_candidates, .. }) =
-            criteria.next(&excluded_candidates)?
-        {
+        let mut documents_scores = Vec::new();
+        let mut ranking_score_details = Vec::new();
+
+        loop {
+            self.check_abort()?;
+            let FinalResult { candidates, bucket_candidates, ranking_details, .. } =
+                match criteria.next(&excluded_candidates)? {
+                    Some(result) => result,
+                    None => break,
+                };
             debug!("Number of candidates found {}", candidates.len());
 
+            // The scores decrease with the buckets, the next ones are under the threshold too.
+            let score = ranking_score(&ranking_details);
+            if self.ranking_score_threshold.map_or(false, |threshold| score < threshold) {
+                break;
+            }
+
             let excluded = take(&mut excluded_candidates);
 
             let mut candidates = distinct.distinct(candidates, excluded);
@@ -225,6 +617,10 @@ impl<'a> Search<'a> {
 
             for candidate in candidates.by_ref().take(self.limit - documents_ids.len()) {
                 documents_ids.push(candidate?);
+                documents_scores.push(score);
+                if self.show_ranking_score_details {
+                    ranking_score_details.push(ranking_details.clone());
+                }
             }
             if documents_ids.len() == self.limit {
                 break;
@@ -232,7 +628,18 @@ impl<'a> Search<'a> {
             excluded_candidates = candidates.into_excluded();
         }
 
-        Ok(SearchResult { matching_words, candidates: initial_candidates, documents_ids })
+        let ranking_scores = if self.ranking_score { Some(documents_scores) } else { None };
+        let ranking_score_details =
+            if self.show_ranking_score_details { Some(ranking_score_details) } else { None };
+
+        Ok(SearchResult {
+            matching_words,
+            candidates: initial_candidates,
+            documents_ids,
+            ranking_scores,
+            ranking_score_details,
+            groups: None,
+        })
     }
 }
 
@@ -244,9 +651,22 @@ impl fmt::Debug for Search<'_> {
             offset,
             limit,
             sort_criteria,
-            optional_words,
+            terms_matching_strategy,
             authorize_typos,
+            last_word_prefix,
             words_limit,
+            locale,
+            group_by,
+            lookup,
+            relationship,
+            dedup_by,
+            session: _,
+            extra_synonyms,
+            term_boosts,
+            ranking_score,
+            ranking_score_threshold,
+            show_ranking_score_details,
+            abort_signal: _,
             rtxn: _,
             index: _,
         } = self;
@@ -256,9 +676,20 @@ impl fmt::Debug for Search<'_> {
             .field("offset", offset)
             .field("limit", limit)
             .field("sort_criteria", sort_criteria)
-            .field("optional_words", optional_words)
+            .field("terms_matching_strategy", terms_matching_strategy)
             .field("authorize_typos", authorize_typos)
+            .field("last_word_prefix", last_word_prefix)
             .field("words_limit", words_limit)
+            .field("locale", locale)
+            .field("group_by", group_by)
+            .field("lookup", lookup)
+            .field("relationship", relationship)
+            .field("dedup_by", dedup_by)
+            .field("extra_synonyms", extra_synonyms)
+            .field("term_boosts", term_boosts)
+            .field("ranking_score", ranking_score)
+            .field("ranking_score_threshold", ranking_score_threshold)
+            .field("show_ranking_score_details", show_ranking_score_details)
             .finish()
     }
 }
@@ -267,11 +698,85 @@ impl fmt::Debug for Search<'_> {
 pub struct SearchResult {
     pub matching_words: MatchingWords,
     pub candidates: RoaringBitmap,
-    // TODO those documents ids should be associated with their criteria scores.
     pub documents_ids: Vec<DocumentId>,
+    /// The relevance score of each of the documents, between 0 and 1,
+    /// only defined when the ranking score is requested.
+    pub ranking_scores: Option<Vec<f64>>,
+    /// The bucket every ranking rule placed each of the documents in,
+    /// only defined when the ranking score details are requested.
+    pub ranking_score_details: Option<Vec<Vec<RankingRuleDetail>>>,
+    /// The number of candidates for each facet value of the grouping field,
+    /// only defined when the search is grouped.
+    pub groups: Option<BTreeMap<String, u64>>,
+}
+
+/// Computes the relevance score of the documents of a bucket from the buckets every ranking
+/// rule placed them in. The n-th bucket of a ranking rule gets the n-th half of the score range
+/// of its parent bucket, which the next ranking rule splits the same way.
+///
+/// The documents in the first bucket of every ranking rule get a score of 1 and the scores
+/// decrease with the buckets, they don't depend on the other documents of the index.
+fn ranking_score(ranking_details: &[RankingRuleDetail]) -> f64 {
+    let (mut low, mut high) = (0.0, 1.0);
+    for detail in ranking_details {
+        let range = (high - low) * 0.5f64.powf(detail.bucket as f64);
+        high = low + range;
+        low += range / 2.0;
+    }
+    high
+}
+
+/// The ratio of documents under which a filter is considered selective enough
+/// to be intersected with the documents ids of the words when they are resolved.
+pub const SELECTIVE_FILTER_RATIO: f64 = 0.1;
+
+fn is_selective_filter(candidates_count: u64, documents_count: u64) -> bool {
+    documents_count != 0
+        && (candidates_count as f64 / documents_count as f64) < SELECTIVE_FILTER_RATIO
+}
+
+/// Normalizes the terms of the boosts with the analyzer, every word of a term gets its boost.
+fn normalize_term_boosts<A: AsRef<[u8]>>(
+    analyzer: &Analyzer<A>,
+    boosts: &HashMap<String, f32>,
+) -> HashMap<String, f32> {
+    let mut normalized = HashMap::new();
+    for (term, boost) in boosts {
+        for token in analyzer.analyze(term).tokens().filter(|token| token.is_word()) {
+            normalized.insert(token.text().to_string(), *boost);
+        }
+    }
+    normalized
 }
 
-pub type WordDerivationsCache = HashMap<(String, bool, u8), Vec<(String, u8)>>;
+/// The derivations of the words of the queries with their number of typos.
+#[derive(Debug, Default)]
+pub struct WordDerivationsCache {
+    derivations: HashMap<(String, bool, u8), Vec<(String, u8)>>,
+    first_letter_typo: FirstLetterTypo,
+    typo_distance: TypoDistance,
+}
+
+impl WordDerivationsCache {
+    pub fn new() -> WordDerivationsCache {
+        WordDerivationsCache::default()
+    }
+
+    /// Counts the typos on the first letter of the words as configured.
+    pub fn with_first_letter_typo(first_letter_typo: FirstLetterTypo) -> WordDerivationsCache {
+        WordDerivationsCache {
+            derivations: HashMap::new(),
+            first_letter_typo,
+            typo_distance: TypoDistance::default(),
+        }
+    }
+
+    /// Counts the typos of the derivations with the given edit distance.
+    pub fn with_typo_distance(mut self, typo_distance: TypoDistance) -> WordDerivationsCache {
+        self.typo_distance = typo_distance;
+        self
+    }
+}
 
 pub fn word_derivations<'c>(
     word: &str,
@@ -280,17 +785,24 @@ pub fn word_derivations<'c>(
     fst: &fst::Set<Cow<[u8]>>,
     cache: &'c mut WordDerivationsCache,
 ) -> StdResult<&'c [(String, u8)], Utf8Error> {
-    match cache.entry((word.to_string(), is_prefix, max_typo)) {
+    let first_letter_typo = cache.first_letter_typo;
+    let typo_distance = cache.typo_distance;
+    match cache.derivations.entry((word.to_string(), is_prefix, max_typo)) {
         Entry::Occupied(entry) => Ok(entry.into_mut()),
         Entry::Vacant(entry) => {
             let mut derived_words = Vec::new();
-            let dfa = build_dfa(word, max_typo, is_prefix);
+            let dfa = build_dfa_with_distance(word, max_typo, is_prefix, typo_distance);
             let mut stream = fst.search_with_state(&dfa).into_stream();
 
-            while let Some((word, state)) = stream.next() {
-                let word = std::str::from_utf8(word)?;
-                let distance = dfa.distance(state);
-                derived_words.push((word.to_string(), distance.to_u8()));
+            while let Some((derived, state)) = stream.next() {
+                let derived = std::str::from_utf8(derived)?;
+                let distance = dfa.distance(state).to_u8();
+                match first_letter_typo.typos(word, derived, distance) {
+                    Some(typos) if typos <= max_typo => {
+                        derived_words.push((derived.to_string(), typos))
+                    }
+                    _otherwise => (),
+                }
             }
 
             Ok(entry.insert(derived_words))
@@ -299,10 +811,21 @@ pub fn word_derivations<'c>(
 }
 
 pub fn build_dfa(word: &str, typos: u8, is_prefix: bool) -> DFA {
-    let lev = match typos {
-        0 => &LEVDIST0,
-        1 => &LEVDIST1,
-        _ => &LEVDIST2,
+    build_dfa_with_distance(word, typos, is_prefix, TypoDistance::default())
+}
+
+pub fn build_dfa_with_distance(
+    word: &str,
+    typos: u8,
+    is_prefix: bool,
+    typo_distance: TypoDistance,
+) -> DFA {
+    let lev = match (typos, typo_distance.transposition_cost_one()) {
+        (0, _) => &LEVDIST0,
+        (1, true) => &LEVDIST1,
+        (1, false) => &LEVDIST1_NO_TRANSPOSITION,
+        (_, true) => &LEVDIST2,
+        (_, false) => &LEVDIST2_NO_TRANSPOSITION,
     };
 
     if is_prefix {
@@ -1,22 +1,31 @@
+use std::collections::HashMap;
 use std::{cmp, fmt, mem};
 
 use fst::Set;
 use meilisearch_tokenizer::token::SeparatorKind;
-use meilisearch_tokenizer::tokenizer::TokenStream;
-use meilisearch_tokenizer::TokenKind;
+use meilisearch_tokenizer::{Token, TokenKind};
+use once_cell::unsync::OnceCell;
 use roaring::RoaringBitmap;
 use slice_group_by::GroupBy;
 
-use crate::{Index, Result};
+use super::TermsMatchingStrategy;
+use crate::index::{DEFAULT_MIN_WORD_SIZE_FOR_ONE_TYPO, DEFAULT_MIN_WORD_SIZE_FOR_TWO_TYPOS};
+use crate::{exact_word, FieldId, Index, Result};
 
 type IsOptionalWord = bool;
 type IsPrefix = bool;
+type ExactWord = String;
+
+/// The number of typos a word of the query costs when it is split in two words.
+pub const SPLIT_WORD_TYPO: u8 = 1;
 
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub enum Operation {
     And(Vec<Operation>),
     // serie of consecutive non prefix and exact words
     Phrase(Vec<String>),
+    // a word of the query split in consecutive words, it costs `SPLIT_WORD_TYPO` typos
+    Split(Vec<String>),
     Or(IsOptionalWord, Vec<Operation>),
     Query(Query),
 }
@@ -32,6 +41,9 @@ impl fmt::Debug for Operation {
                 Operation::Phrase(children) => {
                     writeln!(f, "{:2$}PHRASE {:?}", "", children, depth * 2)
                 }
+                Operation::Split(children) => {
+                    writeln!(f, "{:2$}SPLIT {:?}", "", children, depth * 2)
+                }
                 Operation::Or(true, children) => {
                     writeln!(f, "{:1$}OR(WORD)", "", depth * 2)?;
                     children.iter().try_for_each(|c| pprint_tree(f, c, depth + 1))
@@ -65,9 +77,13 @@ impl Operation {
         }
     }
 
-    fn phrase(mut words: Vec<String>) -> Self {
+    pub(crate) fn phrase(mut words: Vec<String>) -> Self {
         if words.len() == 1 {
-            Self::Query(Query { prefix: false, kind: QueryKind::exact(words.pop().unwrap()) })
+            Self::Query(Query {
+                prefix: false,
+                kind: QueryKind::exact(words.pop().unwrap()),
+                field: None,
+            })
         } else {
             Self::Phrase(words)
         }
@@ -85,6 +101,8 @@ impl Operation {
 pub struct Query {
     pub prefix: IsPrefix,
     pub kind: QueryKind,
+    /// The field the word must be found in, `None` when the word can be in any field.
+    pub field: Option<FieldId>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -131,18 +149,24 @@ impl QueryKind {
 
 impl fmt::Debug for Query {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let Query { prefix, kind } = self;
+        let Query { prefix, kind, field } = self;
         let prefix = if *prefix { String::from("Prefix") } else { String::default() };
-        match kind {
+        let mut debug = match kind {
             QueryKind::Exact { word, .. } => {
-                f.debug_struct(&(prefix + "Exact")).field("word", &word).finish()
+                let mut debug = f.debug_struct(&(prefix + "Exact"));
+                debug.field("word", &word);
+                debug
             }
-            QueryKind::Tolerant { typo, word } => f
-                .debug_struct(&(prefix + "Tolerant"))
-                .field("word", &word)
-                .field("max typo", &typo)
-                .finish(),
+            QueryKind::Tolerant { typo, word } => {
+                let mut debug = f.debug_struct(&(prefix + "Tolerant"));
+                debug.field("word", &word).field("max typo", &typo);
+                debug
+            }
+        };
+        if let Some(field) = field {
+            debug.field("field", field);
         }
+        debug.finish()
     }
 }
 
@@ -155,6 +179,12 @@ trait Context {
             None => Ok(None),
         }
     }
+
+    /// Returns the weight of a word, the words with the lowest weights
+    /// are the first ones to become optional.
+    fn term_boost(&self, _word: &str) -> f32 {
+        1.0
+    }
 }
 
 /// The query tree builder is the interface to build a query tree.
@@ -163,7 +193,12 @@ pub struct QueryTreeBuilder<'a> {
     index: &'a Index,
     optional_words: bool,
     authorize_typos: bool,
+    last_word_prefix: bool,
     words_limit: Option<usize>,
+    extra_synonyms: HashMap<Vec<String>, Vec<Vec<String>>>,
+    term_boosts: HashMap<String, f32>,
+    /// The synonyms of the index, loaded once by the first words that look them up.
+    synonyms: OnceCell<HashMap<Vec<String>, Vec<Vec<String>>>>,
 }
 
 impl<'a> Context for QueryTreeBuilder<'a> {
@@ -172,19 +207,49 @@ impl<'a> Context for QueryTreeBuilder<'a> {
     }
 
     fn synonyms<S: AsRef<str>>(&self, words: &[S]) -> heed::Result<Option<Vec<Vec<String>>>> {
-        self.index.words_synonyms(self.rtxn, words)
+        let index_synonyms =
+            self.synonyms.get_or_try_init(|| self.index.query_synonyms(self.rtxn))?;
+        let words: Vec<_> = words.iter().map(|s| s.as_ref().to_owned()).collect();
+        let synonyms = index_synonyms.get(&words).cloned();
+        if self.extra_synonyms.is_empty() {
+            return Ok(synonyms);
+        }
+
+        match (synonyms, self.extra_synonyms.get(&words)) {
+            (Some(mut synonyms), Some(extra)) => {
+                synonyms.extend(extra.iter().cloned());
+                synonyms.sort_unstable();
+                synonyms.dedup();
+                Ok(Some(synonyms))
+            }
+            (synonyms, extra) => Ok(synonyms.or_else(|| extra.cloned())),
+        }
     }
 
     fn word_documents_count(&self, word: &str) -> heed::Result<Option<u64>> {
         self.index.word_documents_count(self.rtxn, word)
     }
+
+    fn term_boost(&self, word: &str) -> f32 {
+        self.term_boosts.get(word).copied().unwrap_or(1.0)
+    }
 }
 
 impl<'a> QueryTreeBuilder<'a> {
     /// Create a `QueryTreeBuilder` from a heed ReadOnly transaction `rtxn`
     /// and an Index `index`.
     pub fn new(rtxn: &'a heed::RoTxn<'a>, index: &'a Index) -> Self {
-        Self { rtxn, index, optional_words: true, authorize_typos: true, words_limit: None }
+        Self {
+            rtxn,
+            index,
+            optional_words: true,
+            authorize_typos: true,
+            last_word_prefix: true,
+            words_limit: None,
+            extra_synonyms: HashMap::new(),
+            term_boosts: HashMap::new(),
+            synonyms: OnceCell::new(),
+        }
     }
 
     /// if `optional_words` is set to `false` the query tree will be
@@ -197,6 +262,14 @@ impl<'a> QueryTreeBuilder<'a> {
         self
     }
 
+    /// With the `All` strategy the query tree will be generated like when
+    /// `optional_words` is set to `false`.
+    /// default value if not called: `Last`
+    pub fn terms_matching_strategy(&mut self, strategy: TermsMatchingStrategy) -> &mut Self {
+        self.optional_words = strategy == TermsMatchingStrategy::Last;
+        self
+    }
+
     /// if `authorize_typos` is set to `false` the query tree will be generated
     /// forcing all query words to match documents without any typo
     /// (the criterion `typo` will be ignored).
@@ -207,6 +280,14 @@ impl<'a> QueryTreeBuilder<'a> {
         self
     }
 
+    /// if `last_word_prefix` is set to `false` the last word of the query
+    /// only matches the documents that contain the whole word.
+    /// default value if not called: `true`
+    pub fn last_word_prefix(&mut self, last_word_prefix: bool) -> &mut Self {
+        self.last_word_prefix = last_word_prefix;
+        self
+    }
+
     /// Limit words and phrases that will be taken for query building.
     /// Any beyond `words_limit` will be ignored.
     pub fn words_limit(&mut self, words_limit: usize) -> &mut Self {
@@ -214,6 +295,23 @@ impl<'a> QueryTreeBuilder<'a> {
         self
     }
 
+    /// Adds normalized synonyms to the ones of the index, for this query only.
+    pub fn extra_synonyms(
+        &mut self,
+        extra_synonyms: HashMap<Vec<String>, Vec<Vec<String>>>,
+    ) -> &mut Self {
+        self.extra_synonyms = extra_synonyms;
+        self
+    }
+
+    /// Weights the normalized words of the query, the words are `1.0` by default.
+    /// When the words are optional the ones with the lowest weights are removed first,
+    /// the last words of the query are removed first among the words of the same weight.
+    pub fn term_boosts(&mut self, term_boosts: HashMap<String, f32>) -> &mut Self {
+        self.term_boosts = term_boosts;
+        self
+    }
+
     /// Build the query tree:
     /// - if `optional_words` is set to `false` the query tree will be
     ///   generated forcing all query words to be present in each matching documents
@@ -221,21 +319,80 @@ impl<'a> QueryTreeBuilder<'a> {
     /// - if `authorize_typos` is set to `false` the query tree will be generated
     ///   forcing all query words to match documents without any typo
     ///   (the criterion `typo` will be ignored)
-    pub fn build(&self, query: TokenStream) -> Result<Option<(Operation, PrimitiveQuery)>> {
-        let stop_words = self.index.stop_words(self.rtxn)?;
-        let primitive_query = create_primitive_query(query, stop_words, self.words_limit);
+    ///
+    /// The `text` is the analyzed text the tokens come from, it is used
+    /// to retrieve the exact version of the words of the query.
+    pub fn build<'t>(
+        &self,
+        text: &str,
+        query: impl Iterator<Item = Token<'t>>,
+    ) -> Result<Option<(Operation, PrimitiveQuery)>> {
+        let stop_words = self.index.query_stop_words(self.rtxn)?;
+        let fields_ids_map = self.index.fields_ids_map(self.rtxn)?;
+        let searchable_fields = self.index.searchable_fields_ids(self.rtxn)?;
+        let fields: HashMap<_, _> = fields_ids_map
+            .iter()
+            .filter(|(id, _)| searchable_fields.as_ref().map_or(true, |fields| fields.contains(id)))
+            .map(|(id, name)| (name.to_string(), id))
+            .collect();
+        let mut primitive_query =
+            create_primitive_query(text, query, stop_words, &fields, self.words_limit);
+        if !self.last_word_prefix {
+            if let Some(PrimitiveQueryPart::Word(_, prefix, _, _)) = primitive_query.last_mut() {
+                *prefix = false;
+            }
+        }
         if !primitive_query.is_empty() {
-            let qt = create_query_tree(
-                self,
-                self.optional_words,
-                self.authorize_typos,
-                &primitive_query,
-            )?;
+            // The typo settings of the index are useless when the typos are disabled.
+            let exact_words = match self.authorize_typos {
+                true => self.index.typo_disabled_on_words(self.rtxn)?,
+                false => None,
+            };
+            let typo_config = match self.authorize_typos {
+                true => TypoConfig {
+                    authorize_typos: true,
+                    min_word_size_for_one_typo: self.index.min_word_size_for_one_typo(self.rtxn)?,
+                    min_word_size_for_two_typos: self
+                        .index
+                        .min_word_size_for_two_typos(self.rtxn)?,
+                    exact_words: exact_words.as_ref(),
+                },
+                false => TypoConfig::new(false),
+            };
+            let qt = create_query_tree(self, self.optional_words, typo_config, &primitive_query)?;
             Ok(Some((qt, primitive_query)))
         } else {
             Ok(None)
         }
     }
+
+    /// Returns the documents that contain the words of the query that are negated
+    /// with a minus sign (e.g. `-word`), these documents must be excluded from the results.
+    ///
+    /// Returns `None` if there is no negated word in the query.
+    pub fn build_exclusion<'t>(
+        &self,
+        text: &str,
+        query: impl Iterator<Item = Token<'t>>,
+    ) -> Result<Option<RoaringBitmap>> {
+        let mut excluded = None;
+        for token in query.filter(|token| token.is_word() && is_negated_word(text, token)) {
+            let docids = self.word_docids(token.word.as_ref())?.unwrap_or_default();
+            *excluded.get_or_insert_with(RoaringBitmap::new) |= docids;
+        }
+        Ok(excluded)
+    }
+}
+
+/// Returns whether a word of the query is preceded by a minus sign, at the start of
+/// the query or after a whitespace, and isn't part of a quoted phrase.
+fn is_negated_word(text: &str, token: &Token) -> bool {
+    let before = &text[..token.byte_start];
+    let quoted = before.matches('"').count() % 2 != 0;
+    match before.strip_suffix('-') {
+        Some(before) if !quoted => before.chars().next_back().map_or(true, char::is_whitespace),
+        _ => false,
+    }
 }
 
 /// Split the word depending on the frequency of subwords in the database documents.
@@ -255,20 +412,43 @@ fn split_best_frequency(ctx: &impl Context, word: &str) -> heed::Result<Option<O
         }
     }
 
-    Ok(best.map(|(_, left, right)| Operation::Phrase(vec![left.to_string(), right.to_string()])))
+    Ok(best.map(|(_, left, right)| Operation::Split(vec![left.to_string(), right.to_string()])))
 }
 
-/// Return the `QueryKind` of a word depending on `authorize_typos`
-/// and the provided word length.
-fn typos(word: String, authorize_typos: bool) -> QueryKind {
-    if authorize_typos {
-        match word.chars().count() {
-            0..=4 => QueryKind::exact(word),
-            5..=8 => QueryKind::tolerant(1, word),
-            _ => QueryKind::tolerant(2, word),
+/// The number of typos allowed on the words of the query depending on their length.
+#[derive(Debug, Clone, Copy)]
+struct TypoConfig<'a> {
+    authorize_typos: bool,
+    /// The minimum number of characters of a word to allow one typo on it.
+    min_word_size_for_one_typo: u8,
+    /// The minimum number of characters of a word to allow two typos on it.
+    min_word_size_for_two_typos: u8,
+    /// The words that never allow typos, whatever their length.
+    exact_words: Option<&'a Set<&'a [u8]>>,
+}
+
+impl<'a> TypoConfig<'a> {
+    fn new(authorize_typos: bool) -> TypoConfig<'a> {
+        TypoConfig {
+            authorize_typos,
+            min_word_size_for_one_typo: DEFAULT_MIN_WORD_SIZE_FOR_ONE_TYPO,
+            min_word_size_for_two_typos: DEFAULT_MIN_WORD_SIZE_FOR_TWO_TYPOS,
+            exact_words: None,
         }
-    } else {
+    }
+}
+
+/// Return the `QueryKind` of a word depending on the typo config
+/// and the provided word length.
+fn typos(word: String, config: TypoConfig) -> QueryKind {
+    let len = word.chars().count();
+    let exact_word = config.exact_words.map_or(false, |words| words.contains(&word));
+    if !config.authorize_typos || exact_word || len < config.min_word_size_for_one_typo as usize {
         QueryKind::exact(word)
+    } else if len < config.min_word_size_for_two_typos as usize {
+        QueryKind::tolerant(1, word)
+    } else {
+        QueryKind::tolerant(2, word)
     }
 }
 
@@ -284,7 +464,11 @@ fn synonyms(ctx: &impl Context, word: &[&str]) -> heed::Result<Option<Vec<Operat
                 let words = synonym
                     .into_iter()
                     .map(|word| {
-                        Operation::Query(Query { prefix: false, kind: QueryKind::exact(word) })
+                        Operation::Query(Query {
+                            prefix: false,
+                            kind: QueryKind::exact(word),
+                            field: None,
+                        })
                     })
                     .collect();
                 Operation::and(words)
@@ -297,13 +481,13 @@ fn synonyms(ctx: &impl Context, word: &[&str]) -> heed::Result<Option<Vec<Operat
 fn create_query_tree(
     ctx: &impl Context,
     optional_words: bool,
-    authorize_typos: bool,
+    typo_config: TypoConfig,
     query: &[PrimitiveQueryPart],
 ) -> Result<Operation> {
     /// Matches on the `PrimitiveQueryPart` and create an operation from it.
     fn resolve_primitive_part(
         ctx: &impl Context,
-        authorize_typos: bool,
+        typo_config: TypoConfig,
         part: PrimitiveQueryPart,
     ) -> Result<Operation> {
         match part {
@@ -311,13 +495,21 @@ fn create_query_tree(
             // 2. try to fetch synonyms
             // 3. create an operation containing the word
             // 4. wrap all in an OR operation
-            PrimitiveQueryPart::Word(word, prefix) => {
+            // a scoped word is only searched in its field, as is.
+            PrimitiveQueryPart::Word(word, prefix, _exact, Some(field)) => {
+                let kind = typos(word, typo_config);
+                Ok(Operation::Query(Query { prefix, kind, field: Some(field) }))
+            }
+            PrimitiveQueryPart::Word(word, prefix, _exact, None) => {
                 let mut children = synonyms(ctx, &[&word])?.unwrap_or_default();
                 if let Some(child) = split_best_frequency(ctx, &word)? {
                     children.push(child);
                 }
-                children
-                    .push(Operation::Query(Query { prefix, kind: typos(word, authorize_typos) }));
+                children.push(Operation::Query(Query {
+                    prefix,
+                    kind: typos(word, typo_config),
+                    field: None,
+                }));
                 Ok(Operation::or(false, children))
             }
             // create a CONSECUTIVE operation wrapping all word in the phrase
@@ -328,13 +520,15 @@ fn create_query_tree(
     /// Create all ngrams 1..=3 generating query tree branches.
     fn ngrams(
         ctx: &impl Context,
-        authorize_typos: bool,
+        typo_config: TypoConfig,
         query: &[PrimitiveQueryPart],
     ) -> Result<Operation> {
         const MAX_NGRAM: usize = 3;
         let mut op_children = Vec::new();
 
-        for sub_query in query.linear_group_by(|a, b| !(a.is_phrase() || b.is_phrase())) {
+        // the phrases and the scoped words are never concatenated with the other words.
+        let standalone = |part: &PrimitiveQueryPart| part.is_phrase() || part.is_scoped();
+        for sub_query in query.linear_group_by(|a, b| !(standalone(a) || standalone(b))) {
             let mut or_op_children = Vec::new();
 
             for ngram in 1..=MAX_NGRAM.min(sub_query.len()) {
@@ -345,8 +539,7 @@ fn create_query_tree(
 
                     match group {
                         [part] => {
-                            let operation =
-                                resolve_primitive_part(ctx, authorize_typos, part.clone())?;
+                            let operation = resolve_primitive_part(ctx, typo_config, part.clone())?;
                             and_op_children.push(operation);
                         }
                         words => {
@@ -354,7 +547,7 @@ fn create_query_tree(
                             let words: Vec<_> = words
                                 .iter()
                                 .filter_map(|part| {
-                                    if let PrimitiveQueryPart::Word(word, _) = part {
+                                    if let PrimitiveQueryPart::Word(word, _, _, _) = part {
                                         Some(word.as_str())
                                     } else {
                                         None
@@ -363,15 +556,18 @@ fn create_query_tree(
                                 .collect();
                             let mut operations = synonyms(ctx, &words)?.unwrap_or_default();
                             let concat = words.concat();
-                            let query =
-                                Query { prefix: is_prefix, kind: typos(concat, authorize_typos) };
+                            let query = Query {
+                                prefix: is_prefix,
+                                kind: typos(concat, typo_config),
+                                field: None,
+                            };
                             operations.push(Operation::Query(query));
                             and_op_children.push(Operation::or(false, operations));
                         }
                     }
 
                     if !is_last {
-                        let ngrams = ngrams(ctx, authorize_typos, tail)?;
+                        let ngrams = ngrams(ctx, typo_config, tail)?;
                         and_op_children.push(ngrams);
                     }
                     or_op_children.push(Operation::and(and_op_children));
@@ -383,34 +579,42 @@ fn create_query_tree(
         Ok(Operation::and(op_children))
     }
 
-    /// Create a new branch removing the last non-phrase query parts.
+    /// Create a new branch removing the non-phrase query parts with the lowest
+    /// boosts one by one, starting with the last parts of the query.
     fn optional_word(
         ctx: &impl Context,
-        authorize_typos: bool,
+        typo_config: TypoConfig,
         query: PrimitiveQuery,
     ) -> Result<Operation> {
         let number_phrases = query.iter().filter(|p| p.is_phrase()).count();
         let mut operation_children = Vec::new();
 
-        let start = number_phrases + (number_phrases == 0) as usize;
-        for len in start..=query.len() {
-            let mut word_count = len - number_phrases;
+        // The positions of the words in the query in the order they are removed.
+        let mut removal_order: Vec<_> = query
+            .iter()
+            .enumerate()
+            .filter_map(|(i, part)| match part {
+                PrimitiveQueryPart::Word(word, ..) => Some((i, ctx.term_boost(word))),
+                PrimitiveQueryPart::Phrase(_) => None,
+            })
+            .collect();
+        removal_order.sort_by(|(ia, ba), (ib, bb)| {
+            ba.partial_cmp(bb).unwrap_or(cmp::Ordering::Equal).then(ib.cmp(ia))
+        });
+
+        let number_words = removal_order.len();
+        let start = (number_phrases == 0) as usize;
+        for word_count in start..=number_words {
+            let removed: Vec<_> =
+                removal_order[..number_words - word_count].iter().map(|(i, _)| *i).collect();
             let query: Vec<_> = query
                 .iter()
-                .filter(|p| {
-                    if p.is_phrase() {
-                        true
-                    } else if word_count != 0 {
-                        word_count -= 1;
-                        true
-                    } else {
-                        false
-                    }
-                })
-                .cloned()
+                .enumerate()
+                .filter(|(i, _)| !removed.contains(i))
+                .map(|(_, part)| part.clone())
                 .collect();
 
-            let ngrams = ngrams(ctx, authorize_typos, &query)?;
+            let ngrams = ngrams(ctx, typo_config, &query)?;
             operation_children.push(ngrams);
         }
 
@@ -418,9 +622,9 @@ fn create_query_tree(
     }
 
     if optional_words {
-        optional_word(ctx, authorize_typos, query.to_vec())
+        optional_word(ctx, typo_config, query.to_vec())
     } else {
-        ngrams(ctx, authorize_typos, query)
+        ngrams(ctx, typo_config, query)
     }
 }
 
@@ -429,7 +633,9 @@ pub type PrimitiveQuery = Vec<PrimitiveQueryPart>;
 #[derive(Debug, Clone)]
 pub enum PrimitiveQueryPart {
     Phrase(Vec<String>),
-    Word(String, IsPrefix),
+    /// A normalized word, if it is a prefix, the exact version of this word
+    /// and the field it must be found in when the word is scoped (e.g. `title:rust`).
+    Word(String, IsPrefix, ExactWord, Option<FieldId>),
 }
 
 impl PrimitiveQueryPart {
@@ -438,20 +644,39 @@ impl PrimitiveQueryPart {
     }
 
     fn is_prefix(&self) -> bool {
-        matches!(self, Self::Word(_, is_prefix) if *is_prefix)
+        matches!(self, Self::Word(_, is_prefix, _, _) if *is_prefix)
+    }
+
+    fn is_scoped(&self) -> bool {
+        matches!(self, Self::Word(_, _, _, Some(_)))
     }
 }
 
+/// Returns the field the next word is scoped to when this word is the name
+/// of a searchable field directly followed by a colon (e.g. `title:rust`).
+fn scoped_field(text: &str, token: &Token, fields: &HashMap<String, FieldId>) -> Option<FieldId> {
+    let after = text[token.byte_end..].strip_prefix(':')?;
+    if after.chars().next().map_or(true, char::is_whitespace) {
+        return None;
+    }
+    fields.get(&text[token.byte_start..token.byte_end]).copied()
+}
+
 /// Create primitive query from tokenized query string,
 /// the primitive query is an intermediate state to build the query tree.
-fn create_primitive_query(
-    query: TokenStream,
+///
+/// The `fields` are the searchable fields the words of the query can be scoped to.
+fn create_primitive_query<'t>(
+    text: &str,
+    query: impl Iterator<Item = Token<'t>>,
     stop_words: Option<Set<&[u8]>>,
+    fields: &HashMap<String, FieldId>,
     words_limit: Option<usize>,
 ) -> PrimitiveQuery {
     let mut primitive_query = Vec::new();
     let mut phrase = Vec::new();
     let mut quoted = false;
+    let mut scope = None;
 
     let parts_limit = words_limit.unwrap_or(usize::MAX);
 
@@ -466,19 +691,44 @@ fn create_primitive_query(
             TokenKind::Word | TokenKind::StopWord => {
                 // 1. if the word is quoted we push it in a phrase-buffer waiting for the ending quote,
                 // 2. if the word is not the last token of the query and is not a stop_word we push it as a non-prefix word,
-                // 3. if the word is the last token of the query we push it as a prefix word.
+                // 3. if the word is the last token of the query we push it as a prefix word,
+                // 4. a word that directly follows the name of a field and a colon is scoped to this field.
                 if quoted {
                     phrase.push(token.word.to_string());
-                } else if peekable.peek().is_some() {
-                    if !stop_words
-                        .as_ref()
-                        .map_or(false, |swords| swords.contains(token.word.as_ref()))
+                    continue;
+                } else if is_negated_word(text, &token) {
+                    // The negated words only exclude documents, they aren't searched.
+                    continue;
+                } else if let Some(field) = scoped_field(text, &token, fields) {
+                    // The name of the field isn't searched, it scopes the word that follows the colon.
+                    scope = Some((field, token.byte_end + 1));
+                    continue;
+                }
+
+                let field = scope.take().filter(|(_, start)| *start == token.byte_start);
+                let field = field.map(|(field, _)| field);
+                if peekable.peek().is_some() {
+                    if field.is_some()
+                        || !stop_words
+                            .as_ref()
+                            .map_or(false, |swords| swords.contains(token.word.as_ref()))
                     {
-                        primitive_query
-                            .push(PrimitiveQueryPart::Word(token.word.to_string(), false));
+                        let exact = exact_word(&text[token.byte_start..token.byte_end]);
+                        primitive_query.push(PrimitiveQueryPart::Word(
+                            token.word.to_string(),
+                            false,
+                            exact,
+                            field,
+                        ));
                     }
                 } else {
-                    primitive_query.push(PrimitiveQueryPart::Word(token.word.to_string(), true));
+                    let exact = exact_word(&text[token.byte_start..token.byte_end]);
+                    primitive_query.push(PrimitiveQueryPart::Word(
+                        token.word.to_string(),
+                        true,
+                        exact,
+                        field,
+                    ));
                 }
             }
             TokenKind::Separator(separator_kind) => {
@@ -507,25 +757,26 @@ fn create_primitive_query(
 
 /// Returns the maximum number of typos that this Operation allows.
 pub fn maximum_typo(operation: &Operation) -> usize {
-    use Operation::{And, Or, Phrase, Query};
+    use Operation::{And, Or, Phrase, Query, Split};
     match operation {
         Or(_, ops) => ops.iter().map(maximum_typo).max().unwrap_or(0),
         And(ops) => ops.iter().map(maximum_typo).sum::<usize>(),
         Query(q) => q.kind.typo() as usize,
         // no typo allowed in phrases
         Phrase(_) => 0,
+        Split(_) => SPLIT_WORD_TYPO as usize,
     }
 }
 
 /// Returns the maximum proximity that this Operation allows.
 pub fn maximum_proximity(operation: &Operation) -> usize {
-    use Operation::{And, Or, Phrase, Query};
+    use Operation::{And, Or, Phrase, Query, Split};
     match operation {
         Or(_, ops) => ops.iter().map(maximum_proximity).max().unwrap_or(0),
         And(ops) => {
             ops.iter().map(maximum_proximity).sum::<usize>() + ops.len().saturating_sub(1) * 7
         }
-        Query(_) | Phrase(_) => 0,
+        Query(_) | Phrase(_) | Split(_) => 0,
     }
 }
 
@@ -534,6 +785,7 @@ mod test {
     use std::collections::HashMap;
 
     use maplit::hashmap;
+    use meilisearch_tokenizer::tokenizer::TokenStream;
     use meilisearch_tokenizer::{Analyzer, AnalyzerConfig};
     use rand::rngs::StdRng;
     use rand::{Rng, SeedableRng};
@@ -544,6 +796,7 @@ mod test {
     struct TestContext {
         synonyms: HashMap<Vec<String>, Vec<Vec<String>>>,
         postings: HashMap<String, RoaringBitmap>,
+        term_boosts: HashMap<String, f32>,
     }
 
     impl TestContext {
@@ -552,12 +805,14 @@ mod test {
             optional_words: bool,
             authorize_typos: bool,
             words_limit: Option<usize>,
+            text: &str,
             query: TokenStream,
         ) -> Result<Option<(Operation, PrimitiveQuery)>> {
-            let primitive_query = create_primitive_query(query, None, words_limit);
+            let primitive_query =
+                create_primitive_query(text, query, None, &HashMap::new(), words_limit);
             if !primitive_query.is_empty() {
-                let qt =
-                    create_query_tree(self, optional_words, authorize_typos, &primitive_query)?;
+                let typo_config = TypoConfig::new(authorize_typos);
+                let qt = create_query_tree(self, optional_words, typo_config, &primitive_query)?;
                 Ok(Some((qt, primitive_query)))
             } else {
                 Ok(None)
@@ -574,6 +829,10 @@ mod test {
             let words: Vec<_> = words.iter().map(|s| s.as_ref().to_owned()).collect();
             Ok(self.synonyms.get(&words).cloned())
         }
+
+        fn term_boost(&self, word: &str) -> f32 {
+            self.term_boosts.get(word).copied().unwrap_or(1.0)
+        }
     }
 
     impl Default for TestContext {
@@ -630,6 +889,7 @@ mod test {
                     String::from("good")       => random_postings(rng,   1250),
                     String::from("morning")    => random_postings(rng,    125),
                 },
+                term_boosts: HashMap::new(),
             }
         }
     }
@@ -648,21 +908,24 @@ mod test {
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("hey".to_string()),
+                        field: None,
                     }),
                     Operation::Query(Query {
                         prefix: true,
                         kind: QueryKind::tolerant(1, "friends".to_string()),
+                        field: None,
                     }),
                 ]),
                 Operation::Query(Query {
                     prefix: true,
                     kind: QueryKind::tolerant(2, "heyfriends".to_string()),
+                    field: None,
                 }),
             ],
         );
 
         let (query_tree, _) =
-            TestContext::default().build(false, true, None, tokens).unwrap().unwrap();
+            TestContext::default().build(false, true, None, query, tokens).unwrap().unwrap();
 
         assert_eq!(expected, query_tree);
     }
@@ -681,21 +944,24 @@ mod test {
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("hey".to_string()),
+                        field: None,
                     }),
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::tolerant(1, "friends".to_string()),
+                        field: None,
                     }),
                 ]),
                 Operation::Query(Query {
                     prefix: false,
                     kind: QueryKind::tolerant(2, "heyfriends".to_string()),
+                    field: None,
                 }),
             ],
         );
 
         let (query_tree, _) =
-            TestContext::default().build(false, true, None, tokens).unwrap().unwrap();
+            TestContext::default().build(false, true, None, query, tokens).unwrap().unwrap();
 
         assert_eq!(expected, query_tree);
     }
@@ -717,20 +983,24 @@ mod test {
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::exact("hi".to_string()),
+                                field: None,
                             }),
                             Operation::And(vec![
                                 Operation::Query(Query {
                                     prefix: false,
                                     kind: QueryKind::exact("good".to_string()),
+                                    field: None,
                                 }),
                                 Operation::Query(Query {
                                     prefix: false,
                                     kind: QueryKind::exact("morning".to_string()),
+                                    field: None,
                                 }),
                             ]),
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::tolerant(1, "hello".to_string()),
+                                field: None,
                             }),
                         ],
                     ),
@@ -740,14 +1010,17 @@ mod test {
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::exact("earth".to_string()),
+                                field: None,
                             }),
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::exact("nature".to_string()),
+                                field: None,
                             }),
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::tolerant(1, "world".to_string()),
+                                field: None,
                             }),
                         ],
                     ),
@@ -755,12 +1028,13 @@ mod test {
                 Operation::Query(Query {
                     prefix: false,
                     kind: QueryKind::tolerant(2, "helloworld".to_string()),
+                    field: None,
                 }),
             ],
         );
 
         let (query_tree, _) =
-            TestContext::default().build(false, true, None, tokens).unwrap().unwrap();
+            TestContext::default().build(false, true, None, query, tokens).unwrap().unwrap();
 
         assert_eq!(expected, query_tree);
     }
@@ -779,6 +1053,7 @@ mod test {
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("new".to_string()),
+                        field: None,
                     }),
                     Operation::Or(
                         false,
@@ -787,15 +1062,18 @@ mod test {
                                 Operation::Query(Query {
                                     prefix: false,
                                     kind: QueryKind::exact("york".to_string()),
+                                    field: None,
                                 }),
                                 Operation::Query(Query {
                                     prefix: false,
                                     kind: QueryKind::exact("city".to_string()),
+                                    field: None,
                                 }),
                             ]),
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::tolerant(1, "yorkcity".to_string()),
+                                field: None,
                             }),
                         ],
                     ),
@@ -807,30 +1085,36 @@ mod test {
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::exact("nyc".to_string()),
+                                field: None,
                             }),
                             Operation::And(vec![
                                 Operation::Query(Query {
                                     prefix: false,
                                     kind: QueryKind::exact("new".to_string()),
+                                    field: None,
                                 }),
                                 Operation::Query(Query {
                                     prefix: false,
                                     kind: QueryKind::exact("york".to_string()),
+                                    field: None,
                                 }),
                                 Operation::Query(Query {
                                     prefix: false,
                                     kind: QueryKind::exact("city".to_string()),
+                                    field: None,
                                 }),
                             ]),
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::tolerant(1, "newyork".to_string()),
+                                field: None,
                             }),
                         ],
                     ),
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("city".to_string()),
+                        field: None,
                     }),
                 ]),
                 Operation::Or(
@@ -839,20 +1123,24 @@ mod test {
                         Operation::Query(Query {
                             prefix: false,
                             kind: QueryKind::exact("nyc".to_string()),
+                            field: None,
                         }),
                         Operation::And(vec![
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::exact("new".to_string()),
+                                field: None,
                             }),
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::exact("york".to_string()),
+                                field: None,
                             }),
                         ]),
                         Operation::Query(Query {
                             prefix: false,
                             kind: QueryKind::tolerant(2, "newyorkcity".to_string()),
+                            field: None,
                         }),
                     ],
                 ),
@@ -860,7 +1148,7 @@ mod test {
         );
 
         let (query_tree, _) =
-            TestContext::default().build(false, true, None, tokens).unwrap().unwrap();
+            TestContext::default().build(false, true, None, query, tokens).unwrap().unwrap();
 
         assert_eq!(expected, query_tree);
     }
@@ -879,21 +1167,24 @@ mod test {
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("n".to_string()),
+                        field: None,
                     }),
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::tolerant(1, "grams".to_string()),
+                        field: None,
                     }),
                 ]),
                 Operation::Query(Query {
                     prefix: false,
                     kind: QueryKind::tolerant(1, "ngrams".to_string()),
+                    field: None,
                 }),
             ],
         );
 
         let (query_tree, _) =
-            TestContext::default().build(false, true, None, tokens).unwrap().unwrap();
+            TestContext::default().build(false, true, None, query, tokens).unwrap().unwrap();
 
         assert_eq!(expected, query_tree);
     }
@@ -912,27 +1203,30 @@ mod test {
                     Operation::Or(
                         false,
                         vec![
-                            Operation::Phrase(vec!["word".to_string(), "split".to_string()]),
+                            Operation::Split(vec!["word".to_string(), "split".to_string()]),
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::tolerant(2, "wordsplit".to_string()),
+                                field: None,
                             }),
                         ],
                     ),
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("fish".to_string()),
+                        field: None,
                     }),
                 ]),
                 Operation::Query(Query {
                     prefix: false,
                     kind: QueryKind::tolerant(2, "wordsplitfish".to_string()),
+                    field: None,
                 }),
             ],
         );
 
         let (query_tree, _) =
-            TestContext::default().build(false, true, None, tokens).unwrap().unwrap();
+            TestContext::default().build(false, true, None, query, tokens).unwrap().unwrap();
 
         assert_eq!(expected, query_tree);
     }
@@ -946,15 +1240,97 @@ mod test {
 
         let expected = Operation::And(vec![
             Operation::Phrase(vec!["hey".to_string(), "friends".to_string()]),
-            Operation::Query(Query { prefix: false, kind: QueryKind::exact("wooop".to_string()) }),
+            Operation::Query(Query {
+                prefix: false,
+                kind: QueryKind::exact("wooop".to_string()),
+                field: None,
+            }),
         ]);
 
         let (query_tree, _) =
-            TestContext::default().build(false, true, None, tokens).unwrap().unwrap();
+            TestContext::default().build(false, true, None, query, tokens).unwrap().unwrap();
 
         assert_eq!(expected, query_tree);
     }
 
+    #[test]
+    fn scoped_words() {
+        let query = "title:rust is fun desc:hello title: world";
+        let analyzer = Analyzer::new(AnalyzerConfig::<Vec<u8>>::default());
+        let result = analyzer.analyze(query);
+        let fields = hashmap! { String::from("title") => 1 };
+
+        let primitive_query = create_primitive_query(query, result.tokens(), None, &fields, None);
+        let words: Vec<_> = primitive_query
+            .iter()
+            .map(|part| match part {
+                PrimitiveQueryPart::Word(word, _, _, field) => (word.as_str(), *field),
+                PrimitiveQueryPart::Phrase(_) => panic!("unexpected phrase"),
+            })
+            .collect();
+        assert_eq!(
+            words,
+            vec![
+                ("rust", Some(1)),
+                ("is", None),
+                ("fun", None),
+                ("desc", None),
+                ("hello", None),
+                ("title", None),
+                ("world", None),
+            ]
+        );
+
+        // A scoped word is never concatenated with its neighbours.
+        let tokens = result.tokens();
+        let primitive_query = create_primitive_query(query, tokens, None, &fields, Some(2));
+        let query_tree = create_query_tree(
+            &TestContext::default(),
+            false,
+            TypoConfig::new(false),
+            &primitive_query,
+        )
+        .unwrap();
+        let expected = Operation::And(vec![
+            Operation::Query(Query {
+                prefix: false,
+                kind: QueryKind::exact("rust".to_string()),
+                field: Some(1),
+            }),
+            Operation::Query(Query {
+                prefix: false,
+                kind: QueryKind::exact("is".to_string()),
+                field: None,
+            }),
+        ]);
+        assert_eq!(query_tree, expected);
+    }
+
+    #[test]
+    fn negated_words() {
+        let query = "hey -friends well-known \"wooop -wooop\"";
+        let analyzer = Analyzer::new(AnalyzerConfig::<Vec<u8>>::default());
+        let result = analyzer.analyze(query);
+
+        let primitive_query =
+            create_primitive_query(query, result.tokens(), None, &HashMap::new(), None);
+        let words: Vec<_> = primitive_query
+            .iter()
+            .map(|part| match part {
+                PrimitiveQueryPart::Word(word, ..) => vec![word.as_str()],
+                PrimitiveQueryPart::Phrase(words) => words.iter().map(String::as_str).collect(),
+            })
+            .collect();
+        assert_eq!(words, vec![vec!["hey"], vec!["well"], vec!["known"], vec!["wooop", "wooop"]]);
+
+        let negated: Vec<_> = result
+            .tokens()
+            .filter(|token| token.is_word() && is_negated_word(query, token))
+            .map(|token| token.word.to_string())
+            .collect();
+        assert_eq!(negated, vec!["friends"]);
+    }
+
     #[test]
     fn phrase_with_hard_separator() {
         let query = "\"hey friends. wooop wooop\"";
@@ -968,7 +1344,7 @@ mod test {
         ]);
 
         let (query_tree, _) =
-            TestContext::default().build(false, true, None, tokens).unwrap().unwrap();
+            TestContext::default().build(false, true, None, query, tokens).unwrap().unwrap();
 
         assert_eq!(expected, query_tree);
     }
@@ -986,6 +1362,7 @@ mod test {
                 Operation::Query(Query {
                     prefix: false,
                     kind: QueryKind::exact("hey".to_string()),
+                    field: None,
                 }),
                 Operation::Or(
                     false,
@@ -994,15 +1371,18 @@ mod test {
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::exact("hey".to_string()),
+                                field: None,
                             }),
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::exact("my".to_string()),
+                                field: None,
                             }),
                         ]),
                         Operation::Query(Query {
                             prefix: false,
                             kind: QueryKind::tolerant(1, "heymy".to_string()),
+                            field: None,
                         }),
                     ],
                 ),
@@ -1013,6 +1393,7 @@ mod test {
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::exact("hey".to_string()),
+                                field: None,
                             }),
                             Operation::Or(
                                 false,
@@ -1021,15 +1402,18 @@ mod test {
                                         Operation::Query(Query {
                                             prefix: false,
                                             kind: QueryKind::exact("my".to_string()),
+                                            field: None,
                                         }),
                                         Operation::Query(Query {
                                             prefix: false,
                                             kind: QueryKind::tolerant(1, "friend".to_string()),
+                                            field: None,
                                         }),
                                     ]),
                                     Operation::Query(Query {
                                         prefix: false,
                                         kind: QueryKind::tolerant(1, "myfriend".to_string()),
+                                        field: None,
                                     }),
                                 ],
                             ),
@@ -1038,26 +1422,86 @@ mod test {
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::tolerant(1, "heymy".to_string()),
+                                field: None,
                             }),
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::tolerant(1, "friend".to_string()),
+                                field: None,
                             }),
                         ]),
                         Operation::Query(Query {
                             prefix: false,
                             kind: QueryKind::tolerant(2, "heymyfriend".to_string()),
+                            field: None,
                         }),
                     ],
                 ),
             ],
         );
         let (query_tree, _) =
-            TestContext::default().build(true, true, None, tokens).unwrap().unwrap();
+            TestContext::default().build(true, true, None, query, tokens).unwrap().unwrap();
 
         assert_eq!(expected, query_tree);
     }
 
+    #[test]
+    fn optional_word_with_term_boosts() {
+        let query = "hey my friend ";
+        let analyzer = Analyzer::new(AnalyzerConfig::<Vec<u8>>::default());
+        let result = analyzer.analyze(query);
+        let tokens = result.tokens();
+
+        fn words(operation: &Operation, out: &mut Vec<String>) {
+            match operation {
+                Operation::And(ops) | Operation::Or(_, ops) => {
+                    ops.iter().for_each(|op| words(op, out))
+                }
+                Operation::Phrase(phrase) | Operation::Split(phrase) => {
+                    out.extend(phrase.iter().cloned())
+                }
+                Operation::Query(query) => out.push(query.kind.word().to_string()),
+            }
+        }
+
+        // The boosted word is removed last, the other words are removed from the end.
+        let context = TestContext {
+            term_boosts: hashmap! { String::from("hey") => 2.0 },
+            ..TestContext::default()
+        };
+        let (query_tree, _) = context.build(true, false, None, query, tokens).unwrap().unwrap();
+        let branches = match query_tree {
+            Operation::Or(true, branches) => branches,
+            otherwise => panic!("unexpected query tree {:?}", otherwise),
+        };
+        assert_eq!(branches.len(), 3);
+
+        let mut first = Vec::new();
+        words(&branches[0], &mut first);
+        assert!(first.contains(&String::from("hey")));
+        assert!(!first.contains(&String::from("my")) && !first.contains(&String::from("friend")));
+
+        let mut second = Vec::new();
+        words(&branches[1], &mut second);
+        assert!(second.contains(&String::from("hey")) && second.contains(&String::from("my")));
+        assert!(!second.contains(&String::from("friend")));
+
+        let context = TestContext {
+            term_boosts: hashmap! { String::from("friend") => 2.0 },
+            ..TestContext::default()
+        };
+        let tokens = result.tokens();
+        let (query_tree, _) = context.build(true, false, None, query, tokens).unwrap().unwrap();
+        let branches = match query_tree {
+            Operation::Or(true, branches) => branches,
+            otherwise => panic!("unexpected query tree {:?}", otherwise),
+        };
+        let mut first = Vec::new();
+        words(&branches[0], &mut first);
+        assert!(first.contains(&String::from("friend")));
+        assert!(!first.contains(&String::from("hey")) && !first.contains(&String::from("my")));
+    }
+
     #[test]
     fn optional_word_phrase() {
         let query = "\"hey my\"";
@@ -1067,7 +1511,7 @@ mod test {
 
         let expected = Operation::Phrase(vec!["hey".to_string(), "my".to_string()]);
         let (query_tree, _) =
-            TestContext::default().build(true, true, None, tokens).unwrap().unwrap();
+            TestContext::default().build(true, true, None, query, tokens).unwrap().unwrap();
 
         assert_eq!(expected, query_tree);
     }
@@ -1086,30 +1530,36 @@ mod test {
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("hey".to_string()),
+                        field: None,
                     }),
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("friend".to_string()),
+                        field: None,
                     }),
                 ]),
                 Operation::And(vec![
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("hey".to_string()),
+                        field: None,
                     }),
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("my".to_string()),
+                        field: None,
                     }),
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("friend".to_string()),
+                        field: None,
                     }),
                 ]),
                 Operation::And(vec![
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("hey".to_string()),
+                        field: None,
                     }),
                     Operation::Or(
                         false,
@@ -1118,27 +1568,31 @@ mod test {
                                 Operation::Query(Query {
                                     prefix: false,
                                     kind: QueryKind::exact("my".to_string()),
+                                    field: None,
                                 }),
                                 Operation::Query(Query {
                                     prefix: false,
                                     kind: QueryKind::exact("good".to_string()),
+                                    field: None,
                                 }),
                             ]),
                             Operation::Query(Query {
                                 prefix: false,
                                 kind: QueryKind::tolerant(1, "mygood".to_string()),
+                                field: None,
                             }),
                         ],
                     ),
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("friend".to_string()),
+                        field: None,
                     }),
                 ]),
             ],
         );
         let (query_tree, _) =
-            TestContext::default().build(true, true, None, tokens).unwrap().unwrap();
+            TestContext::default().build(true, true, None, query, tokens).unwrap().unwrap();
 
         assert_eq!(expected, query_tree);
     }
@@ -1157,20 +1611,23 @@ mod test {
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("hey".to_string()),
+                        field: None,
                     }),
                     Operation::Query(Query {
                         prefix: false,
                         kind: QueryKind::exact("friends".to_string()),
+                        field: None,
                     }),
                 ]),
                 Operation::Query(Query {
                     prefix: false,
                     kind: QueryKind::exact("heyfriends".to_string()),
+                    field: None,
                 }),
             ],
         );
         let (query_tree, _) =
-            TestContext::default().build(false, false, None, tokens).unwrap().unwrap();
+            TestContext::default().build(false, false, None, query, tokens).unwrap().unwrap();
 
         assert_eq!(expected, query_tree);
     }
@@ -1184,11 +1641,15 @@ mod test {
 
         let expected = Operation::And(vec![
             Operation::Phrase(vec!["hey".to_string(), "my".to_string()]),
-            Operation::Query(Query { prefix: false, kind: QueryKind::exact("good".to_string()) }),
+            Operation::Query(Query {
+                prefix: false,
+                kind: QueryKind::exact("good".to_string()),
+                field: None,
+            }),
         ]);
 
         let (query_tree, _) =
-            TestContext::default().build(false, false, Some(2), tokens).unwrap().unwrap();
+            TestContext::default().build(false, false, Some(2), query, tokens).unwrap().unwrap();
 
         assert_eq!(expected, query_tree);
     }
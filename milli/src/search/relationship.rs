@@ -0,0 +1,263 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use roaring::RoaringBitmap;
+use serde_json::Value;
+
+use super::distinct::{facet_number_values, facet_string_values, Distinct, DocIter};
+use crate::error::{InternalError, UserError};
+use crate::facet::facet_string_key;
+use crate::{DocumentId, ExternalDocumentsIds, FieldId, Index, Result};
+
+/// Defines which documents are returned when the documents of the index are linked
+/// together by the parent field, the documents that are not linked are always returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relationship {
+    /// The matching child documents are replaced by their parent document.
+    Parents,
+    /// The matching parent documents are replaced by their child documents.
+    Children,
+}
+
+/// Retrieves the documents that are linked by the parent field of the index.
+///
+/// The parent field of a child document contains the external id of its parent document,
+/// it is faceted, which allows us to find the children of a parent by its external id.
+pub(crate) struct Relatives<'a> {
+    parent_field: FieldId,
+    primary_key: Option<FieldId>,
+    external_documents_ids: ExternalDocumentsIds<'a>,
+    index: &'a Index,
+    rtxn: &'a heed::RoTxn<'a>,
+}
+
+impl<'a> Relatives<'a> {
+    /// Returns `None` when no document contains the parent field of the index.
+    pub fn new(rtxn: &'a heed::RoTxn, index: &'a Index) -> Result<Option<Relatives<'a>>> {
+        let parent_field = index.parent_field(rtxn)?.ok_or(UserError::ParentFieldMissing)?;
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        let parent_field = match fields_ids_map.id(parent_field) {
+            Some(fid) => fid,
+            None => return Ok(None),
+        };
+        let primary_key = index.primary_key(rtxn)?.and_then(|name| fields_ids_map.id(name));
+        let external_documents_ids = index.external_documents_ids(rtxn)?;
+
+        Ok(Some(Relatives { parent_field, primary_key, external_documents_ids, index, rtxn }))
+    }
+
+    /// Returns the parent of the given document, `None` if it isn't a child document.
+    fn parent(&self, docid: DocumentId) -> Result<Option<DocumentId>> {
+        for result in facet_string_values(docid, self.parent_field, self.index, self.rtxn)? {
+            let ((_, _, _normalized), original) = result?;
+            if let Some(id) = self.external_documents_ids.get(original) {
+                return Ok(Some(id));
+            }
+        }
+
+        for result in facet_number_values(docid, self.parent_field, self.index, self.rtxn)? {
+            let ((_, _, value), ()) = result?;
+            if let Some(id) = self.external_documents_ids.get(value.to_string()) {
+                return Ok(Some(id));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the children of the given document.
+    fn children(&self, docid: DocumentId) -> Result<RoaringBitmap> {
+        let mut children = RoaringBitmap::new();
+
+        let primary_key = match self.primary_key {
+            Some(fid) => fid,
+            None => return Ok(children),
+        };
+
+        let document = self
+            .index
+            .document_bytes(self.rtxn, docid)?
+            .ok_or(UserError::UnknownInternalDocumentId { document_id: docid })?;
+        let document = obkv::KvReaderU16::new(&document);
+        let external_id = match document.get(primary_key) {
+            Some(bytes) => match serde_json::from_slice(bytes).map_err(InternalError::SerdeJson)? {
+                Value::String(string) => string,
+                Value::Number(number) => number.to_string(),
+                _ => return Ok(children),
+            },
+            None => return Ok(children),
+        };
+
+        let normalized = external_id.trim().to_lowercase();
+        let normalized = facet_string_key(&normalized);
+        let key = (self.parent_field, normalized.as_ref());
+        if let Some((_, docids)) = self.index.facet_id_string_docids.get(self.rtxn, &key)? {
+            children |= docids;
+        }
+
+        if let Ok(value) = external_id.parse::<f64>() {
+            let key = (self.parent_field, 0, value, value);
+            if let Some(docids) = self.index.facet_id_f64_docids.get(self.rtxn, &key)? {
+                children |= docids;
+            }
+        }
+
+        Ok(children)
+    }
+
+    /// Returns the documents that must be returned in place of the given document.
+    pub fn relatives(
+        &self,
+        relationship: Relationship,
+        docid: DocumentId,
+    ) -> Result<RoaringBitmap> {
+        let mut relatives = RoaringBitmap::new();
+        match relationship {
+            Relationship::Parents => {
+                relatives.insert(self.parent(docid)?.unwrap_or(docid));
+            }
+            Relationship::Children => {
+                relatives = self.children(docid)?;
+                if relatives.is_empty() {
+                    relatives.insert(docid);
+                }
+            }
+        }
+        Ok(relatives)
+    }
+
+    /// Replaces every one of the candidates by their relatives.
+    pub fn map_candidates(
+        &self,
+        relationship: Relationship,
+        candidates: &RoaringBitmap,
+    ) -> Result<RoaringBitmap> {
+        let mut output = RoaringBitmap::new();
+        for docid in candidates {
+            output |= self.relatives(relationship, docid)?;
+        }
+        Ok(output)
+    }
+}
+
+/// A distinct implementer that replaces the documents returned by another
+/// distinct implementer by their relatives, making sure to return them only once.
+pub(crate) struct RelativesDistinct<'a, D> {
+    inner: D,
+    relationship: Relationship,
+    relatives: Rc<Relatives<'a>>,
+    returned: Rc<RefCell<RoaringBitmap>>,
+}
+
+impl<'a, D> RelativesDistinct<'a, D> {
+    pub fn new(inner: D, relationship: Relationship, relatives: Rc<Relatives<'a>>) -> Self {
+        RelativesDistinct { inner, relationship, relatives, returned: Rc::default() }
+    }
+}
+
+pub(crate) struct RelativesIter<'a, I> {
+    inner: I,
+    relationship: Relationship,
+    relatives: Rc<Relatives<'a>>,
+    returned: Rc<RefCell<RoaringBitmap>>,
+    pending: RoaringBitmap,
+}
+
+impl<I: DocIter> RelativesIter<'_, I> {
+    fn next_inner(&mut self) -> Result<Option<DocumentId>> {
+        loop {
+            if let Some(id) = self.pending.iter().next() {
+                self.pending.remove(id);
+                self.returned.borrow_mut().insert(id);
+                return Ok(Some(id));
+            }
+
+            match self.inner.next() {
+                Some(result) => {
+                    let mut relatives = self.relatives.relatives(self.relationship, result?)?;
+                    relatives -= &*self.returned.borrow();
+                    self.pending = relatives;
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+impl<I: DocIter> Iterator for RelativesIter<'_, I> {
+    type Item = Result<DocumentId>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_inner().transpose()
+    }
+}
+
+impl<I: DocIter> DocIter for RelativesIter<'_, I> {
+    fn into_excluded(self) -> RoaringBitmap {
+        self.inner.into_excluded()
+    }
+}
+
+impl<'a, D: Distinct> Distinct for RelativesDistinct<'a, D> {
+    type Iter = RelativesIter<'a, D::Iter>;
+
+    fn distinct(&mut self, candidates: RoaringBitmap, excluded: RoaringBitmap) -> Self::Iter {
+        RelativesIter {
+            inner: self.inner.distinct(candidates, excluded),
+            relationship: self.relationship,
+            relatives: self.relatives.clone(),
+            returned: self.returned.clone(),
+            pending: RoaringBitmap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::update::{IndexDocuments, Settings};
+    use crate::Search;
+
+    #[test]
+    fn parents_and_children() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_parent_field(S("parent"));
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": "book", "title": "hello world" },
+            { "id": "chapter-1", "title": "hello", "parent": "book" },
+            { "id": "chapter-2", "title": "world", "parent": "book" },
+            { "id": "alone", "title": "hello" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let external_documents_ids = index.external_documents_ids(&rtxn).unwrap();
+        let docid = |id: &str| external_documents_ids.get(id).unwrap();
+
+        let mut search = Search::new(&rtxn, &index);
+        search.query("hello").relationship(Relationship::Parents);
+        let result = search.execute().unwrap();
+        let mut documents_ids = result.documents_ids.clone();
+        documents_ids.sort_unstable();
+        let mut expected = vec![docid("book"), docid("alone")];
+        expected.sort_unstable();
+        assert_eq!(documents_ids, expected);
+        assert_eq!(result.candidates.len(), 2);
+
+        let mut search = Search::new(&rtxn, &index);
+        search.query("world").relationship(Relationship::Children);
+        let result = search.execute().unwrap();
+        let mut documents_ids = result.documents_ids;
+        documents_ids.sort_unstable();
+        let mut expected = vec![docid("chapter-1"), docid("chapter-2")];
+        expected.sort_unstable();
+        assert_eq!(documents_ids, expected);
+    }
+}
@@ -0,0 +1,130 @@
+use std::fmt;
+use std::hash::Hasher;
+use std::sync::Arc;
+use std::time::Duration;
+
+use fxhash::FxHasher64;
+
+use super::{Search, SearchResult};
+
+/// The information about an executed search that is given to the search log hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchLog {
+    /// The query of the search, hashed when the hook anonymizes the queries.
+    pub query: Option<String>,
+    /// The filter of the search, hashed when the hook anonymizes the filters.
+    pub filter: Option<String>,
+    pub processing_time: Duration,
+    /// The number of documents matching the search.
+    pub hits: u64,
+}
+
+/// A callback that is invoked on every search executed on the index.
+///
+/// The queries and filters can be hashed before being given to the callback, they are hashed
+/// with a fast non-cryptographic hash which is only meant to group the identical values.
+#[derive(Clone)]
+pub struct SearchLogHook {
+    callback: Arc<dyn Fn(&SearchLog) + Send + Sync>,
+    hash_queries: bool,
+    hash_filters: bool,
+}
+
+impl SearchLogHook {
+    pub fn new<F>(callback: F) -> SearchLogHook
+    where
+        F: Fn(&SearchLog) + Send + Sync + 'static,
+    {
+        SearchLogHook { callback: Arc::new(callback), hash_queries: false, hash_filters: false }
+    }
+
+    pub fn hash_queries(mut self, value: bool) -> SearchLogHook {
+        self.hash_queries = value;
+        self
+    }
+
+    pub fn hash_filters(mut self, value: bool) -> SearchLogHook {
+        self.hash_filters = value;
+        self
+    }
+
+    pub(crate) fn log(&self, search: &Search, result: &SearchResult, processing_time: Duration) {
+        let query = search.query.as_ref().map(|query| anonymize(query, self.hash_queries));
+        let filter = search
+            .filter
+            .as_ref()
+            .map(|condition| anonymize(&format!("{:?}", condition), self.hash_filters));
+        let log = SearchLog { query, filter, processing_time, hits: result.candidates.len() };
+        (self.callback)(&log);
+    }
+}
+
+fn anonymize(text: &str, hash: bool) -> String {
+    if hash {
+        let mut hasher = FxHasher64::default();
+        hasher.write(text.as_bytes());
+        format!("{:016x}", hasher.finish())
+    } else {
+        text.to_string()
+    }
+}
+
+impl fmt::Debug for SearchLogHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SearchLogHook")
+            .field("hash_queries", &self.hash_queries)
+            .field("hash_filters", &self.hash_filters)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use big_s::S;
+    use maplit::hashset;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::update::{IndexDocuments, Settings};
+    use crate::FilterCondition;
+
+    #[test]
+    fn log_every_executed_search() {
+        let mut index = TempIndex::new();
+        let logs = Arc::new(Mutex::new(Vec::new()));
+        let logs_cloned = logs.clone();
+        let hook = SearchLogHook::new(move |log| logs_cloned.lock().unwrap().push(log.clone()))
+            .hash_filters(true);
+        index.set_search_log_hook(Some(hook));
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("age") });
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": 0, "name": "kevin", "age": 20 },
+            { "id": 1, "name": "kevina", "age": 21 },
+            { "id": 2, "name": "benoit", "age": 35 }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let condition = FilterCondition::from_str(&rtxn, &index, "age < 30").unwrap();
+        index.search(&rtxn).query("kevin").filter(condition).execute().unwrap();
+        index.search(&rtxn).query("benoit").execute().unwrap();
+
+        let logs = logs.lock().unwrap();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].query.as_deref(), Some("kevin"));
+        assert_eq!(logs[0].hits, 2);
+        let filter = logs[0].filter.as_ref().unwrap();
+        assert_eq!(filter.len(), 16);
+        assert!(!filter.contains("age"));
+        assert_eq!(logs[1].query.as_deref(), Some("benoit"));
+        assert_eq!(logs[1].filter, None);
+        assert_eq!(logs[1].hits, 1);
+    }
+}
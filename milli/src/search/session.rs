@@ -0,0 +1,102 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use roaring::RoaringBitmap;
+
+use super::query_tree::Query;
+use crate::{Index, Result};
+
+/// Caches the documents ids matched by the words of the queries of a search-as-you-type session.
+///
+/// Consecutive queries typed by a user share their first words, the documents ids of the
+/// words and prefixes that were already resolved by a previous query of the session are
+/// reused instead of being computed again from the derivations of the words.
+///
+/// The cache is only valid for a single index and a single snapshot of it, it is cleared
+/// when a search is executed on a read transaction that sees other commits.
+#[derive(Debug, Default)]
+pub struct SearchSession {
+    commits_count: RefCell<Option<u64>>,
+    query_docids: RefCell<HashMap<Query, RoaringBitmap>>,
+}
+
+impl SearchSession {
+    pub fn new() -> SearchSession {
+        SearchSession::default()
+    }
+
+    /// Returns the number of queries whose documents ids are cached.
+    pub fn len(&self) -> usize {
+        self.query_docids.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.query_docids.borrow().is_empty()
+    }
+
+    /// Clears the cache if the transaction doesn't see the snapshot of the cached queries.
+    pub(crate) fn refresh(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<()> {
+        let commits_count = index.commits_count(rtxn)?;
+        let mut cached_commits_count = self.commits_count.borrow_mut();
+        if *cached_commits_count != Some(commits_count) {
+            self.query_docids.borrow_mut().clear();
+            *cached_commits_count = Some(commits_count);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn query_docids(&self, query: &Query) -> Option<RoaringBitmap> {
+        self.query_docids.borrow().get(query).cloned()
+    }
+
+    pub(crate) fn insert_query_docids(&self, query: &Query, docids: &RoaringBitmap) {
+        self.query_docids.borrow_mut().insert(query.clone(), docids.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::update::IndexDocuments;
+
+    #[test]
+    fn reuse_queries_of_the_session() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 0, "name": "hello world" },
+            { "id": 1, "name": "hello worm" },
+            { "id": 2, "name": "help wanted" },
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let session = SearchSession::new();
+        let rtxn = index.read_txn().unwrap();
+        for query in &["hel", "hell", "hello", "hello wor", "hello worl"] {
+            let mut search = index.search(&rtxn);
+            search.query(*query);
+            let expected = search.execute().unwrap();
+            let result = search.session(&session).execute().unwrap();
+            assert_eq!(result.documents_ids, expected.documents_ids);
+            assert_eq!(result.candidates, expected.candidates);
+        }
+        assert!(!session.is_empty());
+        drop(rtxn);
+
+        // The cache is cleared when the session is used on a new snapshot.
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 3, "name": "hello worldwide" }]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = index.search(&rtxn);
+        search.query("hello worl");
+        let expected = search.execute().unwrap();
+        let result = search.session(&session).execute().unwrap();
+        assert_eq!(result.documents_ids, expected.documents_ids);
+        assert_eq!(&result.documents_ids[..2], &[0, 3]);
+    }
+}
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// Where the stop words of the index are removed.
+///
+/// Removing the stop words from the documents makes the index smaller but irreversibly
+/// loses their positions, the phrases that contain them can't be matched anymore.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StopWordsMode {
+    /// The stop words are removed from the documents and from the queries.
+    Everywhere,
+    /// The stop words are kept in the documents and only removed from the queries.
+    QueryOnly,
+    /// The stop words are removed from the documents and kept in the queries.
+    IndexOnly,
+}
+
+impl StopWordsMode {
+    /// Whether the stop words are removed from the documents when they are indexed.
+    pub fn removes_from_documents(&self) -> bool {
+        matches!(self, StopWordsMode::Everywhere | StopWordsMode::IndexOnly)
+    }
+
+    /// Whether the stop words are removed from the queries.
+    pub fn removes_from_queries(&self) -> bool {
+        matches!(self, StopWordsMode::Everywhere | StopWordsMode::QueryOnly)
+    }
+}
+
+impl Default for StopWordsMode {
+    fn default() -> StopWordsMode {
+        StopWordsMode::Everywhere
+    }
+}
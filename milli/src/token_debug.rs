@@ -0,0 +1,48 @@
+use meilisearch_tokenizer::token::SeparatorKind;
+use meilisearch_tokenizer::TokenKind;
+
+use crate::Position;
+
+/// A token produced by the analyzer of the index, as returned by `Index::tokenize_debug`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugToken {
+    /// The part of the original text this token was produced from.
+    pub original: String,
+    /// The normalized text of the token, it is the word stored in the word databases.
+    pub normalized: String,
+    /// The lowercased but not normalized word, used to rank the exact matches.
+    pub exact: String,
+    pub kind: DebugTokenKind,
+    /// The byte offsets of the token in the original text.
+    pub byte_start: usize,
+    pub byte_end: usize,
+    /// The position of the word in the attribute, `None` for the separators.
+    pub position: Option<Position>,
+    /// Whether the word is stored in the word databases of the index, the stop words removed
+    /// from the documents and the words that are in a non-searchable attribute or that are
+    /// after the position stride are not.
+    pub indexed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugTokenKind {
+    Word,
+    /// A stop word removed from the documents, it isn't indexed
+    /// but the positions of the next words account for it.
+    StopWord,
+    SoftSeparator,
+    HardSeparator,
+    Unknown,
+}
+
+impl From<TokenKind> for DebugTokenKind {
+    fn from(kind: TokenKind) -> DebugTokenKind {
+        match kind {
+            TokenKind::Word => DebugTokenKind::Word,
+            TokenKind::StopWord => DebugTokenKind::StopWord,
+            TokenKind::Separator(SeparatorKind::Soft) => DebugTokenKind::SoftSeparator,
+            TokenKind::Separator(SeparatorKind::Hard) => DebugTokenKind::HardSeparator,
+            _ => DebugTokenKind::Unknown,
+        }
+    }
+}
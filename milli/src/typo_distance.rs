@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+/// The edit distance used to count the typos between a query word and its derivations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TypoDistance {
+    /// The transposition of two adjacent letters counts as two typos.
+    Levenshtein,
+    /// The transposition of two adjacent letters counts as a single typo, "hlelo" is
+    /// a one typo derivation of "hello".
+    DamerauLevenshtein,
+}
+
+impl TypoDistance {
+    /// Returns `true` if the transposition of two adjacent letters counts as a single typo.
+    pub fn transposition_cost_one(&self) -> bool {
+        matches!(self, TypoDistance::DamerauLevenshtein)
+    }
+}
+
+impl Default for TypoDistance {
+    fn default() -> TypoDistance {
+        TypoDistance::DamerauLevenshtein
+    }
+}
@@ -1,4 +1,4 @@
-use std::iter::{Chain, FromIterator};
+use std::iter::Chain;
 use std::ops::RangeInclusive;
 
 use roaring::bitmap::{IntoIter, RoaringBitmap};
@@ -9,13 +9,26 @@ pub struct AvailableDocumentsIds {
 
 impl AvailableDocumentsIds {
     pub fn from_documents_ids(docids: &RoaringBitmap) -> AvailableDocumentsIds {
+        AvailableDocumentsIds::from_documents_ids_in_range(docids, 0..=u32::max_value())
+    }
+
+    /// Only returns the available documents ids that are in the given range.
+    pub fn from_documents_ids_in_range(
+        docids: &RoaringBitmap,
+        range: RangeInclusive<u32>,
+    ) -> AvailableDocumentsIds {
+        let (first_id, end_id) = range.into_inner();
+        let mut docids = docids.clone();
+        docids.remove_range(0..first_id as u64);
+        docids.remove_range(end_id as u64 + 1..u32::max_value() as u64 + 1);
         match docids.max() {
             Some(last_id) => {
-                let mut available = RoaringBitmap::from_iter(0..last_id);
+                let mut available = RoaringBitmap::new();
+                available.insert_range(first_id as u64..last_id as u64);
                 available -= docids;
 
                 let iter = match last_id.checked_add(1) {
-                    Some(id) => id..=u32::max_value(),
+                    Some(id) => id..=end_id,
                     None => 1..=0, // empty range iterator
                 };
 
@@ -23,7 +36,7 @@ impl AvailableDocumentsIds {
             }
             None => {
                 let empty = RoaringBitmap::new().into_iter();
-                AvailableDocumentsIds { iter: empty.chain(0..=u32::max_value()) }
+                AvailableDocumentsIds { iter: empty.chain(first_id..=end_id) }
             }
         }
     }
@@ -49,6 +62,17 @@ mod tests {
         left.zip(right).take(500).for_each(|(l, r)| assert_eq!(l, r));
     }
 
+    #[test]
+    fn in_range() {
+        let mut base = RoaringBitmap::new();
+        base.insert(5);
+        base.insert(12);
+        base.insert(13);
+
+        let left = AvailableDocumentsIds::from_documents_ids_in_range(&base, 10..=15);
+        assert_eq!(left.collect::<Vec<_>>(), vec![10, 11, 14, 15]);
+    }
+
     #[test]
     fn scattered() {
         let mut base = RoaringBitmap::new();
@@ -1,7 +1,7 @@
 use chrono::Utc;
 use roaring::RoaringBitmap;
 
-use crate::{ExternalDocumentsIds, FieldDistribution, Index, Result};
+use crate::{ExternalDocumentsIds, FieldDistribution, FieldTypes, Index, Result};
 
 pub struct ClearDocuments<'t, 'u, 'i> {
     wtxn: &'t mut heed::RwTxn<'i, 'u>,
@@ -24,18 +24,32 @@ impl<'t, 'u, 'i> ClearDocuments<'t, 'u, 'i> {
             env: _env,
             main: _main,
             word_docids,
+            exact_word_docids,
             word_prefix_docids,
             docid_word_positions,
             word_pair_proximity_docids,
             word_prefix_pair_proximity_docids,
             word_position_docids,
             field_id_word_count_docids,
+            field_id_exact_value_docids,
             word_prefix_position_docids,
             facet_id_f64_docids,
             facet_id_string_docids,
             field_id_docid_facet_f64s,
             field_id_docid_facet_strings,
+            facet_id_exists_docids,
+            facet_id_is_null_docids,
+            facet_id_is_empty_docids,
             documents,
+            docid_fingerprints,
+            fingerprint_band_docids,
+            docid_word_counts,
+            search_log_hook: _,
+            writer_lock: _,
+            documents_cipher: _,
+            filter_attempts: _,
+            facet_range_cache: _,
+            commit_hooks: _,
         } = self.index;
 
         // We retrieve the number of documents ids that we are deleting.
@@ -48,8 +62,10 @@ impl<'t, 'u, 'i> ClearDocuments<'t, 'u, 'i> {
         self.index.put_external_documents_ids(self.wtxn, &ExternalDocumentsIds::default())?;
         self.index.put_documents_ids(self.wtxn, &RoaringBitmap::default())?;
         self.index.put_field_distribution(self.wtxn, &FieldDistribution::default())?;
+        self.index.put_field_types(self.wtxn, &FieldTypes::default())?;
         self.index.delete_geo_rtree(self.wtxn)?;
         self.index.delete_geo_faceted_documents_ids(self.wtxn)?;
+        self.index.delete_facet_values_counts(self.wtxn)?;
 
         // We clean all the faceted documents ids.
         let empty = RoaringBitmap::default();
@@ -60,18 +76,26 @@ impl<'t, 'u, 'i> ClearDocuments<'t, 'u, 'i> {
 
         // Clear the other databases.
         word_docids.clear(self.wtxn)?;
+        exact_word_docids.clear(self.wtxn)?;
         word_prefix_docids.clear(self.wtxn)?;
         docid_word_positions.clear(self.wtxn)?;
         word_pair_proximity_docids.clear(self.wtxn)?;
         word_prefix_pair_proximity_docids.clear(self.wtxn)?;
         word_position_docids.clear(self.wtxn)?;
         field_id_word_count_docids.clear(self.wtxn)?;
+        field_id_exact_value_docids.clear(self.wtxn)?;
         word_prefix_position_docids.clear(self.wtxn)?;
         facet_id_f64_docids.clear(self.wtxn)?;
         facet_id_string_docids.clear(self.wtxn)?;
         field_id_docid_facet_f64s.clear(self.wtxn)?;
         field_id_docid_facet_strings.clear(self.wtxn)?;
+        facet_id_exists_docids.clear(self.wtxn)?;
+        facet_id_is_null_docids.clear(self.wtxn)?;
+        facet_id_is_empty_docids.clear(self.wtxn)?;
         documents.clear(self.wtxn)?;
+        docid_fingerprints.clear(self.wtxn)?;
+        fingerprint_band_docids.clear(self.wtxn)?;
+        docid_word_counts.clear(self.wtxn)?;
 
         Ok(number_of_documents)
     }
@@ -114,19 +138,29 @@ mod tests {
         assert!(index.external_documents_ids(&rtxn).unwrap().is_empty());
         assert!(index.documents_ids(&rtxn).unwrap().is_empty());
         assert!(index.field_distribution(&rtxn).unwrap().is_empty());
+        assert!(index.field_types(&rtxn).unwrap().is_empty());
+        assert!(index.facet_values_counts(&rtxn).unwrap().is_empty());
         assert!(index.geo_rtree(&rtxn).unwrap().is_none());
         assert!(index.geo_faceted_documents_ids(&rtxn).unwrap().is_empty());
 
         assert!(index.word_docids.is_empty(&rtxn).unwrap());
+        assert!(index.exact_word_docids.is_empty(&rtxn).unwrap());
         assert!(index.word_prefix_docids.is_empty(&rtxn).unwrap());
         assert!(index.docid_word_positions.is_empty(&rtxn).unwrap());
         assert!(index.word_pair_proximity_docids.is_empty(&rtxn).unwrap());
         assert!(index.field_id_word_count_docids.is_empty(&rtxn).unwrap());
+        assert!(index.field_id_exact_value_docids.is_empty(&rtxn).unwrap());
         assert!(index.word_prefix_pair_proximity_docids.is_empty(&rtxn).unwrap());
         assert!(index.facet_id_f64_docids.is_empty(&rtxn).unwrap());
         assert!(index.facet_id_string_docids.is_empty(&rtxn).unwrap());
         assert!(index.field_id_docid_facet_f64s.is_empty(&rtxn).unwrap());
         assert!(index.field_id_docid_facet_strings.is_empty(&rtxn).unwrap());
+        assert!(index.facet_id_exists_docids.is_empty(&rtxn).unwrap());
+        assert!(index.facet_id_is_null_docids.is_empty(&rtxn).unwrap());
+        assert!(index.facet_id_is_empty_docids.is_empty(&rtxn).unwrap());
         assert!(index.documents.is_empty(&rtxn).unwrap());
+        assert!(index.docid_fingerprints.is_empty(&rtxn).unwrap());
+        assert!(index.fingerprint_band_docids.is_empty(&rtxn).unwrap());
+        assert!(index.docid_word_counts.is_empty(&rtxn).unwrap());
     }
 }
@@ -0,0 +1,144 @@
+use std::convert::TryInto;
+
+use chrono::Utc;
+use grenad::CompressionType;
+use heed::types::ByteSlice;
+use serde::{Deserialize, Serialize};
+
+use crate::update::index_documents::{create_writer, writer_into_reader};
+use crate::{DocumentId, Index, Result, BEU32};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DocumentsCompactionResult {
+    pub nb_documents: u64,
+    /// The primary key locality of the internal ids of the documents, see
+    /// [`Index::primary_key_locality`]. The compaction keeps the internal ids,
+    /// it only rewrites the pages of the documents database.
+    pub primary_key_locality: f64,
+}
+
+/// Rewrites the documents database by inserting the documents in the order of their primary
+/// key, after many small additions the pages of the documents are scattered in the database
+/// and they are then allocated again in this order.
+///
+/// The documents keep their internal ids, the other databases are not modified.
+pub struct CompactDocuments<'t, 'u, 'i> {
+    wtxn: &'t mut heed::RwTxn<'i, 'u>,
+    index: &'i Index,
+    pub(crate) chunk_compression_type: CompressionType,
+    pub(crate) chunk_compression_level: Option<u32>,
+    _update_id: u64,
+}
+
+impl<'t, 'u, 'i> CompactDocuments<'t, 'u, 'i> {
+    pub fn new(
+        wtxn: &'t mut heed::RwTxn<'i, 'u>,
+        index: &'i Index,
+        update_id: u64,
+    ) -> CompactDocuments<'t, 'u, 'i> {
+        CompactDocuments {
+            wtxn,
+            index,
+            chunk_compression_type: CompressionType::None,
+            chunk_compression_level: None,
+            _update_id: update_id,
+        }
+    }
+
+    #[logging_timer::time("CompactDocuments::{}")]
+    pub fn execute(self) -> Result<DocumentsCompactionResult> {
+        self.index.set_updated_at(self.wtxn, &Utc::now())?;
+
+        let documents = self.index.documents.remap_data_type::<ByteSlice>();
+        let external_documents_ids = self.index.external_documents_ids(self.wtxn)?;
+
+        // The documents are written to a file in the primary key order, their values are
+        // copied as they are stored, encrypted when the index is encrypted.
+        let file = tempfile::tempfile()?;
+        let mut writer =
+            create_writer(self.chunk_compression_type, self.chunk_compression_level, file)?;
+        let mut nb_documents = 0u64;
+        let mut buffer = Vec::new();
+        for (_, docid) in external_documents_ids.iter() {
+            if let Some(bytes) = documents.get(self.wtxn, &BEU32::new(docid))? {
+                buffer.clear();
+                buffer.extend_from_slice(&docid.to_be_bytes());
+                buffer.extend_from_slice(bytes);
+                writer.insert(nb_documents.to_be_bytes(), &buffer)?;
+                nb_documents += 1;
+            }
+        }
+        drop(external_documents_ids);
+        let mut reader = writer_into_reader(writer)?;
+
+        documents.clear(self.wtxn)?;
+        while let Some((_, value)) = reader.next()? {
+            let (docid, bytes) = value.split_at(4);
+            let docid = docid.try_into().map(DocumentId::from_be_bytes).unwrap();
+            documents.put(self.wtxn, &BEU32::new(docid), bytes)?;
+        }
+
+        let primary_key_locality = self.index.primary_key_locality(self.wtxn)?;
+        Ok(DocumentsCompactionResult { nb_documents, primary_key_locality })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::update::{DeleteDocuments, IndexDocuments};
+    use crate::SearchResult;
+
+    fn raw_documents(index: &Index, rtxn: &heed::RoTxn) -> Vec<(u32, Vec<u8>)> {
+        let documents = index.documents.remap_data_type::<ByteSlice>();
+        documents
+            .iter(rtxn)
+            .unwrap()
+            .map(|r| r.map(|(k, v)| (k.get(), v.to_vec())).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn compact_keeps_the_internal_ids() {
+        let index = TempIndex::new();
+
+        // Many small additions and a deletion scatter the documents.
+        let mut wtxn = index.write_txn().unwrap();
+        for (update_id, id) in ["d", "b", "e", "a", "c"].iter().enumerate() {
+            let content = documents!([{ "id": id, "name": "kevin" }]);
+            IndexDocuments::new(&mut wtxn, &index, update_id as u64)
+                .execute(content, |_, _| ())
+                .unwrap();
+        }
+        let mut builder = DeleteDocuments::new(&mut wtxn, &index, 5).unwrap();
+        builder.delete_external_id("e");
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let documents_ids = index.documents_ids(&rtxn).unwrap();
+        let documents = raw_documents(&index, &rtxn);
+        let primary_key_locality = index.primary_key_locality(&rtxn).unwrap();
+        assert!(primary_key_locality < 1.0);
+        drop(rtxn);
+
+        // Only the documents database is rewritten, the documents keep their internal ids.
+        let mut wtxn = index.write_txn().unwrap();
+        let builder = CompactDocuments::new(&mut wtxn, &index, 6);
+        let result = builder.execute().unwrap();
+        assert_eq!(result.nb_documents, 4);
+        assert_eq!(result.primary_key_locality, primary_key_locality);
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let external_ids: Vec<_> =
+            index.external_documents_ids(&rtxn).unwrap().iter().map(|(id, _)| id).collect();
+        assert_eq!(external_ids, vec!["a", "b", "c", "d"]);
+        assert_eq!(index.documents_ids(&rtxn).unwrap(), documents_ids);
+        assert_eq!(raw_documents(&index, &rtxn), documents);
+        let SearchResult { documents_ids, .. } =
+            index.search(&rtxn).query("kevin").execute().unwrap();
+        assert_eq!(documents_ids.len(), 4);
+    }
+}
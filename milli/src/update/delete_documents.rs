@@ -1,27 +1,52 @@
 use std::collections::btree_map::Entry;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::iter::FromIterator;
+use std::mem::take;
 
 use chrono::Utc;
 use fst::IntoStreamer;
 use heed::types::ByteSlice;
 use heed::{BytesDecode, BytesEncode};
+use meilisearch_tokenizer::{Analyzer, AnalyzerConfig};
 use roaring::RoaringBitmap;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use super::ClearDocuments;
+use super::{extract_document_exact_words, ClearDocuments};
 use crate::error::{InternalError, SerializationError, UserError};
+use crate::fingerprint::fingerprint_bands;
 use crate::heed_codec::facet::{
     FacetLevelValueU32Codec, FacetStringLevelZeroValueCodec, FacetStringZeroBoundsValueCodec,
 };
 use crate::heed_codec::CboRoaringBitmapCodec;
 use crate::index::{db_name, main_key};
-use crate::{DocumentId, ExternalDocumentsIds, FieldId, Index, Result, SmallString32, BEU32};
+use crate::{
+    try_split_array_at, DocumentId, ExternalDocumentsIds, FieldId, Index, Result, SmallString32,
+    BEU16, BEU32,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentDeletionResult {
+    pub deleted_documents: u64,
+    /// The status of every one of the external ids that were asked
+    /// to be deleted, once per id, in the order they were first given.
+    pub external_ids: Vec<(String, DeletionStatus)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeletionStatus {
+    /// The document was found and deleted.
+    Deleted,
+    /// No document is known under this external id.
+    Unknown,
+}
 
 pub struct DeleteDocuments<'t, 'u, 'i> {
     wtxn: &'t mut heed::RwTxn<'i, 'u>,
     index: &'i Index,
     external_documents_ids: ExternalDocumentsIds<'static>,
     documents_ids: RoaringBitmap,
+    external_ids: Vec<(String, DeletionStatus)>,
     update_id: u64,
 }
 
@@ -38,6 +63,7 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
             index,
             external_documents_ids,
             documents_ids: RoaringBitmap::new(),
+            external_ids: Vec::new(),
             update_id,
         })
     }
@@ -51,9 +77,27 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
     }
 
     pub fn delete_external_id(&mut self, external_id: &str) -> Option<u32> {
-        let docid = self.external_documents_ids.get(external_id)?;
-        self.delete_document(docid);
-        Some(docid)
+        let docid = self.external_documents_ids.get(external_id);
+        let status = match docid {
+            Some(docid) => {
+                self.delete_document(docid);
+                DeletionStatus::Deleted
+            }
+            None => DeletionStatus::Unknown,
+        };
+        self.external_ids.push((external_id.to_string(), status));
+        docid
+    }
+
+    /// Identical to `execute`, but also returns the deletion status
+    /// of every external id that was asked to be deleted.
+    pub fn execute_with_status(mut self) -> Result<DocumentDeletionResult> {
+        // An external id given several times is only reported once, at its first position.
+        let mut seen = HashSet::new();
+        let mut external_ids = take(&mut self.external_ids);
+        external_ids.retain(|(external_id, _)| seen.insert(external_id.clone()));
+        let deleted_documents = self.execute()?;
+        Ok(DocumentDeletionResult { deleted_documents, external_ids })
     }
 
     pub fn execute(self) -> Result<u64> {
@@ -97,10 +141,12 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
             env: _env,
             main: _main,
             word_docids,
+            exact_word_docids,
             word_prefix_docids,
             docid_word_positions,
             word_pair_proximity_docids,
             field_id_word_count_docids,
+            field_id_exact_value_docids,
             word_prefix_pair_proximity_docids,
             word_position_docids,
             word_prefix_position_docids,
@@ -108,25 +154,68 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
             facet_id_string_docids,
             field_id_docid_facet_f64s,
             field_id_docid_facet_strings,
+            facet_id_exists_docids,
+            facet_id_is_null_docids,
+            facet_id_is_empty_docids,
             documents,
+            docid_fingerprints,
+            fingerprint_band_docids,
+            docid_word_counts,
+            search_log_hook: _,
+            writer_lock: _,
+            documents_cipher: _,
+            filter_attempts: _,
+            facet_range_cache: _,
+            commit_hooks: _,
         } = self.index;
 
+        // The exact words and exact attributes values are extracted from the deleted documents,
+        // the same way they were when the documents were indexed.
+        let searchable_fields =
+            self.index.searchable_fields_ids(self.wtxn)?.map(HashSet::from_iter);
+        let exact_attributes = self.index.exact_attributes_ids(self.wtxn)?;
+        let one_attribute = self.index.position_stride(self.wtxn)?;
+        let stop_words = match self.index.documents_stop_words(self.wtxn)? {
+            Some(stop_words) => Some(stop_words.map_data(|bytes| bytes.to_vec())?),
+            None => None,
+        };
+        let mut config = AnalyzerConfig::default();
+        if let Some(stop_words) = &stop_words {
+            config.stop_words(stop_words);
+        }
+        let analyzer = Analyzer::new(config);
+        let mut exact_words = BTreeSet::new();
+        let mut exact_values = BTreeSet::new();
+
         // Number of fields for each document that has been deleted.
         let mut fields_ids_distribution_diff = HashMap::new();
 
         // Retrieve the words and the external documents ids contained in the documents.
         let mut words = Vec::new();
         let mut external_ids = Vec::new();
+        let mut deleted_band_docids: BTreeMap<u32, RoaringBitmap> = BTreeMap::new();
         for docid in &self.documents_ids {
             // We create an iterator to be able to get the content and delete the document
             // content itself. It's faster to acquire a cursor to get and delete,
             // as we avoid traversing the LMDB B-Tree two times but only once.
             let key = BEU32::new(docid);
-            let mut iter = documents.range_mut(self.wtxn, &(key..=key))?;
-            if let Some((_key, obkv)) = iter.next().transpose()? {
+            let mut iter =
+                documents.remap_data_type::<ByteSlice>().range_mut(self.wtxn, &(key..=key))?;
+            if let Some((_key, bytes)) = iter.next().transpose()? {
+                let obkv = self.index.decrypt_document(bytes)?;
+                let obkv = obkv::KvReaderU16::new(&obkv);
                 for (field_id, _) in obkv.iter() {
                     *fields_ids_distribution_diff.entry(field_id).or_default() += 1;
                 }
+                extract_document_exact_words(
+                    obkv,
+                    &searchable_fields,
+                    &exact_attributes,
+                    &analyzer,
+                    one_attribute,
+                    &mut exact_words,
+                    &mut exact_values,
+                )?;
 
                 if let Some(content) = obkv.get(id_field) {
                     let external_id = match serde_json::from_slice(content).unwrap() {
@@ -142,6 +231,13 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
                 unsafe { iter.del_current()? };
             }
             drop(iter);
+            if let Some(fingerprint) = docid_fingerprints.get(self.wtxn, &key)? {
+                for band in fingerprint_bands(fingerprint.get()) {
+                    deleted_band_docids.entry(band).or_default().insert(docid);
+                }
+                docid_fingerprints.delete(self.wtxn, &key)?;
+            }
+            docid_word_counts.delete(self.wtxn, &key)?;
 
             // We iterate through the words positions of the document id,
             // retrieve the word and delete the positions.
@@ -155,6 +251,19 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
             }
         }
 
+        // We remove the deleted documents from the bands of their fingerprints.
+        for (band, deleted_docids) in deleted_band_docids {
+            let band = BEU32::new(band);
+            if let Some(docids) = fingerprint_band_docids.get(self.wtxn, &band)? {
+                let docids = docids - deleted_docids;
+                if docids.is_empty() {
+                    fingerprint_band_docids.delete(self.wtxn, &band)?;
+                } else {
+                    fingerprint_band_docids.put(self.wtxn, &band, &docids)?;
+                }
+            }
+        }
+
         let mut field_distribution = self.index.field_distribution(self.wtxn)?;
 
         // We use pre-calculated number of fields occurrences that needs to be deleted
@@ -243,6 +352,18 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
         // We write the new words FST into the main database.
         self.index.put_words_fst(self.wtxn, &new_words_fst)?;
 
+        // We remove the documents ids from the exact words of the deleted documents.
+        for word in &exact_words {
+            if let Some(mut docids) = exact_word_docids.get(self.wtxn, word)? {
+                docids -= &self.documents_ids;
+                if docids.is_empty() {
+                    exact_word_docids.delete(self.wtxn, word)?;
+                } else {
+                    exact_word_docids.put(self.wtxn, word, &docids)?;
+                }
+            }
+        }
+
         // We iterate over the word prefix docids database and remove the deleted documents ids
         // from every docids lists. We register the empty prefixes in an fst Set for futur deletion.
         let mut prefixes_to_delete = fst::SetBuilder::memory();
@@ -379,6 +500,37 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
 
         drop(iter);
 
+        // Remove the documents ids from the facet field id exists, is null and is empty
+        // databases, they only have an entry for every one of the faceted fields.
+        for field_id in self.index.faceted_fields_ids(self.wtxn)? {
+            let key = BEU16::new(field_id);
+            for database in
+                &[facet_id_exists_docids, facet_id_is_null_docids, facet_id_is_empty_docids]
+            {
+                if let Some(mut docids) = database.get(self.wtxn, &key)? {
+                    docids -= &self.documents_ids;
+                    if docids.is_empty() {
+                        database.delete(self.wtxn, &key)?;
+                    } else {
+                        database.put(self.wtxn, &key, &docids)?;
+                    }
+                }
+            }
+        }
+
+        // Remove the documents ids from the exact attributes values of the deleted documents.
+        for (field_id, value) in &exact_values {
+            let key = (*field_id, value.as_str());
+            if let Some(mut docids) = field_id_exact_value_docids.get(self.wtxn, &key)? {
+                docids -= &self.documents_ids;
+                if docids.is_empty() {
+                    field_id_exact_value_docids.delete(self.wtxn, &key)?;
+                } else {
+                    field_id_exact_value_docids.put(self.wtxn, &key, &docids)?;
+                }
+            }
+        }
+
         if let Some(mut rtree) = self.index.geo_rtree(self.wtxn)? {
             let mut geo_faceted_doc_ids = self.index.geo_faceted_documents_ids(self.wtxn)?;
 
@@ -397,18 +549,23 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
             self.index.put_geo_faceted_documents_ids(self.wtxn, &geo_faceted_doc_ids)?;
         }
 
-        // We delete the documents ids that are under the facet field id values.
+        // We delete the documents ids that are under the facet field id values,
+        // the facet values that are no more used are not counted anymore.
+        let mut facet_values_counts = self.index.facet_values_counts(self.wtxn)?;
         remove_docids_from_facet_field_id_number_docids(
             self.wtxn,
             facet_id_f64_docids,
             &self.documents_ids,
+            &mut facet_values_counts,
         )?;
 
         remove_docids_from_facet_field_id_string_docids(
             self.wtxn,
             facet_id_string_docids,
             &self.documents_ids,
+            &mut facet_values_counts,
         )?;
+        self.index.put_facet_values_counts(self.wtxn, &facet_values_counts)?;
 
         // Remove the documents ids from the faceted documents ids.
         for field_id in self.index.faceted_fields_ids(self.wtxn)? {
@@ -475,6 +632,7 @@ fn remove_docids_from_facet_field_id_string_docids<'a, C, D>(
     wtxn: &'a mut heed::RwTxn,
     db: &heed::Database<C, D>,
     to_remove: &RoaringBitmap,
+    facet_values_counts: &mut BTreeMap<FieldId, u64>,
 ) -> crate::Result<()> {
     let db_name = Some(crate::index::db_name::FACET_ID_STRING_DOCIDS);
     let mut iter = db.remap_types::<ByteSlice, ByteSlice>().iter_mut(wtxn)?;
@@ -513,6 +671,7 @@ fn remove_docids_from_facet_field_id_string_docids<'a, C, D>(
                 let previous_len = docids.len();
                 docids -= to_remove;
                 if docids.is_empty() {
+                    decrement_facet_values_count(facet_values_counts, key);
                     // safety: we don't keep references from inside the LMDB database.
                     unsafe { iter.del_current()? };
                 } else if docids.len() != previous_len {
@@ -535,6 +694,7 @@ fn remove_docids_from_facet_field_id_number_docids<'a, C>(
     wtxn: &'a mut heed::RwTxn,
     db: &heed::Database<C, CboRoaringBitmapCodec>,
     to_remove: &RoaringBitmap,
+    facet_values_counts: &mut BTreeMap<FieldId, u64>,
 ) -> heed::Result<()>
 where
     C: heed::BytesDecode<'a> + heed::BytesEncode<'a>,
@@ -545,6 +705,7 @@ where
         let previous_len = docids.len();
         docids -= to_remove;
         if docids.is_empty() {
+            decrement_facet_values_count(facet_values_counts, bytes);
             // safety: we don't keep references from inside the LMDB database.
             unsafe { iter.del_current()? };
         } else if docids.len() != previous_len {
@@ -557,6 +718,20 @@ where
     Ok(())
 }
 
+/// Decrements the number of distinct values of the field of this facet key,
+/// only the level zero keys are distinct values, the others are groups of values.
+fn decrement_facet_values_count(facet_values_counts: &mut BTreeMap<FieldId, u64>, key: &[u8]) {
+    if let Some((field_id_bytes, [0, ..])) = try_split_array_at(key) {
+        let field_id = FieldId::from_be_bytes(field_id_bytes);
+        if let Entry::Occupied(mut entry) = facet_values_counts.entry(field_id) {
+            match entry.get().checked_sub(1) {
+                Some(0) | None => entry.remove(),
+                Some(count) => entry.insert(count),
+            };
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashSet;
@@ -599,6 +774,42 @@ mod tests {
         assert!(index.field_distribution(&rtxn).unwrap().is_empty());
     }
 
+    #[test]
+    fn deletion_status_of_external_ids() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": "kevin" },
+            { "id": "kevina" },
+            { "id": "benoit" }
+        ]);
+        let builder = IndexDocuments::new(&mut wtxn, &index, 0);
+        builder.execute(content, |_, _| ()).unwrap();
+
+        let mut builder = DeleteDocuments::new(&mut wtxn, &index, 1).unwrap();
+        builder.delete_external_id("kevina");
+        builder.delete_external_id("tamo");
+        builder.delete_external_id("benoit");
+        builder.delete_external_id("kevina");
+        let result = builder.execute_with_status().unwrap();
+
+        assert_eq!(result.deleted_documents, 2);
+        assert_eq!(
+            result.external_ids,
+            vec![
+                (S("kevina"), DeletionStatus::Deleted),
+                (S("tamo"), DeletionStatus::Unknown),
+                (S("benoit"), DeletionStatus::Deleted),
+            ]
+        );
+
+        wtxn.commit().unwrap();
+    }
+
     #[test]
     fn delete_documents_with_strange_primary_key() {
         let path = tempfile::tempdir().unwrap();
@@ -662,15 +873,21 @@ mod tests {
         let builder = IndexDocuments::new(&mut wtxn, &index, 0);
         builder.execute(content, |_, _| ()).unwrap();
 
+        let label_id = index.fields_ids_map(&wtxn).unwrap().id("label").unwrap();
+        assert_eq!(index.facet_values_count(&wtxn, label_id).unwrap(), 20);
+
         // Delete not all of the documents but some of them.
         let mut builder = DeleteDocuments::new(&mut wtxn, &index, 1).unwrap();
         builder.delete_external_id("1_4");
+        builder.delete_external_id("1_45");
         builder.execute().unwrap();
 
         let filter = FilterCondition::from_str(&wtxn, &index, "label = sign").unwrap();
         let results = index.search(&wtxn).filter(filter).execute().unwrap();
         assert!(results.documents_ids.is_empty());
 
+        assert_eq!(index.facet_values_count(&wtxn, label_id).unwrap(), 18);
+
         wtxn.commit().unwrap();
     }
 
@@ -762,4 +979,44 @@ mod tests {
             "We deleted documents that were not supposed to be deleted"
         );
     }
+
+    #[test]
+    fn delete_documents_exact_words_and_values() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_exact_attributes(hashset! { S("title") });
+        builder.set_filterable_fields(hashset! { S("label") });
+        builder.execute(|_, _| ()).unwrap();
+
+        let content = documents!([
+            { "id": 0, "title": "Hello World", "label": null },
+            { "id": 1, "title": "Hello", "label": "kevin" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+
+        let mut builder = DeleteDocuments::new(&mut wtxn, &index, 2).unwrap();
+        builder.delete_external_id("0");
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        // Only the entries of the deleted document are removed.
+        let rtxn = index.read_txn().unwrap();
+        let title = index.fields_ids_map(&rtxn).unwrap().id("title").unwrap();
+        let label = index.fields_ids_map(&rtxn).unwrap().id("label").unwrap();
+        assert_eq!(index.exact_word_docids.get(&rtxn, "world").unwrap(), None);
+        let docids = index.exact_word_docids.get(&rtxn, "hello").unwrap().unwrap();
+        assert_eq!(docids.iter().collect::<Vec<_>>(), vec![1]);
+        let db = index.field_id_exact_value_docids;
+        assert_eq!(db.get(&rtxn, &(title, "hello world")).unwrap(), None);
+        let docids = db.get(&rtxn, &(title, "hello")).unwrap().unwrap();
+        assert_eq!(docids.iter().collect::<Vec<_>>(), vec![1]);
+        let docids = index.exists_faceted_documents_ids(&rtxn, label).unwrap();
+        assert_eq!(docids.iter().collect::<Vec<_>>(), vec![1]);
+        assert!(index.null_faceted_documents_ids(&rtxn, label).unwrap().is_empty());
+    }
 }
@@ -0,0 +1,269 @@
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryInto;
+
+use fst::{IntoStreamer, Streamer};
+use grenad::CompressionType;
+use heed::types::ByteSlice;
+use heed::BytesDecode;
+use meilisearch_tokenizer::{Analyzer, AnalyzerConfig};
+use roaring::RoaringBitmap;
+
+use crate::error::SerializationError;
+use crate::update::{
+    WordPrefixDocids, WordPrefixPairProximityDocids, WordPrefixPositionDocids, WordsPrefixesFst,
+};
+use crate::{normalize_synonym, FieldId, Index, Result, StrStrU8Codec, BEU32};
+
+/// Removes a set of words from all the word related databases without tokenizing the
+/// documents again, this is used when new stop words are declared: the positions of the
+/// other words of the documents don't change as stop words still count as words.
+pub(crate) struct DeleteWords<'t, 'u, 'i> {
+    wtxn: &'t mut heed::RwTxn<'i, 'u>,
+    index: &'i Index,
+    pub(crate) chunk_compression_type: CompressionType,
+    pub(crate) chunk_compression_level: Option<u32>,
+    pub(crate) max_nb_chunks: Option<usize>,
+    pub(crate) max_memory: Option<usize>,
+    update_id: u64,
+}
+
+impl<'t, 'u, 'i> DeleteWords<'t, 'u, 'i> {
+    pub fn new(
+        wtxn: &'t mut heed::RwTxn<'i, 'u>,
+        index: &'i Index,
+        update_id: u64,
+    ) -> DeleteWords<'t, 'u, 'i> {
+        DeleteWords {
+            wtxn,
+            index,
+            chunk_compression_type: CompressionType::None,
+            chunk_compression_level: None,
+            max_nb_chunks: None,
+            max_memory: None,
+            update_id,
+        }
+    }
+
+    /// Deletes the given words and returns the number of words that were indexed.
+    #[logging_timer::time("DeleteWords::{}")]
+    pub fn execute<A: AsRef<[u8]>>(self, words: &fst::Set<A>) -> Result<usize> {
+        let one_attribute = self.index.position_stride(self.wtxn)?;
+        let Index {
+            env: _env,
+            main: _main,
+            word_docids,
+            exact_word_docids,
+            word_prefix_docids: _word_prefix_docids,
+            docid_word_positions,
+            word_pair_proximity_docids,
+            word_prefix_pair_proximity_docids: _word_prefix_pair_proximity_docids,
+            word_position_docids,
+            field_id_word_count_docids,
+            field_id_exact_value_docids: _field_id_exact_value_docids,
+            word_prefix_position_docids: _word_prefix_position_docids,
+            facet_id_f64_docids: _facet_id_f64_docids,
+            facet_id_string_docids: _facet_id_string_docids,
+            field_id_docid_facet_f64s: _field_id_docid_facet_f64s,
+            field_id_docid_facet_strings: _field_id_docid_facet_strings,
+            facet_id_exists_docids: _facet_id_exists_docids,
+            facet_id_is_null_docids: _facet_id_is_null_docids,
+            facet_id_is_empty_docids: _facet_id_is_empty_docids,
+            documents: _documents,
+            docid_fingerprints: _docid_fingerprints,
+            fingerprint_band_docids: _fingerprint_band_docids,
+            docid_word_counts,
+            search_log_hook: _,
+            writer_lock: _,
+            documents_cipher: _,
+            filter_attempts: _,
+            facet_range_cache: _,
+            commit_hooks: _,
+        } = self.index;
+
+        // We remove the words from the word docids database and keep
+        // track of the documents that were containing them.
+        let mut documents_ids = RoaringBitmap::new();
+        let mut deleted_words = fst::SetBuilder::memory();
+        let mut stream = words.stream();
+        while let Some(bytes) = stream.next() {
+            let word = std::str::from_utf8(bytes)?;
+            if let Some(docids) = word_docids.get(self.wtxn, word)? {
+                documents_ids |= docids;
+                word_docids.delete(self.wtxn, word)?;
+                deleted_words.insert(word)?;
+            }
+        }
+
+        let deleted_words = deleted_words.into_set();
+        if deleted_words.is_empty() {
+            return Ok(0);
+        }
+
+        // We delete the positions of the words in the documents and update the
+        // number of words of every attribute with the positions left empty.
+        let mut fid_word_count_removed = HashMap::<_, RoaringBitmap>::new();
+        let mut fid_word_count_added = HashMap::<_, RoaringBitmap>::new();
+        for docid in &documents_ids {
+            let mut old_positions = HashMap::<FieldId, RoaringBitmap>::new();
+            let mut new_positions = HashMap::<FieldId, RoaringBitmap>::new();
+            let mut iter = docid_word_positions.prefix_iter_mut(self.wtxn, &(docid, ""))?;
+            while let Some(result) = iter.next() {
+                let ((_docid, word), positions) = result?;
+                let must_remove = deleted_words.contains(word);
+                for position in positions {
+                    let field_id = (position / one_attribute) as FieldId;
+                    old_positions.entry(field_id).or_default().insert(position);
+                    if !must_remove {
+                        new_positions.entry(field_id).or_default().insert(position);
+                    }
+                }
+                if must_remove {
+                    // safety: we don't keep references from inside the LMDB database.
+                    unsafe { iter.del_current()? };
+                }
+            }
+            drop(iter);
+
+            let key = BEU32::new(docid);
+            let mut word_counts = BTreeMap::new();
+            if let Some(counts) = docid_word_counts.get(self.wtxn, &key)? {
+                for (field_id, count) in counts.iter() {
+                    let count = count
+                        .try_into()
+                        .map(u32::from_be_bytes)
+                        .map_err(|_| SerializationError::InvalidNumberSerialization)?;
+                    word_counts.insert(field_id, count);
+                }
+            }
+
+            let mut writer = obkv::KvWriterU16::memory();
+            for (field_id, old_count) in word_counts {
+                let removed = match (old_positions.get(&field_id), new_positions.get(&field_id)) {
+                    (Some(old), Some(new)) => (old - new).len(),
+                    (Some(old), None) => old.len(),
+                    (None, _) => 0,
+                };
+                let new_count = old_count.saturating_sub(removed as u32);
+                if new_count != 0 {
+                    writer.insert(field_id, &new_count.to_be_bytes())?;
+                }
+                if old_count != new_count {
+                    if old_count <= 10 {
+                        let key = (field_id, old_count as u8);
+                        fid_word_count_removed.entry(key).or_default().insert(docid);
+                    }
+                    if new_count != 0 && new_count <= 10 {
+                        let key = (field_id, new_count as u8);
+                        fid_word_count_added.entry(key).or_default().insert(docid);
+                    }
+                }
+            }
+            docid_word_counts.put(self.wtxn, &key, &writer)?;
+        }
+
+        // We update the field id word count database with the new word counts.
+        for (key, docids) in fid_word_count_removed {
+            if let Some(mut current) = field_id_word_count_docids.get(self.wtxn, &key)? {
+                current -= docids;
+                if current.is_empty() {
+                    field_id_word_count_docids.delete(self.wtxn, &key)?;
+                } else {
+                    field_id_word_count_docids.put(self.wtxn, &key, &current)?;
+                }
+            }
+        }
+        for (key, docids) in fid_word_count_added {
+            let mut current = field_id_word_count_docids.get(self.wtxn, &key)?.unwrap_or_default();
+            current |= docids;
+            field_id_word_count_docids.put(self.wtxn, &key, &current)?;
+        }
+
+        // We delete all the positions of the words.
+        let mut stream = deleted_words.stream();
+        while let Some(bytes) = stream.next() {
+            let word = std::str::from_utf8(bytes)?;
+            let range = (word, u32::min_value())..=(word, u32::max_value());
+            let mut iter = word_position_docids.range_mut(self.wtxn, &range)?;
+            while iter.next().transpose()?.is_some() {
+                // safety: we don't keep references from inside the LMDB database.
+                unsafe { iter.del_current()? };
+            }
+        }
+
+        // We delete the pairs of words where one of the words is deleted,
+        // the proximity between the other words doesn't change.
+        let mut iter =
+            word_pair_proximity_docids.remap_types::<ByteSlice, ByteSlice>().iter_mut(self.wtxn)?;
+        while let Some((bytes, _)) = iter.next().transpose()? {
+            let must_remove = match StrStrU8Codec::bytes_decode(bytes) {
+                Some((left, right, _)) => {
+                    deleted_words.contains(left) || deleted_words.contains(right)
+                }
+                None => false,
+            };
+            if must_remove {
+                // safety: we don't keep references from inside the LMDB database.
+                unsafe { iter.del_current()? };
+            }
+        }
+
+        drop(iter);
+
+        // We delete the exact words, that are only lowercased, whose normalized
+        // form is one of the deleted words.
+        let analyzer = Analyzer::new(AnalyzerConfig::<Vec<u8>>::default());
+        let mut iter = exact_word_docids.iter_mut(self.wtxn)?;
+        while let Some((exact, _)) = iter.next().transpose()? {
+            let must_remove = match normalize_synonym(&analyzer, exact).as_slice() {
+                [word] => deleted_words.contains(word),
+                _ => false,
+            };
+            if must_remove {
+                // safety: we don't keep references from inside the LMDB database.
+                unsafe { iter.del_current()? };
+            }
+        }
+
+        drop(iter);
+
+        // We write the new words FST into the main database.
+        let new_words_fst = {
+            let words_fst = self.index.words_fst(self.wtxn)?;
+            let difference = words_fst.op().add(&deleted_words).difference();
+            let mut new_words_fst_builder = fst::SetBuilder::memory();
+            new_words_fst_builder.extend_stream(difference.into_stream())?;
+            new_words_fst_builder.into_set()
+        };
+        self.index.put_words_fst(self.wtxn, &new_words_fst)?;
+
+        // The prefix databases are computed from the word databases, we generate them again.
+        if cfg!(feature = "prefix-db") {
+            WordsPrefixesFst::new(self.wtxn, self.index, self.update_id).execute()?;
+
+            let mut builder = WordPrefixDocids::new(self.wtxn, self.index);
+            builder.chunk_compression_type = self.chunk_compression_type;
+            builder.chunk_compression_level = self.chunk_compression_level;
+            builder.max_nb_chunks = self.max_nb_chunks;
+            builder.max_memory = self.max_memory;
+            builder.execute()?;
+
+            if cfg!(feature = "proximity") {
+                let mut builder = WordPrefixPairProximityDocids::new(self.wtxn, self.index);
+                builder.chunk_compression_type = self.chunk_compression_type;
+                builder.chunk_compression_level = self.chunk_compression_level;
+                builder.max_nb_chunks = self.max_nb_chunks;
+                builder.max_memory = self.max_memory;
+                builder.execute()?;
+            }
+
+            let mut builder = WordPrefixPositionDocids::new(self.wtxn, self.index);
+            builder.chunk_compression_type = self.chunk_compression_type;
+            builder.chunk_compression_level = self.chunk_compression_level;
+            builder.max_nb_chunks = self.max_nb_chunks;
+            builder.max_memory = self.max_memory;
+            builder.execute()?;
+        }
+
+        Ok(deleted_words.len())
+    }
+}
@@ -62,6 +62,7 @@ impl<'t, 'u, 'i> Facets<'t, 'u, 'i> {
         self.index.set_updated_at(self.wtxn, &Utc::now())?;
         // We get the faceted fields to be able to create the facet levels.
         let faceted_fields = self.index.faceted_fields_ids(self.wtxn)?;
+        let unleveled_fields = self.index.unleveled_faceted_fields_ids(self.wtxn)?;
 
         debug!("Computing and writing the facet values levels docids into LMDB on disk...");
 
@@ -80,16 +81,6 @@ impl<'t, 'u, 'i> Facets<'t, 'u, 'i> {
                 field_id,
             )?;
 
-            let facet_string_levels = compute_facet_string_levels(
-                self.wtxn,
-                self.index.facet_id_string_docids,
-                self.chunk_compression_type,
-                self.chunk_compression_level,
-                self.level_group_size,
-                self.min_level_size,
-                field_id,
-            )?;
-
             // Clear the facet number levels.
             clear_field_number_levels(self.wtxn, self.index.facet_id_f64_docids, field_id)?;
 
@@ -100,9 +91,26 @@ impl<'t, 'u, 'i> Facets<'t, 'u, 'i> {
                 field_id,
             )?;
 
-            let facet_number_levels = compute_facet_number_levels(
+            self.index.put_string_faceted_documents_ids(
                 self.wtxn,
-                self.index.facet_id_f64_docids,
+                field_id,
+                &string_documents_ids,
+            )?;
+            self.index.put_number_faceted_documents_ids(
+                self.wtxn,
+                field_id,
+                &number_documents_ids,
+            )?;
+
+            // The values of the fields that can't be compared are only read from the
+            // level zero, the levels that speed up the range queries are not needed.
+            if unleveled_fields.contains(&field_id) {
+                continue;
+            }
+
+            let facet_string_levels = compute_facet_string_levels(
+                self.wtxn,
+                self.index.facet_id_string_docids,
                 self.chunk_compression_type,
                 self.chunk_compression_level,
                 self.level_group_size,
@@ -110,15 +118,14 @@ impl<'t, 'u, 'i> Facets<'t, 'u, 'i> {
                 field_id,
             )?;
 
-            self.index.put_string_faceted_documents_ids(
-                self.wtxn,
-                field_id,
-                &string_documents_ids,
-            )?;
-            self.index.put_number_faceted_documents_ids(
+            let facet_number_levels = compute_facet_number_levels(
                 self.wtxn,
+                self.index.facet_id_f64_docids,
+                self.chunk_compression_type,
+                self.chunk_compression_level,
+                self.level_group_size,
+                self.min_level_size,
                 field_id,
-                &number_documents_ids,
             )?;
 
             write_into_lmdb_database(
@@ -0,0 +1,406 @@
+use std::borrow::Cow;
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use roaring::RoaringBitmap;
+use serde::{Deserialize, Serialize};
+use siphasher::sip::SipHasher13;
+
+use super::helpers::{into_clonable_grenad, CursorClonableMmap};
+use super::{TransformOutput, TypedChunk};
+use crate::error::InternalError;
+use crate::{
+    ExternalDocumentsIds, FieldDistribution, FieldTypeWarning, FieldTypes, FieldsIdsMap, Result,
+};
+
+const METADATA_FILE_NAME: &str = "metadata.json";
+const DOCUMENTS_FILE_NAME: &str = "documents";
+const CHUNKS_DIR_NAME: &str = "chunks";
+
+/// The output of the transform phase of a documents addition, without its documents file.
+///
+/// The transform phase can be skipped when the same update is executed again, with the same
+/// documents payload, on an index that didn't receive any other commit since.
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+    update_id: u64,
+    commits_count: u64,
+    #[serde(default)]
+    payload_digest: u64,
+    primary_key: String,
+    fields_ids_map: FieldsIdsMap,
+    field_distribution: FieldDistribution,
+    field_types: FieldTypes,
+    field_type_warnings: Vec<FieldTypeWarning>,
+    hard_external_documents_ids: Vec<u8>,
+    soft_external_documents_ids: Vec<u8>,
+    new_documents_ids: Vec<u8>,
+    replaced_documents_ids: Vec<u8>,
+    #[serde(default)]
+    unchanged_documents_count: usize,
+    #[serde(default)]
+    changed_fields: FieldDistribution,
+    documents_count: usize,
+}
+
+/// Persists the output of the transform phase in the checkpoint directory.
+///
+/// The metadata file is written last and atomically renamed, a checkpoint
+/// that was interrupted while being written is never read back.
+pub(crate) fn write_checkpoint(
+    dir: &Path,
+    update_id: u64,
+    commits_count: u64,
+    payload_digest: u64,
+    output: &mut TransformOutput,
+) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let _ = fs::remove_file(dir.join(METADATA_FILE_NAME));
+    // The chunks extracted from the documents of a previous transform are stale.
+    remove_dir(&dir.join(CHUNKS_DIR_NAME))?;
+
+    let mut documents_file = File::create(dir.join(DOCUMENTS_FILE_NAME))?;
+    output.documents_file.seek(SeekFrom::Start(0))?;
+    io::copy(&mut output.documents_file, &mut documents_file)?;
+    documents_file.sync_all()?;
+    output.documents_file.seek(SeekFrom::Start(0))?;
+
+    let mut new_documents_ids = Vec::new();
+    output.new_documents_ids.serialize_into(&mut new_documents_ids)?;
+    let mut replaced_documents_ids = Vec::new();
+    output.replaced_documents_ids.serialize_into(&mut replaced_documents_ids)?;
+
+    let checkpoint = Checkpoint {
+        update_id,
+        commits_count,
+        payload_digest,
+        primary_key: output.primary_key.clone(),
+        fields_ids_map: output.fields_ids_map.clone(),
+        field_distribution: output.field_distribution.clone(),
+        field_types: output.field_types.clone(),
+        field_type_warnings: output.field_type_warnings.clone(),
+        hard_external_documents_ids: output.external_documents_ids.hard.as_fst().to_vec(),
+        soft_external_documents_ids: output.external_documents_ids.soft.as_fst().to_vec(),
+        new_documents_ids,
+        replaced_documents_ids,
+        unchanged_documents_count: output.unchanged_documents_count,
+        changed_fields: output.changed_fields.clone(),
+        documents_count: output.documents_count,
+    };
+
+    let tmp_path = dir.join(METADATA_FILE_NAME).with_extension("tmp");
+    let metadata_file = File::create(&tmp_path)?;
+    serde_json::to_writer(&metadata_file, &checkpoint).map_err(InternalError::SerdeJson)?;
+    metadata_file.sync_all()?;
+    fs::rename(tmp_path, dir.join(METADATA_FILE_NAME))?;
+
+    Ok(())
+}
+
+/// Reads the output of the transform phase back from the checkpoint directory.
+///
+/// Returns `None` if there is no checkpoint or if it was written by another update, for
+/// another documents payload or on another version of the index, such a checkpoint is stale
+/// and is removed.
+pub(crate) fn read_checkpoint(
+    dir: &Path,
+    update_id: u64,
+    commits_count: u64,
+    payload_digest: u64,
+) -> Result<Option<TransformOutput>> {
+    let metadata_file = match File::open(dir.join(METADATA_FILE_NAME)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let checkpoint: Checkpoint =
+        serde_json::from_reader(metadata_file).map_err(InternalError::SerdeJson)?;
+    if checkpoint.update_id != update_id
+        || checkpoint.commits_count != commits_count
+        || checkpoint.payload_digest != payload_digest
+    {
+        remove_checkpoint(dir)?;
+        return Ok(None);
+    }
+
+    let hard = fst::Map::new(Cow::Owned(checkpoint.hard_external_documents_ids))?;
+    let soft = fst::Map::new(Cow::Owned(checkpoint.soft_external_documents_ids))?;
+
+    Ok(Some(TransformOutput {
+        primary_key: checkpoint.primary_key,
+        fields_ids_map: checkpoint.fields_ids_map,
+        field_distribution: checkpoint.field_distribution,
+        field_types: checkpoint.field_types,
+        field_type_warnings: checkpoint.field_type_warnings,
+        external_documents_ids: ExternalDocumentsIds::new(hard, soft),
+        new_documents_ids: RoaringBitmap::deserialize_from(&checkpoint.new_documents_ids[..])?,
+        replaced_documents_ids: RoaringBitmap::deserialize_from(
+            &checkpoint.replaced_documents_ids[..],
+        )?,
+        unchanged_documents_count: checkpoint.unchanged_documents_count,
+        changed_fields: checkpoint.changed_fields,
+        documents_count: checkpoint.documents_count,
+        documents_file: File::open(dir.join(DOCUMENTS_FILE_NAME))?,
+    }))
+}
+
+/// Removes the checkpoint of a documents addition from the given directory, it can be called
+/// once the write transaction of the documents addition is committed.
+pub(crate) fn remove_checkpoint(dir: &Path) -> Result<()> {
+    for name in &[METADATA_FILE_NAME, DOCUMENTS_FILE_NAME] {
+        match fs::remove_file(dir.join(name)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            _ => (),
+        }
+    }
+    remove_dir(&dir.join(CHUNKS_DIR_NAME))
+}
+
+fn remove_dir(dir: &Path) -> Result<()> {
+    match fs::remove_dir_all(dir) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// The data extracted from a chunk of documents, the typed chunks are stored
+/// in the files of the chunk directory, in the order they were extracted.
+#[derive(Serialize, Deserialize)]
+struct ChunkCheckpoint {
+    chunk_digest: u64,
+    typed_chunks: Vec<String>,
+}
+
+fn chunk_dir(dir: &Path, chunk_index: usize) -> PathBuf {
+    dir.join(CHUNKS_DIR_NAME).join(chunk_index.to_string())
+}
+
+/// Returns the digest of a chunk of documents, it identifies the documents and
+/// the internal ids they were given by the transform phase.
+pub(crate) fn chunk_digest(chunk: grenad::Reader<File>) -> Result<(u64, grenad::Reader<File>)> {
+    let mut file = chunk.into_inner();
+    file.seek(SeekFrom::Start(0))?;
+    let mut hasher = SipHasher13::new_with_keys(0, 0);
+    let mut buffer = [0; 8192];
+    loop {
+        match file.read(&mut buffer)? {
+            0 => break,
+            n => hasher.write(&buffer[..n]),
+        }
+    }
+    file.seek(SeekFrom::Start(0))?;
+    Ok((hasher.finish(), grenad::Reader::new(file)?))
+}
+
+/// Persists the data extracted from a chunk of documents in the checkpoint directory
+/// and returns it, the typed chunks are read back from the copied files.
+///
+/// The metadata file of the chunk is written last and atomically renamed, a chunk
+/// whose extraction was interrupted while being written is extracted again.
+pub(crate) fn write_chunk_checkpoint(
+    dir: &Path,
+    chunk_index: usize,
+    chunk_digest: u64,
+    typed_chunks: Vec<TypedChunk>,
+) -> Result<Vec<TypedChunk>> {
+    let dir = chunk_dir(dir, chunk_index);
+    remove_dir(&dir)?;
+    fs::create_dir_all(&dir)?;
+
+    let mut names = Vec::with_capacity(typed_chunks.len());
+    let mut written_typed_chunks = Vec::with_capacity(typed_chunks.len());
+    for (i, typed_chunk) in typed_chunks.into_iter().enumerate() {
+        let mut file = File::create(dir.join(i.to_string()))?;
+        let (name, typed_chunk) = write_typed_chunk(&mut file, typed_chunk)?;
+        file.sync_all()?;
+        names.push(name.to_string());
+        written_typed_chunks.push(typed_chunk);
+    }
+
+    let checkpoint = ChunkCheckpoint { chunk_digest, typed_chunks: names };
+    let tmp_path = dir.join(METADATA_FILE_NAME).with_extension("tmp");
+    let metadata_file = File::create(&tmp_path)?;
+    serde_json::to_writer(&metadata_file, &checkpoint).map_err(InternalError::SerdeJson)?;
+    metadata_file.sync_all()?;
+    fs::rename(tmp_path, dir.join(METADATA_FILE_NAME))?;
+
+    Ok(written_typed_chunks)
+}
+
+/// Reads the data extracted from a chunk of documents back from the checkpoint directory.
+///
+/// Returns `None` if this chunk wasn't completely extracted or if it was extracted from other
+/// documents, such a chunk checkpoint is stale and is removed.
+pub(crate) fn read_chunk_checkpoint(
+    dir: &Path,
+    chunk_index: usize,
+    chunk_digest: u64,
+) -> Result<Option<Vec<TypedChunk>>> {
+    let dir = chunk_dir(dir, chunk_index);
+    let metadata_file = match File::open(dir.join(METADATA_FILE_NAME)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let checkpoint: ChunkCheckpoint =
+        serde_json::from_reader(metadata_file).map_err(InternalError::SerdeJson)?;
+    if checkpoint.chunk_digest != chunk_digest {
+        remove_dir(&dir)?;
+        return Ok(None);
+    }
+
+    let mut typed_chunks = Vec::with_capacity(checkpoint.typed_chunks.len());
+    for (i, name) in checkpoint.typed_chunks.iter().enumerate() {
+        let file = File::open(dir.join(i.to_string()))?;
+        match read_typed_chunk(name, file)? {
+            Some(typed_chunk) => typed_chunks.push(typed_chunk),
+            None => {
+                remove_dir(&dir)?;
+                return Ok(None);
+            }
+        }
+    }
+
+    Ok(Some(typed_chunks))
+}
+
+/// Copies a typed chunk in the given file and returns its name with the typed chunk.
+fn write_typed_chunk(
+    file: &mut File,
+    typed_chunk: TypedChunk,
+) -> Result<(&'static str, TypedChunk)> {
+    let entry = match typed_chunk {
+        TypedChunk::DocidWordPositions(reader) => {
+            ("docid-word-positions", TypedChunk::DocidWordPositions(copy_mmap(reader, file)?))
+        }
+        TypedChunk::DocidWordCounts(reader) => {
+            ("docid-word-counts", TypedChunk::DocidWordCounts(copy_mmap(reader, file)?))
+        }
+        TypedChunk::FieldIdDocidFacetStrings(reader) => (
+            "field-id-docid-facet-strings",
+            TypedChunk::FieldIdDocidFacetStrings(copy_mmap(reader, file)?),
+        ),
+        TypedChunk::FieldIdDocidFacetNumbers(reader) => (
+            "field-id-docid-facet-numbers",
+            TypedChunk::FieldIdDocidFacetNumbers(copy_mmap(reader, file)?),
+        ),
+        TypedChunk::Documents(reader) => {
+            ("documents", TypedChunk::Documents(copy_mmap(reader, file)?))
+        }
+        TypedChunk::FieldIdWordcountDocids(reader) => (
+            "field-id-wordcount-docids",
+            TypedChunk::FieldIdWordcountDocids(copy_file(reader, file)?),
+        ),
+        TypedChunk::NewDocumentsIds(documents_ids) => {
+            documents_ids.serialize_into(&mut *file)?;
+            ("new-documents-ids", TypedChunk::NewDocumentsIds(documents_ids))
+        }
+        TypedChunk::WordDocids(reader) => {
+            ("word-docids", TypedChunk::WordDocids(copy_file(reader, file)?))
+        }
+        TypedChunk::ExactWordDocids(reader) => {
+            ("exact-word-docids", TypedChunk::ExactWordDocids(copy_file(reader, file)?))
+        }
+        TypedChunk::FieldIdExactValueDocids(reader) => (
+            "field-id-exact-value-docids",
+            TypedChunk::FieldIdExactValueDocids(copy_file(reader, file)?),
+        ),
+        TypedChunk::WordPositionDocids(reader) => {
+            ("word-position-docids", TypedChunk::WordPositionDocids(copy_file(reader, file)?))
+        }
+        TypedChunk::WordPairProximityDocids(reader) => (
+            "word-pair-proximity-docids",
+            TypedChunk::WordPairProximityDocids(copy_file(reader, file)?),
+        ),
+        TypedChunk::FieldIdFacetStringDocids(reader) => (
+            "field-id-facet-string-docids",
+            TypedChunk::FieldIdFacetStringDocids(copy_file(reader, file)?),
+        ),
+        TypedChunk::FieldIdFacetNumberDocids(reader) => (
+            "field-id-facet-number-docids",
+            TypedChunk::FieldIdFacetNumberDocids(copy_file(reader, file)?),
+        ),
+        TypedChunk::FieldIdFacetExistsDocids(reader) => (
+            "field-id-facet-exists-docids",
+            TypedChunk::FieldIdFacetExistsDocids(copy_file(reader, file)?),
+        ),
+        TypedChunk::FieldIdFacetIsNullDocids(reader) => (
+            "field-id-facet-is-null-docids",
+            TypedChunk::FieldIdFacetIsNullDocids(copy_file(reader, file)?),
+        ),
+        TypedChunk::FieldIdFacetIsEmptyDocids(reader) => (
+            "field-id-facet-is-empty-docids",
+            TypedChunk::FieldIdFacetIsEmptyDocids(copy_file(reader, file)?),
+        ),
+        TypedChunk::GeoPoints(reader) => {
+            ("geo-points", TypedChunk::GeoPoints(copy_file(reader, file)?))
+        }
+    };
+    Ok(entry)
+}
+
+/// Reads a typed chunk from the given file, returns `None` if the name is unknown.
+fn read_typed_chunk(name: &str, file: File) -> Result<Option<TypedChunk>> {
+    let typed_chunk = match name {
+        "docid-word-positions" => TypedChunk::DocidWordPositions(open_mmap(file)?),
+        "docid-word-counts" => TypedChunk::DocidWordCounts(open_mmap(file)?),
+        "field-id-docid-facet-strings" => TypedChunk::FieldIdDocidFacetStrings(open_mmap(file)?),
+        "field-id-docid-facet-numbers" => TypedChunk::FieldIdDocidFacetNumbers(open_mmap(file)?),
+        "documents" => TypedChunk::Documents(open_mmap(file)?),
+        "field-id-wordcount-docids" => {
+            TypedChunk::FieldIdWordcountDocids(grenad::Reader::new(file)?)
+        }
+        "new-documents-ids" => TypedChunk::NewDocumentsIds(RoaringBitmap::deserialize_from(file)?),
+        "word-docids" => TypedChunk::WordDocids(grenad::Reader::new(file)?),
+        "exact-word-docids" => TypedChunk::ExactWordDocids(grenad::Reader::new(file)?),
+        "field-id-exact-value-docids" => {
+            TypedChunk::FieldIdExactValueDocids(grenad::Reader::new(file)?)
+        }
+        "word-position-docids" => TypedChunk::WordPositionDocids(grenad::Reader::new(file)?),
+        "word-pair-proximity-docids" => {
+            TypedChunk::WordPairProximityDocids(grenad::Reader::new(file)?)
+        }
+        "field-id-facet-string-docids" => {
+            TypedChunk::FieldIdFacetStringDocids(grenad::Reader::new(file)?)
+        }
+        "field-id-facet-number-docids" => {
+            TypedChunk::FieldIdFacetNumberDocids(grenad::Reader::new(file)?)
+        }
+        "field-id-facet-exists-docids" => {
+            TypedChunk::FieldIdFacetExistsDocids(grenad::Reader::new(file)?)
+        }
+        "field-id-facet-is-null-docids" => {
+            TypedChunk::FieldIdFacetIsNullDocids(grenad::Reader::new(file)?)
+        }
+        "field-id-facet-is-empty-docids" => {
+            TypedChunk::FieldIdFacetIsEmptyDocids(grenad::Reader::new(file)?)
+        }
+        "geo-points" => TypedChunk::GeoPoints(grenad::Reader::new(file)?),
+        _ => return Ok(None),
+    };
+    Ok(Some(typed_chunk))
+}
+
+fn copy_file(reader: grenad::Reader<File>, file: &mut File) -> Result<grenad::Reader<File>> {
+    let mut source = reader.into_inner();
+    source.seek(SeekFrom::Start(0))?;
+    io::copy(&mut source, file)?;
+    source.seek(SeekFrom::Start(0))?;
+    grenad::Reader::new(source).map_err(Into::into)
+}
+
+fn copy_mmap(
+    reader: grenad::Reader<CursorClonableMmap>,
+    file: &mut File,
+) -> Result<grenad::Reader<CursorClonableMmap>> {
+    let mmap = reader.into_inner().into_inner();
+    file.write_all(mmap.as_ref())?;
+    grenad::Reader::new(io::Cursor::new(mmap)).map_err(Into::into)
+}
+
+fn open_mmap(file: File) -> Result<grenad::Reader<CursorClonableMmap>> {
+    // The checkpoint files are never modified once their chunk is completely extracted.
+    unsafe { into_clonable_grenad(grenad::Reader::new(file)?) }
+}
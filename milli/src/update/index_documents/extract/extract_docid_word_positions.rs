@@ -1,30 +1,47 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::convert::TryInto;
 use std::fs::File;
+use std::iter::FromIterator;
 use std::{io, mem, str};
 
-use meilisearch_tokenizer::token::SeparatorKind;
-use meilisearch_tokenizer::{Analyzer, AnalyzerConfig, Token, TokenKind};
+use meilisearch_tokenizer::{Analyzer, AnalyzerConfig, TokenKind};
 use roaring::RoaringBitmap;
 use serde_json::Value;
 
-use super::helpers::{concat_u32s_array, create_sorter, sorter_into_reader, GrenadParameters};
+use super::helpers::{
+    concat_u32s_array, create_sorter, create_writer, merge_roaring_bitmaps,
+    serialize_roaring_bitmap, sorter_into_reader, writer_into_reader, GrenadParameters,
+};
 use crate::error::{InternalError, SerializationError};
-use crate::proximity::ONE_ATTRIBUTE;
-use crate::{FieldId, Result};
+use crate::{exact_word, process_tokens, FieldId, Result};
 
 /// Extracts the word and positions where this word appear and
 /// prefixes it by the document id.
 ///
-/// Returns the generated internal documents ids and a grenad reader
-/// with the list of extracted words from the given chunk of documents.
+/// Returns the generated internal documents ids, a grenad reader
+/// with the list of extracted words from the given chunk of documents,
+/// a grenad reader with the number of words of each field of the documents,
+/// a grenad reader with the exact words and the documents ids where they appear
+/// and a grenad reader with the values of the exact attributes and their documents ids.
+///
+/// The words whose synonyms are expanded at indexing time are added at the positions
+/// of the synonyms found in the documents.
 #[logging_timer::time]
 pub fn extract_docid_word_positions<R: io::Read>(
     mut obkv_documents: grenad::Reader<R>,
     indexer: GrenadParameters,
     searchable_fields: &Option<HashSet<FieldId>>,
+    exact_attributes: &HashSet<FieldId>,
     stop_words: Option<&fst::Set<&[u8]>>,
-) -> Result<(RoaringBitmap, grenad::Reader<File>)> {
+    synonyms_expansions: &HashMap<String, Vec<Vec<String>>>,
+    one_attribute: u32,
+) -> Result<(
+    RoaringBitmap,
+    grenad::Reader<File>,
+    grenad::Reader<File>,
+    grenad::Reader<File>,
+    grenad::Reader<File>,
+)> {
     let max_memory = indexer.max_memory_by_thread();
 
     let mut documents_ids = RoaringBitmap::new();
@@ -33,16 +50,44 @@ pub fn extract_docid_word_positions<R: io::Read>(
         indexer.chunk_compression_type,
         indexer.chunk_compression_level,
         indexer.max_nb_chunks,
-        max_memory,
+        max_memory.map(|m| m / 2),
+    );
+
+    // The documents are read in the order of their ids, the counts are written in this order.
+    let mut docid_word_counts_writer = tempfile::tempfile().and_then(|file| {
+        create_writer(indexer.chunk_compression_type, indexer.chunk_compression_level, file)
+    })?;
+
+    let mut exact_word_docids_sorter = create_sorter(
+        merge_roaring_bitmaps,
+        indexer.chunk_compression_type,
+        indexer.chunk_compression_level,
+        indexer.max_nb_chunks,
+        max_memory.map(|m| m / 4),
+    );
+
+    let mut field_id_exact_value_docids_sorter = create_sorter(
+        merge_roaring_bitmaps,
+        indexer.chunk_compression_type,
+        indexer.chunk_compression_level,
+        indexer.max_nb_chunks,
+        max_memory.map(|m| m / 4),
     );
 
     let mut key_buffer = Vec::new();
+    let mut value_buffer = Vec::new();
     let mut field_buffer = String::new();
+    let mut exact_value_buffer = Vec::new();
+    let mut field_words = Vec::new();
+    let mut synonym_buffer = String::new();
+    let mut word_counts = BTreeMap::new();
+    let max_synonym_words =
+        synonyms_expansions.keys().map(|synonym| synonym.split(' ').count()).max().unwrap_or(0);
     let mut config = AnalyzerConfig::default();
     if let Some(stop_words) = stop_words {
         config.stop_words(stop_words);
     }
-    let analyzer = Analyzer::<Vec<u8>>::new(AnalyzerConfig::default());
+    let analyzer = Analyzer::new(config);
 
     while let Some((key, value)) = obkv_documents.next()? {
         let document_id = key
@@ -54,6 +99,8 @@ pub fn extract_docid_word_positions<R: io::Read>(
         documents_ids.push(document_id);
         key_buffer.clear();
         key_buffer.extend_from_slice(&document_id.to_be_bytes());
+        serialize_roaring_bitmap(&RoaringBitmap::from_iter(Some(document_id)), &mut value_buffer)?;
+        word_counts.clear();
 
         for (field_id, field_bytes) in obkv.iter() {
             if searchable_fields.as_ref().map_or(true, |sf| sf.contains(&field_id)) {
@@ -63,28 +110,165 @@ pub fn extract_docid_word_positions<R: io::Read>(
                 if let Some(field) = json_to_string(&value, &mut field_buffer) {
                     let analyzed = analyzer.analyze(field);
                     let tokens = process_tokens(analyzed.tokens())
-                        .take_while(|(p, _)| (*p as u32) < ONE_ATTRIBUTE);
+                        .take_while(|(p, _)| (*p as u32) < one_attribute);
+
+                    // The value of an exact attribute is indexed as the words it
+                    // contains separated by spaces, like the words of a query are.
+                    let is_exact_attribute = exact_attributes.contains(&field_id);
+                    exact_value_buffer.clear();
+                    exact_value_buffer.extend_from_slice(&field_id.to_be_bytes());
+                    field_words.clear();
 
                     for (index, token) in tokens {
+                        if is_exact_attribute && token.kind != TokenKind::StopWord {
+                            let word = token.text().trim();
+                            if !word.is_empty() {
+                                if exact_value_buffer.len() > mem::size_of::<FieldId>() {
+                                    exact_value_buffer.push(b' ');
+                                }
+                                exact_value_buffer.extend_from_slice(word.as_bytes());
+                            }
+                        }
+
+                        let exact = exact_word(&field[token.byte_start..token.byte_end]);
+                        if !exact.is_empty() && token.kind != TokenKind::StopWord {
+                            exact_word_docids_sorter.insert(exact.as_bytes(), &value_buffer)?;
+                        }
+
                         let token = token.text().trim();
                         if !token.is_empty() {
                             key_buffer.truncate(mem::size_of::<u32>());
                             key_buffer.extend_from_slice(token.as_bytes());
 
-                            let position: u32 = index
+                            let index: u32 = index
                                 .try_into()
                                 .map_err(|_| SerializationError::InvalidNumberSerialization)?;
-                            let position = field_id as u32 * ONE_ATTRIBUTE + position;
+                            let position = field_id as u32 * one_attribute + index;
                             docid_word_positions_sorter
                                 .insert(&key_buffer, &position.to_ne_bytes())?;
+                            *word_counts.entry(field_id).or_insert(0u32) += 1;
+
+                            if max_synonym_words != 0 {
+                                field_words.push((index, token.to_string()));
+                            }
+                        }
+                    }
+
+                    // When the words of the field are a synonym of a word whose synonyms are
+                    // expanded at indexing time, this word is indexed at the same positions.
+                    for (i, (index, _)) in field_words.iter().enumerate() {
+                        synonym_buffer.clear();
+                        for (_, word) in field_words.iter().skip(i).take(max_synonym_words) {
+                            if !synonym_buffer.is_empty() {
+                                synonym_buffer.push(' ');
+                            }
+                            synonym_buffer.push_str(word);
+
+                            let expansions = match synonyms_expansions.get(&synonym_buffer) {
+                                Some(expansions) => expansions,
+                                None => continue,
+                            };
+                            for words in expansions {
+                                for (j, word) in words.iter().enumerate() {
+                                    let index = index + j as u32;
+                                    if index >= one_attribute {
+                                        break;
+                                    }
+                                    let position = field_id as u32 * one_attribute + index;
+                                    key_buffer.truncate(mem::size_of::<u32>());
+                                    key_buffer.extend_from_slice(word.as_bytes());
+                                    docid_word_positions_sorter
+                                        .insert(&key_buffer, &position.to_ne_bytes())?;
+                                }
+                            }
+                        }
+                    }
+
+                    if exact_value_buffer.len() > mem::size_of::<FieldId>() {
+                        field_id_exact_value_docids_sorter
+                            .insert(&exact_value_buffer, &value_buffer)?;
+                    }
+                }
+            }
+        }
+
+        // The words are counted as they are extracted, the positions can't be used
+        // as the hard separators leave gaps between them.
+        if !word_counts.is_empty() {
+            let mut writer = obkv::KvWriter::<_, FieldId>::memory();
+            for (field_id, count) in &word_counts {
+                writer.insert(*field_id, &u32::to_be_bytes(*count))?;
+            }
+            docid_word_counts_writer.insert(&document_id.to_be_bytes(), writer.into_inner()?)?;
+        }
+    }
+
+    let docid_word_positions = sorter_into_reader(docid_word_positions_sorter, indexer)?;
+    let docid_word_counts = writer_into_reader(docid_word_counts_writer)?;
+    let exact_word_docids = sorter_into_reader(exact_word_docids_sorter, indexer)?;
+    let field_id_exact_value_docids =
+        sorter_into_reader(field_id_exact_value_docids_sorter, indexer)?;
+
+    Ok((
+        documents_ids,
+        docid_word_positions,
+        docid_word_counts,
+        exact_word_docids,
+        field_id_exact_value_docids,
+    ))
+}
+
+/// Inserts the exact words of the document and the values of its exact attributes, as they
+/// are extracted by [`extract_docid_word_positions`], the deletion of the document removes
+/// its documents id from these entries only.
+pub fn extract_document_exact_words<A: AsRef<[u8]>>(
+    obkv: obkv::KvReader<FieldId>,
+    searchable_fields: &Option<HashSet<FieldId>>,
+    exact_attributes: &HashSet<FieldId>,
+    analyzer: &Analyzer<A>,
+    one_attribute: u32,
+    exact_words: &mut BTreeSet<String>,
+    exact_values: &mut BTreeSet<(FieldId, String)>,
+) -> Result<()> {
+    let mut field_buffer = String::new();
+    for (field_id, field_bytes) in obkv.iter() {
+        if searchable_fields.as_ref().map_or(false, |sf| !sf.contains(&field_id)) {
+            continue;
+        }
+
+        let value = serde_json::from_slice(field_bytes).map_err(InternalError::SerdeJson)?;
+        field_buffer.clear();
+        if let Some(field) = json_to_string(&value, &mut field_buffer) {
+            let analyzed = analyzer.analyze(field);
+            let tokens =
+                process_tokens(analyzed.tokens()).take_while(|(p, _)| (*p as u32) < one_attribute);
+
+            let is_exact_attribute = exact_attributes.contains(&field_id);
+            let mut exact_value = String::new();
+            for (_, token) in tokens {
+                if is_exact_attribute && token.kind != TokenKind::StopWord {
+                    let word = token.text().trim();
+                    if !word.is_empty() {
+                        if !exact_value.is_empty() {
+                            exact_value.push(' ');
                         }
+                        exact_value.push_str(word);
                     }
                 }
+
+                let exact = exact_word(&field[token.byte_start..token.byte_end]);
+                if !exact.is_empty() && token.kind != TokenKind::StopWord {
+                    exact_words.insert(exact);
+                }
+            }
+
+            if !exact_value.is_empty() {
+                exact_values.insert((field_id, exact_value));
             }
         }
     }
 
-    sorter_into_reader(docid_word_positions_sorter, indexer).map(|reader| (documents_ids, reader))
+    Ok(())
 }
 
 /// Transform a JSON value into a string that can be indexed.
@@ -135,36 +319,3 @@ fn json_to_string<'a>(value: &'a Value, buffer: &'a mut String) -> Option<&'a st
         None
     }
 }
-
-/// take an iterator on tokens and compute their relative position depending on separator kinds
-/// if it's an `Hard` separator we add an additional relative proximity of 8 between words,
-/// else we keep the standart proximity of 1 between words.
-fn process_tokens<'a>(
-    tokens: impl Iterator<Item = Token<'a>>,
-) -> impl Iterator<Item = (usize, Token<'a>)> {
-    tokens
-        .skip_while(|token| token.is_separator().is_some())
-        .scan((0, None), |(offset, prev_kind), token| {
-            match token.kind {
-                TokenKind::Word | TokenKind::StopWord | TokenKind::Unknown => {
-                    *offset += match *prev_kind {
-                        Some(TokenKind::Separator(SeparatorKind::Hard)) => 8,
-                        Some(_) => 1,
-                        None => 0,
-                    };
-                    *prev_kind = Some(token.kind)
-                }
-                TokenKind::Separator(SeparatorKind::Hard) => {
-                    *prev_kind = Some(token.kind);
-                }
-                TokenKind::Separator(SeparatorKind::Soft)
-                    if *prev_kind != Some(TokenKind::Separator(SeparatorKind::Hard)) =>
-                {
-                    *prev_kind = Some(token.kind);
-                }
-                _ => (),
-            }
-            Some((*offset, token))
-        })
-        .filter(|(_, t)| t.is_word())
-}
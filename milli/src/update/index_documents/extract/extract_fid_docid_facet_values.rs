@@ -1,4 +1,5 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
+use std::convert::TryInto;
 use std::fs::File;
 use std::io;
 use std::mem::size_of;
@@ -6,21 +7,42 @@ use std::mem::size_of;
 use heed::zerocopy::AsBytes;
 use serde_json::Value;
 
-use super::helpers::{create_sorter, keep_first, sorter_into_reader, GrenadParameters};
+use super::helpers::{
+    create_sorter, keep_first, merge_cbo_roaring_bitmaps, sorter_into_reader, GrenadParameters,
+};
 use crate::error::InternalError;
+use crate::facet::facet_string_key;
 use crate::facet::value_encoding::f64_into_bytes;
-use crate::{DocumentId, FieldId, Result};
+use crate::{rfc3339_timestamp, DocumentId, FieldId, Result};
+
+/// A faceted field whose values are read in the object of another field,
+/// `address.city` is the `city` key of the object of the `address` field.
+#[derive(Debug, Clone)]
+pub struct NestedField {
+    pub root: FieldId,
+    pub path: Vec<String>,
+    pub field_id: FieldId,
+}
 
 /// Extracts the facet values of each faceted field of each document.
 ///
 /// Returns the generated grenad reader containing the docid the fid and the orginal value as key
-/// and the normalized value as value extracted from the given chunk of documents.
+/// and the normalized value as value extracted from the given chunk of documents, and the
+/// grenad readers containing the fid as key and, as value, the documents ids that contain
+/// the field, where the field is null and where the field is empty.
 #[logging_timer::time]
 pub fn extract_fid_docid_facet_values<R: io::Read>(
     mut obkv_documents: grenad::Reader<R>,
     indexer: GrenadParameters,
     faceted_fields: &HashSet<FieldId>,
-) -> Result<(grenad::Reader<File>, grenad::Reader<File>)> {
+    nested_fields: &[NestedField],
+) -> Result<(
+    grenad::Reader<File>,
+    grenad::Reader<File>,
+    grenad::Reader<File>,
+    grenad::Reader<File>,
+    grenad::Reader<File>,
+)> {
     let max_memory = indexer.max_memory_by_thread();
 
     let mut fid_docid_facet_numbers_sorter = create_sorter(
@@ -28,7 +50,7 @@ pub fn extract_fid_docid_facet_values<R: io::Read>(
         indexer.chunk_compression_type,
         indexer.chunk_compression_level,
         indexer.max_nb_chunks,
-        max_memory.map(|m| m / 2),
+        max_memory.map(|m| m / 5),
     );
 
     let mut fid_docid_facet_strings_sorter = create_sorter(
@@ -36,52 +58,170 @@ pub fn extract_fid_docid_facet_values<R: io::Read>(
         indexer.chunk_compression_type,
         indexer.chunk_compression_level,
         indexer.max_nb_chunks,
-        max_memory.map(|m| m / 2),
+        max_memory.map(|m| m / 5),
+    );
+
+    let mut facet_exists_docids_sorter = create_sorter(
+        merge_cbo_roaring_bitmaps,
+        indexer.chunk_compression_type,
+        indexer.chunk_compression_level,
+        indexer.max_nb_chunks,
+        max_memory.map(|m| m / 5),
     );
 
+    let mut facet_is_null_docids_sorter = create_sorter(
+        merge_cbo_roaring_bitmaps,
+        indexer.chunk_compression_type,
+        indexer.chunk_compression_level,
+        indexer.max_nb_chunks,
+        max_memory.map(|m| m / 5),
+    );
+
+    let mut facet_is_empty_docids_sorter = create_sorter(
+        merge_cbo_roaring_bitmaps,
+        indexer.chunk_compression_type,
+        indexer.chunk_compression_level,
+        indexer.max_nb_chunks,
+        max_memory.map(|m| m / 5),
+    );
+
+    // The nested fields are grouped by root, each root is parsed once per document.
+    let mut nested_fields_by_root: BTreeMap<FieldId, Vec<&NestedField>> = BTreeMap::new();
+    for nested in nested_fields {
+        nested_fields_by_root.entry(nested.root).or_default().push(nested);
+    }
+
     let mut key_buffer = Vec::new();
     while let Some((docid_bytes, value)) = obkv_documents.next()? {
         let obkv = obkv::KvReader::new(value);
+        let document_id = docid_bytes.try_into().map(DocumentId::from_be_bytes).unwrap();
 
+        let mut values = BTreeMap::new();
         for (field_id, field_bytes) in obkv.iter() {
             if faceted_fields.contains(&field_id) {
-                let value =
+                let value: Value =
                     serde_json::from_slice(field_bytes).map_err(InternalError::SerdeJson)?;
-                let (numbers, strings) = extract_facet_values(&value);
+                values.insert(field_id, value);
+            }
+        }
+        for (root, nested_fields) in &nested_fields_by_root {
+            if let Some(field_bytes) = obkv.get(*root) {
+                let root: Value =
+                    serde_json::from_slice(field_bytes).map_err(InternalError::SerdeJson)?;
+                for nested in nested_fields {
+                    if let Some(value) = nested_value(&root, &nested.path) {
+                        // A literal `a.b` key and the `b` key of an `a` object are the same
+                        // field, the document has the values of both.
+                        let value = match values.remove(&nested.field_id) {
+                            Some(literal) => merge_values(literal, value),
+                            None => value,
+                        };
+                        values.insert(nested.field_id, value);
+                    }
+                }
+            }
+        }
 
-                key_buffer.clear();
+        for (field_id, value) in values {
+            // The field exists in the document whatever its value is, even null.
+            facet_exists_docids_sorter
+                .insert(&field_id.to_be_bytes(), document_id.to_ne_bytes())?;
 
-                // prefix key with the field_id and the document_id
-                key_buffer.extend_from_slice(&field_id.to_be_bytes());
-                key_buffer.extend_from_slice(&docid_bytes);
+            if value.is_null() {
+                facet_is_null_docids_sorter
+                    .insert(&field_id.to_be_bytes(), document_id.to_ne_bytes())?;
+            } else if is_empty_value(&value) {
+                facet_is_empty_docids_sorter
+                    .insert(&field_id.to_be_bytes(), document_id.to_ne_bytes())?;
+            }
 
-                // insert facet numbers in sorter
-                for number in numbers {
-                    key_buffer.truncate(size_of::<FieldId>() + size_of::<DocumentId>());
-                    if let Some(value_bytes) = f64_into_bytes(number) {
-                        key_buffer.extend_from_slice(&value_bytes);
-                        key_buffer.extend_from_slice(&number.to_be_bytes());
+            let (numbers, strings) = extract_facet_values(&value);
 
-                        fid_docid_facet_numbers_sorter.insert(&key_buffer, ().as_bytes())?;
-                    }
-                }
+            key_buffer.clear();
 
-                // insert  normalized and original facet string in sorter
-                for (normalized, original) in strings.into_iter().filter(|(n, _)| !n.is_empty()) {
-                    key_buffer.truncate(size_of::<FieldId>() + size_of::<DocumentId>());
-                    key_buffer.extend_from_slice(normalized.as_bytes());
-                    fid_docid_facet_strings_sorter.insert(&key_buffer, original.as_bytes())?;
+            // prefix key with the field_id and the document_id
+            key_buffer.extend_from_slice(&field_id.to_be_bytes());
+            key_buffer.extend_from_slice(&docid_bytes);
+
+            // insert facet numbers in sorter
+            for number in numbers {
+                key_buffer.truncate(size_of::<FieldId>() + size_of::<DocumentId>());
+                if let Some(value_bytes) = f64_into_bytes(number) {
+                    key_buffer.extend_from_slice(&value_bytes);
+                    key_buffer.extend_from_slice(&number.to_be_bytes());
+
+                    fid_docid_facet_numbers_sorter.insert(&key_buffer, ().as_bytes())?;
                 }
             }
+
+            // insert  normalized and original facet string in sorter
+            for (normalized, original) in strings.into_iter().filter(|(n, _)| !n.is_empty()) {
+                key_buffer.truncate(size_of::<FieldId>() + size_of::<DocumentId>());
+                key_buffer.extend_from_slice(facet_string_key(&normalized).as_bytes());
+                fid_docid_facet_strings_sorter.insert(&key_buffer, original.as_bytes())?;
+            }
         }
     }
 
     Ok((
         sorter_into_reader(fid_docid_facet_numbers_sorter, indexer.clone())?,
-        sorter_into_reader(fid_docid_facet_strings_sorter, indexer)?,
+        sorter_into_reader(fid_docid_facet_strings_sorter, indexer.clone())?,
+        sorter_into_reader(facet_exists_docids_sorter, indexer.clone())?,
+        sorter_into_reader(facet_is_null_docids_sorter, indexer.clone())?,
+        sorter_into_reader(facet_is_empty_docids_sorter, indexer)?,
     ))
 }
 
+/// Returns the value at the end of the path of keys, the arrays met along
+/// the path are traversed and their values are gathered in a single array.
+fn nested_value(value: &Value, path: &[String]) -> Option<Value> {
+    let (key, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return Some(value.clone()),
+    };
+
+    match value {
+        Value::Object(object) => object.get(key).and_then(|value| nested_value(value, rest)),
+        Value::Array(values) => {
+            let mut output = Vec::new();
+            for value in values.iter().filter_map(|value| nested_value(value, path)) {
+                match value {
+                    Value::Array(values) => output.extend(values),
+                    value => output.push(value),
+                }
+            }
+            if output.is_empty() {
+                None
+            } else {
+                Some(Value::Array(output))
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Returns an array of the two values, the arrays are flattened.
+fn merge_values(first: Value, second: Value) -> Value {
+    let mut output = Vec::new();
+    for value in vec![first, second] {
+        match value {
+            Value::Array(values) => output.extend(values),
+            value => output.push(value),
+        }
+    }
+    Value::Array(output)
+}
+
+/// An empty string, an empty array or an empty object, the strings are not trimmed.
+fn is_empty_value(value: &Value) -> bool {
+    match value {
+        Value::String(string) => string.is_empty(),
+        Value::Array(values) => values.is_empty(),
+        Value::Object(object) => object.is_empty(),
+        Value::Null | Value::Bool(_) | Value::Number(_) => false,
+    }
+}
+
 fn extract_facet_values(value: &Value) -> (Vec<f64>, Vec<(String, String)>) {
     fn inner_extract_facet_values(
         value: &Value,
@@ -98,6 +238,11 @@ fn extract_facet_values(value: &Value) -> (Vec<f64>, Vec<(String, String)>) {
                 }
             }
             Value::String(original) => {
+                // The dates are also faceted as numbers to be compared with the date filters,
+                // in the same space as the numbers of the field, see `FilterCondition::evaluate`.
+                if let Some(timestamp) = rfc3339_timestamp(original) {
+                    output_numbers.push(timestamp);
+                }
                 let normalized = original.trim().to_lowercase();
                 output_strings.push((normalized, original.clone()));
             }
@@ -1,5 +1,5 @@
 use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::fs::File;
 use std::{cmp, io, mem, str, vec};
 
@@ -10,16 +10,19 @@ use super::helpers::{
 use crate::error::SerializationError;
 use crate::index::db_name::DOCID_WORD_POSITIONS;
 use crate::proximity::{positions_proximity, MAX_DISTANCE};
-use crate::{DocumentId, Result};
+use crate::{DocumentId, FieldId, Result};
 
 /// Extracts the best proximity between pairs of words and the documents ids where this pair appear.
 ///
 /// Returns a grenad reader with the list of extracted word pairs proximities and
-/// documents ids from the given chunk of docid word positions.
+/// documents ids from the given chunk of docid word positions. The positions of
+/// the excluded fields are ignored, no word pair is extracted from these fields.
 #[logging_timer::time]
 pub fn extract_word_pair_proximity_docids<R: io::Read>(
     mut docid_word_positions: grenad::Reader<R>,
     indexer: GrenadParameters,
+    one_attribute: u32,
+    excluded_fields: &HashSet<FieldId>,
 ) -> Result<grenad::Reader<File>> {
     let max_memory = indexer.max_memory_by_thread();
 
@@ -48,12 +51,16 @@ pub fn extract_word_pair_proximity_docids<R: io::Read>(
                 curr_document_id,
                 document_word_positions_heap,
                 &mut word_pair_proximity_docids_sorter,
+                one_attribute,
             )?;
             current_document_id = Some(document_id);
         }
 
         let word = word.to_string();
-        let mut iter = read_u32_ne_bytes(value).collect::<Vec<_>>().into_iter();
+        let mut iter = read_u32_ne_bytes(value)
+            .filter(|position| !excluded_fields.contains(&((position / one_attribute) as FieldId)))
+            .collect::<Vec<_>>()
+            .into_iter();
         if let Some(position) = iter.next() {
             document_word_positions_heap.push(PeekedWordPosition { word, position, iter });
         }
@@ -67,6 +74,7 @@ pub fn extract_word_pair_proximity_docids<R: io::Read>(
             document_id,
             document_word_positions_heap,
             &mut word_pair_proximity_docids_sorter,
+            one_attribute,
         )?;
     }
 
@@ -81,6 +89,7 @@ fn document_word_positions_into_sorter<'b>(
     document_id: DocumentId,
     mut word_positions_heap: BinaryHeap<PeekedWordPosition<vec::IntoIter<u32>>>,
     word_pair_proximity_docids_sorter: &mut grenad::Sorter<MergeFn>,
+    one_attribute: u32,
 ) -> Result<()> {
     let mut word_pair_proximity = HashMap::new();
     let mut ordered_peeked_word_positions = Vec::new();
@@ -94,7 +103,7 @@ fn document_word_positions_into_sorter<'b>(
 
         if let Some((head, tail)) = ordered_peeked_word_positions.split_first() {
             for PeekedWordPosition { word, position, .. } in tail {
-                let prox = positions_proximity(head.position, *position);
+                let prox = positions_proximity(head.position, *position, one_attribute);
                 if prox > 0 && prox < MAX_DISTANCE {
                     word_pair_proximity
                         .entry((head.word.clone(), word.clone()))
@@ -8,7 +8,7 @@ mod extract_word_docids;
 mod extract_word_pair_proximity_docids;
 mod extract_word_position_docids;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 
 use crossbeam_channel::Sender;
@@ -16,9 +16,11 @@ use log::debug;
 use rayon::prelude::*;
 
 use self::extract_docid_word_positions::extract_docid_word_positions;
+pub use self::extract_docid_word_positions::extract_document_exact_words;
 use self::extract_facet_number_docids::extract_facet_number_docids;
 use self::extract_facet_string_docids::extract_facet_string_docids;
 use self::extract_fid_docid_facet_values::extract_fid_docid_facet_values;
+pub use self::extract_fid_docid_facet_values::NestedField;
 use self::extract_fid_word_count_docids::extract_fid_word_count_docids;
 use self::extract_geo_points::extract_geo_points;
 use self::extract_word_docids::extract_word_docids;
@@ -39,11 +41,16 @@ pub(crate) fn data_from_obkv_documents(
     lmdb_writer_sx: Sender<Result<TypedChunk>>,
     searchable_fields: Option<HashSet<FieldId>>,
     faceted_fields: HashSet<FieldId>,
+    nested_faceted_fields: Vec<NestedField>,
     primary_key_id: FieldId,
     geo_field_id: Option<FieldId>,
     stop_words: Option<fst::Set<&[u8]>>,
+    one_attribute: u32,
+    proximity_excluded_fields: HashSet<FieldId>,
+    exact_attributes: HashSet<FieldId>,
+    synonyms_expansions: HashMap<String, Vec<Vec<String>>>,
 ) -> Result<()> {
-    let result: Result<(Vec<_>, (Vec<_>, Vec<_>))> = obkv_chunks
+    let result: Result<Vec<DocumentsDataChunks>> = obkv_chunks
         .par_bridge()
         .map(|result| {
             extract_documents_data(
@@ -51,31 +58,102 @@ pub(crate) fn data_from_obkv_documents(
                 indexer,
                 lmdb_writer_sx.clone(),
                 &searchable_fields,
+                &exact_attributes,
                 &faceted_fields,
+                &nested_faceted_fields,
                 primary_key_id,
                 geo_field_id,
                 &stop_words,
+                &synonyms_expansions,
+                one_attribute,
             )
         })
         .collect();
 
-    let (
-        docid_word_positions_chunks,
-        (docid_fid_facet_numbers_chunks, docid_fid_facet_strings_chunks),
-    ) = result?;
+    let mut docid_word_positions_chunks = Vec::new();
+    let mut docid_word_counts_chunks = Vec::new();
+    let mut exact_word_docids_chunks = Vec::new();
+    let mut field_id_exact_value_docids_chunks = Vec::new();
+    let mut docid_fid_facet_numbers_chunks = Vec::new();
+    let mut docid_fid_facet_strings_chunks = Vec::new();
+    let mut facet_exists_docids_chunks = Vec::new();
+    let mut facet_is_null_docids_chunks = Vec::new();
+    let mut facet_is_empty_docids_chunks = Vec::new();
+    for chunks in result? {
+        docid_word_positions_chunks.push(chunks.docid_word_positions);
+        docid_word_counts_chunks.push(chunks.docid_word_counts);
+        exact_word_docids_chunks.push(chunks.exact_word_docids);
+        field_id_exact_value_docids_chunks.push(chunks.field_id_exact_value_docids);
+        docid_fid_facet_numbers_chunks.push(chunks.docid_fid_facet_numbers);
+        docid_fid_facet_strings_chunks.push(chunks.docid_fid_facet_strings);
+        facet_exists_docids_chunks.push(chunks.facet_exists_docids);
+        facet_is_null_docids_chunks.push(chunks.facet_is_null_docids);
+        facet_is_empty_docids_chunks.push(chunks.facet_is_empty_docids);
+    }
 
-    spawn_extraction_task(
-        docid_word_positions_chunks.clone(),
-        indexer.clone(),
-        lmdb_writer_sx.clone(),
-        extract_word_pair_proximity_docids,
-        merge_cbo_roaring_bitmaps,
-        TypedChunk::WordPairProximityDocids,
-        "word-pair-proximity-docids",
-    );
+    // The exact word docids are directly extracted from the documents,
+    // we only have to merge the chunks before sending them to the DB writer.
+    let exact_word_docids_sender = lmdb_writer_sx.clone();
+    rayon::spawn(move || {
+        debug!("merge exact-word-docids database");
+        let reader = merge_readers(exact_word_docids_chunks, merge_roaring_bitmaps, indexer);
+        let _ = exact_word_docids_sender.send(reader.map(TypedChunk::ExactWordDocids));
+    });
+
+    let field_id_exact_value_docids_sender = lmdb_writer_sx.clone();
+    rayon::spawn(move || {
+        debug!("merge field-id-exact-value-docids database");
+        let reader =
+            merge_readers(field_id_exact_value_docids_chunks, merge_roaring_bitmaps, indexer);
+        let _ = field_id_exact_value_docids_sender
+            .send(reader.map(TypedChunk::FieldIdExactValueDocids));
+    });
+
+    // The documents ids of the existing facet fields are directly extracted from the documents.
+    let facet_exists_docids_sender = lmdb_writer_sx.clone();
+    rayon::spawn(move || {
+        debug!("merge facet-id-exists-docids database");
+        let reader = merge_readers(facet_exists_docids_chunks, merge_cbo_roaring_bitmaps, indexer);
+        let _ = facet_exists_docids_sender.send(reader.map(TypedChunk::FieldIdFacetExistsDocids));
+    });
+
+    let facet_is_null_docids_sender = lmdb_writer_sx.clone();
+    rayon::spawn(move || {
+        debug!("merge facet-id-is-null-docids database");
+        let reader = merge_readers(facet_is_null_docids_chunks, merge_cbo_roaring_bitmaps, indexer);
+        let _ = facet_is_null_docids_sender.send(reader.map(TypedChunk::FieldIdFacetIsNullDocids));
+    });
+
+    let facet_is_empty_docids_sender = lmdb_writer_sx.clone();
+    rayon::spawn(move || {
+        debug!("merge facet-id-is-empty-docids database");
+        let reader =
+            merge_readers(facet_is_empty_docids_chunks, merge_cbo_roaring_bitmaps, indexer);
+        let _ =
+            facet_is_empty_docids_sender.send(reader.map(TypedChunk::FieldIdFacetIsEmptyDocids));
+    });
+
+    if cfg!(feature = "proximity") {
+        spawn_extraction_task(
+            docid_word_positions_chunks.clone(),
+            indexer.clone(),
+            lmdb_writer_sx.clone(),
+            move |chunk, indexer| {
+                extract_word_pair_proximity_docids(
+                    chunk,
+                    indexer,
+                    one_attribute,
+                    &proximity_excluded_fields,
+                )
+            },
+            merge_cbo_roaring_bitmaps,
+            TypedChunk::WordPairProximityDocids,
+            "word-pair-proximity-docids",
+        );
+    }
 
     spawn_extraction_task(
-        docid_word_positions_chunks.clone(),
+        docid_word_counts_chunks,
         indexer.clone(),
         lmdb_writer_sx.clone(),
         extract_fid_word_count_docids,
@@ -162,25 +240,46 @@ fn spawn_extraction_task<FE, FS>(
     });
 }
 
+/// The chunks extracted from a single documents chunk that must be merged
+/// with the chunks of the other documents chunks before being written.
+struct DocumentsDataChunks {
+    docid_word_positions: grenad::Reader<CursorClonableMmap>,
+    docid_word_counts: grenad::Reader<CursorClonableMmap>,
+    exact_word_docids: grenad::Reader<File>,
+    field_id_exact_value_docids: grenad::Reader<File>,
+    docid_fid_facet_numbers: grenad::Reader<CursorClonableMmap>,
+    docid_fid_facet_strings: grenad::Reader<CursorClonableMmap>,
+    facet_exists_docids: grenad::Reader<File>,
+    facet_is_null_docids: grenad::Reader<File>,
+    facet_is_empty_docids: grenad::Reader<File>,
+}
+
 /// Extract chuncked data and send it into lmdb_writer_sx sender:
 /// - documents
 /// - documents_ids
 /// - docid_word_positions
+/// - docid_word_counts
+/// - exact_word_docids
+/// - field_id_exact_value_docids
 /// - docid_fid_facet_numbers
 /// - docid_fid_facet_strings
+/// - facet_exists_docids
+/// - facet_is_null_docids
+/// - facet_is_empty_docids
 fn extract_documents_data(
     documents_chunk: Result<grenad::Reader<File>>,
     indexer: GrenadParameters,
     lmdb_writer_sx: Sender<Result<TypedChunk>>,
     searchable_fields: &Option<HashSet<FieldId>>,
+    exact_attributes: &HashSet<FieldId>,
     faceted_fields: &HashSet<FieldId>,
+    nested_faceted_fields: &[NestedField],
     primary_key_id: FieldId,
     geo_field_id: Option<FieldId>,
     stop_words: &Option<fst::Set<&[u8]>>,
-) -> Result<(
-    grenad::Reader<CursorClonableMmap>,
-    (grenad::Reader<CursorClonableMmap>, grenad::Reader<CursorClonableMmap>),
-)> {
+    synonyms_expansions: &HashMap<String, Vec<Vec<String>>>,
+    one_attribute: u32,
+) -> Result<DocumentsDataChunks> {
     let documents_chunk = documents_chunk.and_then(|c| unsafe { into_clonable_grenad(c) })?;
 
     let _ = lmdb_writer_sx.send(Ok(TypedChunk::Documents(documents_chunk.clone())));
@@ -201,11 +300,20 @@ fn extract_documents_data(
     let (docid_word_positions_chunk, docid_fid_facet_values_chunks): (Result<_>, Result<_>) =
         rayon::join(
             || {
-                let (documents_ids, docid_word_positions_chunk) = extract_docid_word_positions(
+                let (
+                    documents_ids,
+                    docid_word_positions_chunk,
+                    docid_word_counts_chunk,
+                    exact_word_docids_chunk,
+                    field_id_exact_value_docids_chunk,
+                ) = extract_docid_word_positions(
                     documents_chunk.clone(),
                     indexer.clone(),
                     searchable_fields,
+                    exact_attributes,
                     stop_words.as_ref(),
+                    synonyms_expansions,
+                    one_attribute,
                 )?;
 
                 // send documents_ids to DB writer
@@ -217,15 +325,32 @@ fn extract_documents_data(
                 let _ = lmdb_writer_sx
                     .send(Ok(TypedChunk::DocidWordPositions(docid_word_positions_chunk.clone())));
 
-                Ok(docid_word_positions_chunk)
+                // send docid_word_counts_chunk to DB writer
+                let docid_word_counts_chunk =
+                    unsafe { into_clonable_grenad(docid_word_counts_chunk)? };
+                let _ = lmdb_writer_sx
+                    .send(Ok(TypedChunk::DocidWordCounts(docid_word_counts_chunk.clone())));
+
+                Ok((
+                    docid_word_positions_chunk,
+                    docid_word_counts_chunk,
+                    exact_word_docids_chunk,
+                    field_id_exact_value_docids_chunk,
+                ))
             },
             || {
-                let (docid_fid_facet_numbers_chunk, docid_fid_facet_strings_chunk) =
-                    extract_fid_docid_facet_values(
-                        documents_chunk.clone(),
-                        indexer.clone(),
-                        faceted_fields,
-                    )?;
+                let (
+                    docid_fid_facet_numbers_chunk,
+                    docid_fid_facet_strings_chunk,
+                    facet_exists_docids_chunk,
+                    facet_is_null_docids_chunk,
+                    facet_is_empty_docids_chunk,
+                ) = extract_fid_docid_facet_values(
+                    documents_chunk.clone(),
+                    indexer.clone(),
+                    faceted_fields,
+                    nested_faceted_fields,
+                )?;
 
                 // send docid_fid_facet_numbers_chunk to DB writer
                 let docid_fid_facet_numbers_chunk =
@@ -243,9 +368,35 @@ fn extract_documents_data(
                     docid_fid_facet_strings_chunk.clone(),
                 )));
 
-                Ok((docid_fid_facet_numbers_chunk, docid_fid_facet_strings_chunk))
+                Ok((
+                    docid_fid_facet_numbers_chunk,
+                    docid_fid_facet_strings_chunk,
+                    facet_exists_docids_chunk,
+                    facet_is_null_docids_chunk,
+                    facet_is_empty_docids_chunk,
+                ))
             },
         );
 
-    Ok((docid_word_positions_chunk?, docid_fid_facet_values_chunks?))
+    let (docid_word_positions, docid_word_counts, exact_word_docids, field_id_exact_value_docids) =
+        docid_word_positions_chunk?;
+    let (
+        docid_fid_facet_numbers,
+        docid_fid_facet_strings,
+        facet_exists_docids,
+        facet_is_null_docids,
+        facet_is_empty_docids,
+    ) = docid_fid_facet_values_chunks?;
+
+    Ok(DocumentsDataChunks {
+        docid_word_positions,
+        docid_word_counts,
+        exact_word_docids,
+        field_id_exact_value_docids,
+        docid_fid_facet_numbers,
+        docid_fid_facet_strings,
+        facet_exists_docids,
+        facet_is_null_docids,
+        facet_is_empty_docids,
+    })
 }
@@ -1,12 +1,18 @@
+mod checkpoint;
 mod extract;
 mod helpers;
+mod steps;
 mod transform;
 mod typed_chunk;
 
 use std::collections::HashSet;
+use std::fs::File;
 use std::io::{Read, Seek};
-use std::iter::FromIterator;
+use std::iter::{self, FromIterator};
 use std::num::{NonZeroU32, NonZeroUsize};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::task::Poll;
 use std::time::Instant;
 
 use chrono::Utc;
@@ -18,26 +24,48 @@ use roaring::RoaringBitmap;
 use serde::{Deserialize, Serialize};
 use typed_chunk::{write_typed_chunk_into_index, TypedChunk};
 
+pub use self::checkpoint::remove_checkpoint;
+use self::checkpoint::{
+    chunk_digest, read_checkpoint, read_chunk_checkpoint, write_checkpoint, write_chunk_checkpoint,
+};
+pub(crate) use self::extract::extract_document_exact_words;
+use self::extract::NestedField;
 pub use self::helpers::{
     create_sorter, create_writer, merge_cbo_roaring_bitmaps, merge_roaring_bitmaps,
     sorter_into_lmdb_database, write_into_lmdb_database, writer_into_reader, MergeFn,
 };
 use self::helpers::{grenad_obkv_into_chunks, GrenadParameters};
+use self::steps::Indexing;
+pub use self::steps::{IndexingStep, IndexingSteps};
 pub use self::transform::{Transform, TransformOutput};
 use crate::documents::DocumentBatchReader;
+use crate::error::UserError;
 use crate::update::{
     Facets, UpdateBuilder, UpdateIndexingStep, WordPrefixDocids, WordPrefixPairProximityDocids,
     WordPrefixPositionDocids, WordsPrefixesFst,
 };
-use crate::{Index, Result};
+use crate::{FieldDistribution, FieldTypeWarning, Index, Result};
 
-static MERGED_DATABASE_COUNT: usize = 7;
+static MERGED_DATABASE_COUNT: usize = 12;
 static PREFIX_DATABASE_COUNT: usize = 5;
 static TOTAL_POSTING_DATABASE_COUNT: usize = MERGED_DATABASE_COUNT + PREFIX_DATABASE_COUNT;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct DocumentAdditionResult {
     pub nb_documents: usize,
+    /// The number of documents that were added or whose content changed, the documents that
+    /// are replaced by exactly the same content are not counted.
+    #[serde(default)]
+    pub nb_changed_documents: usize,
+    /// The number of fields that were never seen in the documents of the index before.
+    #[serde(default)]
+    pub nb_new_fields: usize,
+    /// The number of documents in which the value of each field was added, modified or
+    /// removed, the fields of the documents replaced by the same content are not counted.
+    #[serde(default)]
+    pub changed_fields: FieldDistribution,
+    /// The fields that received values of a type they never had before.
+    pub warnings: Vec<FieldTypeWarning>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -76,6 +104,8 @@ pub struct IndexDocuments<'t, 'u, 'i, 'a> {
     words_positions_min_level_size: Option<NonZeroU32>,
     update_method: IndexDocumentsMethod,
     autogenerate_docids: bool,
+    documents_ids_range: RangeInclusive<u32>,
+    checkpoint_dir: Option<PathBuf>,
     update_id: u64,
 }
 
@@ -103,6 +133,8 @@ impl<'t, 'u, 'i, 'a> IndexDocuments<'t, 'u, 'i, 'a> {
             words_positions_min_level_size: None,
             update_method: IndexDocumentsMethod::ReplaceDocuments,
             autogenerate_docids: false,
+            documents_ids_range: 0..=u32::max_value(),
+            checkpoint_dir: None,
             update_id,
         }
     }
@@ -123,6 +155,27 @@ impl<'t, 'u, 'i, 'a> IndexDocuments<'t, 'u, 'i, 'a> {
         self.autogenerate_docids = false;
     }
 
+    /// Gives the new documents internal ids taken from the given range, like the
+    /// ranges of [`partition_documents_ids`](crate::documents::partition_documents_ids)
+    /// that keep the ids of the documents of the different partitions distinct.
+    pub fn documents_ids_range(&mut self, range: RangeInclusive<u32>) {
+        self.documents_ids_range = range;
+    }
+
+    /// Persists the output of the transform phase of this update in the given directory,
+    /// when the indexing is aborted the next execution of this update with the same documents
+    /// on the same version of the index resumes from it instead of reading the documents again.
+    ///
+    /// The data extracted from every chunk of documents is checkpointed too, the resumed update
+    /// only extracts the chunks that weren't completely extracted. The merge of the chunks is
+    /// written in the write transaction and is done again from the checkpointed data. Once the
+    /// transaction is committed the checkpoint is stale, it is ignored and removed by the next
+    /// documents addition or it can be removed with
+    /// [`remove_checkpoint`](crate::update::remove_checkpoint).
+    pub fn checkpoint_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.checkpoint_dir = Some(dir.into());
+    }
+
     #[logging_timer::time("IndexDocuments::{}")]
     pub fn execute<R, F>(
         self,
@@ -133,15 +186,55 @@ impl<'t, 'u, 'i, 'a> IndexDocuments<'t, 'u, 'i, 'a> {
         R: Read + Seek,
         F: Fn(UpdateIndexingStep, u64) + Sync,
     {
-        // Early return when there is no document to add
-        if reader.is_empty() {
-            return Ok(DocumentAdditionResult { nb_documents: 0 });
+        let mut steps = self.into_steps(reader, progress_callback);
+        loop {
+            if let Poll::Ready(result) = steps.step()? {
+                return Ok(result);
+            }
         }
+    }
+
+    /// Prepares the documents addition to be executed one step at a time with
+    /// [`IndexingSteps::step`], the transform, the extraction and the merge of every
+    /// chunk of documents and the computation of the prefix databases are separate steps.
+    ///
+    /// ```ignore
+    /// let mut steps = builder.into_steps(reader, |_, _| ());
+    /// let result = loop {
+    ///     match steps.step()? {
+    ///         Poll::Ready(result) => break result,
+    ///         Poll::Pending => run_other_tasks(),
+    ///     }
+    /// };
+    /// ```
+    pub fn into_steps<R, F>(
+        self,
+        reader: DocumentBatchReader<R>,
+        progress_callback: F,
+    ) -> IndexingSteps<'t, 'u, 'i, 'a, R, F>
+    where
+        R: Read + Seek,
+        F: Fn(UpdateIndexingStep, u64) + Sync,
+    {
+        IndexingSteps::new(self, reader, progress_callback)
+    }
 
+    /// Reads the documents, or the checkpoint of a previous execution of this update.
+    fn transform<R, F>(
+        &mut self,
+        reader: DocumentBatchReader<R>,
+        progress_callback: F,
+    ) -> Result<TransformOutput>
+    where
+        R: Read + Seek,
+        F: Fn(UpdateIndexingStep) + Sync,
+    {
+        // The checkpoints are written for the version of the index before this update.
+        let commits_count = self.index.commits_count(self.wtxn)?;
         self.index.set_updated_at(self.wtxn, &Utc::now())?;
         let before_transform = Instant::now();
         let update_id = self.update_id;
-        let progress_callback = |step| progress_callback(step, update_id);
+        let checkpoint_dir = self.checkpoint_dir.clone();
         let transform = Transform {
             rtxn: &self.wtxn,
             index: self.index,
@@ -152,64 +245,118 @@ impl<'t, 'u, 'i, 'a> IndexDocuments<'t, 'u, 'i, 'a> {
             max_memory: self.max_memory,
             index_documents_method: self.update_method,
             autogenerate_docids: self.autogenerate_docids,
+            documents_ids_range: self.documents_ids_range.clone(),
         };
 
-        let output = transform.read_documents(reader, progress_callback)?;
-        let nb_documents = output.documents_count;
+        let mut reader = reader;
+        let payload_digest = match &checkpoint_dir {
+            Some(_) => reader.digest()?,
+            None => 0,
+        };
+        let checkpoint = match &checkpoint_dir {
+            Some(dir) => read_checkpoint(dir, update_id, commits_count, payload_digest)?,
+            None => None,
+        };
+
+        let output = match checkpoint {
+            Some(output) => {
+                info!("Update transform resumed from the checkpoint");
+                output
+            }
+            None => {
+                let mut output = transform.read_documents(reader, progress_callback)?;
+                if let Some(dir) = &checkpoint_dir {
+                    write_checkpoint(dir, update_id, commits_count, payload_digest, &mut output)?;
+                }
+                output
+            }
+        };
 
         info!("Update transformed in {:.02?}", before_transform.elapsed());
 
-        self.execute_raw(output, progress_callback)?;
-        Ok(DocumentAdditionResult { nb_documents })
+        Ok(output)
     }
 
     #[logging_timer::time("IndexDocuments::{}")]
-    pub fn execute_raw<F>(self, output: TransformOutput, progress_callback: F) -> Result<()>
+    pub fn execute_raw<F>(mut self, output: TransformOutput, progress_callback: F) -> Result<()>
     where
         F: Fn(UpdateIndexingStep) + Sync,
     {
+        let mut indexing = self.prepare(output)?;
+        while let Some(chunk) = indexing.chunks.next() {
+            let typed_chunks = self.extract_chunk(&indexing, chunk?)?;
+            self.merge_chunk(&mut indexing, typed_chunks, &progress_callback)?;
+            indexing.next_chunk += 1;
+        }
+        self.write_metadata(&indexing)?;
+        self.execute_prefix_databases(progress_callback)
+    }
+
+    /// Deletes the documents replaced by the transformed ones, splits these documents
+    /// into chunks and reads the settings that their extraction needs.
+    fn prepare(&mut self, output: TransformOutput) -> Result<Indexing> {
         let TransformOutput {
             primary_key,
-            fields_ids_map,
+            mut fields_ids_map,
             field_distribution,
+            field_types,
+            field_type_warnings,
             external_documents_ids,
             new_documents_ids,
             replaced_documents_ids,
+            unchanged_documents_count,
+            changed_fields,
             documents_count,
             documents_file,
         } = output;
 
+        // The settings register the fields in the fields ids map before any document
+        // contains them, the fields are new the first time they appear in a document.
+        let previous_field_distribution = self.index.field_distribution(self.wtxn)?;
+        let nb_new_fields = field_distribution
+            .keys()
+            .filter(|name| !previous_field_distribution.contains_key(*name))
+            .count();
+        let result = DocumentAdditionResult {
+            nb_documents: documents_count,
+            nb_changed_documents: documents_count - unchanged_documents_count,
+            nb_new_fields,
+            changed_fields,
+            warnings: field_type_warnings,
+        };
+
+        // The nested fields that are faceted are registered under their dotted name,
+        // their values are extracted from the object of their root field.
+        for name in self.index.faceted_fields(self.wtxn)? {
+            if let Some((root, _)) = name.split_once('.') {
+                if fields_ids_map.id(root).is_some() && fields_ids_map.id(&name).is_none() {
+                    fields_ids_map.insert(&name).ok_or(UserError::AttributeLimitReached)?;
+                }
+            }
+        }
+
         // The fields_ids_map is put back to the store now so the rest of the transaction sees an
         // up to date field map.
         self.index.put_fields_ids_map(self.wtxn, &fields_ids_map)?;
 
-        let backup_pool;
-        let pool = match self.thread_pool {
-            Some(pool) => pool,
+        let backup_pool = match self.thread_pool {
+            Some(_) => None,
             #[cfg(not(test))]
             None => {
                 // We initialize a bakcup pool with the default
                 // settings if none have already been set.
-                backup_pool = rayon::ThreadPoolBuilder::new().build()?;
-                &backup_pool
+                Some(rayon::ThreadPoolBuilder::new().build()?)
             }
             #[cfg(test)]
             None => {
                 // We initialize a bakcup pool with the default
                 // settings if none have already been set.
-                backup_pool = rayon::ThreadPoolBuilder::new().num_threads(1).build()?;
-                &backup_pool
+                Some(rayon::ThreadPoolBuilder::new().num_threads(1).build()?)
             }
         };
 
         let documents_file = grenad::Reader::new(documents_file)?;
 
-        // create LMDB writer channel
-        let (lmdb_writer_sx, lmdb_writer_rx): (
-            Sender<Result<TypedChunk>>,
-            Receiver<Result<TypedChunk>>,
-        ) = crossbeam_channel::unbounded();
-
         // get the primary key field id
         let primary_key_id = fields_ids_map.id(&primary_key).unwrap();
 
@@ -217,10 +364,27 @@ impl<'t, 'u, 'i, 'a> IndexDocuments<'t, 'u, 'i, 'a> {
         let searchable_fields =
             self.index.searchable_fields_ids(self.wtxn)?.map(HashSet::from_iter);
         // get filterable fields for facet databases
-        let faceted_fields = self.index.faceted_fields_ids(self.wtxn)?;
+        let faceted_fields = if cfg!(feature = "facets") {
+            self.index.faceted_fields_ids(self.wtxn)?
+        } else {
+            HashSet::new()
+        };
+        let nested_faceted_fields: Vec<_> = faceted_fields
+            .iter()
+            .filter_map(|&field_id| {
+                let mut path = fields_ids_map.name(field_id)?.split('.');
+                let root = fields_ids_map.id(path.next()?)?;
+                let path: Vec<_> = path.map(String::from).collect();
+                if path.is_empty() {
+                    None
+                } else {
+                    Some(NestedField { root, path, field_id })
+                }
+            })
+            .collect();
         // get the fid of the `_geo` field.
         let geo_field_id = match self.index.fields_ids_map(self.wtxn)?.id("_geo") {
-            Some(gfid) => {
+            Some(gfid) if cfg!(feature = "geo") => {
                 let is_sortable = self.index.sortable_fields_ids(self.wtxn)?.contains(&gfid);
                 let is_filterable = self.index.filterable_fields_ids(self.wtxn)?.contains(&gfid);
                 if is_sortable || is_filterable {
@@ -229,49 +393,31 @@ impl<'t, 'u, 'i, 'a> IndexDocuments<'t, 'u, 'i, 'a> {
                     None
                 }
             }
-            None => None,
+            _ => None,
         };
 
-        let stop_words = self.index.stop_words(self.wtxn)?;
-        // let stop_words = stop_words.as_ref();
-
-        // Run extraction pipeline in parallel.
-        pool.install(|| {
-            let params = GrenadParameters {
-                chunk_compression_type: self.chunk_compression_type,
-                chunk_compression_level: self.chunk_compression_level,
-                max_memory: self.max_memory,
-                max_nb_chunks: self.max_nb_chunks, // default value, may be chosen.
-            };
-
-            // split obkv file into several chuncks
-            let chunk_iter = grenad_obkv_into_chunks(
-                documents_file,
-                params.clone(),
-                self.documents_chunk_size.unwrap_or(1024 * 1024 * 128), // 128MiB
-            );
-
-            let result = chunk_iter.map(|chunk_iter| {
-                // extract all databases from the chunked obkv douments
-                extract::data_from_obkv_documents(
-                    chunk_iter,
-                    params,
-                    lmdb_writer_sx.clone(),
-                    searchable_fields,
-                    faceted_fields,
-                    primary_key_id,
-                    geo_field_id,
-                    stop_words,
-                )
-            });
+        let stop_words = match self.index.documents_stop_words(self.wtxn)? {
+            Some(stop_words) => Some(stop_words.map_data(|bytes| bytes.to_vec())?),
+            None => None,
+        };
+        let one_attribute = self.index.position_stride(self.wtxn)?;
+        let proximity_excluded_fields = self.index.proximity_excluded_fields_ids(self.wtxn)?;
+        let exact_attributes = self.index.exact_attributes_ids(self.wtxn)?;
+        let synonyms_expansions = self.index.synonyms_expansions(self.wtxn)?;
 
-            if let Err(e) = result {
-                let _ = lmdb_writer_sx.send(Err(e));
-            }
+        let params = GrenadParameters {
+            chunk_compression_type: self.chunk_compression_type,
+            chunk_compression_level: self.chunk_compression_level,
+            max_memory: self.max_memory,
+            max_nb_chunks: self.max_nb_chunks, // default value, may be chosen.
+        };
 
-            // needs to be droped to avoid channel waiting lock.
-            drop(lmdb_writer_sx)
-        });
+        // split obkv file into several chuncks
+        let chunks = grenad_obkv_into_chunks(
+            documents_file,
+            params.clone(),
+            self.documents_chunk_size.unwrap_or(1024 * 1024 * 128), // 128MiB
+        )?;
 
         // We delete the documents that this document addition replaces. This way we are
         // able to simply insert all the documents even if they already exist in the database.
@@ -294,8 +440,130 @@ impl<'t, 'u, 'i, 'a> IndexDocuments<'t, 'u, 'i, 'a> {
         }
 
         let index_documents_ids = self.index.documents_ids(self.wtxn)?;
-        let index_is_empty = index_documents_ids.len() == 0;
-        let mut final_documents_ids = RoaringBitmap::new();
+
+        Ok(Indexing {
+            primary_key,
+            field_distribution,
+            field_types,
+            external_documents_ids,
+            new_documents_ids,
+            replaced_documents_ids,
+            documents_count,
+            chunks: Box::new(chunks),
+            next_chunk: 0,
+            params,
+            backup_pool,
+            searchable_fields,
+            faceted_fields,
+            nested_faceted_fields,
+            primary_key_id,
+            geo_field_id,
+            stop_words,
+            one_attribute,
+            proximity_excluded_fields,
+            exact_attributes,
+            synonyms_expansions,
+            index_documents_ids,
+            final_documents_ids: RoaringBitmap::new(),
+            result,
+        })
+    }
+
+    /// Extracts the data of a chunk of documents, or reads it from the checkpoint of a
+    /// previous execution of this update.
+    fn extract_chunk(
+        &self,
+        indexing: &Indexing,
+        chunk: grenad::Reader<File>,
+    ) -> Result<Vec<TypedChunk>> {
+        let dir = match &self.checkpoint_dir {
+            Some(dir) => dir,
+            None => return self.extract_documents_chunk(indexing, chunk),
+        };
+
+        let chunk_index = indexing.next_chunk;
+        let (chunk_digest, chunk) = chunk_digest(chunk)?;
+        if let Some(typed_chunks) = read_chunk_checkpoint(dir, chunk_index, chunk_digest)? {
+            info!("Chunk {} extraction resumed from the checkpoint", chunk_index);
+            return Ok(typed_chunks);
+        }
+
+        let typed_chunks = self.extract_documents_chunk(indexing, chunk)?;
+        write_chunk_checkpoint(dir, chunk_index, chunk_digest, typed_chunks)
+    }
+
+    /// Extracts the data of every database from the given chunk of documents.
+    fn extract_documents_chunk(
+        &self,
+        indexing: &Indexing,
+        chunk: grenad::Reader<File>,
+    ) -> Result<Vec<TypedChunk>> {
+        let pool = match self.thread_pool {
+            Some(pool) => pool,
+            None => indexing.backup_pool.as_ref().unwrap(),
+        };
+
+        // create LMDB writer channel
+        let (lmdb_writer_sx, lmdb_writer_rx): (
+            Sender<Result<TypedChunk>>,
+            Receiver<Result<TypedChunk>>,
+        ) = crossbeam_channel::unbounded();
+
+        // The documents chunks that remain to be indexed can't be shared with the pool.
+        let params = indexing.params;
+        let searchable_fields = indexing.searchable_fields.clone();
+        let faceted_fields = indexing.faceted_fields.clone();
+        let nested_faceted_fields = indexing.nested_faceted_fields.clone();
+        let primary_key_id = indexing.primary_key_id;
+        let geo_field_id = indexing.geo_field_id;
+        let stop_words = indexing.stop_words.as_ref().map(|stop_words| stop_words.as_ref());
+        let one_attribute = indexing.one_attribute;
+        let proximity_excluded_fields = indexing.proximity_excluded_fields.clone();
+        let exact_attributes = indexing.exact_attributes.clone();
+        let synonyms_expansions = indexing.synonyms_expansions.clone();
+
+        // Run extraction pipeline in parallel.
+        pool.install(|| {
+            // extract all databases from the chunked obkv douments
+            let result = extract::data_from_obkv_documents(
+                iter::once(Ok(chunk)),
+                params,
+                lmdb_writer_sx.clone(),
+                searchable_fields,
+                faceted_fields,
+                nested_faceted_fields,
+                primary_key_id,
+                geo_field_id,
+                stop_words,
+                one_attribute,
+                proximity_excluded_fields,
+                exact_attributes,
+                synonyms_expansions,
+            );
+
+            if let Err(e) = result {
+                let _ = lmdb_writer_sx.send(Err(e));
+            }
+
+            // needs to be droped to avoid channel waiting lock.
+            drop(lmdb_writer_sx)
+        });
+
+        lmdb_writer_rx.into_iter().collect()
+    }
+
+    /// Writes the data extracted from a chunk of documents into the databases.
+    fn merge_chunk<F>(
+        &mut self,
+        indexing: &mut Indexing,
+        typed_chunks: Vec<TypedChunk>,
+        progress_callback: F,
+    ) -> Result<()>
+    where
+        F: Fn(UpdateIndexingStep) + Sync,
+    {
+        // The entries of the first chunk are appended to the databases of an empty index.
+        let index_is_empty = indexing.index_documents_ids.is_empty() && indexing.next_chunk == 0;
 
         let mut databases_seen = 0;
         progress_callback(UpdateIndexingStep::MergeDataIntoFinalDatabase {
@@ -303,19 +571,19 @@ impl<'t, 'u, 'i, 'a> IndexDocuments<'t, 'u, 'i, 'a> {
             total_databases: TOTAL_POSTING_DATABASE_COUNT,
         });
 
-        for typed_chunk in lmdb_writer_rx {
+        for typed_chunk in typed_chunks {
             let (docids, is_merged_database) =
-                write_typed_chunk_into_index(typed_chunk?, &self.index, self.wtxn, index_is_empty)?;
+                write_typed_chunk_into_index(typed_chunk, &self.index, self.wtxn, index_is_empty)?;
             if !docids.is_empty() {
-                final_documents_ids |= docids;
-                let documents_seen_count = final_documents_ids.len();
+                indexing.final_documents_ids |= docids;
+                let documents_seen_count = indexing.final_documents_ids.len();
                 progress_callback(UpdateIndexingStep::IndexDocuments {
                     documents_seen: documents_seen_count as usize,
-                    total_documents: documents_count,
+                    total_documents: indexing.documents_count,
                 });
                 debug!(
                     "We have seen {} documents on {} total document so far",
-                    documents_seen_count, documents_count
+                    documents_seen_count, indexing.documents_count
                 );
             }
             if is_merged_database {
@@ -327,19 +595,27 @@ impl<'t, 'u, 'i, 'a> IndexDocuments<'t, 'u, 'i, 'a> {
             }
         }
 
+        Ok(())
+    }
+
+    /// Writes the fields, the primary key and the documents ids once all the chunks are merged.
+    fn write_metadata(&mut self, indexing: &Indexing) -> Result<()> {
         // We write the field distribution into the main database
-        self.index.put_field_distribution(self.wtxn, &field_distribution)?;
+        self.index.put_field_distribution(self.wtxn, &indexing.field_distribution)?;
+        self.index.put_field_types(self.wtxn, &indexing.field_types)?;
 
         // We write the primary key field id into the main database
-        self.index.put_primary_key(self.wtxn, &primary_key)?;
+        self.index.put_primary_key(self.wtxn, &indexing.primary_key)?;
 
         // We write the external documents ids into the main database.
-        self.index.put_external_documents_ids(self.wtxn, &external_documents_ids)?;
+        self.index.put_external_documents_ids(self.wtxn, &indexing.external_documents_ids)?;
 
-        let all_documents_ids = index_documents_ids | new_documents_ids | replaced_documents_ids;
+        let all_documents_ids = &indexing.index_documents_ids
+            | &indexing.new_documents_ids
+            | &indexing.replaced_documents_ids;
         self.index.put_documents_ids(self.wtxn, &all_documents_ids)?;
 
-        self.execute_prefix_databases(progress_callback)
+        Ok(())
     }
 
     #[logging_timer::time("IndexDocuments::{}")]
@@ -350,17 +626,19 @@ impl<'t, 'u, 'i, 'a> IndexDocuments<'t, 'u, 'i, 'a> {
         // Merged databases are already been indexed, we start from this count;
         let mut databases_seen = MERGED_DATABASE_COUNT;
 
-        // Run the facets update operation.
-        let mut builder = Facets::new(self.wtxn, self.index, self.update_id);
-        builder.chunk_compression_type = self.chunk_compression_type;
-        builder.chunk_compression_level = self.chunk_compression_level;
-        if let Some(value) = self.facet_level_group_size {
-            builder.level_group_size(value);
-        }
-        if let Some(value) = self.facet_min_level_size {
-            builder.min_level_size(value);
+        if cfg!(feature = "facets") {
+            // Run the facets update operation.
+            let mut builder = Facets::new(self.wtxn, self.index, self.update_id);
+            builder.chunk_compression_type = self.chunk_compression_type;
+            builder.chunk_compression_level = self.chunk_compression_level;
+            if let Some(value) = self.facet_level_group_size {
+                builder.level_group_size(value);
+            }
+            if let Some(value) = self.facet_min_level_size {
+                builder.min_level_size(value);
+            }
+            builder.execute()?;
         }
-        builder.execute()?;
 
         databases_seen += 1;
         progress_callback(UpdateIndexingStep::MergeDataIntoFinalDatabase {
@@ -368,15 +646,17 @@ impl<'t, 'u, 'i, 'a> IndexDocuments<'t, 'u, 'i, 'a> {
             total_databases: TOTAL_POSTING_DATABASE_COUNT,
         });
 
-        // Run the words prefixes update operation.
-        let mut builder = WordsPrefixesFst::new(self.wtxn, self.index, self.update_id);
-        if let Some(value) = self.words_prefix_threshold {
-            builder.threshold(value);
-        }
-        if let Some(value) = self.max_prefix_length {
-            builder.max_prefix_length(value);
+        if cfg!(feature = "prefix-db") {
+            // Run the words prefixes update operation.
+            let mut builder = WordsPrefixesFst::new(self.wtxn, self.index, self.update_id);
+            if let Some(value) = self.words_prefix_threshold {
+                builder.threshold(value);
+            }
+            if let Some(value) = self.max_prefix_length {
+                builder.max_prefix_length(value);
+            }
+            builder.execute()?;
         }
-        builder.execute()?;
 
         databases_seen += 1;
         progress_callback(UpdateIndexingStep::MergeDataIntoFinalDatabase {
@@ -384,13 +664,15 @@ impl<'t, 'u, 'i, 'a> IndexDocuments<'t, 'u, 'i, 'a> {
             total_databases: TOTAL_POSTING_DATABASE_COUNT,
         });
 
-        // Run the word prefix docids update operation.
-        let mut builder = WordPrefixDocids::new(self.wtxn, self.index);
-        builder.chunk_compression_type = self.chunk_compression_type;
-        builder.chunk_compression_level = self.chunk_compression_level;
-        builder.max_nb_chunks = self.max_nb_chunks;
-        builder.max_memory = self.max_memory;
-        builder.execute()?;
+        if cfg!(feature = "prefix-db") {
+            // Run the word prefix docids update operation.
+            let mut builder = WordPrefixDocids::new(self.wtxn, self.index);
+            builder.chunk_compression_type = self.chunk_compression_type;
+            builder.chunk_compression_level = self.chunk_compression_level;
+            builder.max_nb_chunks = self.max_nb_chunks;
+            builder.max_memory = self.max_memory;
+            builder.execute()?;
+        }
 
         databases_seen += 1;
         progress_callback(UpdateIndexingStep::MergeDataIntoFinalDatabase {
@@ -398,13 +680,17 @@ impl<'t, 'u, 'i, 'a> IndexDocuments<'t, 'u, 'i, 'a> {
             total_databases: TOTAL_POSTING_DATABASE_COUNT,
         });
 
-        // Run the word prefix pair proximity docids update operation.
-        let mut builder = WordPrefixPairProximityDocids::new(self.wtxn, self.index);
-        builder.chunk_compression_type = self.chunk_compression_type;
-        builder.chunk_compression_level = self.chunk_compression_level;
-        builder.max_nb_chunks = self.max_nb_chunks;
-        builder.max_memory = self.max_memory;
-        builder.execute()?;
+        if cfg!(all(feature = "prefix-db", feature = "proximity"))
+            && self.index.prefix_pair_proximity(self.wtxn)?
+        {
+            // Run the word prefix pair proximity docids update operation.
+            let mut builder = WordPrefixPairProximityDocids::new(self.wtxn, self.index);
+            builder.chunk_compression_type = self.chunk_compression_type;
+            builder.chunk_compression_level = self.chunk_compression_level;
+            builder.max_nb_chunks = self.max_nb_chunks;
+            builder.max_memory = self.max_memory;
+            builder.execute()?;
+        }
 
         databases_seen += 1;
         progress_callback(UpdateIndexingStep::MergeDataIntoFinalDatabase {
@@ -412,19 +698,21 @@ impl<'t, 'u, 'i, 'a> IndexDocuments<'t, 'u, 'i, 'a> {
             total_databases: TOTAL_POSTING_DATABASE_COUNT,
         });
 
-        // Run the words prefix position docids update operation.
-        let mut builder = WordPrefixPositionDocids::new(self.wtxn, self.index);
-        builder.chunk_compression_type = self.chunk_compression_type;
-        builder.chunk_compression_level = self.chunk_compression_level;
-        builder.max_nb_chunks = self.max_nb_chunks;
-        builder.max_memory = self.max_memory;
-        if let Some(value) = self.words_positions_level_group_size {
-            builder.level_group_size(value);
-        }
-        if let Some(value) = self.words_positions_min_level_size {
-            builder.min_level_size(value);
+        if cfg!(feature = "prefix-db") {
+            // Run the words prefix position docids update operation.
+            let mut builder = WordPrefixPositionDocids::new(self.wtxn, self.index);
+            builder.chunk_compression_type = self.chunk_compression_type;
+            builder.chunk_compression_level = self.chunk_compression_level;
+            builder.max_nb_chunks = self.max_nb_chunks;
+            builder.max_memory = self.max_memory;
+            if let Some(value) = self.words_positions_level_group_size {
+                builder.level_group_size(value);
+            }
+            if let Some(value) = self.words_positions_min_level_size {
+                builder.min_level_size(value);
+            }
+            builder.execute()?;
         }
-        builder.execute()?;
 
         databases_seen += 1;
         progress_callback(UpdateIndexingStep::MergeDataIntoFinalDatabase {
@@ -442,11 +730,12 @@ mod tests {
 
     use big_s::S;
     use heed::EnvOpenOptions;
+    use maplit::{btreemap, hashset};
 
     use super::*;
     use crate::documents::DocumentBatchBuilder;
-    use crate::update::DeleteDocuments;
-    use crate::HashMap;
+    use crate::update::{DeleteDocuments, Settings};
+    use crate::{FilterCondition, HashMap};
 
     #[test]
     fn simple_document_replacement() {
@@ -1018,4 +1307,362 @@ mod tests {
         let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
         assert_eq!(documents_ids.len(), 1);
     }
+
+    #[test]
+    fn index_documents_step_by_step() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "name": "kevin" },
+            { "id": 2, "name": "kevina" },
+            { "id": 3, "name": "benoit" }
+        ]);
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, 0);
+        // Every document is in its own chunk.
+        builder.documents_chunk_size = Some(1);
+        let mut steps = builder.into_steps(content, |_, _| ());
+        let mut executed = Vec::new();
+        let result = loop {
+            executed.push(steps.next_step());
+            if let Poll::Ready(result) = steps.step().unwrap() {
+                break result;
+            }
+        };
+        assert_eq!(steps.next_step(), IndexingStep::Done);
+        drop(steps);
+        wtxn.commit().unwrap();
+
+        assert_eq!(result.nb_documents, 3);
+        assert_eq!(
+            &executed[..5],
+            &[
+                IndexingStep::Transform,
+                IndexingStep::ExtractChunk(0),
+                IndexingStep::MergeChunk(0),
+                IndexingStep::ExtractChunk(1),
+                IndexingStep::MergeChunk(1),
+            ]
+        );
+        assert_eq!(executed.last(), Some(&IndexingStep::PrefixDatabases));
+
+        let rtxn = index.read_txn().unwrap();
+        let count = index.number_of_documents(&rtxn).unwrap();
+        assert_eq!(count, 3);
+        let words = index.word_docids.get(&rtxn, "benoit").unwrap().unwrap();
+        assert_eq!(words.len(), 1);
+    }
+
+    #[test]
+    fn filter_on_nested_fields() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("address.city") });
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": 0, "address": { "city": "Paris", "zip": 75001 } },
+            { "id": 1, "address": { "city": "Lyon" } },
+            { "id": 2, "address": [{ "city": "Lyon" }, { "city": "Paris" }] },
+            { "id": 3, "address": "Paris" },
+            { "id": 4, "address.city": "Paris" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let filter = |expression: &str| -> Vec<u32> {
+            let condition = FilterCondition::from_str(&rtxn, &index, expression).unwrap();
+            condition.evaluate(&rtxn, &index).unwrap().into_iter().collect()
+        };
+        assert_eq!(filter("address.city = Paris"), vec![0, 2, 4]);
+        assert_eq!(filter("address.city = lyon"), vec![1, 2]);
+        assert_eq!(filter("address.city EXISTS"), vec![0, 1, 2, 4]);
+        drop(rtxn);
+
+        // The nested fields declared filterable after the documents are indexed too.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.set_filterable_fields(hashset! { S("address.city"), S("address.zip") });
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let condition = FilterCondition::from_str(&rtxn, &index, "address.zip > 75000").unwrap();
+        let documents_ids: Vec<_> =
+            condition.evaluate(&rtxn, &index).unwrap().into_iter().collect();
+        assert_eq!(documents_ids, vec![0]);
+    }
+
+    #[test]
+    fn filter_on_literal_and_nested_field_of_the_same_document() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("address.city") });
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": 0, "address.city": "Paris", "address": { "city": "Lyon" } },
+            { "id": 1, "address.city": null, "address": { "city": "Lyon" } },
+            { "id": 2, "address.city": "Paris", "address": { "zip": 75001 } }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // The literal key and the nested path are the same field, a document
+        // that contains both is faceted with the values of both.
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        assert_eq!(fields_ids_map.iter().filter(|(_, name)| *name == "address.city").count(), 1);
+
+        let filter = |expression: &str| -> Vec<u32> {
+            let condition = FilterCondition::from_str(&rtxn, &index, expression).unwrap();
+            condition.evaluate(&rtxn, &index).unwrap().into_iter().collect()
+        };
+        assert_eq!(filter("address.city = Paris"), vec![0, 2]);
+        assert_eq!(filter("address.city = Lyon"), vec![0, 1]);
+        assert_eq!(filter("address.city IS NULL"), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn changed_documents_and_new_fields() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "name": "kevin" },
+            { "id": 2, "name": "kevina" }
+        ]);
+        let result = IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        assert_eq!(result.nb_documents, 2);
+        assert_eq!(result.nb_changed_documents, 2);
+        assert_eq!(result.nb_new_fields, 2);
+        assert_eq!(result.changed_fields, btreemap! { S("id") => 2, S("name") => 2 });
+
+        // The first document is replaced by the same content.
+        let content = documents!([
+            { "id": 1, "name": "kevin" },
+            { "id": 2, "name": "benoit" },
+            { "id": 3, "name": "bob", "age": 25 }
+        ]);
+        let result = IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        assert_eq!(result.nb_documents, 3);
+        assert_eq!(result.nb_changed_documents, 2);
+        assert_eq!(result.nb_new_fields, 1);
+        assert_eq!(
+            result.changed_fields,
+            btreemap! { S("age") => 1, S("id") => 1, S("name") => 2 }
+        );
+
+        // A partial update with the values the documents already have is a no-op.
+        let content = documents!([
+            { "id": 2, "name": "benoit" },
+            { "id": 3, "age": 25 }
+        ]);
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, 2);
+        builder.index_documents_method(IndexDocumentsMethod::UpdateDocuments);
+        let result = builder.execute(content, |_, _| ()).unwrap();
+        assert_eq!(result.nb_documents, 2);
+        assert_eq!(result.nb_changed_documents, 0);
+        assert_eq!(result.nb_new_fields, 0);
+        assert!(result.changed_fields.is_empty());
+
+        // A field declared in the settings is only new once a document contains it.
+        let mut builder = Settings::new(&mut wtxn, &index, 3);
+        builder.set_searchable_fields(vec![S("name"), S("city")]);
+        builder.execute(|_, _| ()).unwrap();
+
+        let content = documents!([{ "id": 3, "name": "bob", "age": 25, "city": "Paris" }]);
+        let result = IndexDocuments::new(&mut wtxn, &index, 4).execute(content, |_, _| ()).unwrap();
+        assert_eq!(result.nb_changed_documents, 1);
+        assert_eq!(result.nb_new_fields, 1);
+        assert_eq!(result.changed_fields, btreemap! { S("city") => 1 });
+        wtxn.commit().unwrap();
+    }
+
+    #[test]
+    fn resume_from_checkpoint() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let checkpoint_dir = tempfile::tempdir().unwrap();
+
+        // The transform phase of the update is checkpointed but the indexing is aborted.
+        let wtxn = index.write_txn().unwrap();
+        let mut content = documents!([
+            { "id": 1, "name": "kevin" },
+            { "id": 2, "name": "kevina" }
+        ]);
+        let payload_digest = content.digest().unwrap();
+        let transform = Transform {
+            rtxn: &wtxn,
+            index: &index,
+            log_every_n: None,
+            chunk_compression_type: CompressionType::None,
+            chunk_compression_level: None,
+            max_nb_chunks: None,
+            max_memory: None,
+            index_documents_method: IndexDocumentsMethod::ReplaceDocuments,
+            autogenerate_docids: false,
+            documents_ids_range: 0..=u32::max_value(),
+        };
+        let mut output = transform.read_documents(content, |_| ()).unwrap();
+        let commits_count = index.commits_count(&wtxn).unwrap();
+        write_checkpoint(checkpoint_dir.path(), 0, commits_count, payload_digest, &mut output)
+            .unwrap();
+        drop(output);
+        wtxn.abort().unwrap();
+
+        // The same update is executed again and resumes from the checkpointed documents,
+        // the checkpoint is kept until the transaction is committed.
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "name": "kevin" },
+            { "id": 2, "name": "kevina" }
+        ]);
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, 0);
+        builder.checkpoint_dir(checkpoint_dir.path());
+        let result = builder.execute(content, |_, _| ()).unwrap();
+        assert_eq!(result.nb_documents, 2);
+        assert!(read_checkpoint(checkpoint_dir.path(), 0, commits_count, payload_digest)
+            .unwrap()
+            .is_some());
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let external_documents_ids = index.external_documents_ids(&rtxn).unwrap();
+        assert!(external_documents_ids.get("1").is_some());
+        assert!(external_documents_ids.get("2").is_some());
+
+        // The checkpoint is stale once the transaction is committed.
+        let commits_count = index.commits_count(&rtxn).unwrap();
+        assert!(read_checkpoint(checkpoint_dir.path(), 0, commits_count, payload_digest)
+            .unwrap()
+            .is_none());
+        assert!(!checkpoint_dir.path().join("metadata.json").exists());
+    }
+
+    #[test]
+    fn resume_from_chunk_checkpoint() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let checkpoint_dir = tempfile::tempdir().unwrap();
+        let chunks_dir = checkpoint_dir.path().join("chunks");
+
+        // The first chunk of documents is extracted and merged, then the indexing is aborted.
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "name": "kevin" },
+            { "id": 2, "name": "kevina" },
+            { "id": 3, "name": "benoit" }
+        ]);
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, 0);
+        builder.documents_chunk_size = Some(1);
+        builder.checkpoint_dir(checkpoint_dir.path());
+        let mut steps = builder.into_steps(content, |_, _| ());
+        while steps.next_step() != IndexingStep::ExtractChunk(1) {
+            assert!(steps.step().unwrap().is_pending());
+        }
+        drop(steps);
+        wtxn.abort().unwrap();
+        assert!(chunks_dir.join("0").join("metadata.json").exists());
+        assert!(!chunks_dir.join("1").exists());
+
+        // A chunk that is extracted again replaces its directory, this file stays
+        // only if the first chunk is read back from the checkpoint.
+        File::create(chunks_dir.join("0").join("marker")).unwrap();
+
+        // The same update is executed again and only extracts the remaining chunks.
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "name": "kevin" },
+            { "id": 2, "name": "kevina" },
+            { "id": 3, "name": "benoit" }
+        ]);
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, 0);
+        builder.documents_chunk_size = Some(1);
+        builder.checkpoint_dir(checkpoint_dir.path());
+        let result = builder.execute(content, |_, _| ()).unwrap();
+        assert_eq!(result.nb_documents, 3);
+        wtxn.commit().unwrap();
+        assert!(chunks_dir.join("0").join("marker").exists());
+        assert!(chunks_dir.join("2").join("metadata.json").exists());
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.number_of_documents(&rtxn).unwrap(), 3);
+        for word in &["kevin", "kevina", "benoit"] {
+            let docids = index.word_docids.get(&rtxn, word).unwrap().unwrap();
+            assert_eq!(docids.len(), 1);
+        }
+    }
+
+    #[test]
+    fn checkpoint_of_another_payload() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+        let checkpoint_dir = tempfile::tempdir().unwrap();
+
+        // The transform phase of the update is checkpointed and the commit fails.
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 1, "name": "kevin" }]);
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, 0);
+        builder.checkpoint_dir(checkpoint_dir.path());
+        builder.execute(content, |_, _| ()).unwrap();
+        wtxn.abort().unwrap();
+
+        // The update is executed again with other documents, the checkpoint is ignored.
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 3, "name": "benoit" }]);
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, 0);
+        builder.checkpoint_dir(checkpoint_dir.path());
+        let result = builder.execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(result.nb_documents, 1);
+
+        let rtxn = index.read_txn().unwrap();
+        let external_documents_ids = index.external_documents_ids(&rtxn).unwrap();
+        assert!(external_documents_ids.get("1").is_none());
+        assert!(external_documents_ids.get("3").is_some());
+    }
+
+    #[test]
+    fn documents_ids_range() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "name": "kevin" },
+            { "id": 2, "name": "kevina" }
+        ]);
+        let mut builder = IndexDocuments::new(&mut wtxn, &index, 0);
+        builder.documents_ids_range(crate::documents::partition_documents_ids(1, 4));
+        builder.execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let documents_ids: Vec<_> = index.documents_ids(&rtxn).unwrap().into_iter().collect();
+        assert_eq!(documents_ids, vec![1 << 30, (1 << 30) + 1]);
+    }
 }
@@ -0,0 +1,160 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::task::Poll;
+
+use rayon::ThreadPool;
+use roaring::RoaringBitmap;
+
+use super::extract::NestedField;
+use super::helpers::GrenadParameters;
+use super::{DocumentAdditionResult, IndexDocuments, TypedChunk};
+use crate::documents::DocumentBatchReader;
+use crate::update::UpdateIndexingStep;
+use crate::{ExternalDocumentsIds, FieldDistribution, FieldId, FieldTypes, Result};
+
+/// The next step of a documents addition driven by [`IndexingSteps::step`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexingStep {
+    /// Reads the documents and assigns them their internal ids.
+    Transform,
+    /// Extracts the words and the facet values of the given chunk of documents.
+    ExtractChunk(usize),
+    /// Writes the data extracted from the given chunk of documents in the databases.
+    MergeChunk(usize),
+    /// Computes the facet levels and the words prefixes databases.
+    PrefixDatabases,
+    /// The documents addition is done.
+    Done,
+}
+
+/// A documents addition executed one step at a time by the caller, see
+/// [`IndexDocuments::into_steps`].
+///
+/// The caller can run its other tasks between two steps, the write transaction stays
+/// borrowed until the documents addition is done or the steps are dropped.
+pub struct IndexingSteps<'t, 'u, 'i, 'a, R, F> {
+    builder: Option<IndexDocuments<'t, 'u, 'i, 'a>>,
+    progress_callback: F,
+    state: State<R>,
+}
+
+enum State<R> {
+    Transform(DocumentBatchReader<R>),
+    ExtractChunk(Box<Indexing>, grenad::Reader<File>),
+    MergeChunk(Box<Indexing>, Vec<TypedChunk>),
+    PrefixDatabases(Box<Indexing>),
+    Done,
+}
+
+/// The documents chunks that remain to be indexed and everything their extraction needs.
+pub(super) struct Indexing {
+    pub primary_key: String,
+    pub field_distribution: FieldDistribution,
+    pub field_types: FieldTypes,
+    pub external_documents_ids: ExternalDocumentsIds<'static>,
+    pub new_documents_ids: RoaringBitmap,
+    pub replaced_documents_ids: RoaringBitmap,
+    pub documents_count: usize,
+    pub chunks: Box<dyn Iterator<Item = Result<grenad::Reader<File>>> + Send>,
+    pub next_chunk: usize,
+    pub params: GrenadParameters,
+    pub backup_pool: Option<ThreadPool>,
+    pub searchable_fields: Option<HashSet<FieldId>>,
+    pub faceted_fields: HashSet<FieldId>,
+    pub nested_faceted_fields: Vec<NestedField>,
+    pub primary_key_id: FieldId,
+    pub geo_field_id: Option<FieldId>,
+    pub stop_words: Option<fst::Set<Vec<u8>>>,
+    pub one_attribute: u32,
+    pub proximity_excluded_fields: HashSet<FieldId>,
+    pub exact_attributes: HashSet<FieldId>,
+    pub synonyms_expansions: HashMap<String, Vec<Vec<String>>>,
+    pub index_documents_ids: RoaringBitmap,
+    pub final_documents_ids: RoaringBitmap,
+    pub result: DocumentAdditionResult,
+}
+
+impl<'t, 'u, 'i, 'a, R, F> IndexingSteps<'t, 'u, 'i, 'a, R, F>
+where
+    R: Read + Seek,
+    F: Fn(UpdateIndexingStep, u64) + Sync,
+{
+    pub(super) fn new(
+        builder: IndexDocuments<'t, 'u, 'i, 'a>,
+        reader: DocumentBatchReader<R>,
+        progress_callback: F,
+    ) -> Self {
+        IndexingSteps { builder: Some(builder), progress_callback, state: State::Transform(reader) }
+    }
+
+    /// Returns the step that the next call to [`IndexingSteps::step`] executes.
+    pub fn next_step(&self) -> IndexingStep {
+        match &self.state {
+            State::Transform(_) => IndexingStep::Transform,
+            State::ExtractChunk(indexing, _) => IndexingStep::ExtractChunk(indexing.next_chunk),
+            State::MergeChunk(indexing, _) => IndexingStep::MergeChunk(indexing.next_chunk),
+            State::PrefixDatabases(_) => IndexingStep::PrefixDatabases,
+            State::Done => IndexingStep::Done,
+        }
+    }
+
+    /// Executes the next step of the documents addition, returns `Poll::Pending`
+    /// until the last step is executed and the documents addition is done.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it is called again once the documents addition is done or a step failed.
+    pub fn step(&mut self) -> Result<Poll<DocumentAdditionResult>> {
+        let IndexingSteps { builder, progress_callback: callback, state } = self;
+        let update_id = match builder {
+            Some(builder) => builder.update_id,
+            None => panic!("the documents addition is already done"),
+        };
+        let callback = &*callback;
+        let progress_callback = |step| callback(step, update_id);
+
+        match std::mem::replace(state, State::Done) {
+            State::Transform(reader) => {
+                if reader.is_empty() {
+                    *builder = None;
+                    return Ok(Poll::Ready(DocumentAdditionResult::default()));
+                }
+                let builder = builder.as_mut().unwrap();
+                let output = builder.transform(reader, &progress_callback)?;
+                let indexing = builder.prepare(output)?;
+                *state = next_chunk_state(Box::new(indexing))?;
+                Ok(Poll::Pending)
+            }
+            State::ExtractChunk(indexing, chunk) => {
+                let builder = builder.as_mut().unwrap();
+                let typed_chunks = builder.extract_chunk(&indexing, chunk)?;
+                *state = State::MergeChunk(indexing, typed_chunks);
+                Ok(Poll::Pending)
+            }
+            State::MergeChunk(mut indexing, typed_chunks) => {
+                let builder = builder.as_mut().unwrap();
+                builder.merge_chunk(&mut indexing, typed_chunks, &progress_callback)?;
+                indexing.next_chunk += 1;
+                *state = next_chunk_state(indexing)?;
+                Ok(Poll::Pending)
+            }
+            State::PrefixDatabases(indexing) => {
+                let mut builder = builder.take().unwrap();
+                builder.write_metadata(&indexing)?;
+                builder.execute_prefix_databases(&progress_callback)?;
+                Ok(Poll::Ready(indexing.result))
+            }
+            State::Done => panic!("the documents addition failed"),
+        }
+    }
+}
+
+/// Returns the state that extracts the next chunk of documents
+/// or that computes the prefix databases once they are all merged.
+fn next_chunk_state<R>(mut indexing: Box<Indexing>) -> Result<State<R>> {
+    match indexing.chunks.next() {
+        Some(chunk) => Ok(State::ExtractChunk(indexing, chunk?)),
+        None => Ok(State::PrefixDatabases(indexing)),
+    }
+}
@@ -3,9 +3,11 @@ use std::collections::btree_map::Entry;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
+use std::ops::RangeInclusive;
 use std::time::Instant;
 
 use grenad::CompressionType;
+use heed::types::ByteSlice;
 use itertools::Itertools;
 use log::info;
 use roaring::RoaringBitmap;
@@ -17,9 +19,13 @@ use super::helpers::{
 use super::IndexDocumentsMethod;
 use crate::documents::{DocumentBatchReader, DocumentsBatchIndex};
 use crate::error::{Error, InternalError, UserError};
+use crate::field_types::record_field_type;
 use crate::index::db_name;
 use crate::update::{AvailableDocumentsIds, UpdateIndexingStep};
-use crate::{ExternalDocumentsIds, FieldDistribution, FieldId, FieldsIdsMap, Index, Result, BEU32};
+use crate::{
+    ExternalDocumentsIds, FieldDistribution, FieldId, FieldTypeWarning, FieldTypes, FieldsIdsMap,
+    Index, Result,
+};
 
 const DEFAULT_PRIMARY_KEY_NAME: &str = "id";
 
@@ -27,9 +33,15 @@ pub struct TransformOutput {
     pub primary_key: String,
     pub fields_ids_map: FieldsIdsMap,
     pub field_distribution: FieldDistribution,
+    pub field_types: FieldTypes,
+    pub field_type_warnings: Vec<FieldTypeWarning>,
     pub external_documents_ids: ExternalDocumentsIds<'static>,
     pub new_documents_ids: RoaringBitmap,
     pub replaced_documents_ids: RoaringBitmap,
+    /// The number of replaced documents whose content is the same as before.
+    pub unchanged_documents_count: usize,
+    /// The number of documents in which the value of each field was added, modified or removed.
+    pub changed_fields: FieldDistribution,
     pub documents_count: usize,
     pub documents_file: File,
 }
@@ -50,6 +62,8 @@ pub struct Transform<'t, 'i> {
     pub max_memory: Option<usize>,
     pub index_documents_method: IndexDocumentsMethod,
     pub autogenerate_docids: bool,
+    /// The range the internal ids of the new documents are taken from.
+    pub documents_ids_range: RangeInclusive<u32>,
 }
 
 /// Create a mapping between the field ids found in the document batch and the one that were
@@ -252,7 +266,12 @@ impl Transform<'_, '_> {
         let mut external_documents_ids = self.index.external_documents_ids(self.rtxn).unwrap();
         let documents_ids = self.index.documents_ids(self.rtxn)?;
         let mut field_distribution = self.index.field_distribution(self.rtxn)?;
-        let mut available_documents_ids = AvailableDocumentsIds::from_documents_ids(&documents_ids);
+        let mut field_types = self.index.field_types(self.rtxn)?;
+        let mut field_type_warnings = Vec::new();
+        let mut available_documents_ids = AvailableDocumentsIds::from_documents_ids_in_range(
+            &documents_ids,
+            self.documents_ids_range.clone(),
+        );
 
         // consume sorter, in order to free the internal allocation, before creating a new one.
         let mut iter = sorter.into_merger_iter()?;
@@ -274,6 +293,8 @@ impl Transform<'_, '_> {
         let mut new_external_documents_ids_builder = fst::MapBuilder::memory();
         let mut replaced_documents_ids = RoaringBitmap::new();
         let mut new_documents_ids = RoaringBitmap::new();
+        let mut unchanged_documents_count = 0;
+        let mut changed_fields = FieldDistribution::new();
         let mut obkv_buffer = Vec::new();
 
         // While we write into final file we get or generate the internal documents ids.
@@ -292,13 +313,13 @@ impl Transform<'_, '_> {
                     // we use it and insert it in the list of replaced documents.
                     replaced_documents_ids.insert(docid);
 
-                    let key = BEU32::new(docid);
-                    let base_obkv = self.index.documents.get(&self.rtxn, &key)?.ok_or(
+                    let base_bytes = self.index.document_bytes(&self.rtxn, docid)?.ok_or(
                         InternalError::DatabaseMissingEntry {
                             db_name: db_name::DOCUMENTS,
                             key: None,
                         },
                     )?;
+                    let base_obkv = obkv::KvReader::new(base_bytes.as_ref());
 
                     // we remove all the fields that were already counted
                     for (field_id, _) in base_obkv.iter() {
@@ -315,14 +336,29 @@ impl Transform<'_, '_> {
 
                     // Depending on the update indexing method we will merge
                     // the document update with the current document or not.
-                    match self.index_documents_method {
-                        IndexDocumentsMethod::ReplaceDocuments => (docid, update_obkv),
+                    let obkv = match self.index_documents_method {
+                        IndexDocumentsMethod::ReplaceDocuments => update_obkv,
                         IndexDocumentsMethod::UpdateDocuments => {
                             let update_obkv = obkv::KvReader::new(update_obkv);
                             merge_two_obkvs(base_obkv, update_obkv, &mut obkv_buffer);
-                            (docid, obkv_buffer.as_slice())
+                            obkv_buffer.as_slice()
                         }
+                    };
+
+                    // The fields are written in the order of their ids, the same content
+                    // is always encoded in the same bytes.
+                    if obkv == &*base_bytes {
+                        unchanged_documents_count += 1;
+                    } else {
+                        count_changed_fields(
+                            &fields_ids_map,
+                            obkv::KvReader::new(base_bytes.as_ref()),
+                            obkv::KvReader::new(obkv),
+                            &mut changed_fields,
+                        );
                     }
+
+                    (docid, obkv)
                 }
                 None => {
                     // If this user id is new we add it to the external documents ids map
@@ -331,6 +367,10 @@ impl Transform<'_, '_> {
                         available_documents_ids.next().ok_or(UserError::DocumentLimitReached)?;
                     new_external_documents_ids_builder.insert(external_id, new_docid as u64)?;
                     new_documents_ids.insert(new_docid);
+                    for (field_id, _) in obkv::KvReader::new(update_obkv).iter() {
+                        let field_name = fields_ids_map.name(field_id).unwrap();
+                        *changed_fields.entry(field_name.to_string()).or_default() += 1;
+                    }
                     (new_docid, update_obkv)
                 }
             };
@@ -340,9 +380,14 @@ impl Transform<'_, '_> {
             documents_count += 1;
 
             let reader = obkv::KvReader::new(obkv);
-            for (field_id, _) in reader.iter() {
+            for (field_id, value) in reader.iter() {
                 let field_name = fields_ids_map.name(field_id).unwrap();
                 *field_distribution.entry(field_name.to_string()).or_default() += 1;
+
+                // A warning is only reported the first time a field receives a new type.
+                if let Some(warning) = record_field_type(&mut field_types, field_name, value) {
+                    field_type_warnings.push(warning);
+                }
             }
         }
 
@@ -373,9 +418,13 @@ impl Transform<'_, '_> {
             primary_key,
             fields_ids_map,
             field_distribution,
+            field_types,
+            field_type_warnings,
             external_documents_ids: external_documents_ids.into_static(),
             new_documents_ids,
             replaced_documents_ids,
+            unchanged_documents_count,
+            changed_fields,
             documents_count,
             documents_file,
         })
@@ -391,6 +440,7 @@ impl Transform<'_, '_> {
         new_fields_ids_map: FieldsIdsMap,
     ) -> Result<TransformOutput> {
         let field_distribution = self.index.field_distribution(self.rtxn)?;
+        let field_types = self.index.field_types(self.rtxn)?;
         let external_documents_ids = self.index.external_documents_ids(self.rtxn)?;
         let documents_ids = self.index.documents_ids(self.rtxn)?;
         let documents_count = documents_ids.len() as usize;
@@ -401,9 +451,12 @@ impl Transform<'_, '_> {
             create_writer(self.chunk_compression_type, self.chunk_compression_level, file)?;
 
         let mut obkv_buffer = Vec::new();
-        for result in self.index.documents.iter(self.rtxn)? {
-            let (docid, obkv) = result?;
+        let documents = self.index.documents.remap_data_type::<ByteSlice>();
+        for result in documents.iter(self.rtxn)? {
+            let (docid, bytes) = result?;
             let docid = docid.get();
+            let obkv = self.index.decrypt_document(bytes)?;
+            let obkv = obkv::KvReaderU16::new(&obkv);
 
             obkv_buffer.clear();
             let mut obkv_writer = obkv::KvWriter::<_, FieldId>::new(&mut obkv_buffer);
@@ -428,9 +481,13 @@ impl Transform<'_, '_> {
             primary_key,
             fields_ids_map: new_fields_ids_map,
             field_distribution,
+            field_types,
+            field_type_warnings: Vec::new(),
             external_documents_ids: external_documents_ids.into_static(),
             new_documents_ids: documents_ids,
             replaced_documents_ids: RoaringBitmap::default(),
+            unchanged_documents_count: 0,
+            changed_fields: FieldDistribution::new(),
             documents_count,
             documents_file,
         })
@@ -481,6 +538,26 @@ fn validate_document_id(document_id: &str) -> Option<&str> {
     })
 }
 
+/// Increments the count of the fields whose value differs between the two versions of a
+/// document, the fields that are only in one of them are counted too.
+fn count_changed_fields(
+    fields_ids_map: &FieldsIdsMap,
+    base: obkv::KvReaderU16,
+    update: obkv::KvReaderU16,
+    changed_fields: &mut FieldDistribution,
+) {
+    use itertools::EitherOrBoth::{Both, Left, Right};
+
+    for eob in base.iter().merge_join_by(update.iter(), |(b, _), (u, _)| b.cmp(u)) {
+        let field_id = match eob {
+            Both((_, base), (_, update)) if base == update => continue,
+            Both((field_id, _), _) | Left((field_id, _)) | Right((field_id, _)) => field_id,
+        };
+        let field_name = fields_ids_map.name(field_id).unwrap();
+        *changed_fields.entry(field_name.to_string()).or_default() += 1;
+    }
+}
+
 /// Drops all the value of type `U` in vec, and reuses the allocation to create a `Vec<T>`.
 ///
 /// The size and alignment of T and U must match.
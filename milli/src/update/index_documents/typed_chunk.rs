@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::convert::TryInto;
 use std::fs::File;
 
@@ -10,22 +11,34 @@ use super::helpers::{
     self, roaring_bitmap_from_u32s_array, serialize_roaring_bitmap, valid_lmdb_key,
     CursorClonableMmap,
 };
+use crate::error::SerializationError;
+use crate::fingerprint::{fingerprint_bands, SimHasher};
 use crate::heed_codec::facet::{decode_prefix_string, encode_prefix_string};
+use crate::index::db_name::DOCID_WORD_POSITIONS;
 use crate::update::index_documents::helpers::into_clonable_grenad;
-use crate::{BoRoaringBitmapCodec, CboRoaringBitmapCodec, DocumentId, GeoPoint, Index, Result};
+use crate::{
+    BoRoaringBitmapCodec, CboRoaringBitmapCodec, DocumentId, FieldId, GeoPoint, Index, Result,
+    BEU32, BEU64,
+};
 
 pub(crate) enum TypedChunk {
     DocidWordPositions(grenad::Reader<CursorClonableMmap>),
+    DocidWordCounts(grenad::Reader<CursorClonableMmap>),
     FieldIdDocidFacetStrings(grenad::Reader<CursorClonableMmap>),
     FieldIdDocidFacetNumbers(grenad::Reader<CursorClonableMmap>),
     Documents(grenad::Reader<CursorClonableMmap>),
     FieldIdWordcountDocids(grenad::Reader<File>),
     NewDocumentsIds(RoaringBitmap),
     WordDocids(grenad::Reader<File>),
+    ExactWordDocids(grenad::Reader<File>),
+    FieldIdExactValueDocids(grenad::Reader<File>),
     WordPositionDocids(grenad::Reader<File>),
     WordPairProximityDocids(grenad::Reader<File>),
     FieldIdFacetStringDocids(grenad::Reader<File>),
     FieldIdFacetNumberDocids(grenad::Reader<File>),
+    FieldIdFacetExistsDocids(grenad::Reader<File>),
+    FieldIdFacetIsNullDocids(grenad::Reader<File>),
+    FieldIdFacetIsEmptyDocids(grenad::Reader<File>),
     GeoPoints(grenad::Reader<File>),
 }
 
@@ -40,6 +53,10 @@ pub(crate) fn write_typed_chunk_into_index(
     let mut is_merged_database = false;
     match typed_chunk {
         TypedChunk::DocidWordPositions(docid_word_positions_iter) => {
+            if index.documents_fingerprinting(wtxn)? {
+                write_fingerprints_into_index(docid_word_positions_iter.clone(), index, wtxn)?;
+            }
+
             write_entries_into_database(
                 docid_word_positions_iter,
                 &index.docid_word_positions,
@@ -62,10 +79,21 @@ pub(crate) fn write_typed_chunk_into_index(
                 },
             )?;
         }
+        TypedChunk::DocidWordCounts(mut docid_word_counts_iter) => {
+            let db = index.docid_word_counts.remap_types::<ByteSlice, ByteSlice>();
+            while let Some((key, value)) = docid_word_counts_iter.next()? {
+                db.put(wtxn, key, value)?;
+            }
+        }
         TypedChunk::Documents(mut obkv_documents_iter) => {
+            let mut written_documents_ids = RoaringBitmap::new();
             while let Some((key, value)) = obkv_documents_iter.next()? {
-                index.documents.remap_types::<ByteSlice, ByteSlice>().put(wtxn, key, value)?;
+                let docid = key.try_into().map(DocumentId::from_be_bytes).unwrap();
+                written_documents_ids.insert(docid);
+                let value = index.encrypt_document(value);
+                index.documents.remap_types::<ByteSlice, ByteSlice>().put(wtxn, key, &value)?;
             }
+            index.record_written_documents_ids(&written_documents_ids);
         }
         TypedChunk::FieldIdWordcountDocids(fid_word_count_docids_iter) => {
             append_entries_into_database(
@@ -92,22 +120,51 @@ pub(crate) fn write_typed_chunk_into_index(
                 merge_roaring_bitmaps,
             )?;
 
-            // create fst from word docids
+            // create a delta fst with the words that are not in the database fst
+            let db_fst = index.words_fst(wtxn)?;
             let mut builder = fst::SetBuilder::memory();
+            let mut new_words = 0;
             while let Some((word, _value)) = word_docids_iter.next()? {
                 // This is a lexicographically ordered word position
                 // we use the key to construct the words fst.
-                builder.insert(word)?;
+                if !db_fst.contains(word) {
+                    builder.insert(word)?;
+                    new_words += 1;
+                }
             }
-            let fst = builder.into_set().map_data(std::borrow::Cow::Owned)?;
-            let db_fst = index.words_fst(wtxn)?;
 
-            // merge new fst with database fst
-            let union_stream = fst.op().add(db_fst.stream()).union();
-            let mut builder = fst::SetBuilder::memory();
-            builder.extend_stream(union_stream)?;
-            let fst = builder.into_set();
-            index.put_words_fst(wtxn, &fst)?;
+            // merge the delta fst with the database fst, small updates of large
+            // vocabularies often only contain known words and don't rewrite the fst.
+            if new_words != 0 {
+                let fst = builder.into_set();
+                let union_stream = fst.op().add(db_fst.stream()).union();
+                let mut builder = fst::SetBuilder::memory();
+                builder.extend_stream(union_stream)?;
+                let fst = builder.into_set();
+                index.put_words_fst(wtxn, &fst)?;
+            }
+            is_merged_database = true;
+        }
+        TypedChunk::ExactWordDocids(exact_word_docids_iter) => {
+            append_entries_into_database(
+                exact_word_docids_iter,
+                &index.exact_word_docids,
+                wtxn,
+                index_is_empty,
+                |value, _buffer| Ok(value),
+                merge_roaring_bitmaps,
+            )?;
+            is_merged_database = true;
+        }
+        TypedChunk::FieldIdExactValueDocids(field_id_exact_value_docids_iter) => {
+            append_entries_into_database(
+                field_id_exact_value_docids_iter,
+                &index.field_id_exact_value_docids,
+                wtxn,
+                index_is_empty,
+                |value, _buffer| Ok(value),
+                merge_roaring_bitmaps,
+            )?;
             is_merged_database = true;
         }
         TypedChunk::WordPositionDocids(word_position_docids_iter) => {
@@ -122,14 +179,16 @@ pub(crate) fn write_typed_chunk_into_index(
             is_merged_database = true;
         }
         TypedChunk::FieldIdFacetNumberDocids(facet_id_f64_docids_iter) => {
-            append_entries_into_database(
+            let mut facet_values_counts = index.facet_values_counts(wtxn)?;
+            write_facet_values_into_database(
                 facet_id_f64_docids_iter,
                 &index.facet_id_f64_docids,
                 wtxn,
                 index_is_empty,
-                |value, _buffer| Ok(value),
                 merge_cbo_roaring_bitmaps,
+                &mut facet_values_counts,
             )?;
+            index.put_facet_values_counts(wtxn, &facet_values_counts)?;
             is_merged_database = true;
         }
         TypedChunk::WordPairProximityDocids(word_pair_proximity_docids_iter) => {
@@ -162,12 +221,12 @@ pub(crate) fn write_typed_chunk_into_index(
             }
         }
         TypedChunk::FieldIdFacetStringDocids(facet_id_string_docids) => {
-            append_entries_into_database(
+            let mut facet_values_counts = index.facet_values_counts(wtxn)?;
+            write_facet_values_into_database(
                 facet_id_string_docids,
                 &index.facet_id_string_docids,
                 wtxn,
                 index_is_empty,
-                |value, _buffer| Ok(value),
                 |new_values, db_values, buffer| {
                     let (_, new_values) = decode_prefix_string(new_values).unwrap();
                     let new_values = RoaringBitmap::deserialize_from(new_values)?;
@@ -177,6 +236,41 @@ pub(crate) fn write_typed_chunk_into_index(
                     encode_prefix_string(db_original, buffer)?;
                     Ok(values.serialize_into(buffer)?)
                 },
+                &mut facet_values_counts,
+            )?;
+            index.put_facet_values_counts(wtxn, &facet_values_counts)?;
+            is_merged_database = true;
+        }
+        TypedChunk::FieldIdFacetExistsDocids(facet_id_exists_docids_iter) => {
+            append_entries_into_database(
+                facet_id_exists_docids_iter,
+                &index.facet_id_exists_docids,
+                wtxn,
+                index_is_empty,
+                |value, _buffer| Ok(value),
+                merge_cbo_roaring_bitmaps,
+            )?;
+            is_merged_database = true;
+        }
+        TypedChunk::FieldIdFacetIsNullDocids(facet_id_is_null_docids_iter) => {
+            append_entries_into_database(
+                facet_id_is_null_docids_iter,
+                &index.facet_id_is_null_docids,
+                wtxn,
+                index_is_empty,
+                |value, _buffer| Ok(value),
+                merge_cbo_roaring_bitmaps,
+            )?;
+            is_merged_database = true;
+        }
+        TypedChunk::FieldIdFacetIsEmptyDocids(facet_id_is_empty_docids_iter) => {
+            append_entries_into_database(
+                facet_id_is_empty_docids_iter,
+                &index.facet_id_is_empty_docids,
+                wtxn,
+                index_is_empty,
+                |value, _buffer| Ok(value),
+                merge_cbo_roaring_bitmaps,
             )?;
             is_merged_database = true;
         }
@@ -203,6 +297,73 @@ pub(crate) fn write_typed_chunk_into_index(
     Ok((RoaringBitmap::new(), is_merged_database))
 }
 
+/// Computes the fingerprint of the documents from their words and positions, the entries
+/// of a document are all in the same chunk and are ordered by document id.
+fn write_fingerprints_into_index(
+    mut docid_word_positions_iter: grenad::Reader<CursorClonableMmap>,
+    index: &Index,
+    wtxn: &mut RwTxn,
+) -> Result<()> {
+    let mut current: Option<(DocumentId, SimHasher)> = None;
+    while let Some((key, value)) = docid_word_positions_iter.next()? {
+        let (docid_bytes, word) = helpers::try_split_array_at(key)
+            .ok_or_else(|| SerializationError::Decoding { db_name: Some(DOCID_WORD_POSITIONS) })?;
+        let docid = DocumentId::from_be_bytes(docid_bytes);
+
+        if current.as_ref().map_or(true, |(current_docid, _)| *current_docid != docid) {
+            if let Some((docid, hasher)) = current.take() {
+                put_fingerprint(index, wtxn, docid, hasher.finish())?;
+            }
+            current = Some((docid, SimHasher::new()));
+        }
+
+        if let Some((_, hasher)) = current.as_mut() {
+            let weight = value.len() / std::mem::size_of::<u32>();
+            hasher.add(word, weight as u32);
+        }
+    }
+
+    if let Some((docid, hasher)) = current {
+        put_fingerprint(index, wtxn, docid, hasher.finish())?;
+    }
+
+    Ok(())
+}
+
+/// Writes the fingerprint of a document and adds the document to the bands of this
+/// fingerprint, the document is removed from the bands of its previous fingerprint.
+fn put_fingerprint(
+    index: &Index,
+    wtxn: &mut RwTxn,
+    docid: DocumentId,
+    fingerprint: u64,
+) -> Result<()> {
+    let key = BEU32::new(docid);
+    if let Some(previous) = index.docid_fingerprints.get(wtxn, &key)? {
+        for band in fingerprint_bands(previous.get()) {
+            let band = BEU32::new(band);
+            if let Some(mut docids) = index.fingerprint_band_docids.get(wtxn, &band)? {
+                docids.remove(docid);
+                if docids.is_empty() {
+                    index.fingerprint_band_docids.delete(wtxn, &band)?;
+                } else {
+                    index.fingerprint_band_docids.put(wtxn, &band, &docids)?;
+                }
+            }
+        }
+    }
+
+    index.docid_fingerprints.put(wtxn, &key, &BEU64::new(fingerprint))?;
+    for band in fingerprint_bands(fingerprint) {
+        let band = BEU32::new(band);
+        let mut docids = index.fingerprint_band_docids.get(wtxn, &band)?.unwrap_or_default();
+        docids.insert(docid);
+        index.fingerprint_band_docids.put(wtxn, &band, &docids)?;
+    }
+
+    Ok(())
+}
+
 fn merge_roaring_bitmaps(new_value: &[u8], db_value: &[u8], buffer: &mut Vec<u8>) -> Result<()> {
     let new_value = RoaringBitmap::deserialize_from(new_value)?;
     let db_value = RoaringBitmap::deserialize_from(db_value)?;
@@ -221,6 +382,48 @@ fn merge_cbo_roaring_bitmaps(
     )?)
 }
 
+/// Write the provided level zero facet entries in the database, merge_values function
+/// is used if an entry already exist in the database. The facet values that weren't
+/// in the database are counted as new distinct values of their field.
+fn write_facet_values_into_database<R, K, V, FM>(
+    mut data: grenad::Reader<R>,
+    database: &heed::Database<K, V>,
+    wtxn: &mut RwTxn,
+    index_is_empty: bool,
+    merge_values: FM,
+    facet_values_counts: &mut BTreeMap<FieldId, u64>,
+) -> Result<()>
+where
+    R: std::io::Read,
+    FM: Fn(&[u8], &[u8], &mut Vec<u8>) -> Result<()>,
+{
+    let mut buffer = Vec::new();
+    let database = database.remap_types::<ByteSlice, ByteSlice>();
+
+    while let Some((key, value)) = data.next()? {
+        if valid_lmdb_key(key) {
+            buffer.clear();
+            let prev_value = if index_is_empty { None } else { database.get(wtxn, key)? };
+            let value = match prev_value {
+                Some(prev_value) => {
+                    merge_values(value, prev_value, &mut buffer)?;
+                    &buffer[..]
+                }
+                None => {
+                    if let Some((field_id_bytes, _)) = helpers::try_split_array_at(key) {
+                        let field_id = FieldId::from_be_bytes(field_id_bytes);
+                        *facet_values_counts.entry(field_id).or_default() += 1;
+                    }
+                    value
+                }
+            };
+            database.put(wtxn, key, value)?;
+        }
+    }
+
+    Ok(())
+}
+
 /// Write provided entries in database using serialize_value function.
 /// merge_values function is used if an entry already exist in the database.
 fn write_entries_into_database<R, K, V, FS, FM>(
@@ -1,10 +1,19 @@
 pub use self::available_documents_ids::AvailableDocumentsIds;
 pub use self::clear_documents::ClearDocuments;
-pub use self::delete_documents::DeleteDocuments;
+pub use self::compact_documents::{CompactDocuments, DocumentsCompactionResult};
+pub use self::delete_documents::{DeleteDocuments, DeletionStatus, DocumentDeletionResult};
+pub(crate) use self::delete_words::DeleteWords;
 pub use self::facets::Facets;
-pub use self::index_documents::{DocumentAdditionResult, IndexDocuments, IndexDocumentsMethod};
+pub(crate) use self::index_documents::extract_document_exact_words;
+pub use self::index_documents::{
+    remove_checkpoint, DocumentAdditionResult, IndexDocuments, IndexDocumentsMethod, IndexingStep,
+    IndexingSteps,
+};
+pub use self::rename_facet_values::RenameFacetValues;
 pub use self::settings::{Setting, Settings};
+pub use self::settings_preset::{SettingsPreset, SettingsPresets};
 pub use self::update_builder::UpdateBuilder;
+pub use self::update_by_function::{DocumentEditionResult, UpdateByFunction};
 pub use self::update_step::UpdateIndexingStep;
 pub use self::word_prefix_docids::WordPrefixDocids;
 pub use self::word_prefix_pair_proximity_docids::WordPrefixPairProximityDocids;
@@ -13,11 +22,16 @@ pub use self::words_prefixes_fst::WordsPrefixesFst;
 
 mod available_documents_ids;
 mod clear_documents;
+mod compact_documents;
 mod delete_documents;
+mod delete_words;
 mod facets;
 mod index_documents;
+mod rename_facet_values;
 mod settings;
+mod settings_preset;
 mod update_builder;
+mod update_by_function;
 mod update_step;
 mod word_prefix_docids;
 mod word_prefix_pair_proximity_docids;
@@ -0,0 +1,203 @@
+use std::collections::{BTreeMap, HashMap};
+
+use grenad::CompressionType;
+use rayon::ThreadPool;
+use roaring::RoaringBitmap;
+use serde_json::Value;
+
+use super::{DocumentEditionResult, UpdateByFunction, UpdateIndexingStep};
+use crate::error::UserError;
+use crate::facet::facet_string_key;
+use crate::{Index, Result};
+
+/// Renames the string values of faceted fields in all the documents of the index,
+/// renaming several values to the same new value merges them.
+///
+/// The values are matched like the filters do, ignoring the case and the surrounding
+/// whitespaces. The edited documents are indexed again in the same transaction.
+pub struct RenameFacetValues<'a, 't, 'u, 'i> {
+    wtxn: &'t mut heed::RwTxn<'i, 'u>,
+    index: &'i Index,
+    pub(crate) log_every_n: Option<usize>,
+    pub(crate) max_nb_chunks: Option<usize>,
+    pub(crate) max_memory: Option<usize>,
+    pub(crate) documents_chunk_size: Option<usize>,
+    pub(crate) chunk_compression_type: CompressionType,
+    pub(crate) chunk_compression_level: Option<u32>,
+    pub(crate) thread_pool: Option<&'a ThreadPool>,
+    /// The new values of every field, indexed by the normalized previous values.
+    renames: BTreeMap<String, HashMap<String, String>>,
+    update_id: u64,
+}
+
+impl<'a, 't, 'u, 'i> RenameFacetValues<'a, 't, 'u, 'i> {
+    pub fn new(
+        wtxn: &'t mut heed::RwTxn<'i, 'u>,
+        index: &'i Index,
+        update_id: u64,
+    ) -> RenameFacetValues<'a, 't, 'u, 'i> {
+        RenameFacetValues {
+            wtxn,
+            index,
+            log_every_n: None,
+            max_nb_chunks: None,
+            max_memory: None,
+            documents_chunk_size: None,
+            chunk_compression_type: CompressionType::None,
+            chunk_compression_level: None,
+            thread_pool: None,
+            renames: BTreeMap::new(),
+            update_id,
+        }
+    }
+
+    pub fn log_every_n(&mut self, n: usize) {
+        self.log_every_n = Some(n);
+    }
+
+    /// Replaces the `from` value of the field by the `to` value.
+    pub fn rename(&mut self, field: impl Into<String>, from: &str, to: impl Into<String>) {
+        let renames = self.renames.entry(field.into()).or_default();
+        renames.insert(normalize(from), to.into());
+    }
+
+    #[logging_timer::time("RenameFacetValues::{}")]
+    pub fn execute<F>(self, progress_callback: F) -> Result<DocumentEditionResult>
+    where
+        F: Fn(UpdateIndexingStep, u64) + Sync,
+    {
+        let faceted_fields = self.index.faceted_fields(self.wtxn)?;
+        let fields_ids_map = self.index.fields_ids_map(self.wtxn)?;
+
+        // We only edit the documents that contain one of the renamed values.
+        let mut documents_ids = RoaringBitmap::new();
+        for (field, renames) in &self.renames {
+            if !faceted_fields.contains(field) {
+                return Err(UserError::InvalidFacetRenameAttribute {
+                    field: field.to_string(),
+                    valid_fields: faceted_fields,
+                })?;
+            }
+
+            let field_id = match fields_ids_map.id(field) {
+                Some(field_id) => field_id,
+                None => continue,
+            };
+
+            for normalized in renames.keys() {
+                let normalized = facet_string_key(normalized);
+                let key = (field_id, normalized.as_ref());
+                if let Some((_, docids)) = self.index.facet_id_string_docids.get(self.wtxn, &key)? {
+                    documents_ids |= docids;
+                }
+            }
+        }
+
+        let mut builder = UpdateByFunction::new(self.wtxn, self.index, self.update_id);
+        builder.log_every_n = self.log_every_n;
+        builder.max_nb_chunks = self.max_nb_chunks;
+        builder.max_memory = self.max_memory;
+        builder.documents_chunk_size = self.documents_chunk_size;
+        builder.chunk_compression_type = self.chunk_compression_type;
+        builder.chunk_compression_level = self.chunk_compression_level;
+        builder.thread_pool = self.thread_pool;
+        builder.documents_ids(documents_ids);
+
+        let renames = self.renames;
+        builder.execute(
+            |mut document| {
+                let mut edited = false;
+                for (field, renames) in &renames {
+                    if let Some(value) = document.get_mut(field) {
+                        edited |= rename_value(value, renames);
+                    }
+                }
+                Ok(if edited { Some(document) } else { None })
+            },
+            progress_callback,
+        )
+    }
+}
+
+fn normalize(value: &str) -> String {
+    value.trim().to_lowercase()
+}
+
+/// Renames the string value or the strings of the array, the values nested
+/// deeper are not faceted and are left untouched. Returns `true` if renamed.
+fn rename_value(value: &mut Value, renames: &HashMap<String, String>) -> bool {
+    fn rename_string(value: &mut Value, renames: &HashMap<String, String>) -> bool {
+        match value {
+            Value::String(string) => match renames.get(&normalize(string)) {
+                Some(new_value) if new_value != string => {
+                    *string = new_value.clone();
+                    true
+                }
+                _otherwise => false,
+            },
+            _otherwise => false,
+        }
+    }
+
+    match value {
+        Value::Array(values) => {
+            let mut renamed = false;
+            for value in values.iter_mut() {
+                renamed |= rename_string(value, renames);
+            }
+            renamed
+        }
+        value => rename_string(value, renames),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::update::{IndexDocuments, Settings};
+    use crate::FilterCondition;
+
+    #[test]
+    fn rename_and_merge_values() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("country") });
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": 0, "country": "USA" },
+            { "id": 1, "country": "United States" },
+            { "id": 2, "country": ["usa ", "France"] },
+            { "id": 3, "country": "France" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = RenameFacetValues::new(&mut wtxn, &index, 2);
+        builder.rename("country", "USA", "United States");
+        let result = builder.execute(|_, _| ()).unwrap();
+        assert_eq!(result.nb_documents, 2);
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let count = |filter: &str| {
+            let condition = FilterCondition::from_str(&rtxn, &index, filter).unwrap();
+            condition.evaluate(&rtxn, &index).unwrap().len()
+        };
+        assert_eq!(count("country = \"United States\""), 3);
+        assert_eq!(count("country = France"), 2);
+        assert_eq!(count("country = USA"), 0);
+        drop(rtxn);
+
+        // Only the faceted fields can be renamed.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = RenameFacetValues::new(&mut wtxn, &index, 3);
+        builder.rename("id", "0", "1");
+        assert!(builder.execute(|_, _| ()).is_err());
+    }
+}
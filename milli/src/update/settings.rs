@@ -1,4 +1,4 @@
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::result::Result as StdResult;
 
 use chrono::Utc;
@@ -8,11 +8,19 @@ use meilisearch_tokenizer::{Analyzer, AnalyzerConfig};
 use rayon::ThreadPool;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::criterion::Criterion;
+use crate::criterion::{Criterion, CriterionWarning};
 use crate::error::UserError;
+use crate::index::{DEFAULT_MIN_WORD_SIZE_FOR_ONE_TYPO, DEFAULT_MIN_WORD_SIZE_FOR_TWO_TYPOS};
+use crate::proximity::MAX_ONE_ATTRIBUTE;
 use crate::update::index_documents::{IndexDocumentsMethod, Transform};
-use crate::update::{ClearDocuments, IndexDocuments, UpdateIndexingStep};
-use crate::{FieldsIdsMap, Index, Result};
+use crate::update::{
+    ClearDocuments, DeleteWords, Facets, IndexDocuments, SettingsPreset, UpdateIndexingStep,
+    WordPrefixPairProximityDocids,
+};
+use crate::{
+    normalize_synonym, normalize_synonyms, AscDesc, FieldsIdsMap, FilterCapabilities,
+    FirstLetterTypo, Index, MaskingMethod, Result, SortError, StopWordsMode, TypoDistance,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Setting<T> {
@@ -74,11 +82,31 @@ pub struct Settings<'a, 't, 'u, 'i> {
     searchable_fields: Setting<Vec<String>>,
     displayed_fields: Setting<Vec<String>>,
     filterable_fields: Setting<HashSet<String>>,
+    filter_capabilities: Setting<HashMap<String, FilterCapabilities>>,
     sortable_fields: Setting<HashSet<String>>,
+    default_sort: Setting<Vec<String>>,
     criteria: Setting<Vec<String>>,
     stop_words: Setting<BTreeSet<String>>,
+    stop_words_mode: Setting<StopWordsMode>,
     distinct_field: Setting<String>,
+    parent_field: Setting<String>,
+    position_stride: Setting<u32>,
+    proximity_approximation_threshold: Setting<u64>,
+    proximity_excluded_fields: Setting<HashSet<String>>,
+    prefix_pair_proximity: Setting<bool>,
+    exact_attributes: Setting<HashSet<String>>,
+    max_values_per_facet: Setting<usize>,
+    max_attributes_to_rank: Setting<usize>,
+    min_word_size_for_one_typo: Setting<u8>,
+    min_word_size_for_two_typos: Setting<u8>,
+    typo_disabled_on_words: Setting<BTreeSet<String>>,
+    attributes_max_typos: Setting<BTreeMap<String, u8>>,
+    first_letter_typo: Setting<FirstLetterTypo>,
+    typo_distance: Setting<TypoDistance>,
+    masked_fields: Setting<BTreeMap<String, MaskingMethod>>,
+    documents_fingerprinting: Setting<bool>,
     synonyms: Setting<HashMap<String, Vec<String>>>,
+    indexed_synonyms: Setting<HashSet<String>>,
     primary_key: Setting<String>,
 }
 
@@ -101,11 +129,31 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
             searchable_fields: Setting::NotSet,
             displayed_fields: Setting::NotSet,
             filterable_fields: Setting::NotSet,
+            filter_capabilities: Setting::NotSet,
             sortable_fields: Setting::NotSet,
+            default_sort: Setting::NotSet,
             criteria: Setting::NotSet,
             stop_words: Setting::NotSet,
+            stop_words_mode: Setting::NotSet,
             distinct_field: Setting::NotSet,
+            parent_field: Setting::NotSet,
+            position_stride: Setting::NotSet,
+            proximity_approximation_threshold: Setting::NotSet,
+            proximity_excluded_fields: Setting::NotSet,
+            prefix_pair_proximity: Setting::NotSet,
+            exact_attributes: Setting::NotSet,
+            max_values_per_facet: Setting::NotSet,
+            max_attributes_to_rank: Setting::NotSet,
+            min_word_size_for_one_typo: Setting::NotSet,
+            min_word_size_for_two_typos: Setting::NotSet,
+            typo_disabled_on_words: Setting::NotSet,
+            attributes_max_typos: Setting::NotSet,
+            first_letter_typo: Setting::NotSet,
+            typo_distance: Setting::NotSet,
+            masked_fields: Setting::NotSet,
+            documents_fingerprinting: Setting::NotSet,
             synonyms: Setting::NotSet,
+            indexed_synonyms: Setting::NotSet,
             primary_key: Setting::NotSet,
             update_id,
         }
@@ -139,6 +187,17 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         self.filterable_fields = Setting::Set(names);
     }
 
+    pub fn reset_filter_capabilities(&mut self) {
+        self.filter_capabilities = Setting::Reset;
+    }
+
+    /// Sets the kinds of filters that can be used on the filterable fields, the fields
+    /// that are not given support all the filters. The facet levels are not computed for
+    /// the fields that can't be compared, unless they are sortable.
+    pub fn set_filter_capabilities(&mut self, capabilities: HashMap<String, FilterCapabilities>) {
+        self.filter_capabilities = Setting::Set(capabilities);
+    }
+
     pub fn set_sortable_fields(&mut self, names: HashSet<String>) {
         self.sortable_fields = Setting::Set(names);
     }
@@ -147,6 +206,17 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         self.sortable_fields = Setting::Reset;
     }
 
+    /// Sorts the documents of the searches that have neither a query nor a sort
+    /// (e.g. `price:asc`), the documents are browsed in this order instead of the
+    /// order of their internal ids.
+    pub fn set_default_sort(&mut self, sort: Vec<String>) {
+        self.default_sort = Setting::Set(sort);
+    }
+
+    pub fn reset_default_sort(&mut self) {
+        self.default_sort = Setting::Reset;
+    }
+
     pub fn reset_criteria(&mut self) {
         self.criteria = Setting::Reset;
     }
@@ -164,6 +234,15 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
             if stop_words.is_empty() { Setting::Reset } else { Setting::Set(stop_words) }
     }
 
+    pub fn reset_stop_words_mode(&mut self) {
+        self.stop_words_mode = Setting::Reset;
+    }
+
+    /// Defines whether the stop words are removed from the documents, the queries or both.
+    pub fn set_stop_words_mode(&mut self, mode: StopWordsMode) {
+        self.stop_words_mode = Setting::Set(mode);
+    }
+
     pub fn reset_distinct_field(&mut self) {
         self.distinct_field = Setting::Reset;
     }
@@ -172,6 +251,163 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         self.distinct_field = Setting::Set(distinct_field);
     }
 
+    pub fn reset_parent_field(&mut self) {
+        self.parent_field = Setting::Reset;
+    }
+
+    pub fn set_parent_field(&mut self, parent_field: String) {
+        self.parent_field = Setting::Set(parent_field);
+    }
+
+    pub fn reset_position_stride(&mut self) {
+        self.position_stride = Setting::Reset;
+    }
+
+    /// Sets the number of positions reserved for every attribute, the words
+    /// that are after this number of words in an attribute are not indexed.
+    pub fn set_position_stride(&mut self, stride: u32) {
+        self.position_stride = Setting::Set(stride);
+    }
+
+    pub fn reset_proximity_approximation_threshold(&mut self) {
+        self.proximity_approximation_threshold = Setting::Reset;
+    }
+
+    pub fn set_proximity_approximation_threshold(&mut self, threshold: u64) {
+        self.proximity_approximation_threshold = Setting::Set(threshold);
+    }
+
+    pub fn reset_proximity_excluded_fields(&mut self) {
+        self.proximity_excluded_fields = Setting::Reset;
+    }
+
+    /// Sets the fields whose word pairs proximities are not indexed, like the huge text fields
+    /// that dominate the size of the proximity database. They are still searchable but their
+    /// words are ranked as if they were far from each other.
+    pub fn set_proximity_excluded_fields(&mut self, names: HashSet<String>) {
+        self.proximity_excluded_fields =
+            if names.is_empty() { Setting::Reset } else { Setting::Set(names) }
+    }
+
+    pub fn reset_exact_attributes(&mut self) {
+        self.exact_attributes = Setting::Reset;
+    }
+
+    /// Sets the searchable fields whose whole value is indexed, the exactness criterion
+    /// ranks the documents whose one of these fields is exactly the query first.
+    pub fn set_exact_attributes(&mut self, names: HashSet<String>) {
+        self.exact_attributes = if names.is_empty() { Setting::Reset } else { Setting::Set(names) }
+    }
+
+    pub fn reset_max_values_per_facet(&mut self) {
+        self.max_values_per_facet = Setting::Reset;
+    }
+
+    /// Sets the maximum number of values returned for every facet by the facet distribution.
+    pub fn set_max_values_per_facet(&mut self, max: usize) {
+        self.max_values_per_facet = Setting::Set(max);
+    }
+
+    pub fn reset_max_attributes_to_rank(&mut self) {
+        self.max_attributes_to_rank = Setting::Reset;
+    }
+
+    /// Sets the number of searchable attributes, in their ranking order, whose positions are
+    /// used by the attribute criterion, the matches in the other attributes are ranked last.
+    pub fn set_max_attributes_to_rank(&mut self, max: usize) {
+        self.max_attributes_to_rank = Setting::Set(max);
+    }
+
+    pub fn reset_min_word_size_for_one_typo(&mut self) {
+        self.min_word_size_for_one_typo = Setting::Reset;
+    }
+
+    /// Sets the minimum number of characters of a query word to allow one typo on it.
+    pub fn set_min_word_size_for_one_typo(&mut self, size: u8) {
+        self.min_word_size_for_one_typo = Setting::Set(size);
+    }
+
+    pub fn reset_min_word_size_for_two_typos(&mut self) {
+        self.min_word_size_for_two_typos = Setting::Reset;
+    }
+
+    /// Sets the minimum number of characters of a query word to allow two typos on it.
+    pub fn set_min_word_size_for_two_typos(&mut self, size: u8) {
+        self.min_word_size_for_two_typos = Setting::Set(size);
+    }
+
+    pub fn reset_typo_disabled_on_words(&mut self) {
+        self.typo_disabled_on_words = Setting::Reset;
+    }
+
+    /// Sets the words of the queries that only match exactly, like the product names,
+    /// they are normalized like the words of the queries.
+    pub fn set_typo_disabled_on_words(&mut self, words: BTreeSet<String>) {
+        self.typo_disabled_on_words =
+            if words.is_empty() { Setting::Reset } else { Setting::Set(words) }
+    }
+
+    pub fn reset_attributes_max_typos(&mut self) {
+        self.attributes_max_typos = Setting::Reset;
+    }
+
+    /// Overrides the maximum number of typos allowed on the words found in the given fields,
+    /// zero disables the typos in a field. The number of typos allowed by the length of the
+    /// query words can only be lowered by an override.
+    pub fn set_attributes_max_typos(&mut self, max_typos: BTreeMap<String, u8>) {
+        self.attributes_max_typos =
+            if max_typos.is_empty() { Setting::Reset } else { Setting::Set(max_typos) }
+    }
+
+    pub fn reset_first_letter_typo(&mut self) {
+        self.first_letter_typo = Setting::Reset;
+    }
+
+    /// Sets how the typos on the first letter of the query words are counted.
+    pub fn set_first_letter_typo(&mut self, first_letter_typo: FirstLetterTypo) {
+        self.first_letter_typo = Setting::Set(first_letter_typo);
+    }
+
+    pub fn reset_typo_distance(&mut self) {
+        self.typo_distance = Setting::Reset;
+    }
+
+    /// Sets the edit distance used to count the typos, the plain Levenshtein distance
+    /// counts a transposition of two letters as two typos.
+    pub fn set_typo_distance(&mut self, typo_distance: TypoDistance) {
+        self.typo_distance = Setting::Set(typo_distance);
+    }
+
+    pub fn reset_masked_fields(&mut self) {
+        self.masked_fields = Setting::Reset;
+    }
+
+    /// Sets the fields whose values are masked by the document retrieval APIs
+    /// unless the caller asks for the unmasked documents.
+    pub fn set_masked_fields(&mut self, fields: BTreeMap<String, MaskingMethod>) {
+        self.masked_fields = if fields.is_empty() { Setting::Reset } else { Setting::Set(fields) }
+    }
+
+    pub fn reset_prefix_pair_proximity(&mut self) {
+        self.prefix_pair_proximity = Setting::Reset;
+    }
+
+    /// Disabling the word prefix pair proximity database makes the indexing faster, the
+    /// proximity of the prefix queries is then computed from the words of the prefixes.
+    pub fn set_prefix_pair_proximity(&mut self, enabled: bool) {
+        self.prefix_pair_proximity = Setting::Set(enabled);
+    }
+
+    pub fn reset_documents_fingerprinting(&mut self) {
+        self.documents_fingerprinting = Setting::Reset;
+    }
+
+    /// Computes a fingerprint of the words of every document during indexing,
+    /// it allows finding the near-duplicate documents of the index.
+    pub fn set_documents_fingerprinting(&mut self, enabled: bool) {
+        self.documents_fingerprinting = Setting::Set(enabled);
+    }
+
     pub fn reset_synonyms(&mut self) {
         self.synonyms = Setting::Reset;
     }
@@ -180,6 +416,18 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         self.synonyms = if synonyms.is_empty() { Setting::Reset } else { Setting::Set(synonyms) }
     }
 
+    pub fn reset_indexed_synonyms(&mut self) {
+        self.indexed_synonyms = Setting::Reset;
+    }
+
+    /// Sets the words of the synonyms whose synonyms are added to the documents at indexing
+    /// time instead of being added to the queries, it makes the index bigger but the queries
+    /// that contain these words faster. The documents that contain one of the synonyms
+    /// of these words are indexed as if they also contained the words.
+    pub fn set_indexed_synonyms(&mut self, words: HashSet<String>) {
+        self.indexed_synonyms = if words.is_empty() { Setting::Reset } else { Setting::Set(words) }
+    }
+
     pub fn reset_primary_key(&mut self) {
         self.primary_key = Setting::Reset;
     }
@@ -188,6 +436,30 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         self.primary_key = Setting::Set(primary_key);
     }
 
+    /// Applies the settings that are set or reset by the preset,
+    /// replacing the ones that were previously given to this builder.
+    pub fn apply_preset(&mut self, preset: &SettingsPreset) {
+        let SettingsPreset {
+            criteria,
+            stop_words,
+            proximity_approximation_threshold,
+            max_values_per_facet,
+        } = preset;
+
+        if !criteria.is_not_set() {
+            self.criteria = criteria.clone();
+        }
+        if !stop_words.is_not_set() {
+            self.stop_words = stop_words.clone();
+        }
+        if !proximity_approximation_threshold.is_not_set() {
+            self.proximity_approximation_threshold = proximity_approximation_threshold.clone();
+        }
+        if !max_values_per_facet.is_not_set() {
+            self.max_values_per_facet = max_values_per_facet.clone();
+        }
+    }
+
     fn reindex<F>(&mut self, cb: &F, old_fields_ids_map: FieldsIdsMap) -> Result<()>
     where
         F: Fn(UpdateIndexingStep, u64) + Sync,
@@ -211,6 +483,7 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
             max_memory: self.max_memory,
             index_documents_method: IndexDocumentsMethod::ReplaceDocuments,
             autogenerate_docids: false,
+            documents_ids_range: 0..=u32::max_value(),
         };
 
         // There already has been a document addition, the primary key should be set by now.
@@ -270,18 +543,274 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         Ok(true)
     }
 
+    fn update_parent_field(&mut self) -> Result<bool> {
+        match self.parent_field {
+            Setting::Set(ref attr) => {
+                self.index.put_parent_field(self.wtxn, &attr)?;
+            }
+            Setting::Reset => {
+                self.index.delete_parent_field(self.wtxn)?;
+            }
+            Setting::NotSet => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn update_proximity_approximation_threshold(&mut self) -> Result<()> {
+        match self.proximity_approximation_threshold {
+            Setting::Set(threshold) => {
+                self.index.put_proximity_approximation_threshold(self.wtxn, threshold)?;
+            }
+            Setting::Reset => {
+                self.index.delete_proximity_approximation_threshold(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+        Ok(())
+    }
+
+    fn update_proximity_excluded_fields(&mut self) -> Result<bool> {
+        let old_fields = self.index.proximity_excluded_fields(self.wtxn)?;
+        match self.proximity_excluded_fields {
+            Setting::Set(ref fields) => {
+                self.index.put_proximity_excluded_fields(self.wtxn, fields)?;
+            }
+            Setting::Reset => {
+                self.index.delete_proximity_excluded_fields(self.wtxn)?;
+            }
+            Setting::NotSet => return Ok(false),
+        }
+        Ok(old_fields != self.index.proximity_excluded_fields(self.wtxn)?)
+    }
+
+    fn update_exact_attributes(&mut self) -> Result<bool> {
+        let old_fields = self.index.exact_attributes(self.wtxn)?;
+        match self.exact_attributes {
+            Setting::Set(ref fields) => {
+                self.index.put_exact_attributes(self.wtxn, fields)?;
+            }
+            Setting::Reset => {
+                self.index.delete_exact_attributes(self.wtxn)?;
+            }
+            Setting::NotSet => return Ok(false),
+        }
+        Ok(old_fields != self.index.exact_attributes(self.wtxn)?)
+    }
+
+    fn update_max_values_per_facet(&mut self) -> Result<()> {
+        match self.max_values_per_facet {
+            Setting::Set(max) => {
+                self.index.put_max_values_per_facet(self.wtxn, max)?;
+            }
+            Setting::Reset => {
+                self.index.delete_max_values_per_facet(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+        Ok(())
+    }
+
+    fn update_max_attributes_to_rank(&mut self) -> Result<()> {
+        match self.max_attributes_to_rank {
+            Setting::Set(0) => return Err(UserError::InvalidMaxAttributesToRank.into()),
+            Setting::Set(max) => {
+                self.index.put_max_attributes_to_rank(self.wtxn, max)?;
+            }
+            Setting::Reset => {
+                self.index.delete_max_attributes_to_rank(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+        Ok(())
+    }
+
+    fn update_min_word_sizes_for_typos(&mut self) -> Result<()> {
+        let one_typo = match self.min_word_size_for_one_typo {
+            Setting::Set(size) => size,
+            Setting::Reset => DEFAULT_MIN_WORD_SIZE_FOR_ONE_TYPO,
+            Setting::NotSet => self.index.min_word_size_for_one_typo(self.wtxn)?,
+        };
+        let two_typos = match self.min_word_size_for_two_typos {
+            Setting::Set(size) => size,
+            Setting::Reset => DEFAULT_MIN_WORD_SIZE_FOR_TWO_TYPOS,
+            Setting::NotSet => self.index.min_word_size_for_two_typos(self.wtxn)?,
+        };
+        if one_typo > two_typos {
+            return Err(UserError::InvalidMinWordSizeForTypos { one_typo, two_typos }.into());
+        }
+
+        match self.min_word_size_for_one_typo {
+            Setting::Set(size) => self.index.put_min_word_size_for_one_typo(self.wtxn, size)?,
+            Setting::Reset => {
+                self.index.delete_min_word_size_for_one_typo(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+        match self.min_word_size_for_two_typos {
+            Setting::Set(size) => self.index.put_min_word_size_for_two_typos(self.wtxn, size)?,
+            Setting::Reset => {
+                self.index.delete_min_word_size_for_two_typos(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+        Ok(())
+    }
+
+    fn update_typo_disabled_on_words(&mut self) -> Result<()> {
+        match self.typo_disabled_on_words {
+            Setting::Set(ref words) => {
+                let analyzer = Analyzer::new(AnalyzerConfig::<Vec<u8>>::default());
+                let words: BTreeSet<_> =
+                    words.iter().flat_map(|word| normalize_synonym(&analyzer, word)).collect();
+                let fst = fst::Set::from_iter(words)?;
+                self.index.put_typo_disabled_on_words(self.wtxn, &fst)?;
+            }
+            Setting::Reset => {
+                self.index.delete_typo_disabled_on_words(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+        Ok(())
+    }
+
+    fn update_attributes_max_typos(&mut self) -> Result<()> {
+        match self.attributes_max_typos {
+            Setting::Set(ref max_typos) => {
+                self.index.put_attributes_max_typos(self.wtxn, max_typos)?;
+            }
+            Setting::Reset => {
+                self.index.delete_attributes_max_typos(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+        Ok(())
+    }
+
+    fn update_first_letter_typo(&mut self) -> Result<()> {
+        match self.first_letter_typo {
+            Setting::Set(first_letter_typo) => {
+                self.index.put_first_letter_typo(self.wtxn, first_letter_typo)?;
+            }
+            Setting::Reset => {
+                self.index.delete_first_letter_typo(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+        Ok(())
+    }
+
+    fn update_typo_distance(&mut self) -> Result<()> {
+        match self.typo_distance {
+            Setting::Set(typo_distance) => {
+                self.index.put_typo_distance(self.wtxn, typo_distance)?;
+            }
+            Setting::Reset => {
+                self.index.delete_typo_distance(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+        Ok(())
+    }
+
+    fn update_masked_fields(&mut self) -> Result<()> {
+        match self.masked_fields {
+            Setting::Set(ref fields) => {
+                self.index.ensure_masking_secret(self.wtxn)?;
+                self.index.put_masked_fields(self.wtxn, fields)?;
+            }
+            Setting::Reset => {
+                self.index.delete_masked_fields(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+        Ok(())
+    }
+
+    /// Updates the position stride and returns `true` if it has been modified,
+    /// the positions of the words must then be computed again.
+    fn update_position_stride(&mut self) -> Result<bool> {
+        let old_stride = self.index.position_stride(self.wtxn)?;
+        match self.position_stride {
+            Setting::Set(stride) => {
+                if stride == 0 || stride > MAX_ONE_ATTRIBUTE {
+                    return Err(UserError::InvalidPositionStride { stride }.into());
+                }
+                self.index.put_position_stride(self.wtxn, stride)?;
+            }
+            Setting::Reset => {
+                self.index.delete_position_stride(self.wtxn)?;
+            }
+            Setting::NotSet => return Ok(false),
+        }
+        Ok(old_stride != self.index.position_stride(self.wtxn)?)
+    }
+
+    /// The database is cleared when it is disabled and computed again from the word pair
+    /// proximity database when it is enabled, the documents don't need to be reindexed.
+    fn update_prefix_pair_proximity(&mut self) -> Result<()> {
+        let old_enabled = self.index.prefix_pair_proximity(self.wtxn)?;
+        match self.prefix_pair_proximity {
+            Setting::Set(false) => self.index.put_prefix_pair_proximity_disabled(self.wtxn)?,
+            Setting::Set(true) | Setting::Reset => {
+                self.index.delete_prefix_pair_proximity_disabled(self.wtxn)?;
+            }
+            Setting::NotSet => return Ok(()),
+        }
+
+        let enabled = self.index.prefix_pair_proximity(self.wtxn)?;
+        if old_enabled && !enabled {
+            self.index.word_prefix_pair_proximity_docids.clear(self.wtxn)?;
+        } else if !old_enabled && enabled && cfg!(all(feature = "prefix-db", feature = "proximity"))
+        {
+            let mut builder = WordPrefixPairProximityDocids::new(self.wtxn, self.index);
+            builder.chunk_compression_type = self.chunk_compression_type;
+            builder.chunk_compression_level = self.chunk_compression_level;
+            builder.max_nb_chunks = self.max_nb_chunks;
+            builder.max_memory = self.max_memory;
+            builder.execute()?;
+        }
+        Ok(())
+    }
+
+    fn update_documents_fingerprinting(&mut self) -> Result<bool> {
+        let old_enabled = self.index.documents_fingerprinting(self.wtxn)?;
+        match self.documents_fingerprinting {
+            Setting::Set(true) => self.index.put_documents_fingerprinting(self.wtxn)?,
+            Setting::Set(false) | Setting::Reset => {
+                self.index.delete_documents_fingerprinting(self.wtxn)?;
+            }
+            Setting::NotSet => return Ok(false),
+        }
+        Ok(old_enabled != self.index.documents_fingerprinting(self.wtxn)?)
+    }
+
     /// Updates the index's searchable attributes. This causes the field map to be recomputed to
     /// reflect the order of the searchable attributes.
+    ///
+    /// When the new searchable attributes are only a reordering of the previous ones the
+    /// field map is kept as is, the search remaps the positions using the attributes ranks,
+    /// and no reindexing is required.
     fn update_searchable(&mut self) -> Result<bool> {
         match self.searchable_fields {
             Setting::Set(ref fields) => {
+                // fields are deduplicated, only the first occurrence is taken into account
+                let names = fields.iter().unique().map(String::as_str).collect::<Vec<_>>();
+
+                if let Some(old_names) = self.index.searchable_fields(self.wtxn)? {
+                    let old_names: HashSet<_> = old_names.into_iter().map(String::from).collect();
+                    if old_names.len() == names.len()
+                        && names.iter().all(|name| old_names.contains(*name))
+                    {
+                        self.index.put_searchable_fields(self.wtxn, &names)?;
+                        return Ok(false);
+                    }
+                }
+
                 // every time the searchable attributes are updated, we need to update the
                 // ids for any settings that uses the facets. (distinct_fields, filterable_fields).
                 let old_fields_ids_map = self.index.fields_ids_map(self.wtxn)?;
 
                 let mut new_fields_ids_map = FieldsIdsMap::new();
-                // fields are deduplicated, only the first occurrence is taken into account
-                let names = fields.iter().unique().map(String::as_str).collect::<Vec<_>>();
 
                 // Add all the searchable attributes to the field map, and then add the
                 // remaining fields from the old field map to the new one
@@ -304,7 +833,46 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         Ok(true)
     }
 
+    fn update_stop_words_mode(&mut self) -> Result<bool> {
+        let old_mode = self.index.stop_words_mode(self.wtxn)?;
+        match self.stop_words_mode {
+            Setting::Set(mode) => self.index.put_stop_words_mode(self.wtxn, mode)?,
+            Setting::Reset => drop(self.index.delete_stop_words_mode(self.wtxn)?),
+            Setting::NotSet => return Ok(false),
+        }
+        let new_mode = self.index.stop_words_mode(self.wtxn)?;
+
+        // The documents must only be tokenized again when the
+        // stop words start or stop being removed from them.
+        let has_stop_words = self.index.stop_words(self.wtxn)?.is_some();
+        Ok(has_stop_words && old_mode.removes_from_documents() != new_mode.removes_from_documents())
+    }
+
+    /// The documents were tokenized without the stop words of the index before the extraction
+    /// was fixed, the stop words of these indexes are removed from the word databases once,
+    /// by the first settings update, the indexes created since are marked as migrated.
+    fn remove_indexed_stop_words(&mut self) -> Result<()> {
+        if self.index.stop_words_migrated(self.wtxn)? {
+            return Ok(());
+        }
+
+        if let Some(stop_words) = self.index.documents_stop_words(self.wtxn)? {
+            let stop_words = stop_words.map_data(|bytes| bytes.to_vec())?;
+            let mut builder = DeleteWords::new(self.wtxn, self.index, self.update_id);
+            builder.chunk_compression_type = self.chunk_compression_type;
+            builder.chunk_compression_level = self.chunk_compression_level;
+            builder.max_nb_chunks = self.max_nb_chunks;
+            builder.max_memory = self.max_memory;
+            builder.execute(&stop_words)?;
+        }
+        self.index.put_stop_words_migrated(self.wtxn)?;
+        Ok(())
+    }
+
     fn update_stop_words(&mut self) -> Result<bool> {
+        // When the stop words are kept in the documents there is nothing to reindex.
+        let removes_from_documents =
+            self.index.stop_words_mode(self.wtxn)?.removes_from_documents();
         match self.stop_words {
             Setting::Set(ref stop_words) => {
                 let current = self.index.stop_words(self.wtxn)?;
@@ -313,17 +881,39 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
                 let fst = fst::Set::from_iter(stop_words)?;
 
                 // Does the new FST differ from the previous one?
-                if current
-                    .map_or(true, |current| current.as_fst().as_bytes() != fst.as_fst().as_bytes())
-                {
+                let differ = current
+                    .as_ref()
+                    .map_or(true, |current| current.as_fst().as_bytes() != fst.as_fst().as_bytes());
+                // Are the previous stop words all part of the new ones?
+                let only_additions =
+                    current.as_ref().map_or(true, |current| current.is_subset(fst.stream()));
+
+                if !differ {
+                    Ok(false)
+                } else if !removes_from_documents {
+                    self.index.put_stop_words(self.wtxn, &fst)?;
+                    Ok(false)
+                } else if only_additions {
                     // we want to re-create our FST.
                     self.index.put_stop_words(self.wtxn, &fst)?;
-                    Ok(true)
-                } else {
+                    // When stop words are only added we don't need to tokenize the documents
+                    // again, the new stop words can be removed from the word databases directly.
+                    let mut builder = DeleteWords::new(self.wtxn, self.index, self.update_id);
+                    builder.chunk_compression_type = self.chunk_compression_type;
+                    builder.chunk_compression_level = self.chunk_compression_level;
+                    builder.max_nb_chunks = self.max_nb_chunks;
+                    builder.max_memory = self.max_memory;
+                    builder.execute(&fst)?;
                     Ok(false)
+                } else {
+                    // we want to re-create our FST.
+                    self.index.put_stop_words(self.wtxn, &fst)?;
+                    Ok(true)
                 }
             }
-            Setting::Reset => Ok(self.index.delete_stop_words(self.wtxn)?),
+            Setting::Reset => {
+                Ok(self.index.delete_stop_words(self.wtxn)? && removes_from_documents)
+            }
             Setting::NotSet => Ok(false),
         }
     }
@@ -331,45 +921,13 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
     fn update_synonyms(&mut self) -> Result<bool> {
         match self.synonyms {
             Setting::Set(ref synonyms) => {
-                fn normalize(analyzer: &Analyzer<&[u8]>, text: &str) -> Vec<String> {
-                    analyzer
-                        .analyze(text)
-                        .tokens()
-                        .filter_map(|token| {
-                            if token.is_word() {
-                                Some(token.text().to_string())
-                            } else {
-                                None
-                            }
-                        })
-                        .collect::<Vec<_>>()
-                }
-
                 let mut config = AnalyzerConfig::default();
-                let stop_words = self.index.stop_words(self.wtxn)?;
+                let stop_words = self.index.query_stop_words(self.wtxn)?;
                 if let Some(stop_words) = &stop_words {
                     config.stop_words(stop_words);
                 }
                 let analyzer = Analyzer::new(config);
-
-                let mut new_synonyms = HashMap::new();
-                for (word, synonyms) in synonyms {
-                    // Normalize both the word and associated synonyms.
-                    let normalized_word = normalize(&analyzer, word);
-                    let normalized_synonyms =
-                        synonyms.iter().map(|synonym| normalize(&analyzer, synonym));
-
-                    // Store the normalized synonyms under the normalized word,
-                    // merging the possible duplicate words.
-                    let entry = new_synonyms.entry(normalized_word).or_insert_with(Vec::new);
-                    entry.extend(normalized_synonyms);
-                }
-
-                // Make sure that we don't have duplicate synonyms.
-                new_synonyms.iter_mut().for_each(|(_, synonyms)| {
-                    synonyms.sort_unstable();
-                    synonyms.dedup();
-                });
+                let new_synonyms = normalize_synonyms(&analyzer, synonyms);
 
                 let old_synonyms = self.index.synonyms(self.wtxn)?;
 
@@ -385,14 +943,40 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         }
     }
 
-    fn update_filterable(&mut self) -> Result<()> {
-        match self.filterable_fields {
-            Setting::Set(ref fields) => {
-                let mut new_facets = HashSet::new();
-                for name in fields {
-                    new_facets.insert(name.clone());
+    /// Updates the words whose synonyms are expanded at indexing time and
+    /// returns `true` if they have been modified, the documents must then be reindexed.
+    fn update_indexed_synonyms(&mut self) -> Result<bool> {
+        match self.indexed_synonyms {
+            Setting::Set(ref words) => {
+                let mut config = AnalyzerConfig::default();
+                let stop_words = self.index.query_stop_words(self.wtxn)?;
+                if let Some(stop_words) = &stop_words {
+                    config.stop_words(stop_words);
+                }
+                let analyzer = Analyzer::new(config);
+                let new_words: HashSet<_> =
+                    words.iter().map(|word| normalize_synonym(&analyzer, word)).collect();
+
+                if new_words != self.index.indexed_synonyms(self.wtxn)? {
+                    self.index.put_indexed_synonyms(self.wtxn, &new_words)?;
+                    Ok(true)
+                } else {
+                    Ok(false)
                 }
-                self.index.put_filterable_fields(self.wtxn, &new_facets)?;
+            }
+            Setting::Reset => Ok(self.index.delete_indexed_synonyms(self.wtxn)?),
+            Setting::NotSet => Ok(false),
+        }
+    }
+
+    fn update_filterable(&mut self) -> Result<()> {
+        match self.filterable_fields {
+            Setting::Set(ref fields) => {
+                let mut new_facets = HashSet::new();
+                for name in fields {
+                    new_facets.insert(name.clone());
+                }
+                self.index.put_filterable_fields(self.wtxn, &new_facets)?;
             }
             Setting::Reset => {
                 self.index.delete_filterable_fields(self.wtxn)?;
@@ -402,6 +986,25 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         Ok(())
     }
 
+    fn update_filter_capabilities(&mut self) -> Result<()> {
+        match self.filter_capabilities {
+            Setting::Set(ref capabilities) => {
+                // The fields that support all the filters don't need to be stored.
+                let capabilities: HashMap<_, _> = capabilities
+                    .iter()
+                    .filter(|(_, capabilities)| **capabilities != FilterCapabilities::default())
+                    .map(|(name, capabilities)| (name.clone(), *capabilities))
+                    .collect();
+                self.index.put_filter_capabilities(self.wtxn, &capabilities)?;
+            }
+            Setting::Reset => {
+                self.index.delete_filter_capabilities(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+        Ok(())
+    }
+
     fn update_sortable(&mut self) -> Result<()> {
         match self.sortable_fields {
             Setting::Set(ref fields) => {
@@ -419,6 +1022,24 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         Ok(())
     }
 
+    fn update_default_sort(&mut self) -> Result<()> {
+        match self.default_sort {
+            Setting::Set(ref sort) => {
+                let mut default_sort = Vec::new();
+                for text in sort {
+                    let asc_desc: AscDesc = text.parse().map_err(SortError::from)?;
+                    default_sort.push(asc_desc);
+                }
+                self.index.put_default_sort(self.wtxn, &default_sort)?;
+            }
+            Setting::Reset => {
+                self.index.delete_default_sort(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+        Ok(())
+    }
+
     fn update_criteria(&mut self) -> Result<()> {
         match self.criteria {
             Setting::Set(ref fields) => {
@@ -437,133 +1058,811 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         Ok(())
     }
 
-    fn update_primary_key(&mut self) -> Result<()> {
-        match self.primary_key {
-            Setting::Set(ref primary_key) => {
-                if self.index.number_of_documents(&self.wtxn)? == 0 {
-                    let mut fields_ids_map = self.index.fields_ids_map(self.wtxn)?;
-                    fields_ids_map.insert(primary_key).ok_or(UserError::AttributeLimitReached)?;
-                    self.index.put_fields_ids_map(self.wtxn, &fields_ids_map)?;
-                    self.index.put_primary_key(self.wtxn, primary_key)?;
-                    Ok(())
-                } else {
-                    Err(UserError::PrimaryKeyCannotBeChanged.into())
-                }
-            }
-            Setting::Reset => {
-                if self.index.number_of_documents(&self.wtxn)? == 0 {
-                    self.index.delete_primary_key(self.wtxn)?;
-                    Ok(())
-                } else {
-                    Err(UserError::PrimaryKeyCannotBeReset.into())
-                }
-            }
-            Setting::NotSet => Ok(()),
+    fn update_primary_key(&mut self) -> Result<()> {
+        match self.primary_key {
+            Setting::Set(ref primary_key) => {
+                if self.index.number_of_documents(&self.wtxn)? == 0 {
+                    let mut fields_ids_map = self.index.fields_ids_map(self.wtxn)?;
+                    fields_ids_map.insert(primary_key).ok_or(UserError::AttributeLimitReached)?;
+                    self.index.put_fields_ids_map(self.wtxn, &fields_ids_map)?;
+                    self.index.put_primary_key(self.wtxn, primary_key)?;
+                    Ok(())
+                } else {
+                    Err(UserError::PrimaryKeyCannotBeChanged.into())
+                }
+            }
+            Setting::Reset => {
+                if self.index.number_of_documents(&self.wtxn)? == 0 {
+                    self.index.delete_primary_key(self.wtxn)?;
+                    Ok(())
+                } else {
+                    Err(UserError::PrimaryKeyCannotBeReset.into())
+                }
+            }
+            Setting::NotSet => Ok(()),
+        }
+    }
+
+    pub fn execute<F>(self, progress_callback: F) -> Result<()>
+    where
+        F: Fn(UpdateIndexingStep, u64) + Sync,
+    {
+        self.execute_with_warnings(progress_callback).map(drop)
+    }
+
+    /// Identical to `execute`, but returns the warnings about the ranking rules
+    /// if the ranking rules or the sortable attributes were updated.
+    pub fn execute_with_warnings<F>(mut self, progress_callback: F) -> Result<Vec<CriterionWarning>>
+    where
+        F: Fn(UpdateIndexingStep, u64) + Sync,
+    {
+        self.index.set_updated_at(self.wtxn, &Utc::now())?;
+        let criteria_dependencies_updated =
+            !self.criteria.is_not_set() || !self.sortable_fields.is_not_set();
+
+        let old_faceted_fields = self.index.faceted_fields(&self.wtxn)?;
+        let old_unleveled_fields = self.index.unleveled_faceted_fields_ids(&self.wtxn)?;
+        let old_fields_ids_map = self.index.fields_ids_map(&self.wtxn)?;
+
+        self.update_displayed()?;
+        self.update_filterable()?;
+        self.update_filter_capabilities()?;
+        self.update_sortable()?;
+        self.update_default_sort()?;
+        self.update_distinct_field()?;
+        self.update_parent_field()?;
+        self.update_criteria()?;
+        self.update_primary_key()?;
+        self.update_proximity_approximation_threshold()?;
+        self.update_prefix_pair_proximity()?;
+        self.update_max_values_per_facet()?;
+        self.update_max_attributes_to_rank()?;
+        self.update_min_word_sizes_for_typos()?;
+        self.update_typo_disabled_on_words()?;
+        self.update_attributes_max_typos()?;
+        self.update_first_letter_typo()?;
+        self.update_typo_distance()?;
+        self.update_masked_fields()?;
+
+        // If there is new faceted fields we indicate that we must reindex as we must
+        // index new fields as facets. It means that the distinct attribute,
+        // an Asc/Desc criterion or a filtered attribute as be added or removed.
+        let new_faceted_fields = self.index.faceted_fields(&self.wtxn)?;
+        let faceted_updated = old_faceted_fields != new_faceted_fields;
+
+        self.remove_indexed_stop_words()?;
+        let stop_words_mode_updated = self.update_stop_words_mode()?;
+        let stop_words_updated = self.update_stop_words()?;
+        let synonyms_updated = self.update_synonyms()?;
+        let indexed_synonyms_updated = self.update_indexed_synonyms()?;
+        let searchable_updated = self.update_searchable()?;
+        let position_stride_updated = self.update_position_stride()?;
+        let fingerprinting_updated = self.update_documents_fingerprinting()?;
+        let proximity_excluded_updated = self.update_proximity_excluded_fields()?;
+        let exact_attributes_updated = self.update_exact_attributes()?;
+
+        if stop_words_updated
+            || stop_words_mode_updated
+            || faceted_updated
+            || synonyms_updated
+            || indexed_synonyms_updated
+            || searchable_updated
+            || position_stride_updated
+            || fingerprinting_updated
+            || proximity_excluded_updated
+            || exact_attributes_updated
+        {
+            self.reindex(&progress_callback, old_fields_ids_map)?;
+        } else if old_unleveled_fields != self.index.unleveled_faceted_fields_ids(&self.wtxn)? {
+            // The facet levels are computed again for the fields that became comparable
+            // and removed for the ones that can no longer be compared.
+            let mut builder = Facets::new(self.wtxn, self.index, self.update_id);
+            builder.chunk_compression_type = self.chunk_compression_type;
+            builder.chunk_compression_level = self.chunk_compression_level;
+            builder.execute()?;
+        }
+
+        if criteria_dependencies_updated {
+            self.index.criteria_warnings(self.wtxn)
+        } else {
+            Ok(Vec::new())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use big_s::S;
+    use heed::types::{ByteSlice, Str};
+    use heed::EnvOpenOptions;
+    use maplit::{btreemap, btreeset, hashmap, hashset};
+    use serde_json::json;
+
+    use super::*;
+    use crate::documents::{DocumentBatchBuilder, DocumentBatchReader};
+    use crate::error::Error;
+    use crate::index::main_key;
+    use crate::index::tests::TempIndex;
+    use crate::proximity::ONE_ATTRIBUTE;
+    use crate::update::IndexDocuments;
+    use crate::{Criterion, FilterCondition, Member, SearchResult};
+
+    #[test]
+    fn set_and_reset_searchable_fields() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        // First we send 3 documents with ids from 1 to 3.
+        let mut wtxn = index.write_txn().unwrap();
+
+        let content = documents!([
+            { "id": 1, "name": "kevin", "age": 23 },
+            { "id": 2, "name": "kevina", "age": 21},
+            { "id": 3, "name": "benoit", "age": 34 }
+        ]);
+        let builder = IndexDocuments::new(&mut wtxn, &index, 0);
+        builder.execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // We change the searchable fields to be the "name" field only.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 1);
+        builder.set_searchable_fields(vec!["name".into()]);
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // Check that the searchable field is correctly set to "name" only.
+        let rtxn = index.read_txn().unwrap();
+        // When we search for something that is not in
+        // the searchable fields it must not return any document.
+        let result = index.search(&rtxn).query("23").execute().unwrap();
+        assert!(result.documents_ids.is_empty());
+
+        // When we search for something that is in the searchable fields
+        // we must find the appropriate document.
+        let result = index.search(&rtxn).query(r#""kevin""#).execute().unwrap();
+        let documents = index.documents(&rtxn, result.documents_ids).unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].1.get(0), Some(&br#""kevin""#[..]));
+        drop(rtxn);
+
+        // We change the searchable fields to be the "name" field only.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.reset_searchable_fields();
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // Check that the searchable field have been reset and documents are found now.
+        let rtxn = index.read_txn().unwrap();
+        let searchable_fields = index.searchable_fields(&rtxn).unwrap();
+        assert_eq!(searchable_fields, None);
+        let result = index.search(&rtxn).query("23").execute().unwrap();
+        assert_eq!(result.documents_ids.len(), 1);
+        let documents = index.documents(&rtxn, result.documents_ids).unwrap();
+        assert_eq!(documents[0].1.get(0), Some(&br#""kevin""#[..]));
+    }
+
+    #[test]
+    fn set_and_reset_position_stride() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 1, "name": "hello world" },
+            { "id": 2, "name": "one two three four five" }
+        ]);
+        let builder = IndexDocuments::new(&mut wtxn, &index, 0);
+        builder.execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // The indexes that don't store a stride use the previous constant one.
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.position_stride(&rtxn).unwrap(), ONE_ATTRIBUTE);
+        let positions = index.docid_word_positions.get(&rtxn, &(0, "world")).unwrap().unwrap();
+        assert_eq!(positions.iter().collect::<Vec<_>>(), vec![ONE_ATTRIBUTE + 1]);
+        drop(rtxn);
+
+        // We reduce the stride, the documents are reindexed and the words
+        // that are after the stride in an attribute are not indexed anymore.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 1);
+        builder.set_position_stride(4);
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.position_stride(&rtxn).unwrap(), 4);
+        let positions = index.docid_word_positions.get(&rtxn, &(0, "world")).unwrap().unwrap();
+        assert_eq!(positions.iter().collect::<Vec<_>>(), vec![5]);
+        assert!(index.docid_word_positions.get(&rtxn, &(1, "five")).unwrap().is_none());
+        let result = index.search(&rtxn).query("hello world").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+        drop(rtxn);
+
+        // A stride of zero is invalid.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.set_position_stride(0);
+        assert!(builder.execute(|_, _| ()).is_err());
+        drop(wtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.reset_position_stride();
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let positions = index.docid_word_positions.get(&rtxn, &(1, "five")).unwrap().unwrap();
+        assert_eq!(positions.iter().collect::<Vec<_>>(), vec![ONE_ATTRIBUTE + 4]);
+    }
+
+    #[test]
+    fn set_and_reset_min_word_sizes_for_typos() {
+        let index = TempIndex::new();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 1, "name": "kevin" }]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // A word of four characters doesn't allow any typo by default.
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.min_word_size_for_one_typo(&rtxn).unwrap(), 5);
+        assert_eq!(index.min_word_size_for_two_typos(&rtxn).unwrap(), 9);
+        let result = index.search(&rtxn).query("kevn").execute().unwrap();
+        assert!(result.documents_ids.is_empty());
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 1);
+        builder.set_min_word_size_for_one_typo(4);
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.min_word_size_for_one_typo(&rtxn).unwrap(), 4);
+        let result = index.search(&rtxn).query("kevn").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+        drop(rtxn);
+
+        // A word can't allow two typos before it allows one.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.set_min_word_size_for_two_typos(3);
+        let error = builder.execute(|_, _| ()).unwrap_err();
+        assert!(matches!(error, Error::UserError(UserError::InvalidMinWordSizeForTypos { .. })));
+        drop(wtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.reset_min_word_size_for_one_typo();
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.min_word_size_for_one_typo(&rtxn).unwrap(), 5);
+        let result = index.search(&rtxn).query("kevn").execute().unwrap();
+        assert!(result.documents_ids.is_empty());
+    }
+
+    #[test]
+    fn disable_typos_on_attributes() {
+        let index = TempIndex::new();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 0, "title": "hello", "sku": "cable" },
+            { "id": 1, "title": "world", "sku": "table" },
+            { "id": 2, "title": "cable", "sku": "world" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let result = index.search(&rtxn).query("tablex").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![1]);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 1);
+        builder.set_attributes_max_typos(btreemap! { S("sku") => 0 });
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // The typos are still allowed in the title but the skus must match exactly.
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.attributes_max_typos(&rtxn).unwrap(), btreemap! { S("sku") => 0 });
+        let result = index.search(&rtxn).query("tablex").execute().unwrap();
+        assert!(result.documents_ids.is_empty());
+        let result = index.search(&rtxn).query("cablex").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![2]);
+        let result = index.search(&rtxn).query("table").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![1]);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.reset_attributes_max_typos();
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.attributes_max_typos(&rtxn).unwrap().is_empty());
+        let result = index.search(&rtxn).query("tablex").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![1]);
+    }
+
+    #[test]
+    fn set_typo_disabled_on_words() {
+        let index = TempIndex::new();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 0, "name": "galaxy" },
+            { "id": 1, "name": "galaxi" },
+            { "id": 2, "name": "nebula" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let result = index.search(&rtxn).query("galaxy").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0, 1]);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 1);
+        builder.set_typo_disabled_on_words(btreeset! { S("Galaxy ") });
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // The words are normalized and the other words still allow typos.
+        let rtxn = index.read_txn().unwrap();
+        let words = index.typo_disabled_on_words(&rtxn).unwrap().unwrap();
+        assert!(words.contains("galaxy"));
+        let result = index.search(&rtxn).query("galaxy").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+        let result = index.search(&rtxn).query("nebulo").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![2]);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.reset_typo_disabled_on_words();
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.typo_disabled_on_words(&rtxn).unwrap().is_none());
+        let result = index.search(&rtxn).query("galaxy").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0, 1]);
+    }
+
+    #[test]
+    fn set_proximity_excluded_fields() {
+        let index = TempIndex::new();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 0, "title": "hello world", "raw_html": "lorem ipsum" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let docids = index.word_pair_proximity_docids.get(&rtxn, &("lorem", "ipsum", 1)).unwrap();
+        assert!(docids.is_some());
+        drop(rtxn);
+
+        // The documents are reindexed without the word pairs of the raw html.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 1);
+        builder.set_proximity_excluded_fields(hashset! { S("raw_html") });
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.proximity_excluded_fields(&rtxn).unwrap(), hashset! { S("raw_html") });
+        let docids = index.word_pair_proximity_docids.get(&rtxn, &("lorem", "ipsum", 1)).unwrap();
+        assert!(docids.is_none());
+        let docids = index.word_pair_proximity_docids.get(&rtxn, &("hello", "world", 1)).unwrap();
+        assert!(docids.is_some());
+        let result = index.search(&rtxn).query("lorem ipsum").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+
+        // The phrases are verified with the positions of the words of the excluded fields.
+        let result = index.search(&rtxn).query("\"lorem ipsum\"").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+        let result = index.search(&rtxn).query("\"ipsum lorem\"").execute().unwrap();
+        assert!(result.documents_ids.is_empty());
+    }
+
+    #[test]
+    fn set_exact_attributes() {
+        let index = TempIndex::new();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_criteria(vec![S("words"), S("exactness")]);
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": 0, "title": "hello world kevin" },
+            { "id": 1, "title": "Hello. World" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.field_id_exact_value_docids.is_empty(&rtxn).unwrap());
+
+        // The hard separator moves the second word away from the first one,
+        // the title that starts with the query is ranked first.
+        let result = index.search(&rtxn).query("hello world").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0, 1]);
+        drop(rtxn);
+
+        // The documents are reindexed with the whole value of their title.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.set_exact_attributes(hashset! { S("title") });
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.exact_attributes(&rtxn).unwrap(), hashset! { S("title") });
+        let title = index.fields_ids_map(&rtxn).unwrap().id("title").unwrap();
+        let docids = index.field_id_exact_value_docids.get(&rtxn, &(title, "hello world")).unwrap();
+        assert_eq!(docids.unwrap().into_iter().collect::<Vec<_>>(), vec![1]);
+        let result = index.search(&rtxn).query("hello world").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![1, 0]);
+    }
+
+    #[test]
+    fn set_and_reset_max_attributes_to_rank() {
+        let index = TempIndex::new();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_searchable_fields(vec![S("title"), S("description")]);
+        builder.set_criteria(vec![S("words"), S("attribute")]);
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": 0, "title": "kevin", "description": "the world says hello" },
+            { "id": 1, "title": "kevina", "description": "hello" },
+            { "id": 2, "title": "hello", "description": "kevin" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.max_attributes_to_rank(&rtxn).unwrap(), None);
+        let result = index.search(&rtxn).query("hello").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![2, 1, 0]);
+        drop(rtxn);
+
+        // The positions in the description are no longer ranked, the documents that only
+        // match in the description are in the same bucket and still returned.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.set_max_attributes_to_rank(1);
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.max_attributes_to_rank(&rtxn).unwrap(), Some(1));
+        let result = index.search(&rtxn).query("hello").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![2, 0, 1]);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 3);
+        builder.set_max_attributes_to_rank(0);
+        assert!(builder.execute(|_, _| ()).is_err());
+        let mut builder = Settings::new(&mut wtxn, &index, 3);
+        builder.reset_max_attributes_to_rank();
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.max_attributes_to_rank(&rtxn).unwrap(), None);
+        let result = index.search(&rtxn).query("hello").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn max_attributes_to_rank_with_many_candidates() {
+        let path = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024); // 10 MB
+        let index = Index::new(options, &path).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_searchable_fields(vec![S("title"), S("description")]);
+        builder.set_criteria(vec![S("words"), S("attribute")]);
+        builder.set_max_attributes_to_rank(1);
+        builder.execute(|_, _| ()).unwrap();
+
+        // Enough documents for the attribute criterion to compute its buckets with the
+        // words positions sets, the odd documents only match in the description.
+        let documents: Vec<_> = (0..600)
+            .map(|id| match id % 2 {
+                0 => json!({ "id": id, "title": "hello", "description": "world" }),
+                _ => json!({ "id": id, "title": "world", "description": "hello" }),
+            })
+            .collect();
+        let content = documents!(documents);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let result = index.search(&rtxn).query("hello").limit(600).execute().unwrap();
+        assert_eq!(result.documents_ids.len(), 600);
+        assert!(result.documents_ids[..300].iter().all(|id| id % 2 == 0));
+        assert!(result.documents_ids[300..].iter().all(|id| id % 2 == 1));
+    }
+
+    #[test]
+    fn set_attributes_max_typos() {
+        let index = TempIndex::new();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 0, "username": "kevinlee", "bio": "hello" },
+            { "id": 1, "username": "hello", "bio": "kevinlea" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let search = |rtxn: &heed::RoTxn| {
+            let mut documents_ids =
+                index.search(rtxn).query("kevinlex").execute().unwrap().documents_ids;
+            documents_ids.sort_unstable();
+            documents_ids
+        };
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(search(&rtxn), vec![0, 1]);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 1);
+        builder.set_attributes_max_typos(btreemap! { S("username") => 0, S("bio") => 2 });
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.attributes_max_typos(&rtxn).unwrap().get("username"), Some(&0));
+        assert_eq!(search(&rtxn), vec![1]);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.reset_attributes_max_typos();
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.attributes_max_typos(&rtxn).unwrap().is_empty());
+        assert_eq!(search(&rtxn), vec![0, 1]);
+    }
+
+    #[test]
+    fn set_first_letter_typo() {
+        let index = TempIndex::new();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 0, "name": "kevin wonderful" },
+            { "id": 1, "name": "devin" },
+            { "id": 2, "name": "ponderful" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let set_first_letter_typo = |update_id, first_letter_typo| {
+            let mut wtxn = index.write_txn().unwrap();
+            let mut builder = Settings::new(&mut wtxn, &index, update_id);
+            builder.set_first_letter_typo(first_letter_typo);
+            builder.execute(|_, _| ()).unwrap();
+            wtxn.commit().unwrap();
+        };
+        let search = |query: &str| {
+            let rtxn = index.read_txn().unwrap();
+            let mut documents_ids =
+                index.search(&rtxn).query(query).execute().unwrap().documents_ids;
+            documents_ids.sort_unstable();
+            documents_ids
+        };
+
+        assert_eq!(search("kevin"), vec![0, 1]);
+        assert_eq!(search("wonderful"), vec![0, 2]);
+
+        // The words of less than nine letters only allow one typo.
+        set_first_letter_typo(1, FirstLetterTypo::Double);
+        assert_eq!(search("kevin"), vec![0]);
+        assert_eq!(search("wonderful"), vec![0, 2]);
+
+        set_first_letter_typo(2, FirstLetterTypo::Disallowed);
+        assert_eq!(search("kevin"), vec![0]);
+        assert_eq!(search("wonderful"), vec![0]);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 3);
+        builder.reset_first_letter_typo();
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(search("kevin"), vec![0, 1]);
+    }
+
+    #[test]
+    fn set_typo_distance() {
+        let index = TempIndex::new();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 0, "name": "kevin" },
+            { "id": 1, "name": "kelly" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let search = |query: &str| {
+            let rtxn = index.read_txn().unwrap();
+            let mut documents_ids =
+                index.search(&rtxn).query(query).execute().unwrap().documents_ids;
+            documents_ids.sort_unstable();
+            documents_ids
+        };
+
+        // A transposition counts as a single typo by default.
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.typo_distance(&rtxn).unwrap(), TypoDistance::DamerauLevenshtein);
+        drop(rtxn);
+        assert_eq!(search("kvein"), vec![0]);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 1);
+        builder.set_typo_distance(TypoDistance::Levenshtein);
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(search("kvein"), vec![]);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.reset_typo_distance();
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(search("kvein"), vec![0]);
+    }
+
+    #[test]
+    fn disable_prefix_pair_proximity() {
+        let index = TempIndex::new();
+
+        // Enough words starting with the same prefix to fill the prefix databases.
+        let mut cursor = Cursor::new(Vec::new());
+        let mut builder = DocumentBatchBuilder::new(&mut cursor).unwrap();
+        for id in 0..130u8 {
+            let word = format!("hel{}{}", (b'a' + id / 26) as char, (b'a' + id % 26) as char);
+            builder.add_documents(json!({ "id": id, "title": format!("good {}", word) })).unwrap();
         }
-    }
+        builder.finish().unwrap();
+        cursor.set_position(0);
+        let content = DocumentBatchReader::from_reader(cursor).unwrap();
 
-    pub fn execute<F>(mut self, progress_callback: F) -> Result<()>
-    where
-        F: Fn(UpdateIndexingStep, u64) + Sync,
-    {
-        self.index.set_updated_at(self.wtxn, &Utc::now())?;
+        let mut wtxn = index.write_txn().unwrap();
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
 
-        let old_faceted_fields = self.index.faceted_fields(&self.wtxn)?;
-        let old_fields_ids_map = self.index.fields_ids_map(&self.wtxn)?;
+        let search = || {
+            let rtxn = index.read_txn().unwrap();
+            let result = index.search(&rtxn).query("good hel").limit(200).execute().unwrap();
+            result.documents_ids
+        };
 
-        self.update_displayed()?;
-        self.update_filterable()?;
-        self.update_sortable()?;
-        self.update_distinct_field()?;
-        self.update_criteria()?;
-        self.update_primary_key()?;
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.prefix_pair_proximity(&rtxn).unwrap());
+        assert!(!index.word_prefix_pair_proximity_docids.is_empty(&rtxn).unwrap());
+        drop(rtxn);
+        let expected = search();
+        assert_eq!(expected.len(), 130);
 
-        // If there is new faceted fields we indicate that we must reindex as we must
-        // index new fields as facets. It means that the distinct attribute,
-        // an Asc/Desc criterion or a filtered attribute as be added or removed.
-        let new_faceted_fields = self.index.faceted_fields(&self.wtxn)?;
-        let faceted_updated = old_faceted_fields != new_faceted_fields;
+        // Disabling the database clears it, the prefix queries give the same results.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 1);
+        builder.set_prefix_pair_proximity(false);
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
 
-        let stop_words_updated = self.update_stop_words()?;
-        let synonyms_updated = self.update_synonyms()?;
-        let searchable_updated = self.update_searchable()?;
+        let rtxn = index.read_txn().unwrap();
+        assert!(!index.prefix_pair_proximity(&rtxn).unwrap());
+        assert!(index.word_prefix_pair_proximity_docids.is_empty(&rtxn).unwrap());
+        drop(rtxn);
+        assert_eq!(search(), expected);
 
-        if stop_words_updated || faceted_updated || synonyms_updated || searchable_updated {
-            self.reindex(&progress_callback, old_fields_ids_map)?;
-        }
+        // The database isn't filled by the next indexations.
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 200, "title": "good helzz" }]);
+        IndexDocuments::new(&mut wtxn, &index, 2).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
 
-        Ok(())
-    }
-}
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.word_prefix_pair_proximity_docids.is_empty(&rtxn).unwrap());
+        drop(rtxn);
+        let expected = search();
+        assert_eq!(expected.len(), 131);
 
-#[cfg(test)]
-mod tests {
-    use big_s::S;
-    use heed::types::ByteSlice;
-    use heed::EnvOpenOptions;
-    use maplit::{btreeset, hashmap, hashset};
+        // Enabling the database computes it again without reindexing the documents.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 3);
+        builder.reset_prefix_pair_proximity();
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
 
-    use super::*;
-    use crate::error::Error;
-    use crate::update::IndexDocuments;
-    use crate::{Criterion, FilterCondition, SearchResult};
+        let rtxn = index.read_txn().unwrap();
+        assert!(!index.word_prefix_pair_proximity_docids.is_empty(&rtxn).unwrap());
+        drop(rtxn);
+        assert_eq!(search(), expected);
+    }
 
     #[test]
-    fn set_and_reset_searchable_fields() {
+    fn reorder_searchable_fields_without_reindexing() {
         let path = tempfile::tempdir().unwrap();
         let mut options = EnvOpenOptions::new();
         options.map_size(10 * 1024 * 1024); // 10 MB
         let index = Index::new(options, &path).unwrap();
 
-        // First we send 3 documents with ids from 1 to 3.
+        // We set the searchable fields and send 2 documents.
         let mut wtxn = index.write_txn().unwrap();
-
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_searchable_fields(vec![S("title"), S("description")]);
+        builder.execute(|_, _| ()).unwrap();
         let content = documents!([
-            { "id": 1, "name": "kevin", "age": 23 },
-            { "id": 2, "name": "kevina", "age": 21},
-            { "id": 3, "name": "benoit", "age": 34 }
+            { "id": 1, "title": "hello", "description": "world" },
+            { "id": 2, "title": "world", "description": "hello" }
         ]);
-        let builder = IndexDocuments::new(&mut wtxn, &index, 0);
+        let builder = IndexDocuments::new(&mut wtxn, &index, 1);
         builder.execute(content, |_, _| ()).unwrap();
         wtxn.commit().unwrap();
 
-        // We change the searchable fields to be the "name" field only.
-        let mut wtxn = index.write_txn().unwrap();
-        let mut builder = Settings::new(&mut wtxn, &index, 1);
-        builder.set_searchable_fields(vec!["name".into()]);
-        builder.execute(|_, _| ()).unwrap();
-        wtxn.commit().unwrap();
-
-        // Check that the searchable field is correctly set to "name" only.
         let rtxn = index.read_txn().unwrap();
-        // When we search for something that is not in
-        // the searchable fields it must not return any document.
-        let result = index.search(&rtxn).query("23").execute().unwrap();
-        assert!(result.documents_ids.is_empty());
-
-        // When we search for something that is in the searchable fields
-        // we must find the appropriate document.
-        let result = index.search(&rtxn).query(r#""kevin""#).execute().unwrap();
-        let documents = index.documents(&rtxn, result.documents_ids).unwrap();
-        assert_eq!(documents.len(), 1);
-        assert_eq!(documents[0].1.get(0), Some(&br#""kevin""#[..]));
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let title_id = fields_ids_map.id("title").unwrap();
+        let id_fid = fields_ids_map.id("id").unwrap();
+        let SearchResult { documents_ids, .. } =
+            index.search(&rtxn).query("hello").execute().unwrap();
+        let documents = index.documents(&rtxn, documents_ids).unwrap();
+        assert_eq!(documents[0].1.get(id_fid), Some(&b"1"[..]));
         drop(rtxn);
 
-        // We change the searchable fields to be the "name" field only.
+        // We reorder the searchable fields, the field map must not change.
         let mut wtxn = index.write_txn().unwrap();
         let mut builder = Settings::new(&mut wtxn, &index, 2);
-        builder.reset_searchable_fields();
+        builder.set_searchable_fields(vec![S("description"), S("title")]);
         builder.execute(|_, _| ()).unwrap();
         wtxn.commit().unwrap();
 
-        // Check that the searchable field have been reset and documents are found now.
         let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        assert_eq!(fields_ids_map.id("title"), Some(title_id));
         let searchable_fields = index.searchable_fields(&rtxn).unwrap();
-        assert_eq!(searchable_fields, None);
-        let result = index.search(&rtxn).query("23").execute().unwrap();
-        assert_eq!(result.documents_ids.len(), 1);
-        let documents = index.documents(&rtxn, result.documents_ids).unwrap();
-        assert_eq!(documents[0].1.get(0), Some(&br#""kevin""#[..]));
+        assert_eq!(searchable_fields, Some(vec!["description", "title"]));
+
+        // But the ranking must follow the new order of the attributes.
+        let SearchResult { documents_ids, .. } =
+            index.search(&rtxn).query("hello").execute().unwrap();
+        let documents = index.documents(&rtxn, documents_ids).unwrap();
+        assert_eq!(documents[0].1.get(id_fid), Some(&b"2"[..]));
     }
 
     #[test]
@@ -795,6 +2094,89 @@ mod tests {
         assert_eq!(iter.collect::<Vec<_>>(), vec![21, 23, 34]);
     }
 
+    #[test]
+    fn criteria_warnings() {
+        let index = TempIndex::new();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([{ "id": 0, "name": "kevin", "age": 23 }]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+
+        let mut builder = Settings::new(&mut wtxn, &index, 1);
+        builder.set_criteria(vec![S("words"), S("sort"), S("age:asc"), S("size:desc")]);
+        let warnings = builder.execute_with_warnings(|_, _| ()).unwrap();
+        assert_eq!(
+            warnings,
+            vec![
+                CriterionWarning::SortWithoutSortableAttributes,
+                CriterionWarning::UnknownAscDescField {
+                    criterion: Criterion::Desc(S("size")),
+                    field: S("size"),
+                },
+            ]
+        );
+
+        // The sort ranking rule has a sortable attribute to sort on.
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.set_sortable_fields(hashset! { S("age") });
+        let warnings = builder.execute_with_warnings(|_, _| ()).unwrap();
+        assert_eq!(warnings.len(), 1);
+
+        // The warnings are only returned when the ranking rules or their dependencies change.
+        let mut builder = Settings::new(&mut wtxn, &index, 3);
+        builder.set_displayed_fields(vec![S("name")]);
+        assert!(builder.execute_with_warnings(|_, _| ()).unwrap().is_empty());
+        wtxn.commit().unwrap();
+    }
+
+    #[test]
+    fn set_and_reset_default_sort() {
+        let index = TempIndex::new();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_sortable_fields(hashset! { S("age") });
+        builder.set_default_sort(vec![S("age:desc")]);
+        builder.execute(|_, _| ()).unwrap();
+
+        let content = documents!([
+            { "id": 0, "name": "kevin", "age": 23 },
+            { "id": 1, "name": "kevina", "age": 21 },
+            { "id": 2, "name": "benoit", "age": 34 }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // The default sort is only applied when browsing the documents.
+        let rtxn = index.read_txn().unwrap();
+        let default_sort = index.default_sort(&rtxn).unwrap();
+        assert_eq!(default_sort, Some(vec![AscDesc::Desc(Member::Field(S("age")))]));
+        let SearchResult { documents_ids, .. } = index.search(&rtxn).execute().unwrap();
+        assert_eq!(documents_ids, vec![2, 0, 1]);
+        let SearchResult { documents_ids, .. } = index.search(&rtxn).query(" ").execute().unwrap();
+        assert_eq!(documents_ids, vec![2, 0, 1]);
+        let sort = vec![AscDesc::Asc(Member::Field(S("age")))];
+        let SearchResult { documents_ids, .. } =
+            index.search(&rtxn).sort_criteria(sort).execute().unwrap();
+        assert_eq!(documents_ids, vec![1, 0, 2]);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.set_default_sort(vec![S("age:sideways")]);
+        assert!(builder.execute(|_, _| ()).is_err());
+
+        let mut builder = Settings::new(&mut wtxn, &index, 3);
+        builder.reset_default_sort();
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.default_sort(&rtxn).unwrap(), None);
+        let SearchResult { documents_ids, .. } = index.search(&rtxn).execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 1, 2]);
+    }
+
     #[test]
     fn set_distinct_field() {
         let path = tempfile::tempdir().unwrap();
@@ -858,6 +2240,82 @@ mod tests {
         assert!(stop_words.is_none());
     }
 
+    #[test]
+    fn stop_words_mode() {
+        let index = TempIndex::new();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 0, "maxim": "I love dogs" },
+            { "id": 1, "maxim": "Doggos are the best" },
+            { "id": 2, "maxim": "The crepes are really good" },
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+
+        // The stop words are only removed from the queries.
+        let mut builder = Settings::new(&mut wtxn, &index, 1);
+        builder.set_stop_words(btreeset! { S("the"), S("are") });
+        builder.set_stop_words_mode(StopWordsMode::QueryOnly);
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.word_docids.get(&rtxn, "the").unwrap().is_some());
+        let result = index.search(&rtxn).query("the ").execute().unwrap();
+        assert_eq!(result.documents_ids.len(), 3);
+        drop(rtxn);
+
+        // The documents are indexed again to remove the stop words from them.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.reset_stop_words_mode();
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.stop_words_mode(&rtxn).unwrap(), StopWordsMode::Everywhere);
+        assert!(index.word_docids.get(&rtxn, "the").unwrap().is_none());
+        assert!(index.word_docids.get(&rtxn, "crepes").unwrap().is_some());
+    }
+
+    #[test]
+    fn remove_stop_words_indexed_by_the_old_extraction() {
+        let index = TempIndex::new();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 0, "maxim": "The crepes are really good" },
+            { "id": 1, "maxim": "Doggos are the best" },
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+
+        // The old extraction ignored the stop words, the documents were indexed
+        // with them even if the index had stop words, and it wasn't migrated.
+        let stop_words = fst::Set::from_iter(&["are", "the"]).unwrap();
+        index.put_stop_words(&mut wtxn, &stop_words).unwrap();
+        index.main.delete::<_, Str>(&mut wtxn, main_key::STOP_WORDS_MIGRATED_KEY).unwrap();
+        assert!(index.word_docids.get(&wtxn, "the").unwrap().is_some());
+        assert!(index.exact_word_docids.get(&wtxn, "the").unwrap().is_some());
+        wtxn.commit().unwrap();
+
+        // The first settings update removes them from the word databases.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 1);
+        builder.set_displayed_fields(vec![S("maxim")]);
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        for word in ["are", "the"] {
+            assert!(index.word_docids.get(&rtxn, word).unwrap().is_none());
+            assert!(index.exact_word_docids.get(&rtxn, word).unwrap().is_none());
+            assert!(!index.words_fst(&rtxn).unwrap().contains(word));
+        }
+        assert!(index.word_docids.get(&rtxn, "crepes").unwrap().is_some());
+        assert!(index.exact_word_docids.get(&rtxn, "best").unwrap().is_some());
+        assert!(index.stop_words_migrated(&rtxn).unwrap());
+    }
+
     #[test]
     fn set_and_reset_stop_words() {
         let path = tempfile::tempdir().unwrap();
@@ -892,6 +2350,12 @@ mod tests {
         let expected = fst::Set::from_iter(&set).unwrap();
         assert_eq!(stop_words.as_fst().as_bytes(), expected.as_fst().as_bytes());
 
+        // the stop words must have been removed from the words databases
+        assert!(index.word_docids.get(&rtxn, "the").unwrap().is_none());
+        assert!(index.exact_word_docids.get(&rtxn, "the").unwrap().is_none());
+        assert!(!index.words_fst(&rtxn).unwrap().contains("the"));
+        assert!(index.word_docids.get(&rtxn, "crepes").unwrap().is_some());
+
         // when we search for something that is a non prefix stop_words it should be ignored
         // thus we should get a placeholder search (all the results = 3)
         let result = index.search(&rtxn).query("the ").execute().unwrap();
@@ -994,6 +2458,127 @@ mod tests {
         assert!(result.documents_ids.is_empty());
     }
 
+    #[test]
+    fn set_and_reset_indexed_synonyms() {
+        let index = TempIndex::new();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let content = documents!([
+            { "id": 0, "maxim": "I love dogs" },
+            { "id": 1, "maxim": "Doggos are the best" },
+            { "id": 2, "maxim": "I live in New York" }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+
+        let mut builder = Settings::new(&mut wtxn, &index, 1);
+        builder.set_synonyms(hashmap! {
+            S("puppies") => vec![S("dogs"), S("doggos")],
+            S("NYC") => vec![S("new york")]
+        });
+        builder.set_indexed_synonyms(hashset! { S("puppies"), S("NYC") });
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // The words are in the documents that contain their synonyms
+        // and they are no longer expanded at query time.
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(
+            index.indexed_synonyms(&rtxn).unwrap(),
+            hashset! { vec![S("puppies")], vec![S("nyc")] }
+        );
+        let docids = index.word_docids.get(&rtxn, "puppies").unwrap().unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![0, 1]);
+        let docids = index.word_docids.get(&rtxn, "nyc").unwrap().unwrap();
+        assert_eq!(docids.into_iter().collect::<Vec<_>>(), vec![2]);
+        assert_eq!(index.words_synonyms(&rtxn, &["puppies"]).unwrap(), None);
+
+        let result = index.search(&rtxn).query("puppies").execute().unwrap();
+        assert_eq!(result.documents_ids.len(), 2);
+        let result = index.search(&rtxn).query("nyc").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![2]);
+        drop(rtxn);
+
+        // The synonyms are expanded at query time again.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.reset_indexed_synonyms();
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.indexed_synonyms(&rtxn).unwrap().is_empty());
+        assert!(index.word_docids.get(&rtxn, "puppies").unwrap().is_none());
+        assert!(index.words_synonyms(&rtxn, &["puppies"]).unwrap().is_some());
+        let result = index.search(&rtxn).query("puppies").execute().unwrap();
+        assert_eq!(result.documents_ids.len(), 2);
+        let result = index.search(&rtxn).query("nyc").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![2]);
+    }
+
+    #[test]
+    fn set_and_reset_filter_capabilities() {
+        let index = TempIndex::new();
+
+        // Enough different values for the facet levels to be computed.
+        let mut cursor = Cursor::new(Vec::new());
+        let mut builder = DocumentBatchBuilder::new(&mut cursor).unwrap();
+        for id in 0..100 {
+            builder.add_documents(json!({ "id": id, "code": id * 10 })).unwrap();
+        }
+        builder.finish().unwrap();
+        cursor.set_position(0);
+        let content = DocumentBatchReader::from_reader(cursor).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        IndexDocuments::new(&mut wtxn, &index, 0).execute(content, |_, _| ()).unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 1);
+        builder.set_filterable_fields(hashset! { S("code") });
+        builder.set_filter_capabilities(hashmap! {
+            S("code") => FilterCapabilities { equality: true, comparison: false },
+        });
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        // Count the facet values of the levels above the level zero.
+        let levels_count = |rtxn: &heed::RoTxn| {
+            let fid = index.fields_ids_map(rtxn).unwrap().id("code").unwrap();
+            let [a, b] = fid.to_be_bytes();
+            index
+                .facet_id_f64_docids
+                .remap_key_type::<ByteSlice>()
+                .prefix_iter(rtxn, &[a, b])
+                .unwrap()
+                .filter(|result| result.as_ref().unwrap().0[2] != 0)
+                .count()
+        };
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(levels_count(&rtxn), 0);
+        let condition = FilterCondition::from_str(&rtxn, &index, "code = 120").unwrap();
+        let result = index.search(&rtxn).filter(condition).execute().unwrap();
+        assert_eq!(result.documents_ids, vec![12]);
+        let condition = FilterCondition::from_str(&rtxn, &index, "code IN [10, 20]").unwrap();
+        let result = index.search(&rtxn).filter(condition).execute().unwrap();
+        assert_eq!(result.documents_ids, vec![1, 2]);
+        let error = FilterCondition::from_str(&rtxn, &index, "code > 120").unwrap_err();
+        assert!(error.to_string().contains("doesn't support the comparison filters"));
+        drop(rtxn);
+
+        // The levels are computed again once the field can be compared.
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 2);
+        builder.reset_filter_capabilities();
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.filter_capabilities(&rtxn).unwrap().is_empty());
+        assert_ne!(levels_count(&rtxn), 0);
+        let condition = FilterCondition::from_str(&rtxn, &index, "code > 950").unwrap();
+        let result = index.search(&rtxn).filter(condition).execute().unwrap();
+        assert_eq!(result.documents_ids, vec![96, 97, 98, 99]);
+    }
+
     #[test]
     fn setting_searchable_recomputes_other_settings() {
         let path = tempfile::tempdir().unwrap();
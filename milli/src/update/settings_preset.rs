@@ -0,0 +1,154 @@
+use std::collections::{BTreeSet, HashMap};
+
+use super::Setting;
+
+/// A curated set of settings that can be applied to an index in one call.
+///
+/// Only the settings that are set or reset by the preset are modified,
+/// the other settings of the index are left untouched.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SettingsPreset {
+    pub criteria: Setting<Vec<String>>,
+    pub stop_words: Setting<BTreeSet<String>>,
+    pub proximity_approximation_threshold: Setting<u64>,
+    pub max_values_per_facet: Setting<usize>,
+}
+
+impl SettingsPreset {
+    /// Log lines are mostly sorted by date and searched for exact identifiers,
+    /// the proximity between the words is approximated on large sets of candidates.
+    pub fn logs() -> SettingsPreset {
+        SettingsPreset {
+            criteria: Setting::Set(vec![
+                "words".to_string(),
+                "sort".to_string(),
+                "exactness".to_string(),
+                "typo".to_string(),
+            ]),
+            stop_words: Setting::NotSet,
+            proximity_approximation_threshold: Setting::Set(1000),
+            max_values_per_facet: Setting::NotSet,
+        }
+    }
+
+    /// Products are sorted by the user choice before the proximity of the words
+    /// and the facet distribution only shows the most frequent values.
+    pub fn ecommerce() -> SettingsPreset {
+        SettingsPreset {
+            criteria: Setting::Set(vec![
+                "words".to_string(),
+                "typo".to_string(),
+                "sort".to_string(),
+                "proximity".to_string(),
+                "attribute".to_string(),
+                "exactness".to_string(),
+            ]),
+            stop_words: Setting::NotSet,
+            proximity_approximation_threshold: Setting::NotSet,
+            max_values_per_facet: Setting::Set(20),
+        }
+    }
+
+    /// Documentation pages are ranked by relevancy first and the most common
+    /// english words are ignored as they appear in all the pages.
+    pub fn docs() -> SettingsPreset {
+        let stop_words = ["a", "an", "and", "in", "is", "of", "or", "the", "to"];
+        SettingsPreset {
+            criteria: Setting::Set(vec![
+                "words".to_string(),
+                "typo".to_string(),
+                "proximity".to_string(),
+                "attribute".to_string(),
+                "exactness".to_string(),
+                "sort".to_string(),
+            ]),
+            stop_words: Setting::Set(stop_words.iter().map(|word| word.to_string()).collect()),
+            proximity_approximation_threshold: Setting::NotSet,
+            max_values_per_facet: Setting::NotSet,
+        }
+    }
+}
+
+/// The presets that can be applied by name, it contains the `logs`, `ecommerce`
+/// and `docs` presets by default and embedders can register their own presets.
+#[derive(Debug, Clone)]
+pub struct SettingsPresets {
+    presets: HashMap<String, SettingsPreset>,
+}
+
+impl SettingsPresets {
+    /// Registers a preset under the given name, replacing and
+    /// returning the preset previously registered under this name.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        preset: SettingsPreset,
+    ) -> Option<SettingsPreset> {
+        self.presets.insert(name.into(), preset)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SettingsPreset> {
+        self.presets.get(name)
+    }
+
+    /// Returns the names of the registered presets.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.presets.keys().map(String::as_str)
+    }
+}
+
+impl Default for SettingsPresets {
+    fn default() -> SettingsPresets {
+        let mut presets = HashMap::new();
+        presets.insert("logs".to_string(), SettingsPreset::logs());
+        presets.insert("ecommerce".to_string(), SettingsPreset::ecommerce());
+        presets.insert("docs".to_string(), SettingsPreset::docs());
+        SettingsPresets { presets }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::update::Settings;
+    use crate::Criterion;
+
+    #[test]
+    fn apply_registered_presets() {
+        let index = TempIndex::new();
+
+        let mut presets = SettingsPresets::default();
+        let custom = SettingsPreset {
+            criteria: Setting::Set(vec![S("words"), S("exactness")]),
+            ..SettingsPreset::default()
+        };
+        assert!(presets.register("custom", custom).is_none());
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_max_values_per_facet(42);
+        builder.apply_preset(presets.get("docs").unwrap());
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.criteria(&rtxn).unwrap().last(), Some(&Criterion::Sort));
+        assert!(index.stop_words(&rtxn).unwrap().unwrap().contains("the"));
+        // The settings that are not set by the preset are kept.
+        assert_eq!(index.max_values_per_facet(&rtxn).unwrap(), Some(42));
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 1);
+        builder.apply_preset(presets.get("custom").unwrap());
+        builder.execute(|_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.criteria(&rtxn).unwrap(), vec![Criterion::Words, Criterion::Exactness]);
+        assert!(index.stop_words(&rtxn).unwrap().unwrap().contains("the"));
+    }
+}
@@ -1,7 +1,10 @@
 use grenad::CompressionType;
 use rayon::ThreadPool;
 
-use super::{ClearDocuments, DeleteDocuments, Facets, IndexDocuments, Settings};
+use super::{
+    ClearDocuments, CompactDocuments, DeleteDocuments, Facets, IndexDocuments, RenameFacetValues,
+    Settings, UpdateByFunction,
+};
 use crate::{Index, Result};
 
 pub struct UpdateBuilder<'a> {
@@ -65,6 +68,19 @@ impl<'a> UpdateBuilder<'a> {
         ClearDocuments::new(wtxn, index, self.update_id)
     }
 
+    pub fn compact_documents<'t, 'u, 'i>(
+        self,
+        wtxn: &'t mut heed::RwTxn<'i, 'u>,
+        index: &'i Index,
+    ) -> CompactDocuments<'t, 'u, 'i> {
+        let mut builder = CompactDocuments::new(wtxn, index, self.update_id);
+
+        builder.chunk_compression_type = self.chunk_compression_type;
+        builder.chunk_compression_level = self.chunk_compression_level;
+
+        builder
+    }
+
     pub fn delete_documents<'t, 'u, 'i>(
         self,
         wtxn: &'t mut heed::RwTxn<'i, 'u>,
@@ -91,6 +107,42 @@ impl<'a> UpdateBuilder<'a> {
         builder
     }
 
+    pub fn update_by_function<'t, 'u, 'i>(
+        self,
+        wtxn: &'t mut heed::RwTxn<'i, 'u>,
+        index: &'i Index,
+    ) -> UpdateByFunction<'a, 't, 'u, 'i> {
+        let mut builder = UpdateByFunction::new(wtxn, index, self.update_id);
+
+        builder.log_every_n = self.log_every_n;
+        builder.max_nb_chunks = self.max_nb_chunks;
+        builder.max_memory = self.max_memory;
+        builder.documents_chunk_size = self.documents_chunk_size;
+        builder.chunk_compression_type = self.chunk_compression_type;
+        builder.chunk_compression_level = self.chunk_compression_level;
+        builder.thread_pool = self.thread_pool;
+
+        builder
+    }
+
+    pub fn rename_facet_values<'t, 'u, 'i>(
+        self,
+        wtxn: &'t mut heed::RwTxn<'i, 'u>,
+        index: &'i Index,
+    ) -> RenameFacetValues<'a, 't, 'u, 'i> {
+        let mut builder = RenameFacetValues::new(wtxn, index, self.update_id);
+
+        builder.log_every_n = self.log_every_n;
+        builder.max_nb_chunks = self.max_nb_chunks;
+        builder.max_memory = self.max_memory;
+        builder.documents_chunk_size = self.documents_chunk_size;
+        builder.chunk_compression_type = self.chunk_compression_type;
+        builder.chunk_compression_level = self.chunk_compression_level;
+        builder.thread_pool = self.thread_pool;
+
+        builder
+    }
+
     pub fn settings<'t, 'u, 'i>(
         self,
         wtxn: &'t mut heed::RwTxn<'i, 'u>,
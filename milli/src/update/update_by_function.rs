@@ -0,0 +1,219 @@
+use std::io::Cursor;
+
+use chrono::Utc;
+use grenad::CompressionType;
+use rayon::ThreadPool;
+use roaring::RoaringBitmap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{IndexDocuments, IndexDocumentsMethod, UpdateIndexingStep};
+use crate::documents::{DocumentBatchBuilder, DocumentBatchReader};
+use crate::error::{Object, UserError};
+use crate::{DocumentReader, FilterCondition, Index, Result};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DocumentEditionResult {
+    pub nb_documents: usize,
+}
+
+/// Applies a function to every document of the index that matches a filter.
+///
+/// The documents edited by the function are indexed again by replacing the
+/// previous version of the documents, the other ones are left untouched.
+pub struct UpdateByFunction<'a, 't, 'u, 'i> {
+    wtxn: &'t mut heed::RwTxn<'i, 'u>,
+    index: &'i Index,
+    pub(crate) log_every_n: Option<usize>,
+    pub(crate) max_nb_chunks: Option<usize>,
+    pub(crate) max_memory: Option<usize>,
+    pub(crate) documents_chunk_size: Option<usize>,
+    pub(crate) chunk_compression_type: CompressionType,
+    pub(crate) chunk_compression_level: Option<u32>,
+    pub(crate) thread_pool: Option<&'a ThreadPool>,
+    filter: Option<FilterCondition>,
+    documents_ids: Option<RoaringBitmap>,
+    update_id: u64,
+}
+
+impl<'a, 't, 'u, 'i> UpdateByFunction<'a, 't, 'u, 'i> {
+    pub fn new(
+        wtxn: &'t mut heed::RwTxn<'i, 'u>,
+        index: &'i Index,
+        update_id: u64,
+    ) -> UpdateByFunction<'a, 't, 'u, 'i> {
+        UpdateByFunction {
+            wtxn,
+            index,
+            log_every_n: None,
+            max_nb_chunks: None,
+            max_memory: None,
+            documents_chunk_size: None,
+            chunk_compression_type: CompressionType::None,
+            chunk_compression_level: None,
+            thread_pool: None,
+            filter: None,
+            documents_ids: None,
+            update_id,
+        }
+    }
+
+    pub fn log_every_n(&mut self, n: usize) {
+        self.log_every_n = Some(n);
+    }
+
+    /// Only the documents matching this filter are given to the function,
+    /// all the documents of the index are given to it when no filter is set.
+    pub fn filter(&mut self, condition: FilterCondition) {
+        self.filter = Some(condition);
+    }
+
+    /// Only these documents are given to the function, on top of the filter.
+    pub(crate) fn documents_ids(&mut self, documents_ids: RoaringBitmap) {
+        self.documents_ids = Some(documents_ids);
+    }
+
+    /// Calls the function with every one of the documents to edit. The function returns
+    /// the new version of the document or `None` when the document must not be modified.
+    ///
+    /// The function is not allowed to modify the primary key of the documents.
+    #[logging_timer::time("UpdateByFunction::{}")]
+    pub fn execute<E, F>(
+        self,
+        mut function: E,
+        progress_callback: F,
+    ) -> Result<DocumentEditionResult>
+    where
+        E: FnMut(Object) -> Result<Option<Object>>,
+        F: Fn(UpdateIndexingStep, u64) + Sync,
+    {
+        self.index.set_updated_at(self.wtxn, &Utc::now())?;
+
+        let primary_key = match self.index.primary_key(self.wtxn)? {
+            Some(primary_key) => primary_key.to_string(),
+            // There is no document in the index, there is nothing to edit.
+            None => return Ok(DocumentEditionResult { nb_documents: 0 }),
+        };
+
+        let mut documents_ids = match &self.filter {
+            Some(condition) => condition.evaluate(self.wtxn, self.index)?,
+            None => self.index.documents_ids(self.wtxn)?,
+        };
+        if let Some(restriction) = &self.documents_ids {
+            documents_ids &= restriction;
+        }
+
+        let fields_ids_map = self.index.fields_ids_map(self.wtxn)?;
+        let mut writer = Cursor::new(Vec::new());
+        let mut builder = DocumentBatchBuilder::new(&mut writer)?;
+
+        for docid in documents_ids {
+            let document = match self.index.document_bytes(self.wtxn, docid)? {
+                Some(document) => document,
+                None => continue,
+            };
+            let document = DocumentReader::new(obkv::KvReaderU16::new(&document));
+
+            let object = document.to_json(&fields_ids_map)?;
+            let document_id = object.get(&primary_key).cloned().unwrap_or(Value::Null);
+            if let Some(edited) = function(object)? {
+                if edited.get(&primary_key) != Some(&document_id) {
+                    return Err(UserError::PrimaryKeyCannotBeEdited { document_id }.into());
+                }
+                builder.add_documents(edited)?;
+            }
+        }
+
+        let nb_documents = builder.len();
+        builder.finish()?;
+
+        if nb_documents == 0 {
+            return Ok(DocumentEditionResult { nb_documents });
+        }
+
+        writer.set_position(0);
+        let reader = DocumentBatchReader::from_reader(writer)?;
+
+        // The edited documents are complete, they replace the previous version
+        // of the documents and go through the usual indexing process.
+        let mut indexing_builder = IndexDocuments::new(self.wtxn, self.index, self.update_id);
+        indexing_builder.log_every_n = self.log_every_n;
+        indexing_builder.max_nb_chunks = self.max_nb_chunks;
+        indexing_builder.max_memory = self.max_memory;
+        indexing_builder.documents_chunk_size = self.documents_chunk_size;
+        indexing_builder.chunk_compression_type = self.chunk_compression_type;
+        indexing_builder.chunk_compression_level = self.chunk_compression_level;
+        indexing_builder.thread_pool = self.thread_pool;
+        indexing_builder.index_documents_method(IndexDocumentsMethod::ReplaceDocuments);
+        indexing_builder.execute(reader, progress_callback)?;
+
+        Ok(DocumentEditionResult { nb_documents })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+    use serde_json::json;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::update::Settings;
+    use crate::Search;
+
+    #[test]
+    fn edit_filtered_documents() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = Settings::new(&mut wtxn, &index, 0);
+        builder.set_filterable_fields(hashset! { S("age") });
+        builder.execute(|_, _| ()).unwrap();
+        let content = documents!([
+            { "id": 0, "name": "kevin", "age": 20 },
+            { "id": 1, "name": "kevina", "age": 21 },
+            { "id": 2, "name": "benoit", "age": 35 }
+        ]);
+        IndexDocuments::new(&mut wtxn, &index, 1).execute(content, |_, _| ()).unwrap();
+        wtxn.commit().unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let condition = FilterCondition::from_str(&wtxn, &index, "age < 30").unwrap();
+        let mut builder = UpdateByFunction::new(&mut wtxn, &index, 2);
+        builder.filter(condition);
+        let result = builder
+            .execute(
+                |mut document| {
+                    document.insert(S("name"), json!("young"));
+                    Ok(Some(document))
+                },
+                |_, _| (),
+            )
+            .unwrap();
+        assert_eq!(result.nb_documents, 2);
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = Search::new(&rtxn, &index);
+        search.query("young");
+        assert_eq!(search.execute().unwrap().documents_ids.len(), 2);
+        assert_eq!(index.number_of_documents(&rtxn).unwrap(), 3);
+
+        let mut search = Search::new(&rtxn, &index);
+        search.query("kevin");
+        assert!(search.execute().unwrap().documents_ids.is_empty());
+        drop(rtxn);
+
+        // The primary key of the documents can't be modified.
+        let mut wtxn = index.write_txn().unwrap();
+        let builder = UpdateByFunction::new(&mut wtxn, &index, 3);
+        let result = builder.execute(
+            |mut document| {
+                document.insert(S("id"), json!(42));
+                Ok(Some(document))
+            },
+            |_, _| (),
+        );
+        assert!(result.is_err());
+    }
+}
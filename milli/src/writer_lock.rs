@@ -0,0 +1,103 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::process;
+
+use fs2::FileExt;
+
+use crate::error::UserError;
+use crate::Result;
+
+/// The name of the file created by the writer process in the index directory.
+pub const WRITER_LOCK_FILE: &str = "writer.lock";
+
+/// Ensures that a single process writes into an index directory at a time.
+///
+/// LMDB allows many processes to read an index while one of them is writing into it, it
+/// serializes the write transactions of the different processes but the update operations
+/// of milli expect to be the only writer of the index. The writer holds an exclusive advisory
+/// lock on a file of the index directory for the lifetime of the `WriterLock`, the operating
+/// system releases it when the process exits, even when it crashed.
+///
+/// The file also contains the pid of the writer, it is only used to report which process
+/// holds the lock and never to decide whether the lock is held.
+#[derive(Debug)]
+pub struct WriterLock {
+    file: File,
+}
+
+impl WriterLock {
+    /// Acquires the writer lock of the index directory,
+    /// returns an error if another writer is running.
+    pub fn acquire<P: AsRef<Path>>(index_path: P) -> Result<WriterLock> {
+        let path = index_path.as_ref().join(WRITER_LOCK_FILE);
+        let mut file = OpenOptions::new().read(true).write(true).create(true).open(&path)?;
+
+        if let Err(error) = file.try_lock_exclusive() {
+            if error.kind() == fs2::lock_contended_error().kind() {
+                let pid = read_pid(&mut file)?;
+                return Err(UserError::IndexAlreadyHasWriter { pid }.into());
+            }
+            return Err(error.into());
+        }
+
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        write!(file, "{}", process::id())?;
+        file.flush()?;
+
+        Ok(WriterLock { file })
+    }
+
+    /// Returns `true` if a process currently holds the writer lock of the index directory.
+    pub fn is_locked<P: AsRef<Path>>(index_path: P) -> Result<bool> {
+        let path = index_path.as_ref().join(WRITER_LOCK_FILE);
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(error) => return Err(error.into()),
+        };
+
+        match file.try_lock_shared() {
+            Ok(()) => {
+                file.unlock()?;
+                Ok(false)
+            }
+            Err(error) if error.kind() == fs2::lock_contended_error().kind() => Ok(true),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Returns the pid of the process that is currently writing into the index directory.
+    ///
+    /// A writer that is acquiring the lock may not have written its pid yet,
+    /// use [`WriterLock::is_locked`] to know if the lock is held.
+    pub fn current_writer<P: AsRef<Path>>(index_path: P) -> Result<Option<u32>> {
+        if !WriterLock::is_locked(index_path.as_ref())? {
+            return Ok(None);
+        }
+
+        let path = index_path.as_ref().join(WRITER_LOCK_FILE);
+        match File::open(path) {
+            Ok(mut file) => read_pid(&mut file),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+impl Drop for WriterLock {
+    fn drop(&mut self) {
+        // The file is kept, removing it would let another process lock the removed
+        // file while a third one creates and locks a new one.
+        let _ = self.file.set_len(0);
+        let _ = self.file.unlock();
+    }
+}
+
+fn read_pid(file: &mut File) -> Result<Option<u32>> {
+    let mut content = String::new();
+    file.seek(SeekFrom::Start(0))?;
+    file.read_to_string(&mut content)?;
+    Ok(content.trim().parse().ok())
+}
@@ -70,3 +70,25 @@ test_distinct!(distinct_string_criterion_words_attribute, tag, vec![Words, Attri
 test_distinct!(distinct_number_criterion_words_attribute, asc_desc_rank, vec![Words, Attribute]);
 test_distinct!(distinct_string_criterion_words_exactness, tag, vec![Words, Exactness]);
 test_distinct!(distinct_number_criterion_words_exactness, asc_desc_rank, vec![Words, Exactness]);
+
+#[test]
+fn distinct_field_missing_from_the_documents() {
+    let index = search::setup_search_index_with_criteria(&[Words]);
+
+    let mut wtxn = index.write_txn().unwrap();
+    let mut builder = Settings::new(&mut wtxn, &index, 0);
+    builder.set_distinct_field(S("unknown"));
+    builder.execute(|_, _| ()).unwrap();
+    wtxn.commit().unwrap();
+
+    // No document can be returned while the distinct field isn't known.
+    let rtxn = index.read_txn().unwrap();
+    let mut search = Search::new(&rtxn, &index);
+    search.query(search::TEST_QUERY);
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+    let SearchResult { documents_ids, .. } = search.execute().unwrap();
+    assert!(documents_ids.is_empty());
+
+    let SearchResult { documents_ids, .. } = Search::new(&rtxn, &index).execute().unwrap();
+    assert!(documents_ids.is_empty());
+}
@@ -42,6 +42,8 @@ macro_rules! test_filter {
 
 test_filter!(eq_simple_string_filter, vec![Right("tag=red")]);
 test_filter!(eq_simple_number_filter, vec![Right("asc_desc_rank=1")]);
+// This filter only matches one document, it is intersected with the words documents ids.
+test_filter!(eq_selective_number_filter, vec![Right("asc_desc_rank=6")]);
 test_filter!(eq_string_and_filter_return_empty, vec![Right("tag=red"), Right("tag=green")]);
 test_filter!(eq_mix_and_filter, vec![Right("tag=red"), Right("asc_desc_rank=1")]);
 test_filter!(eq_string_or_filter, vec![Left(vec!["tag=red", "tag=green"])]);
@@ -81,3 +83,31 @@ test_filter!(
     lower_complex_filter_2,
     vec![Left(vec!["tag=red", "tag=green"]), Left(vec!["asc_desc_rank<3", "asc_desc_rank<1"])]
 );
+
+#[test]
+fn placeholder_search_paginates_filtered_documents() {
+    let criteria = vec![Words, Typo, Proximity, Attribute, Exactness];
+    let index = search::setup_search_index_with_criteria(&criteria);
+    let rtxn = index.read_txn().unwrap();
+
+    let filter = vec![Left(vec!["tag=red", "asc_desc_rank>1"])];
+    let filter_conditions = FilterCondition::from_array::<Vec<Either<Vec<&str>, &str>>, _, _, _>(
+        &rtxn,
+        &index,
+        filter.clone(),
+    )
+    .unwrap()
+    .unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.filter(filter_conditions);
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+    let SearchResult { candidates, documents_ids, .. } = search.execute().unwrap();
+    assert_eq!(candidates.len() as usize, search::expected_filtered_ids(filter).len());
+    assert_eq!(documents_ids, candidates.iter().collect::<Vec<_>>());
+
+    // The documents are returned in the order of their ids, one page after the other.
+    search.offset(2).limit(3);
+    let SearchResult { documents_ids: page, .. } = search.execute().unwrap();
+    assert_eq!(page, documents_ids.iter().copied().skip(2).take(3).collect::<Vec<_>>());
+}
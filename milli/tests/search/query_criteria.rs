@@ -1,14 +1,20 @@
 use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::io::Cursor;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use big_s::S;
 use heed::EnvOpenOptions;
 use itertools::Itertools;
-use maplit::hashset;
+use maplit::{hashmap, hashset};
 use milli::documents::{DocumentBatchBuilder, DocumentBatchReader};
 use milli::update::{Settings, UpdateBuilder};
-use milli::{AscDesc, Criterion, Index, Member, Search, SearchResult};
+use milli::{
+    AscDesc, Criterion, Error, Index, Member, Search, SearchResult, TermsMatchingStrategy,
+};
 use rand::Rng;
+use roaring::RoaringBitmap;
 use Criterion::*;
 
 use crate::search::{self, EXTERNAL_DOCUMENTS_IDS};
@@ -462,3 +468,283 @@ fn criteria_ascdesc() {
         assert_eq!(documents_ids, expected_document_ids);
     }
 }
+
+#[test]
+fn proximity_approximation() {
+    let index = search::setup_search_index_with_criteria(&[Words, Proximity]);
+
+    let search_ids = |index: &Index| {
+        let rtxn = index.read_txn().unwrap();
+        let mut search = Search::new(&rtxn, index);
+        search.query("world america");
+        search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+        search.optional_words(DISALLOW_OPTIONAL_WORDS);
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        documents_ids
+    };
+
+    let exact_ids = search_ids(&index);
+
+    // Any number of candidates is above the threshold, the documents
+    // are only bucketed by the minimal proximity between the query words.
+    let mut wtxn = index.write_txn().unwrap();
+    let mut builder = Settings::new(&mut wtxn, &index, 1);
+    builder.set_proximity_approximation_threshold(0);
+    builder.execute(|_, _| ()).unwrap();
+    wtxn.commit().unwrap();
+
+    let approximated_ids = search_ids(&index);
+    assert_eq!(approximated_ids.len(), exact_ids.len());
+    assert_eq!(
+        approximated_ids.iter().sorted().collect::<Vec<_>>(),
+        exact_ids.iter().sorted().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn extra_synonyms() {
+    let index = search::setup_search_index_with_criteria(&[Words]);
+    let rtxn = index.read_txn().unwrap();
+
+    let search_ids = |query: &str, extra_synonyms| {
+        let mut search = Search::new(&rtxn, &index);
+        search.query(query);
+        search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+        search.extra_synonyms(extra_synonyms);
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        documents_ids.into_iter().sorted().collect::<Vec<_>>()
+    };
+
+    assert!(search_ids("vacation", HashMap::new()).is_empty());
+
+    let extra_synonyms = hashmap! { S("Vacation") => vec![S("adele")] };
+    let vacation_ids = search_ids("vacation", extra_synonyms);
+    assert!(!vacation_ids.is_empty());
+    assert!(vacation_ids.iter().all(|id| search_ids("adele", HashMap::new()).contains(id)));
+
+    // The synonyms of the query are merged with the ones of the index.
+    let extra_synonyms = hashmap! { S("world") => vec![S("vacation")] };
+    assert_eq!(search_ids("world", extra_synonyms), search_ids("world", HashMap::new()));
+}
+
+#[test]
+fn negated_words() {
+    let index = search::setup_search_index_with_criteria(&[Words]);
+    let rtxn = index.read_txn().unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query("hello -world");
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+    let SearchResult { documents_ids, candidates, .. } = search.execute().unwrap();
+
+    let world_docids = index.word_docids.get(&rtxn, "world").unwrap().unwrap();
+    assert!(!documents_ids.is_empty());
+    assert!(documents_ids.iter().all(|id| !world_docids.contains(*id)));
+    assert!(candidates.is_disjoint(&world_docids));
+}
+
+#[test]
+fn terms_matching_strategy() {
+    let index = search::setup_search_index_with_criteria(&[Words]);
+    let rtxn = index.read_txn().unwrap();
+
+    let search_candidates = |strategy| {
+        let mut search = Search::new(&rtxn, &index);
+        search.query("hello world america");
+        search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+        search.terms_matching_strategy(strategy);
+        let SearchResult { candidates, .. } = search.execute().unwrap();
+        candidates
+    };
+
+    let all = search_candidates(TermsMatchingStrategy::All);
+    let last = search_candidates(TermsMatchingStrategy::Last);
+    assert!(all.is_subset(&last));
+    assert!(all.len() < last.len());
+
+    // Only the documents that contain all the words are returned.
+    let mut all_words = index.word_docids.get(&rtxn, "hello").unwrap().unwrap_or_default();
+    all_words &= index.word_docids.get(&rtxn, "world").unwrap().unwrap_or_default();
+    all_words &= index.word_docids.get(&rtxn, "america").unwrap().unwrap_or_default();
+    assert!(all_words.is_subset(&all));
+}
+
+#[test]
+fn scoped_words() {
+    let index = search::setup_search_index_with_criteria(&[Words]);
+    let rtxn = index.read_txn().unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query("title:hello ");
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+    search.authorize_typos(DISALLOW_TYPOS);
+    let SearchResult { documents_ids, .. } = search.execute().unwrap();
+    assert!(!documents_ids.is_empty());
+
+    let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+    let title_id = fields_ids_map.id("title").unwrap();
+    for (_, document) in index.documents(&rtxn, documents_ids).unwrap() {
+        let title: String = serde_json::from_slice(document.get(title_id).unwrap()).unwrap();
+        assert!(title.split_whitespace().any(|word| word == "hello"), "{}", title);
+    }
+}
+
+#[test]
+fn ranking_scores() {
+    let index = search::setup_search_index_with_criteria(&[Words, Typo, Proximity]);
+    let rtxn = index.read_txn().unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query("hello world america");
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+    let SearchResult { ranking_scores, .. } = search.execute().unwrap();
+    assert_eq!(ranking_scores, None);
+
+    search.ranking_score(true);
+    let SearchResult { documents_ids, ranking_scores, .. } = search.execute().unwrap();
+    let ranking_scores = ranking_scores.unwrap();
+    assert_eq!(ranking_scores.len(), documents_ids.len());
+    assert_eq!(ranking_scores[0], 1.0);
+    assert!(ranking_scores.windows(2).all(|w| w[0] >= w[1]));
+    assert!(ranking_scores.iter().all(|score| *score > 0.0 && *score <= 1.0));
+
+    // The score is derived from the buckets of the document, the n-th bucket of
+    // the first ranking rule gets the n-th half of the scores.
+    search.show_ranking_score_details(true);
+    let SearchResult { ranking_scores, ranking_score_details, .. } = search.execute().unwrap();
+    for (score, details) in ranking_scores.unwrap().iter().zip(ranking_score_details.unwrap()) {
+        let buckets: Vec<_> = details.iter().map(|d| d.bucket).collect();
+        assert_eq!(*score == 1.0, buckets.iter().all(|bucket| *bucket == 0));
+        let first = buckets[0] as i32;
+        assert!(*score <= 0.5f64.powi(first) && *score > 0.5f64.powi(first + 1));
+    }
+}
+
+#[test]
+fn ranking_score_details() {
+    let index = search::setup_search_index_with_criteria(&[Words, Typo, Proximity]);
+    let rtxn = index.read_txn().unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query("hello world america");
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+    search.show_ranking_score_details(true);
+    let SearchResult { documents_ids, ranking_score_details, .. } = search.execute().unwrap();
+    let ranking_score_details = ranking_score_details.unwrap();
+    assert_eq!(ranking_score_details.len(), documents_ids.len());
+
+    for details in &ranking_score_details {
+        let ranking_rules: Vec<_> = details.iter().map(|d| d.ranking_rule.as_str()).collect();
+        assert_eq!(ranking_rules, vec!["words", "typo", "proximity"]);
+    }
+
+    // The documents are returned in the order of their buckets.
+    let buckets: Vec<Vec<u32>> = ranking_score_details
+        .iter()
+        .map(|details| details.iter().map(|d| d.bucket).collect())
+        .collect();
+    assert!(buckets.windows(2).all(|w| w[0] <= w[1]));
+}
+
+#[test]
+fn ranking_score_threshold() {
+    let index = search::setup_search_index_with_criteria(&[Words, Typo, Proximity]);
+    let rtxn = index.read_txn().unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query("hello world america");
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+    search.ranking_score(true);
+    let SearchResult { documents_ids, ranking_scores, .. } = search.execute().unwrap();
+    let ranking_scores = ranking_scores.unwrap();
+    let threshold = (ranking_scores[0] + ranking_scores[ranking_scores.len() - 1]) / 2.0;
+    let expected_len = ranking_scores.iter().filter(|score| **score >= threshold).count();
+    assert!(expected_len < documents_ids.len());
+
+    // The documents under the threshold are no longer returned.
+    search.ranking_score_threshold(threshold);
+    let SearchResult { documents_ids: kept, ranking_scores, .. } = search.execute().unwrap();
+    assert_eq!(kept, documents_ids[..expected_len]);
+    assert!(ranking_scores.unwrap().iter().all(|score| *score >= threshold));
+}
+
+#[test]
+fn abort_search() {
+    let index = search::setup_search_index_with_criteria(&[Words, Typo, Proximity]);
+    let rtxn = index.read_txn().unwrap();
+
+    let signal = Arc::new(AtomicBool::new(false));
+    let mut search = Search::new(&rtxn, &index);
+    search.query("hello world america");
+    search.abort_signal(signal.clone());
+    assert!(search.execute().is_ok());
+
+    signal.store(true, Ordering::Relaxed);
+    assert!(matches!(search.execute(), Err(Error::SearchAborted)));
+
+    // The placeholder searches are aborted too.
+    let mut search = Search::new(&rtxn, &index);
+    search.abort_signal(signal);
+    assert!(matches!(search.execute(), Err(Error::SearchAborted)));
+}
+
+#[test]
+fn authorize_typos_toggle() {
+    let index = search::setup_search_index_with_criteria(&[Words, Typo]);
+    let rtxn = index.read_txn().unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query("worlf");
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+    let SearchResult { documents_ids, .. } = search.execute().unwrap();
+    assert!(!documents_ids.is_empty());
+
+    // Only the documents that contain the exact word are returned in exact mode.
+    search.authorize_typos(false);
+    let SearchResult { documents_ids, .. } = search.execute().unwrap();
+    let exact = index.word_docids.get(&rtxn, "worlf").unwrap().unwrap_or_default();
+    assert_eq!(documents_ids.into_iter().collect::<RoaringBitmap>(), exact);
+}
+
+#[test]
+fn last_word_prefix_toggle() {
+    let index = search::setup_search_index_with_criteria(&[Words, Typo]);
+    let rtxn = index.read_txn().unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query("hello worl");
+    search.authorize_typos(false);
+    search.optional_words(false);
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+    let SearchResult { documents_ids, .. } = search.execute().unwrap();
+    assert!(!documents_ids.is_empty());
+
+    // The last word must be a whole word of the documents without the prefix search.
+    search.last_word_prefix(false);
+    let SearchResult { documents_ids, .. } = search.execute().unwrap();
+    let hello = index.word_docids.get(&rtxn, "hello").unwrap().unwrap_or_default();
+    let worl = index.word_docids.get(&rtxn, "worl").unwrap().unwrap_or_default();
+    assert_eq!(documents_ids.into_iter().collect::<RoaringBitmap>(), hello & worl);
+}
+
+#[test]
+fn split_word_costs_a_typo() {
+    let index = search::setup_search_index_with_criteria(&[Typo]);
+    let rtxn = index.read_txn().unwrap();
+
+    // The word is not in the documents, it is split into the two words of the documents.
+    let mut search = Search::new(&rtxn, &index);
+    search.query("helloworld");
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+    search.show_ranking_score_details(true);
+    let SearchResult { documents_ids, ranking_score_details, .. } = search.execute().unwrap();
+    assert!(!documents_ids.is_empty());
+
+    let hello = index.word_docids.get(&rtxn, "hello").unwrap().unwrap_or_default();
+    let world = index.word_docids.get(&rtxn, "world").unwrap().unwrap_or_default();
+    assert!(documents_ids.iter().all(|id| hello.contains(*id) && world.contains(*id)));
+
+    // The split words are never ranked with the exact matches.
+    let ranking_score_details = ranking_score_details.unwrap();
+    assert!(ranking_score_details.iter().all(|details| details[0].bucket > 0));
+}